@@ -41,7 +41,7 @@ export default function AdminUsersPage() {
     if (!token) { router.replace("/login"); return; }
 
     api.admin.users(token)
-      .then((r) => setUsers(r.users))
+      .then((r) => setUsers(r.items))
       .catch((e) => {
         if (e instanceof ApiError && e.status === 403) router.replace("/dashboard");
         else toast.error("Failed to load users");
@@ -57,7 +57,7 @@ export default function BusinessProfilePage() {
         setBusiness(profileRes.business);
         setServices(profileRes.services);
         setBranches(profileRes.branches);
-        setReviews(reviewRes.reviews);
+        setReviews(reviewRes.items);
       } catch {
         toast.error("Business not found");
         router.push("/search");
@@ -71,7 +71,7 @@ export default function BusinessProfilePage() {
   useEffect(() => {
     if (!isAuthenticated || !token) return;
     api.favorites.list(token).then((res) => {
-      const found = res.favorites.some((f) => f.target_type === "business" && f.target_id === Number(id));
+      const found = res.items.some((f) => f.target_type === "business" && f.target_id === Number(id));
       setIsFavorited(found);
     }).catch(() => {});
   }, [id, isAuthenticated, token]);
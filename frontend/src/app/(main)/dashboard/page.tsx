@@ -94,7 +94,7 @@ export default function DashboardPage() {
         <StatCard
           icon={<CalendarCheck className="h-5 w-5 text-primary" />}
           label="Upcoming bookings"
-          value={String(data.upcoming_bookings)}
+          value={String(data.upcoming_bookings ?? 0)}
         />
         <StatCard
           icon={<Bell className="h-5 w-5 text-primary" />}
@@ -3,7 +3,7 @@
 import { useEffect, useState } from "react";
 import { useRouter } from "next/navigation";
 import { useAuthStore } from "@/store/auth";
-import { api, type ProviderProfile, type BusinessProfile } from "@/lib/api";
+import { api, type Favorite } from "@/lib/api";
 import { Card, CardContent } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Avatar, AvatarFallback, AvatarImage } from "@/components/ui/avatar";
@@ -12,14 +12,10 @@ import { Skeleton } from "@/components/ui/skeleton";
 import { Heart, Star, MapPin, ArrowRight } from "lucide-react";
 import { toast } from "sonner";
 
-type FavoriteItem =
-  | { type: "provider"; id: number; data: ProviderProfile }
-  | { type: "business"; id: number; data: BusinessProfile };
-
 export default function FavoritesPage() {
   const { token, isAuthenticated, user, _hasHydrated } = useAuthStore();
   const router = useRouter();
-  const [items, setItems] = useState<FavoriteItem[]>([]);
+  const [items, setItems] = useState<Favorite[]>([]);
   const [loading, setLoading] = useState(true);
   const [removingId, setRemovingId] = useState<string | null>(null);
 
@@ -34,26 +30,8 @@ export default function FavoritesPage() {
   async function loadFavorites() {
     setLoading(true);
     try {
-      const { favorites } = await api.favorites.list(token!);
-      if (favorites.length === 0) { setItems([]); return; }
-
-      const results = await Promise.allSettled(
-        favorites.map(async (f) => {
-          if (f.target_type === "provider") {
-            const res = await api.providers.getById(f.target_id);
-            return { type: "provider" as const, id: f.target_id, data: res.provider };
-          } else {
-            const res = await api.businesses.getById(f.target_id);
-            return { type: "business" as const, id: f.target_id, data: res.business };
-          }
-        }),
-      );
-
-      setItems(
-        results
-          .filter((r): r is PromiseFulfilledResult<FavoriteItem> => r.status === "fulfilled")
-          .map((r) => r.value),
-      );
+      const { items: favorites } = await api.favorites.list(token!);
+      setItems(favorites);
     } catch {
       toast.error("Could not load favourites");
     } finally {
@@ -61,12 +39,12 @@ export default function FavoritesPage() {
     }
   }
 
-  async function remove(item: FavoriteItem) {
-    const key = `${item.type}-${item.id}`;
+  async function remove(item: Favorite) {
+    const key = `${item.target_type}-${item.target_id}`;
     setRemovingId(key);
     try {
-      await api.favorites.remove(item.id, item.type, token!);
-      setItems((prev) => prev.filter((i) => !(i.type === item.type && i.id === item.id)));
+      await api.favorites.remove(item.target_id, item.target_type, token!);
+      setItems((prev) => prev.filter((i) => !(i.target_type === item.target_type && i.target_id === item.target_id)));
       toast.success("Removed from favourites");
     } catch (err: unknown) {
       toast.error(err instanceof Error ? err.message : "Could not remove");
@@ -75,19 +53,14 @@ export default function FavoritesPage() {
     }
   }
 
-  function navigate(item: FavoriteItem) {
-    router.push(item.type === "provider" ? `/providers/${item.id}` : `/businesses/${item.id}`);
+  function navigate(item: Favorite) {
+    router.push(item.target_type === "provider" ? `/providers/${item.target_id}` : `/businesses/${item.target_id}`);
   }
 
-  const name = (item: FavoriteItem) =>
-    item.type === "provider"
-      ? (item.data as ProviderProfile).service_name ?? "Provider"
-      : (item.data as BusinessProfile).business_name;
+  const name = (item: Favorite) =>
+    item.name ?? (item.target_type === "provider" ? "Provider" : "Business");
 
-  const photo = (item: FavoriteItem) =>
-    item.type === "provider"
-      ? (item.data as ProviderProfile).profile_photo
-      : (item.data as BusinessProfile).logo ?? (item.data as BusinessProfile).profile_photo;
+  const photo = (item: Favorite) => item.photo_thumb_url ?? item.photo;
 
   if (!_hasHydrated) return null;
 
@@ -111,7 +84,7 @@ export default function FavoritesPage() {
       ) : (
         <div className="space-y-3">
           {items.map((item) => {
-            const key = `${item.type}-${item.id}`;
+            const key = `${item.target_type}-${item.target_id}`;
             const isRemoving = removingId === key;
             return (
               <Card key={key} className="border border-border hover:border-primary/40 transition-colors">
@@ -127,25 +100,25 @@ export default function FavoritesPage() {
                     <div className="flex-1 min-w-0">
                       <div className="flex items-center gap-2 flex-wrap">
                         <p className="text-sm font-semibold text-foreground truncate">{name(item)}</p>
-                        <Badge variant="secondary" className="text-xs capitalize shrink-0">{item.type}</Badge>
+                        <Badge variant="secondary" className="text-xs capitalize shrink-0">{item.target_type}</Badge>
                       </div>
 
                       <div className="flex flex-wrap items-center gap-3 mt-0.5 text-xs text-muted-foreground">
-                        {item.data.category && (
-                          <span>{item.data.category}</span>
+                        {item.category && (
+                          <span>{item.category}</span>
                         )}
-                        {item.data.avg_rating != null && item.data.avg_rating > 0 && (
+                        {item.avg_rating != null && item.avg_rating > 0 && (
                           <span className="flex items-center gap-0.5">
                             <Star className="h-3 w-3 fill-amber-400 text-amber-400" />
-                            {item.data.avg_rating.toFixed(1)}
-                            {item.data.review_count != null && item.data.review_count > 0 && (
-                              <span className="text-muted-foreground/70">({item.data.review_count})</span>
+                            {item.avg_rating.toFixed(1)}
+                            {item.review_count != null && item.review_count > 0 && (
+                              <span className="text-muted-foreground/70">({item.review_count})</span>
                             )}
                           </span>
                         )}
-                        {item.data.location && (
+                        {item.location && (
                           <span className="flex items-center gap-0.5">
-                            <MapPin className="h-3 w-3" />{item.data.location}
+                            <MapPin className="h-3 w-3" />{item.location}
                           </span>
                         )}
                       </div>
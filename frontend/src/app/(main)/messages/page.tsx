@@ -74,8 +74,8 @@ export default function MessagesPage() {
         target_id: selected.target_id,
       })
       .then((r) => {
-        setMessages(r.messages);
-        const unreadIds = r.messages
+        setMessages(r.items);
+        const unreadIds = r.items
           .filter((m) => !m.is_read && m.sender_id !== user?.id)
           .map((m) => m.id);
         if (unreadIds.length > 0) {
@@ -51,7 +51,7 @@ export default function NotificationsPage() {
   async function load() {
     try {
       const res = await api.notifications.list(token!, { page: 1 });
-      setNotifications(res.notifications);
+      setNotifications(res.items);
     } catch {
       toast.error("Could not load notifications");
     } finally {
@@ -55,6 +55,7 @@ export default function ProviderProfilePage() {
   const [availability, setAvailability] = useState<import("@/lib/api").Availability[] | null>(null);
   const [isFavorited, setIsFavorited] = useState(false);
   const [favLoading, setFavLoading] = useState(false);
+  const [pastInteractions, setPastInteractions] = useState<string[]>([]);
   const [flagTarget, setFlagTarget] = useState<number | null>(null);
   const [flagReason, setFlagReason] = useState("");
   const [flagSending, setFlagSending] = useState(false);
@@ -74,7 +75,7 @@ export default function ProviderProfilePage() {
         ]);
         setProvider(profileRes.provider);
         setServices(profileRes.services);
-        setReviews(reviewRes.reviews);
+        setReviews(reviewRes.items);
       } catch {
         toast.error("Provider not found");
         router.push("/search");
@@ -88,11 +89,24 @@ export default function ProviderProfilePage() {
   useEffect(() => {
     if (!isAuthenticated || !token) return;
     api.favorites.list(token).then((res) => {
-      const found = res.favorites.some((f) => f.target_type === "provider" && f.target_id === Number(id));
+      const found = res.items.some((f) => f.target_type === "provider" && f.target_id === Number(id));
       setIsFavorited(found);
     }).catch(() => {});
   }, [id, isAuthenticated, token]);
 
+  useEffect(() => {
+    if (!isAuthenticated || !token) return;
+    api.interactions.mine(token, { target_type: "provider", target_id: Number(id) }).then((res) => {
+      setPastInteractions(res.items[0]?.interaction_types ?? []);
+    }).catch(() => {});
+  }, [id, isAuthenticated, token]);
+
+  // Best-effort: feeds "you've contacted this provider before" hints
+  function recordContact() {
+    if (!token) return;
+    api.interactions.contact({ target_type: "provider", target_id: Number(id) }, token).catch(() => {});
+  }
+
   async function toggleFavorite() {
     if (!isAuthenticated) { router.push(`/login?next=/providers/${id}`); return; }
     setFavLoading(true);
@@ -279,6 +293,14 @@ export default function ProviderProfilePage() {
               </div>
             </div>
 
+            {pastInteractions.some((t) => t === "message" || t === "contact_click" || t.startsWith("booking")) && (
+              <p className="mt-3 text-xs text-muted-foreground">
+                {pastInteractions.includes("booking_completed")
+                  ? "You've booked this provider before"
+                  : "You've contacted this provider before"}
+              </p>
+            )}
+
             {provider.service_description && (
               <p className="mt-4 text-sm text-muted-foreground leading-relaxed">
                 {provider.service_description}
@@ -305,7 +327,7 @@ export default function ProviderProfilePage() {
                 </Button>
               )}
               {provider.website && (
-                <a href={provider.website} target="_blank" rel="noopener noreferrer">
+                <a href={provider.website} target="_blank" rel="noopener noreferrer" onClick={recordContact}>
                   <Button variant="outline" size="icon">
                     <Globe className="h-4 w-4" />
                   </Button>
@@ -472,7 +494,7 @@ export default function ProviderProfilePage() {
               >
                 <Copy className="h-3.5 w-3.5" />
               </Button>
-              <a href={`tel:${provider.phone_number}`}>
+              <a href={`tel:${provider.phone_number}`} onClick={recordContact}>
                 <Button size="sm" className="gap-1.5">
                   <Phone className="h-3.5 w-3.5" />
                   Call
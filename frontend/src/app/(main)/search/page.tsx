@@ -18,7 +18,10 @@ import { toast } from "sonner";
 const PER_PAGE = 12;
 
 function ResultCard({ result }: { result: SearchResult }) {
-  const href = result.type === "business" ? `/businesses/${result.id}` : `/providers/${result.id}`;
+  // A service links to whoever offers it
+  const target = result.type === "service" ? result.owner_type : result.type;
+  const targetId = result.type === "service" ? result.owner_id : result.id;
+  const href = target === "business" ? `/businesses/${targetId}` : `/providers/${targetId}`;
   const initials = result.name?.slice(0, 2).toUpperCase() ?? "??";
 
   return (
@@ -45,6 +48,9 @@ function ResultCard({ result }: { result: SearchResult }) {
                 {result.type === "business" && (
                   <Badge variant="outline" className="text-xs font-normal">Business</Badge>
                 )}
+                {result.type === "service" && result.owner_name && (
+                  <Badge variant="outline" className="text-xs font-normal">by {result.owner_name}</Badge>
+                )}
               </div>
             </div>
           </div>
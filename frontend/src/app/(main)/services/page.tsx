@@ -58,7 +58,7 @@ export default function ServicesPage() {
       setTargetId(id);
       return api.services.list(type, id, token!);
     }).then((res) => {
-      if (res) setServices(res.services);
+      if (res) setServices(res.items);
     }).catch(() => toast.error("Could not load services"))
       .finally(() => setLoading(false));
   // eslint-disable-next-line react-hooks/exhaustive-deps
@@ -11,7 +11,7 @@ export default function AuthSync() {
   const router = useRouter();
 
   // Refresh user state from the backend so email_verified / onboarding_completed
-  // reflect server truth instead of the value cached at login time.
+  // / is_admin reflect server truth instead of the value cached at login time.
   useEffect(() => {
     if (!_hasHydrated || !token || !user) return;
 
@@ -22,12 +22,14 @@ export default function AuthSync() {
         if (cancelled) return;
         if (
           fresh.email_verified !== user.email_verified ||
-          fresh.onboarding_completed !== user.onboarding_completed
+          fresh.onboarding_completed !== user.onboarding_completed ||
+          fresh.is_admin !== user.is_admin
         ) {
           updateUser({
             ...user,
             email_verified: fresh.email_verified,
             onboarding_completed: fresh.onboarding_completed,
+            is_admin: fresh.is_admin,
           });
         }
       })
@@ -2,7 +2,7 @@
 
 import { useAuthStore } from "@/store/auth";
 import { useState } from "react";
-import { api } from "@/lib/api";
+import { API_URL, api } from "@/lib/api";
 import { MailWarning, X, Loader2 } from "lucide-react";
 import { toast } from "sonner";
 
@@ -20,7 +20,7 @@ export default function EmailVerificationBanner() {
     try {
       // Re-register doesn't work; call a resend endpoint or use forgot-password flow.
       // For now, call a lightweight resend endpoint if it exists, otherwise guide user.
-      await fetch(`${process.env.NEXT_PUBLIC_API_URL ?? "http://localhost:7878"}/auth/resend-verification`, {
+      await fetch(`${API_URL}/auth/resend-verification`, {
         method: "POST",
         headers: { Authorization: `Bearer ${token}`, "Content-Type": "application/json" },
       });
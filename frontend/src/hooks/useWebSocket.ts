@@ -1,11 +1,11 @@
 "use client";
 
 import { useEffect, useRef } from "react";
+import { API_URL } from "@/lib/api";
 
 type EventHandlers = Record<string, (data: unknown) => void>;
 
-const WS_BASE = (process.env.NEXT_PUBLIC_API_URL ?? "http://localhost:7878")
-  .replace(/^http/, "ws");
+const WS_BASE = API_URL.replace(/^http/, "ws");
 
 export function useWebSocket(token: string | null, handlers: EventHandlers) {
   const handlersRef = useRef<EventHandlers>(handlers);
@@ -1,4 +1,6 @@
 const BASE_URL = (process.env.NEXT_PUBLIC_API_URL ?? "http://localhost:7878").replace(/\/+$/, "");
+// Versioned API root; uploads and other static files stay at BASE_URL
+export const API_URL = `${BASE_URL}/api/v1`;
 
 type RequestOptions = {
   method?: string;
@@ -15,7 +17,7 @@ async function request<T>(path: string, opts: RequestOptions = {}): Promise<T> {
   };
   if (token) headers["Authorization"] = `Bearer ${token}`;
 
-  const res = await fetch(`${BASE_URL}${path}`, {
+  const res = await fetch(`${API_URL}${path}`, {
     method,
     headers,
     body: body !== undefined ? JSON.stringify(body) : undefined,
@@ -59,6 +61,7 @@ type MeResponse = {
   role: string;
   email_verified?: boolean;
   onboarding_completed?: boolean;
+  is_admin?: boolean;
 };
 
 export const api = {
@@ -119,6 +122,11 @@ export const api = {
   // ── Dashboard ───────────────────────────────────────────────────────────
   dashboard: {
     get: (token: string) => request<DashboardData>("/dashboard", { token }),
+
+    dismiss: (token: string, type: string, occurrence: string) =>
+      request<{ message: string }>("/dashboard/dismiss", { method: "POST", body: { type, occurrence }, token }),
+
+    onboarding: (token: string) => request<OnboardingProgress>("/dashboard/onboarding", { token }),
   },
 
   // ── Search ──────────────────────────────────────────────────────────────
@@ -133,50 +141,43 @@ export const api = {
       if (params.radius_km) qs.set("radius_km", String(params.radius_km));
       if (params.page) qs.set("page", String(params.page));
       if (params.per_page) qs.set("per_page", String(params.per_page));
+      if (params.type) qs.set("type", params.type);
+      if (params.county_id) qs.set("county_id", String(params.county_id));
 
-      // Backend returns separate providers/businesses arrays with different
-      // field names (average_rating, service_name, business_name). Transform
-      // them into the unified SearchResult shape the UI consumes.
+      // Rename the backend's hit fields to the ones the UI consumes.
       const raw = await request<RawSearchResponse>(`/search?${qs}`);
 
-      const results: SearchResult[] = [
-        ...(raw.providers ?? []).map((p) => ({
-          id: p.id,
-          type: "provider" as const,
-          name: p.service_name ?? "Provider",
-          description: p.service_description,
-          category: p.category,
-          location: p.location,
-          profile_photo: p.profile_photo,
-          avg_rating: p.average_rating,
-          review_count: p.review_count,
-          distance_km: p.distance_km,
-        })),
-        ...(raw.businesses ?? []).map((b) => ({
-          id: b.id,
-          type: "business" as const,
-          name: b.business_name,
-          description: b.description,
-          category: b.category,
-          location: b.location,
-          profile_photo: b.profile_photo ?? b.logo,
-          avg_rating: b.average_rating,
-          review_count: b.review_count,
-          distance_km: b.distance_km,
-        })),
-      ];
-
-      return { results, total: raw.total };
+      const results: SearchResult[] = raw.items.map((hit) => ({
+        id: hit.id,
+        type: hit.type,
+        name: hit.name ?? (hit.type === "business" ? "Business" : "Provider"),
+        description: hit.description,
+        category: hit.category,
+        location: hit.location,
+        profile_photo: hit.photo_url,
+        avg_rating: hit.average_rating,
+        review_count: hit.review_count,
+        distance_km: hit.distance_km,
+        owner_type: hit.owner_type,
+        owner_id: hit.owner_id,
+        owner_name: hit.owner_name,
+        price: hit.price,
+      }));
+
+      return { results, total: raw.total, counts: raw.counts };
     },
   },
 
   // ── Providers ───────────────────────────────────────────────────────────
   providers: {
-    list: (params?: { category?: string; location?: string }) => {
+    list: (params?: { category?: string; location?: string; sort?: string; page?: number; per_page?: number }) => {
       const qs = new URLSearchParams();
       if (params?.category) qs.set("category", params.category);
       if (params?.location) qs.set("location", params.location);
-      return request<{ providers: PublicProvider[] }>(`/service_providers/listProviders?${qs}`);
+      if (params?.sort) qs.set("sort", params.sort);
+      if (params?.page) qs.set("page", String(params.page));
+      if (params?.per_page) qs.set("per_page", String(params.per_page));
+      return request<Paginated<PublicProvider>>(`/service_providers/listProviders?${qs}`);
     },
     getById: (id: number) =>
       request<{ provider: ProviderProfile; services: Service[] }>(`/service_providers/${id}`),
@@ -201,11 +202,13 @@ export const api = {
 
   // ── Businesses ──────────────────────────────────────────────────────────
   businesses: {
-    list: (params?: { category?: string; location?: string }) => {
+    list: (params?: { category?: string; location?: string; page?: number; per_page?: number }) => {
       const qs = new URLSearchParams();
       if (params?.category) qs.set("category", params.category);
       if (params?.location) qs.set("location", params.location);
-      return request<{ businesses: PublicBusiness[] }>(`/businesses/listBusinesses?${qs}`);
+      if (params?.page) qs.set("page", String(params.page));
+      if (params?.per_page) qs.set("per_page", String(params.per_page));
+      return request<Paginated<PublicBusiness>>(`/businesses/listBusinesses?${qs}`);
     },
     getById: (id: number) =>
       request<{ business: BusinessProfile; services: Service[]; branches: Branch[] }>(`/businesses/${id}`),
@@ -285,8 +288,10 @@ export const api = {
         body: data,
         token,
       }),
-    get: (target_type: string, target_id: number) =>
-      request<{ reviews: Review[] }>(`/reviews/getReviews?target_type=${target_type}&target_id=${target_id}`),
+    get: (target_type: string, target_id: number, page = 1) =>
+      request<Paginated<Review>>(
+        `/reviews/getReviews?target_type=${target_type}&target_id=${target_id}&page=${page}`,
+      ),
     aggregate: (target_type: string, target_id: number) =>
       request<{ aggregated_rating: { average_rating: number; review_count: number } }>(
         `/reviews/getReviewAggById?target_type=${target_type}&target_id=${target_id}`,
@@ -299,13 +304,18 @@ export const api = {
   messages: {
     conversations: (token: string) =>
       request<{ conversations: Conversation[] }>("/messages/conversations", { token }),
-    get: (token: string, params: { other_user_id: number; target_type: string; target_id: number }) => {
+    get: (
+      token: string,
+      params: { other_user_id: number; target_type: string; target_id: number; page?: number; per_page?: number },
+    ) => {
       const qs = new URLSearchParams({
         other_user_id: String(params.other_user_id),
         target_type: params.target_type,
         target_id: String(params.target_id),
+        page: String(params.page ?? 1),
+        per_page: String(params.per_page ?? 100),
       });
-      return request<{ messages: Message[] }>(`/messages/getMessages?${qs}`, { token });
+      return request<Paginated<Message>>(`/messages/getMessages?${qs}`, { token });
     },
     send: (
       data: { receiver_id: number; content: string; target_type: string; target_id: number },
@@ -328,7 +338,7 @@ export const api = {
       const qs = new URLSearchParams();
       if (params?.page) qs.set("page", String(params.page));
       if (params?.unread_only) qs.set("unread_only", "true");
-      return request<{ notifications: Notification[]; total: number }>(`/notifications?${qs}`, { token });
+      return request<Paginated<Notification>>(`/notifications?${qs}`, { token });
     },
     unreadCount: (token: string) =>
       request<{ unread_count: number }>("/notifications/unread-count", { token }),
@@ -336,13 +346,15 @@ export const api = {
       request("/notifications/read-all", { method: "POST", token }),
     markRead: (id: number, token: string) =>
       request(`/notifications/${id}/read`, { method: "POST", token }),
+    markManyRead: (ids: number[], token: string) =>
+      request<{ updated: number }>("/notifications/markRead", { method: "POST", body: { ids }, token }),
   },
 
   // ── Services ────────────────────────────────────────────────────────────
   services: {
     list: (targetType: string, targetId: number, token: string) =>
-      request<{ services: ManagedService[] }>(
-        `/services/getServices?target_type=${targetType}&target_id=${targetId}`, { token }),
+      request<Paginated<ManagedService>>(
+        `/services/getServices?target_type=${targetType}&target_id=${targetId}&per_page=100`, { token }),
     create: (data: CreateServiceInput, token: string) =>
       request<{ service_id: number }>("/services/createService", { method: "POST", body: data, token }),
     update: (data: UpdateServiceInput, token: string) =>
@@ -376,12 +388,24 @@ export const api = {
   favorites: {
     add: (data: { target_type: string; target_id: number }, token: string) =>
       request("/favorites/addFavorite", { method: "POST", body: data, token }),
-    list: (token: string) =>
-      request<{ favorites: { target_type: string; target_id: number }[] }>("/favorites/getFavorites", { token }),
+    list: (token: string, page = 1, perPage = 100) =>
+      request<Paginated<Favorite>>(`/favorites/getFavorites?page=${page}&per_page=${perPage}`, { token }),
     remove: (target_id: number, target_type: string, token: string) =>
       request(`/favorites/removeFavorite/${target_id}?target_type=${target_type}`, { method: "POST", token }),
   },
 
+  // ── Interactions ─────────────────────────────────────────────────────────
+  interactions: {
+    mine: (token: string, params?: { target_type?: "provider" | "business"; target_id?: number }) => {
+      const qs = new URLSearchParams();
+      if (params?.target_type) qs.set("target_type", params.target_type);
+      if (params?.target_id) qs.set("target_id", String(params.target_id));
+      return request<Paginated<ListingInteractions>>(`/interactions/mine?${qs}`, { token });
+    },
+    contact: (data: { target_type: "provider" | "business"; target_id: number }, token: string) =>
+      request<{ message: string }>("/interactions/contact", { method: "POST", body: data, token }),
+  },
+
   // ── Categories ───────────────────────────────────────────────────────────
   categories: {
     all: () => request<{ categories: Category[] }>("/categories/allCategories"),
@@ -414,8 +438,8 @@ export const api = {
   admin: {
     dashboard: (token: string) =>
       request<AdminDashboardStats>("/admin/dashboard", { token }),
-    users: (token: string) =>
-      request<{ users: AdminUser[] }>("/admin/users", { token }),
+    users: (token: string, page = 1, perPage = 100) =>
+      request<Paginated<AdminUser>>(`/admin/users?page=${page}&per_page=${perPage}`, { token }),
     deleteUser: (userId: number, token: string) =>
       request("/admin/delete_user", { method: "POST", body: { user_id: userId }, token }),
     userAnalytics: (token: string) =>
@@ -458,6 +482,34 @@ export type User = {
   role: "client" | "provider" | "business" | "admin";
   email_verified?: boolean;
   onboarding_completed?: boolean;
+  is_admin?: boolean;
+};
+
+export type MoneyAmount = { amount: string; formatted: string };
+
+export type DashboardBooking = {
+  id: number;
+  confirmation_code: string;
+  status: string;
+  scheduled_time: string;
+  target_type: string;
+  target_id: number;
+  with_name: string | null;
+  service_name: string | null;
+};
+
+export type ActionItem = {
+  type: string;
+  message: string;
+  count: number;
+  link: Record<string, unknown>;
+  occurrence: string;
+};
+
+export type OnboardingProgress = {
+  score: number;
+  complete: boolean;
+  steps: { key: string; label: string; weight: number; done: boolean }[];
 };
 
 export type DashboardData = {
@@ -466,7 +518,40 @@ export type DashboardData = {
   email: string;
   role: string;
   unread_notifications: number;
-  upcoming_bookings: number;
+  unread_messages: number;
+  upcoming_bookings?: number;
+  upcoming?: DashboardBooking[];
+  pending_review_invites?: { target_type: string; target_id: number; target_name: string | null; booking_id: number }[];
+  todays_bookings?: DashboardBooking[];
+  new_reviews?: number;
+  bookings_this_week?: number;
+  bookings_last_week?: number;
+  bookings_week_delta?: number;
+  earnings?: {
+    currency: string;
+    collected_this_week: MoneyAmount;
+    collected_this_month: MoneyAmount;
+    pending_payouts: MoneyAmount;
+    daily: { date: string; amount: string }[];
+  };
+  action_items?: ActionItem[];
+  onboarding?: OnboardingProgress;
+  open_flags?: {
+    count: number;
+    latest: { id: number; target_type: string; target_id: number; reason: string; open_reports_on_target: number; created_at: string }[];
+  };
+  pending_verifications?: {
+    count: number;
+    oldest: { entity_type: string; entity_id: number; name: string | null; created_at: string | null }[];
+  };
+  failing_jobs?: { job_name: string; failures: number; last_failure_at: string; last_error: string | null }[];
+  overview?: {
+    users: { all: number; new_30d: number };
+    bookings_created: { this_week: number; last_week: number };
+    gmv_last_30d: number;
+    open_disputes: number;
+    pending_appeals: number;
+  };
   provider_id?: number;
   business_id?: number;
   pending_bookings?: number;
@@ -483,11 +568,15 @@ export type SearchParams = {
   radius_km?: number;
   page?: number;
   per_page?: number;
+  type?: SearchResultType;
+  county_id?: number;
 };
 
+export type SearchResultType = "provider" | "business" | "service";
+
 export type SearchResult = {
   id: number;
-  type: "provider" | "business";
+  type: SearchResultType;
   name: string;
   description?: string;
   category?: string;
@@ -496,43 +585,46 @@ export type SearchResult = {
   avg_rating?: number;
   review_count?: number;
   distance_km?: number;
+  // Services only: who offers it, and what it costs
+  owner_type?: "provider" | "business";
+  owner_id?: number;
+  owner_name?: string;
+  price?: string;
 };
 
-export type SearchResults = { results: SearchResult[]; total: number };
+export type SearchCounts = Record<SearchResultType, number>;
 
-// Raw shape returned by the backend /search endpoint before transformation
-type RawProviderResult = {
-  id: number;
-  service_name?: string;
-  service_description?: string;
-  category?: string;
-  location?: string;
-  profile_photo?: string;
-  average_rating: number;
-  review_count: number;
-  distance_km?: number;
+export type SearchResults = { results: SearchResult[]; total: number; counts: SearchCounts };
+
+// Envelope returned by paginated list endpoints
+export type Paginated<T> = {
+  items: T[];
+  page: number;
+  per_page: number;
+  total: number;
+  has_more: boolean;
 };
 
-type RawBusinessResult = {
+// Raw shape returned by the backend /search endpoint before transformation
+type RawSearchHit = {
+  type: SearchResultType;
   id: number;
-  business_name: string;
+  name?: string;
   description?: string;
   category?: string;
   location?: string;
-  profile_photo?: string;
-  logo?: string;
+  photo_url?: string;
+  owner_type?: "provider" | "business";
+  owner_id?: number;
+  owner_name?: string;
+  price?: string;
   average_rating: number;
   review_count: number;
   distance_km?: number;
+  score: number;
 };
 
-type RawSearchResponse = {
-  providers: RawProviderResult[];
-  businesses: RawBusinessResult[];
-  total: number;
-  page: number;
-  per_page: number;
-};
+type RawSearchResponse = Paginated<RawSearchHit> & { counts: SearchCounts };
 
 export type PublicProvider = {
   id: number;
@@ -577,6 +669,30 @@ export type BusinessProfile = PublicBusiness & {
   onboarding_completed?: boolean;
 };
 
+export type Favorite = {
+  target_type: "provider" | "business";
+  target_id: number;
+  notify_on_posts: boolean;
+  name?: string;
+  photo?: string;
+  photo_thumb_url?: string;
+  category?: string;
+  location?: string;
+  avg_rating?: number;
+  review_count: number;
+};
+
+export type InteractionType = "message" | "booking_created" | "booking_completed" | "contact_click" | "favorite";
+
+// What the signed-in user has done with one listing
+export type ListingInteractions = {
+  target_type: "provider" | "business";
+  target_id: number;
+  interaction_types: InteractionType[];
+  first_at: string;
+  last_at: string;
+};
+
 export type Service = {
   id: number;
   title: string;
@@ -707,6 +823,7 @@ export type Conversation = {
   last_message: string;
   last_message_at: string;
   unread_count: number;
+  other_last_seen_at?: string | null;
 };
 
 export type Notification = {
@@ -716,7 +833,9 @@ export type Notification = {
   body: string;
   target_type?: string;
   target_id?: number;
+  data?: Record<string, unknown> | null;
   is_read: boolean;
+  read_at?: string | null;
   created_at: string;
 };
 
@@ -794,6 +913,8 @@ export type Post = {
   image_urls: string[];
   like_count: number;
   comment_count: number;
+  /** Present only when the request was signed in. */
+  liked_by_me?: boolean;
   created_at: string;
   updated_at: string;
 };
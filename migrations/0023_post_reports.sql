@@ -0,0 +1,49 @@
+-- Post takedown: hidden posts drop out of public feeds but stay visible to their author
+ALTER TABLE posts
+    ADD COLUMN IF NOT EXISTS hidden        BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS hidden_reason TEXT,
+    ADD COLUMN IF NOT EXISTS appeal_note   TEXT;
+
+-- Post reports share the content_flags table with review reports.
+-- detail:        free-text context from the reporter
+-- resolution:    what the admin did with the report once resolved
+-- target_owner:  user who authored the reported content, kept so repeat
+--                offenders can still be counted after a post is deleted
+ALTER TABLE content_flags
+    ADD COLUMN IF NOT EXISTS detail          TEXT,
+    ADD COLUMN IF NOT EXISTS resolution      VARCHAR(20)
+        CHECK (resolution IN ('dismissed', 'hidden', 'deleted')),
+    ADD COLUMN IF NOT EXISTS resolved_by     INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS resolved_at     TIMESTAMP WITHOUT TIME ZONE,
+    ADD COLUMN IF NOT EXISTS target_owner_id INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+-- One report per user per item. Existing duplicates are merged into the
+-- earliest of them: it collects every distinct reason given and stays open
+-- while any of the duplicates was still open. Only then are the rest removed.
+UPDATE content_flags f
+SET reason = d.reasons, resolved = d.resolved
+FROM (
+    SELECT MIN(id) AS keep_id,
+           string_agg(DISTINCT reason, '; ' ORDER BY reason) AS reasons,
+           bool_and(resolved) AS resolved
+    FROM content_flags
+    WHERE flagged_by IS NOT NULL
+    GROUP BY target_type, target_id, flagged_by
+    HAVING COUNT(*) > 1
+) d
+WHERE f.id = d.keep_id;
+
+DELETE FROM content_flags a USING content_flags b
+    WHERE a.id > b.id
+      AND a.target_type = b.target_type
+      AND a.target_id = b.target_id
+      AND a.flagged_by = b.flagged_by;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_content_flags_one_per_reporter
+    ON content_flags (target_type, target_id, flagged_by)
+    WHERE flagged_by IS NOT NULL;
+
+-- Used by the repeat-offender count in the admin user list
+CREATE INDEX IF NOT EXISTS idx_content_flags_owner_upheld
+    ON content_flags (target_owner_id, resolved_at)
+    WHERE resolution IN ('hidden', 'deleted');
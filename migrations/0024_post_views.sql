@@ -0,0 +1,17 @@
+-- Running total shown on feed cards
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS view_count INTEGER NOT NULL DEFAULT 0;
+
+-- One row per viewer per post per day, used to deduplicate views and to chart
+-- daily reach. viewer_key is 'u:<user_id>' for signed-in users and, for
+-- anonymous visitors, 'ip:' and the first 32 hex digits of an HMAC-SHA256 of
+-- the day and client IP keyed by VIEWER_HASH_SECRET. Days are UTC. Pruned
+-- after 90 days by the cleanup sweep.
+CREATE TABLE IF NOT EXISTS post_view_events (
+    post_id    INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    viewed_on  DATE    NOT NULL DEFAULT CURRENT_DATE,
+    viewer_key TEXT    NOT NULL,
+    PRIMARY KEY (post_id, viewed_on, viewer_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_post_view_events_viewed_on
+    ON post_view_events (viewed_on);
@@ -0,0 +1,40 @@
+-- posts.created_at / updated_at must be TIMESTAMPTZ everywhere. Databases that
+-- predate 0001 may still have them as TIMESTAMP WITHOUT TIME ZONE; those values
+-- were always written as UTC, so reinterpret them as such.
+DO $$
+BEGIN
+    IF (SELECT data_type FROM information_schema.columns
+        WHERE table_name = 'posts' AND column_name = 'created_at') = 'timestamp without time zone' THEN
+        ALTER TABLE posts ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at AT TIME ZONE 'UTC';
+    END IF;
+    IF (SELECT data_type FROM information_schema.columns
+        WHERE table_name = 'posts' AND column_name = 'updated_at') = 'timestamp without time zone' THEN
+        ALTER TABLE posts ALTER COLUMN updated_at TYPE TIMESTAMPTZ USING updated_at AT TIME ZONE 'UTC';
+    END IF;
+END
+$$;
+
+UPDATE posts SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE posts SET updated_at = created_at WHERE updated_at IS NULL;
+
+ALTER TABLE posts
+    ALTER COLUMN created_at SET DEFAULT NOW(),
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET DEFAULT NOW(),
+    ALTER COLUMN updated_at SET NOT NULL;
+
+-- Keep updated_at current on edits without every UPDATE having to remember it.
+-- Counter and moderation updates (view_count, hidden, ...) leave it alone.
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS posts_set_updated_at ON posts;
+CREATE TRIGGER posts_set_updated_at
+    BEFORE UPDATE ON posts
+    FOR EACH ROW
+    WHEN (OLD.title IS DISTINCT FROM NEW.title OR OLD.content IS DISTINCT FROM NEW.content)
+    EXECUTE FUNCTION set_updated_at();
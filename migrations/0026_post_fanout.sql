@@ -0,0 +1,22 @@
+-- Followers can mute post notifications per provider/business they favourite
+ALTER TABLE favorites ADD COLUMN IF NOT EXISTS notify_on_posts BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Structured payload so the app can deep-link from a notification
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS data JSONB;
+
+-- Set by the fan-out sweep once followers have been notified about a post.
+-- Existing posts are treated as already fanned out.
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS fanned_out_at TIMESTAMPTZ;
+UPDATE posts SET fanned_out_at = NOW() WHERE fanned_out_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_posts_pending_fanout
+    ON posts (id) WHERE fanned_out_at IS NULL;
+
+-- At most one post notification per follower per author per day
+CREATE TABLE IF NOT EXISTS post_fanout_log (
+    user_id     INTEGER     NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    author_type VARCHAR(50) NOT NULL CHECK (author_type IN ('provider', 'business')),
+    author_id   INTEGER     NOT NULL,
+    sent_on     DATE        NOT NULL DEFAULT CURRENT_DATE,
+    PRIMARY KEY (user_id, author_type, author_id, sent_on)
+);
@@ -0,0 +1,9 @@
+-- Activity timestamps behind the admin user analytics.
+-- last_login_at: set on successful password login
+-- last_seen_at:  bumped (at most every 5 minutes) by any authenticated request
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS last_login_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS last_seen_at  TIMESTAMPTZ;
+
+-- Registration series and cohort queries range-scan on signup date
+CREATE INDEX IF NOT EXISTS idx_users_created_at ON users (created_at);
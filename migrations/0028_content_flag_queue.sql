@@ -0,0 +1,35 @@
+-- One moderation queue for every kind of report, user-raised or admin-raised.
+-- flagged_by stays NULL when an admin flags something directly.
+ALTER TABLE content_flags
+    ADD COLUMN IF NOT EXISTS status VARCHAR(10)
+        GENERATED ALWAYS AS (CASE WHEN resolved THEN 'resolved' ELSE 'open' END) STORED;
+
+ALTER TABLE content_flags DROP CONSTRAINT IF EXISTS content_flags_resolution_check;
+ALTER TABLE content_flags ADD CONSTRAINT content_flags_resolution_check
+    CHECK (resolution IN ('dismissed', 'hidden', 'deleted', 'owner_suspended'));
+
+ALTER TABLE content_flags DROP CONSTRAINT IF EXISTS content_flags_target_type_check;
+ALTER TABLE content_flags ADD CONSTRAINT content_flags_target_type_check
+    CHECK (target_type IN ('review', 'post', 'message', 'provider', 'business')) NOT VALID;
+
+-- Admin queue is read newest-first, open items only by default
+CREATE INDEX IF NOT EXISTS idx_content_flags_open_created
+    ON content_flags (created_at DESC)
+    WHERE resolved = FALSE;
+
+-- Hidden reviews drop out of public listings and rating averages
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS hidden BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Append-only record of admin actions
+CREATE TABLE IF NOT EXISTS audit_log (
+    id          BIGSERIAL PRIMARY KEY,
+    actor_id    INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    action      VARCHAR(50) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id   INTEGER NOT NULL,
+    details     JSONB NOT NULL DEFAULT '{}',
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log (target_type, target_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log (created_at DESC);
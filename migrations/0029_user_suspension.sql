@@ -0,0 +1,18 @@
+-- Account-level suspension. suspended_until NULL with suspended_at set means
+-- indefinite; a past suspended_until means the suspension has lapsed on its own.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS suspended_at      TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS suspended_until   TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS suspension_reason TEXT;
+
+-- Single definition of "currently suspended" shared by the auth check and
+-- every public listing query
+CREATE OR REPLACE FUNCTION user_is_suspended(uid INTEGER) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM users
+        WHERE id = uid
+          AND suspended_at IS NOT NULL
+          AND (suspended_until IS NULL OR suspended_until > NOW())
+    )
+$$;
@@ -0,0 +1,3 @@
+-- Upload size, for storage accounting. NULL for rows created before sizes were
+-- recorded and for post attachments that reference an already-uploaded file.
+ALTER TABLE attachments ADD COLUMN IF NOT EXISTS size_bytes BIGINT;
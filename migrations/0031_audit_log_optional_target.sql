@@ -0,0 +1,2 @@
+-- Some audited actions (data exports, settings changes) have no single target row
+ALTER TABLE audit_log ALTER COLUMN target_id DROP NOT NULL;
@@ -0,0 +1,28 @@
+-- Admin-authored banners shown in the apps (maintenance windows, outages).
+-- ends_at NULL means the banner stays up until an admin removes it.
+CREATE TABLE IF NOT EXISTS announcements (
+    id          SERIAL PRIMARY KEY,
+    title       VARCHAR(200) NOT NULL,
+    body        TEXT NOT NULL,
+    severity    VARCHAR(20) NOT NULL DEFAULT 'info'
+        CHECK (severity IN ('info', 'warning', 'critical')),
+    audience    VARCHAR(20) NOT NULL DEFAULT 'all'
+        CHECK (audience IN ('all', 'clients', 'providers', 'businesses')),
+    starts_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ends_at     TIMESTAMPTZ,
+    created_by  INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_at IS NULL OR ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_announcements_window
+    ON announcements (starts_at, ends_at);
+
+-- One row per user per dismissed banner
+CREATE TABLE IF NOT EXISTS announcement_dismissals (
+    announcement_id INTEGER NOT NULL REFERENCES announcements(id) ON DELETE CASCADE,
+    user_id         INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    dismissed_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (announcement_id, user_id)
+);
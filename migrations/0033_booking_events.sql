@@ -0,0 +1,30 @@
+-- Per-booking timeline: every status change, reschedule and admin action,
+-- with who did it. actor_id NULL means the system (payment callback etc.).
+CREATE TABLE IF NOT EXISTS booking_events (
+    id          BIGSERIAL PRIMARY KEY,
+    booking_id  INTEGER NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+    actor_id    INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    event_type  VARCHAR(30) NOT NULL,
+    from_status TEXT,
+    to_status   TEXT,
+    note        TEXT,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_booking_events_booking
+    ON booking_events (booking_id, created_at);
+
+-- Existing bookings start their timeline at creation
+INSERT INTO booking_events (booking_id, actor_id, event_type, to_status, created_at)
+SELECT b.id, b.client_id, 'created', 'pending', COALESCE(b.created_at, NOW())
+FROM bookings b
+WHERE NOT EXISTS (SELECT 1 FROM booking_events e WHERE e.booking_id = b.id);
+
+-- Short code both parties can quote to support. The volatile default gives
+-- every existing row its own value when the column is added.
+ALTER TABLE bookings
+    ADD COLUMN IF NOT EXISTS confirmation_code VARCHAR(8) NOT NULL
+        DEFAULT upper(substr(md5(random()::text || clock_timestamp()::text), 1, 8));
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_bookings_confirmation_code
+    ON bookings (confirmation_code);
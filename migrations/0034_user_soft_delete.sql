@@ -0,0 +1,22 @@
+-- Admin deletion is two-phase: deleted_at hides the account and blocks login,
+-- and the cleanup sweep purges it once the retention window has passed.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at
+    ON users (deleted_at)
+    WHERE deleted_at IS NOT NULL;
+
+-- Whether a user's profile, listings and posts should be kept off public
+-- surfaces: suspended or pending deletion.
+CREATE OR REPLACE FUNCTION user_is_hidden(uid INTEGER) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM users
+        WHERE id = uid
+          AND (deleted_at IS NOT NULL
+               OR (suspended_at IS NOT NULL
+                   AND (suspended_until IS NULL OR suspended_until > NOW())))
+    )
+$$;
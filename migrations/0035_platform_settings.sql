@@ -0,0 +1,8 @@
+-- Runtime-tunable limits, edited through /admin/settings. Keys missing here
+-- fall back to the defaults compiled into utils/settings.rs.
+CREATE TABLE IF NOT EXISTS platform_settings (
+    key        TEXT PRIMARY KEY,
+    value      JSONB NOT NULL,
+    updated_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
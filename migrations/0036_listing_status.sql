@@ -0,0 +1,24 @@
+-- Moderation state of a provider/business listing. Existing profiles are
+-- grandfathered in as approved; new onboards start as pending_review only
+-- while the listings.require_review setting is on.
+ALTER TABLE providers
+    ADD COLUMN IF NOT EXISTS listing_status VARCHAR(20) NOT NULL DEFAULT 'approved'
+        CHECK (listing_status IN ('pending_review', 'approved', 'rejected')),
+    ADD COLUMN IF NOT EXISTS listing_reviewed_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS listing_reviewed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS listing_rejection_reason TEXT;
+
+ALTER TABLE businesses
+    ADD COLUMN IF NOT EXISTS listing_status VARCHAR(20) NOT NULL DEFAULT 'approved'
+        CHECK (listing_status IN ('pending_review', 'approved', 'rejected')),
+    ADD COLUMN IF NOT EXISTS listing_reviewed_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS listing_reviewed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS listing_rejection_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_providers_listing_pending
+    ON providers (id)
+    WHERE listing_status = 'pending_review';
+
+CREATE INDEX IF NOT EXISTS idx_businesses_listing_pending
+    ON businesses (id)
+    WHERE listing_status = 'pending_review';
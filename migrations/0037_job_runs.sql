@@ -0,0 +1,17 @@
+-- One row per pass of a background job. finished_at stays NULL while the pass
+-- is running (or if the process died mid-run).
+CREATE TABLE IF NOT EXISTS job_runs (
+    id              BIGSERIAL PRIMARY KEY,
+    job_name        TEXT NOT NULL,
+    started_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at     TIMESTAMPTZ,
+    items_processed BIGINT NOT NULL DEFAULT 0,
+    error           TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_runs_name_started
+    ON job_runs (job_name, started_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_job_runs_failed
+    ON job_runs (started_at DESC)
+    WHERE error IS NOT NULL;
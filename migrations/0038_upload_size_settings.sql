@@ -0,0 +1,8 @@
+-- attachments.max_file_bytes became uploads.max_image_bytes when the cap was
+-- extended to profile media; carry over any value an admin already set.
+UPDATE platform_settings
+SET key = 'uploads.max_image_bytes'
+WHERE key = 'attachments.max_file_bytes'
+  AND NOT EXISTS (SELECT 1 FROM platform_settings WHERE key = 'uploads.max_image_bytes');
+
+DELETE FROM platform_settings WHERE key = 'attachments.max_file_bytes';
@@ -0,0 +1,18 @@
+-- Resized copies of uploaded images, keyed by the original's public path.
+CREATE TABLE IF NOT EXISTS image_variants (
+    original_path TEXT PRIMARY KEY,
+    thumb_path    TEXT NOT NULL,
+    medium_path   TEXT NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Path of the 'thumb' or 'medium' variant of an image, or the original path
+-- when no variant was generated.
+CREATE OR REPLACE FUNCTION image_variant(path TEXT, variant TEXT) RETURNS TEXT
+LANGUAGE sql STABLE AS $$
+    SELECT COALESCE(
+        (SELECT CASE variant WHEN 'thumb' THEN thumb_path ELSE medium_path END
+         FROM image_variants WHERE original_path = path),
+        path
+    )
+$$;
@@ -0,0 +1,5 @@
+-- Per-target attachment quotas sum the uploads filed under a provider or
+-- business. Post attachments point at files already counted, so they are left out.
+CREATE INDEX IF NOT EXISTS idx_attachments_target_uploads
+    ON attachments (target_type, target_id)
+    WHERE post_id IS NULL;
@@ -0,0 +1,9 @@
+-- Who may fetch an attachment: anyone ('public', served from /uploads),
+-- clients with a booking or conversation with the target ('participants'),
+-- or only the owner and admins ('admin'). Non-public files are kept out of
+-- the static directory and served through /attachments/:id/download.
+ALTER TABLE attachments
+    ADD COLUMN IF NOT EXISTS visibility VARCHAR(20) NOT NULL DEFAULT 'public'
+        CHECK (visibility IN ('public', 'participants', 'admin')),
+    -- Sniffed at upload; NULL for rows created before it was recorded
+    ADD COLUMN IF NOT EXISTS content_type TEXT;
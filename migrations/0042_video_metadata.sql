@@ -0,0 +1,7 @@
+-- Probed at upload for video attachments; NULL for images and older rows.
+-- poster_path is a JPEG frame stored next to the video.
+ALTER TABLE attachments
+    ADD COLUMN IF NOT EXISTS duration_seconds DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS width INTEGER,
+    ADD COLUMN IF NOT EXISTS height INTEGER,
+    ADD COLUMN IF NOT EXISTS poster_path TEXT;
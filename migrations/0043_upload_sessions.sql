@@ -0,0 +1,26 @@
+-- Resumable uploads: a session per file being sent in chunks, and one row per
+-- chunk received. The chunk data itself sits in storage until completion.
+CREATE TABLE IF NOT EXISTS upload_sessions (
+    id           SERIAL PRIMARY KEY,
+    user_id      INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_type  VARCHAR(20) NOT NULL,
+    target_id    INTEGER NOT NULL,
+    visibility   VARCHAR(20) NOT NULL DEFAULT 'public',
+    file_name    TEXT NOT NULL,
+    total_bytes  BIGINT NOT NULL,
+    chunk_bytes  INTEGER NOT NULL,
+    checksum     TEXT NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_upload_sessions_user ON upload_sessions (user_id);
+CREATE INDEX IF NOT EXISTS idx_upload_sessions_updated ON upload_sessions (updated_at);
+
+CREATE TABLE IF NOT EXISTS upload_chunks (
+    session_id   INTEGER NOT NULL REFERENCES upload_sessions(id) ON DELETE CASCADE,
+    chunk_number INTEGER NOT NULL,
+    size_bytes   INTEGER NOT NULL,
+    sha256       TEXT NOT NULL,
+    PRIMARY KEY (session_id, chunk_number)
+);
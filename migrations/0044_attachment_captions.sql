@@ -0,0 +1,9 @@
+-- Optional caption and screen-reader text for attachments; sessions carry
+-- them until a resumable upload completes.
+ALTER TABLE attachments
+    ADD COLUMN IF NOT EXISTS caption VARCHAR(200),
+    ADD COLUMN IF NOT EXISTS alt_text VARCHAR(300);
+
+ALTER TABLE upload_sessions
+    ADD COLUMN IF NOT EXISTS caption VARCHAR(200),
+    ADD COLUMN IF NOT EXISTS alt_text VARCHAR(300);
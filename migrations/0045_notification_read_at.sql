@@ -0,0 +1,21 @@
+-- When a notification was read, replacing the is_read flag. Rows already
+-- marked read count as read when they were created.
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS read_at TIMESTAMP WITHOUT TIME ZONE;
+UPDATE notifications SET read_at = COALESCE(created_at, NOW()) WHERE is_read AND read_at IS NULL;
+
+DROP INDEX IF EXISTS idx_notifications_user_unread;
+ALTER TABLE notifications DROP COLUMN IF EXISTS is_read;
+
+UPDATE notifications SET created_at = NOW() WHERE created_at IS NULL;
+ALTER TABLE notifications ALTER COLUMN created_at SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_unread
+    ON notifications (user_id) WHERE read_at IS NULL;
+
+-- Newest-first listing per user
+CREATE INDEX IF NOT EXISTS idx_notifications_user_created
+    ON notifications (user_id, created_at DESC, id DESC);
+
+-- Retention sweep over read notifications
+CREATE INDEX IF NOT EXISTS idx_notifications_read_created
+    ON notifications (created_at) WHERE read_at IS NOT NULL;
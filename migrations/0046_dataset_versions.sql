@@ -0,0 +1,41 @@
+-- Version counters for slow-changing reference data, used as ETags so
+-- clients can revalidate the category tree and location lists cheaply.
+CREATE TABLE IF NOT EXISTS dataset_versions (
+    name       TEXT PRIMARY KEY,
+    version    BIGINT NOT NULL DEFAULT 1,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO dataset_versions (name) VALUES ('categories'), ('locations')
+ON CONFLICT (name) DO NOTHING;
+
+-- Any write to a dataset's tables bumps its version, whether it comes from an
+-- admin endpoint, the seed loader or a manual fix.
+CREATE OR REPLACE FUNCTION bump_dataset_version() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE dataset_versions
+    SET version = version + 1, updated_at = NOW()
+    WHERE name = TG_ARGV[0];
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS categories_bump_version ON categories;
+CREATE TRIGGER categories_bump_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON categories
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_dataset_version('categories');
+
+DROP TRIGGER IF EXISTS counties_bump_version ON counties;
+CREATE TRIGGER counties_bump_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON counties
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_dataset_version('locations');
+
+DROP TRIGGER IF EXISTS constituencies_bump_version ON constituencies;
+CREATE TRIGGER constituencies_bump_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON constituencies
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_dataset_version('locations');
+
+DROP TRIGGER IF EXISTS wards_bump_version ON wards;
+CREATE TRIGGER wards_bump_version
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON wards
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_dataset_version('locations');
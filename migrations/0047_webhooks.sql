@@ -0,0 +1,46 @@
+-- Outbound webhooks: a provider or business owner subscribes a URL to events
+-- on their listing, and a background job delivers them.
+CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+    id                   SERIAL PRIMARY KEY,
+    user_id              INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_type          TEXT NOT NULL CHECK (target_type IN ('provider', 'business')),
+    target_id            INTEGER NOT NULL,
+    url                  TEXT NOT NULL,
+    secret               TEXT NOT NULL,
+    event_types          TEXT[] NOT NULL,
+    active               BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Failed attempts in a row across all deliveries; reset by a success
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    disabled_reason      TEXT,
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_subscriptions_target
+    ON webhook_subscriptions (target_type, target_id)
+    WHERE active;
+
+CREATE INDEX IF NOT EXISTS idx_webhook_subscriptions_user
+    ON webhook_subscriptions (user_id);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id               BIGSERIAL PRIMARY KEY,
+    subscription_id  INTEGER NOT NULL REFERENCES webhook_subscriptions(id) ON DELETE CASCADE,
+    event_type       TEXT NOT NULL,
+    payload          JSONB NOT NULL,
+    status           TEXT NOT NULL DEFAULT 'pending'
+                         CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts         INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INTEGER,
+    last_error       TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at     TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due
+    ON webhook_deliveries (next_attempt_at)
+    WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_subscription
+    ON webhook_deliveries (subscription_id, created_at DESC);
@@ -0,0 +1,13 @@
+-- Admin overrides for feature flags, edited through /admin/feature_flags.
+-- Flags without a row use the FEATURE_FLAGS / compiled-in default for the
+-- environment (see utils/flags.rs).
+CREATE TABLE IF NOT EXISTS feature_flags (
+    name            TEXT PRIMARY KEY,
+    enabled         BOOLEAN NOT NULL,
+    -- Share of signed-in users who get the feature, by a stable hash of their id
+    rollout_percent SMALLINT NOT NULL DEFAULT 100 CHECK (rollout_percent BETWEEN 0 AND 100),
+    -- Roles the feature is limited to; empty means every role
+    roles           TEXT[] NOT NULL DEFAULT '{}',
+    updated_by      INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    updated_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,22 @@
+-- Indexes for the listing read paths, which scanned whole tables once they
+-- grew past a few thousand rows.
+
+-- Ratings per provider/business (listings, public profiles, search)
+CREATE INDEX IF NOT EXISTS idx_reviews_target
+    ON reviews (target_type, target_id);
+
+-- Bookings received by a provider/business, newest appointment first
+CREATE INDEX IF NOT EXISTS idx_bookings_target_scheduled
+    ON bookings (target_type, target_id, scheduled_time DESC);
+
+-- Unread counts, and the receiving half of the conversation list
+CREATE INDEX IF NOT EXISTS idx_messages_receiver_read
+    ON messages (receiver_id, is_read);
+
+-- The sending half of the conversation list
+CREATE INDEX IF NOT EXISTS idx_messages_sender
+    ON messages (sender_id);
+
+-- A user's favorites, newest first
+CREATE INDEX IF NOT EXISTS idx_favorites_user_created
+    ON favorites (user_id, created_at DESC);
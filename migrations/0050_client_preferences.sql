@@ -0,0 +1,8 @@
+-- Name shown to providers and businesses instead of the login username
+ALTER TABLE users ADD COLUMN IF NOT EXISTS display_name TEXT;
+
+-- Client notification preferences. In-app notifications are always sent;
+-- these only turn off the booking SMS and email copies.
+ALTER TABLE clients
+    ADD COLUMN IF NOT EXISTS sms_notifications   BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN IF NOT EXISTS email_notifications BOOLEAN NOT NULL DEFAULT TRUE;
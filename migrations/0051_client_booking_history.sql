@@ -0,0 +1,3 @@
+-- A client's bookings by appointment time (GET /clients/bookings)
+CREATE INDEX IF NOT EXISTS idx_bookings_client_scheduled
+    ON bookings (client_id, scheduled_time);
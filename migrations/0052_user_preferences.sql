@@ -0,0 +1,7 @@
+-- Per-user switches, edited through /clients/preferences. Only values a user
+-- has changed are stored; the keys, types and defaults live in utils/prefs.rs.
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id    INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    prefs      JSONB NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,30 @@
+-- Saved addresses a client can pick when booking a home service
+CREATE TABLE IF NOT EXISTS client_addresses (
+    id          SERIAL PRIMARY KEY,
+    user_id     INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    label       TEXT NOT NULL,
+    ward_id     INTEGER REFERENCES wards(id) ON DELETE SET NULL,
+    address     TEXT NOT NULL,
+    latitude    DOUBLE PRECISION,
+    longitude   DOUBLE PRECISION,
+    -- Number to call at this address, instead of the profile phone
+    phone       TEXT,
+    is_default  BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_client_addresses_user ON client_addresses (user_id);
+
+-- At most one default per client
+CREATE UNIQUE INDEX IF NOT EXISTS idx_client_addresses_default
+    ON client_addresses (user_id)
+    WHERE is_default;
+
+-- Bookings keep a copy of the chosen address in client_address,
+-- client_latitude/longitude and client_phone, so editing or deleting the
+-- saved address never changes where a past booking took place.
+ALTER TABLE bookings
+    ADD COLUMN IF NOT EXISTS address_id           INTEGER REFERENCES client_addresses(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS client_address_label TEXT,
+    ADD COLUMN IF NOT EXISTS client_ward_id       INTEGER REFERENCES wards(id) ON DELETE SET NULL;
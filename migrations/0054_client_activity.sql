@@ -0,0 +1,6 @@
+-- Client activity feed: a client's reviews and the replies to them
+CREATE INDEX IF NOT EXISTS idx_reviews_reviewer
+    ON reviews (reviewer_id);
+
+CREATE INDEX IF NOT EXISTS idx_review_replies_review
+    ON review_replies (review_id);
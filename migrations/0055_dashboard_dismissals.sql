@@ -0,0 +1,10 @@
+-- Dashboard action items an owner has dismissed. `occurrence` identifies
+-- the instance dismissed (see utils::action_items), so the item comes back
+-- when something new needs attention.
+CREATE TABLE IF NOT EXISTS dashboard_dismissals (
+    user_id      INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    item_type    TEXT NOT NULL,
+    occurrence   TEXT NOT NULL,
+    dismissed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, item_type, occurrence)
+);
@@ -0,0 +1,20 @@
+-- Onboarding completeness (see utils::onboarding). The score is stored so
+-- list_providers can rank complete profiles first without recomputing it.
+ALTER TABLE providers
+    ADD COLUMN IF NOT EXISTS phone_verified_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS onboarding_score SMALLINT NOT NULL DEFAULT 0;
+
+-- Backfill with the weights in effect when this ran
+UPDATE providers p SET onboarding_score =
+      CASE WHEN NULLIF(TRIM(p.service_name), '') IS NOT NULL
+                AND NULLIF(TRIM(p.service_description), '') IS NOT NULL
+                AND NULLIF(TRIM(p.category), '') IS NOT NULL
+                AND NULLIF(TRIM(p.location), '') IS NOT NULL
+                AND NULLIF(TRIM(p.phone_number), '') IS NOT NULL
+                AND NULLIF(TRIM(p.email), '') IS NOT NULL THEN 25 ELSE 0 END
+    + CASE WHEN p.profile_photo IS NOT NULL OR p.cover_photo IS NOT NULL THEN 15 ELSE 0 END
+    + CASE WHEN EXISTS (SELECT 1 FROM provider_availability a
+                        WHERE a.provider_id = p.id AND a.is_available) THEN 15 ELSE 0 END
+    + CASE WHEN EXISTS (SELECT 1 FROM services s
+                        WHERE s.target_type = 'provider' AND s.target_id = p.id AND s.is_active) THEN 20 ELSE 0 END
+    + CASE WHEN EXISTS (SELECT 1 FROM provider_locations l WHERE l.provider_id = p.id) THEN 15 ELSE 0 END;
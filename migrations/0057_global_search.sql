@@ -0,0 +1,70 @@
+-- Stored search documents for /search. Names weigh most, then category,
+-- then the free-text description.
+ALTER TABLE providers
+    ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english'::regconfig, coalesce(service_name, '')), 'A') ||
+        setweight(to_tsvector('english'::regconfig, coalesce(category, '')), 'B') ||
+        setweight(to_tsvector('english'::regconfig, coalesce(service_description, '')), 'C')
+    ) STORED;
+
+ALTER TABLE businesses
+    ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english'::regconfig, coalesce(business_name, '')), 'A') ||
+        setweight(to_tsvector('english'::regconfig, coalesce(category, '')), 'B') ||
+        setweight(to_tsvector('english'::regconfig, coalesce(description, '')), 'C')
+    ) STORED;
+
+ALTER TABLE services
+    ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english'::regconfig, coalesce(title, '')), 'A') ||
+        setweight(to_tsvector('english'::regconfig, coalesce(description, '')), 'C')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_providers_search_vector ON providers USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_businesses_search_vector ON businesses USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_services_search_vector ON services USING GIN (search_vector);
+
+-- Replaced by the stored columns above
+DROP INDEX IF EXISTS idx_providers_fts;
+DROP INDEX IF EXISTS idx_businesses_fts;
+
+-- Great-circle distance in km.
+CREATE OR REPLACE FUNCTION haversine_km(lat1 FLOAT8, lng1 FLOAT8, lat2 FLOAT8, lng2 FLOAT8) RETURNS FLOAT8
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT 6371.0 * 2.0 * asin(LEAST(1.0, sqrt(
+        power(sin(radians(lat2 - lat1) / 2.0), 2) +
+        cos(radians(lat1)) * cos(radians(lat2)) *
+        power(sin(radians(lng2 - lng1) / 2.0), 2)
+    )))
+$$;
+
+-- Where a provider or business can be found: its provider locations or
+-- business branches, with the county each sits in when known.
+CREATE OR REPLACE FUNCTION listing_places(owner_type TEXT, owner_id INTEGER)
+RETURNS TABLE (latitude FLOAT8, longitude FLOAT8, county_id INTEGER)
+LANGUAGE sql STABLE AS $$
+    SELECT pl.latitude, pl.longitude, c.county_id
+    FROM provider_locations pl
+    JOIN wards w ON w.id = pl.ward_id
+    JOIN constituencies c ON c.id = w.constituency_id
+    WHERE owner_type = 'provider' AND pl.provider_id = owner_id
+    UNION ALL
+    SELECT bb.latitude, bb.longitude, c.county_id
+    FROM business_branches bb
+    LEFT JOIN wards w ON w.id = bb.ward_id
+    LEFT JOIN constituencies c ON c.id = w.constituency_id
+    WHERE owner_type = 'business' AND bb.business_id = owner_id
+$$;
+
+-- A sample of searches, for popular-term ranking and zero-result analysis.
+CREATE TABLE IF NOT EXISTS search_queries (
+    id           BIGSERIAL PRIMARY KEY,
+    query        TEXT NOT NULL,
+    result_type  TEXT,
+    county_id    INTEGER,
+    result_count INTEGER NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_queries_created_at ON search_queries (created_at);
+CREATE INDEX IF NOT EXISTS idx_search_queries_zero_results ON search_queries (created_at) WHERE result_count = 0;
@@ -0,0 +1,38 @@
+-- interactions records who has dealt with which listing: messages, bookings,
+-- contact clicks and favorites. One row per user, listing, kind and day.
+UPDATE interactions SET occurred_at = CURRENT_TIMESTAMP WHERE occurred_at IS NULL;
+ALTER TABLE interactions ALTER COLUMN occurred_at SET NOT NULL;
+
+DELETE FROM interactions a
+USING interactions b
+WHERE a.user_id = b.user_id
+  AND a.target_type = b.target_type
+  AND a.target_id = b.target_id
+  AND a.interaction_type = b.interaction_type
+  AND a.occurred_at::date = b.occurred_at::date
+  AND a.id > b.id;
+
+ALTER TABLE interactions
+    ADD CONSTRAINT interactions_type_check
+    CHECK (interaction_type IN ('message', 'booking_created', 'booking_completed', 'contact_click', 'favorite'));
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_interactions_daily
+    ON interactions (user_id, target_type, target_id, interaction_type, (occurred_at::date));
+
+-- Backfill what happened before bookings and favorites were recorded here
+INSERT INTO interactions (user_id, target_type, target_id, interaction_type, occurred_at)
+SELECT client_id, target_type, target_id, 'booking_created', COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM bookings
+ON CONFLICT DO NOTHING;
+
+INSERT INTO interactions (user_id, target_type, target_id, interaction_type, occurred_at)
+SELECT client_id, target_type, target_id, 'booking_completed', COALESCE(updated_at, CURRENT_TIMESTAMP)
+FROM bookings
+WHERE status = 'completed'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO interactions (user_id, target_type, target_id, interaction_type, occurred_at)
+SELECT user_id, target_type, target_id, 'favorite', COALESCE(created_at, CURRENT_TIMESTAMP)
+FROM favorites
+WHERE target_type IN ('provider', 'business')
+ON CONFLICT DO NOTHING;
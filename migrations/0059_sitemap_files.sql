@@ -0,0 +1,7 @@
+-- Generated sitemap documents, served as-is by /sitemap.xml and
+-- /sitemaps/:name. Rewritten wholesale by the sitemap job.
+CREATE TABLE IF NOT EXISTS sitemap_files (
+    name         TEXT PRIMARY KEY,
+    body         TEXT NOT NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,3 @@
+-- Per-run breakdown a job wants to keep beyond items_processed, such as the
+-- rows the retention job deleted from each table.
+ALTER TABLE job_runs ADD COLUMN IF NOT EXISTS details JSONB;
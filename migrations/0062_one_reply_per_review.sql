@@ -0,0 +1,10 @@
+-- A review gets at most one public reply, from the owner of what was
+-- reviewed. Older duplicates (only possible if ownership changed hands)
+-- keep the first reply.
+DELETE FROM review_replies rr
+USING review_replies earlier
+WHERE earlier.review_id = rr.review_id
+  AND (earlier.created_at, earlier.id) < (rr.created_at, rr.id);
+
+DROP INDEX IF EXISTS idx_review_replies_review;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_review_replies_review ON review_replies (review_id);
@@ -0,0 +1,7 @@
+-- rejected: the provider or business declined a pending booking
+-- no_show: the client didn't turn up to a confirmed booking
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_status_check;
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_status_check
+    CHECK (status IN ('pending', 'confirmed', 'rejected', 'cancelled', 'pending_confirmation',
+                      'completed', 'disputed', 'no_show'));
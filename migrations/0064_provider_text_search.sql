@@ -0,0 +1,8 @@
+-- Typo- and stem-tolerant matching for listProviders ?q=, e.g. "plumber"
+-- finding the Plumbing category, which full-text search stems apart.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_providers_search_text_trgm ON providers USING GIN (
+    (coalesce(service_name, '') || ' ' || coalesce(category, '') || ' ' || coalesce(service_description, ''))
+    gin_trgm_ops
+);
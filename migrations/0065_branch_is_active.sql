@@ -0,0 +1,19 @@
+-- Deactivated branches stay on record (bookings point at them) but stop
+-- taking bookings and drop off public profiles and location search.
+ALTER TABLE business_branches ADD COLUMN IF NOT EXISTS is_active BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE OR REPLACE FUNCTION listing_places(owner_type TEXT, owner_id INTEGER)
+RETURNS TABLE (latitude FLOAT8, longitude FLOAT8, county_id INTEGER)
+LANGUAGE sql STABLE AS $$
+    SELECT pl.latitude, pl.longitude, c.county_id
+    FROM provider_locations pl
+    JOIN wards w ON w.id = pl.ward_id
+    JOIN constituencies c ON c.id = w.constituency_id
+    WHERE owner_type = 'provider' AND pl.provider_id = owner_id
+    UNION ALL
+    SELECT bb.latitude, bb.longitude, c.county_id
+    FROM business_branches bb
+    LEFT JOIN wards w ON w.id = bb.ward_id
+    LEFT JOIN constituencies c ON c.id = w.constituency_id
+    WHERE owner_type = 'business' AND bb.business_id = owner_id AND bb.is_active
+$$;
@@ -0,0 +1,34 @@
+-- Posts get a URL slug for their public page, /posts/<slug>, which is what
+-- the sitemap lists. The id suffix keeps slugs unique however often a title
+-- repeats. A slug is set once on insert and kept when the title is edited, so
+-- links already out there don't break.
+CREATE OR REPLACE FUNCTION post_slug(title TEXT, post_id INTEGER) RETURNS TEXT
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT COALESCE(
+        NULLIF(btrim(left(btrim(regexp_replace(lower(COALESCE(title, '')), '[^a-z0-9]+', '-', 'g'), '-'), 60), '-'), ''),
+        'post'
+    ) || '-' || post_id
+$$;
+
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS slug TEXT;
+
+UPDATE posts SET slug = post_slug(title, id) WHERE slug IS NULL;
+
+ALTER TABLE posts ALTER COLUMN slug SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_posts_slug ON posts (slug);
+
+-- Column defaults are applied before BEFORE INSERT triggers run, so NEW.id is
+-- already the post's id here.
+CREATE OR REPLACE FUNCTION set_post_slug() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.slug = COALESCE(NEW.slug, post_slug(NEW.title, NEW.id));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS posts_set_slug ON posts;
+CREATE TRIGGER posts_set_slug
+    BEFORE INSERT ON posts
+    FOR EACH ROW
+    EXECUTE FUNCTION set_post_slug();
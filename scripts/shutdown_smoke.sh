@@ -0,0 +1,56 @@
+#!/usr/bin/env bash
+# Smoke test for graceful shutdown: starts the server, opens a request whose
+# body never finishes, sends SIGTERM and checks the process exits cleanly
+# within SHUTDOWN_TIMEOUT_SECONDS (plus a little slack).
+#
+# Needs DATABASE_URL and JWT_SECRET (or a .env) like a normal run.
+#   cargo build && scripts/shutdown_smoke.sh
+set -euo pipefail
+
+BIN=${BIN:-target/debug/mtaalink}
+export PORT=${PORT:-7899}
+export BIND_ADDRESS=127.0.0.1
+export SHUTDOWN_TIMEOUT_SECONDS=${SHUTDOWN_TIMEOUT_SECONDS:-3}
+SLACK_SECONDS=5
+URL="http://127.0.0.1:$PORT"
+
+"$BIN" &
+server=$!
+trap 'kill -9 $server 2>/dev/null || true; rm -f "${body:-}"' EXIT
+
+for _ in $(seq 1 60); do
+    curl -fs "$URL/" >/dev/null && break
+    sleep 0.5
+done
+curl -fs "$URL/" >/dev/null || { echo "FAIL: server did not come up"; exit 1; }
+
+# `-T -` streams a chunked body from a fifo we never write to or close, so
+# the handler keeps waiting on it
+body=$(mktemp -u)
+mkfifo "$body"
+curl -s -o /dev/null -X POST -H 'Content-Type: application/json' \
+    -T - "$URL/auth/login" <"$body" &
+hanging=$!
+exec 3>"$body"
+sleep 1
+
+started=$(date +%s)
+kill -TERM "$server"
+deadline=$((started + SHUTDOWN_TIMEOUT_SECONDS + SLACK_SECONDS))
+while kill -0 "$server" 2>/dev/null; do
+    if [ "$(date +%s)" -ge "$deadline" ]; then
+        echo "FAIL: still running $((SHUTDOWN_TIMEOUT_SECONDS + SLACK_SECONDS))s after SIGTERM"
+        exit 1
+    fi
+    sleep 0.2
+done
+
+status=0
+wait "$server" || status=$?
+exec 3>&-
+kill "$hanging" 2>/dev/null || true
+if [ "$status" -ne 0 ]; then
+    echo "FAIL: server exited with status $status"
+    exit 1
+fi
+echo "OK: exited cleanly after $(( $(date +%s) - started ))s"
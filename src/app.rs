@@ -0,0 +1,153 @@
+use crate::config::Config;
+use crate::routes;
+use crate::routes::health::health_routes;
+use crate::routes::sitemap::sitemap_routes;
+use crate::utils;
+use crate::utils::flags::{FeatureFlags, SharedFeatureFlags};
+use crate::utils::jobs::{JobTriggers, SharedJobTriggers};
+use crate::utils::maintenance::{Maintenance, SharedMaintenance};
+use crate::utils::video::SharedVideoProbe;
+use crate::utils::ws_state::{WsConnections, new_ws_connections};
+use axum::{Extension, Router, extract::DefaultBodyLimit, routing::get};
+use sentry::integrations::tower as sentry_tower;
+use sqlx::PgPool;
+use std::future::Future;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::services::ServeDir;
+
+/// Everything the server runs apart from the listener.
+pub struct App {
+    pub router: Router,
+    /// Lets the caller stop the background job loops at shutdown.
+    pub job_triggers: SharedJobTriggers,
+}
+
+/// Builds the full router with its shared state and starts the background
+/// job loops. `config` must be the one passed to `config::init`. Used by
+/// `main` and by the integration tests, so both exercise the same stack.
+pub fn build_app(pool: PgPool, config: &'static Config) -> App {
+    let video_probe = Arc::new(utils::video::FfmpegProbe::new(&config.ffprobe_path, &config.ffmpeg_path));
+    build_app_with_video_probe(pool, config, video_probe)
+}
+
+/// `build_app` with another `VideoProbe` in place of ffmpeg, so tests can
+/// upload videos without it installed.
+pub fn build_app_with_video_probe(pool: PgPool, config: &'static Config, video_probe: SharedVideoProbe) -> App {
+    let storage = utils::storage::init(config);
+    let ws_connections: WsConnections = new_ws_connections();
+    let feature_flags: SharedFeatureFlags = Arc::new(FeatureFlags::new(pool.clone(), config));
+    let maintenance: SharedMaintenance = Arc::new(Maintenance::new(pool.clone(), config));
+
+    let mut job_triggers = JobTriggers::new();
+    utils::reminders::start_reminder_task(
+        pool.clone(),
+        ws_connections.clone(),
+        job_triggers.register(&utils::jobs::BOOKING_REMINDERS),
+    );
+    utils::cleanup::start_cleanup_task(
+        pool.clone(),
+        storage.clone(),
+        job_triggers.register(&utils::jobs::CLEANUP),
+    );
+    utils::post_fanout::start_post_fanout_task(
+        pool.clone(),
+        ws_connections.clone(),
+        job_triggers.register(&utils::jobs::POST_FANOUT),
+    );
+    utils::storage_gc::start_storage_gc_task(
+        pool.clone(),
+        storage.clone(),
+        job_triggers.register(&utils::jobs::STORAGE_GC),
+    );
+    utils::booking_expiry::start_booking_expiry_task(
+        pool.clone(),
+        ws_connections.clone(),
+        job_triggers.register(&utils::jobs::BOOKING_EXPIRY),
+    );
+    utils::password_resets::start_password_reset_cleanup_task(
+        pool.clone(),
+        job_triggers.register(&utils::jobs::PASSWORD_RESET_CLEANUP),
+    );
+    utils::webhooks::start_webhook_delivery_task(
+        pool.clone(),
+        ws_connections.clone(),
+        job_triggers.register(&utils::jobs::WEBHOOK_DELIVERY),
+    );
+    utils::sitemap::start_sitemap_task(
+        pool.clone(),
+        job_triggers.register(&utils::jobs::SITEMAP),
+    );
+    utils::retention::start_retention_task(
+        pool.clone(),
+        job_triggers.register(&utils::jobs::RETENTION),
+    );
+    let job_triggers: SharedJobTriggers = Arc::new(job_triggers);
+
+    // One route table, served under the API prefix and, for apps built
+    // before it existed, at the root with deprecation headers
+    let api = routes::api_routes(pool.clone());
+    let router = Router::new()
+        .nest(&config.api_prefix, api.clone())
+        .merge(api.layer(axum::middleware::from_fn(utils::deprecation::mark_unprefixed)))
+        .nest_service("/uploads", ServeDir::new(&config.upload_dir)) // Serve static files from the uploads directory
+        .merge(sitemap_routes(pool.clone()))
+        .layer(axum::middleware::from_fn_with_state(maintenance.clone(), utils::maintenance::enforce_read_only))
+        .layer(axum::middleware::from_fn_with_state(pool.clone(), utils::activity::track_last_seen))
+        .layer(Extension(ws_connections))
+        .layer(Extension(storage.clone()))
+        .layer(Extension(video_probe))
+        .layer(Extension(job_triggers.clone()))
+        .layer(Extension(feature_flags))
+        .layer(Extension(maintenance))
+        .layer(axum::middleware::from_fn(utils::request_timeout::enforce_timeout))
+        .layer(axum::middleware::from_fn(utils::body_limit::json_payload_too_large))
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .layer(utils::cors::cors_layer(config))
+        .layer(axum::middleware::from_fn(utils::request_log::log_requests))
+        .layer(sentry_tower::NewSentryLayer::new_from_top())
+        .layer(sentry_tower::SentryHttpLayer::with_transaction())
+        .route("/", get(root))
+        .merge(health_routes(pool.clone(), storage));
+
+    App { router, job_triggers }
+}
+
+/// Serves `app` on `listener` until `shutdown` resolves, then stops accepting
+/// connections and gives in-flight requests, and after them any running job
+/// pass, up to `timeout` each to finish. Connections still open after that
+/// are closed.
+pub async fn serve(
+    app: App,
+    listener: TcpListener,
+    timeout: Duration,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    let handle = axum_server::Handle::new();
+    let job_triggers = app.job_triggers;
+    tokio::spawn({
+        let (handle, job_triggers) = (handle.clone(), job_triggers.clone());
+        async move {
+            shutdown.await;
+            tracing::info!("Shutting down, waiting up to {}s for in-flight work", timeout.as_secs());
+            job_triggers.stop();
+            handle.graceful_shutdown(Some(timeout));
+        }
+    });
+
+    axum_server::from_tcp(listener)
+        .handle(handle)
+        .serve(app.router.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
+
+    // In-flight requests are done; let a job pass that is still running finish
+    if !job_triggers.wait_stopped(timeout).await {
+        tracing::warn!("Background jobs still running at shutdown; abandoning them");
+    }
+    Ok(())
+}
+
+async fn root() -> &'static str {
+    "mtaalink is running!"
+}
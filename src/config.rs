@@ -0,0 +1,576 @@
+use crate::utils::client_ip::TrustedProxy;
+use crate::utils::cors::OriginPattern;
+use crate::utils::flags;
+use crate::utils::storage::{S3Config, StorageBackend};
+use std::collections::HashMap;
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Process-wide settings read from the environment once at startup. Runtime
+/// tunables that admins can change live in `utils::settings` instead.
+pub struct Config {
+    /// `APP_ENV`; "production" changes a few defaults.
+    pub app_env: String,
+    pub database_url: String,
+    pub database_max_connections: u32,
+    /// Connections the pool keeps open even when idle.
+    pub database_min_connections: u32,
+    /// How long a request waits for a free pooled connection before failing.
+    pub database_acquire_timeout: Duration,
+    /// Postgres `statement_timeout` set on every pooled connection.
+    pub database_statement_timeout: Duration,
+    /// How long a request may take before it is answered with a 504.
+    pub request_timeout: Duration,
+    /// The same for uploads (multipart bodies and resumable upload chunks).
+    pub upload_timeout: Duration,
+    /// Largest request body outside the upload routes, which follow the
+    /// `uploads.max_request_bytes` setting instead.
+    pub max_body_bytes: usize,
+    pub bind_address: IpAddr,
+    pub port: u16,
+    /// Proxies whose `X-Forwarded-For` is believed, from `TRUSTED_PROXIES`.
+    /// Empty, the peer address is the client.
+    pub trusted_proxies: Vec<TrustedProxy>,
+    /// Where the API is mounted, e.g. `/api/v1`. The same routes also answer
+    /// unprefixed, marked deprecated, until older app builds are retired.
+    pub api_prefix: String,
+    /// How long shutdown waits for in-flight requests and running jobs.
+    pub shutdown_timeout: Duration,
+    /// JWT keys, newest first: the first signs new tokens and every one is
+    /// accepted, so a secret can be rotated without logging everyone out.
+    pub jwt_secrets: Vec<String>,
+    /// How long a sign-in lasts: refresh tokens expire this many days after
+    /// they are issued.
+    pub jwt_ttl_days: i64,
+    /// How long an access token (the JWT) is valid before it must be
+    /// refreshed.
+    pub jwt_access_ttl_minutes: i64,
+    /// `iss` stamped on and required of tokens: which service issued them.
+    pub jwt_issuer: String,
+    /// `aud` stamped on and required of tokens: which environment they are
+    /// for, so a staging token is refused by production even with a shared key.
+    pub jwt_audience: String,
+    /// Still accept tokens minted before they carried `iss`/`aud`. Turn off
+    /// once `jwt_ttl_days` have passed since the claims were introduced.
+    pub jwt_accept_unscoped: bool,
+    /// Keys the HMAC that buckets anonymous post views by IP. Kept apart from
+    /// the JWT keys so rotating those doesn't reset view dedup.
+    pub viewer_hash_secret: String,
+    /// Directory public uploads are written to and served from.
+    pub upload_dir: String,
+    /// Directory for non-public uploads; never served directly.
+    pub private_upload_dir: String,
+    /// Where uploads are kept: local disk, or an S3-compatible bucket.
+    pub storage: StorageBackend,
+    /// `ffprobe` binary that reads video duration and size.
+    pub ffprobe_path: String,
+    /// `ffmpeg` binary that extracts video poster frames.
+    pub ffmpeg_path: String,
+    /// Origins allowed by CORS, from `ALLOWED_ORIGINS` or else `FRONTEND_URL`.
+    pub cors_origins: Vec<OriginPattern>,
+    /// Reflect any origin; only in development with no `ALLOWED_ORIGINS`.
+    pub cors_permissive: bool,
+    /// Base of links in emails: the first `FRONTEND_URL` origin.
+    pub frontend_url: String,
+    /// Per-environment flag defaults from `FEATURE_FLAGS`, e.g.
+    /// `payments=on,websocket=off`. Admin overrides in the database win.
+    pub feature_flags: HashMap<String, bool>,
+    /// `MAINTENANCE_MODE`: `on` holds the API read-only and `off` keeps it
+    /// writable whatever the `maintenance.read_only` setting says. Unset, the
+    /// setting decides.
+    pub maintenance_mode: Option<bool>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Every problem found, so a misconfigured deployment is fixed in one pass.
+pub struct ConfigError(Vec<String>);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for problem in &self.0 {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where variables are read from: the process environment in `from_env`, a
+/// map in tests.
+type Lookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Reads an optional variable, treating blank values as unset.
+fn var(lookup: Lookup, name: &str) -> Option<String> {
+    lookup(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Parses an optional variable, falling back to `default` when unset.
+fn parse_or<T: std::str::FromStr>(lookup: Lookup, name: &str, default: T, problems: &mut Vec<String>) -> T {
+    match var(lookup, name) {
+        None => default,
+        Some(raw) => raw.parse().unwrap_or_else(|_| {
+            problems.push(format!("{} is not valid: {:?}", name, raw));
+            default
+        }),
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Result<Config, ConfigError> {
+        Config::from_lookup(&|name| env::var(name).ok())
+    }
+
+    /// Builds the config from whatever `lookup` returns for each variable
+    /// name, so loading can be checked without touching the environment.
+    pub fn from_lookup(lookup: Lookup) -> Result<Config, ConfigError> {
+        let mut problems = Vec::new();
+
+        let app_env = var(lookup, "APP_ENV").unwrap_or_else(|| "development".to_string());
+        let production = app_env == "production";
+
+        let database_url = var(lookup, "DATABASE_URL").unwrap_or_default();
+        if database_url.is_empty() {
+            problems.push("DATABASE_URL must be set".to_string());
+        } else if !database_url.starts_with("postgres://") && !database_url.starts_with("postgresql://") {
+            problems.push("DATABASE_URL must be a postgres:// URL".to_string());
+        }
+        let database_max_connections = parse_or(lookup, "DATABASE_MAX_CONNECTIONS", 25u32, &mut problems);
+        if database_max_connections == 0 {
+            problems.push("DATABASE_MAX_CONNECTIONS must be at least 1".to_string());
+        }
+        let database_min_connections =
+            parse_or(lookup, "DATABASE_MIN_CONNECTIONS", 2u32.min(database_max_connections), &mut problems);
+        if database_min_connections > database_max_connections {
+            problems.push("DATABASE_MIN_CONNECTIONS must not exceed DATABASE_MAX_CONNECTIONS".to_string());
+        }
+        let acquire_timeout_seconds = parse_or(lookup, "DATABASE_ACQUIRE_TIMEOUT_SECONDS", 5u64, &mut problems);
+        if !(1..=60).contains(&acquire_timeout_seconds) {
+            problems.push("DATABASE_ACQUIRE_TIMEOUT_SECONDS must be between 1 and 60".to_string());
+        }
+        let statement_timeout_seconds = parse_or(lookup, "DATABASE_STATEMENT_TIMEOUT_SECONDS", 20u64, &mut problems);
+        if !(1..=3600).contains(&statement_timeout_seconds) {
+            problems.push("DATABASE_STATEMENT_TIMEOUT_SECONDS must be between 1 and 3600".to_string());
+        }
+        let request_timeout_seconds = parse_or(lookup, "REQUEST_TIMEOUT_SECONDS", 30u64, &mut problems);
+        if !(1..=3600).contains(&request_timeout_seconds) {
+            problems.push("REQUEST_TIMEOUT_SECONDS must be between 1 and 3600".to_string());
+        }
+        let upload_timeout_seconds = parse_or(lookup, "UPLOAD_TIMEOUT_SECONDS", 300u64, &mut problems);
+        if !(1..=3600).contains(&upload_timeout_seconds) {
+            problems.push("UPLOAD_TIMEOUT_SECONDS must be between 1 and 3600".to_string());
+        }
+        let max_body_bytes = parse_or(lookup, "MAX_BODY_BYTES", 1024 * 1024usize, &mut problems);
+        if !(1024..=64 * 1024 * 1024).contains(&max_body_bytes) {
+            problems.push("MAX_BODY_BYTES must be between 1024 and 67108864".to_string());
+        }
+
+        // Containers need every interface; a dev machine shouldn't expose itself
+        let default_bind = if production { Ipv4Addr::UNSPECIFIED } else { Ipv4Addr::LOCALHOST };
+        // HOST is the name most container platforms use; BIND_ADDRESS wins if both are set
+        let bind_var = if var(lookup, "BIND_ADDRESS").is_none() && var(lookup, "HOST").is_some() { "HOST" } else { "BIND_ADDRESS" };
+        let bind_address = parse_or(lookup, bind_var, IpAddr::V4(default_bind), &mut problems);
+        let port = parse_or(lookup, "PORT", 7878u16, &mut problems);
+        let mut trusted_proxies = Vec::new();
+        for entry in var(lookup, "TRUSTED_PROXIES").unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match TrustedProxy::parse(entry) {
+                Some(proxy) => trusted_proxies.push(proxy),
+                None => problems.push(format!("TRUSTED_PROXIES entry {:?} is not an IP address or CIDR range", entry)),
+            }
+        }
+        let api_prefix = var(lookup, "API_PREFIX").unwrap_or_else(|| "/api/v1".to_string());
+        let prefix_ok = api_prefix.len() > 1
+            && api_prefix.starts_with('/')
+            && !api_prefix.ends_with('/')
+            && !api_prefix.contains("//")
+            && api_prefix.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'));
+        if !prefix_ok {
+            problems.push(format!("API_PREFIX must be a path like /api/v1, not {:?}", api_prefix));
+        }
+        let shutdown_timeout_seconds = parse_or(lookup, "SHUTDOWN_TIMEOUT_SECONDS", 30u64, &mut problems);
+        if !(1..=600).contains(&shutdown_timeout_seconds) {
+            problems.push("SHUTDOWN_TIMEOUT_SECONDS must be between 1 and 600".to_string());
+        }
+
+        // JWT_SECRETS lists keys for rotation; JWT_SECRET is the single-key form
+        let jwt_secrets: Vec<String> = var(lookup, "JWT_SECRETS")
+            .or_else(|| var(lookup, "JWT_SECRET"))
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        if jwt_secrets.is_empty() {
+            problems.push("JWT_SECRET must be set (generate one with `openssl rand -hex 64`)".to_string());
+        } else if jwt_secrets.iter().any(|s| s.len() < 32) {
+            tracing::warn!("A JWT secret is shorter than 32 characters; use longer random secrets");
+        }
+        if (1..jwt_secrets.len()).any(|i| jwt_secrets[..i].contains(&jwt_secrets[i])) {
+            problems.push("JWT_SECRETS lists the same key twice".to_string());
+        }
+        let jwt_ttl_days = parse_or(lookup, "JWT_TTL_DAYS", 30i64, &mut problems);
+        if !(1..=365).contains(&jwt_ttl_days) {
+            problems.push("JWT_TTL_DAYS must be between 1 and 365".to_string());
+        }
+        let jwt_access_ttl_minutes = parse_or(lookup, "JWT_ACCESS_TTL_MINUTES", 15i64, &mut problems);
+        if !(1..=1440).contains(&jwt_access_ttl_minutes) {
+            problems.push("JWT_ACCESS_TTL_MINUTES must be between 1 and 1440".to_string());
+        }
+        let jwt_issuer = var(lookup, "JWT_ISSUER").unwrap_or_else(|| "mtaalink-api".to_string());
+        let jwt_audience = var(lookup, "JWT_AUDIENCE").unwrap_or_else(|| format!("mtaalink-{}", app_env));
+        let jwt_accept_unscoped = parse_or(lookup, "JWT_ACCEPT_UNSCOPED", true, &mut problems);
+
+        // Outside production a per-process key will do; views then dedup
+        // afresh after a restart
+        let viewer_hash_secret = match var(lookup, "VIEWER_HASH_SECRET") {
+            Some(secret) => {
+                if jwt_secrets.contains(&secret) {
+                    problems.push("VIEWER_HASH_SECRET must not reuse a JWT secret".to_string());
+                }
+                secret
+            }
+            None if production => {
+                problems.push("VIEWER_HASH_SECRET must be set in production (generate one with `openssl rand -hex 32`)".to_string());
+                String::new()
+            }
+            None => hex::encode(rand::random::<[u8; 32]>()),
+        };
+
+        let upload_dir = var(lookup, "UPLOAD_DIR").unwrap_or_else(|| "uploads".to_string());
+        let private_upload_dir = var(lookup, "PRIVATE_UPLOAD_DIR").unwrap_or_else(|| "private_uploads".to_string());
+        if upload_dir == private_upload_dir {
+            problems.push("UPLOAD_DIR and PRIVATE_UPLOAD_DIR must differ, or private files would be public".to_string());
+        }
+
+        // A bad S3 setup must stop the boot: falling back to local disk would
+        // scatter uploads across the disks of separate instances
+        let storage = match var(lookup, "STORAGE_BACKEND").as_deref().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("local") => StorageBackend::Local,
+            Some("s3") => {
+                let mut required = |name: &str| {
+                    var(lookup, name).unwrap_or_else(|| {
+                        problems.push(format!("{} must be set when STORAGE_BACKEND=s3", name));
+                        String::new()
+                    })
+                };
+                let bucket = required("AWS_S3_BUCKET");
+                let access_key = required("AWS_ACCESS_KEY_ID");
+                let secret_key = required("AWS_SECRET_ACCESS_KEY");
+                let mut url = |name: &str| {
+                    let value = var(lookup, name)?;
+                    if !value.starts_with("https://") && !value.starts_with("http://") {
+                        problems.push(format!("{} must be an http(s) URL, not {:?}", name, value));
+                    }
+                    Some(value)
+                };
+                let endpoint = url("AWS_S3_ENDPOINT");
+                let base_url = url("AWS_S3_BASE_URL");
+                StorageBackend::S3(S3Config {
+                    region: var(lookup, "AWS_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+                    bucket,
+                    access_key,
+                    secret_key,
+                    endpoint,
+                    base_url,
+                })
+            }
+            Some(other) => {
+                problems.push(format!("STORAGE_BACKEND must be local or s3, not {:?}", other));
+                StorageBackend::Local
+            }
+        };
+
+        // Bare names are looked up on PATH when run; an explicit path is
+        // checked now rather than on the first video upload
+        let mut tool = |name: &str, default: &str| {
+            let path = var(lookup, name).unwrap_or_else(|| default.to_string());
+            if path.contains(std::path::MAIN_SEPARATOR) && !std::path::Path::new(&path).is_file() {
+                problems.push(format!("{} {:?} is not a file", name, path));
+            }
+            path
+        };
+        let ffprobe_path = tool("FFPROBE_PATH", "ffprobe");
+        let ffmpeg_path = tool("FFMPEG_PATH", "ffmpeg");
+
+        let frontend = var(lookup, "FRONTEND_URL").unwrap_or_else(|| "http://localhost:3000".to_string());
+        let origins: Vec<&str> = frontend.split(',').map(str::trim).filter(|o| !o.is_empty()).collect();
+        for origin in &origins {
+            if OriginPattern::parse(origin).is_none_or(|p| !matches!(p, OriginPattern::Exact(_))) {
+                problems.push(format!("FRONTEND_URL entry {:?} is not an http(s) origin", origin));
+            }
+        }
+        let frontend_url = origins
+            .first()
+            .map(|o| o.trim_end_matches('/').to_string())
+            .unwrap_or_default();
+        if frontend_url.is_empty() {
+            problems.push("FRONTEND_URL must list at least one origin".to_string());
+        }
+
+        // A list of nothing but separators counts as unset, like a blank one
+        let allowed_origins =
+            var(lookup, "ALLOWED_ORIGINS").filter(|list| list.split(',').any(|o| !o.trim().is_empty()));
+        let cors_permissive = allowed_origins.is_none() && app_env == "development";
+        let mut cors_origins = Vec::new();
+        let (cors_var, cors_list) = match &allowed_origins {
+            Some(list) => ("ALLOWED_ORIGINS", list.as_str()),
+            None => ("FRONTEND_URL", frontend.as_str()),
+        };
+        for entry in cors_list.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            match OriginPattern::parse(entry) {
+                Some(pattern) => cors_origins.push(pattern),
+                None => problems.push(format!(
+                    "{} entry {:?} is not an http(s) origin or https://*.domain pattern",
+                    cors_var, entry
+                )),
+            }
+        }
+
+        let mut feature_flags = HashMap::new();
+        for entry in var(lookup, "FEATURE_FLAGS").unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, value) = entry.split_once('=').map(|(n, v)| (n.trim(), v.trim())).unwrap_or((entry, "on"));
+            let value = match value.to_ascii_lowercase().as_str() {
+                "on" | "true" | "1" => Some(true),
+                "off" | "false" | "0" => Some(false),
+                _ => None,
+            };
+            match (flags::definition(name), value) {
+                (Some(def), Some(value)) => {
+                    feature_flags.insert(def.name.to_string(), value);
+                }
+                (None, _) => problems.push(format!("FEATURE_FLAGS names an unknown flag {:?}", name)),
+                (_, None) => problems.push(format!("FEATURE_FLAGS entry {:?} must be name=on or name=off", entry)),
+            }
+        }
+
+        let maintenance_mode = var(lookup, "MAINTENANCE_MODE").and_then(|raw| match raw.to_ascii_lowercase().as_str() {
+            "on" | "true" | "1" => Some(true),
+            "off" | "false" | "0" => Some(false),
+            _ => {
+                problems.push(format!("MAINTENANCE_MODE must be on or off, not {:?}", raw));
+                None
+            }
+        });
+
+        if !problems.is_empty() {
+            return Err(ConfigError(problems));
+        }
+        Ok(Config {
+            app_env,
+            database_url,
+            database_max_connections,
+            database_min_connections,
+            database_acquire_timeout: Duration::from_secs(acquire_timeout_seconds),
+            database_statement_timeout: Duration::from_secs(statement_timeout_seconds),
+            request_timeout: Duration::from_secs(request_timeout_seconds),
+            upload_timeout: Duration::from_secs(upload_timeout_seconds),
+            max_body_bytes,
+            bind_address,
+            port,
+            trusted_proxies,
+            api_prefix,
+            shutdown_timeout: Duration::from_secs(shutdown_timeout_seconds),
+            jwt_secrets,
+            jwt_ttl_days,
+            jwt_access_ttl_minutes,
+            jwt_issuer,
+            jwt_audience,
+            jwt_accept_unscoped,
+            viewer_hash_secret,
+            upload_dir,
+            private_upload_dir,
+            storage,
+            ffprobe_path,
+            ffmpeg_path,
+            cors_origins,
+            cors_permissive,
+            frontend_url,
+            feature_flags,
+            maintenance_mode,
+        })
+    }
+
+    /// The database URL with any password masked, for logs.
+    pub fn database_url_redacted(&self) -> String {
+        let Some((scheme, rest)) = self.database_url.split_once("://") else {
+            return "<invalid>".to_string();
+        };
+        match rest.split_once('@').and_then(|(userinfo, host)| Some((userinfo.split_once(':')?.0, host))) {
+            Some((user, host)) => format!("{}://{}:***@{}", scheme, user, host),
+            None => self.database_url.clone(),
+        }
+    }
+}
+
+/// Makes `config` available through `config::get`. Called once from `main`.
+pub fn init(config: Config) -> &'static Config {
+    CONFIG.get_or_init(|| config)
+}
+
+/// The loaded configuration.
+pub fn get() -> &'static Config {
+    CONFIG.get().expect("config::init runs at startup before anything reads the config")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn load(vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Config::from_lookup(&move |name| vars.get(name).cloned())
+    }
+
+    fn load_with(extra: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let mut vars = vec![("DATABASE_URL", "postgres://app:pw@localhost/mtaalink"), ("JWT_SECRET", SECRET)];
+        vars.extend_from_slice(extra);
+        load(&vars)
+    }
+
+    fn problems(result: Result<Config, ConfigError>) -> Vec<String> {
+        match result {
+            Ok(_) => panic!("config should have been refused"),
+            Err(ConfigError(problems)) => problems,
+        }
+    }
+
+    #[test]
+    fn defaults_apply_when_optional_variables_are_unset() {
+        let config = load_with(&[]).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.app_env, "development");
+        assert_eq!(config.port, 7878);
+        assert_eq!(config.bind_address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(config.database_max_connections, 25);
+        assert_eq!(config.database_min_connections, 2);
+        assert_eq!(config.jwt_secrets, vec![SECRET.to_string()]);
+        assert_eq!(config.jwt_ttl_days, 30);
+        assert_eq!(config.jwt_access_ttl_minutes, 15);
+        assert_eq!(config.jwt_audience, "mtaalink-development");
+        assert_eq!(config.api_prefix, "/api/v1");
+        assert_eq!(config.request_timeout, Duration::from_secs(30));
+        assert_eq!(config.frontend_url, "http://localhost:3000");
+        assert!(config.cors_permissive);
+        assert!(config.feature_flags.is_empty());
+        assert_eq!(config.maintenance_mode, None);
+        assert!(config.trusted_proxies.is_empty());
+        assert_eq!((config.ffprobe_path.as_str(), config.ffmpeg_path.as_str()), ("ffprobe", "ffmpeg"));
+    }
+
+    #[test]
+    fn blank_values_count_as_unset() {
+        let config = load_with(&[("PORT", "  "), ("APP_ENV", "")]).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.port, 7878);
+        assert_eq!(config.app_env, "development");
+    }
+
+    #[test]
+    fn production_binds_every_interface() {
+        let config = load_with(&[("APP_ENV", "production"), ("VIEWER_HASH_SECRET", "views")]).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.bind_address, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert!(!config.cors_permissive);
+    }
+
+    #[test]
+    fn a_missing_jwt_secret_is_a_clear_error() {
+        let problems = problems(load(&[("DATABASE_URL", "postgres://localhost/mtaalink")]));
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].starts_with("JWT_SECRET must be set"), "{problems:?}");
+    }
+
+    #[test]
+    fn the_viewer_hash_secret_is_required_in_production_and_kept_apart_from_jwt_keys() {
+        let missing = problems(load_with(&[("APP_ENV", "production")]));
+        assert!(missing.iter().any(|p| p.starts_with("VIEWER_HASH_SECRET must be set")), "{missing:?}");
+
+        let reused = problems(load_with(&[("VIEWER_HASH_SECRET", SECRET)]));
+        assert!(reused.iter().any(|p| p.starts_with("VIEWER_HASH_SECRET must not reuse")), "{reused:?}");
+
+        // Development makes one up rather than borrowing a JWT key
+        let config = load_with(&[]).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.viewer_hash_secret.len(), 64);
+        assert!(!config.jwt_secrets.contains(&config.viewer_hash_secret));
+    }
+
+    #[test]
+    fn s3_storage_needs_a_bucket_and_credentials() {
+        assert!(matches!(load_with(&[]).map(|c| c.storage), Ok(StorageBackend::Local)));
+
+        let missing = problems(load_with(&[("STORAGE_BACKEND", "s3"), ("AWS_S3_BUCKET", "uploads")]));
+        assert_eq!(
+            missing,
+            [
+                "AWS_ACCESS_KEY_ID must be set when STORAGE_BACKEND=s3",
+                "AWS_SECRET_ACCESS_KEY must be set when STORAGE_BACKEND=s3",
+            ]
+        );
+
+        let config = load_with(&[
+            ("STORAGE_BACKEND", "S3"),
+            ("AWS_S3_BUCKET", "uploads"),
+            ("AWS_ACCESS_KEY_ID", "key"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("AWS_S3_ENDPOINT", "http://127.0.0.1:9000"),
+        ])
+        .unwrap_or_else(|e| panic!("{e}"));
+        let StorageBackend::S3(s3) = config.storage else { panic!("expected the S3 backend") };
+        assert_eq!(s3.region, "us-east-1");
+        assert_eq!(s3.endpoint.as_deref(), Some("http://127.0.0.1:9000"));
+        assert_eq!(s3.base_url, None);
+    }
+
+    #[test]
+    fn unknown_storage_backends_and_non_http_endpoints_are_rejected() {
+        let problems_for = |vars: &[(&str, &str)]| problems(load_with(vars));
+        assert_eq!(problems_for(&[("STORAGE_BACKEND", "gcs")]), ["STORAGE_BACKEND must be local or s3, not \"gcs\""]);
+        let bad_url = problems_for(&[
+            ("STORAGE_BACKEND", "s3"),
+            ("AWS_S3_BUCKET", "uploads"),
+            ("AWS_ACCESS_KEY_ID", "key"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("AWS_S3_ENDPOINT", "minio:9000"),
+        ]);
+        assert_eq!(bad_url, ["AWS_S3_ENDPOINT must be an http(s) URL, not \"minio:9000\""]);
+    }
+
+    #[test]
+    fn video_tool_paths_must_exist_unless_left_to_path() {
+        let ffprobe = std::env::temp_dir().join(format!("ffprobe-{}", std::process::id()));
+        std::fs::write(&ffprobe, b"").expect("write stand-in binary");
+        let ffprobe = ffprobe.to_str().expect("utf-8 path");
+
+        let config = load_with(&[("FFPROBE_PATH", ffprobe), ("FFMPEG_PATH", "ffmpeg6")]).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.ffprobe_path, ffprobe);
+        assert_eq!(config.ffmpeg_path, "ffmpeg6");
+
+        let missing = problems(load_with(&[("FFMPEG_PATH", "/opt/ffmpeg/bin/ffmpeg")]));
+        assert_eq!(missing, ["FFMPEG_PATH \"/opt/ffmpeg/bin/ffmpeg\" is not a file"]);
+        let _ = std::fs::remove_file(ffprobe);
+    }
+
+    #[test]
+    fn invalid_ports_pool_sizes_and_ttls_are_rejected() {
+        for (name, value) in [
+            ("PORT", "http"),
+            ("PORT", "70000"),
+            ("DATABASE_MAX_CONNECTIONS", "0"),
+            ("DATABASE_MAX_CONNECTIONS", "-3"),
+            ("JWT_TTL_DAYS", "0"),
+            ("JWT_TTL_DAYS", "400"),
+            ("JWT_ACCESS_TTL_MINUTES", "soon"),
+            ("TRUSTED_PROXIES", "10.0.0.0/8, railway"),
+        ] {
+            let problems = problems(load_with(&[(name, value)]));
+            assert!(problems.iter().any(|p| p.starts_with(name)), "{name}={value}: {problems:?}");
+        }
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let problems = problems(load(&[("PORT", "x"), ("JWT_TTL_DAYS", "0")]));
+        assert_eq!(problems.len(), 4, "{problems:?}");
+    }
+}
@@ -0,0 +1,46 @@
+use crate::config::Config;
+use sqlx::Executor;
+use sqlx::postgres::PgPoolOptions;
+
+/// Pool settings from the config. Every connection gets the configured
+/// `statement_timeout`, so a runaway query is cancelled by Postgres instead of
+/// holding its connection (and the request waiting on it) indefinitely.
+pub fn pool_options(config: &Config) -> PgPoolOptions {
+    let statement_timeout_ms = config.database_statement_timeout.as_millis();
+    PgPoolOptions::new()
+        .max_connections(config.database_max_connections)
+        .min_connections(config.database_min_connections)
+        .acquire_timeout(config.database_acquire_timeout)
+        .after_connect(move |conn, _meta| {
+            Box::pin(async move {
+                conn.execute(format!("SET statement_timeout = {}", statement_timeout_ms).as_str())
+                    .await?;
+                Ok(())
+            })
+        })
+}
+
+/// Pool usage, for the readiness check.
+#[derive(serde::Serialize, Debug)]
+pub struct PoolStats {
+    pub max: u32,
+    pub size: u32,
+    pub idle: usize,
+    pub in_use: usize,
+    /// Every connection is open and busy, so new queries wait for one.
+    pub saturated: bool,
+}
+
+pub fn pool_stats(pool: &sqlx::PgPool) -> PoolStats {
+    let max = pool.options().get_max_connections();
+    let size = pool.size();
+    let idle = pool.num_idle();
+    let in_use = (size as usize).saturating_sub(idle);
+    PoolStats {
+        max,
+        size,
+        idle,
+        in_use,
+        saturated: size >= max && idle == 0,
+    }
+}
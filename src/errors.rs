@@ -12,7 +12,13 @@ pub enum AppError {
     Unauthorized(String),
     Forbidden(String),
     BadRequest(String),
+    Validation(validator::ValidationErrors),
     Conflict(String),
+    PayloadTooLarge(String),
+    /// An upload whose contents aren't a type the route accepts.
+    UnsupportedMediaType(String),
+    /// The request ran past its time limit.
+    Timeout(String),
     Internal(String),
     EmailError(String),
 }
@@ -27,35 +33,119 @@ impl std::fmt::Display for AppError {
             AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
             AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            AppError::Validation(e) => write!(f, "Validation failed: {}", e),
             AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::PayloadTooLarge(msg) => write!(f, "Payload too large: {}", msg),
+            AppError::UnsupportedMediaType(msg) => write!(f, "Unsupported media type: {}", msg),
+            AppError::Timeout(msg) => write!(f, "Timed out: {}", msg),
             AppError::Internal(msg) => write!(f, "Internal error: {}", msg),
             AppError::EmailError(msg) => write!(f, "Email error: {}", msg),
         }
     }
 }
 
+/// Body of every error response: a stable `code` for clients to branch on,
+/// a `message` for people, and `details` when there is more to say.
+pub fn error_body(code: &str, message: &str, details: Option<serde_json::Value>) -> Json<serde_json::Value> {
+    let mut body = json!({ "code": code, "message": message });
+    if let Some(details) = details {
+        body["details"] = details;
+    }
+    Json(body)
+}
+
+/// A readable message for one failed rule. Validator's own formatting
+/// includes the submitted value, which must not be echoed (it may be a
+/// password).
+fn describe_validation_error(error: &validator::ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).map(|v| v.to_string());
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("email", _, _) => "must be a valid email address".to_string(),
+        ("url", _, _) => "must be a valid URL".to_string(),
+        ("length", Some(min), Some(max)) => format!("must be between {} and {} characters", min, max),
+        ("length", Some(min), None) => format!("must be at least {} characters", min),
+        ("length", None, Some(max)) => format!("must be at most {} characters", max),
+        ("range", Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+        ("range", Some(min), None) => format!("must be at least {}", min),
+        ("range", None, Some(max)) => format!("must be at most {}", max),
+        ("must_match", _, _) => "does not match".to_string(),
+        _ => "is invalid".to_string(),
+    }
+}
+
+/// Field name -> messages, sorted by field for a stable message.
+fn validation_fields(errors: &validator::ValidationErrors) -> Vec<(String, Vec<String>)> {
+    let mut fields: Vec<(String, Vec<String>)> = errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, errors)| {
+            (field.to_string(), errors.iter().map(describe_validation_error).collect())
+        })
+        .collect();
+    fields.sort();
+    fields
+}
+
+const INTERNAL_MESSAGE: &str = "Something went wrong. Please try again later.";
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
+        let (status, code, message, details) = match self {
+            AppError::Database(sqlx::Error::RowNotFound) => {
+                (StatusCode::NOT_FOUND, "not_found", "Not found".to_string(), None)
+            }
+            AppError::Database(sqlx::Error::Database(ref e)) if e.is_unique_violation() => {
+                tracing::warn!("Unique violation: {}", e);
+                (StatusCode::CONFLICT, "conflict", "That record already exists".to_string(), None)
+            }
+            AppError::Database(sqlx::Error::PoolTimedOut) => {
+                tracing::warn!("Timed out waiting for a database connection");
+                (StatusCode::SERVICE_UNAVAILABLE, "unavailable", "The server is busy. Please try again.".to_string(), None)
+            }
+            // 57014 is query_canceled, raised when statement_timeout fires
+            AppError::Database(sqlx::Error::Database(ref e)) if e.code().as_deref() == Some("57014") => {
+                tracing::warn!("Query cancelled by statement timeout: {}", e);
+                (StatusCode::GATEWAY_TIMEOUT, "timeout", "The request took too long. Please try again.".to_string(), None)
+            }
+            // Causes are logged, never sent: they can carry SQL and internals
             AppError::Database(ref e) => {
                 tracing::error!("Database error: {}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, "A database error occurred".to_string())
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", INTERNAL_MESSAGE.to_string(), None)
+            }
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg, None),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "unauthorized", msg, None),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "forbidden", msg, None),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg, None),
+            AppError::Validation(ref e) => {
+                let fields = validation_fields(e);
+                let message = fields
+                    .iter()
+                    .map(|(field, messages)| format!("{}: {}", field, messages.join(", ")))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                let details: serde_json::Map<String, serde_json::Value> =
+                    fields.into_iter().map(|(field, messages)| (field, json!(messages))).collect();
+                (StatusCode::BAD_REQUEST, "validation_failed", message, Some(json!({ "fields": details })))
             }
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
-            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "conflict", msg, None),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", msg, None),
+            AppError::UnsupportedMediaType(msg) => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type", msg, None)
+            }
+            AppError::Timeout(msg) => (StatusCode::GATEWAY_TIMEOUT, "timeout", msg, None),
             AppError::Internal(ref msg) => {
                 tracing::error!("Internal error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, msg.clone())
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", INTERNAL_MESSAGE.to_string(), None)
             }
             AppError::EmailError(ref msg) => {
                 tracing::error!("Email error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to send email".to_string())
+                (StatusCode::INTERNAL_SERVER_ERROR, "email_failed", "Failed to send email".to_string(), None)
             }
         };
-        (status, Json(json!({ "message": message }))).into_response()
+        (status, error_body(code, &message, details)).into_response()
     }
 }
 
@@ -83,9 +173,21 @@ impl From<std::io::Error> for AppError {
     }
 }
 
+/// Multipart bodies that run past the route's body limit are a 413; any
+/// other multipart problem is the client's malformed request.
+impl From<axum::extract::multipart::MultipartError> for AppError {
+    fn from(e: axum::extract::multipart::MultipartError) -> Self {
+        if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            AppError::PayloadTooLarge("Request body is too large".to_string())
+        } else {
+            AppError::BadRequest(format!("Invalid multipart body: {}", e.body_text()))
+        }
+    }
+}
+
 impl From<validator::ValidationErrors> for AppError {
     fn from(e: validator::ValidationErrors) -> Self {
-        AppError::BadRequest(e.to_string())
+        AppError::Validation(e)
     }
 }
 
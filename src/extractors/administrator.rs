@@ -1,33 +1,37 @@
+use crate::errors::error_body;
 use crate::extractors::current_user::CurrentUser;
 use axum::{
-    Json,
     body::Body,
     extract::State,
     http::{Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use serde_json::json;
 use sqlx::PgPool;
 
+/// Whether the user passes the admin check `require_admin` applies, for
+/// handlers outside the admin router that vary by it.
+pub async fn is_admin(pool: &PgPool, user_id: i32) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query!(
+        "SELECT is_super_admin FROM admins WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some_and(|row| row.is_super_admin.unwrap_or(false)))
+}
+
 pub async fn require_admin(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     request: Request<Body>,
     next: Next,
 ) -> Result<Response, impl IntoResponse> {
-    let admin_check = sqlx::query!(
-        "SELECT is_super_admin FROM admins WHERE user_id = $1",
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await;
-
-    match admin_check {
-        Ok(Some(row)) if row.is_super_admin.unwrap_or(false) => Ok(next.run(request).await),
+    match is_admin(&pool, user_id).await {
+        Ok(true) => Ok(next.run(request).await),
         _ => Err((
             StatusCode::FORBIDDEN,
-            Json(json!({ "message": "Admin access required" })),
+            error_body("forbidden", "Admin access required", None),
         )),
     }
 }
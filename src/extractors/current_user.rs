@@ -1,49 +1,215 @@
 use axum::{
     Json,
     async_trait,
-    extract::FromRequestParts,
-    http::{StatusCode, request::Parts},
+    extract::{FromRef, FromRequestParts},
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION, request::Parts},
 };
 use serde_json::json;
+use sqlx::PgPool;
 
-use crate::utils::jwt::decode_jwt;
+use crate::errors::{AppError, AppResult, error_body};
+use crate::utils::jwt::{decode_jwt, is_wrong_environment};
 
+#[derive(Clone)]
 pub struct CurrentUser {
     pub user_id: i32,
+    /// `client`, `provider`, `business`, or `unknown` for an account that
+    /// never picked one. Read from the account on each request, so a role
+    /// change applies to tokens already issued.
+    pub role: String,
+    /// Set when an admin is acting as this user via an impersonation token.
+    pub impersonator_id: Option<i32>,
+}
+
+impl CurrentUser {
+    /// 403 unless the caller has one of `roles`. For checks that also need
+    /// the profile id, use `CurrentProvider` and friends instead.
+    pub fn require_role(&self, roles: &[&str]) -> AppResult<()> {
+        if roles.contains(&self.role.as_str()) {
+            return Ok(());
+        }
+        Err(AppError::Forbidden(format!("Only {} accounts can do this", roles.join(" or "))))
+    }
+
+    pub fn is_impersonated(&self) -> bool {
+        self.impersonator_id.is_some()
+    }
+
+    /// Guard for account-destroying and money-moving endpoints, which support
+    /// staff must never trigger on a user's behalf.
+    pub fn deny_impersonation(&self) -> AppResult<()> {
+        if self.is_impersonated() {
+            return Err(AppError::Forbidden(
+                "This action is not available while impersonating a user".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The token in an `Authorization` header value. The scheme is matched
+/// case-insensitively and extra whitespace around the token is ignored, so
+/// `bearer x` and `Bearer  x` both give `x`.
+pub fn bearer_token(header_value: &str) -> Option<&str> {
+    let (scheme, token) = header_value.trim().split_once(char::is_whitespace)?;
+    let token = token.trim_start();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// The admin behind the request's Bearer token when it is a valid
+/// impersonation token. For routes that don't authenticate the caller but
+/// must still refuse support staff, like logout.
+pub fn impersonator_in(headers: &HeaderMap) -> Option<i32> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(bearer_token)
+        .and_then(|token| decode_jwt(token).ok())
+        .and_then(|claims| claims.impersonator)
 }
 
 type AuthRejection = (StatusCode, Json<serde_json::Value>);
 
 fn auth_error(msg: &'static str) -> AuthRejection {
-    (StatusCode::UNAUTHORIZED, Json(json!({ "message": msg })))
+    (StatusCode::UNAUTHORIZED, error_body("unauthorized", msg, None))
+}
+
+/// Message shown to a suspended user, both at login and on any request made
+/// with a token issued before the suspension.
+pub fn suspension_message(
+    reason: Option<&str>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> String {
+    let period = match until {
+        Some(until) => format!("until {}", until.format("%Y-%m-%d %H:%M UTC")),
+        None => "indefinitely".to_string(),
+    };
+    format!(
+        "Your account is suspended {period}. Reason: {}",
+        reason.unwrap_or("Violation of our terms of service.")
+    )
+}
+
+/// 403 `account_suspended` response, with the end of the suspension if any.
+pub fn suspension_rejection(
+    reason: Option<&str>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> AuthRejection {
+    (
+        StatusCode::FORBIDDEN,
+        error_body(
+            "account_suspended",
+            &suspension_message(reason, until),
+            Some(json!({ "suspended_until": until })),
+        ),
+    )
 }
 
 #[async_trait]
 impl<S> FromRequestParts<S> for CurrentUser
 where
+    PgPool: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = AuthRejection;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, AuthRejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, AuthRejection> {
+        // Already checked for this request by another extractor, e.g. `CurrentProvider`
+        if let Some(user) = parts.extensions.get::<CurrentUser>() {
+            return Ok(user.clone());
+        }
+
         let auth_header = parts
             .headers
-            .get("Authorization")
+            .get(AUTHORIZATION)
             .and_then(|h| h.to_str().ok())
             .ok_or_else(|| auth_error("Missing Authorization header"))?;
 
-        let token = auth_header
-            .strip_prefix("Bearer ")
+        let token = bearer_token(auth_header)
             .ok_or_else(|| auth_error("Invalid Authorization header format"))?;
 
-        let claims = decode_jwt(token)
-            .map_err(|_| auth_error("Invalid or expired token"))?;
+        let claims = decode_jwt(token).map_err(|e| {
+            if is_wrong_environment(&e) {
+                tracing::warn!("Rejected a token issued for another environment: {}", e);
+                (
+                    StatusCode::UNAUTHORIZED,
+                    error_body(
+                        "token_wrong_environment",
+                        "This token was issued for a different environment. Please sign in again.",
+                        None,
+                    ),
+                )
+            } else {
+                auth_error("Invalid or expired token")
+            }
+        })?;
 
         let user_id = claims
             .sub
             .parse::<i32>()
             .map_err(|_| auth_error("Invalid token subject"))?;
 
-        Ok(CurrentUser { user_id })
+        // Tokens outlive suspensions and deletions, so the account state is checked on every request
+        let pool = PgPool::from_ref(state);
+        let account = sqlx::query!(
+            r#"SELECT user_is_suspended(id) AS "suspended!", suspended_until, suspension_reason,
+                      deleted_at IS NOT NULL AS "deleted!", COALESCE(role, 'unknown') AS "role!"
+               FROM users WHERE id = $1"#,
+            user_id
+        )
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Account check failed for user {}: {}", user_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                error_body("internal_error", "Something went wrong. Please try again later.", None),
+            )
+        })?
+        .ok_or_else(|| auth_error("Account no longer exists"))?;
+
+        if account.deleted {
+            return Err(auth_error("Account no longer exists"));
+        }
+
+        if account.suspended {
+            return Err(suspension_rejection(
+                account.suspension_reason.as_deref(),
+                account.suspended_until,
+            ));
+        }
+
+        let user = CurrentUser { user_id, role: account.role, impersonator_id: claims.impersonator };
+        parts.extensions.insert(user.clone());
+        Ok(user)
+    }
+}
+
+/// The caller on endpoints that work anonymously but add personal extras
+/// (`is_favorited`, `liked_by_me`, owner-only fields) for a signed-in user.
+/// No `Authorization` header means anonymous; a header that is present but
+/// bad is rejected exactly as `CurrentUser` would, so an expired session
+/// shows up as a 401 instead of quietly turning into a logged-out view.
+pub struct OptionalCurrentUser(pub Option<CurrentUser>);
+
+impl OptionalCurrentUser {
+    pub fn user_id(&self) -> Option<i32> {
+        self.0.as_ref().map(|user| user.user_id)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for OptionalCurrentUser
+where
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, AuthRejection> {
+        if !parts.headers.contains_key(AUTHORIZATION) {
+            return Ok(OptionalCurrentUser(None));
+        }
+        CurrentUser::from_request_parts(parts, state).await.map(|user| OptionalCurrentUser(Some(user)))
     }
 }
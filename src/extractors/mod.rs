@@ -1,2 +1,4 @@
 pub mod administrator;
 pub mod current_user;
+pub mod roles;
+pub mod tx;
@@ -0,0 +1,140 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use sqlx::PgPool;
+
+use crate::errors::AppError;
+use crate::extractors::current_user::CurrentUser;
+
+/// The caller, who must have a provider profile.
+pub struct CurrentProvider {
+    pub user_id: i32,
+    pub provider_id: i32,
+}
+
+/// The caller, who must have a business profile.
+pub struct CurrentBusiness {
+    pub user_id: i32,
+    pub business_id: i32,
+}
+
+/// The caller, who must have a client profile.
+pub struct CurrentClient {
+    pub user_id: i32,
+    pub client_id: i32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Provider,
+    Business,
+    Client,
+}
+
+/// Profile ids already looked up for this request, so a handler (or a layer
+/// in front of it) taking more than one of these extractors queries each
+/// table once. `None` means the caller has no profile of that kind.
+#[derive(Clone, Default)]
+struct RoleIds(Vec<(Role, Option<i32>)>);
+
+async fn profile_id(pool: &PgPool, role: Role, user_id: i32) -> Result<Option<i32>, sqlx::Error> {
+    match role {
+        Role::Provider => {
+            sqlx::query_scalar!("SELECT id FROM providers WHERE user_id = $1", user_id)
+                .fetch_optional(pool)
+                .await
+        }
+        Role::Business => {
+            sqlx::query_scalar!("SELECT id FROM businesses WHERE user_id = $1", user_id)
+                .fetch_optional(pool)
+                .await
+        }
+        Role::Client => {
+            sqlx::query_scalar!("SELECT id FROM clients WHERE user_id = $1", user_id)
+                .fetch_optional(pool)
+                .await
+        }
+    }
+}
+
+/// Authenticates the caller and finds their `role` profile: 401 as for
+/// `CurrentUser`, 403 if they have no such profile.
+async fn resolve<S>(parts: &mut Parts, state: &S, role: Role) -> Result<(i32, i32), Response>
+where
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    let CurrentUser { user_id, .. } = CurrentUser::from_request_parts(parts, state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let cached = parts
+        .extensions
+        .get::<RoleIds>()
+        .and_then(|ids| ids.0.iter().find(|(r, _)| *r == role).map(|(_, id)| *id));
+    let id = match cached {
+        Some(id) => id,
+        None => {
+            let id = profile_id(&PgPool::from_ref(state), role, user_id)
+                .await
+                .map_err(|e| AppError::from(e).into_response())?;
+            let mut ids = parts.extensions.remove::<RoleIds>().unwrap_or_default();
+            ids.0.push((role, id));
+            parts.extensions.insert(ids);
+            id
+        }
+    };
+
+    let message = match role {
+        Role::Provider => "You are not a provider",
+        Role::Business => "You are not a business",
+        Role::Client => "You are not a client",
+    };
+    id.map(|id| (user_id, id))
+        .ok_or_else(|| AppError::Forbidden(message.to_string()).into_response())
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentProvider
+where
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Response> {
+        let (user_id, provider_id) = resolve(parts, state, Role::Provider).await?;
+        Ok(CurrentProvider { user_id, provider_id })
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentBusiness
+where
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Response> {
+        let (user_id, business_id) = resolve(parts, state, Role::Business).await?;
+        Ok(CurrentBusiness { user_id, business_id })
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentClient
+where
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Response> {
+        let (user_id, client_id) = resolve(parts, state, Role::Client).await?;
+        Ok(CurrentClient { user_id, client_id })
+    }
+}
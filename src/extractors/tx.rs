@@ -0,0 +1,105 @@
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRef, FromRequestParts},
+    http::{Request, request::Parts},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use crate::errors::AppError;
+
+/// Where a `Tx` hands its transaction back when the handler is done, for
+/// `commit_on_success` to finish.
+#[derive(Clone, Default)]
+struct TxSlot(Arc<Mutex<Option<Transaction<'static, Postgres>>>>);
+
+/// A transaction begun for this request. Use it like one (`&mut *tx`). When
+/// the handler returns, it is committed if the response is a 2xx and rolled
+/// back otherwise, so early `?` returns can't leave half a change behind.
+/// Call `commit` instead when follow-up work has to see the data first.
+///
+/// Only works on routes wrapped in `transactional`.
+pub struct Tx {
+    tx: Option<Transaction<'static, Postgres>>,
+    slot: TxSlot,
+}
+
+impl Tx {
+    /// Commits now rather than after the response.
+    pub async fn commit(mut self) -> Result<(), sqlx::Error> {
+        match self.tx.take() {
+            Some(tx) => tx.commit().await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Deref for Tx {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        self.tx.as_ref().expect("transaction is present until commit or drop")
+    }
+}
+
+impl DerefMut for Tx {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        self.tx.as_mut().expect("transaction is present until commit or drop")
+    }
+}
+
+impl Drop for Tx {
+    fn drop(&mut self) {
+        if let Some(tx) = self.tx.take() {
+            *self.slot.0.lock().unwrap() = Some(tx);
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Tx
+where
+    PgPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Response> {
+        let slot = parts.extensions.get::<TxSlot>().cloned().ok_or_else(|| {
+            AppError::Internal(format!("Tx used on {} without the transactional layer", parts.uri.path()))
+                .into_response()
+        })?;
+        let tx = PgPool::from_ref(state)
+            .begin()
+            .await
+            .map_err(|e| AppError::from(e).into_response())?;
+        Ok(Tx { tx: Some(tx), slot })
+    }
+}
+
+/// Lets the route's handler take a `Tx`.
+pub fn transactional(route: MethodRouter<PgPool>) -> MethodRouter<PgPool> {
+    route.layer(middleware::from_fn(commit_on_success))
+}
+
+async fn commit_on_success(mut request: Request<Body>, next: Next) -> Response {
+    let slot = TxSlot::default();
+    request.extensions_mut().insert(slot.clone());
+
+    let response = next.run(request).await;
+
+    let tx = slot.0.lock().unwrap().take();
+    match tx {
+        Some(tx) if response.status().is_success() => match tx.commit().await {
+            Ok(()) => response,
+            Err(e) => AppError::from(e).into_response(),
+        },
+        // Dropping the transaction rolls it back
+        _ => response,
+    }
+}
@@ -0,0 +1,12 @@
+pub mod app;
+pub mod config;
+pub mod db;
+pub mod errors;
+pub mod extractors;
+pub mod routes;
+pub mod utils;
+
+pub use errors::{AppError, AppResult};
+
+/// The schema migrations, applied at startup and by the test harness.
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
@@ -1,48 +1,10 @@
-use axum::http::header;
-use axum::{Extension, Router, routing::get};
-use std::sync::Arc;
-use axum_server::bind;
 use dotenvy::dotenv;
-use sqlx::postgres::PgPoolOptions;
+use mtaalink::app::{build_app, serve};
+use mtaalink::{config, db};
+use sqlx::{Connection, PgConnection};
 use std::env;
-use std::net::SocketAddr;
-use tower_http::cors::CorsLayer;
-use tower_http::services::ServeDir;
-use tower_http::trace::TraceLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use sentry::integrations::tower as sentry_tower;
-
-mod errors;
-mod extractors;
-mod routes;
-mod utils;
-
-pub use errors::{AppError, AppResult};
-
-use routes::admin::admin_routes;
-use routes::analytics::analytics_routes;
-use routes::auth::auth_routes;
-use routes::availability::availability_routes;
-use routes::bookings::booking_routes;
-use routes::businesses::businesses_routes;
-use routes::categories::category_routes;
-use routes::clients::client_routes;
-use routes::dashboard::dashboard_routes;
-use routes::favorites::favorites_routes;
-use routes::locations::locations_routes;
-use routes::messages::messages_routes;
-use routes::posts::posts_routes;
-use routes::reviews::reviews_routes;
-use routes::service_providers::service_providers_routes;
-use utils::attachments::attachments_routes;
-use routes::notifications::notification_routes;
-use routes::packages::package_routes;
-use routes::payments::payment_routes;
-use routes::search::search_routes;
-use routes::services::services_routes;
-use routes::wallet::wallet_routes;
-use routes::ws::ws_routes;
-use utils::ws_state::{WsConnections, new_ws_connections};
+use std::net::{SocketAddr, TcpListener};
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() {
@@ -56,102 +18,81 @@ async fn main() {
         }))
     });
 
+    // RUST_LOG narrows or widens what is logged; LOG_FORMAT=json emits one
+    // JSON object per line, with the request span's fields on every event
+    let json_logs = env::var("LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json"));
     tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
         .with(sentry::integrations::tracing::layer())
-        .with(tracing_subscriber::fmt::layer())
+        .with(json_logs.then(|| tracing_subscriber::fmt::layer().json().flatten_event(true).with_span_list(false)))
+        .with((!json_logs).then(tracing_subscriber::fmt::layer))
         .init();
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    println!("Using database URL: {}", database_url);
-
-    // FRONTEND_URL accepts comma-separated origins, e.g.:
-    // FRONTEND_URL=https://mtaalink.vercel.app,http://localhost:3000
-    let allowed_origins: Vec<axum::http::HeaderValue> =
-        env::var("FRONTEND_URL")
-            .unwrap_or_else(|_| "http://localhost:3000".to_string())
-            .split(',')
-            .map(|s| {
-                s.trim()
-                    .parse::<axum::http::HeaderValue>()
-                    .expect("Invalid value in FRONTEND_URL")
-            })
-            .collect();
-
-    let cors_layer = CorsLayer::new()
-        .allow_methods([
-            axum::http::Method::GET,
-            axum::http::Method::POST,
-            axum::http::Method::PUT,
-            axum::http::Method::DELETE,
-        ])
-        .allow_headers([
-            axum::http::header::AUTHORIZATION,
-            axum::http::header::CONTENT_TYPE,
-            header::CONTENT_LENGTH,
-            header::ACCEPT,
-        ])
-        .allow_origin(allowed_origins)
-        .allow_credentials(true);
-
-    // Create a connection pool
-    let pool = PgPoolOptions::new()
-        .max_connections(25)
-        .connect(&database_url)
+    // Fail at boot, not on the first request that needs a missing value
+    let config = match config::Config::from_env() {
+        Ok(config) => config::init(config),
+        Err(e) => {
+            eprintln!("Invalid configuration:\n{}", e);
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("Starting in {} mode", config.app_env);
+    tracing::info!("Using database URL: {}", config.database_url_redacted());
+
+    if config.cors_permissive {
+        tracing::info!("CORS allows every origin (APP_ENV=development, ALLOWED_ORIGINS unset)");
+    }
+
+    // Migrations get a connection of their own, without the pool's
+    // statement_timeout, so a slow index build isn't cancelled halfway
+    let mut migration_conn = PgConnection::connect(&config.database_url)
         .await
-        .expect("Failed to create pool");
-    println!("Database connection pool created successfully");
-
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+        .expect("Failed to connect to the database");
+    mtaalink::MIGRATOR
+        .run(&mut migration_conn)
         .await
         .expect("Failed to run database migrations");
-    println!("Database migrations applied successfully");
-
-    let storage = Arc::new(utils::storage::AppStorage::init());
-    let ws_connections: WsConnections = new_ws_connections();
-
-    utils::reminders::start_reminder_task(pool.clone());
-
-    let app = Router::new()
-        .nest("/auth", auth_routes(pool.clone())) // Mount the auth routes
-        .nest("/dashboard", dashboard_routes(pool.clone()))
-        .nest("/service_providers", service_providers_routes(pool.clone())) // Mount the service providers routes
-        .nest("/businesses", businesses_routes(pool.clone())) // Mount the businesses routes
-        .nest("/clients", client_routes(pool.clone())) // Mount the clients routes
-        .nest("/reviews", reviews_routes(pool.clone())) // Mount the reviews routes
-        .nest("/favorites", favorites_routes(pool.clone())) // Mount the favorites routes
-        .nest("/messages", messages_routes(pool.clone())) // Mount the messages routes
-        .nest("/categories", category_routes(pool.clone())) // Mount the categories routes
-        .nest("/bookings", booking_routes(pool.clone())) // Mount the bookings routes
-        .nest("/admin", admin_routes(pool.clone())) // Mount the admin routes
-        .nest("/locations", locations_routes(pool.clone())) // Mount the locations routes
-        .nest("/posts", posts_routes(pool.clone())) // Mount the posts routes
-        .nest("/attachments", attachments_routes(pool.clone())) // Mount the attachments routes
-        .nest("/services", services_routes(pool.clone()))
-        .nest("/payments", payment_routes(pool.clone()))
-        .nest("/notifications", notification_routes(pool.clone()))
-        .nest("/packages", package_routes(pool.clone()))
-        .nest("/search", search_routes(pool.clone()))
-        .nest("/analytics", analytics_routes(pool.clone()))
-        .nest("/availability", availability_routes(pool.clone()))
-        .nest("/wallet", wallet_routes(pool.clone()))
-        .nest("/ws", ws_routes())
-        .nest_service("/uploads", ServeDir::new("uploads")) // Serve static files from the uploads directory
-        .layer(Extension(ws_connections))
-        .layer(Extension(storage))
-        .layer(cors_layer)
-        .layer(TraceLayer::new_for_http())
-        .layer(sentry_tower::NewSentryLayer::new_from_top())
-        .layer(sentry_tower::SentryHttpLayer::with_transaction())
-        .route("/", get(root));
+    migration_conn.close().await.ok();
+    tracing::info!("Database migrations applied successfully");
 
-    let port = env::var("PORT").unwrap_or_else(|_| "7878".to_string());
-    let addr = SocketAddr::from(([0, 0, 0, 0], port.parse::<u16>().unwrap()));
-    println!("listening on http://{}", addr);
-
-    bind(addr).serve(app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+    let pool = db::pool_options(config)
+        .connect(&config.database_url)
+        .await
+        .expect("Failed to create pool");
+    tracing::info!(
+        "Database connection pool created ({}-{} connections, {}s statement timeout)",
+        config.database_min_connections,
+        config.database_max_connections,
+        config.database_statement_timeout.as_secs()
+    );
+
+    let app = build_app(pool.clone(), config);
+    let addr = SocketAddr::new(config.bind_address, config.port);
+    let listener = TcpListener::bind(addr).unwrap_or_else(|e| panic!("Failed to bind {}: {}", addr, e));
+    tracing::info!("listening on http://{}", addr);
+
+    serve(app, listener, config.shutdown_timeout, shutdown_signal()).await.unwrap();
+    pool.close().await;
+    tracing::info!("Shutdown complete");
 }
 
-async fn root() -> &'static str {
-    "mtaalink is running!"
+/// Resolves on ctrl-c or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("Failed to listen for ctrl-c");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
@@ -1,18 +1,42 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::administrator::require_admin;
-use crate::utils::notifications::notify_best_effort;
+use crate::extractors::current_user::CurrentUser;
+use crate::routes::announcements::{Announcement, validate_announcement};
+use crate::routes::bookings::Booking;
+use crate::utils::booking_status::BOOKING_STATUSES;
+use crate::utils::account_deletion;
+use crate::utils::audit::record_audit;
+use crate::utils::booking_events::record_booking_event;
+use crate::utils::flags::{self, SharedFeatureFlags};
+use crate::utils::interactions::{self, Interaction};
+use crate::utils::jobs::{self, SharedJobTriggers};
+use crate::utils::jwt::create_impersonation_jwt;
+use crate::utils::maintenance::{self, Maintenance, SharedMaintenance};
+use crate::utils::notifications::notify_and_push;
+use crate::utils::pagination::{Paginated, Pagination};
+use crate::utils::refresh_tokens;
+use crate::utils::retention;
+use crate::utils::seed;
+use crate::utils::settings;
+use crate::utils::storage::SharedStorage;
+use crate::utils::storage_gc;
+use crate::utils::webhooks;
+use crate::utils::ws_state::WsConnections;
 use bigdecimal::BigDecimal;
 use axum::{
-    Json, Router,
-    extract::{Path, State},
-    http::StatusCode,
-    routing::{get, post},
+    Extension, Json, Router,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderName, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
 };
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
 use validator::Validate;
-use chrono::{Utc, Duration};
+use chrono::{Utc, Duration, NaiveDate};
 
 pub fn admin_routes(pool: PgPool) -> Router {
     Router::new()
@@ -22,19 +46,51 @@ pub fn admin_routes(pool: PgPool) -> Router {
         .route("/delete_category", post(delete_category))
         .route("/users", get(get_users))
         .route("/delete_user", post(delete_user))
+        .route("/restore_user", post(restore_user))
+        .route("/suspend_user", post(suspend_user))
+        .route("/unsuspend_user", post(unsuspend_user))
         .route("/userAnalytics", get(get_user_analytics))
+        .route("/analytics", get(get_platform_analytics))
+        .route("/analytics/users", get(get_users_analytics))
         .route("/flagContent", post(flag_content))
+        .route("/flag_content", post(flag_content))
+        .route("/flags", get(list_flags))
+        .route("/flags/:id/resolve", post(resolve_content_flag))
         .route("/resolveFlag", post(resolve_flag))
         .route("/moderateReviews", get(moderate_reviews))
+        .route("/post_reports", get(list_post_reports))
+        .route("/post_reports/:post_id/resolve", post(resolve_post_reports))
         .route("/payouts", get(list_pending_payouts))
         .route("/payouts/:id/approve", post(approve_payout))
         .route("/payouts/:id/reject", post(reject_payout))
+        .route("/bookings", get(list_bookings))
+        .route("/bookings/:id", get(get_booking_detail))
+        .route("/bookings/:id/override_status", post(override_booking_status))
         .route("/disputes", get(list_disputes))
         .route("/disputes/:id/resolve", post(resolve_dispute))
         .route("/suspend/:entity_type/:entity_id", post(suspend_entity))
         .route("/unsuspend/:entity_type/:entity_id", post(unsuspend_entity))
         .route("/approve/:entity_type/:entity_id", post(approve_entity))
         .route("/dashboard", get(platform_dashboard))
+        .route("/overview", get(platform_overview))
+        .route("/export", get(export_data))
+        .route("/impersonate", post(impersonate_user))
+        .route("/impersonation_log", get(impersonation_log))
+        .route("/seed", post(run_seed))
+        .route("/seed/status", get(seed_status))
+        .route("/settings", get(get_settings).post(update_settings))
+        .route("/maintenance", get(get_maintenance).post(set_maintenance))
+        .route("/feature_flags", get(list_feature_flags))
+        .route("/feature_flags/:name", put(update_feature_flag).delete(reset_feature_flag))
+        .route("/announcements", get(list_announcements).post(create_announcement))
+        .route("/announcements/:id", put(update_announcement).delete(delete_announcement))
+        .route("/listings/pending", get(list_pending_listings))
+        .route("/listings/:entity_type/:entity_id/approve", post(approve_listing))
+        .route("/listings/:entity_type/:entity_id/reject", post(reject_listing))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:name/run_now", post(run_job_now))
+        .route("/retention", get(get_retention))
+        .route("/attachments/gc", post(run_storage_gc))
         .layer(axum::middleware::from_fn_with_state(pool.clone(), require_admin))
         .with_state(pool)
 }
@@ -72,7 +128,7 @@ pub async fn create_category(
     State(pool): State<PgPool>,
     Json(payload): Json<NewCategory>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
     let row = sqlx::query!(
         "INSERT INTO categories (name, parent_id) VALUES ($1, $2) RETURNING id",
@@ -151,35 +207,142 @@ pub async fn delete_category(
     Ok((StatusCode::OK, Json(json!({ "message": "Category deleted successfully" }))))
 }
 
+#[derive(Deserialize, Debug)]
+pub struct UserListQuery {
+    /// Matches username or email, case-insensitively.
+    pub q: Option<String>,
+    /// `client`, `provider`, `business` or `unknown` (no profile yet).
+    pub role: Option<String>,
+    pub suspended: Option<bool>,
+    /// `true` for only soft-deleted accounts, `false` to leave them out.
+    pub deleted: Option<bool>,
+    pub created_after: Option<NaiveDate>,
+}
+
 #[derive(Serialize, sqlx::FromRow, Debug)]
 pub struct User {
     pub id: i32,
     pub username: String,
     pub email: String,
-    pub role: Option<String>,
+    pub role: String,
+    pub is_admin: bool,
+    pub created_at: Option<chrono::NaiveDateTime>,
+    pub last_active_at: Option<chrono::DateTime<Utc>>,
     pub provider_id: Option<i32>,
     pub provider_approved: Option<bool>,
     pub business_id: Option<i32>,
     pub business_verified: Option<bool>,
+    pub suspended: bool,
+    pub suspended_until: Option<chrono::DateTime<Utc>>,
+    pub suspension_reason: Option<String>,
+    pub deleted_at: Option<chrono::DateTime<Utc>>,
+    pub bookings_made: i64,
+    pub bookings_received: i64,
+    pub reviews_written: i64,
+    pub reviews_received: i64,
+    /// Every report filed against their content, open or resolved.
+    pub reports_against: i64,
+    /// Distinct items of theirs taken down after a report in the last 90 days.
+    pub upheld_reports_90d: i64,
+    pub repeat_offender: bool,
 }
 
+/// Users matching the list filters, with the role derived from profile tables
+/// the same way `/auth/me` does. Shared by the page and total queries.
+const FILTERED_USERS_SQL: &str = r#"
+    WITH filtered AS (
+        SELECT u.id, u.username, u.email, u.created_at,
+               GREATEST(u.last_login_at, u.last_seen_at) AS last_active_at,
+               CASE
+                 WHEN c.id IS NOT NULL THEN 'client'
+                 WHEN p.id IS NOT NULL THEN 'provider'
+                 WHEN b.id IS NOT NULL THEN 'business'
+                 ELSE 'unknown'
+               END AS role,
+               a.id IS NOT NULL AS is_admin,
+               p.id AS provider_id, p.approved AS provider_approved,
+               b.id AS business_id, b.verified AS business_verified,
+               user_is_suspended(u.id) AS suspended,
+               u.suspended_until, u.suspension_reason, u.deleted_at
+        FROM users u
+        LEFT JOIN clients    c ON c.user_id = u.id
+        LEFT JOIN providers  p ON p.user_id = u.id
+        LEFT JOIN businesses b ON b.user_id = u.id
+        LEFT JOIN admins     a ON a.user_id = u.id
+    )
+    SELECT * FROM filtered f
+    WHERE ($1::text IS NULL OR f.username ILIKE '%' || $1 || '%' OR f.email ILIKE '%' || $1 || '%')
+      AND ($2::text IS NULL OR f.role = $2)
+      AND ($3::bool IS NULL OR f.suspended = $3)
+      AND ($4::date IS NULL OR f.created_at >= $4)
+      AND ($5::bool IS NULL OR (f.deleted_at IS NOT NULL) = $5)
+"#;
+
 pub async fn get_users(
     State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let users = sqlx::query_as!(
-        User,
-        r#"SELECT u.id, u.username, u.email, u.role,
-                  p.id AS "provider_id?", p.approved AS "provider_approved?",
-                  b.id AS "business_id?", b.verified AS "business_verified?"
-           FROM users u
-           LEFT JOIN providers   p ON p.user_id = u.id
-           LEFT JOIN businesses  b ON b.user_id = u.id
-           ORDER BY u.id DESC"#
-    )
-    .fetch_all(&pool)
-    .await?;
+    Query(params): Query<UserListQuery>,
+    pagination: Pagination,
+) -> AppResult<(StatusCode, Json<Paginated<User>>)> {
+    let role = params.role.map(|r| r.to_lowercase());
+    if let Some(ref role) = role {
+        if !["client", "provider", "business", "unknown"].contains(&role.as_str()) {
+            return Err(AppError::BadRequest(
+                "role must be 'client', 'provider', 'business' or 'unknown'".to_string(),
+            ));
+        }
+    }
+    let q = params
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(|q| q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    // Summary counts are only computed for the rows on this page
+    let list_sql = format!(
+        r#"SELECT pg.*,
+                  (SELECT COUNT(*) FROM bookings bk WHERE bk.client_id = pg.id) AS bookings_made,
+                  (SELECT COUNT(*) FROM bookings bk
+                   WHERE (bk.target_type = 'provider' AND bk.target_id = pg.provider_id)
+                      OR (bk.target_type = 'business' AND bk.target_id = pg.business_id)) AS bookings_received,
+                  (SELECT COUNT(*) FROM reviews r WHERE r.reviewer_id = pg.id) AS reviews_written,
+                  (SELECT COUNT(*) FROM reviews r
+                   WHERE (r.target_type = 'provider' AND r.target_id = pg.provider_id)
+                      OR (r.target_type = 'business' AND r.target_id = pg.business_id)) AS reviews_received,
+                  (SELECT COUNT(*) FROM content_flags cf WHERE cf.target_owner_id = pg.id) AS reports_against,
+                  o.upheld AS upheld_reports_90d,
+                  o.upheld >= 3 AS repeat_offender
+           FROM ({FILTERED_USERS_SQL} ORDER BY f.id DESC LIMIT $6 OFFSET $7) pg
+           CROSS JOIN LATERAL (
+               SELECT COUNT(DISTINCT (cf.target_type, cf.target_id)) AS upheld
+               FROM content_flags cf
+               WHERE cf.target_owner_id = pg.id
+                 AND cf.resolution IN ('hidden', 'deleted')
+                 AND cf.resolved_at >= NOW() - INTERVAL '90 days'
+           ) o
+           ORDER BY pg.id DESC"#
+    );
+    let count_sql = format!("SELECT COUNT(*) FROM ({FILTERED_USERS_SQL}) t");
+
+    let (users, total) = tokio::try_join!(
+        sqlx::query_as::<_, User>(&list_sql)
+            .bind(&q)
+            .bind(&role)
+            .bind(params.suspended)
+            .bind(params.created_after)
+            .bind(params.deleted)
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&pool),
+        sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(&q)
+            .bind(&role)
+            .bind(params.suspended)
+            .bind(params.created_after)
+            .bind(params.deleted)
+            .fetch_one(&pool),
+    )?;
 
-    Ok((StatusCode::OK, Json(json!({ "users": users }))))
+    Ok((StatusCode::OK, Json(Paginated::new(users, pagination, total))))
 }
 
 #[derive(Deserialize, Debug)]
@@ -187,15 +350,186 @@ pub struct DeleteUserParams {
     pub user_id: i32,
 }
 
+/// Soft-deletes an account: it drops off public surfaces, can no longer sign
+/// in and loses its sessions and pending password resets, but stays
+/// restorable until the cleanup sweep purges it after the retention window.
 pub async fn delete_user(
     State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
     Json(payload): Json<DeleteUserParams>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    sqlx::query!("DELETE FROM users WHERE id = $1", payload.user_id)
-        .execute(&pool)
+    if payload.user_id == admin_id {
+        return Err(AppError::BadRequest("You cannot delete your own account".to_string()));
+    }
+
+    let retention = account_deletion::retention_days(&pool).await;
+    let mut tx = pool.begin().await?;
+
+    let purge_after = sqlx::query_scalar!(
+        r#"UPDATE users SET deleted_at = NOW(), deleted_by = $1
+           WHERE id = $2 AND deleted_at IS NULL
+           RETURNING NOW() + make_interval(days => $3) AS "purge_after!""#,
+        admin_id,
+        payload.user_id,
+        retention
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found or already deleted".to_string()))?;
+
+    // A reset link or refresh token issued before the delete would otherwise
+    // still work on the account if it is restored
+    sqlx::query!("DELETE FROM password_resets WHERE user_id = $1", payload.user_id)
+        .execute(&mut *tx)
+        .await?;
+    refresh_tokens::revoke_all(&mut tx, payload.user_id).await?;
+
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "user_deleted",
+        "user",
+        Some(payload.user_id),
+        json!({ "purge_after": purge_after }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "User deleted successfully", "purge_after": purge_after })),
+    ))
+}
+
+/// Undoes a soft delete while the account is still inside the retention window.
+pub async fn restore_user(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Json(payload): Json<DeleteUserParams>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let mut tx = pool.begin().await?;
+
+    let restored = sqlx::query!(
+        r#"UPDATE users SET deleted_at = NULL, deleted_by = NULL
+           WHERE id = $1
+             AND deleted_at IS NOT NULL
+             AND deleted_at >= NOW() - make_interval(days => $2)"#,
+        payload.user_id,
+        account_deletion::retention_days(&pool).await
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if restored.rows_affected() == 0 {
+        return Err(AppError::NotFound("No restorable deletion found for this user".to_string()));
+    }
+
+    record_audit(&mut *tx, Some(admin_id), "user_restored", "user", Some(payload.user_id), json!({}))
+        .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "User restored" }))))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SuspendUserPayload {
+    pub user_id: i32,
+    pub reason: String,
+    /// End of the suspension; omit to suspend indefinitely.
+    pub until: Option<chrono::DateTime<Utc>>,
+}
+
+/// Suspends an account without deleting anything. Login and existing tokens
+/// are refused and the user's listings and posts drop out of public queries
+/// until `until` passes or an admin lifts it.
+pub async fn suspend_user(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Json(payload): Json<SuspendUserPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::BadRequest("Reason cannot be empty".to_string()));
+    }
+    if payload.until.is_some_and(|until| until <= Utc::now()) {
+        return Err(AppError::BadRequest("'until' must be in the future".to_string()));
+    }
+    if payload.user_id == admin_id {
+        return Err(AppError::BadRequest("You cannot suspend your own account".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query!(
+        r#"UPDATE users
+           SET suspended_at = NOW(), suspended_until = $1, suspension_reason = $2
+           WHERE id = $3"#,
+        payload.until,
+        reason,
+        payload.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "user_suspended",
+        "user",
+        Some(payload.user_id),
+        json!({ "reason": reason, "until": payload.until }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "User suspended", "until": payload.until }))))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UnsuspendUserPayload {
+    pub user_id: i32,
+}
+
+pub async fn unsuspend_user(
+    State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Json(payload): Json<UnsuspendUserPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query!(
+        r#"UPDATE users
+           SET suspended_at = NULL, suspended_until = NULL, suspension_reason = NULL
+           WHERE id = $1 AND suspended_at IS NOT NULL"#,
+        payload.user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("No suspension found for this user".to_string()));
+    }
+
+    record_audit(&mut *tx, Some(admin_id), "user_unsuspended", "user", Some(payload.user_id), json!({}))
         .await?;
 
-    Ok((StatusCode::OK, Json(json!({ "message": "User deleted successfully" }))))
+    tx.commit().await?;
+
+    notify_and_push(
+        &pool, &ws_conns, payload.user_id, "account_reinstated", "Account reinstated",
+        "Your account suspension has been lifted. Welcome back.",
+        None, None,
+    ).await;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "User unsuspended" }))))
 }
 
 // ── Analytics ─────────────────────────────────────────────────────────────────
@@ -255,38 +589,2122 @@ pub async fn get_user_analytics(
     ))
 }
 
-// ── Content moderation ────────────────────────────────────────────────────────
+// ── User analytics ────────────────────────────────────────────────────────────
 
-#[derive(serde::Deserialize, Debug)]
-pub struct FlagContentPayload {
-    pub target_type: String,
-    pub target_id: i32,
-    pub reason: String,
+/// Longest range accepted by `/analytics/users` (24 months).
+const MAX_ANALYTICS_RANGE_DAYS: i64 = 731;
+
+#[derive(Deserialize, Debug)]
+pub struct UserAnalyticsQuery {
+    /// Inclusive start date (default: 90 days before `to`).
+    pub from: Option<NaiveDate>,
+    /// Inclusive end date (default: today).
+    pub to: Option<NaiveDate>,
+    /// `day` (default) or `week`.
+    pub granularity: Option<String>,
 }
 
-pub async fn flag_content(
+/// Registrations, role mix, activity, signup cohorts and provider geography,
+/// shaped as label/value series for the admin charts.
+pub async fn get_users_analytics(
     State(pool): State<PgPool>,
-    Json(payload): Json<FlagContentPayload>,
+    Query(params): Query<UserAnalyticsQuery>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    if payload.reason.trim().is_empty() {
-        return Err(AppError::BadRequest("Reason cannot be empty".to_string()));
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params.from.unwrap_or(to - Duration::days(90));
+    if from > to {
+        return Err(AppError::BadRequest("'from' must not be after 'to'".to_string()));
     }
-    if payload.target_id <= 0 {
-        return Err(AppError::BadRequest("Invalid target ID".to_string()));
+    if (to - from).num_days() > MAX_ANALYTICS_RANGE_DAYS {
+        return Err(AppError::BadRequest("Range cannot exceed 24 months".to_string()));
+    }
+
+    let granularity = params.granularity.unwrap_or_else(|| "day".to_string());
+    if !["day", "week"].contains(&granularity.as_str()) {
+        return Err(AppError::BadRequest(
+            "granularity must be 'day' or 'week'".to_string(),
+        ));
+    }
+
+    let (registrations, roles, active, cohorts, counties) = tokio::try_join!(
+        // Zero-filled buckets so the series has no gaps
+        sqlx::query!(
+            r#"WITH buckets AS (
+                   SELECT generate_series(
+                       date_trunc($3, $1::date::timestamp),
+                       date_trunc($3, $2::date::timestamp),
+                       ('1 ' || $3)::interval
+                   ) AS bucket
+               )
+               SELECT b.bucket::date AS "bucket!", COUNT(u.id) AS "count!"
+               FROM buckets b
+               LEFT JOIN users u
+                 ON u.created_at >= b.bucket
+                AND u.created_at <  b.bucket + ('1 ' || $3)::interval
+                AND u.created_at >= $1::date
+                AND u.created_at <  $2::date + 1
+               GROUP BY b.bucket
+               ORDER BY b.bucket"#,
+            from,
+            to,
+            granularity
+        )
+        .fetch_all(&pool),
+        sqlx::query!(
+            r#"SELECT COALESCE(role, 'unknown') AS "role!",
+                      COUNT(*) AS "total!",
+                      COUNT(*) FILTER (WHERE created_at >= $1::date AND created_at < $2::date + 1) AS "in_range!"
+               FROM users
+               GROUP BY 1
+               ORDER BY 2 DESC"#,
+            from,
+            to
+        )
+        .fetch_all(&pool),
+        sqlx::query!(
+            r#"SELECT
+                 COUNT(*) FILTER (WHERE GREATEST(last_login_at, last_seen_at) >= NOW() - INTERVAL '7 days')  AS "last_7_days!",
+                 COUNT(*) FILTER (WHERE GREATEST(last_login_at, last_seen_at) >= NOW() - INTERVAL '30 days') AS "last_30_days!"
+               FROM users"#
+        )
+        .fetch_one(&pool),
+        // Retained = seen at least 30 days after signing up
+        sqlx::query!(
+            r#"SELECT date_trunc('month', created_at)::date AS "cohort!",
+                      COUNT(*) AS "signups!",
+                      COUNT(*) FILTER (
+                          WHERE GREATEST(last_login_at, last_seen_at) >= created_at + INTERVAL '30 days'
+                      ) AS "retained_30d!",
+                      COUNT(*) FILTER (
+                          WHERE GREATEST(last_login_at, last_seen_at) >= NOW() - INTERVAL '30 days'
+                      ) AS "active_last_30d!"
+               FROM users
+               WHERE created_at >= $1::date AND created_at < $2::date + 1
+               GROUP BY 1
+               ORDER BY 1"#,
+            from,
+            to
+        )
+        .fetch_all(&pool),
+        sqlx::query!(
+            r#"SELECT co.name AS county, COUNT(DISTINCT pl.provider_id) AS "providers!"
+               FROM provider_locations pl
+               JOIN wards w ON w.id = pl.ward_id
+               JOIN constituencies cn ON cn.id = w.constituency_id
+               JOIN counties co ON co.id = cn.county_id
+               GROUP BY co.id, co.name
+               ORDER BY 2 DESC, co.name
+               LIMIT 10"#
+        )
+        .fetch_all(&pool),
+    )?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "range": { "from": from, "to": to, "granularity": granularity },
+            "registrations": {
+                "labels": registrations.iter().map(|r| r.bucket).collect::<Vec<_>>(),
+                "values": registrations.iter().map(|r| r.count).collect::<Vec<_>>(),
+            },
+            "roles": {
+                "labels": roles.iter().map(|r| r.role.clone()).collect::<Vec<_>>(),
+                "total": roles.iter().map(|r| r.total).collect::<Vec<_>>(),
+                "in_range": roles.iter().map(|r| r.in_range).collect::<Vec<_>>(),
+            },
+            "active_users": {
+                "last_7_days": active.last_7_days,
+                "last_30_days": active.last_30_days,
+            },
+            "cohorts": {
+                "labels": cohorts.iter().map(|r| r.cohort.format("%Y-%m").to_string()).collect::<Vec<_>>(),
+                "signups": cohorts.iter().map(|r| r.signups).collect::<Vec<_>>(),
+                "retained_30d": cohorts.iter().map(|r| r.retained_30d).collect::<Vec<_>>(),
+                "active_last_30d": cohorts.iter().map(|r| r.active_last_30d).collect::<Vec<_>>(),
+            },
+            "top_counties": {
+                "labels": counties.iter().map(|r| r.county.clone()).collect::<Vec<_>>(),
+                "values": counties.iter().map(|r| r.providers).collect::<Vec<_>>(),
+            },
+        })),
+    ))
+}
+
+// ── Platform analytics ────────────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct PlatformAnalyticsQuery {
+    /// Inclusive start date (default: the Monday 11 weeks before `to`'s, so
+    /// the series has 12 weeks).
+    pub from: Option<NaiveDate>,
+    /// Inclusive end date (default: today).
+    pub to: Option<NaiveDate>,
+}
+
+/// Users by role, weekly onboarding and message volume, bookings by status
+/// and review counts in one payload. Totals cover everything; `in_range`
+/// counts and the weekly series are bounded by `from`/`to`.
+pub async fn get_platform_analytics(
+    State(pool): State<PgPool>,
+    Query(params): Query<PlatformAnalyticsQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params.from.unwrap_or(to - Duration::weeks(11));
+    if from > to {
+        return Err(AppError::BadRequest("'from' must not be after 'to'".to_string()));
+    }
+    if (to - from).num_days() > MAX_ANALYTICS_RANGE_DAYS {
+        return Err(AppError::BadRequest("Range cannot exceed 24 months".to_string()));
+    }
+
+    let (roles, weeks, bookings, reviews, messages) = tokio::try_join!(
+        sqlx::query!(
+            r#"SELECT COALESCE(role, 'unknown') AS "role!",
+                      COUNT(*) AS "total!",
+                      COUNT(*) FILTER (WHERE created_at >= $1::date AND created_at < $2::date + 1) AS "in_range!"
+               FROM users
+               GROUP BY 1
+               ORDER BY 2 DESC"#,
+            from,
+            to
+        )
+        .fetch_all(&pool),
+        // Monday buckets, zero-filled. Clients have no timestamp of their own,
+        // so their account's signup stands in.
+        sqlx::query!(
+            r#"WITH weeks AS (
+                   SELECT generate_series(
+                       date_trunc('week', $1::date::timestamp),
+                       date_trunc('week', $2::date::timestamp),
+                       INTERVAL '1 week'
+                   ) AS week
+               )
+               SELECT w.week::date AS "week!",
+                      (SELECT COUNT(*) FROM providers p
+                        WHERE p.created_at >= GREATEST(w.week, $1::date)
+                          AND p.created_at <  LEAST(w.week + INTERVAL '1 week', $2::date + 1)) AS "providers!",
+                      (SELECT COUNT(*) FROM businesses b
+                        WHERE b.created_at >= GREATEST(w.week, $1::date)
+                          AND b.created_at <  LEAST(w.week + INTERVAL '1 week', $2::date + 1)) AS "businesses!",
+                      (SELECT COUNT(*) FROM clients c JOIN users u ON u.id = c.user_id
+                        WHERE u.created_at >= GREATEST(w.week, $1::date)
+                          AND u.created_at <  LEAST(w.week + INTERVAL '1 week', $2::date + 1)) AS "clients!",
+                      (SELECT COUNT(*) FROM messages m
+                        WHERE m.created_at >= GREATEST(w.week, $1::date)
+                          AND m.created_at <  LEAST(w.week + INTERVAL '1 week', $2::date + 1)) AS "messages!"
+               FROM weeks w
+               ORDER BY w.week"#,
+            from,
+            to
+        )
+        .fetch_all(&pool),
+        sqlx::query!(
+            r#"SELECT status AS "status!",
+                      COUNT(*) AS "total!",
+                      COUNT(*) FILTER (WHERE created_at >= $1::date AND created_at < $2::date + 1) AS "in_range!"
+               FROM bookings
+               GROUP BY status"#,
+            from,
+            to
+        )
+        .fetch_all(&pool),
+        sqlx::query!(
+            r#"SELECT
+                 COUNT(*) AS "total!",
+                 COUNT(*) FILTER (WHERE created_at >= $1::date AND created_at < $2::date + 1) AS "in_range!",
+                 COALESCE(AVG(rating), 0)::float8 AS "average_rating!",
+                 COUNT(*) FILTER (WHERE rating = 1) AS "one!",
+                 COUNT(*) FILTER (WHERE rating = 2) AS "two!",
+                 COUNT(*) FILTER (WHERE rating = 3) AS "three!",
+                 COUNT(*) FILTER (WHERE rating = 4) AS "four!",
+                 COUNT(*) FILTER (WHERE rating = 5) AS "five!"
+               FROM reviews"#,
+            from,
+            to
+        )
+        .fetch_one(&pool),
+        sqlx::query!(
+            r#"SELECT
+                 COUNT(*) AS "total!",
+                 COUNT(*) FILTER (WHERE created_at >= $1::date AND created_at < $2::date + 1) AS "in_range!"
+               FROM messages"#,
+            from,
+            to
+        )
+        .fetch_one(&pool),
+    )?;
+
+    // Every known status gets a slot, even with no bookings in it
+    let booking_count = |status: &str| bookings.iter().find(|r| r.status == status);
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "range": { "from": from, "to": to },
+            "users_by_role": {
+                "labels": roles.iter().map(|r| r.role.clone()).collect::<Vec<_>>(),
+                "total": roles.iter().map(|r| r.total).collect::<Vec<_>>(),
+                "in_range": roles.iter().map(|r| r.in_range).collect::<Vec<_>>(),
+            },
+            "onboarded_per_week": {
+                "labels": weeks.iter().map(|r| r.week).collect::<Vec<_>>(),
+                "providers": weeks.iter().map(|r| r.providers).collect::<Vec<_>>(),
+                "businesses": weeks.iter().map(|r| r.businesses).collect::<Vec<_>>(),
+                "clients": weeks.iter().map(|r| r.clients).collect::<Vec<_>>(),
+            },
+            "bookings_by_status": {
+                "labels": BOOKING_STATUSES,
+                "total": BOOKING_STATUSES.iter().map(|s| booking_count(s).map_or(0, |r| r.total)).collect::<Vec<_>>(),
+                "in_range": BOOKING_STATUSES.iter().map(|s| booking_count(s).map_or(0, |r| r.in_range)).collect::<Vec<_>>(),
+            },
+            "reviews": {
+                "total": reviews.total,
+                "in_range": reviews.in_range,
+                "average_rating": reviews.average_rating,
+                "by_rating": {
+                    "labels": [1, 2, 3, 4, 5],
+                    "values": [reviews.one, reviews.two, reviews.three, reviews.four, reviews.five],
+                },
+            },
+            "messages": {
+                "total": messages.total,
+                "in_range": messages.in_range,
+                "per_week": {
+                    "labels": weeks.iter().map(|r| r.week).collect::<Vec<_>>(),
+                    "values": weeks.iter().map(|r| r.messages).collect::<Vec<_>>(),
+                },
+            },
+        })),
+    ))
+}
+
+// ── Impersonation ─────────────────────────────────────────────────────────────
+
+/// Lifetime of a token minted by `/impersonate`.
+const IMPERSONATION_TTL_MINUTES: i64 = 30;
+
+#[derive(Deserialize, Debug)]
+pub struct ImpersonatePayload {
+    pub user_id: i32,
+    /// Support ticket or note explaining why; stored in the audit log.
+    pub reason: Option<String>,
+}
+
+/// Mints a short-lived token that acts as the target user. Every request made
+/// with it is audited, and payment/account-destroying endpoints refuse it.
+pub async fn impersonate_user(
+    State(pool): State<PgPool>,
+    current_user: CurrentUser,
+    Json(payload): Json<ImpersonatePayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    current_user.deny_impersonation()?;
+    let admin_id = current_user.user_id;
+    if payload.user_id == admin_id {
+        return Err(AppError::BadRequest("You cannot impersonate yourself".to_string()));
+    }
+
+    let target = sqlx::query!(
+        r#"SELECT u.username, a.id IS NOT NULL AS "is_admin!"
+           FROM users u
+           LEFT JOIN admins a ON a.user_id = u.id
+           WHERE u.id = $1"#,
+        payload.user_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    if target.is_admin {
+        return Err(AppError::Forbidden("Admin accounts cannot be impersonated".to_string()));
+    }
+
+    let (token, expires_at) = create_impersonation_jwt(
+        payload.user_id,
+        admin_id,
+        Duration::minutes(IMPERSONATION_TTL_MINUTES),
+    )?;
+
+    record_audit(
+        &pool,
+        Some(admin_id),
+        "impersonation_started",
+        "user",
+        Some(payload.user_id),
+        json!({
+            "reason": payload.reason.as_deref().map(str::trim).filter(|r| !r.is_empty()),
+            "expires_at": expires_at,
+        }),
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "token": token,
+            "expires_at": expires_at,
+            "user_id": payload.user_id,
+            "username": target.username,
+        })),
+    ))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImpersonationLogQuery {
+    pub admin_id: Option<i32>,
+    pub user_id: Option<i32>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImpersonationLogRow {
+    pub id: i64,
+    pub action: String,
+    pub admin_id: Option<i32>,
+    pub admin_username: Option<String>,
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+/// Impersonation sessions and every request made during them, newest first.
+pub async fn impersonation_log(
+    State(pool): State<PgPool>,
+    Query(params): Query<ImpersonationLogQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 200);
+    let offset = (page - 1) * per_page;
+
+    let (entries, total) = tokio::try_join!(
+        sqlx::query_as!(
+            ImpersonationLogRow,
+            r#"SELECT al.id, al.action,
+                      al.actor_id AS admin_id, a.username AS "admin_username?",
+                      al.target_id AS user_id, u.username AS "username?",
+                      al.details, al.created_at
+               FROM audit_log al
+               LEFT JOIN users a ON a.id = al.actor_id
+               LEFT JOIN users u ON u.id = al.target_id
+               WHERE al.action IN ('impersonation_started', 'impersonated_request')
+                 AND ($1::int IS NULL OR al.actor_id = $1)
+                 AND ($2::int IS NULL OR al.target_id = $2)
+               ORDER BY al.created_at DESC, al.id DESC
+               LIMIT $3 OFFSET $4"#,
+            params.admin_id,
+            params.user_id,
+            per_page,
+            offset
+        )
+        .fetch_all(&pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM audit_log
+               WHERE action IN ('impersonation_started', 'impersonated_request')
+                 AND ($1::int IS NULL OR actor_id = $1)
+                 AND ($2::int IS NULL OR target_id = $2)"#,
+            params.admin_id,
+            params.user_id
+        )
+        .fetch_one(&pool),
+    )?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "entries": entries,
+            "total": total,
+            "page": page,
+            "per_page": per_page,
+        })),
+    ))
+}
+
+// ── Announcements ─────────────────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct AnnouncementListQuery {
+    /// `scheduled`, `active` or `expired`; omit for all.
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct AdminAnnouncementRow {
+    pub id: i32,
+    pub title: String,
+    pub body: String,
+    pub severity: String,
+    pub audience: String,
+    pub starts_at: chrono::DateTime<Utc>,
+    pub ends_at: Option<chrono::DateTime<Utc>>,
+    pub status: String,
+    pub dismissals: i64,
+    pub created_by: Option<i32>,
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewAnnouncement {
+    pub title: String,
+    pub body: String,
+    pub severity: Option<String>,
+    pub audience: Option<String>,
+    /// Defaults to now.
+    pub starts_at: Option<chrono::DateTime<Utc>>,
+    /// Omit to keep the banner up until it is deleted.
+    pub ends_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdateAnnouncement {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub severity: Option<String>,
+    pub audience: Option<String>,
+    pub starts_at: Option<chrono::DateTime<Utc>>,
+    pub ends_at: Option<chrono::DateTime<Utc>>,
+    /// Remove the end time so the banner runs indefinitely.
+    #[serde(default)]
+    pub clear_ends_at: bool,
+}
+
+/// Every announcement, including scheduled and expired ones, with how many
+/// users have dismissed each.
+pub async fn list_announcements(
+    State(pool): State<PgPool>,
+    Query(params): Query<AnnouncementListQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * per_page;
+
+    let status = params.status.map(|s| s.to_lowercase());
+    if let Some(ref status) = status {
+        if !["scheduled", "active", "expired"].contains(&status.as_str()) {
+            return Err(AppError::BadRequest(
+                "status must be 'scheduled', 'active' or 'expired'".to_string(),
+            ));
+        }
+    }
+
+    let (announcements, total) = tokio::try_join!(
+        sqlx::query_as!(
+            AdminAnnouncementRow,
+            r#"SELECT x.id, x.title, x.body, x.severity, x.audience, x.starts_at, x.ends_at,
+                      x.status AS "status!", x.dismissals AS "dismissals!",
+                      x.created_by, x.created_at, x.updated_at
+               FROM (
+                   SELECT a.id, a.title, a.body, a.severity, a.audience, a.starts_at, a.ends_at,
+                          CASE WHEN a.starts_at > NOW() THEN 'scheduled'
+                               WHEN a.ends_at IS NOT NULL AND a.ends_at <= NOW() THEN 'expired'
+                               ELSE 'active'
+                          END AS status,
+                          (SELECT COUNT(*) FROM announcement_dismissals d
+                           WHERE d.announcement_id = a.id) AS dismissals,
+                          a.created_by, a.created_at, a.updated_at
+                   FROM announcements a
+               ) x
+               WHERE ($1::text IS NULL OR x.status = $1)
+               ORDER BY x.starts_at DESC, x.id DESC
+               LIMIT $2 OFFSET $3"#,
+            status,
+            per_page,
+            offset
+        )
+        .fetch_all(&pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM announcements a
+               WHERE $1::text IS NULL
+                  OR $1 = CASE WHEN a.starts_at > NOW() THEN 'scheduled'
+                               WHEN a.ends_at IS NOT NULL AND a.ends_at <= NOW() THEN 'expired'
+                               ELSE 'active'
+                          END"#,
+            status
+        )
+        .fetch_one(&pool),
+    )?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "announcements": announcements,
+            "total": total,
+            "page": page,
+            "per_page": per_page,
+        })),
+    ))
+}
+
+pub async fn create_announcement(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Json(payload): Json<NewAnnouncement>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let title = payload.title.trim();
+    let body = payload.body.trim();
+    let severity = payload.severity.as_deref().unwrap_or("info");
+    let audience = payload.audience.as_deref().unwrap_or("all");
+    let starts_at = payload.starts_at.unwrap_or_else(Utc::now);
+    validate_announcement(title, body, severity, audience, starts_at, payload.ends_at)?;
+
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query_scalar!(
+        r#"INSERT INTO announcements (title, body, severity, audience, starts_at, ends_at, created_by)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           RETURNING id"#,
+        title,
+        body,
+        severity,
+        audience,
+        starts_at,
+        payload.ends_at,
+        admin_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "announcement_created",
+        "announcement",
+        Some(id),
+        json!({ "title": title, "audience": audience, "severity": severity }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(json!({ "message": "Announcement created", "id": id }))))
+}
+
+pub async fn update_announcement(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateAnnouncement>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let mut tx = pool.begin().await?;
+
+    let current = sqlx::query_as!(
+        Announcement,
+        r#"SELECT id, title, body, severity, audience, starts_at, ends_at
+           FROM announcements WHERE id = $1 FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Announcement not found".to_string()))?;
+
+    let title = payload.title.as_deref().map(str::trim).unwrap_or(&current.title);
+    let body = payload.body.as_deref().map(str::trim).unwrap_or(&current.body);
+    let severity = payload.severity.as_deref().unwrap_or(&current.severity);
+    let audience = payload.audience.as_deref().unwrap_or(&current.audience);
+    let starts_at = payload.starts_at.unwrap_or(current.starts_at);
+    let ends_at = if payload.clear_ends_at { None } else { payload.ends_at.or(current.ends_at) };
+    validate_announcement(title, body, severity, audience, starts_at, ends_at)?;
+
+    sqlx::query!(
+        r#"UPDATE announcements
+           SET title = $1, body = $2, severity = $3, audience = $4,
+               starts_at = $5, ends_at = $6, updated_at = NOW()
+           WHERE id = $7"#,
+        title,
+        body,
+        severity,
+        audience,
+        starts_at,
+        ends_at,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "announcement_updated",
+        "announcement",
+        Some(id),
+        json!({
+            "title": title,
+            "audience": audience,
+            "severity": severity,
+            "starts_at": starts_at,
+            "ends_at": ends_at,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Announcement updated" }))))
+}
+
+pub async fn delete_announcement(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let mut tx = pool.begin().await?;
+
+    let title = sqlx::query_scalar!("DELETE FROM announcements WHERE id = $1 RETURNING title", id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Announcement not found".to_string()))?;
+
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "announcement_deleted",
+        "announcement",
+        Some(id),
+        json!({ "title": title }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Announcement deleted" }))))
+}
+
+// ── Listing moderation ────────────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct PendingListingsQuery {
+    /// `provider` or `business`; omit for both.
+    #[serde(rename = "type")]
+    pub entity_type: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct PendingListing {
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub location: Option<String>,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RejectListingPayload {
+    pub reason: String,
+}
+
+/// Providers and businesses waiting for a listing decision, oldest first.
+pub async fn list_pending_listings(
+    State(pool): State<PgPool>,
+    Query(params): Query<PendingListingsQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * per_page;
+
+    let entity_type = params.entity_type.map(|t| t.to_lowercase());
+    if let Some(ref t) = entity_type {
+        if t != "provider" && t != "business" {
+            return Err(AppError::BadRequest("type must be 'provider' or 'business'".to_string()));
+        }
+    }
+
+    let (listings, total) = pending_listings(&pool, entity_type, per_page, offset).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "listings": listings,
+            "total": total,
+            "page": page,
+            "per_page": per_page,
+        })),
+    ))
+}
+
+/// A page of the listing review queue, oldest first, and its total size.
+pub async fn pending_listings(
+    pool: &PgPool,
+    entity_type: Option<String>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<PendingListing>, i64), sqlx::Error> {
+    tokio::try_join!(
+        sqlx::query_as!(
+            PendingListing,
+            r#"SELECT x.entity_type AS "entity_type!", x.entity_id AS "entity_id!",
+                      x.user_id AS "user_id!", x.username AS "username!",
+                      x.name, x.category, x.location, x.created_at
+               FROM (
+                   SELECT 'provider' AS entity_type, p.id AS entity_id, p.user_id, u.username,
+                          p.service_name AS name, p.category, p.location, p.created_at
+                   FROM providers p JOIN users u ON u.id = p.user_id
+                   WHERE p.listing_status = 'pending_review'
+                   UNION ALL
+                   SELECT 'business', b.id, b.user_id, u.username,
+                          b.business_name, b.category, b.location, b.created_at
+                   FROM businesses b JOIN users u ON u.id = b.user_id
+                   WHERE b.listing_status = 'pending_review'
+               ) x
+               WHERE ($1::text IS NULL OR x.entity_type = $1)
+               ORDER BY x.created_at ASC NULLS LAST, x.entity_id
+               LIMIT $2 OFFSET $3"#,
+            entity_type,
+            limit,
+            offset
+        )
+        .fetch_all(pool),
+        sqlx::query_scalar!(
+            r#"SELECT
+                   (CASE WHEN $1::text IS NULL OR $1 = 'provider'
+                         THEN (SELECT COUNT(*) FROM providers WHERE listing_status = 'pending_review')
+                         ELSE 0 END)
+                 + (CASE WHEN $1::text IS NULL OR $1 = 'business'
+                         THEN (SELECT COUNT(*) FROM businesses WHERE listing_status = 'pending_review')
+                         ELSE 0 END) AS "count!""#,
+            entity_type
+        )
+        .fetch_one(pool),
+    )
+}
+
+pub async fn approve_listing(
+    State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path((entity_type, entity_id)): Path<(String, i32)>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    decide_listing(&pool, &ws_conns, admin_id, &entity_type, entity_id, None).await?;
+    Ok((StatusCode::OK, Json(json!({ "message": "Listing approved" }))))
+}
+
+pub async fn reject_listing(
+    State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path((entity_type, entity_id)): Path<(String, i32)>,
+    Json(payload): Json<RejectListingPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::BadRequest("A rejection reason is required".to_string()));
+    }
+    decide_listing(&pool, &ws_conns, admin_id, &entity_type, entity_id, Some(reason)).await?;
+    Ok((StatusCode::OK, Json(json!({ "message": "Listing rejected" }))))
+}
+
+/// Moves a pending listing to approved (`reason` is `None`) or rejected, then
+/// tells the owner. Only the status and review fields change.
+async fn decide_listing(
+    pool: &PgPool,
+    ws_conns: &WsConnections,
+    admin_id: i32,
+    entity_type: &str,
+    entity_id: i32,
+    reason: Option<&str>,
+) -> AppResult<()> {
+    let (table, label) = match entity_type {
+        "provider" => ("providers", "provider profile"),
+        "business" => ("businesses", "business profile"),
+        _ => return Err(AppError::BadRequest("entity_type must be 'provider' or 'business'".to_string())),
+    };
+    let status = if reason.is_some() { "rejected" } else { "approved" };
+
+    let mut tx = pool.begin().await?;
+
+    let current: Option<(i32, String)> = sqlx::query_as(&format!(
+        "SELECT user_id, listing_status FROM {table} WHERE id = $1 FOR UPDATE"
+    ))
+    .bind(entity_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let (owner_id, current_status) =
+        current.ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+    if current_status != "pending_review" {
+        return Err(AppError::Conflict(format!("Listing is already {}", current_status)));
+    }
+
+    sqlx::query(&format!(
+        r#"UPDATE {table}
+           SET listing_status = $1, listing_rejection_reason = $2,
+               listing_reviewed_at = NOW(), listing_reviewed_by = $3
+           WHERE id = $4"#
+    ))
+    .bind(status)
+    .bind(reason)
+    .bind(admin_id)
+    .bind(entity_id)
+    .execute(&mut *tx)
+    .await?;
+
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        if reason.is_some() { "listing_rejected" } else { "listing_approved" },
+        entity_type,
+        Some(entity_id),
+        json!({ "reason": reason }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    let (title, body) = match reason {
+        None => (
+            "Listing approved".to_string(),
+            format!("Your {} has been approved and is now visible on MtaaLink.", label),
+        ),
+        Some(reason) => (
+            "Listing not approved".to_string(),
+            format!(
+                "Your {} was not approved: {}. Update your details and onboard again to resubmit.",
+                label, reason
+            ),
+        ),
+    };
+    notify_and_push(pool, ws_conns, owner_id, "listing_review", &title, &body, Some(entity_type), Some(entity_id)).await;
+
+    Ok(())
+}
+
+// ── Background jobs ───────────────────────────────────────────────────────────
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct JobRun {
+    pub id: i64,
+    pub job_name: String,
+    pub started_at: chrono::DateTime<Utc>,
+    pub finished_at: Option<chrono::DateTime<Utc>>,
+    pub items_processed: i64,
+    pub error: Option<String>,
+    pub details: Option<serde_json::Value>,
+}
+
+/// Last run of every background job plus the most recent failures. A job is
+/// `stale` once it has gone more than twice its interval without starting a
+/// run (measured from process start if it has never run).
+pub async fn list_jobs(
+    State(pool): State<PgPool>,
+    Extension(triggers): Extension<SharedJobTriggers>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let names: Vec<String> = jobs::JOBS.iter().map(|def| def.name.to_string()).collect();
+
+    let (last_runs, last_successes, recent_failures) = tokio::try_join!(
+        sqlx::query_as!(
+            JobRun,
+            r#"SELECT DISTINCT ON (job_name) id, job_name, started_at, finished_at, items_processed, error, details
+               FROM job_runs
+               WHERE job_name = ANY($1)
+               ORDER BY job_name, started_at DESC"#,
+            &names
+        )
+        .fetch_all(&pool),
+        sqlx::query!(
+            r#"SELECT job_name, MAX(finished_at) AS "finished_at!"
+               FROM job_runs
+               WHERE job_name = ANY($1) AND finished_at IS NOT NULL AND error IS NULL
+               GROUP BY job_name"#,
+            &names
+        )
+        .fetch_all(&pool),
+        recent_job_failures(&pool, 20),
+    )?;
+
+    let now = Utc::now();
+    let jobs: Vec<serde_json::Value> = jobs::JOBS
+        .iter()
+        .map(|def| {
+            let last_run = last_runs.iter().find(|r| r.job_name == def.name);
+            let last_success = last_successes
+                .iter()
+                .find(|r| r.job_name == def.name)
+                .map(|r| r.finished_at);
+            let reference = last_run.map(|r| r.started_at).unwrap_or(triggers.started_at);
+            let stale = (now - reference).to_std().is_ok_and(|idle| idle > def.interval * 2);
+            json!({
+                "name": def.name,
+                "interval_seconds": def.interval.as_secs(),
+                "last_run": last_run,
+                "last_success_at": last_success,
+                "stale": stale,
+            })
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "jobs": jobs, "recent_failures": recent_failures })),
+    ))
+}
+
+/// The latest failed job runs, newest first.
+pub async fn recent_job_failures(pool: &PgPool, limit: i64) -> Result<Vec<JobRun>, sqlx::Error> {
+    sqlx::query_as!(
+        JobRun,
+        r#"SELECT id, job_name, started_at, finished_at, items_processed, error, details
+           FROM job_runs
+           WHERE error IS NOT NULL
+           ORDER BY started_at DESC
+           LIMIT $1"#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Wakes the job's loop on this instance so it runs now instead of waiting
+/// out its interval.
+pub async fn run_job_now(
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    State(pool): State<PgPool>,
+    Extension(triggers): Extension<SharedJobTriggers>,
+    Path(name): Path<String>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let def = jobs::definition(&name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown job '{}'", name)))?;
+
+    if !triggers.trigger(def.name) {
+        return Err(AppError::Conflict(format!("A run of {} is already queued", def.name)));
+    }
+
+    record_audit(&pool, Some(admin_id), "job_run_requested", "job", None, json!({ "job": def.name })).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "message": format!("{} queued", def.name) }))))
+}
+
+// ── Data retention ────────────────────────────────────────────────────────────
+
+/// The retention policies with their current settings, the tables that are
+/// never pruned, and what the last retention run deleted from each table.
+pub async fn get_retention(
+    State(pool): State<PgPool>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let last_run = sqlx::query_as!(
+        JobRun,
+        r#"SELECT id, job_name, started_at, finished_at, items_processed, error, details
+           FROM job_runs
+           WHERE job_name = $1
+           ORDER BY started_at DESC
+           LIMIT 1"#,
+        jobs::RETENTION.name
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    let mut policies = Vec::with_capacity(retention::RETENTION_POLICIES.len());
+    for policy in &retention::RETENTION_POLICIES {
+        let days = settings::int(&pool, policy.setting).await;
+        let last_deleted = last_run
+            .as_ref()
+            .and_then(|run| run.details.as_ref())
+            .and_then(|details| details.get(policy.table))
+            .cloned();
+        policies.push(json!({
+            "table": policy.table,
+            "setting": policy.setting,
+            "days": days,
+            "enabled": days > 0,
+            "age_column": policy.age_column,
+            "condition": policy.condition,
+            "last_deleted": last_deleted,
+        }));
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "policies": policies,
+            "protected_tables": retention::PROTECTED_TABLES,
+            "last_run": last_run,
+        })),
+    ))
+}
+
+// ── Storage garbage collection ────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct StorageGcQuery {
+    pub dry_run: Option<bool>,
+}
+
+/// Reconciles stored files with the database. Only reports what would be
+/// removed unless called with `dry_run=false`.
+pub async fn run_storage_gc(
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    State(pool): State<PgPool>,
+    Extension(storage): Extension<SharedStorage>,
+    Query(params): Query<StorageGcQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let dry_run = params.dry_run.unwrap_or(true);
+    let report = storage_gc::collect_garbage(&pool, &storage, dry_run).await?;
+
+    if !dry_run {
+        record_audit(
+            &pool,
+            Some(admin_id),
+            "storage_gc",
+            "storage",
+            None,
+            json!({
+                "deleted": report.deleted,
+                "orphan_bytes": report.orphan_bytes,
+                "missing_count": report.missing_count,
+            }),
+        )
+        .await?;
+    }
+
+    Ok((StatusCode::OK, Json(json!({ "report": report }))))
+}
+
+// ── Platform settings ─────────────────────────────────────────────────────────
+
+#[derive(Serialize, Debug)]
+pub struct SettingView {
+    pub key: &'static str,
+    pub value: i64,
+    pub default: i64,
+    pub min: i64,
+    pub max: i64,
+    pub description: &'static str,
+    pub updated_by: Option<i32>,
+    pub updated_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// Every known setting with its effective value; unset keys show the default.
+pub async fn get_settings(
+    State(pool): State<PgPool>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let stored = sqlx::query!("SELECT key, value, updated_by, updated_at FROM platform_settings")
+        .fetch_all(&pool)
+        .await?;
+
+    let settings: Vec<SettingView> = settings::KNOWN_SETTINGS
+        .iter()
+        .map(|def| {
+            let row = stored.iter().find(|r| r.key == def.key);
+            SettingView {
+                key: def.key,
+                value: row.and_then(|r| r.value.as_i64()).unwrap_or(def.default),
+                default: def.default,
+                min: def.min,
+                max: def.max,
+                description: def.description,
+                updated_by: row.and_then(|r| r.updated_by),
+                updated_at: row.map(|r| r.updated_at),
+            }
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "settings": settings }))))
+}
+
+/// Updates one or more settings, given as `{ "key": value, ... }`. The whole
+/// batch is rejected if any key is unknown or out of bounds.
+pub async fn update_settings(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Json(payload): Json<serde_json::Map<String, serde_json::Value>>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if payload.is_empty() {
+        return Err(AppError::BadRequest("No settings given".to_string()));
+    }
+
+    let mut updates = Vec::with_capacity(payload.len());
+    for (key, value) in &payload {
+        let def = settings::definition(key)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown setting '{key}'")))?;
+        let value = def.validate(value).map_err(AppError::BadRequest)?;
+        updates.push((def.key, value));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    for (key, value) in &updates {
+        let previous = sqlx::query_scalar!("SELECT value FROM platform_settings WHERE key = $1", key)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO platform_settings (key, value, updated_by, updated_at)
+               VALUES ($1, $2, $3, NOW())
+               ON CONFLICT (key) DO UPDATE
+               SET value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = NOW()"#,
+            key,
+            json!(value),
+            admin_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        record_audit(
+            &mut *tx,
+            Some(admin_id),
+            "setting_updated",
+            "setting",
+            None,
+            json!({ "key": key, "from": previous, "to": value }),
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    settings::invalidate();
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "message": "Settings updated",
+            "updated": updates.iter().map(|(k, v)| (k.to_string(), json!(v))).collect::<serde_json::Map<_, _>>(),
+        })),
+    ))
+}
+
+// ── Maintenance mode ──────────────────────────────────────────────────────────
+
+fn maintenance_view(read_only: bool, maintenance: &Maintenance) -> serde_json::Value {
+    json!({
+        "read_only": read_only,
+        "source": maintenance.source().as_str(),
+        "retry_after_seconds": maintenance::RETRY_AFTER_SECONDS,
+    })
+}
+
+/// Whether the API is refusing writes, and whether the environment or the
+/// setting decided it.
+pub async fn get_maintenance(
+    Extension(maintenance): Extension<SharedMaintenance>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let read_only = maintenance.read_only().await;
+    Ok((StatusCode::OK, Json(maintenance_view(read_only, &maintenance))))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+}
+
+/// Turns read-only mode on or off. Takes effect here at once and on other
+/// instances within a few seconds. Refused while `MAINTENANCE_MODE` pins it.
+pub async fn set_maintenance(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Extension(maintenance): Extension<SharedMaintenance>,
+    Json(payload): Json<MaintenanceRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if maintenance.source() == maintenance::Source::Environment {
+        return Err(AppError::Conflict(
+            "Maintenance mode is set by MAINTENANCE_MODE in the environment".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        r#"INSERT INTO platform_settings (key, value, updated_by, updated_at)
+           VALUES ($1, $2, $3, NOW())
+           ON CONFLICT (key) DO UPDATE
+           SET value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = NOW()"#,
+        settings::MAINTENANCE_READ_ONLY,
+        json!(i64::from(payload.enabled)),
+        admin_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "maintenance_toggled",
+        "setting",
+        None,
+        json!({ "read_only": payload.enabled }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    settings::invalidate();
+    maintenance.remember(payload.enabled);
+
+    Ok((StatusCode::OK, Json(maintenance_view(payload.enabled, &maintenance))))
+}
+
+// ── Feature flags ─────────────────────────────────────────────────────────────
+
+#[derive(Serialize, Debug)]
+pub struct FeatureFlagView {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Value for this environment when no override is set.
+    pub default: bool,
+    /// `None` until an admin sets an override.
+    pub enabled: Option<bool>,
+    pub rollout_percent: Option<i16>,
+    pub roles: Option<Vec<String>>,
+    pub updated_by: Option<i32>,
+    pub updated_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FeatureFlagUpdate {
+    pub enabled: bool,
+    /// Share of signed-in users who get the feature; defaults to 100.
+    pub rollout_percent: Option<i16>,
+    /// Roles the feature is limited to; empty or omitted means all.
+    pub roles: Option<Vec<String>>,
+}
+
+/// Every known flag with its environment default and any override.
+pub async fn list_feature_flags(
+    State(pool): State<PgPool>,
+    Extension(feature_flags): Extension<SharedFeatureFlags>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let stored = sqlx::query!(
+        "SELECT name, enabled, rollout_percent, roles, updated_by, updated_at FROM feature_flags"
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let flags: Vec<FeatureFlagView> = flags::KNOWN_FLAGS
+        .iter()
+        .map(|def| {
+            let row = stored.iter().find(|r| r.name == def.name);
+            FeatureFlagView {
+                name: def.name,
+                description: def.description,
+                default: feature_flags.default_for(def.name),
+                enabled: row.map(|r| r.enabled),
+                rollout_percent: row.map(|r| r.rollout_percent),
+                roles: row.map(|r| r.roles.clone()),
+                updated_by: row.and_then(|r| r.updated_by),
+                updated_at: row.map(|r| r.updated_at),
+            }
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "feature_flags": flags }))))
+}
+
+/// Sets the override for a flag. Takes effect on this instance at once and
+/// on the others within 30s.
+pub async fn update_feature_flag(
+    State(pool): State<PgPool>,
+    Extension(feature_flags): Extension<SharedFeatureFlags>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path(name): Path<String>,
+    Json(payload): Json<FeatureFlagUpdate>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let def = flags::definition(&name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown feature flag '{name}'")))?;
+    let rollout_percent = payload.rollout_percent.unwrap_or(100);
+    if !(0..=100).contains(&rollout_percent) {
+        return Err(AppError::BadRequest("rollout_percent must be between 0 and 100".to_string()));
+    }
+    let mut roles = payload.roles.unwrap_or_default();
+    if let Some(role) = roles.iter().find(|r| !flags::ROLES.contains(&r.as_str())) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown role '{role}'. Use one of: {}",
+            flags::ROLES.join(", ")
+        )));
+    }
+    roles.sort();
+    roles.dedup();
+
+    let mut tx = pool.begin().await?;
+
+    let previous = sqlx::query!(
+        "SELECT enabled, rollout_percent, roles FROM feature_flags WHERE name = $1",
+        def.name
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .map(|r| json!({ "enabled": r.enabled, "rollout_percent": r.rollout_percent, "roles": r.roles }));
+
+    sqlx::query!(
+        r#"INSERT INTO feature_flags (name, enabled, rollout_percent, roles, updated_by, updated_at)
+           VALUES ($1, $2, $3, $4, $5, NOW())
+           ON CONFLICT (name) DO UPDATE
+           SET enabled = EXCLUDED.enabled, rollout_percent = EXCLUDED.rollout_percent,
+               roles = EXCLUDED.roles, updated_by = EXCLUDED.updated_by, updated_at = NOW()"#,
+        def.name,
+        payload.enabled,
+        rollout_percent,
+        &roles,
+        admin_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let current = json!({ "enabled": payload.enabled, "rollout_percent": rollout_percent, "roles": roles });
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "feature_flag_updated",
+        "feature_flag",
+        None,
+        json!({ "name": def.name, "from": previous, "to": current }),
+    )
+    .await?;
+
+    tx.commit().await?;
+    feature_flags.invalidate();
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Feature flag updated", "name": def.name, "flag": current })),
+    ))
+}
+
+/// Removes the override, returning the flag to its environment default.
+pub async fn reset_feature_flag(
+    State(pool): State<PgPool>,
+    Extension(feature_flags): Extension<SharedFeatureFlags>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path(name): Path<String>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let def = flags::definition(&name)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown feature flag '{name}'")))?;
+
+    let mut tx = pool.begin().await?;
+    let removed = sqlx::query!("DELETE FROM feature_flags WHERE name = $1", def.name)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if removed > 0 {
+        record_audit(
+            &mut *tx,
+            Some(admin_id),
+            "feature_flag_reset",
+            "feature_flag",
+            None,
+            json!({ "name": def.name }),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    feature_flags.invalidate();
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "message": "Feature flag reset to its default",
+            "name": def.name,
+            "enabled": feature_flags.default_for(def.name),
+        })),
+    ))
+}
+
+// ── Seeding ───────────────────────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SeedPayload {
+    /// Seed sets to load; defaults to all of them.
+    pub sets: Option<Vec<String>>,
+    /// Load into tables that already have rows, inserting only what's missing.
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Loads the compiled-in reference data (locations, category tree) in one
+/// transaction. Refuses to touch non-empty tables unless `force` is set.
+pub async fn run_seed(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    payload: Option<Json<SeedPayload>>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let sets: Vec<String> = match payload.sets {
+        Some(sets) => sets.into_iter().map(|s| s.to_lowercase()).collect(),
+        None => seed::SEED_SETS.iter().map(|s| s.to_string()).collect(),
+    };
+    if let Some(unknown) = sets.iter().find(|s| !seed::SEED_SETS.contains(&s.as_str())) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown seed set '{unknown}'. Available: {}",
+            seed::SEED_SETS.join(", ")
+        )));
+    }
+
+    if !payload.force {
+        for set in &sets {
+            if seed::set_has_rows(&pool, set).await? {
+                return Err(AppError::Conflict(format!(
+                    "Tables for '{set}' already contain data. Pass \"force\": true to add missing rows."
+                )));
+            }
+        }
+    }
+
+    let mut counts = seed::SeedCounts::default();
+    let mut tx = pool.begin().await?;
+    for set in &sets {
+        match set.as_str() {
+            "locations" => seed::seed_locations(&mut tx, &mut counts).await?,
+            _ => seed::seed_categories(&mut tx, &mut counts).await?,
+        }
+    }
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "seed_applied",
+        "seed",
+        None,
+        json!({ "sets": sets, "force": payload.force, "inserted": counts }),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Seed data loaded", "sets": sets, "inserted": counts })),
+    ))
+}
+
+pub async fn seed_status(
+    State(pool): State<PgPool>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let sets = seed::seed_status(&pool).await?;
+    Ok((StatusCode::OK, Json(json!({ "sets": sets }))))
+}
+
+// ── Data export ───────────────────────────────────────────────────────────────
+
+/// Rows are flushed to the client in chunks of roughly this many bytes.
+const EXPORT_CHUNK_BYTES: usize = 32 * 1024;
+
+struct ExportDataset {
+    name: &'static str,
+    /// Column order for CSV output; also the keys of each JSON line.
+    columns: &'static [&'static str],
+    /// Selects exactly `columns`. `$1`/`$2` are the optional from/to dates,
+    /// applied to `created_at`.
+    sql: &'static str,
+}
+
+const EXPORT_DATASETS: [ExportDataset; 4] = [
+    ExportDataset {
+        name: "users",
+        columns: &[
+            "id", "username", "email", "role", "created_at",
+            "last_login_at", "last_seen_at", "suspended",
+        ],
+        sql: r#"SELECT u.id, u.username, u.email,
+                       CASE
+                         WHEN c.id IS NOT NULL THEN 'client'
+                         WHEN p.id IS NOT NULL THEN 'provider'
+                         WHEN b.id IS NOT NULL THEN 'business'
+                         ELSE 'unknown'
+                       END AS role,
+                       u.created_at, u.last_login_at, u.last_seen_at,
+                       user_is_suspended(u.id) AS suspended
+                FROM users u
+                LEFT JOIN clients    c ON c.user_id = u.id
+                LEFT JOIN providers  p ON p.user_id = u.id
+                LEFT JOIN businesses b ON b.user_id = u.id
+                WHERE ($1::date IS NULL OR u.created_at >= $1)
+                  AND ($2::date IS NULL OR u.created_at < $2 + 1)
+                ORDER BY u.id"#,
+    },
+    ExportDataset {
+        name: "bookings",
+        columns: &[
+            "id", "client_id", "target_type", "target_id", "service_id",
+            "service_description", "status", "scheduled_time", "created_at", "updated_at",
+        ],
+        sql: r#"SELECT id, client_id, target_type, target_id, service_id,
+                       service_description, status, scheduled_time, created_at, updated_at
+                FROM bookings
+                WHERE ($1::date IS NULL OR created_at >= $1)
+                  AND ($2::date IS NULL OR created_at < $2 + 1)
+                ORDER BY id"#,
+    },
+    ExportDataset {
+        name: "reviews",
+        columns: &[
+            "id", "reviewer_id", "target_type", "target_id", "rating",
+            "comment", "verified", "hidden", "created_at",
+        ],
+        sql: r#"SELECT id, reviewer_id, target_type, target_id, rating, comment,
+                       verified_booking_id IS NOT NULL AS verified, hidden, created_at
+                FROM reviews
+                WHERE ($1::date IS NULL OR created_at >= $1)
+                  AND ($2::date IS NULL OR created_at < $2 + 1)
+                ORDER BY id"#,
+    },
+    ExportDataset {
+        name: "providers",
+        columns: &[
+            "id", "user_id", "service_name", "category", "location", "email",
+            "phone_number", "approved", "onboarding_completed", "suspended_until", "created_at",
+        ],
+        sql: r#"SELECT id, user_id, service_name, category, location, email,
+                       phone_number, approved, onboarding_completed, suspended_until, created_at
+                FROM providers
+                WHERE ($1::date IS NULL OR created_at >= $1)
+                  AND ($2::date IS NULL OR created_at < $2 + 1)
+                ORDER BY id"#,
+    },
+];
+
+#[derive(Deserialize, Debug)]
+pub struct ExportQuery {
+    pub dataset: String,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// `csv` (default) or `json_lines`.
+    pub format: Option<String>,
+}
+
+/// One CSV field. Quotes when needed and defuses values a spreadsheet would
+/// otherwise evaluate as a formula.
+fn csv_field(value: &serde_json::Value) -> String {
+    let text = match value {
+        serde_json::Value::Null => return String::new(),
+        serde_json::Value::String(s) if s.starts_with(['=', '+', '-', '@']) => format!("'{s}"),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Streams a dataset as CSV or JSON lines. Rows are read with a cursor and
+/// written out in chunks, so memory use doesn't grow with the export size.
+pub async fn export_data(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Query(params): Query<ExportQuery>,
+) -> AppResult<Response> {
+    let dataset = EXPORT_DATASETS
+        .iter()
+        .find(|d| d.name == params.dataset.to_lowercase())
+        .ok_or_else(|| {
+            AppError::BadRequest(
+                "dataset must be 'users', 'bookings', 'reviews' or 'providers'".to_string(),
+            )
+        })?;
+    let json_lines = match params.format.as_deref().unwrap_or("csv") {
+        "csv" => false,
+        "json_lines" => true,
+        _ => {
+            return Err(AppError::BadRequest(
+                "format must be 'csv' or 'json_lines'".to_string(),
+            ))
+        }
+    };
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from > to {
+            return Err(AppError::BadRequest("'from' must not be after 'to'".to_string()));
+        }
+    }
+
+    // The count and the rows come from one snapshot, so rows written in
+    // between can neither slip past the cap nor make the count wrong
+    let mut snapshot = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *snapshot)
+        .await?;
+    let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM ({}) t", dataset.sql))
+        .bind(params.from)
+        .bind(params.to)
+        .fetch_one(&mut *snapshot)
+        .await?;
+    // Larger exports are refused; narrow the date range instead
+    let row_cap = settings::int(&pool, "admin.export_row_cap").await;
+    if rows > row_cap {
+        return Err(AppError::BadRequest(format!(
+            "Export would contain {rows} rows, above the limit of {row_cap}. Narrow the date range with 'from' and 'to'."
+        )));
+    }
+
+    record_audit(
+        &pool,
+        Some(admin_id),
+        "data_exported",
+        dataset.name,
+        None,
+        json!({
+            "format": if json_lines { "json_lines" } else { "csv" },
+            "from": params.from,
+            "to": params.to,
+            "rows": rows,
+        }),
+    )
+    .await?;
+
+    let sql = format!("SELECT row_to_json(t)::text FROM ({}) t LIMIT $3", dataset.sql);
+    let columns = dataset.columns;
+    let (from, to) = (params.from, params.to);
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<String, std::io::Error>>(8);
+
+    tokio::spawn(async move {
+        let mut buf = String::new();
+        if !json_lines {
+            buf.push_str(&columns.join(","));
+            buf.push('\n');
+        }
+
+        let mut cursor = sqlx::query_scalar::<_, String>(&sql)
+            .bind(from)
+            .bind(to)
+            .bind(row_cap)
+            .fetch(&mut *snapshot);
+        let mut first = true;
+        while let Some(row) = cursor.next().await {
+            let row = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    tracing::error!("Export query failed: {}", e);
+                    let _ = tx.send(Err(std::io::Error::other(e))).await;
+                    return;
+                }
+            };
+            if json_lines {
+                buf.push_str(&row);
+            } else {
+                let record: serde_json::Value = serde_json::from_str(&row).unwrap_or_default();
+                let fields: Vec<String> = columns.iter().map(|c| csv_field(&record[*c])).collect();
+                buf.push_str(&fields.join(","));
+            }
+            buf.push('\n');
+
+            // The first row goes out at once: it is what the handler waits on
+            // before committing to a 200
+            let full = first || buf.len() >= EXPORT_CHUNK_BYTES;
+            first = false;
+            if full && tx.send(Ok(std::mem::take(&mut buf))).await.is_err() {
+                // Client went away
+                return;
+            }
+        }
+        if !buf.is_empty() || first {
+            let _ = tx.send(Ok(buf)).await;
+        }
+    });
+
+    // A query that fails outright is still a 500. A failure after the first
+    // row aborts the body instead of ending it, so a client can't take a cut
+    // short export for a complete one; X-Export-Rows gives the full count.
+    let first_chunk = match rx.recv().await {
+        Some(Ok(chunk)) => chunk,
+        _ => return Err(AppError::Internal("Export failed".to_string())),
+    };
+    let rest = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    let body = Body::from_stream(futures_util::stream::once(async { Ok(first_chunk) }).chain(rest));
+    let (content_type, extension) = if json_lines {
+        ("application/x-ndjson", "jsonl")
+    } else {
+        ("text/csv; charset=utf-8", "csv")
+    };
+    let file_name = format!("{}-{}.{extension}", dataset.name, Utc::now().format("%Y%m%d"));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file_name}\"")),
+            (HeaderName::from_static("x-export-rows"), rows.to_string()),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+// ── Content moderation ────────────────────────────────────────────────────────
+
+/// Everything that can be reported into `content_flags`.
+const FLAG_TARGET_TYPES: [&str; 5] = ["review", "post", "message", "provider", "business"];
+
+/// User who owns the flagged item, or `None` if the item no longer exists.
+async fn flag_target_owner(pool: &PgPool, target_type: &str, target_id: i32) -> AppResult<Option<i32>> {
+    let owner = match target_type {
+        "review" => sqlx::query_scalar!("SELECT reviewer_id FROM reviews WHERE id = $1", target_id)
+            .fetch_optional(pool)
+            .await?,
+        "post" => sqlx::query_scalar!(
+            r#"SELECT COALESCE(pr.user_id, b.user_id) AS "user_id!"
+               FROM posts p
+               LEFT JOIN providers  pr ON pr.id = p.provider_id
+               LEFT JOIN businesses b  ON b.id  = p.business_id
+               WHERE p.id = $1"#,
+            target_id
+        )
+        .fetch_optional(pool)
+        .await?,
+        "message" => sqlx::query_scalar!("SELECT sender_id FROM messages WHERE id = $1", target_id)
+            .fetch_optional(pool)
+            .await?,
+        "provider" => sqlx::query_scalar!("SELECT user_id FROM providers WHERE id = $1", target_id)
+            .fetch_optional(pool)
+            .await?,
+        _ => sqlx::query_scalar!("SELECT user_id FROM businesses WHERE id = $1", target_id)
+            .fetch_optional(pool)
+            .await?,
+    };
+    Ok(owner)
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct FlagContentPayload {
+    pub target_type: String,
+    pub target_id: i32,
+    pub reason: String,
+    pub detail: Option<String>,
+}
+
+/// Admin-raised flag. Lands in the same queue as user reports, with no reporter.
+pub async fn flag_content(
+    State(pool): State<PgPool>,
+    Json(payload): Json<FlagContentPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let target_type = payload.target_type.to_lowercase();
+    if !FLAG_TARGET_TYPES.contains(&target_type.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "target_type must be one of: {}",
+            FLAG_TARGET_TYPES.join(", ")
+        )));
+    }
+    if payload.reason.trim().is_empty() {
+        return Err(AppError::BadRequest("Reason cannot be empty".to_string()));
+    }
+    if payload.target_id <= 0 {
+        return Err(AppError::BadRequest("Invalid target ID".to_string()));
+    }
+
+    let owner_id = flag_target_owner(&pool, &target_type, payload.target_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("{target_type} not found")))?;
+    let detail = payload.detail.as_deref().map(str::trim).filter(|d| !d.is_empty());
+
+    let record = sqlx::query!(
+        r#"INSERT INTO content_flags (target_type, target_id, reason, detail, target_owner_id)
+           VALUES ($1, $2, $3, $4, $5)
+           RETURNING id"#,
+        target_type,
+        payload.target_id,
+        payload.reason.trim(),
+        detail,
+        owner_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "message": "Content flagged successfully", "flag_id": record.id })),
+    ))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FlagListQuery {
+    /// `open` (default), `resolved` or `all`.
+    pub status: Option<String>,
+    pub target_type: Option<String>,
+    pub reason: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FlagRow {
+    pub id: i32,
+    pub target_type: String,
+    pub target_id: i32,
+    pub reason: String,
+    pub detail: Option<String>,
+    pub status: String,
+    pub resolution: Option<String>,
+    pub reporter_id: Option<i32>,
+    pub reporter_username: Option<String>,
+    pub target_owner_id: Option<i32>,
+    pub open_reports_on_target: i64,
+    pub resolved_by: Option<i32>,
+    pub resolved_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// The moderation queue: every flag, newest first.
+pub async fn list_flags(
+    State(pool): State<PgPool>,
+    Query(params): Query<FlagListQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let status = match params.status.as_deref().unwrap_or("open") {
+        "all" => None,
+        s @ ("open" | "resolved") => Some(s.to_string()),
+        _ => {
+            return Err(AppError::BadRequest(
+                "status must be 'open', 'resolved' or 'all'".to_string(),
+            ))
+        }
+    };
+    let target_type = params.target_type.map(|t| t.to_lowercase());
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * per_page;
+
+    let (flags, total) = flag_page(&pool, status, target_type, params.reason, per_page, offset).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "flags": flags,
+            "total": total,
+            "page": page,
+            "per_page": per_page,
+        })),
+    ))
+}
+
+/// A page of flags matching the filters, newest first, and the total count.
+pub async fn flag_page(
+    pool: &PgPool,
+    status: Option<String>,
+    target_type: Option<String>,
+    reason: Option<String>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<FlagRow>, i64), sqlx::Error> {
+    tokio::try_join!(
+        sqlx::query_as!(
+            FlagRow,
+            r#"SELECT cf.id, cf.target_type, cf.target_id, cf.reason, cf.detail,
+                      cf.status AS "status!", cf.resolution,
+                      cf.flagged_by AS reporter_id,
+                      u.username AS "reporter_username?",
+                      cf.target_owner_id,
+                      (SELECT COUNT(*) FROM content_flags o
+                       WHERE o.target_type = cf.target_type
+                         AND o.target_id = cf.target_id
+                         AND o.resolved = FALSE) AS "open_reports_on_target!",
+                      cf.resolved_by, cf.resolved_at, cf.created_at
+               FROM content_flags cf
+               LEFT JOIN users u ON u.id = cf.flagged_by
+               WHERE ($1::text IS NULL OR cf.status = $1)
+                 AND ($2::text IS NULL OR cf.target_type = $2)
+                 AND ($3::text IS NULL OR cf.reason = $3)
+               ORDER BY cf.created_at DESC, cf.id DESC
+               LIMIT $4 OFFSET $5"#,
+            status,
+            target_type,
+            reason,
+            limit,
+            offset
+        )
+        .fetch_all(pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM content_flags cf
+               WHERE ($1::text IS NULL OR cf.status = $1)
+                 AND ($2::text IS NULL OR cf.target_type = $2)
+                 AND ($3::text IS NULL OR cf.reason = $3)"#,
+            status,
+            target_type,
+            reason
+        )
+        .fetch_one(pool),
+    )
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ResolveContentFlagPayload {
+    /// `dismiss`, `hide`, `delete` or `suspend_owner`.
+    pub action: String,
+    pub note: Option<String>,
+    /// Suspension length for `suspend_owner`; 0 or absent suspends indefinitely.
+    pub days: Option<i64>,
+}
+
+/// Resolves every open flag on the flagged item in one go and applies the
+/// action to the underlying record. Runs in a single transaction together
+/// with the audit_log entry.
+pub async fn resolve_content_flag(
+    State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path(flag_id): Path<i32>,
+    Json(payload): Json<ResolveContentFlagPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let resolution = match payload.action.to_lowercase().as_str() {
+        "dismiss" => "dismissed",
+        "hide" => "hidden",
+        "delete" => "deleted",
+        "suspend_owner" => "owner_suspended",
+        _ => {
+            return Err(AppError::BadRequest(
+                "Action must be 'dismiss', 'hide', 'delete' or 'suspend_owner'".to_string(),
+            ))
+        }
+    };
+    let note = payload.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+
+    let flag = sqlx::query!(
+        "SELECT target_type, target_id, resolved FROM content_flags WHERE id = $1",
+        flag_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Flag not found".to_string()))?;
+
+    if flag.resolved {
+        return Err(AppError::Conflict("Flag has already been resolved".to_string()));
+    }
+
+    let target_type = flag.target_type.as_str();
+    let target_id = flag.target_id;
+    match (target_type, resolution) {
+        ("message", "hidden") => {
+            return Err(AppError::BadRequest(
+                "Messages cannot be hidden — delete them instead".to_string(),
+            ))
+        }
+        ("provider" | "business", "hidden" | "deleted") => {
+            return Err(AppError::BadRequest(
+                "Profiles can only be dismissed or have their owner suspended".to_string(),
+            ))
+        }
+        _ => {}
+    }
+
+    let owner_id = flag_target_owner(&pool, target_type, target_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let resolved = sqlx::query!(
+        r#"UPDATE content_flags
+           SET resolved = TRUE, resolution = $1, resolved_by = $2, resolved_at = NOW(),
+               target_owner_id = COALESCE(target_owner_id, $5)
+           WHERE target_type = $3 AND target_id = $4 AND resolved = FALSE"#,
+        resolution,
+        admin_id,
+        target_type,
+        target_id,
+        owner_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    match (resolution, target_type) {
+        // A dismissed report clears a pending appeal on the post as well
+        ("dismissed", "post") => {
+            sqlx::query!(
+                r#"UPDATE posts SET hidden = FALSE, hidden_reason = NULL, appeal_note = NULL
+                   WHERE id = $1 AND appeal_note IS NOT NULL"#,
+                target_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        ("hidden", "post") => {
+            sqlx::query!(
+                "UPDATE posts SET hidden = TRUE, hidden_reason = $1, appeal_note = NULL WHERE id = $2",
+                note,
+                target_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        ("hidden", "review") => {
+            sqlx::query!("UPDATE reviews SET hidden = TRUE WHERE id = $1", target_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ("deleted", "post") => {
+            sqlx::query!("DELETE FROM posts WHERE id = $1", target_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ("deleted", "review") => {
+            sqlx::query!("DELETE FROM reviews WHERE id = $1", target_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ("deleted", "message") => {
+            sqlx::query!("DELETE FROM messages WHERE id = $1", target_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ("owner_suspended", _) => {
+            let owner = owner_id.ok_or_else(|| {
+                AppError::BadRequest("The flagged item no longer exists".to_string())
+            })?;
+            let days = payload.days.unwrap_or(0).max(0);
+            let suspended_until = (days > 0).then(|| Utc::now() + Duration::days(days));
+            let reason = note.map(str::to_string).unwrap_or_else(|| {
+                format!("Upheld report on your {target_type}.")
+            });
+            sqlx::query!(
+                r#"UPDATE users
+                   SET suspended_at = NOW(), suspended_until = $1, suspension_reason = $2
+                   WHERE id = $3"#,
+                suspended_until,
+                reason,
+                owner
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        _ => {}
     }
 
-    let record = sqlx::query!(
-        "INSERT INTO content_flags (target_type, target_id, reason) VALUES ($1, $2, $3) RETURNING id",
-        payload.target_type,
-        payload.target_id,
-        payload.reason.trim()
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "flag_resolved",
+        target_type,
+        Some(target_id),
+        json!({
+            "flag_id": flag_id,
+            "resolution": resolution,
+            "note": note,
+            "owner_id": owner_id,
+            "flags_resolved": resolved.rows_affected(),
+        }),
     )
-    .fetch_one(&pool)
     .await?;
 
+    tx.commit().await?;
+
+    if let Some(owner) = owner_id {
+        let reason = note.unwrap_or("It breaks our community guidelines.");
+        match resolution {
+            "hidden" => {
+                notify_and_push(
+                    &pool, &ws_conns, owner, "content_hidden", "Content hidden",
+                    &format!("Your {target_type} was hidden by our moderators. Reason: {reason}"),
+                    Some(target_type), Some(target_id),
+                ).await;
+            }
+            "deleted" => {
+                notify_and_push(
+                    &pool, &ws_conns, owner, "content_removed", "Content removed",
+                    &format!("Your {target_type} was removed by our moderators. Reason: {reason}"),
+                    None, None,
+                ).await;
+            }
+            "owner_suspended" => {
+                notify_and_push(
+                    &pool, &ws_conns, owner, "account_suspended", "Account suspended",
+                    &format!("Your account has been suspended following a report on your {target_type}. Reason: {reason}"),
+                    None, None,
+                ).await;
+            }
+            _ => {}
+        }
+    }
+
     Ok((
-        StatusCode::CREATED,
-        Json(json!({ "message": "Content flagged successfully", "flag_id": record.id })),
+        StatusCode::OK,
+        Json(json!({
+            "message": format!("Flag {resolution}"),
+            "flags_resolved": resolved.rows_affected(),
+        })),
     ))
 }
 
@@ -295,21 +2713,41 @@ pub struct ResolveFlagPayload {
     pub review_id: i32,
 }
 
+/// Dismisses all open flags on a review. Kept for the review moderation page;
+/// new callers should use `/flags/:id/resolve`.
 pub async fn resolve_flag(
     State(pool): State<PgPool>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
     Json(payload): Json<ResolveFlagPayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let mut tx = pool.begin().await?;
+
     let updated = sqlx::query!(
-        "UPDATE content_flags SET resolved = TRUE WHERE target_type = 'review' AND target_id = $1 AND resolved = FALSE",
-        payload.review_id
+        r#"UPDATE content_flags
+           SET resolved = TRUE, resolution = 'dismissed', resolved_by = $2, resolved_at = NOW()
+           WHERE target_type = 'review' AND target_id = $1 AND resolved = FALSE"#,
+        payload.review_id,
+        admin_id
     )
-    .execute(&pool)
+    .execute(&mut *tx)
     .await?;
 
     if updated.rows_affected() == 0 {
         return Err(AppError::NotFound("No active flags found for this review".to_string()));
     }
 
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "flag_resolved",
+        "review",
+        Some(payload.review_id),
+        json!({ "resolution": "dismissed", "flags_resolved": updated.rows_affected() }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok((StatusCode::OK, Json(json!({ "message": "All flags on review resolved" }))))
 }
 
@@ -350,6 +2788,187 @@ pub async fn moderate_reviews(
     Ok((StatusCode::OK, Json(json!({ "flagged_reviews": reviews }))))
 }
 
+// ── Post reports ──────────────────────────────────────────────────────────────
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct PostReportRow {
+    pub post_id: i32,
+    pub title: Option<String>,
+    pub content: String,
+    pub hidden: bool,
+    pub hidden_reason: Option<String>,
+    pub appeal_note: Option<String>,
+    pub author_user_id: Option<i32>,
+    pub author_username: Option<String>,
+    pub report_count: i64,
+    pub reasons: Vec<String>,
+    pub details: Vec<String>,
+    pub last_reported_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Posts with open reports, plus hidden posts whose author has appealed.
+pub async fn list_post_reports(
+    State(pool): State<PgPool>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let reports = sqlx::query_as!(
+        PostReportRow,
+        r#"SELECT
+               p.id AS post_id,
+               p.title,
+               p.content,
+               p.hidden,
+               p.hidden_reason,
+               p.appeal_note,
+               COALESCE(pr.user_id, b.user_id)                                   AS author_user_id,
+               u.username                                                        AS "author_username?",
+               COUNT(cf.id)                                                      AS "report_count!",
+               COALESCE(array_agg(DISTINCT cf.reason) FILTER (WHERE cf.id IS NOT NULL), '{}') AS "reasons!",
+               COALESCE(array_agg(cf.detail) FILTER (WHERE cf.detail IS NOT NULL), '{}')      AS "details!",
+               MAX(cf.created_at)                                                AS last_reported_at
+           FROM posts p
+           LEFT JOIN providers  pr ON pr.id = p.provider_id
+           LEFT JOIN businesses b  ON b.id  = p.business_id
+           LEFT JOIN users      u  ON u.id  = COALESCE(pr.user_id, b.user_id)
+           LEFT JOIN content_flags cf
+               ON cf.target_type = 'post' AND cf.target_id = p.id AND cf.resolved = FALSE
+           GROUP BY p.id, pr.user_id, b.user_id, u.username
+           HAVING COUNT(cf.id) > 0 OR (p.hidden AND p.appeal_note IS NOT NULL)
+           ORDER BY COUNT(cf.id) DESC, MAX(cf.created_at) DESC NULLS LAST"#
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "post_reports": reports }))))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ResolvePostReportsPayload {
+    /// One of `dismiss`, `hide`, `delete`.
+    pub action: String,
+    pub note: Option<String>,
+}
+
+/// Resolves every open report on a post in one go.
+/// `dismiss` also grants a pending appeal, `hide` takes the post off public
+/// feeds, and `delete` removes it entirely.
+pub async fn resolve_post_reports(
+    State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path(post_id): Path<i32>,
+    Json(payload): Json<ResolvePostReportsPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let resolution = match payload.action.to_lowercase().as_str() {
+        "dismiss" => "dismissed",
+        "hide" => "hidden",
+        "delete" => "deleted",
+        _ => {
+            return Err(AppError::BadRequest(
+                "Action must be 'dismiss', 'hide' or 'delete'".to_string(),
+            ))
+        }
+    };
+    let note = payload.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+
+    let post = sqlx::query!(
+        r#"SELECT p.title, COALESCE(pr.user_id, b.user_id) AS author_user_id
+           FROM posts p
+           LEFT JOIN providers  pr ON pr.id = p.provider_id
+           LEFT JOIN businesses b  ON b.id  = p.business_id
+           WHERE p.id = $1"#,
+        post_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    let mut tx = pool.begin().await?;
+
+    let resolved = sqlx::query!(
+        r#"UPDATE content_flags
+           SET resolved = TRUE, resolution = $1, resolved_by = $2, resolved_at = NOW()
+           WHERE target_type = 'post' AND target_id = $3 AND resolved = FALSE"#,
+        resolution,
+        admin_id,
+        post_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    match resolution {
+        "dismissed" => {
+            sqlx::query!(
+                r#"UPDATE posts SET hidden = FALSE, hidden_reason = NULL, appeal_note = NULL
+                   WHERE id = $1 AND appeal_note IS NOT NULL"#,
+                post_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        "hidden" => {
+            sqlx::query!(
+                "UPDATE posts SET hidden = TRUE, hidden_reason = $1, appeal_note = NULL WHERE id = $2",
+                note,
+                post_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        _ => {
+            sqlx::query!("DELETE FROM posts WHERE id = $1", post_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "flag_resolved",
+        "post",
+        Some(post_id),
+        json!({
+            "resolution": resolution,
+            "note": note,
+            "owner_id": post.author_user_id,
+            "flags_resolved": resolved.rows_affected(),
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    if let Some(author_id) = post.author_user_id {
+        let title = post.title.as_deref().unwrap_or("your post");
+        let reason = note.unwrap_or("It breaks our community guidelines.");
+        match resolution {
+            "hidden" => {
+                notify_and_push(
+                    &pool, &ws_conns, author_id, "post_hidden", "Post hidden",
+                    &format!("\"{title}\" was hidden by our moderators. Reason: {reason} You can appeal from the post page."),
+                    Some("post"), Some(post_id),
+                ).await;
+            }
+            "deleted" => {
+                notify_and_push(
+                    &pool, &ws_conns, author_id, "post_removed", "Post removed",
+                    &format!("\"{title}\" was removed by our moderators. Reason: {reason}"),
+                    None, None,
+                ).await;
+            }
+            _ => {}
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "message": format!("Post reports {resolution}"),
+            "reports_resolved": resolved.rows_affected(),
+        })),
+    ))
+}
+
 // ── Payout management ─────────────────────────────────────────────────────────
 
 #[derive(Serialize, sqlx::FromRow, Debug)]
@@ -540,6 +3159,458 @@ pub async fn platform_dashboard(
     ))
 }
 
+// ── Overview ──────────────────────────────────────────────────────────────────
+
+/// How long a computed `/overview` payload is served before recomputing.
+const OVERVIEW_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+static OVERVIEW_CACHE: std::sync::Mutex<Option<(std::time::Instant, serde_json::Value)>> =
+    std::sync::Mutex::new(None);
+
+pub async fn platform_overview(
+    State(pool): State<PgPool>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    Ok((StatusCode::OK, Json(overview(&pool).await?)))
+}
+
+/// Headline numbers for the admin home screen. Each metric is its own
+/// statement, run concurrently; the assembled payload is cached for a minute.
+pub async fn overview(pool: &PgPool) -> AppResult<serde_json::Value> {
+    if let Some((at, payload)) = OVERVIEW_CACHE.lock().unwrap().as_ref() {
+        if at.elapsed() < OVERVIEW_CACHE_TTL {
+            return Ok(payload.clone());
+        }
+    }
+
+    let (users, bookings, gmv, reviews, moderation, storage) = tokio::join!(
+        sqlx::query!(
+            r#"SELECT
+                 COUNT(*) FILTER (WHERE c.id IS NOT NULL) AS "clients!",
+                 COUNT(*) FILTER (WHERE c.id IS NOT NULL AND u.created_at >= NOW() - INTERVAL '30 days') AS "clients_new_30d!",
+                 COUNT(*) FILTER (WHERE p.id IS NOT NULL) AS "providers!",
+                 COUNT(*) FILTER (WHERE p.id IS NOT NULL AND u.created_at >= NOW() - INTERVAL '30 days') AS "providers_new_30d!",
+                 COUNT(*) FILTER (WHERE b.id IS NOT NULL) AS "businesses!",
+                 COUNT(*) FILTER (WHERE b.id IS NOT NULL AND u.created_at >= NOW() - INTERVAL '30 days') AS "businesses_new_30d!",
+                 COUNT(*) AS "total!",
+                 COUNT(*) FILTER (WHERE u.created_at >= NOW() - INTERVAL '30 days') AS "total_new_30d!"
+               FROM users u
+               LEFT JOIN clients    c ON c.user_id = u.id
+               LEFT JOIN providers  p ON p.user_id = u.id
+               LEFT JOIN businesses b ON b.user_id = u.id"#
+        )
+        .fetch_one(pool),
+        // Weeks start on Monday; completion time is the last status change
+        sqlx::query!(
+            r#"SELECT
+                 COUNT(*) FILTER (WHERE created_at >= date_trunc('week', NOW())) AS "created_this_week!",
+                 COUNT(*) FILTER (WHERE created_at >= date_trunc('week', NOW()) - INTERVAL '1 week'
+                                    AND created_at <  date_trunc('week', NOW())) AS "created_last_week!",
+                 COUNT(*) FILTER (WHERE status = 'completed'
+                                    AND updated_at >= date_trunc('week', NOW())) AS "completed_this_week!",
+                 COUNT(*) FILTER (WHERE status = 'completed'
+                                    AND updated_at >= date_trunc('week', NOW()) - INTERVAL '1 week'
+                                    AND updated_at <  date_trunc('week', NOW())) AS "completed_last_week!"
+               FROM bookings"#
+        )
+        .fetch_one(pool),
+        sqlx::query!(
+            r#"SELECT
+                 COALESCE(SUM(amount), 0)::float8 AS "total!",
+                 COALESCE(SUM(amount) FILTER (WHERE created_at >= NOW() - INTERVAL '30 days'), 0)::float8 AS "last_30d!"
+               FROM payments
+               WHERE status = 'completed'"#
+        )
+        .fetch_one(pool),
+        sqlx::query!(
+            r#"SELECT
+                 COUNT(*) AS "total!",
+                 COUNT(*) FILTER (WHERE created_at >= NOW() - INTERVAL '30 days') AS "last_30d!"
+               FROM reviews"#
+        )
+        .fetch_one(pool),
+        sqlx::query!(
+            r#"SELECT
+                 (SELECT COUNT(DISTINCT (target_type, target_id)) FROM content_flags WHERE resolved = FALSE) AS "open_flags!",
+                 (SELECT COUNT(*) FROM posts WHERE hidden AND appeal_note IS NOT NULL) AS "pending_appeals!",
+                 (SELECT COUNT(*) FROM bookings WHERE status = 'disputed') AS "open_disputes!""#
+        )
+        .fetch_one(pool),
+        sqlx::query!(
+            r#"SELECT
+                 COALESCE(SUM(size_bytes), 0)::int8 AS "bytes!",
+                 COUNT(*) AS "files!",
+                 COUNT(*) FILTER (WHERE size_bytes IS NULL) AS "files_without_size!"
+               FROM attachments"#
+        )
+        .fetch_one(pool),
+    );
+    let (users, bookings, gmv, reviews, moderation, storage) =
+        (users?, bookings?, gmv?, reviews?, moderation?, storage?);
+
+    let payload = json!({
+        "generated_at": Utc::now(),
+        "users": {
+            "total":      { "all": users.total,      "new_30d": users.total_new_30d },
+            "clients":    { "all": users.clients,    "new_30d": users.clients_new_30d },
+            "providers":  { "all": users.providers,  "new_30d": users.providers_new_30d },
+            "businesses": { "all": users.businesses, "new_30d": users.businesses_new_30d },
+        },
+        "bookings": {
+            "created":   { "this_week": bookings.created_this_week,   "last_week": bookings.created_last_week },
+            "completed": { "this_week": bookings.completed_this_week, "last_week": bookings.completed_last_week },
+        },
+        "gmv": { "total": gmv.total, "last_30d": gmv.last_30d },
+        "reviews": { "total": reviews.total, "last_30d": reviews.last_30d },
+        "moderation": {
+            "open_flags": moderation.open_flags,
+            "pending_appeals": moderation.pending_appeals,
+            "open_disputes": moderation.open_disputes,
+        },
+        "storage": {
+            "bytes_used": storage.bytes,
+            "files": storage.files,
+            "files_without_size": storage.files_without_size,
+        },
+    });
+
+    *OVERVIEW_CACHE.lock().unwrap() = Some((std::time::Instant::now(), payload.clone()));
+
+    Ok(payload)
+}
+
+// ── Booking oversight ─────────────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct AdminBookingQuery {
+    pub client_id: Option<i32>,
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
+    pub status: Option<String>,
+    /// Inclusive range on `scheduled_time`.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Confirmation code, case-insensitive.
+    pub code: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct AdminBookingRow {
+    pub id: i32,
+    pub confirmation_code: String,
+    pub client_id: i32,
+    pub client_username: Option<String>,
+    pub target_type: String,
+    pub target_id: i32,
+    pub target_name: Option<String>,
+    pub service_description: Option<String>,
+    pub scheduled_time: chrono::NaiveDateTime,
+    pub status: String,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct BookingEventRow {
+    pub id: i64,
+    pub event_type: String,
+    pub from_status: Option<String>,
+    pub to_status: Option<String>,
+    pub note: Option<String>,
+    pub actor_id: Option<i32>,
+    pub actor_username: Option<String>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct BookingPaymentRow {
+    pub id: i32,
+    pub amount: BigDecimal,
+    pub phone_number: String,
+    pub transaction_id: Option<String>,
+    pub status: String,
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+pub async fn list_bookings(
+    State(pool): State<PgPool>,
+    Query(params): Query<AdminBookingQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * per_page;
+
+    let target_type = params.target_type.map(|t| t.to_lowercase());
+    if let Some(ref t) = target_type {
+        if t != "provider" && t != "business" {
+            return Err(AppError::BadRequest("target_type must be 'provider' or 'business'".to_string()));
+        }
+    }
+    let status = params.status.map(|s| s.to_lowercase());
+    if let Some(ref s) = status {
+        if !BOOKING_STATUSES.contains(&s.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "status must be one of: {}",
+                BOOKING_STATUSES.join(", ")
+            )));
+        }
+    }
+    let code = params
+        .code
+        .map(|c| c.trim().to_uppercase())
+        .filter(|c| !c.is_empty());
+    let from = params.from.map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default());
+    let to = params.to.map(|d| (d + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default());
+
+    let (bookings, total) = tokio::try_join!(
+        sqlx::query_as!(
+            AdminBookingRow,
+            r#"SELECT b.id, b.confirmation_code, b.client_id, u.username AS "client_username?",
+                      b.target_type, b.target_id,
+                      COALESCE(p.service_name, biz.business_name) AS target_name,
+                      b.service_description, b.scheduled_time, b.status, b.created_at
+               FROM bookings b
+               LEFT JOIN users u ON u.id = b.client_id
+               LEFT JOIN providers  p   ON b.target_type = 'provider' AND b.target_id = p.id
+               LEFT JOIN businesses biz ON b.target_type = 'business' AND b.target_id = biz.id
+               WHERE ($1::int  IS NULL OR b.client_id = $1)
+                 AND ($2::text IS NULL OR b.target_type = $2)
+                 AND ($3::int  IS NULL OR b.target_id = $3)
+                 AND ($4::text IS NULL OR b.status = $4)
+                 AND ($5::timestamp IS NULL OR b.scheduled_time >= $5)
+                 AND ($6::timestamp IS NULL OR b.scheduled_time < $6)
+                 AND ($7::text IS NULL OR b.confirmation_code = $7)
+               ORDER BY b.scheduled_time DESC, b.id DESC
+               LIMIT $8 OFFSET $9"#,
+            params.client_id,
+            target_type,
+            params.target_id,
+            status,
+            from,
+            to,
+            code,
+            per_page,
+            offset
+        )
+        .fetch_all(&pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM bookings b
+               WHERE ($1::int  IS NULL OR b.client_id = $1)
+                 AND ($2::text IS NULL OR b.target_type = $2)
+                 AND ($3::int  IS NULL OR b.target_id = $3)
+                 AND ($4::text IS NULL OR b.status = $4)
+                 AND ($5::timestamp IS NULL OR b.scheduled_time >= $5)
+                 AND ($6::timestamp IS NULL OR b.scheduled_time < $6)
+                 AND ($7::text IS NULL OR b.confirmation_code = $7)"#,
+            params.client_id,
+            target_type,
+            params.target_id,
+            status,
+            from,
+            to,
+            code
+        )
+        .fetch_one(&pool),
+    )?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "bookings": bookings,
+            "total": total,
+            "page": page,
+            "per_page": per_page,
+        })),
+    ))
+}
+
+/// Everything support needs for one booking: the row itself, its timeline,
+/// payments, how many messages the two parties exchanged and how to reach them.
+pub async fn get_booking_detail(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let booking = sqlx::query_as::<_, Booking>("SELECT * FROM bookings WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Booking not found".to_string()))?;
+
+    let (events, payments, messages_count, client, target) = tokio::try_join!(
+        sqlx::query_as!(
+            BookingEventRow,
+            r#"SELECT e.id, e.event_type, e.from_status, e.to_status, e.note,
+                      e.actor_id, u.username AS "actor_username?", e.created_at
+               FROM booking_events e
+               LEFT JOIN users u ON u.id = e.actor_id
+               WHERE e.booking_id = $1
+               ORDER BY e.created_at, e.id"#,
+            id
+        )
+        .fetch_all(&pool),
+        sqlx::query_as!(
+            BookingPaymentRow,
+            r#"SELECT id, amount, phone_number, transaction_id, status, created_at
+               FROM payments WHERE booking_id = $1
+               ORDER BY created_at"#,
+            id
+        )
+        .fetch_all(&pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM messages
+               WHERE target_type = $1 AND target_id = $2
+                 AND (sender_id = $3 OR receiver_id = $3)"#,
+            booking.target_type,
+            booking.target_id,
+            booking.client_id
+        )
+        .fetch_one(&pool),
+        sqlx::query!(
+            r#"SELECT u.username, u.email, c.phone AS "phone?"
+               FROM users u
+               LEFT JOIN clients c ON c.user_id = u.id
+               WHERE u.id = $1"#,
+            booking.client_id
+        )
+        .fetch_optional(&pool),
+        sqlx::query!(
+            r#"SELECT COALESCE(p.user_id, biz.user_id) AS owner_user_id,
+                      COALESCE(p.service_name, biz.business_name) AS name,
+                      COALESCE(p.email, biz.email) AS email,
+                      COALESCE(p.phone_number, biz.phone_number) AS phone_number,
+                      u.username AS "owner_username?", u.email AS "owner_email?"
+               FROM (SELECT $1::text AS target_type, $2::int AS target_id) t
+               LEFT JOIN providers  p   ON t.target_type = 'provider' AND p.id = t.target_id
+               LEFT JOIN businesses biz ON t.target_type = 'business' AND biz.id = t.target_id
+               LEFT JOIN users u ON u.id = COALESCE(p.user_id, biz.user_id)"#,
+            booking.target_type,
+            booking.target_id
+        )
+        .fetch_one(&pool),
+    )?;
+
+    let client = client.map(|c| {
+        json!({
+            "user_id": booking.client_id,
+            "username": c.username,
+            "email": c.email,
+            // Phone given on the booking wins over the profile phone
+            "phone": booking.client_phone.clone().or(c.phone),
+        })
+    });
+    let target = json!({
+        "target_type": booking.target_type,
+        "target_id": booking.target_id,
+        "name": target.name,
+        "email": target.email.or(target.owner_email),
+        "phone_number": target.phone_number,
+        "owner_user_id": target.owner_user_id,
+        "owner_username": target.owner_username,
+    });
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "booking": booking,
+            "events": events,
+            "payments": payments,
+            "messages_count": messages_count,
+            "client": client,
+            "target": target,
+        })),
+    ))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OverrideBookingStatusPayload {
+    pub status: String,
+    pub reason: String,
+}
+
+/// Forces a booking into any status, bypassing the normal transition rules.
+/// The reason is kept on the booking timeline and in the audit log.
+pub async fn override_booking_status(
+    State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<OverrideBookingStatusPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let new_status = payload.status.to_lowercase();
+    if !BOOKING_STATUSES.contains(&new_status.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "status must be one of: {}",
+            BOOKING_STATUSES.join(", ")
+        )));
+    }
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::BadRequest("Reason cannot be empty".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let booking = sqlx::query!(
+        r#"SELECT b.status, b.client_id,
+                  COALESCE(p.user_id, biz.user_id) AS service_owner_user_id
+           FROM bookings b
+           LEFT JOIN providers   p   ON b.target_type = 'provider' AND b.target_id = p.id
+           LEFT JOIN businesses  biz ON b.target_type = 'business' AND b.target_id = biz.id
+           WHERE b.id = $1
+           FOR UPDATE OF b"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Booking not found".to_string()))?;
+
+    if booking.status == new_status {
+        return Err(AppError::BadRequest(format!("Booking is already {}", new_status)));
+    }
+
+    sqlx::query!(
+        "UPDATE bookings SET status = $1, updated_at = NOW() WHERE id = $2",
+        new_status,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    record_booking_event(
+        &mut *tx, id, Some(admin_id), "admin_override",
+        Some(&booking.status), Some(&new_status), Some(reason),
+    ).await?;
+    if new_status == "completed" {
+        interactions::record_for_booking(&mut *tx, id, Interaction::BookingCompleted).await?;
+    }
+    webhooks::enqueue_booking_event(&mut *tx, webhooks::BOOKING_STATUS_CHANGED, id, Some(&booking.status)).await?;
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "booking_status_overridden",
+        "booking",
+        Some(id),
+        json!({ "from": booking.status, "to": new_status, "reason": reason }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    let body = format!(
+        "Support changed booking #{id} from {} to {new_status}. Reason: {reason}",
+        booking.status
+    );
+    notify_and_push(&pool, &ws_conns, booking.client_id, "booking_status_overridden", "Booking updated by support", &body, Some("booking"), Some(id)).await;
+    if let Some(owner_id) = booking.service_owner_user_id {
+        notify_and_push(&pool, &ws_conns, owner_id, "booking_status_overridden", "Booking updated by support", &body, Some("booking"), Some(id)).await;
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Booking status overridden", "from": booking.status, "to": new_status })),
+    ))
+}
+
 // ── Disputes ──────────────────────────────────────────────────────────────────
 
 #[derive(Serialize, sqlx::FromRow, Debug)]
@@ -599,6 +3670,8 @@ pub struct ResolveDisputePayload {
 
 pub async fn resolve_dispute(
     State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id: admin_id, .. }: CurrentUser,
     Path(id): Path<i32>,
     Json(payload): Json<ResolveDisputePayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -623,6 +3696,8 @@ pub async fn resolve_dispute(
     .await?
     .ok_or_else(|| AppError::NotFound("Disputed booking not found or already resolved".to_string()))?;
 
+    let mut tx = pool.begin().await?;
+
     sqlx::query!(
         r#"UPDATE bookings
            SET status = $1, admin_resolution = $2, updated_at = NOW()
@@ -631,9 +3706,29 @@ pub async fn resolve_dispute(
         payload.note.as_deref(),
         id
     )
-    .execute(&pool)
+    .execute(&mut *tx)
+    .await?;
+
+    record_booking_event(
+        &mut *tx, id, Some(admin_id), "dispute_resolved",
+        Some("disputed"), Some(&resolution), payload.note.as_deref(),
+    ).await?;
+    if resolution == "completed" {
+        interactions::record_for_booking(&mut *tx, id, Interaction::BookingCompleted).await?;
+    }
+    webhooks::enqueue_booking_event(&mut *tx, webhooks::BOOKING_STATUS_CHANGED, id, Some("disputed")).await?;
+    record_audit(
+        &mut *tx,
+        Some(admin_id),
+        "dispute_resolved",
+        "booking",
+        Some(id),
+        json!({ "resolution": resolution, "note": payload.note }),
+    )
     .await?;
 
+    tx.commit().await?;
+
     let note_snippet = payload.note.as_deref().unwrap_or("No additional notes.");
     let client_title = if resolution == "completed" {
         "Dispute resolved — booking completed"
@@ -653,11 +3748,11 @@ pub async fn resolve_dispute(
     );
 
     // Notify client
-    notify_best_effort(&pool, booking.client_id, "dispute_resolved", client_title, &client_body, Some("booking"), Some(id)).await;
+    notify_and_push(&pool, &ws_conns, booking.client_id, "dispute_resolved", client_title, &client_body, Some("booking"), Some(id)).await;
 
     // Notify service owner if known
     if let Some(owner_id) = booking.service_owner_user_id {
-        notify_best_effort(&pool, owner_id, "dispute_resolved", provider_title, &provider_body, Some("booking"), Some(id)).await;
+        notify_and_push(&pool, &ws_conns, owner_id, "dispute_resolved", provider_title, &provider_body, Some("booking"), Some(id)).await;
     }
 
     Ok((StatusCode::OK, Json(json!({ "message": format!("Booking marked as {resolution}") }))))
@@ -673,6 +3768,7 @@ pub struct SuspendPayload {
 
 pub async fn suspend_entity(
     State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
     Path((entity_type, entity_id)): Path<(String, i32)>,
     Json(payload): Json<SuspendPayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -699,8 +3795,8 @@ pub async fn suspend_entity(
                 "SELECT user_id FROM providers WHERE id = $1", entity_id
             ).fetch_optional(&pool).await? {
                 let label = if payload.days == 0 { "indefinitely".to_string() } else { format!("for {} day(s)", payload.days) };
-                notify_best_effort(
-                    &pool, user_id, "account_suspended",
+                notify_and_push(
+                    &pool, &ws_conns, user_id, "account_suspended",
                     "Account suspended",
                     &format!("Your provider account has been suspended {label} by an admin. You cannot accept new bookings during this period."),
                     Some("provider"), Some(entity_id),
@@ -722,8 +3818,8 @@ pub async fn suspend_entity(
                 "SELECT user_id FROM businesses WHERE id = $1", entity_id
             ).fetch_optional(&pool).await? {
                 let label = if payload.days == 0 { "indefinitely".to_string() } else { format!("for {} day(s)", payload.days) };
-                notify_best_effort(
-                    &pool, user_id, "account_suspended",
+                notify_and_push(
+                    &pool, &ws_conns, user_id, "account_suspended",
                     "Account suspended",
                     &format!("Your business account has been suspended {label} by an admin. You cannot accept new bookings during this period."),
                     Some("business"), Some(entity_id),
@@ -738,6 +3834,7 @@ pub async fn suspend_entity(
 
 pub async fn unsuspend_entity(
     State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
     Path((entity_type, entity_id)): Path<(String, i32)>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     match entity_type.as_str() {
@@ -751,8 +3848,8 @@ pub async fn unsuspend_entity(
             if let Some(user_id) = sqlx::query_scalar!(
                 "SELECT user_id FROM providers WHERE id = $1", entity_id
             ).fetch_optional(&pool).await? {
-                notify_best_effort(
-                    &pool, user_id, "account_unsuspended",
+                notify_and_push(
+                    &pool, &ws_conns, user_id, "account_unsuspended",
                     "Account suspension lifted",
                     "Your provider account suspension has been lifted. You can now receive new bookings.",
                     Some("provider"), Some(entity_id),
@@ -769,8 +3866,8 @@ pub async fn unsuspend_entity(
             if let Some(user_id) = sqlx::query_scalar!(
                 "SELECT user_id FROM businesses WHERE id = $1", entity_id
             ).fetch_optional(&pool).await? {
-                notify_best_effort(
-                    &pool, user_id, "account_unsuspended",
+                notify_and_push(
+                    &pool, &ws_conns, user_id, "account_unsuspended",
                     "Account suspension lifted",
                     "Your business account suspension has been lifted. You can now receive new bookings.",
                     Some("business"), Some(entity_id),
@@ -792,6 +3889,7 @@ pub struct ApprovePayload {
 
 pub async fn approve_entity(
     State(pool): State<PgPool>,
+    Extension(ws_conns): Extension<WsConnections>,
     Path((entity_type, entity_id)): Path<(String, i32)>,
     Json(payload): Json<ApprovePayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -815,7 +3913,7 @@ pub async fn approve_entity(
                 } else {
                     "Your provider account approval has been revoked by an admin."
                 };
-                notify_best_effort(&pool, user_id, "account_approved", title, body, Some("provider"), Some(entity_id)).await;
+                notify_and_push(&pool, &ws_conns, user_id, "account_approved", title, body, Some("provider"), Some(entity_id)).await;
             }
         }
         "business" => {
@@ -837,7 +3935,7 @@ pub async fn approve_entity(
                 } else {
                     "Your business verification has been revoked by an admin."
                 };
-                notify_best_effort(&pool, user_id, "account_approved", title, body, Some("business"), Some(entity_id)).await;
+                notify_and_push(&pool, &ws_conns, user_id, "account_approved", title, body, Some("business"), Some(entity_id)).await;
             }
         }
         _ => return Err(AppError::BadRequest("entity_type must be 'provider' or 'business'".to_string())),
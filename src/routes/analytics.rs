@@ -65,7 +65,7 @@ struct RepeatClientStats {
 
 pub async fn get_analytics(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path((target_type, target_id)): Path<(String, i32)>,
     Query(params): Query<AnalyticsParams>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -214,7 +214,7 @@ async fn query_average_rating(
     target_id: i32,
 ) -> AppResult<f64> {
     sqlx::query_scalar::<_, Option<f64>>(
-        "SELECT AVG(rating)::float8 FROM reviews WHERE target_type = $1 AND target_id = $2",
+        "SELECT AVG(rating)::float8 FROM reviews WHERE target_type = $1 AND target_id = $2 AND hidden = FALSE",
     )
     .bind(target_type)
     .bind(target_id)
@@ -230,7 +230,7 @@ async fn query_review_count(
     target_id: i32,
 ) -> AppResult<i64> {
     sqlx::query_scalar::<_, i64>(
-        "SELECT COUNT(*) FROM reviews WHERE target_type = $1 AND target_id = $2",
+        "SELECT COUNT(*) FROM reviews WHERE target_type = $1 AND target_id = $2 AND hidden = FALSE",
     )
     .bind(target_type)
     .bind(target_id)
@@ -0,0 +1,137 @@
+use crate::errors::{AppError, AppResult};
+use crate::extractors::current_user::{CurrentUser, OptionalCurrentUser};
+use crate::utils::prefs;
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::PgPool;
+
+pub fn announcements_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/active", get(active_announcements))
+        .route("/:id/dismiss", post(dismiss_announcement))
+        .with_state(pool)
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+pub const ANNOUNCEMENT_SEVERITIES: [&str; 3] = ["info", "warning", "critical"];
+pub const ANNOUNCEMENT_AUDIENCES: [&str; 4] = ["all", "clients", "providers", "businesses"];
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct Announcement {
+    pub id: i32,
+    pub title: String,
+    pub body: String,
+    pub severity: String,
+    pub audience: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+/// Checks the admin-supplied fields shared by create and update.
+pub fn validate_announcement(
+    title: &str,
+    body: &str,
+    severity: &str,
+    audience: &str,
+    starts_at: DateTime<Utc>,
+    ends_at: Option<DateTime<Utc>>,
+) -> AppResult<()> {
+    if title.trim().is_empty() || title.chars().count() > 200 {
+        return Err(AppError::BadRequest("Title must be 1-200 characters".to_string()));
+    }
+    if body.trim().is_empty() {
+        return Err(AppError::BadRequest("Body cannot be empty".to_string()));
+    }
+    if !ANNOUNCEMENT_SEVERITIES.contains(&severity) {
+        return Err(AppError::BadRequest(
+            "severity must be 'info', 'warning' or 'critical'".to_string(),
+        ));
+    }
+    if !ANNOUNCEMENT_AUDIENCES.contains(&audience) {
+        return Err(AppError::BadRequest(
+            "audience must be 'all', 'clients', 'providers' or 'businesses'".to_string(),
+        ));
+    }
+    if ends_at.is_some_and(|end| end <= starts_at) {
+        return Err(AppError::BadRequest("ends_at must be after starts_at".to_string()));
+    }
+    Ok(())
+}
+
+// ── GET /announcements/active ─────────────────────────────────────────────────
+
+/// Banners currently inside their display window. Anonymous callers only see
+/// `all`; signed-in callers also get banners for their role, minus any they
+/// have dismissed, and only critical ones if they turned broadcasts off.
+/// Critical banners come first.
+pub async fn active_announcements(
+    State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let viewer_id = viewer.user_id();
+    let critical_only = match viewer_id {
+        Some(id) => !prefs::flag(&pool, id, prefs::ALLOW_BROADCASTS).await,
+        None => false,
+    };
+
+    let announcements = sqlx::query_as!(
+        Announcement,
+        r#"SELECT a.id, a.title, a.body, a.severity, a.audience, a.starts_at, a.ends_at
+           FROM announcements a
+           WHERE a.starts_at <= NOW()
+             AND (a.ends_at IS NULL OR a.ends_at > NOW())
+             AND (a.audience = 'all'
+                  OR a.audience = (SELECT CASE u.role
+                                            WHEN 'client'   THEN 'clients'
+                                            WHEN 'provider' THEN 'providers'
+                                            WHEN 'business' THEN 'businesses'
+                                          END
+                                   FROM users u WHERE u.id = $1))
+             AND NOT EXISTS (SELECT 1 FROM announcement_dismissals d
+                             WHERE d.announcement_id = a.id AND d.user_id = $1)
+             AND (a.severity = 'critical' OR NOT $2)
+           ORDER BY CASE a.severity WHEN 'critical' THEN 0 WHEN 'warning' THEN 1 ELSE 2 END,
+                    a.starts_at DESC"#,
+        viewer_id,
+        critical_only
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "announcements": announcements }))))
+}
+
+// ── POST /announcements/:id/dismiss ───────────────────────────────────────────
+
+pub async fn dismiss_announcement(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let exists = sqlx::query_scalar!("SELECT id FROM announcements WHERE id = $1", id)
+        .fetch_optional(&pool)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::NotFound("Announcement not found".to_string()));
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO announcement_dismissals (announcement_id, user_id)
+           VALUES ($1, $2)
+           ON CONFLICT (announcement_id, user_id) DO NOTHING"#,
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Announcement dismissed" }))))
+}
@@ -1,7 +1,10 @@
+use crate::config;
 use crate::errors::{AppError, AppResult};
-use crate::extractors::current_user::CurrentUser;
+use crate::extractors::current_user::{CurrentUser, impersonator_in, suspension_rejection};
+use crate::extractors::administrator::is_admin;
 use crate::utils::email::{email_verification_html, password_reset_html, send_email};
 use crate::utils::jwt::create_jwt;
+use crate::utils::refresh_tokens::{self, Refused};
 use argon2::{
     Argon2, PasswordVerifier,
     password_hash::{PasswordHash, PasswordHasher, SaltString, rand_core::OsRng},
@@ -9,12 +12,12 @@ use argon2::{
 use axum::{
     Json, Router,
     extract::{Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     routing::{get, post},
 };
 use chrono::{Duration, Utc};
 use serde::Deserialize;
-use std::{env, sync::Arc, time::Duration as StdDuration};
+use std::{sync::Arc, time::Duration as StdDuration};
 use serde_json::json;
 use sqlx::PgPool;
 use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
@@ -47,6 +50,8 @@ pub fn auth_routes(pool: PgPool) -> Router {
         .route("/forgot-password", post(forgot_password))
         .route("/reset-password", post(reset_password))
         .route("/resend-verification", post(resend_verification))
+        .route("/refresh", post(refresh))
+        .route("/logout", post(logout))
         .layer(GovernorLayer { config: sensitive_governor_conf });
 
     let read_routes = Router::new()
@@ -102,7 +107,7 @@ pub async fn register(
     if payload.password != payload.confirm_password {
         return Err(AppError::BadRequest("Passwords do not match".to_string()));
     }
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
     let existing_user = sqlx::query_scalar!(
         "SELECT 1 FROM users WHERE username = $1 OR email = $2",
@@ -132,10 +137,7 @@ pub async fn register(
         hashed_password
     )
     .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| {
-        AppError::Internal(format!("Error creating user: {}", e))
-    })?;
+    .await?;
 
     let user_id = user.id;
 
@@ -181,10 +183,8 @@ pub async fn register(
         _ => return Err(AppError::BadRequest("Invalid role".to_string())),
     };
 
-    if let Err(e) = role_result {
-        let _ = tx.rollback().await;
-        return Err(AppError::Internal(format!("Error assigning role: {}", e)));
-    }
+    // Dropping the transaction on error rolls it back
+    role_result?;
 
     sqlx::query!(
         "UPDATE users SET role = $1::text WHERE id = $2",
@@ -192,11 +192,9 @@ pub async fn register(
         user_id
     )
     .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        AppError::Internal(format!("Error updating user role: {}", e))
-    })?;
+    .await?;
 
+    let (refresh_token, _) = refresh_tokens::issue(&mut tx, user_id).await?;
     tx.commit().await?;
 
     // Send verification email in background (non-blocking — register still succeeds if email fails)
@@ -212,14 +210,13 @@ pub async fn register(
         .execute(&pool_clone)
         .await;
 
-        let frontend_url = env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-        let verify_url = format!("{}/verify-email?token={}", frontend_url, token_clone);
+        let verify_url = format!("{}/verify-email?token={}", config::get().frontend_url, token_clone);
         if let Err(e) = send_email(&email_clone, "Verify your Sokavi email", &email_verification_html(&verify_url)).await {
             tracing::error!("Failed to send verification email to {}: {}", email_clone, e);
         }
     });
 
-    let token = create_jwt(&user_id.to_string())?;
+    let token = create_jwt(&user_id.to_string(), Some(&payload.role))?;
 
     Ok((
         StatusCode::CREATED,
@@ -229,6 +226,8 @@ pub async fn register(
             "username": payload.username,
             "role": payload.role,
             "token": token,
+            "refresh_token": refresh_token,
+            "expires_in": access_ttl_seconds(),
             "email_verified": false,
             "onboarding_completed": payload.role == "client",
         })),
@@ -291,6 +290,8 @@ pub async fn login_handler(
     let user = sqlx::query!(
         r#"SELECT
             u.id, u.username, u.password, u.role, u.email_verified,
+            u.suspended_until, u.suspension_reason,
+            user_is_suspended(u.id) AS "suspended!",
             CASE
                 WHEN u.role = 'provider' THEN COALESCE(p.onboarding_completed, FALSE)
                 WHEN u.role = 'business' THEN COALESCE(b.onboarding_completed, FALSE)
@@ -299,7 +300,7 @@ pub async fn login_handler(
         FROM users u
         LEFT JOIN providers p ON p.user_id = u.id
         LEFT JOIN businesses b ON b.user_id = u.id
-        WHERE u.email = $1"#,
+        WHERE u.email = $1 AND u.deleted_at IS NULL"#,
         payload.email
     )
     .fetch_optional(&db)
@@ -313,15 +314,33 @@ pub async fn login_handler(
             .verify_password(payload.password.as_bytes(), &parsed_hash)
             .is_ok()
         {
-            let token = create_jwt(&user.id.to_string())?;
+            // Only reveal the suspension once the password has checked out
+            if user.suspended {
+                return Ok(suspension_rejection(
+                    user.suspension_reason.as_deref(),
+                    user.suspended_until,
+                ));
+            }
+
+            let mut tx = db.begin().await?;
+            sqlx::query!("UPDATE users SET last_login_at = NOW() WHERE id = $1", user.id)
+                .execute(&mut *tx)
+                .await?;
+            let (refresh_token, _) = refresh_tokens::issue(&mut tx, user.id).await?;
+            tx.commit().await?;
+
+            let role = user.role.unwrap_or_else(|| "unknown".to_string());
+            let token = create_jwt(&user.id.to_string(), Some(&role))?;
             return Ok((
                 StatusCode::OK,
                 Json(json!({
                     "message": "Login successful",
                     "token": token,
+                    "refresh_token": refresh_token,
+                    "expires_in": access_ttl_seconds(),
                     "user_id": user.id,
                     "username": user.username,
-                    "role": user.role.unwrap_or_else(|| "unknown".to_string()),
+                    "role": role,
                     "email_verified": user.email_verified,
                     "onboarding_completed": user.onboarding_completed,
                 })),
@@ -332,8 +351,89 @@ pub async fn login_handler(
     Err(AppError::Unauthorized("Invalid email or password".to_string()))
 }
 
+/// Seconds an access token stays valid, sent as `expires_in`.
+fn access_ttl_seconds() -> i64 {
+    config::get().jwt_access_ttl_minutes * 60
+}
+
+// ── Refresh / logout ──────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// Exchanges a refresh token for a new access token and a new refresh token.
+/// The one presented stops working.
+pub async fn refresh(
+    State(pool): State<PgPool>,
+    Json(payload): Json<RefreshRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let mut tx = pool.begin().await?;
+    let redeemed = match refresh_tokens::redeem(&mut tx, &payload.refresh_token).await? {
+        Ok(redeemed) => redeemed,
+        Err(refused) => {
+            // Keeps the revocation of a reused token's sessions
+            tx.commit().await?;
+            let message = match refused {
+                Refused::Unknown => "Invalid refresh token",
+                Refused::Expired => "Your session has expired. Please log in again.",
+                Refused::Revoked => "This session has ended. Please log in again.",
+            };
+            return Err(AppError::Unauthorized(message.to_string()));
+        }
+    };
+
+    let user = sqlx::query!(
+        r#"SELECT suspended_until, suspension_reason, user_is_suspended(id) AS "suspended!", deleted_at,
+                  COALESCE(role, 'unknown') AS "role!"
+           FROM users WHERE id = $1"#,
+        redeemed.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if user.deleted_at.is_some() {
+        return Err(AppError::Unauthorized("This session has ended. Please log in again.".to_string()));
+    }
+    if user.suspended {
+        return Ok(suspension_rejection(user.suspension_reason.as_deref(), user.suspended_until));
+    }
+
+    let (refresh_token, replacement_id) = refresh_tokens::issue(&mut tx, redeemed.user_id).await?;
+    refresh_tokens::rotate(&mut tx, redeemed.id, replacement_id).await?;
+    tx.commit().await?;
+
+    let token = create_jwt(&redeemed.user_id.to_string(), Some(&user.role))?;
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "token": token,
+            "refresh_token": refresh_token,
+            "expires_in": access_ttl_seconds(),
+        })),
+    ))
+}
+
+/// Revokes a refresh token. The access token stays valid until it expires,
+/// so clients should drop it too.
+pub async fn logout(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Json(payload): Json<RefreshRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    // Ending an impersonation session must not sign the real user out
+    if impersonator_in(&headers).is_some() {
+        return Err(AppError::Forbidden(
+            "This action is not available while impersonating a user".to_string(),
+        ));
+    }
+    let mut conn = pool.acquire().await?;
+    refresh_tokens::revoke(&mut conn, &payload.refresh_token).await?;
+    Ok((StatusCode::OK, Json(json!({ "message": "Logged out" }))))
+}
+
 pub async fn me(
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     State(pool): State<PgPool>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let user = sqlx::query!(
@@ -361,6 +461,7 @@ pub async fn me(
     )
     .fetch_optional(&pool)
     .await?;
+    let is_admin = is_admin(&pool, user_id).await?;
 
     match user {
         Some(u) => Ok((
@@ -372,6 +473,7 @@ pub async fn me(
                 "role": u.role.unwrap_or_else(|| "unknown".to_string()),
                 "email_verified": u.email_verified,
                 "onboarding_completed": u.onboarding_completed,
+                "is_admin": is_admin,
             })),
         )),
         None => Err(AppError::NotFound("User not found".to_string())),
@@ -387,7 +489,7 @@ pub async fn forgot_password(
     State(pool): State<PgPool>,
     Json(payload): Json<ForgotPasswordRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let user = sqlx::query!("SELECT id FROM users WHERE email = $1", payload.email)
+    let user = sqlx::query!("SELECT id FROM users WHERE email = $1 AND deleted_at IS NULL", payload.email)
         .fetch_optional(&pool)
         .await?;
 
@@ -413,8 +515,7 @@ pub async fn forgot_password(
     .execute(&pool)
     .await?;
 
-    let frontend_url = env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-    let reset_url = format!("{}/reset-password?token={}", frontend_url, token);
+    let reset_url = format!("{}/reset-password?token={}", config::get().frontend_url, token);
     let html = password_reset_html(&reset_url, 15);
 
     if let Err(e) = send_email(&payload.email, "Reset your Sokavi password", &html).await {
@@ -462,21 +563,26 @@ pub async fn reset_password(
         .hash_password(payload.password.as_bytes(), &salt)?
         .to_string();
 
+    let mut tx = pool.begin().await?;
     sqlx::query!(
         "UPDATE users SET password = $1 WHERE id = $2",
         hashed_password,
         reset.user_id
     )
-    .execute(&pool)
+    .execute(&mut *tx)
     .await?;
 
     sqlx::query!(
         "DELETE FROM password_resets WHERE token = $1",
         payload.token
     )
-    .execute(&pool)
+    .execute(&mut *tx)
     .await?;
 
+    // Signed-in devices have to log in again with the new password
+    refresh_tokens::revoke_all(&mut tx, reset.user_id).await?;
+    tx.commit().await?;
+
     Ok((
         StatusCode::OK,
         Json(json!({ "message": "Password reset successfully" })),
@@ -487,7 +593,7 @@ pub async fn reset_password(
 
 pub async fn resend_verification(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let user = sqlx::query!("SELECT email, email_verified FROM users WHERE id = $1", user_id)
         .fetch_optional(&pool)
@@ -519,8 +625,7 @@ pub async fn resend_verification(
         .execute(&pool_clone)
         .await;
 
-        let frontend_url = env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-        let verify_url = format!("{}/verify-email?token={}", frontend_url, token_clone);
+        let verify_url = format!("{}/verify-email?token={}", config::get().frontend_url, token_clone);
         if let Err(e) = send_email(&email, "Verify your Sokavi email", &email_verification_html(&verify_url)).await {
             tracing::error!("Failed to send verification email to {}: {}", email, e);
         }
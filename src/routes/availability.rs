@@ -1,12 +1,14 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::utils::booking_slots::{self, weekday_name};
+use crate::utils::onboarding;
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
     http::StatusCode,
     routing::get,
 };
-use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
@@ -37,18 +39,6 @@ fn parse_time(s: &str) -> AppResult<NaiveTime> {
         .map_err(|_| AppError::BadRequest(format!("Invalid time '{}'. Use HH:MM or HH:MM:SS", s)))
 }
 
-fn weekday_name(w: Weekday) -> &'static str {
-    match w {
-        Weekday::Mon => "Monday",
-        Weekday::Tue => "Tuesday",
-        Weekday::Wed => "Wednesday",
-        Weekday::Thu => "Thursday",
-        Weekday::Fri => "Friday",
-        Weekday::Sat => "Saturday",
-        Weekday::Sun => "Sunday",
-    }
-}
-
 fn capitalize(s: &str) -> String {
     let mut c = s.trim().chars();
     match c.next() {
@@ -97,7 +87,17 @@ pub async fn get_availability(
         .await?
         .ok_or_else(|| AppError::NotFound("Provider not found".to_string()))?;
 
-    let schedule = sqlx::query_as::<_, AvailabilityRow>(
+    let schedule = schedule(&pool, provider_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "provider_id": provider_id, "schedule": schedule })),
+    ))
+}
+
+/// A provider's weekly schedule, Monday first.
+pub(crate) async fn schedule(pool: &PgPool, provider_id: i32) -> Result<Vec<AvailabilityRow>, sqlx::Error> {
+    sqlx::query_as::<_, AvailabilityRow>(
         r#"SELECT id, day, start_time, end_time, is_available
            FROM provider_availability
            WHERE provider_id = $1
@@ -109,13 +109,8 @@ pub async fn get_availability(
            END"#,
     )
     .bind(provider_id)
-    .fetch_all(&pool)
-    .await?;
-
-    Ok((
-        StatusCode::OK,
-        Json(json!({ "provider_id": provider_id, "schedule": schedule })),
-    ))
+    .fetch_all(pool)
+    .await
 }
 
 // ── PUT /availability/provider/:id ───────────────────────────────────────────
@@ -123,7 +118,7 @@ pub async fn get_availability(
 pub async fn set_availability(
     State(pool): State<PgPool>,
     Path(provider_id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(schedule): Json<Vec<DaySchedule>>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     // Ownership check
@@ -210,6 +205,7 @@ pub async fn set_availability(
     }
 
     tx.commit().await?;
+    onboarding::refresh_score(&pool, provider_id).await;
 
     Ok((
         StatusCode::OK,
@@ -272,33 +268,13 @@ pub async fn get_available_slots(
         }
     };
 
-    // Generate all slots within the availability window
+    // Slots clashing with a booking anywhere in their length are left out
     let slot_dur = Duration::minutes(slot_minutes);
-    let mut all_slots: Vec<NaiveTime> = Vec::new();
-    let mut cursor = start_time;
-    while cursor + slot_dur <= end_time && all_slots.len() < 96 {
-        all_slots.push(cursor);
-        cursor += slot_dur;
-    }
-
-    // Fetch booked times on this date (exclude cancelled bookings)
-    let booked: Vec<NaiveDateTime> = sqlx::query_scalar(
-        r#"SELECT scheduled_time FROM bookings
-           WHERE target_type = 'provider' AND target_id = $1
-             AND status <> 'cancelled'
-             AND DATE(scheduled_time) = $2"#,
-    )
-    .bind(provider_id)
-    .bind(date)
-    .fetch_all(&pool)
-    .await
-    .map_err(AppError::Database)?;
-
-    let booked_times: HashSet<NaiveTime> = booked.iter().map(|dt| dt.time()).collect();
-
-    let available_slots: Vec<String> = all_slots
-        .into_iter()
-        .filter(|t| !booked_times.contains(t))
+    let (open, close) = (date.and_time(start_time), date.and_time(end_time));
+    let mut conn = pool.acquire().await?;
+    let booked = booking_slots::booked_between(&mut conn, provider_id, open, close).await?;
+    let available_slots: Vec<String> = booking_slots::free_starts(open, close, slot_dur, slot_dur, &booked, NaiveDateTime::MIN)
+        .iter()
         .map(|t| t.format("%H:%M").to_string())
         .collect();
 
@@ -1,10 +1,20 @@
 use crate::errors::{AppError, AppResult};
+use crate::extractors::administrator::is_admin;
 use crate::extractors::current_user::CurrentUser;
+use crate::utils::body_limit::upload_route;
+use crate::utils::booking_events::record_booking_event;
+use crate::utils::booking_slots::{self, Hours, weekday_name};
+use crate::utils::booking_status::{BOOKING_STATUSES, BookingStatus, Party};
+use crate::utils::image_upload::{SniffedMedia, UploadLimits, read_media_field};
+use crate::utils::spool::SpooledFile;
+use crate::utils::interactions::{self, Interaction};
+use crate::utils::settings;
 use crate::utils::email::{booking_confirmation_html, send_email};
 use crate::utils::notifications::{notify_and_push, notify_target_owner_and_push};
-use crate::utils::sms::{SmsConfig, booking_confirmation_sms, booking_cancelled_sms,
+use crate::utils::sms::{SmsConfig, booking_confirmation_sms, booking_cancelled_sms, client_sms_phone,
                         new_booking_received_sms, send_sms_best_effort};
 use crate::utils::storage::{SharedStorage, generate_key};
+use crate::utils::webhooks;
 use crate::utils::ws_state::WsConnections;
 use axum::{
     Extension, Json, Router,
@@ -12,7 +22,7 @@ use axum::{
     http::StatusCode,
     routing::{get, post},
 };
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
@@ -20,6 +30,7 @@ use sqlx::PgPool;
 pub fn booking_routes(pool: PgPool) -> Router {
     Router::new()
         .route("/createBooking", post(create_booking))
+        .route("/availableSlots", get(get_available_slots))
         .route("/getBookings/me", get(get_bookings_client))
         .route("/getBookings/received", get(get_bookings_received))
         .route("/:id", get(get_booking_by_id))
@@ -27,7 +38,7 @@ pub fn booking_routes(pool: PgPool) -> Router {
         .route("/:id/delete", post(delete_booking))
         .route("/:id/reschedule", post(reschedule_booking))
         .route("/:id/dispute_response", post(submit_dispute_response))
-        .route("/:id/evidence", post(upload_dispute_evidence))
+        .route("/:id/evidence", upload_route(post(upload_dispute_evidence), &pool))
         .route("/:id/evidence", get(get_dispute_evidence))
         .route("/:id/evidence/url", post(record_dispute_evidence_url))
         .with_state(pool)
@@ -56,6 +67,10 @@ pub struct Booking {
     pub dispute_response: Option<String>,
     pub admin_resolution: Option<String>,
     pub reminder_sent: Option<bool>,
+    pub confirmation_code: String,
+    pub address_id: Option<i32>,
+    pub client_address_label: Option<String>,
+    pub client_ward_id: Option<i32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -72,12 +87,16 @@ pub struct CreateBookingInput {
     pub client_address: Option<String>,
     pub client_latitude: Option<f64>,
     pub client_longitude: Option<f64>,
+    /// One of the client's saved addresses. Its address, coordinates and ward
+    /// are copied onto the booking in place of the three fields above; its
+    /// phone is used unless `client_phone` is given.
+    pub address_id: Option<i32>,
 }
 
 pub async fn create_booking(
     State(pool): State<PgPool>,
     Extension(ws_conns): Extension<WsConnections>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<CreateBookingInput>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let target_type = payload.target_type.to_lowercase();
@@ -122,14 +141,15 @@ pub async fn create_booking(
         ));
     }
 
-    // Block bookings to unapproved providers / unverified businesses
+    // Block bookings to unapproved providers / unverified businesses, and to
+    // listings that have not cleared moderation
     let is_unapproved = match target_type.as_str() {
         "provider" => sqlx::query_scalar!(
-            "SELECT 1 FROM providers WHERE id = $1 AND approved = FALSE",
+            "SELECT 1 FROM providers WHERE id = $1 AND (approved = FALSE OR listing_status <> 'approved')",
             target_id
         ).fetch_optional(&pool).await?.is_some(),
         "business" => sqlx::query_scalar!(
-            "SELECT 1 FROM businesses WHERE id = $1 AND verified = FALSE",
+            "SELECT 1 FROM businesses WHERE id = $1 AND (verified = FALSE OR listing_status <> 'approved')",
             target_id
         ).fetch_optional(&pool).await?.is_some(),
         _ => false,
@@ -144,17 +164,17 @@ pub async fn create_booking(
         return Err(AppError::BadRequest("Scheduled time cannot be in the past".to_string()));
     }
 
-    let existing = sqlx::query_scalar!(
-        "SELECT id FROM bookings WHERE target_type = $1 AND target_id = $2 AND scheduled_time = $3",
-        target_type,
-        target_id,
-        payload.scheduled_time
+    let max_pending = settings::int(&pool, "bookings.max_pending_per_client").await;
+    let pending = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM bookings WHERE client_id = $1 AND status = 'pending'"#,
+        user_id
     )
-    .fetch_optional(&pool)
+    .fetch_one(&pool)
     .await?;
-
-    if existing.is_some() {
-        return Err(AppError::Conflict("This time slot has already been booked".to_string()));
+    if pending >= max_pending {
+        return Err(AppError::BadRequest(format!(
+            "You already have {pending} pending bookings. Wait for them to be confirmed or cancel some first."
+        )));
     }
 
     if let Some(service_id) = payload.service_id {
@@ -172,6 +192,19 @@ pub async fn create_booking(
         }
     }
 
+    if let Some(branch_id) = payload.branch_id {
+        let branch = sqlx::query_scalar!(
+            "SELECT id FROM business_branches WHERE id = $1 AND business_id = $2 AND is_active",
+            branch_id,
+            target_id
+        )
+        .fetch_optional(&pool)
+        .await?;
+        if target_type != "business" || branch.is_none() {
+            return Err(AppError::BadRequest("Branch ID does not exist for this business".to_string()));
+        }
+    }
+
     let service_duration = if let Some(service_id) = payload.service_id {
         sqlx::query_scalar!("SELECT duration FROM services WHERE id = $1", service_id)
             .fetch_optional(&pool)
@@ -182,11 +215,56 @@ pub async fn create_booking(
         60
     };
 
+    let saved_address = match payload.address_id {
+        Some(address_id) => Some(
+            sqlx::query!(
+                r#"SELECT label, ward_id, address, latitude, longitude, phone
+                   FROM client_addresses WHERE id = $1 AND user_id = $2"#,
+                address_id,
+                user_id
+            )
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| AppError::BadRequest("Address not found".to_string()))?,
+        ),
+        None => None,
+    };
+    let (client_address, client_latitude, client_longitude, client_address_label, client_ward_id) =
+        match &saved_address {
+            Some(a) => (Some(a.address.clone()), a.latitude, a.longitude, Some(a.label.clone()), a.ward_id),
+            None => (payload.client_address.clone(), payload.client_latitude, payload.client_longitude, None, None),
+        };
+    let client_phone = payload
+        .client_phone
+        .clone()
+        .or_else(|| saved_address.as_ref().and_then(|a| a.phone.clone()));
+
+    let mut tx = pool.begin().await?;
+
+    if target_type == "provider" {
+        check_provider_slot(&mut tx, target_id, payload.scheduled_time, service_duration).await?;
+    } else {
+        let existing = sqlx::query_scalar!(
+            "SELECT id FROM bookings WHERE target_type = $1 AND target_id = $2 AND scheduled_time = $3",
+            target_type,
+            target_id,
+            payload.scheduled_time
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if existing.is_some() {
+            return Err(AppError::Conflict("This time slot has already been booked".to_string()));
+        }
+    }
+
     let record = sqlx::query!(
         r#"INSERT INTO bookings (client_id, target_type, target_id, branch_id, service_id,
            service_description, scheduled_time, duration, status,
-           client_address, client_latitude, client_longitude, client_phone)
-           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id"#,
+           client_address, client_latitude, client_longitude, client_phone,
+           address_id, client_address_label, client_ward_id)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+           RETURNING id, confirmation_code"#,
         user_id,
         target_type,
         target_id,
@@ -196,25 +274,33 @@ pub async fn create_booking(
         payload.scheduled_time,
         service_duration,
         "pending",
-        payload.client_address.as_deref(),
-        payload.client_latitude,
-        payload.client_longitude,
-        payload.client_phone.as_deref(),
+        client_address,
+        client_latitude,
+        client_longitude,
+        client_phone,
+        payload.address_id,
+        client_address_label,
+        client_ward_id,
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
     .await?;
 
     let booking_id = record.id;
+    record_booking_event(&mut *tx, booking_id, Some(user_id), "created", None, Some("pending"), None).await?;
+    interactions::record(&mut *tx, user_id, &target_type, target_id, Interaction::BookingCreated).await?;
+    webhooks::enqueue_booking_event(&mut *tx, webhooks::BOOKING_CREATED, booking_id, None).await?;
+    tx.commit().await?;
+
     let scheduled_str = payload.scheduled_time.format("%d %b %Y %H:%M").to_string();
 
     // ── SMS notifications (best-effort, non-blocking) ─────────────────────────
     if let Ok(sms_cfg) = SmsConfig::from_env() {
-        // 1. Confirmation SMS to client (if phone provided)
-        if let Some(ref phone) = payload.client_phone {
+        // 1. Confirmation SMS to client (if they have a phone and want SMS)
+        if let Some(phone) = client_sms_phone(&pool, booking_id).await {
             let msg = booking_confirmation_sms(
                 booking_id, &scheduled_str, payload.service_description.trim(),
             );
-            send_sms_best_effort(&sms_cfg, phone, &msg).await;
+            send_sms_best_effort(&sms_cfg, &phone, &msg).await;
         }
 
         // 2. New booking alert to provider/business
@@ -265,7 +351,141 @@ pub async fn create_booking(
 
     Ok((
         StatusCode::CREATED,
-        Json(json!({ "message": "Booking created successfully", "booking_id": booking_id })),
+        Json(json!({
+            "message": "Booking created successfully",
+            "booking_id": booking_id,
+            "confirmation_code": record.confirmation_code,
+        })),
+    ))
+}
+
+/// Rejects a provider booking that falls outside the provider's hours for
+/// that weekday, or overlaps a booking they already hold (by each booking's
+/// `duration`). Providers who never set a schedule take bookings at any time.
+///
+/// Locks the provider row so two clients can't both get the same window.
+async fn check_provider_slot(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    provider_id: i32,
+    start: NaiveDateTime,
+    duration: i32,
+) -> AppResult<()> {
+    sqlx::query!("SELECT id FROM providers WHERE id = $1 FOR UPDATE", provider_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+    let end = start + Duration::minutes(i64::from(duration));
+    let day = weekday_name(start.weekday());
+    match booking_slots::provider_hours(tx, provider_id, start.date()).await? {
+        Hours::Unrestricted => {}
+        Hours::Closed => {
+            return Err(AppError::Conflict(format!("This provider does not take bookings on {day}s")));
+        }
+        Hours::Open { start: opens, end: closes } => {
+            if start.time() < opens || end.date() != start.date() || end.time() > closes {
+                return Err(AppError::Conflict(format!(
+                    "This provider takes bookings between {} and {} on {day}s",
+                    opens.format("%H:%M"),
+                    closes.format("%H:%M"),
+                )));
+            }
+        }
+    }
+
+    if let Some(clash) = booking_slots::booked_between(tx, provider_id, start, end).await?.first() {
+        return Err(AppError::Conflict(format!(
+            "This provider is already booked from {} to {} on {}",
+            clash.start.format("%H:%M"),
+            clash.end.format("%H:%M"),
+            clash.start.format("%d %b %Y"),
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvailableSlotsQuery {
+    pub target_type: String,
+    pub target_id: i32,
+    pub date: NaiveDate,
+    /// Sets both the spacing of the start times and how long each booking
+    /// runs.
+    pub service_id: Option<i32>,
+}
+
+/// Start times on `date` a booking could be created at: within the
+/// provider's hours, clear of their existing bookings and not already past.
+/// Start times are the service's duration apart (30 minutes without one),
+/// and each must leave room for a booking as long as `create_booking` would
+/// make it.
+pub async fn get_available_slots(
+    State(pool): State<PgPool>,
+    Query(params): Query<AvailableSlotsQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if params.target_type != "provider" {
+        return Err(AppError::BadRequest("Available slots can only be listed for providers".to_string()));
+    }
+    let provider_id = params.target_id;
+    sqlx::query_scalar!("SELECT id FROM providers WHERE id = $1", provider_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Provider not found".to_string()))?;
+
+    let service_duration = match params.service_id {
+        Some(service_id) => sqlx::query_scalar!(
+            "SELECT duration FROM services WHERE id = $1 AND target_type = 'provider' AND target_id = $2",
+            service_id,
+            provider_id
+        )
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Service ID does not exist".to_string()))?
+        .filter(|minutes| *minutes > 0),
+        None => None,
+    };
+    let step = Duration::minutes(i64::from(service_duration.unwrap_or(30)));
+    let length = Duration::minutes(i64::from(service_duration.unwrap_or(60)));
+
+    let date = params.date;
+    let mut conn = pool.acquire().await?;
+    let day = weekday_name(date.weekday());
+    let (open, close, hours) = match booking_slots::provider_hours(&mut conn, provider_id, date).await? {
+        Hours::Closed => (None, None, None),
+        Hours::Unrestricted => (Some(date.and_time(NaiveTime::MIN)), Some((date + Duration::days(1)).and_time(NaiveTime::MIN)), None),
+        Hours::Open { start, end } => (
+            Some(date.and_time(start)),
+            Some(date.and_time(end)),
+            Some(json!({ "start": start.format("%H:%M").to_string(), "end": end.format("%H:%M").to_string() })),
+        ),
+    };
+
+    let slots: Vec<String> = match (open, close) {
+        (Some(open), Some(close)) => {
+            let booked = booking_slots::booked_between(&mut conn, provider_id, open, close).await?;
+            let now = chrono::Local::now().naive_local();
+            booking_slots::free_starts(open, close, step, length, &booked, now)
+                .iter()
+                .map(|start| start.format("%H:%M").to_string())
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "date": date,
+            "day": day,
+            "target_type": "provider",
+            "target_id": provider_id,
+            "service_id": params.service_id,
+            "slot_minutes": step.num_minutes(),
+            "duration_minutes": length.num_minutes(),
+            "hours": hours,
+            "total_available": slots.len(),
+            "available_slots": slots,
+            "message": open.is_none().then(|| format!("This provider does not take bookings on {day}s")),
+        })),
     ))
 }
 
@@ -277,9 +497,23 @@ pub struct BookingQuery {
 
 pub async fn get_bookings_client(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Query(params): Query<BookingQuery>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if let Some(ref status) = params.status {
+        if !BOOKING_STATUSES.contains(&status.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "status must be one of: {}",
+                BOOKING_STATUSES.join(", ")
+            )));
+        }
+    }
+    if let Some(ref target_type) = params.target_type {
+        if target_type != "provider" && target_type != "business" {
+            return Err(AppError::BadRequest("target_type must be 'provider' or 'business'".to_string()));
+        }
+    }
+
     let bookings = sqlx::query_as::<_, Booking>(
         r#"SELECT * FROM bookings
            WHERE client_id = $1
@@ -323,12 +557,14 @@ pub struct BookingResponse {
     pub client_address: Option<String>,
     pub client_latitude: Option<f64>,
     pub client_longitude: Option<f64>,
+    pub client_address_label: Option<String>,
+    pub client_ward: Option<String>,
     pub service_name: String,
 }
 
 pub async fn get_bookings_received(
     State(pool): State<PgPool>,
-    CurrentUser { user_id: _ }: CurrentUser,
+    CurrentUser { user_id: _, .. }: CurrentUser,
     Query(params): Query<BookingsQueryByReceiver>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let target_type = params.target_type.to_lowercase();
@@ -347,12 +583,16 @@ pub async fn get_bookings_received(
     let rows = sqlx::query!(
         r#"SELECT b.id, b.client_id, b.target_type, b.target_id, b.branch_id, b.service_id,
                b.service_description, b.scheduled_time, b.status, b.duration, b.created_at,
-               b.client_address, b.client_latitude, b.client_longitude, b.client_phone,
-               u.username as client_name, u.email as client_email,
+               b.client_address, b.client_latitude, b.client_longitude,
+               b.client_address_label, w.name AS "client_ward?",
+               COALESCE(b.client_phone, c.phone) AS client_phone,
+               COALESCE(u.display_name, u.username) AS "client_name!", u.email as client_email,
                CASE WHEN b.service_id IS NOT NULL THEN s.title ELSE b.service_description END AS service_name
         FROM bookings b
         LEFT JOIN users u ON b.client_id = u.id
+        LEFT JOIN clients c ON c.user_id = b.client_id
         LEFT JOIN services s ON b.service_id = s.id
+        LEFT JOIN wards w ON w.id = b.client_ward_id
         WHERE b.target_type = $1 AND b.target_id = $2
           AND ($3::text IS NULL OR b.status = $3)
         ORDER BY b.scheduled_time DESC"#,
@@ -383,6 +623,8 @@ pub async fn get_bookings_received(
             client_address: row.client_address,
             client_latitude: row.client_latitude,
             client_longitude: row.client_longitude,
+            client_address_label: row.client_address_label,
+            client_ward: row.client_ward,
             service_name: row.service_name.unwrap_or_default(),
         })
         .collect();
@@ -392,7 +634,7 @@ pub async fn get_bookings_received(
 
 pub async fn get_booking_by_id(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if id <= 0 {
@@ -415,7 +657,29 @@ pub async fn get_booking_by_id(
     .await?
     .ok_or_else(|| AppError::NotFound("Booking not found".to_string()))?;
 
-    Ok((StatusCode::OK, Json(json!({ "booking": booking }))))
+    let history = sqlx::query_as!(
+        StatusChange,
+        r#"SELECT from_status, to_status AS "to_status!", actor_id AS changed_by, note, created_at AS changed_at
+           FROM booking_events
+           WHERE booking_id = $1 AND to_status IS NOT NULL
+           ORDER BY created_at, id"#,
+        id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "booking": booking, "history": history }))))
+}
+
+/// One entry of a booking's status history. `changed_by` is `None` for
+/// system changes such as expiry or the payment callback.
+#[derive(Serialize, Debug)]
+pub struct StatusChange {
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub changed_by: Option<i32>,
+    pub note: Option<String>,
+    pub changed_at: DateTime<Utc>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -429,7 +693,7 @@ pub async fn update_booking(
     State(pool): State<PgPool>,
     Extension(ws_conns): Extension<WsConnections>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<BookingUpdate>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if id <= 0 {
@@ -437,7 +701,10 @@ pub async fn update_booking(
     }
 
     let booking = sqlx::query!(
-        "SELECT target_type, target_id, client_id, status FROM bookings WHERE id = $1", id
+        r#"SELECT target_type, target_id, client_id, status, scheduled_time <= $2 AS "started!"
+           FROM bookings WHERE id = $1"#,
+        id,
+        Utc::now().naive_utc()
     )
     .fetch_optional(&pool)
     .await?
@@ -463,55 +730,69 @@ pub async fn update_booking(
         return Err(AppError::Forbidden("You don't have permission to update this booking".to_string()));
     }
 
-    let new_status = payload.status.to_lowercase();
-    let current_status = booking.status.to_lowercase();
-
-    // Role-based transition rules
-    if is_service_owner {
-        // Provider/business may confirm, cancel, or signal completion.
-        // "completed" from the service side means "I'm done — pending client confirmation".
-        let allowed = ["confirmed", "cancelled", "pending_confirmation"];
-        if !allowed.contains(&new_status.as_str()) {
-            return Err(AppError::BadRequest(format!(
-                "Providers may only set status to: {}",
-                allowed.join(", ")
-            )));
-        }
-    } else {
-        // Client may only confirm or dispute, and only when awaiting confirmation.
-        if current_status != "pending_confirmation" {
-            return Err(AppError::BadRequest(
-                "You can only confirm or dispute a booking that is awaiting confirmation".to_string(),
-            ));
-        }
-        let allowed = ["completed", "disputed"];
-        if !allowed.contains(&new_status.as_str()) {
-            return Err(AppError::BadRequest(
-                "Clients may only confirm completion or raise a dispute".to_string(),
-            ));
-        }
+    let new = BookingStatus::parse(&payload.status.to_lowercase()).ok_or_else(|| {
+        AppError::BadRequest(format!("status must be one of: {}", BOOKING_STATUSES.join(", ")))
+    })?;
+    let current = BookingStatus::parse(&booking.status)
+        .ok_or_else(|| AppError::Internal(format!("Booking {} has unknown status {:?}", id, booking.status)))?;
+
+    // The owner takes the booking from request to done; the client then
+    // confirms or disputes. Owner wins for someone who booked themselves.
+    let party = if is_service_owner { Party::Owner } else { Party::Client };
+    let allowed = current.next_for(party);
+    if !allowed.contains(&new) {
+        let options: Vec<&str> = allowed.iter().map(|s| s.as_str()).collect();
+        return Err(AppError::Conflict(if options.is_empty() {
+            format!("You can't change a {} booking", current.as_str())
+        } else {
+            format!(
+                "A {} booking can only be moved to: {}",
+                current.as_str(),
+                options.join(", ")
+            )
+        }));
     }
+    if new == BookingStatus::NoShow && !booking.started {
+        return Err(AppError::Conflict(
+            "A booking can only be marked a no-show after its scheduled time".to_string(),
+        ));
+    }
+    let new_status = new.as_str();
+    let current_status = current.as_str();
 
-    sqlx::query!(
-        "UPDATE bookings SET status = $1, cancel_reason = $2, dispute_reason = $3 WHERE id = $4",
+    let mut tx = pool.begin().await?;
+
+    // Only from the status checked above, so a concurrent change isn't overwritten
+    let updated = sqlx::query!(
+        "UPDATE bookings SET status = $1, cancel_reason = $2, dispute_reason = $3, updated_at = NOW()
+         WHERE id = $4 AND status = $5",
         new_status,
         payload.cancel_reason.as_deref(),
         payload.dispute_reason.as_deref(),
         id,
+        current_status,
     )
-    .execute(&pool)
+    .execute(&mut *tx)
     .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::Conflict("This booking was changed by someone else; reload it and try again".to_string()));
+    }
+
+    let note = payload.cancel_reason.as_deref().or(payload.dispute_reason.as_deref());
+    record_booking_event(
+        &mut *tx, id, Some(user_id), "status_changed",
+        Some(current_status), Some(new_status), note,
+    ).await?;
+    if new_status == "completed" {
+        interactions::record_for_booking(&mut *tx, id, Interaction::BookingCompleted).await?;
+    }
+    webhooks::enqueue_booking_event(&mut *tx, webhooks::BOOKING_STATUS_CHANGED, id, Some(current_status)).await?;
+    tx.commit().await?;
 
     // ── SMS ─────────────────────────────────────────────────────────────────
     if new_status == "confirmed" || new_status == "cancelled" {
         if let Ok(sms_cfg) = SmsConfig::from_env() {
-            let client_phone = sqlx::query_scalar!(
-                "SELECT phone_number FROM payments WHERE booking_id = $1 ORDER BY created_at DESC LIMIT 1",
-                id
-            )
-            .fetch_optional(&pool).await.ok().flatten();
-
-            if let Some(phone) = client_phone {
+            if let Some(phone) = client_sms_phone(&pool, id).await {
                 let msg = if new_status == "confirmed" {
                     let bk = sqlx::query!(
                         "SELECT service_description, scheduled_time FROM bookings WHERE id = $1", id
@@ -532,13 +813,14 @@ pub async fn update_booking(
     // ── Email ────────────────────────────────────────────────────────────────
     if new_status == "confirmed" {
         let details = sqlx::query!(
-            r#"SELECT u.email, u.username, b.service_description, b.scheduled_time,
+            r#"SELECT u.email, COALESCE(u.display_name, u.username) AS "username!", b.service_description, b.scheduled_time,
                       COALESCE(p.service_name, biz.business_name, 'Provider') AS provider_name
                FROM bookings b
                JOIN users u ON u.id = b.client_id
+               LEFT JOIN clients c ON c.user_id = b.client_id
                LEFT JOIN providers p ON b.target_type = 'provider' AND b.target_id = p.id
                LEFT JOIN businesses biz ON b.target_type = 'business' AND b.target_id = biz.id
-               WHERE b.id = $1"#, id
+               WHERE b.id = $1 AND COALESCE(c.email_notifications, TRUE)"#, id
         ).fetch_optional(&pool).await.ok().flatten();
 
         if let Some(d) = details {
@@ -557,26 +839,38 @@ pub async fn update_booking(
     // ── In-app notifications ─────────────────────────────────────────────────
     let client_id = booking.client_id;
 
-    // Notify client: confirmed / cancelled / pending_confirmation
-    match new_status.as_str() {
+    // Notify client: confirmed / rejected / cancelled / pending_confirmation / no_show
+    match new_status {
         "confirmed" => {
-            notify_and_push(&pool, &ws_conns, client_id, &new_status,
+            notify_and_push(&pool, &ws_conns, client_id, new_status,
                 "Booking Confirmed",
                 &format!("Your booking #{} has been confirmed", id),
                 Some("booking"), Some(id)).await;
         }
+        "rejected" => {
+            notify_and_push(&pool, &ws_conns, client_id, new_status,
+                "Booking Declined",
+                &format!("Your booking #{} was declined", id),
+                Some("booking"), Some(id)).await;
+        }
         "cancelled" => {
-            notify_and_push(&pool, &ws_conns, client_id, &new_status,
+            notify_and_push(&pool, &ws_conns, client_id, new_status,
                 "Booking Cancelled",
                 &format!("Your booking #{} has been cancelled", id),
                 Some("booking"), Some(id)).await;
         }
         "pending_confirmation" => {
-            notify_and_push(&pool, &ws_conns, client_id, &new_status,
+            notify_and_push(&pool, &ws_conns, client_id, new_status,
                 "Job Marked Complete",
                 &format!("The provider says booking #{} is done. Please confirm or raise a dispute.", id),
                 Some("booking"), Some(id)).await;
         }
+        "no_show" => {
+            notify_and_push(&pool, &ws_conns, client_id, new_status,
+                "Missed Booking",
+                &format!("Booking #{} was marked as missed because you didn't show up", id),
+                Some("booking"), Some(id)).await;
+        }
         _ => {}
     }
 
@@ -598,7 +892,7 @@ pub async fn update_booking(
             } else {
                 ("Dispute Raised", format!("Client raised a dispute on booking #{}", id))
             };
-            notify_and_push(&pool, &ws_conns, puid, &new_status, &title, &body, Some("booking"), Some(id)).await;
+            notify_and_push(&pool, &ws_conns, puid, new_status, title, &body, Some("booking"), Some(id)).await;
         }
     }
 
@@ -608,7 +902,7 @@ pub async fn update_booking(
 pub async fn delete_booking(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if id <= 0 {
         return Err(AppError::BadRequest("Invalid booking ID".to_string()));
@@ -641,7 +935,7 @@ pub struct ReschedulePayload {
 pub async fn reschedule_booking(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<ReschedulePayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if id <= 0 {
@@ -651,15 +945,34 @@ pub async fn reschedule_booking(
         return Err(AppError::BadRequest("New scheduled time cannot be in the past".to_string()));
     }
 
-    sqlx::query!(
-        "UPDATE bookings SET scheduled_time = $1 WHERE id = $2 AND client_id = $3",
-        payload.scheduled_time,
+    let mut tx = pool.begin().await?;
+
+    let previous = sqlx::query_scalar!(
+        "SELECT scheduled_time FROM bookings WHERE id = $1 AND client_id = $2 FOR UPDATE",
         id,
         user_id
     )
-    .execute(&pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
+    if let Some(previous) = previous {
+        sqlx::query!(
+            "UPDATE bookings SET scheduled_time = $1, updated_at = NOW() WHERE id = $2",
+            payload.scheduled_time,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let note = format!(
+            "{} -> {}",
+            previous.format("%Y-%m-%d %H:%M"),
+            payload.scheduled_time.format("%Y-%m-%d %H:%M")
+        );
+        record_booking_event(&mut *tx, id, Some(user_id), "rescheduled", None, None, Some(&note)).await?;
+    }
+    tx.commit().await?;
+
     Ok((StatusCode::OK, Json(json!({ "message": "Booking rescheduled successfully" }))))
 }
 
@@ -674,7 +987,7 @@ pub async fn submit_dispute_response(
     State(pool): State<PgPool>,
     Extension(ws_conns): Extension<WsConnections>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<DisputeResponsePayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if payload.response.trim().is_empty() {
@@ -710,14 +1023,21 @@ pub async fn submit_dispute_response(
         return Err(AppError::Forbidden("Only the service provider can submit a dispute response".to_string()));
     }
 
+    let mut tx = pool.begin().await?;
+
     sqlx::query!(
         "UPDATE bookings SET dispute_response = $1 WHERE id = $2",
         payload.response.trim(),
         id
     )
-    .execute(&pool)
+    .execute(&mut *tx)
     .await?;
 
+    record_booking_event(
+        &mut *tx, id, Some(user_id), "dispute_response", None, None, Some(payload.response.trim()),
+    ).await?;
+    tx.commit().await?;
+
     // Notify the client that the provider has responded
     use crate::utils::notifications::notify_and_push;
     notify_and_push(
@@ -740,7 +1060,7 @@ pub async fn upload_dispute_evidence(
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     mut multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let booking = sqlx::query!(
@@ -776,35 +1096,25 @@ pub async fn upload_dispute_evidence(
     let uploader_role = if is_client { "client" } else { "provider" };
 
     // Parse multipart: collect file bytes and optional caption text field
-    let mut file_data: Option<bytes::Bytes> = None;
-    let mut file_ext = "jpg".to_string();
+    let mut file_data: Option<(SpooledFile, SniffedMedia)> = None;
     let mut caption: Option<String> = None;
+    let mut limits = UploadLimits::load(&pool, false).await;
 
-    while let Some(field) = multipart.next_field().await
-        .map_err(|e| AppError::BadRequest(e.to_string()))?
-    {
+    while let Some(mut field) = multipart.next_field().await? {
         let name = field.name().unwrap_or("").to_string();
         if name == "caption" {
-            let text = field.text().await
-                .map_err(|e| AppError::BadRequest(e.to_string()))?;
+            let text = field.text().await?;
             if !text.trim().is_empty() { caption = Some(text.trim().to_string()); }
         } else {
             // treat as the image file
             let file_name = field.file_name()
                 .map(|s| s.to_string())
-                .unwrap_or_else(|| "upload.jpg".to_string());
-            file_ext = std::path::Path::new(&file_name)
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("jpg")
-                .to_lowercase();
-            let data = field.bytes().await
-                .map_err(|e| AppError::BadRequest(e.to_string()))?;
-            if !data.is_empty() { file_data = Some(data); }
+                .unwrap_or_else(|| "upload".to_string());
+            file_data = Some(read_media_field(&mut field, &file_name, &mut limits).await?);
         }
     }
 
-    let data = file_data.ok_or_else(|| AppError::BadRequest("No image uploaded".to_string()))?;
+    let (file, media) = file_data.ok_or_else(|| AppError::BadRequest("No image uploaded".to_string()))?;
 
     // Limit: max 5 evidence images per party per booking
     let count = sqlx::query_scalar!(
@@ -819,9 +1129,8 @@ pub async fn upload_dispute_evidence(
         return Err(AppError::BadRequest("Maximum 5 evidence images per party".to_string()));
     }
 
-    let key = generate_key("disputes/evidence", &file_ext);
-    let url = storage.save(&key, &data).await
-        .map_err(|e| { let _ = tokio::spawn(async move {}); e })?;
+    let key = generate_key("disputes/evidence", media.extension);
+    let url = storage.save_file(&key, file.path(), media.content_type).await?;
 
     sqlx::query!(
         r#"INSERT INTO dispute_evidence (booking_id, uploaded_by, uploader_role, file_url, caption)
@@ -845,7 +1154,7 @@ pub struct EvidenceUrlPayload {
 pub async fn record_dispute_evidence_url(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<EvidenceUrlPayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let booking = sqlx::query!(
@@ -906,7 +1215,7 @@ pub async fn record_dispute_evidence_url(
 pub async fn get_dispute_evidence(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     // Verify the requester is a party to this booking or an admin
     let booking = sqlx::query!(
@@ -929,15 +1238,7 @@ pub async fn get_dispute_evidence(
         ).fetch_optional(&pool).await?.is_some(),
         _ => false,
     };
-    let is_admin = sqlx::query_scalar!(
-        "SELECT is_super_admin FROM admins WHERE user_id = $1", user_id
-    )
-    .fetch_optional(&pool)
-    .await?
-    .flatten()
-    .unwrap_or(false);
-
-    if !is_client && !is_service_owner && !is_admin {
+    if !is_client && !is_service_owner && !is_admin(&pool, user_id).await? {
         return Err(AppError::Forbidden("Access denied".to_string()));
     }
 
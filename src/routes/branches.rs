@@ -0,0 +1,279 @@
+use crate::errors::{AppError, AppResult};
+use crate::extractors::current_user::{CurrentUser, OptionalCurrentUser};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use validator::Validate;
+
+/// `/branches/:id` routes. Creating and listing live under the business, at
+/// `/businesses/:id/branches`; the older `/locations/branches/...` paths use
+/// the same handlers.
+pub fn branches_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/:id", get(get_branch).put(update_branch))
+        .route("/:id/deactivate", post(deactivate_branch))
+        .with_state(pool)
+}
+
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct Branch {
+    pub id: i32,
+    pub business_id: i32,
+    pub name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub ward_id: Option<i32>,
+    pub ward_name: Option<String>,
+    pub constituency_name: Option<String>,
+    pub county_name: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub is_active: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Selects `Branch` rows as `bb`; callers add the `WHERE`.
+const BRANCH_SELECT: &str = r#"
+    SELECT bb.id, bb.business_id, bb.name, bb.latitude, bb.longitude, bb.ward_id,
+           w.name AS ward_name, c.name AS constituency_name, co.name AS county_name,
+           bb.phone, bb.address, bb.is_active, bb.created_at, bb.updated_at
+    FROM business_branches bb
+    LEFT JOIN wards w ON w.id = bb.ward_id
+    LEFT JOIN constituencies c ON c.id = w.constituency_id
+    LEFT JOIN counties co ON co.id = c.county_id"#;
+
+async fn fetch_branch(pool: &PgPool, id: i32) -> AppResult<Option<Branch>> {
+    Ok(sqlx::query_as::<_, Branch>(&format!("{BRANCH_SELECT} WHERE bb.id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// The user who owns the branch's business; 404 when there's no such branch.
+async fn branch_owner(pool: &PgPool, id: i32) -> AppResult<i32> {
+    sqlx::query_scalar!(
+        "SELECT b.user_id FROM business_branches bb
+         JOIN businesses b ON bb.business_id = b.id
+         WHERE bb.id = $1",
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Branch location not found".to_string()))
+}
+
+async fn ensure_ward_exists(pool: &PgPool, ward_id: i32) -> AppResult<()> {
+    sqlx::query_scalar!("SELECT id FROM wards WHERE id = $1", ward_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Ward not found".to_string()))?;
+    Ok(())
+}
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct CreateBranchRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(range(min = -90.0, max = 90.0))]
+    pub latitude: f64,
+    #[validate(range(min = -180.0, max = 180.0))]
+    pub longitude: f64,
+    pub ward_id: i32,
+    #[validate(length(min = 1, max = 15))]
+    pub phone: String,
+    #[validate(length(min = 1, max = 255))]
+    pub address: String,
+}
+
+pub async fn create_branch(
+    Path(business_id): Path<i32>,
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<CreateBranchRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let owns = sqlx::query_scalar!(
+        "SELECT id FROM businesses WHERE id = $1 AND user_id = $2",
+        business_id, user_id
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    if owns.is_none() {
+        return Err(AppError::Forbidden(
+            "You do not have permission to create a branch for this business".to_string(),
+        ));
+    }
+
+    payload.validate()?;
+    ensure_ward_exists(&pool, payload.ward_id).await?;
+
+    let id = sqlx::query_scalar!(
+        r#"INSERT INTO business_branches (business_id, name, latitude, longitude, ward_id, phone, address)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           RETURNING id"#,
+        business_id,
+        payload.name.trim(),
+        payload.latitude,
+        payload.longitude,
+        payload.ward_id,
+        payload.phone.trim(),
+        payload.address.trim()
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let branch = fetch_branch(&pool, id).await?;
+    Ok((StatusCode::CREATED, Json(json!({ "data": branch }))))
+}
+
+/// A business's branches. Deactivated ones are only listed for the owner.
+pub async fn list_branches(
+    Path(business_id): Path<i32>,
+    State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let owner = sqlx::query_scalar!("SELECT user_id FROM businesses WHERE id = $1", business_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Business not found".to_string()))?;
+    let is_owner = viewer.user_id() == Some(owner);
+
+    let branches = sqlx::query_as::<_, Branch>(&format!(
+        "{BRANCH_SELECT} WHERE bb.business_id = $1 AND (bb.is_active OR $2) ORDER BY bb.id"
+    ))
+    .bind(business_id)
+    .bind(is_owner)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "data": branches }))))
+}
+
+/// One branch. A deactivated branch is a 404 for everyone but the owner.
+pub async fn get_branch(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let not_found = || AppError::NotFound("Branch location not found".to_string());
+    let branch = fetch_branch(&pool, id).await?.ok_or_else(not_found)?;
+    if !branch.is_active && viewer.user_id() != Some(branch_owner(&pool, id).await?) {
+        return Err(not_found());
+    }
+
+    Ok((StatusCode::OK, Json(json!({ "data": branch }))))
+}
+
+#[derive(Deserialize, Validate, Debug)]
+pub struct UpdateBranchRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(range(min = -90.0, max = 90.0))]
+    pub latitude: Option<f64>,
+    #[validate(range(min = -180.0, max = 180.0))]
+    pub longitude: Option<f64>,
+    pub ward_id: Option<i32>,
+    #[validate(length(min = 1, max = 15))]
+    pub phone: Option<String>,
+    #[validate(length(min = 1, max = 255))]
+    pub address: Option<String>,
+    /// `true` reactivates a deactivated branch.
+    pub is_active: Option<bool>,
+}
+
+pub async fn update_branch(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<UpdateBranchRequest>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    payload.validate()?;
+
+    if branch_owner(&pool, id).await? != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to update this branch".to_string(),
+        ));
+    }
+    if let Some(ward_id) = payload.ward_id {
+        ensure_ward_exists(&pool, ward_id).await?;
+    }
+
+    sqlx::query!(
+        r#"UPDATE business_branches SET
+               name       = COALESCE($1, name),
+               latitude   = COALESCE($2, latitude),
+               longitude  = COALESCE($3, longitude),
+               ward_id    = COALESCE($4, ward_id),
+               phone      = COALESCE($5, phone),
+               address    = COALESCE($6, address),
+               is_active  = COALESCE($7, is_active),
+               updated_at = NOW()
+           WHERE id = $8"#,
+        payload.name.as_deref().map(str::trim),
+        payload.latitude,
+        payload.longitude,
+        payload.ward_id,
+        payload.phone.as_deref().map(str::trim),
+        payload.address.as_deref().map(str::trim),
+        payload.is_active,
+        id
+    )
+    .execute(&pool)
+    .await?;
+
+    let branch = fetch_branch(&pool, id).await?;
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Branch location updated successfully", "data": branch })),
+    ))
+}
+
+/// Stops a branch taking bookings and hides it from the public without
+/// losing the bookings that reference it.
+pub async fn deactivate_branch(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if branch_owner(&pool, id).await? != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to deactivate this branch".to_string(),
+        ));
+    }
+
+    sqlx::query!(
+        "UPDATE business_branches SET is_active = FALSE, updated_at = NOW() WHERE id = $1 AND is_active",
+        id
+    )
+    .execute(&pool)
+    .await?;
+
+    let branch = fetch_branch(&pool, id).await?;
+    Ok((StatusCode::OK, Json(json!({ "message": "Branch deactivated", "data": branch }))))
+}
+
+pub async fn delete_branch(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if branch_owner(&pool, id).await? != user_id {
+        return Err(AppError::Forbidden(
+            "You do not have permission to delete this branch".to_string(),
+        ));
+    }
+
+    sqlx::query!("DELETE FROM business_branches WHERE id = $1", id)
+        .execute(&pool)
+        .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Branch location deleted successfully" }))))
+}
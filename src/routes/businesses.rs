@@ -1,11 +1,21 @@
 use crate::errors::{AppError, AppResult};
-use crate::extractors::current_user::CurrentUser;
+use crate::extractors::current_user::{CurrentUser, OptionalCurrentUser};
+use crate::extractors::tx::{Tx, transactional};
+use crate::routes::branches;
+use crate::routes::favorites::favorited_by;
+use crate::utils::body_limit::upload_route;
+use crate::utils::etag::{CacheClass, conditional_route};
 use crate::utils::image_upload::parse_image_from_multipart;
+use crate::utils::pagination::{Paginated, Pagination};
+use crate::utils::settings;
 use crate::utils::storage::{SharedStorage, generate_key};
+use crate::utils::structured_data;
+use crate::utils::thumbnails::store_image_variants;
 use axum::{
     Extension, Json, Router,
     extract::{Multipart, Path, Query, State},
     http::StatusCode,
+    response::Response,
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
@@ -15,13 +25,15 @@ use validator::Validate;
 
 pub fn businesses_routes(pool: PgPool) -> Router {
     Router::new()
-        .route("/onboard", post(onboard_business))
-        .route("/listBusinesses", get(list_businesses))
+        .route("/onboard", transactional(post(onboard_business)))
+        .route("/listBusinesses", conditional_route(get(list_businesses), CacheClass::Listing))
         .route("/:id", get(get_business_public_profile))
+        .route("/:id/schema.json", get(get_business_schema))
+        .route("/:id/branches", get(branches::list_branches).post(branches::create_branch))
         .route("/updateProfile", post(update_business_profile))
-        .route("/uploadLogo", post(upload_business_logo))
-        .route("/uploadProfilePicture", post(upload_business_profile_picture))
-        .route("/uploadCoverPhoto", post(upload_business_cover_photo))
+        .route("/uploadLogo", upload_route(post(upload_business_logo), &pool))
+        .route("/uploadProfilePicture", upload_route(post(upload_business_profile_picture), &pool))
+        .route("/uploadCoverPhoto", upload_route(post(upload_business_cover_photo), &pool))
         .with_state(pool)
 }
 
@@ -46,42 +58,46 @@ pub struct BusinessOnboardRequest {
 }
 
 pub async fn onboard_business(
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     State(pool): State<PgPool>,
+    mut tx: Tx,
     Json(payload): Json<BusinessOnboardRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
-    let exists = sqlx::query_scalar!(
-        "SELECT 1 FROM businesses WHERE user_id = $1",
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    if exists.is_none() {
-        return Err(AppError::BadRequest(
-            "Business not found. Please use the onboard endpoint to register first.".to_string(),
-        ));
-    }
-
-    let mut tx = pool.begin().await?;
+    let require_review = settings::flag(&pool, "listings.require_review").await;
 
+    // Creates the profile if the account has none yet, otherwise updates it.
+    // A first onboard (or a resubmission after rejection) goes back into the
+    // moderation queue while review is required. Only business accounts get
+    // a new profile: for anyone else the SELECT yields no row to insert.
     let record = sqlx::query!(
-        r#"UPDATE businesses SET
-            business_name = $1,
-            description = $2,
-            category = $3,
-            location = $4,
-            license_number = $5,
-            krapin = $6,
-            phone_number = $7,
-            email = $8,
-            website = $9,
-            whatsapp = $10,
-            profile_photo = COALESCE($11, profile_photo),
+        r#"INSERT INTO businesses (user_id, business_name, description, category, location,
+                                 license_number, krapin, phone_number, email, website, whatsapp,
+                                 profile_photo, listing_status, onboarding_completed)
+           SELECT u.id, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
+                  CASE WHEN $13 THEN 'pending_review' ELSE 'approved' END, TRUE
+           FROM users u
+           WHERE u.id = $12 AND u.role = 'business'
+           ON CONFLICT (user_id) DO UPDATE SET
+            business_name = EXCLUDED.business_name,
+            description = EXCLUDED.description,
+            category = EXCLUDED.category,
+            location = EXCLUDED.location,
+            license_number = EXCLUDED.license_number,
+            krapin = EXCLUDED.krapin,
+            phone_number = EXCLUDED.phone_number,
+            email = EXCLUDED.email,
+            website = EXCLUDED.website,
+            whatsapp = EXCLUDED.whatsapp,
+            profile_photo = COALESCE(EXCLUDED.profile_photo, businesses.profile_photo),
+            listing_status = CASE
+                WHEN $13 AND (NOT businesses.onboarding_completed OR businesses.listing_status = 'rejected')
+                    THEN 'pending_review'
+                ELSE businesses.listing_status
+            END,
             onboarding_completed = TRUE
-         WHERE user_id = $12 RETURNING id"#,
+           RETURNING id, listing_status, (xmax = 0) AS "created!""#,
         payload.business_name,
         payload.description,
         payload.category,
@@ -93,17 +109,26 @@ pub async fn onboard_business(
         payload.website,
         payload.whatsapp,
         payload.profile_photo,
-        user_id
+        user_id,
+        require_review
     )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| AppError::Internal(format!("Failed to update business: {}", e)))?;
-
-    tx.commit().await?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Forbidden("Only business accounts can onboard as a business".to_string()))?;
 
+    let (status, message) = if record.created {
+        (StatusCode::CREATED, "Business profile created successfully")
+    } else {
+        (StatusCode::OK, "Business onboarded successfully")
+    };
     Ok((
-        StatusCode::OK,
-        Json(json!({ "message": "Business onboarded successfully", "business_id": record.id })),
+        status,
+        Json(json!({
+            "message": message,
+            "business_id": record.id,
+            "listing_status": record.listing_status,
+            "created": record.created,
+        })),
     ))
 }
 
@@ -115,73 +140,73 @@ pub struct BusinessQuery {
 }
 
 #[derive(Serialize, Debug, sqlx::FromRow)]
-struct BusinessRecord {
+pub struct BusinessRecord {
     pub id: i32,
     pub business_name: String,
-    pub description: String,
+    pub description: Option<String>,
     pub category: Option<String>,
     pub location: Option<String>,
-    pub phone_number: String,
-    pub email: String,
+    pub phone_number: Option<String>,
+    pub email: Option<String>,
     pub website: Option<String>,
     pub whatsapp: Option<String>,
+    /// Only sent to signed-in callers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_favorited: Option<bool>,
 }
 
+/// Filters shared by the listing and its count. `$1` is an exact category,
+/// `$2` and `$3` are `ILIKE` patterns; unset filters are NULL.
+const LISTED_BUSINESSES_WHERE: &str = r#"
+    WHERE b.onboarding_completed = TRUE
+      AND b.listing_status = 'approved'
+      AND NOT user_is_hidden(b.user_id)
+      AND ($1::text IS NULL OR b.category = $1)
+      AND ($2::text IS NULL OR b.business_name ILIKE $2)
+      AND ($3::text IS NULL OR b.location ILIKE $3)
+"#;
+
+/// Approved businesses, newest first, paged with `?page=&per_page=`.
 pub async fn list_businesses(
     State(pool): State<PgPool>,
     Query(params): Query<BusinessQuery>,
-    CurrentUser { user_id }: CurrentUser,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let role = sqlx::query_scalar!(
-        "SELECT role FROM users WHERE id = $1",
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await?
-    .flatten()
-    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
-
-    if role == "business" || role == "provider" {
-        return Err(AppError::Forbidden(
-            "You are not authorized to view this resource".to_string(),
-        ));
-    }
-
-    let mut query = String::from(
-        "SELECT b.id, b.business_name, b.description, b.category, b.location, \
-         b.phone_number, b.email, b.website, b.whatsapp \
-         FROM businesses b JOIN users u ON b.user_id = u.id \
-         WHERE b.onboarding_completed = TRUE",
+    pagination: Pagination,
+    viewer: OptionalCurrentUser,
+) -> AppResult<(StatusCode, Json<Paginated<BusinessRecord>>)> {
+    let list_sql = format!(
+        r#"SELECT b.id, b.business_name, b.description, b.category, b.location,
+                  b.phone_number, b.email, b.website, b.whatsapp, {} AS is_favorited
+           FROM businesses b
+           {LISTED_BUSINESSES_WHERE}
+           ORDER BY b.created_at DESC NULLS LAST, b.id DESC
+           LIMIT $4 OFFSET $5"#,
+        favorited_by("business", "b.id", 6)
     );
-    let mut bindings: Vec<String> = Vec::new();
-    let mut param_index = 1;
-
-    if let Some(ref category) = params.category {
-        query.push_str(&format!(" AND b.category = ${}", param_index));
-        param_index += 1;
-        bindings.push(category.clone());
-    }
-    if let Some(ref name) = params.business_name {
-        query.push_str(&format!(" AND b.business_name ILIKE ${}", param_index));
-        param_index += 1;
-        bindings.push(format!("%{}%", name));
-    }
-    if let Some(ref location) = params.location {
-        query.push_str(&format!(" AND b.location ILIKE ${}", param_index));
-        bindings.push(format!("%{}%", location));
-    }
-
-    let mut q = sqlx::query_as::<_, BusinessRecord>(&query);
-    for bind in bindings {
-        q = q.bind(bind);
-    }
-
-    let businesses = q.fetch_all(&pool).await.map_err(AppError::Database)?;
-
-    Ok((
-        StatusCode::OK,
-        Json(json!({ "message": "Businesses fetched successfully", "businesses": businesses })),
-    ))
+    let count_sql = format!("SELECT COUNT(*) FROM businesses b {LISTED_BUSINESSES_WHERE}");
+    let category = params.category.as_deref().map(str::trim).filter(|v| !v.is_empty());
+    let pattern = |value: Option<&str>| {
+        value.map(str::trim).filter(|v| !v.is_empty()).map(|v| format!("%{}%", v))
+    };
+    let name = pattern(params.business_name.as_deref());
+    let location = pattern(params.location.as_deref());
+
+    let (businesses, total) = tokio::try_join!(
+        sqlx::query_as::<_, BusinessRecord>(&list_sql)
+            .bind(category)
+            .bind(&name)
+            .bind(&location)
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .bind(viewer.user_id())
+            .fetch_all(&pool),
+        sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(category)
+            .bind(&name)
+            .bind(&location)
+            .fetch_one(&pool),
+    )?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(businesses, pagination, total))))
 }
 
 #[derive(Deserialize, Debug, Validate)]
@@ -196,26 +221,14 @@ pub struct BusinessUpdateRequest {
 }
 
 pub async fn update_business_profile(
-    CurrentUser { user_id }: CurrentUser,
+    user: CurrentUser,
     State(pool): State<PgPool>,
     Json(payload): Json<BusinessUpdateRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let role = sqlx::query_scalar!(
-        "SELECT role FROM users WHERE id = $1",
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await?
-    .flatten()
-    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    user.require_role(&["business"])?;
+    let user_id = user.user_id;
 
-    if role != "business" {
-        return Err(AppError::Forbidden(
-            "You are not authorized to update this business".to_string(),
-        ));
-    }
-
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
     let mut query = String::from("UPDATE businesses SET ");
     let mut updates: Vec<String> = Vec::new();
@@ -266,32 +279,18 @@ pub async fn update_business_profile(
     Ok((StatusCode::OK, Json(json!({ "message": "Business profile updated successfully" }))))
 }
 
-async fn check_business_role(pool: &PgPool, user_id: i32) -> AppResult<()> {
-    let role = sqlx::query_scalar!("SELECT role FROM users WHERE id = $1", user_id)
-        .fetch_optional(pool)
-        .await?
-        .flatten()
-        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
-
-    if role != "business" {
-        return Err(AppError::Forbidden(
-            "You are not authorized to perform this action".to_string(),
-        ));
-    }
-    Ok(())
-}
-
 pub async fn upload_business_logo(
-    CurrentUser { user_id }: CurrentUser,
+    user: CurrentUser,
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
     multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    check_business_role(&pool, user_id).await?;
+    user.require_role(&["business"])?;
+    let user_id = user.user_id;
 
-    let (data, ext, _ct) = parse_image_from_multipart(multipart).await?;
+    let (data, ext, content_type) = parse_image_from_multipart(&pool, multipart).await?;
     let key = generate_key("businesses/logos", &ext);
-    let url = storage.save(&key, &data).await?;
+    let url = storage.save(&key, &data, &content_type).await?;
 
     let result = sqlx::query!(
         "UPDATE businesses SET logo = $1 WHERE user_id = $2",
@@ -305,18 +304,20 @@ pub async fn upload_business_logo(
         return Err(AppError::Database(e));
     }
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Logo uploaded successfully", "logo": url }))))
+    let variants = store_image_variants(&pool, &storage, &key, &url, data).await;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Logo uploaded successfully", "logo": url, "thumb_url": variants.thumb_url, "medium_url": variants.medium_url }))))
 }
 
 pub async fn upload_business_profile_picture(
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
     multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let (data, ext, _ct) = parse_image_from_multipart(multipart).await?;
+    let (data, ext, content_type) = parse_image_from_multipart(&pool, multipart).await?;
     let key = generate_key("businesses/profile_pictures", &ext);
-    let url = storage.save(&key, &data).await?;
+    let url = storage.save(&key, &data, &content_type).await?;
 
     let result = sqlx::query!(
         "UPDATE businesses SET profile_photo = $1 WHERE user_id = $2",
@@ -330,18 +331,20 @@ pub async fn upload_business_profile_picture(
         return Err(AppError::Database(e));
     }
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Profile picture uploaded successfully", "profile_picture": url }))))
+    let variants = store_image_variants(&pool, &storage, &key, &url, data).await;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Profile picture uploaded successfully", "profile_picture": url, "thumb_url": variants.thumb_url, "medium_url": variants.medium_url }))))
 }
 
 pub async fn upload_business_cover_photo(
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
     multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let (data, ext, _ct) = parse_image_from_multipart(multipart).await?;
+    let (data, ext, content_type) = parse_image_from_multipart(&pool, multipart).await?;
     let key = generate_key("businesses/cover_photos", &ext);
-    let url = storage.save(&key, &data).await?;
+    let url = storage.save(&key, &data, &content_type).await?;
 
     let result = sqlx::query!(
         "UPDATE businesses SET cover_photo = $1 WHERE user_id = $2",
@@ -355,7 +358,9 @@ pub async fn upload_business_cover_photo(
         return Err(AppError::Database(e));
     }
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Cover photo uploaded successfully", "cover_photo": url }))))
+    let variants = store_image_variants(&pool, &storage, &key, &url, data).await;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Cover photo uploaded successfully", "cover_photo": url, "thumb_url": variants.thumb_url, "medium_url": variants.medium_url }))))
 }
 
 // ── Public profile ────────────────────────────────────────────────────────────
@@ -372,29 +377,49 @@ struct BusinessPublicProfile {
     website: Option<String>,
     whatsapp: Option<String>,
     logo: Option<String>,
+    logo_thumb_url: Option<String>,
     profile_photo: Option<String>,
+    profile_photo_thumb_url: Option<String>,
+    profile_photo_medium_url: Option<String>,
     cover_photo: Option<String>,
+    cover_photo_medium_url: Option<String>,
     onboarding_completed: bool,
+    listing_status: String,
     avg_rating: Option<f64>,
     review_count: Option<i64>,
+    /// Only sent to signed-in callers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_favorited: Option<bool>,
 }
 
+/// Listings still under review (or rejected) are only visible to their owner.
 pub async fn get_business_public_profile(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
+    viewer: OptionalCurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let profile = sqlx::query_as::<_, BusinessPublicProfile>(
+    let viewer_id = viewer.user_id();
+
+    let profile = sqlx::query_as::<_, BusinessPublicProfile>(&format!(
         r#"SELECT b.id, b.business_name, b.description, b.category, b.location,
                   b.phone_number, b.email, b.website, b.whatsapp,
-                  b.logo, b.profile_photo, b.cover_photo, b.onboarding_completed,
+                  b.logo, b.profile_photo, b.cover_photo, b.onboarding_completed, b.listing_status,
+                  image_variant(b.logo, 'thumb') AS logo_thumb_url,
+                  image_variant(b.profile_photo, 'thumb') AS profile_photo_thumb_url,
+                  image_variant(b.profile_photo, 'medium') AS profile_photo_medium_url,
+                  image_variant(b.cover_photo, 'medium') AS cover_photo_medium_url,
                   ROUND(AVG(r.rating)::numeric, 1)::float8 AS avg_rating,
-                  COUNT(r.id) AS review_count
+                  COUNT(r.id) AS review_count,
+                  {} AS is_favorited
            FROM businesses b
-           LEFT JOIN reviews r ON r.target_id = b.id AND r.target_type = 'business'
-           WHERE b.id = $1
+           LEFT JOIN reviews r ON r.target_id = b.id AND r.target_type = 'business' AND r.hidden = FALSE
+           WHERE b.id = $1 AND NOT user_is_hidden(b.user_id)
+             AND (b.listing_status = 'approved' OR b.user_id = $2)
            GROUP BY b.id"#,
-    )
+        favorited_by("business", "b.id", 2)
+    ))
     .bind(id)
+    .bind(viewer_id)
     .fetch_optional(&pool)
     .await?
     .ok_or_else(|| AppError::NotFound("Business not found".to_string()))?;
@@ -430,7 +455,7 @@ pub async fn get_business_public_profile(
            JOIN wards w ON bb.ward_id = w.id
            JOIN constituencies c ON w.constituency_id = c.id
            JOIN counties co ON c.county_id = co.id
-           WHERE bb.business_id = $1"#,
+           WHERE bb.business_id = $1 AND bb.is_active"#,
         id
     )
     .fetch_all(&pool)
@@ -457,3 +482,12 @@ pub async fn get_business_public_profile(
         "branches": branches_json,
     }))))
 }
+
+/// schema.org `LocalBusiness` JSON-LD for the business's page, for search
+/// engines. Only listed businesses have one.
+pub async fn get_business_schema(State(pool): State<PgPool>, Path(id): Path<i32>) -> AppResult<Response> {
+    let doc = structured_data::business(&pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Business not found".to_string()))?;
+    Ok(structured_data::json_ld(doc))
+}
@@ -1,8 +1,12 @@
 use crate::errors::{AppError, AppResult};
+use crate::extractors::current_user::CurrentUser;
+use crate::utils::etag::{self, CacheClass};
+use crate::utils::onboarding;
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
+    response::Response,
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
@@ -29,7 +33,13 @@ pub struct CategoryWithParent {
 
 pub async fn get_categories(
     State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let etag = etag::dataset_etag(&pool, etag::CATEGORIES).await?;
+    if etag::matches(&headers, &etag) {
+        return Ok(etag::not_modified(&etag, CacheClass::Reference));
+    }
+
     let categories = sqlx::query_as!(
         CategoryWithParent,
         r#"SELECT c.id, c.name AS category_name, c.parent_id, p.name AS parent_name
@@ -38,7 +48,7 @@ pub async fn get_categories(
     .fetch_all(&pool)
     .await?;
 
-    Ok((StatusCode::OK, Json(json!({ "categories": categories }))))
+    Ok(etag::tagged(&etag, CacheClass::Reference, Json(json!({ "categories": categories }))))
 }
 
 #[derive(Deserialize, Serialize, sqlx::FromRow)]
@@ -50,7 +60,13 @@ pub struct Category {
 pub async fn get_subcategories_by_category_id(
     Path(parent_id): Path<i32>,
     State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let etag = etag::dataset_etag(&pool, etag::CATEGORIES).await?;
+    if etag::matches(&headers, &etag) {
+        return Ok(etag::not_modified(&etag, CacheClass::Reference));
+    }
+
     let subcategories = sqlx::query_as::<_, Category>(
         "SELECT id, name FROM categories WHERE parent_id = $1",
     )
@@ -58,7 +74,7 @@ pub async fn get_subcategories_by_category_id(
     .fetch_all(&pool)
     .await?;
 
-    Ok((StatusCode::OK, Json(json!({ "subcategories": subcategories }))))
+    Ok(etag::tagged(&etag, CacheClass::Reference, Json(json!({ "subcategories": subcategories }))))
 }
 
 #[derive(Serialize, Deserialize, sqlx::FromRow, Debug)]
@@ -154,30 +170,63 @@ pub struct CategoryAssignment {
     category_ids: Vec<i32>,
 }
 
+/// Replaces the categories of a listing the caller owns. The first id
+/// becomes the listing's main `category`.
 pub async fn assign_categories(
     State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<CategoryAssignment>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let target_type = payload.target_type.to_lowercase();
     if target_type != "provider" && target_type != "business" {
         return Err(AppError::BadRequest("Invalid target type. Must be 'provider' or 'business'".to_string()));
     }
-    if payload.category_ids.is_empty() {
+    let mut category_ids: Vec<i32> = Vec::with_capacity(payload.category_ids.len());
+    for id in payload.category_ids {
+        if !category_ids.contains(&id) {
+            category_ids.push(id);
+        }
+    }
+    if category_ids.is_empty() {
         return Err(AppError::BadRequest("No category IDs provided".to_string()));
     }
-    if payload.category_ids.len() > 5 {
+    if category_ids.len() > 5 {
         return Err(AppError::BadRequest("You can assign a maximum of 5 categories".to_string()));
     }
     if payload.target_id <= 0 {
         return Err(AppError::BadRequest("Invalid target ID".to_string()));
     }
 
-    let top_category_name = sqlx::query_scalar!(
-        "SELECT name FROM categories WHERE id = $1",
-        payload.category_ids[0]
-    )
-    .fetch_one(&pool)
-    .await?;
+    let owned = match target_type.as_str() {
+        "provider" => sqlx::query_scalar!(
+            "SELECT id FROM providers WHERE id = $1 AND user_id = $2",
+            payload.target_id, user_id
+        ).fetch_optional(&pool).await?,
+        _ => sqlx::query_scalar!(
+            "SELECT id FROM businesses WHERE id = $1 AND user_id = $2",
+            payload.target_id, user_id
+        ).fetch_optional(&pool).await?,
+    };
+    if owned.is_none() {
+        return Err(AppError::Forbidden("You are not authorized to assign categories to this target".to_string()));
+    }
+
+    let found = sqlx::query!("SELECT id, name FROM categories WHERE id = ANY($1)", &category_ids)
+        .fetch_all(&pool)
+        .await?;
+    let categories: Vec<serde_json::Value> = category_ids
+        .iter()
+        .filter_map(|id| found.iter().find(|c| c.id == *id))
+        .map(|c| json!({ "id": c.id, "name": c.name }))
+        .collect();
+    let unknown: Vec<String> = category_ids
+        .iter()
+        .filter(|id| !found.iter().any(|c| c.id == **id))
+        .map(|id| id.to_string())
+        .collect();
+    if !unknown.is_empty() {
+        return Err(AppError::BadRequest(format!("Unknown category IDs: {}", unknown.join(", "))));
+    }
 
     let mut tx = pool.begin().await?;
 
@@ -197,26 +246,29 @@ pub async fn assign_categories(
         _ => unreachable!(),
     };
     sqlx::query(update_query)
-        .bind(&top_category_name)
+        .bind(categories[0]["name"].as_str())
         .bind(payload.target_id)
         .execute(&mut *tx)
         .await?;
 
     let insert_query = match target_type.as_str() {
-        "provider" => "INSERT INTO provider_categories (provider_id, category_id) VALUES ($1, $2)",
-        "business" => "INSERT INTO business_categories (business_id, category_id) VALUES ($1, $2)",
+        "provider" => "INSERT INTO provider_categories (provider_id, category_id) SELECT $1, UNNEST($2::int4[])",
+        "business" => "INSERT INTO business_categories (business_id, category_id) SELECT $1, UNNEST($2::int4[])",
         _ => unreachable!(),
     };
-    for &cat_id in &payload.category_ids {
-        sqlx::query(insert_query)
-            .bind(payload.target_id)
-            .bind(cat_id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| AppError::Internal(format!("Failed to assign category {}: {}", cat_id, e)))?;
-    }
+    sqlx::query(insert_query)
+        .bind(payload.target_id)
+        .bind(&category_ids)
+        .execute(&mut *tx)
+        .await?;
 
     tx.commit().await?;
+    if target_type == "provider" {
+        onboarding::refresh_score(&pool, payload.target_id).await;
+    }
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Categories assigned successfully" }))))
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Categories assigned successfully", "categories": categories })),
+    ))
 }
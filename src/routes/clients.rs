@@ -1,95 +1,778 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::utils::body_limit::upload_route;
 use crate::utils::image_upload::parse_image_from_multipart;
+use crate::utils::mpesa::normalize_phone;
+use crate::utils::pagination::{Paginated, Pagination};
+use crate::utils::prefs;
 use crate::utils::storage::{SharedStorage, generate_key};
+use crate::utils::takeout::{self, TakeoutSection};
+use crate::utils::thumbnails::store_image_variants;
 use axum::{
     Extension, Json, Router,
-    extract::{Multipart, State},
-    http::StatusCode,
-    routing::{get, post},
+    extract::{Multipart, Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
 };
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
 
 pub fn client_routes(pool: PgPool) -> Router {
     Router::new()
-        .route("/uploadProfilePicture", post(upload_profile_picture))
+        .route("/uploadProfilePicture", upload_route(post(upload_profile_picture), &pool))
+        .route("/me", get(get_my_profile))
+        .route("/updateProfile", post(update_my_profile))
         .route("/me/profile", get(get_my_profile).put(update_my_profile))
+        .route("/bookings", get(get_booking_history))
+        .route("/activity", get(get_activity))
+        .route("/export", get(export_my_data))
+        .route("/privacy", get(get_privacy_summary))
+        .route("/preferences", get(get_preferences).post(update_preferences))
+        .route("/addresses", get(list_addresses).post(create_address))
+        .route("/addresses/:id", put(update_address).delete(delete_address))
         .with_state(pool)
 }
 
 // ── Profile ───────────────────────────────────────────────────────────────────
 
+const DISPLAY_NAME_LEN: std::ops::RangeInclusive<usize> = 2..=50;
+const MAX_BIO_LEN: usize = 500;
+const MAX_LOCATION_LEN: usize = 100;
+
 #[derive(Serialize, sqlx::FromRow, Debug)]
 pub struct ClientProfile {
     pub id: i32,
+    pub user_id: i32,
     pub username: String,
+    pub display_name: Option<String>,
     pub email: String,
+    pub email_verified: bool,
+    pub created_at: Option<NaiveDateTime>,
     pub profile_picture: Option<String>,
+    pub profile_picture_thumb_url: Option<String>,
+    pub profile_picture_medium_url: Option<String>,
     pub phone: Option<String>,
     pub bio: Option<String>,
     pub location: Option<String>,
+    pub sms_notifications: bool,
+    pub email_notifications: bool,
 }
 
+/// Omitted fields are left alone; an empty string clears a text field.
 #[derive(Deserialize, Debug)]
 pub struct UpdateProfileInput {
+    pub display_name: Option<String>,
     pub phone: Option<String>,
     pub bio: Option<String>,
+    /// Default location for searches and bookings
     pub location: Option<String>,
+    pub sms_notifications: Option<bool>,
+    pub email_notifications: Option<bool>,
 }
 
-pub async fn get_my_profile(
-    State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let profile = sqlx::query_as!(
+impl UpdateProfileInput {
+    /// Trims the text fields, checks their lengths and normalizes the phone
+    /// number to 254XXXXXXXXX.
+    fn validated(self) -> AppResult<UpdateProfileInput> {
+        let trim = |v: Option<String>| v.map(|v| v.trim().to_string());
+        let display_name = trim(self.display_name);
+        let bio = trim(self.bio);
+        let location = trim(self.location);
+
+        if let Some(name) = display_name.as_deref().filter(|n| !n.is_empty()) {
+            if !DISPLAY_NAME_LEN.contains(&name.chars().count()) {
+                return Err(AppError::BadRequest(format!(
+                    "Display name must be {} to {} characters",
+                    DISPLAY_NAME_LEN.start(),
+                    DISPLAY_NAME_LEN.end()
+                )));
+            }
+        }
+        if bio.as_deref().is_some_and(|b| b.chars().count() > MAX_BIO_LEN) {
+            return Err(AppError::BadRequest(format!("Bio must be at most {MAX_BIO_LEN} characters")));
+        }
+        if location.as_deref().is_some_and(|l| l.chars().count() > MAX_LOCATION_LEN) {
+            return Err(AppError::BadRequest(format!("Location must be at most {MAX_LOCATION_LEN} characters")));
+        }
+        let phone = match trim(self.phone) {
+            Some(phone) if !phone.is_empty() => Some(normalize_phone(&phone)?),
+            other => other,
+        };
+
+        Ok(UpdateProfileInput { display_name, phone, bio, location, ..self })
+    }
+}
+
+async fn fetch_profile(pool: &PgPool, user_id: i32) -> AppResult<ClientProfile> {
+    sqlx::query_as!(
         ClientProfile,
-        r#"SELECT c.id, u.username, u.email,
-                  c.profile_picture, c.phone, c.bio, c.location
+        r#"SELECT c.id, c.user_id, u.username, u.display_name, u.email, u.email_verified, u.created_at,
+                  c.profile_picture, c.phone, c.bio, c.location,
+                  c.sms_notifications, c.email_notifications,
+                  image_variant(c.profile_picture, 'thumb') AS profile_picture_thumb_url,
+                  image_variant(c.profile_picture, 'medium') AS profile_picture_medium_url
            FROM clients c
            JOIN users u ON u.id = c.user_id
            WHERE c.user_id = $1"#,
         user_id
     )
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
-    .ok_or_else(|| AppError::NotFound("Client profile not found".to_string()))?;
+    .ok_or_else(|| AppError::NotFound("Client profile not found".to_string()))
+}
 
+pub async fn get_my_profile(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let profile = fetch_profile(&pool, user_id).await?;
     Ok((StatusCode::OK, Json(json!({ "profile": profile }))))
 }
 
+/// Updates the display name on the user and the rest on the client profile,
+/// together.
 pub async fn update_my_profile(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<UpdateProfileInput>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    sqlx::query!(
+    let payload = payload.validated()?;
+
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query!(
         r#"UPDATE clients
-           SET phone    = COALESCE($1, phone),
-               bio      = COALESCE($2, bio),
-               location = COALESCE($3, location)
-           WHERE user_id = $4"#,
+           SET phone               = CASE WHEN $1::text IS NULL THEN phone ELSE NULLIF($1, '') END,
+               bio                 = CASE WHEN $2::text IS NULL THEN bio ELSE NULLIF($2, '') END,
+               location            = CASE WHEN $3::text IS NULL THEN location ELSE NULLIF($3, '') END,
+               sms_notifications   = COALESCE($4, sms_notifications),
+               email_notifications = COALESCE($5, email_notifications)
+           WHERE user_id = $6"#,
         payload.phone,
         payload.bio,
         payload.location,
+        payload.sms_notifications,
+        payload.email_notifications,
         user_id
     )
+    .execute(&mut *tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Client profile not found".to_string()));
+    }
+
+    if let Some(display_name) = payload.display_name {
+        sqlx::query!(
+            "UPDATE users SET display_name = NULLIF($1, '') WHERE id = $2",
+            display_name,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    let profile = fetch_profile(&pool, user_id).await?;
+    Ok((StatusCode::OK, Json(json!({ "message": "Profile updated successfully", "profile": profile }))))
+}
+
+// ── Preferences ───────────────────────────────────────────────────────────────
+
+pub async fn get_preferences(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let preferences = prefs::all(&pool, user_id).await;
+    Ok((StatusCode::OK, Json(json!({ "preferences": preferences }))))
+}
+
+/// Sets one or more preferences, given as `{ "key": value, ... }`. The whole
+/// batch is rejected if any key is unknown or a value has the wrong type.
+pub async fn update_preferences(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<serde_json::Map<String, serde_json::Value>>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if payload.is_empty() {
+        return Err(AppError::BadRequest("No preferences given".to_string()));
+    }
+
+    let mut updates = serde_json::Map::with_capacity(payload.len());
+    for (key, value) in &payload {
+        let def = prefs::definition(key)
+            .ok_or_else(|| AppError::BadRequest(format!("Unknown preference '{key}'")))?;
+        let value = def.validate(value).map_err(AppError::BadRequest)?;
+        updates.insert(def.key.to_string(), value);
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO user_preferences (user_id, prefs, updated_at)
+           VALUES ($1, $2, NOW())
+           ON CONFLICT (user_id) DO UPDATE
+           SET prefs = user_preferences.prefs || EXCLUDED.prefs, updated_at = NOW()"#,
+        user_id,
+        serde_json::Value::Object(updates)
+    )
     .execute(&pool)
     .await?;
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Profile updated successfully" }))))
+    let preferences = prefs::all(&pool, user_id).await;
+    Ok((StatusCode::OK, Json(json!({ "message": "Preferences updated", "preferences": preferences }))))
+}
+
+// ── Address book ──────────────────────────────────────────────────────────────
+
+const MAX_ADDRESSES: i64 = 5;
+const MAX_LABEL_LEN: usize = 30;
+const MAX_ADDRESS_LEN: usize = 255;
+
+#[derive(Serialize, Debug)]
+pub struct ClientAddress {
+    pub id: i32,
+    pub label: String,
+    pub ward_id: Option<i32>,
+    pub ward_name: Option<String>,
+    pub address: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub phone: Option<String>,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body for both adding and replacing an address.
+#[derive(Deserialize, Debug)]
+pub struct AddressInput {
+    pub label: String,
+    pub ward_id: Option<i32>,
+    pub address: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl AddressInput {
+    /// Trims and checks the fields and normalizes the phone.
+    async fn validated(self, pool: &PgPool) -> AppResult<AddressInput> {
+        let label = self.label.trim().to_string();
+        let address = self.address.trim().to_string();
+        if label.is_empty() || label.chars().count() > MAX_LABEL_LEN {
+            return Err(AppError::BadRequest(format!("Label must be 1 to {MAX_LABEL_LEN} characters")));
+        }
+        if address.is_empty() || address.chars().count() > MAX_ADDRESS_LEN {
+            return Err(AppError::BadRequest(format!("Address must be 1 to {MAX_ADDRESS_LEN} characters")));
+        }
+        match (self.latitude, self.longitude) {
+            (Some(lat), Some(lng)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) => {}
+            (None, None) => {}
+            _ => {
+                return Err(AppError::BadRequest(
+                    "latitude and longitude must be given together and be valid coordinates".to_string(),
+                ));
+            }
+        }
+        if let Some(ward_id) = self.ward_id {
+            let exists = sqlx::query_scalar!("SELECT id FROM wards WHERE id = $1", ward_id)
+                .fetch_optional(pool)
+                .await?;
+            if exists.is_none() {
+                return Err(AppError::BadRequest("Ward not found".to_string()));
+            }
+        }
+        let phone = match self.phone.as_deref().map(str::trim) {
+            Some(phone) if !phone.is_empty() => Some(normalize_phone(phone)?),
+            _ => None,
+        };
+
+        Ok(AddressInput { label, address, phone, ..self })
+    }
+}
+
+async fn fetch_addresses(pool: &PgPool, user_id: i32) -> AppResult<Vec<ClientAddress>> {
+    let addresses = sqlx::query_as!(
+        ClientAddress,
+        r#"SELECT a.id, a.label, a.ward_id, w.name AS "ward_name?", a.address, a.latitude, a.longitude,
+                  a.phone, a.is_default, a.created_at, a.updated_at
+           FROM client_addresses a
+           LEFT JOIN wards w ON w.id = a.ward_id
+           WHERE a.user_id = $1
+           ORDER BY a.is_default DESC, a.created_at, a.id"#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(addresses)
+}
+
+/// Makes `address_id` the user's only default. Runs inside the caller's
+/// transaction so the partial unique index never sees two defaults.
+async fn set_default_address(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+    address_id: i32,
+) -> AppResult<()> {
+    sqlx::query!(
+        "UPDATE client_addresses SET is_default = FALSE WHERE user_id = $1 AND is_default AND id <> $2",
+        user_id,
+        address_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!("UPDATE client_addresses SET is_default = TRUE WHERE id = $1", address_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_addresses(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let addresses = fetch_addresses(&pool, user_id).await?;
+    Ok((StatusCode::OK, Json(json!({ "addresses": addresses }))))
+}
+
+/// Saves a new address. The first one becomes the default; a later one only
+/// when `is_default` is set.
+pub async fn create_address(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<AddressInput>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let input = payload.validated(&pool).await?;
+
+    let mut tx = pool.begin().await?;
+    // Serializes concurrent adds by the same user so the cap holds
+    sqlx::query!("SELECT id FROM users WHERE id = $1 FOR UPDATE", user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM client_addresses WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if count >= MAX_ADDRESSES {
+        return Err(AppError::BadRequest(format!(
+            "You can save at most {MAX_ADDRESSES} addresses. Delete one first."
+        )));
+    }
+
+    let id = sqlx::query_scalar!(
+        r#"INSERT INTO client_addresses (user_id, label, ward_id, address, latitude, longitude, phone)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           RETURNING id"#,
+        user_id,
+        input.label,
+        input.ward_id,
+        input.address,
+        input.latitude,
+        input.longitude,
+        input.phone
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if input.is_default || count == 0 {
+        set_default_address(&mut tx, user_id, id).await?;
+    }
+    tx.commit().await?;
+
+    let addresses = fetch_addresses(&pool, user_id).await?;
+    Ok((StatusCode::CREATED, Json(json!({ "message": "Address saved", "id": id, "addresses": addresses }))))
+}
+
+/// Replaces an address. Bookings already made with it keep their copy.
+pub async fn update_address(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<AddressInput>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let input = payload.validated(&pool).await?;
+
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query!(
+        r#"UPDATE client_addresses
+           SET label = $3, ward_id = $4, address = $5, latitude = $6, longitude = $7, phone = $8, updated_at = NOW()
+           WHERE id = $1 AND user_id = $2"#,
+        id,
+        user_id,
+        input.label,
+        input.ward_id,
+        input.address,
+        input.latitude,
+        input.longitude,
+        input.phone
+    )
+    .execute(&mut *tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Address not found".to_string()));
+    }
+    if input.is_default {
+        set_default_address(&mut tx, user_id, id).await?;
+    }
+    tx.commit().await?;
+
+    let addresses = fetch_addresses(&pool, user_id).await?;
+    Ok((StatusCode::OK, Json(json!({ "message": "Address updated", "addresses": addresses }))))
+}
+
+/// Deletes an address. If it was the default, the oldest remaining one takes
+/// over. Bookings made with it keep their copy of the address.
+pub async fn delete_address(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let mut tx = pool.begin().await?;
+    let was_default = sqlx::query_scalar!(
+        "DELETE FROM client_addresses WHERE id = $1 AND user_id = $2 RETURNING is_default",
+        id,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Address not found".to_string()))?;
+
+    if was_default {
+        let next = sqlx::query_scalar!(
+            "SELECT id FROM client_addresses WHERE user_id = $1 ORDER BY created_at, id LIMIT 1",
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(next) = next {
+            set_default_address(&mut tx, user_id, next).await?;
+        }
+    }
+    tx.commit().await?;
+
+    let addresses = fetch_addresses(&pool, user_id).await?;
+    Ok((StatusCode::OK, Json(json!({ "message": "Address deleted", "addresses": addresses }))))
+}
+
+// ── Booking history ───────────────────────────────────────────────────────────
+
+/// Bookings that are still ahead of the client, or under way: pending or
+/// confirmed and not yet over ($2 = now). Everything else, including
+/// cancelled and expired bookings, is past.
+const UPCOMING_BOOKING: &str = "b.status IN ('pending', 'confirmed')
+     AND b.scheduled_time + make_interval(mins => COALESCE(b.duration, 60)) >= $2";
+
+#[derive(Deserialize, Debug)]
+pub struct BookingHistoryQuery {
+    /// `upcoming` (default) or `past`
+    pub scope: Option<String>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct ClientBooking {
+    pub id: i32,
+    pub confirmation_code: String,
+    pub status: String,
+    pub scheduled_time: NaiveDateTime,
+    pub duration: Option<i32>,
+    pub service_id: Option<i32>,
+    pub service_name: Option<String>,
+    pub cancel_reason: Option<String>,
+    pub target_type: String,
+    pub target_id: i32,
+    pub target_name: Option<String>,
+    pub target_photo: Option<String>,
+    pub target_photo_thumb_url: Option<String>,
+    /// The branch address for branch bookings, else the listing's location.
+    pub target_location: Option<String>,
+    pub branch_id: Option<i32>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// The caller's bookings as a client, with what the "My bookings" screen
+/// shows for each. Upcoming bookings come soonest first, past ones latest first.
+pub async fn get_booking_history(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Query(params): Query<BookingHistoryQuery>,
+    pagination: Pagination,
+) -> AppResult<(StatusCode, Json<Paginated<ClientBooking>>)> {
+    let (scope, order) = match params.scope.as_deref().unwrap_or("upcoming") {
+        "upcoming" => (UPCOMING_BOOKING.to_string(), "ASC"),
+        "past" => (format!("NOT ({UPCOMING_BOOKING})"), "DESC"),
+        _ => return Err(AppError::BadRequest("scope must be 'upcoming' or 'past'".to_string())),
+    };
+    let now = Utc::now().naive_utc();
+
+    let list_sql = format!(
+        r#"SELECT b.id, b.confirmation_code, b.status, b.scheduled_time, b.duration, b.service_id,
+                  COALESCE(s.title, b.service_description) AS service_name,
+                  b.cancel_reason, b.target_type, b.target_id, b.branch_id, b.created_at,
+                  COALESCE(p.service_name, biz.business_name) AS target_name,
+                  COALESCE(p.profile_photo, biz.logo, biz.profile_photo) AS target_photo,
+                  image_variant(COALESCE(p.profile_photo, biz.logo, biz.profile_photo), 'thumb') AS target_photo_thumb_url,
+                  COALESCE(br.address, p.location, biz.location) AS target_location
+           FROM bookings b
+           LEFT JOIN services s ON s.id = b.service_id
+           LEFT JOIN providers p ON b.target_type = 'provider' AND p.id = b.target_id
+           LEFT JOIN businesses biz ON b.target_type = 'business' AND biz.id = b.target_id
+           LEFT JOIN business_branches br ON br.id = b.branch_id
+           WHERE b.client_id = $1 AND {scope}
+           ORDER BY b.scheduled_time {order}, b.id {order}
+           LIMIT $3 OFFSET $4"#
+    );
+    let count_sql = format!("SELECT COUNT(*) FROM bookings b WHERE b.client_id = $1 AND {scope}");
+
+    let (bookings, total) = tokio::try_join!(
+        sqlx::query_as::<_, ClientBooking>(&list_sql)
+            .bind(user_id)
+            .bind(now)
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&pool),
+        sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(user_id)
+            .bind(now)
+            .fetch_one(&pool),
+    )?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(bookings, pagination, total))))
+}
+
+// ── Activity feed ─────────────────────────────────────────────────────────────
+
+const ACTIVITY_PAGE: i64 = 20;
+const MAX_ACTIVITY_PAGE: i64 = 50;
+
+#[derive(Deserialize, Debug)]
+pub struct ActivityQuery {
+    /// Only items strictly older than this; the previous page's `next_cursor`.
+    pub before: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ActivityItem {
+    /// `notification`, `booking_event` or `review_reply`
+    pub kind: String,
+    /// Row id within its kind
+    pub id: i64,
+    /// The notification type, booking event type, or `review_reply`
+    pub activity_type: String,
+    pub title: String,
+    pub body: String,
+    /// What to open when the item is tapped
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
+    pub data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Recent activity for the caller, newest first: their notifications, plus
+/// changes others made to their bookings and replies to their reviews. Booking
+/// events and replies the caller was already notified about are left out, so
+/// each happening shows once. Each branch is limited before the merge.
+pub async fn get_activity(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Query(params): Query<ActivityQuery>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let limit = params.limit.unwrap_or(ACTIVITY_PAGE).clamp(1, MAX_ACTIVITY_PAGE);
+
+    let items = sqlx::query_as!(
+        ActivityItem,
+        r#"SELECT kind AS "kind!", id AS "id!", activity_type AS "activity_type!", title AS "title!",
+                  body AS "body!", target_type, target_id, data AS "data!", created_at AS "created_at!"
+           FROM (
+               (SELECT 'notification' AS kind, n.id::bigint AS id, n.notif_type::text AS activity_type,
+                       n.title, n.body, n.target_type::text AS target_type, n.target_id,
+                       COALESCE(n.data, '{}'::jsonb) AS data, n.created_at AT TIME ZONE 'UTC' AS created_at
+                FROM notifications n
+                WHERE n.user_id = $1
+                  AND ($2::timestamptz IS NULL OR n.created_at < $2 AT TIME ZONE 'UTC')
+                ORDER BY n.created_at DESC
+                LIMIT $3)
+               UNION ALL
+               (SELECT 'booking_event', e.id, e.event_type::text,
+                       'Booking ' || replace(COALESCE(e.to_status, e.event_type), '_', ' '),
+                       COALESCE(s.title, b.service_description, 'Booking') || ' · ' || b.confirmation_code,
+                       'booking', b.id,
+                       jsonb_build_object('booking_id', b.id, 'confirmation_code', b.confirmation_code,
+                                          'from_status', e.from_status, 'to_status', e.to_status),
+                       e.created_at
+                FROM booking_events e
+                JOIN bookings b ON b.id = e.booking_id
+                LEFT JOIN services s ON s.id = b.service_id
+                WHERE b.client_id = $1
+                  AND e.actor_id IS DISTINCT FROM $1
+                  AND ($2::timestamptz IS NULL OR e.created_at < $2)
+                  AND NOT EXISTS (
+                      SELECT 1 FROM notifications n
+                      WHERE n.user_id = $1 AND n.target_type = 'booking' AND n.target_id = b.id
+                        AND n.created_at AT TIME ZONE 'UTC' BETWEEN e.created_at - INTERVAL '1 minute'
+                                                              AND e.created_at + INTERVAL '1 minute')
+                ORDER BY e.created_at DESC
+                LIMIT $3)
+               UNION ALL
+               (SELECT 'review_reply', rr.id::bigint, 'review_reply', 'Reply to your review', rr.comment,
+                       'review', r.id,
+                       jsonb_build_object('review_id', r.id, 'reply_id', rr.id,
+                                          'listing_type', r.target_type, 'listing_id', r.target_id),
+                       rr.created_at AT TIME ZONE 'UTC'
+                FROM review_replies rr
+                JOIN reviews r ON r.id = rr.review_id
+                WHERE r.reviewer_id = $1
+                  AND rr.reviewer_id <> $1
+                  AND ($2::timestamptz IS NULL OR rr.created_at < $2 AT TIME ZONE 'UTC')
+                  AND NOT EXISTS (
+                      SELECT 1 FROM notifications n
+                      WHERE n.user_id = $1 AND n.notif_type = 'review_reply'
+                        AND n.target_type = 'review' AND n.target_id = r.id)
+                ORDER BY rr.created_at DESC
+                LIMIT $3)
+           ) activity
+           ORDER BY created_at DESC, kind, id DESC
+           LIMIT $3"#,
+        user_id,
+        params.before,
+        limit
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let next_cursor = (items.len() as i64 == limit).then(|| items.last().map(|item| item.created_at)).flatten();
+    Ok((StatusCode::OK, Json(json!({ "items": items, "next_cursor": next_cursor }))))
+}
+
+// ── Data export and privacy summary ───────────────────────────────────────────
+
+/// What a client's export holds, in document order.
+const CLIENT_TAKEOUT: [TakeoutSection; 9] = [
+    TakeoutSection {
+        name: "profile",
+        description: "Account and profile details, notification settings and preferences",
+        fields: &[
+            "id", "username", "display_name", "email", "email_verified", "created_at", "last_login_at",
+            "phone", "bio", "location", "profile_picture", "sms_notifications", "email_notifications",
+            "preferences",
+        ],
+        sql: r#"SELECT u.id, u.username, u.display_name, u.email, u.email_verified, u.created_at, u.last_login_at,
+                       c.phone, c.bio, c.location, c.profile_picture, c.sms_notifications, c.email_notifications,
+                       up.prefs AS preferences
+                FROM users u
+                LEFT JOIN clients c ON c.user_id = u.id
+                LEFT JOIN user_preferences up ON up.user_id = u.id
+                WHERE u.id = $1"#,
+        single: true,
+    },
+    TakeoutSection {
+        name: "addresses",
+        description: "Saved addresses, with coordinates and contact phone",
+        fields: &["label", "ward_id", "address", "latitude", "longitude", "phone", "is_default", "created_at"],
+        sql: "SELECT * FROM client_addresses WHERE user_id = $1 ORDER BY id",
+        single: false,
+    },
+    TakeoutSection {
+        name: "bookings",
+        description: "Bookings made, with the address and phone given for each",
+        fields: &[
+            "id", "confirmation_code", "target_type", "target_id", "service_id", "service_description",
+            "status", "scheduled_time", "duration", "client_phone", "client_address", "client_address_label",
+            "client_latitude", "client_longitude", "cancel_reason", "dispute_reason", "created_at",
+        ],
+        sql: "SELECT * FROM bookings WHERE client_id = $1 ORDER BY id",
+        single: false,
+    },
+    TakeoutSection {
+        name: "payments",
+        description: "M-Pesa payments for bookings, with the paying phone number",
+        fields: &["booking_id", "phone_number", "amount", "transaction_id", "status", "result_desc", "created_at"],
+        sql: r#"SELECT p.* FROM payments p JOIN bookings b ON b.id = p.booking_id
+                WHERE b.client_id = $1 ORDER BY p.id"#,
+        single: false,
+    },
+    TakeoutSection {
+        name: "reviews",
+        description: "Reviews written",
+        fields: &["id", "target_type", "target_id", "rating", "comment", "hidden", "created_at"],
+        sql: "SELECT * FROM reviews WHERE reviewer_id = $1 ORDER BY id",
+        single: false,
+    },
+    TakeoutSection {
+        name: "favorites",
+        description: "Favorited providers and businesses",
+        fields: &["target_type", "target_id", "notify_on_posts", "created_at"],
+        sql: "SELECT * FROM favorites WHERE user_id = $1 ORDER BY created_at, id",
+        single: false,
+    },
+    TakeoutSection {
+        name: "interactions",
+        description: "Providers and businesses messaged, booked, contacted or favorited, one entry per kind per day",
+        fields: &["target_type", "target_id", "interaction_type", "occurred_at"],
+        sql: "SELECT * FROM interactions WHERE user_id = $1 ORDER BY occurred_at, id",
+        single: false,
+    },
+    TakeoutSection {
+        name: "messages",
+        description: "Messages sent",
+        fields: &["id", "receiver_id", "target_type", "target_id", "content", "read_at", "created_at"],
+        sql: "SELECT * FROM messages WHERE sender_id = $1 ORDER BY id",
+        single: false,
+    },
+    TakeoutSection {
+        name: "notifications",
+        description: "Notification history",
+        fields: &["id", "notif_type", "title", "body", "target_type", "target_id", "data", "read_at", "created_at"],
+        sql: "SELECT * FROM notifications WHERE user_id = $1 ORDER BY id",
+        single: false,
+    },
+];
+
+/// Downloads everything we hold about the caller as a client, as one JSON
+/// document streamed section by section.
+pub async fn export_my_data(current_user: CurrentUser, State(pool): State<PgPool>) -> AppResult<Response> {
+    // Support sees what the user sees, not a copy of everything they hold
+    current_user.deny_impersonation()?;
+    let body = takeout::stream_document(pool, current_user.user_id, "client", &CLIENT_TAKEOUT);
+    let file_name = format!("mtaalink-data-{}.json", Utc::now().format("%Y%m%d"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file_name}\"")),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// The categories of data held about the caller and how many records each
+/// has, matching the sections of `/clients/export`.
+pub async fn get_privacy_summary(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let categories: Vec<serde_json::Value> = takeout::counts(&pool, user_id, &CLIENT_TAKEOUT)
+        .await?
+        .into_iter()
+        .map(|(section, count)| json!({ "name": section.name, "description": section.description, "count": count }))
+        .collect();
+    Ok((StatusCode::OK, Json(json!({ "categories": categories }))))
 }
 
 pub async fn upload_profile_picture(
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let (data, ext, _content_type) = parse_image_from_multipart(multipart).await?;
+    let (data, ext, content_type) = parse_image_from_multipart(&pool, multipart).await?;
     let key = generate_key("clients/profile_pictures", &ext);
-    let url = storage.save(&key, &data).await?;
+    let url = storage.save(&key, &data, &content_type).await?;
 
     let result = sqlx::query!(
         "UPDATE clients SET profile_picture = $1 WHERE user_id = $2",
@@ -99,13 +782,21 @@ pub async fn upload_profile_picture(
     .execute(&pool)
     .await;
 
-    if let Err(e) = result {
+    // No client row (e.g. a provider account) means nothing to attach it to
+    let error = match result {
+        Ok(done) if done.rows_affected() == 0 => Some(AppError::NotFound("Client profile not found".to_string())),
+        Ok(_) => None,
+        Err(e) => Some(AppError::Database(e)),
+    };
+    if let Some(error) = error {
         let _ = storage.delete(&key).await;
-        return Err(AppError::Database(e));
+        return Err(error);
     }
 
+    let variants = store_image_variants(&pool, &storage, &key, &url, data).await;
+
     Ok((
         StatusCode::OK,
-        Json(json!({ "message": "Profile picture uploaded successfully", "url": url })),
+        Json(json!({ "message": "Profile picture uploaded successfully", "url": url, "thumb_url": variants.thumb_url, "medium_url": variants.medium_url })),
     ))
 }
@@ -1,118 +1,488 @@
 use crate::errors::{AppError, AppResult};
+use crate::extractors::administrator::is_admin;
 use crate::extractors::current_user::CurrentUser;
+use crate::extractors::roles::CurrentProvider;
+use crate::routes::admin;
+use crate::utils::action_items;
+use crate::utils::flags::{self, SharedFeatureFlags};
+use crate::utils::money;
+use crate::utils::onboarding::{self, OnboardingProgress};
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
     extract::State,
     http::StatusCode,
-    routing::get,
+    routing::{get, post},
 };
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a user's dashboard is reused. The mobile app fetches it on every
+/// foreground, so a burst of opens costs one set of queries.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Cached dashboards kept before expired ones are pruned.
+const CACHE_MAX_USERS: usize = 10_000;
+
+/// Upcoming bookings, today's bookings and review invites shown at most.
+const LIST_LIMIT: i64 = 5;
+
+/// Days covered by the earnings series, ending today.
+const EARNINGS_DAYS: i64 = 30;
+
+/// Job failures older than this no longer count as recent on the admin view.
+const JOB_FAILURE_WINDOW_HOURS: i64 = 24;
+
+type DashboardCache = Arc<Mutex<HashMap<i32, (Instant, serde_json::Value)>>>;
 
 pub fn dashboard_routes(pool: PgPool) -> Router {
+    let cache: DashboardCache = Arc::default();
     Router::new()
         .route("/", get(dashboard))
+        .route("/dismiss", post(dismiss_action_item))
+        .route("/onboarding", get(onboarding_progress))
+        .layer(Extension(cache))
         .with_state(pool)
 }
 
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct DashboardBooking {
+    pub id: i32,
+    pub confirmation_code: String,
+    pub status: String,
+    pub scheduled_time: NaiveDateTime,
+    pub target_type: String,
+    pub target_id: i32,
+    /// The provider or business for clients, the client for owners.
+    pub with_name: Option<String>,
+    pub service_name: Option<String>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct ReviewInvite {
+    pub target_type: String,
+    pub target_id: i32,
+    pub target_name: Option<String>,
+    pub booking_id: i32,
+}
+
+/// A summary for the caller's home screen. `role` picks the layout: clients
+/// get their upcoming bookings, unread messages and the listings they can
+/// review; providers and businesses get today's bookings, pending requests,
+/// unread messages, new reviews and how bookings compare with last week, plus
+/// an `earnings` block while payments are on for them. Admins get the
+/// moderation queues and platform headline numbers instead.
 pub async fn dashboard(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    Extension(cache): Extension<DashboardCache>,
+    Extension(feature_flags): Extension<SharedFeatureFlags>,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if let Some((at, resp)) = cache.lock().unwrap().get(&user_id) {
+        if at.elapsed() < CACHE_TTL {
+            return Ok((StatusCode::OK, Json(resp.clone())));
+        }
+    }
+
     let user = sqlx::query!(
-        "SELECT username, email, role FROM users WHERE id = $1", user_id
+        r#"SELECT u.username, u.email,
+                  COALESCE(
+                      CASE WHEN u.role IN ('client', 'provider', 'business') THEN u.role END,
+                      CASE
+                          WHEN EXISTS (SELECT 1 FROM providers WHERE user_id = u.id) THEN 'provider'
+                          WHEN EXISTS (SELECT 1 FROM businesses WHERE user_id = u.id) THEN 'business'
+                          ELSE 'client'
+                      END
+                  ) AS "role!"
+           FROM users u WHERE u.id = $1"#,
+        user_id
     )
     .fetch_optional(&pool)
     .await?
     .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
-    let unread_notifications: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND is_read = false",
-        user_id
-    )
-    .fetch_one(&pool)
-    .await?
-    .unwrap_or(0);
-
-    let upcoming_bookings: i64 = sqlx::query_scalar!(
-        r#"SELECT COUNT(*) FROM bookings
-           WHERE client_id = $1 AND status = 'confirmed' AND scheduled_time > NOW()"#,
-        user_id
-    )
-    .fetch_one(&pool)
-    .await?
-    .unwrap_or(0);
-
     let mut resp = json!({
         "user_id": user_id,
         "username": user.username,
         "email": user.email,
         "role": user.role,
-        "unread_notifications": unread_notifications,
-        "upcoming_bookings": upcoming_bookings,
     });
+    let now = Utc::now().naive_utc();
 
-    match user.role.as_deref() {
-        Some("provider") => {
-            if let Some(p) = sqlx::query!("SELECT id FROM providers WHERE user_id = $1", user_id)
-                .fetch_optional(&pool)
-                .await?
-            {
-                let pending: i64 = sqlx::query_scalar!(
-                    r#"SELECT COUNT(*) FROM bookings
-                       WHERE target_type = 'provider' AND target_id = $1 AND status = 'pending'"#,
-                    p.id
-                )
-                .fetch_one(&pool)
-                .await?
-                .unwrap_or(0);
-
-                let wallet = sqlx::query!(
-                    "SELECT balance, total_earned FROM wallets WHERE target_type = 'provider' AND target_id = $1",
-                    p.id
-                )
-                .fetch_optional(&pool)
-                .await?;
-
-                resp["provider_id"] = json!(p.id);
-                resp["pending_bookings"] = json!(pending);
-                if let Some(w) = wallet {
-                    resp["balance"] = json!(w.balance);
-                    resp["total_earned"] = json!(w.total_earned);
-                }
-            }
+    if is_admin(&pool, user_id).await? {
+        resp["role"] = json!("admin");
+        admin_summary(&pool, user_id, &mut resp).await?;
+        return Ok((StatusCode::OK, Json(cache_dashboard(&cache, user_id, resp))));
+    }
+
+    match user.role.as_str() {
+        "provider" | "business" => {
+            let with_earnings = feature_flags.enabled_for(flags::PAYMENTS, Some(user_id)).await;
+            owner_summary(&pool, user_id, &user.role, now, with_earnings, &mut resp).await?
         }
-        Some("business") => {
-            if let Some(b) = sqlx::query!("SELECT id FROM businesses WHERE user_id = $1", user_id)
-                .fetch_optional(&pool)
-                .await?
-            {
-                let pending: i64 = sqlx::query_scalar!(
-                    r#"SELECT COUNT(*) FROM bookings
-                       WHERE target_type = 'business' AND target_id = $1 AND status = 'pending'"#,
-                    b.id
-                )
-                .fetch_one(&pool)
-                .await?
-                .unwrap_or(0);
-
-                let wallet = sqlx::query!(
-                    "SELECT balance, total_earned FROM wallets WHERE target_type = 'business' AND target_id = $1",
-                    b.id
-                )
-                .fetch_optional(&pool)
-                .await?;
-
-                resp["business_id"] = json!(b.id);
-                resp["pending_bookings"] = json!(pending);
-                if let Some(w) = wallet {
-                    resp["balance"] = json!(w.balance);
-                    resp["total_earned"] = json!(w.total_earned);
-                }
-            }
+        _ => client_summary(&pool, user_id, now, &mut resp).await?,
+    }
+
+    Ok((StatusCode::OK, Json(cache_dashboard(&cache, user_id, resp))))
+}
+
+fn cache_dashboard(cache: &DashboardCache, user_id: i32, resp: serde_json::Value) -> serde_json::Value {
+    let mut cache = cache.lock().unwrap();
+    if cache.len() >= CACHE_MAX_USERS {
+        cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+    }
+    cache.insert(user_id, (Instant::now(), resp.clone()));
+    resp
+}
+
+async fn unread_counts(pool: &PgPool, user_id: i32) -> Result<(i64, i64), sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT (SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL) AS "notifications!",
+                  (SELECT COUNT(*) FROM messages WHERE receiver_id = $1 AND is_read = FALSE) AS "messages!""#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok((row.notifications, row.messages))
+}
+
+/// The heads of the admin queues, sized for a glance: the newest open flags,
+/// the longest-waiting listings, jobs that failed lately and the overview
+/// headline numbers. Each links through to its full admin endpoint.
+async fn admin_summary(pool: &PgPool, user_id: i32, resp: &mut serde_json::Value) -> AppResult<()> {
+    let ((unread_notifications, unread_messages), (flags, open_flags), (listings, pending_listings), failures) =
+        tokio::try_join!(
+            unread_counts(pool, user_id),
+            admin::flag_page(pool, Some("open".to_string()), None, None, LIST_LIMIT, 0),
+            admin::pending_listings(pool, None, LIST_LIMIT, 0),
+            admin::recent_job_failures(pool, 50),
+        )?;
+    let overview = admin::overview(pool).await?;
+
+    let since = Utc::now() - ChronoDuration::hours(JOB_FAILURE_WINDOW_HOURS);
+    let mut failing_jobs: Vec<serde_json::Value> = Vec::new();
+    for run in failures.iter().filter(|r| r.started_at >= since) {
+        // Newest first, so the first run seen per job is its latest failure
+        match failing_jobs.iter_mut().find(|j| j["job_name"] == run.job_name.as_str()) {
+            Some(job) => job["failures"] = json!(job["failures"].as_i64().unwrap_or(0) + 1),
+            None => failing_jobs.push(json!({
+                "job_name": run.job_name,
+                "failures": 1,
+                "last_failure_at": run.started_at,
+                "last_error": run.error,
+            })),
         }
-        _ => {}
     }
 
-    Ok((StatusCode::OK, Json(resp)))
+    resp["unread_notifications"] = json!(unread_notifications);
+    resp["unread_messages"] = json!(unread_messages);
+    resp["open_flags"] = json!({
+        "count": open_flags,
+        "latest": flags.iter().map(|f| json!({
+            "id": f.id,
+            "target_type": f.target_type,
+            "target_id": f.target_id,
+            "reason": f.reason,
+            "open_reports_on_target": f.open_reports_on_target,
+            "created_at": f.created_at,
+        })).collect::<Vec<_>>(),
+    });
+    resp["pending_verifications"] = json!({
+        "count": pending_listings,
+        "oldest": listings.iter().map(|l| json!({
+            "entity_type": l.entity_type,
+            "entity_id": l.entity_id,
+            "name": l.name,
+            "created_at": l.created_at,
+        })).collect::<Vec<_>>(),
+    });
+    resp["failing_jobs"] = json!(failing_jobs);
+    resp["overview"] = json!({
+        "users": overview["users"]["total"],
+        "bookings_created": overview["bookings"]["created"],
+        "gmv_last_30d": overview["gmv"]["last_30d"],
+        "open_disputes": overview["moderation"]["open_disputes"],
+        "pending_appeals": overview["moderation"]["pending_appeals"],
+    });
+    Ok(())
+}
+
+async fn client_summary(
+    pool: &PgPool,
+    user_id: i32,
+    now: NaiveDateTime,
+    resp: &mut serde_json::Value,
+) -> AppResult<()> {
+    let ((unread_notifications, unread_messages), upcoming_count, upcoming, review_invites) = tokio::try_join!(
+        unread_counts(pool, user_id),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM bookings
+               WHERE client_id = $1 AND status IN ('pending', 'confirmed') AND scheduled_time >= $2"#,
+            user_id,
+            now
+        )
+        .fetch_one(pool),
+        sqlx::query_as!(
+            DashboardBooking,
+            r#"SELECT b.id, b.confirmation_code, b.status, b.scheduled_time, b.target_type, b.target_id,
+                      COALESCE(p.service_name, biz.business_name) AS with_name,
+                      COALESCE(s.title, b.service_description) AS service_name
+               FROM bookings b
+               LEFT JOIN providers p ON b.target_type = 'provider' AND p.id = b.target_id
+               LEFT JOIN businesses biz ON b.target_type = 'business' AND biz.id = b.target_id
+               LEFT JOIN services s ON s.id = b.service_id
+               WHERE b.client_id = $1 AND b.status IN ('pending', 'confirmed') AND b.scheduled_time >= $2
+               ORDER BY b.scheduled_time, b.id
+               LIMIT $3"#,
+            user_id,
+            now,
+            LIST_LIMIT
+        )
+        .fetch_all(pool),
+        // Listings with a completed booking the client hasn't reviewed yet
+        sqlx::query_as!(
+            ReviewInvite,
+            r#"SELECT b.target_type, b.target_id, MAX(b.id) AS "booking_id!",
+                      MAX(COALESCE(p.service_name, biz.business_name)) AS target_name
+               FROM bookings b
+               LEFT JOIN providers p ON b.target_type = 'provider' AND p.id = b.target_id
+               LEFT JOIN businesses biz ON b.target_type = 'business' AND biz.id = b.target_id
+               WHERE b.client_id = $1 AND b.status = 'completed'
+                 AND NOT EXISTS (
+                     SELECT 1 FROM reviews r
+                     WHERE r.reviewer_id = $1 AND r.target_type = b.target_type AND r.target_id = b.target_id)
+               GROUP BY b.target_type, b.target_id
+               ORDER BY MAX(b.scheduled_time) DESC
+               LIMIT $2"#,
+            user_id,
+            LIST_LIMIT
+        )
+        .fetch_all(pool),
+    )?;
+
+    resp["unread_notifications"] = json!(unread_notifications);
+    resp["unread_messages"] = json!(unread_messages);
+    resp["upcoming_bookings"] = json!(upcoming_count);
+    resp["upcoming"] = json!(upcoming);
+    resp["pending_review_invites"] = json!(review_invites);
+    Ok(())
+}
+
+async fn owner_summary(
+    pool: &PgPool,
+    user_id: i32,
+    role: &str,
+    now: NaiveDateTime,
+    with_earnings: bool,
+    resp: &mut serde_json::Value,
+) -> AppResult<()> {
+    let listing = match role {
+        "provider" => sqlx::query!(
+            "SELECT id, listing_status, listing_rejection_reason FROM providers WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|p| (p.id, p.listing_status, p.listing_rejection_reason)),
+        _ => sqlx::query!(
+            "SELECT id, listing_status, listing_rejection_reason FROM businesses WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|b| (b.id, b.listing_status, b.listing_rejection_reason)),
+    };
+
+    let (unread_notifications, unread_messages) = unread_counts(pool, user_id).await?;
+    resp["unread_notifications"] = json!(unread_notifications);
+    resp["unread_messages"] = json!(unread_messages);
+
+    // Not onboarded yet: nothing to summarise
+    let Some((target_id, listing_status, listing_rejection_reason)) = listing else {
+        return Ok(());
+    };
+
+    let today = now.date().and_hms_opt(0, 0, 0).unwrap_or(now);
+    let week_ago = now - ChronoDuration::days(7);
+    let two_weeks_ago = now - ChronoDuration::days(14);
+
+    let (todays, pending, new_reviews, weeks, wallet, action_items) = tokio::try_join!(
+        sqlx::query_as!(
+            DashboardBooking,
+            r#"SELECT b.id, b.confirmation_code, b.status, b.scheduled_time, b.target_type, b.target_id,
+                      COALESCE(u.display_name, u.username) AS with_name,
+                      COALESCE(s.title, b.service_description) AS service_name
+               FROM bookings b
+               LEFT JOIN users u ON u.id = b.client_id
+               LEFT JOIN services s ON s.id = b.service_id
+               WHERE b.target_type = $1 AND b.target_id = $2
+                 AND b.status IN ('pending', 'confirmed')
+                 AND b.scheduled_time >= $3 AND b.scheduled_time < $3 + INTERVAL '1 day'
+               ORDER BY b.scheduled_time, b.id"#,
+            role,
+            target_id,
+            today
+        )
+        .fetch_all(pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM bookings
+               WHERE target_type = $1 AND target_id = $2 AND status = 'pending'"#,
+            role,
+            target_id
+        )
+        .fetch_one(pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM reviews
+               WHERE target_type = $1 AND target_id = $2 AND hidden = FALSE AND created_at >= $3"#,
+            role,
+            target_id,
+            week_ago
+        )
+        .fetch_one(pool),
+        // Bookings made in the last 7 days and the 7 before that
+        sqlx::query!(
+            r#"SELECT COUNT(*) FILTER (WHERE created_at >= $3) AS "this_week!",
+                      COUNT(*) FILTER (WHERE created_at < $3) AS "last_week!"
+               FROM bookings
+               WHERE target_type = $1 AND target_id = $2 AND created_at >= $4"#,
+            role,
+            target_id,
+            week_ago,
+            two_weeks_ago
+        )
+        .fetch_one(pool),
+        sqlx::query!(
+            "SELECT balance, total_earned FROM wallets WHERE target_type = $1 AND target_id = $2",
+            role,
+            target_id
+        )
+        .fetch_optional(pool),
+        action_items::for_owner(pool, user_id, role, target_id, now),
+    )?;
+
+    resp[format!("{role}_id")] = json!(target_id);
+    resp["listing_status"] = json!(listing_status);
+    resp["listing_rejection_reason"] = json!(listing_rejection_reason);
+    resp["todays_bookings"] = json!(todays);
+    resp["pending_bookings"] = json!(pending);
+    resp["new_reviews"] = json!(new_reviews);
+    resp["action_items"] = json!(action_items);
+    if role == "provider" {
+        resp["onboarding"] = json!(onboarding::progress(pool, target_id).await?);
+    }
+    resp["bookings_this_week"] = json!(weeks.this_week);
+    resp["bookings_last_week"] = json!(weeks.last_week);
+    resp["bookings_week_delta"] = json!(weeks.this_week - weeks.last_week);
+    if let Some(w) = wallet {
+        resp["balance"] = json!(w.balance);
+        resp["total_earned"] = json!(w.total_earned);
+    }
+    // Left out entirely, not zeroed, so apps don't show an empty card
+    if with_earnings {
+        resp["earnings"] = earnings(pool, role, target_id, now).await?;
+    }
+    Ok(())
+}
+
+/// Deposits collected through M-Pesa this week (from Monday) and this month,
+/// payouts requested but not yet paid, and collections per day for the last
+/// `EARNINGS_DAYS` days. Only completed payments count.
+async fn earnings(pool: &PgPool, role: &str, target_id: i32, now: NaiveDateTime) -> AppResult<serde_json::Value> {
+    let today = now.date();
+    let first_day = today - ChronoDuration::days(EARNINGS_DAYS - 1);
+
+    let (totals, pending_payouts, daily) = tokio::try_join!(
+        sqlx::query!(
+            r#"SELECT COALESCE(SUM(p.amount) FILTER (WHERE COALESCE(p.updated_at, p.created_at) >= date_trunc('week', $3::timestamp)), 0) AS "week!",
+                      COALESCE(SUM(p.amount) FILTER (WHERE COALESCE(p.updated_at, p.created_at) >= date_trunc('month', $3::timestamp)), 0) AS "month!"
+               FROM payments p
+               JOIN bookings b ON b.id = p.booking_id
+               WHERE b.target_type = $1 AND b.target_id = $2 AND p.status = 'completed'
+                 AND COALESCE(p.updated_at, p.created_at) >= LEAST(date_trunc('week', $3::timestamp), date_trunc('month', $3::timestamp))"#,
+            role,
+            target_id,
+            now
+        )
+        .fetch_one(pool),
+        sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(pr.amount), 0) AS "pending!"
+               FROM payout_requests pr
+               JOIN wallets w ON w.id = pr.wallet_id
+               WHERE w.target_type = $1 AND w.target_id = $2 AND pr.status IN ('pending', 'approved')"#,
+            role,
+            target_id
+        )
+        .fetch_one(pool),
+        sqlx::query!(
+            r#"SELECT d.day::date AS "date!", COALESCE(SUM(p.amount), 0) AS "amount!"
+               FROM generate_series($3::date, $4::date, INTERVAL '1 day') d(day)
+               LEFT JOIN bookings b ON b.target_type = $1 AND b.target_id = $2
+               LEFT JOIN payments p ON p.booking_id = b.id AND p.status = 'completed'
+                    AND COALESCE(p.updated_at, p.created_at)::date = d.day::date
+               GROUP BY d.day
+               ORDER BY d.day"#,
+            role,
+            target_id,
+            first_day,
+            today
+        )
+        .fetch_all(pool),
+    )?;
+
+    let daily: Vec<serde_json::Value> = daily
+        .iter()
+        .map(|d| json!({ "date": d.date, "amount": money::to_cents_string(&d.amount) }))
+        .collect();
+    Ok(json!({
+        "currency": money::CURRENCY,
+        "collected_this_week": money::amount_json(&totals.week),
+        "collected_this_month": money::amount_json(&totals.month),
+        "pending_payouts": money::amount_json(&pending_payouts),
+        "daily": daily,
+    }))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DismissInput {
+    #[serde(rename = "type")]
+    pub item_type: String,
+    /// The item's `occurrence`, as returned by `/dashboard`.
+    pub occurrence: String,
+}
+
+/// Hides an action item until something new needs attention.
+pub async fn dismiss_action_item(
+    State(pool): State<PgPool>,
+    Extension(cache): Extension<DashboardCache>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<DismissInput>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let item_type = action_items::ITEM_TYPES
+        .iter()
+        .find(|t| **t == payload.item_type)
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown action item type '{}'", payload.item_type)))?;
+    let occurrence = payload.occurrence.trim();
+    if occurrence.is_empty() || occurrence.len() > 64 {
+        return Err(AppError::BadRequest("occurrence must be 1 to 64 characters".to_string()));
+    }
+
+    action_items::dismiss(&pool, user_id, item_type, occurrence).await?;
+    cache.lock().unwrap().remove(&user_id);
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Dismissed" }))))
+}
+
+/// The caller's provider setup checklist, as shown in `/dashboard`.
+pub async fn onboarding_progress(
+    State(pool): State<PgPool>,
+    CurrentProvider { provider_id, .. }: CurrentProvider,
+) -> AppResult<(StatusCode, Json<OnboardingProgress>)> {
+    let progress = onboarding::progress(&pool, provider_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Provider not found".to_string()))?;
+    Ok((StatusCode::OK, Json(progress)))
 }
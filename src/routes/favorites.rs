@@ -1,7 +1,12 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::utils::interactions::{self, Interaction};
+use crate::utils::notifications::notify_and_push;
+use crate::utils::pagination::{Paginated, Pagination};
+use crate::utils::prefs;
+use crate::utils::ws_state::WsConnections;
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
     extract::{Path, Query, State},
     http::StatusCode,
     routing::{get, post},
@@ -14,6 +19,19 @@ use sqlx::PgPool;
 pub struct FavoritePayload {
     target_type: String,
     target_id: i32,
+    /// Whether new posts from this provider/business notify the user. Defaults to true.
+    notify_on_posts: Option<bool>,
+}
+
+/// SQL for whether the signed-in user bound to `$param` has favorited the
+/// listing `target_id` (a column), or NULL when the parameter is NULL.
+pub fn favorited_by(target_type: &str, target_id: &str, param: usize) -> String {
+    format!(
+        "CASE WHEN ${param}::int IS NULL THEN NULL ELSE EXISTS (
+             SELECT 1 FROM favorites f
+             WHERE f.user_id = ${param} AND f.target_type = '{target_type}' AND f.target_id = {target_id})
+         END"
+    )
 }
 
 pub fn favorites_routes(pool: PgPool) -> Router {
@@ -21,12 +39,15 @@ pub fn favorites_routes(pool: PgPool) -> Router {
         .route("/addFavorite", post(add_favorite))
         .route("/getFavorites", get(get_favorites))
         .route("/removeFavorite/:id", post(remove_favorite))
+        .route("/notifyOnPosts", post(set_notify_on_posts))
         .with_state(pool)
 }
 
+/// Adds a favorite, and tells the listing's owner unless they turned that off.
 pub async fn add_favorite(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    Extension(ws_conns): Extension<WsConnections>,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<FavoritePayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let target_type = payload.target_type.to_lowercase();
@@ -37,36 +58,118 @@ pub async fn add_favorite(
         return Err(AppError::BadRequest("Invalid target ID".to_string()));
     }
 
-    sqlx::query!(
-        "INSERT INTO favorites (user_id, target_type, target_id) VALUES ($1, $2, $3)
+    let inserted = sqlx::query!(
+        "INSERT INTO favorites (user_id, target_type, target_id, notify_on_posts) VALUES ($1, $2, $3, $4)
          ON CONFLICT (user_id, target_type, target_id) DO NOTHING",
         user_id,
         target_type,
-        payload.target_id
+        payload.target_id,
+        payload.notify_on_posts.unwrap_or(true)
     )
     .execute(&pool)
-    .await?;
+    .await?
+    .rows_affected()
+        > 0;
+
+    if inserted {
+        interactions::record(&pool, user_id, &target_type, payload.target_id, Interaction::Favorite).await?;
+
+        let owner = sqlx::query!(
+            r#"SELECT COALESCE(
+                          (SELECT user_id FROM providers WHERE $1 = 'provider' AND id = $2),
+                          (SELECT user_id FROM businesses WHERE $1 = 'business' AND id = $2)
+                      ) AS owner_id,
+                      (SELECT COALESCE(display_name, username) FROM users WHERE id = $3) AS fan_name"#,
+            target_type,
+            payload.target_id,
+            user_id
+        )
+        .fetch_one(&pool)
+        .await?;
+
+        if let Some(owner_id) = owner.owner_id.filter(|&id| id != user_id) {
+            if prefs::flag(&pool, owner_id, prefs::NOTIFY_ON_FAVORITE).await {
+                let fan_name = owner.fan_name.unwrap_or_else(|| "Someone".to_string());
+                notify_and_push(
+                    &pool, &ws_conns, owner_id, "favorite_added", "New Favorite",
+                    &format!("{fan_name} added your listing to their favorites"),
+                    Some(&target_type), Some(payload.target_id),
+                ).await;
+            }
+        }
+    }
 
     Ok((StatusCode::OK, Json(json!({ "message": "Favorite added successfully" }))))
 }
 
+/// A favorite with enough of its listing to render it, so the favorites page
+/// doesn't fetch every profile separately.
+#[derive(Serialize, Debug, sqlx::FromRow)]
+pub struct FavoriteListing {
+    target_type: String,
+    target_id: i32,
+    notify_on_posts: bool,
+    name: Option<String>,
+    photo: Option<String>,
+    photo_thumb_url: Option<String>,
+    category: Option<String>,
+    location: Option<String>,
+    avg_rating: Option<f64>,
+    review_count: i64,
+}
+
+/// Favorites whose listing is still public, newest first, paged with
+/// `?page=&per_page=`. Ratings come from one indexed lookup per favorite
+/// rather than a request per profile.
 pub async fn get_favorites(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let favs = sqlx::query!(
-        "SELECT target_type, target_id FROM favorites WHERE user_id = $1 ORDER BY created_at DESC",
+    pagination: Pagination,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<Paginated<FavoriteListing>>)> {
+    let favorites = sqlx::query_as!(
+        FavoriteListing,
+        r#"SELECT f.target_type, f.target_id, f.notify_on_posts,
+                  COALESCE(p.service_name, b.business_name) AS name,
+                  COALESCE(p.profile_photo, b.logo, b.profile_photo) AS photo,
+                  image_variant(COALESCE(p.profile_photo, b.logo, b.profile_photo), 'thumb') AS photo_thumb_url,
+                  COALESCE(p.category, b.category) AS category,
+                  COALESCE(p.location, b.location) AS location,
+                  r.avg_rating,
+                  r.review_count AS "review_count!"
+           FROM favorites f
+           LEFT JOIN providers p ON f.target_type = 'provider' AND p.id = f.target_id
+           LEFT JOIN businesses b ON f.target_type = 'business' AND b.id = f.target_id
+           CROSS JOIN LATERAL (
+               SELECT ROUND(AVG(rating)::numeric, 1)::float8 AS avg_rating, COUNT(*) AS review_count
+               FROM reviews
+               WHERE target_type = f.target_type AND target_id = f.target_id AND hidden = FALSE
+           ) r
+           WHERE f.user_id = $1
+             AND COALESCE(p.listing_status, b.listing_status) = 'approved'
+             AND NOT user_is_hidden(COALESCE(p.user_id, b.user_id))
+           ORDER BY f.created_at DESC, f.id DESC
+           LIMIT $2 OFFSET $3"#,
+        user_id,
+        pagination.limit(),
+        pagination.offset()
+    )
+    .fetch_all(&pool);
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!"
+           FROM favorites f
+           LEFT JOIN providers p ON f.target_type = 'provider' AND p.id = f.target_id
+           LEFT JOIN businesses b ON f.target_type = 'business' AND b.id = f.target_id
+           WHERE f.user_id = $1
+             AND COALESCE(p.listing_status, b.listing_status) = 'approved'
+             AND NOT user_is_hidden(COALESCE(p.user_id, b.user_id))"#,
         user_id
     )
-    .fetch_all(&pool)
-    .await?;
+    .fetch_one(&pool);
 
-    let result: Vec<FavoritePayload> = favs
-        .into_iter()
-        .map(|f| FavoritePayload { target_type: f.target_type, target_id: f.target_id })
-        .collect();
+    let (favorites, total) = tokio::try_join!(favorites, total)?;
 
-    Ok((StatusCode::OK, Json(json!({ "favorites": result }))))
+    Ok((StatusCode::OK, Json(Paginated::new(favorites, pagination, total))))
 }
 
 #[derive(Deserialize)]
@@ -78,7 +181,7 @@ pub async fn remove_favorite(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
     Query(params): Query<RemoveFavoriteQuery>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if id <= 0 {
         return Err(AppError::BadRequest("Invalid target ID".to_string()));
@@ -97,3 +200,34 @@ pub async fn remove_favorite(
 
     Ok((StatusCode::OK, Json(json!({ "message": "Favorite removed successfully" }))))
 }
+
+/// Turns post notifications on or off for one favourited provider/business.
+pub async fn set_notify_on_posts(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<FavoritePayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let target_type = payload.target_type.to_lowercase();
+    if !["provider", "business"].contains(&target_type.as_str()) {
+        return Err(AppError::BadRequest("Invalid target type. Must be 'provider' or 'business'".to_string()));
+    }
+    let notify_on_posts = payload
+        .notify_on_posts
+        .ok_or_else(|| AppError::BadRequest("notify_on_posts is required".to_string()))?;
+
+    let updated = sqlx::query!(
+        "UPDATE favorites SET notify_on_posts = $1 WHERE user_id = $2 AND target_type = $3 AND target_id = $4",
+        notify_on_posts,
+        user_id,
+        target_type,
+        payload.target_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound("Favorite not found".to_string()));
+    }
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Post notification preference updated", "notify_on_posts": notify_on_posts }))))
+}
@@ -0,0 +1,161 @@
+use crate::db::pool_stats;
+use crate::utils::storage::SharedStorage;
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long each readiness check may take before it counts as failed.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+/// The storage probe writes an object, which costs a round trip (and money on
+/// S3), so its result is reused for this long.
+const STORAGE_CHECK_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct HealthState {
+    pool: PgPool,
+    storage: SharedStorage,
+    last_storage_check: Arc<Mutex<Option<(Instant, Check)>>>,
+    started: Instant,
+}
+
+/// Probes for load balancers and orchestrators. No auth, and mounted outside
+/// the tracing and activity layers so polling every few seconds stays quiet.
+pub fn health_routes(pool: PgPool, storage: SharedStorage) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/health", get(health))
+        .with_state(HealthState {
+            pool,
+            storage,
+            last_storage_check: Arc::new(Mutex::new(None)),
+            started: Instant::now(),
+        })
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Check {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn passed() -> Self {
+        Check { ok: true, error: None }
+    }
+
+    fn failed(error: impl Into<String>) -> Self {
+        Check {
+            ok: false,
+            error: Some(error.into()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct BuildInfo {
+    pub commit: &'static str,
+    pub built_at: Option<DateTime<Utc>>,
+}
+
+/// Commit hash and build time baked in by `build.rs`.
+fn build_info() -> BuildInfo {
+    BuildInfo {
+        commit: env!("BUILD_GIT_COMMIT"),
+        built_at: env!("BUILD_TIMESTAMP")
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// Liveness: the process is up and serving requests. Never touches the database.
+async fn healthz() -> impl IntoResponse {
+    Json(json!({ "status": "ok", "build": build_info() }))
+}
+
+/// Readiness: the database answers and storage accepts writes. 503 with the
+/// failing check's detail otherwise. `pool` shows how busy the connection
+/// pool is; when it stays saturated the database check times out and fails.
+async fn readyz(State(state): State<HealthState>) -> impl IntoResponse {
+    let database = database_check(&state.pool).await;
+    let storage = storage_check(&state).await;
+
+    let ready = database.ok && storage.ok;
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = json!({
+        "status": if ready { "ok" } else { "unavailable" },
+        "checks": {
+            "database": database,
+            "storage": storage,
+        },
+        "pool": pool_stats(&state.pool),
+        "build": build_info(),
+    });
+    (status, Json(body))
+}
+
+/// Database health for uptime monitors: `SELECT 1` within the check timeout,
+/// the app version and uptime, and pool usage for graphing saturation.
+async fn health(State(state): State<HealthState>) -> impl IntoResponse {
+    let database = database_check(&state.pool).await;
+    let status = if database.ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = json!({
+        "status": if database.ok { "ok" } else { "unavailable" },
+        "db": if database.ok { "up" } else { "down" },
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_seconds": state.started.elapsed().as_secs(),
+        "pool": pool_stats(&state.pool),
+        "build": build_info(),
+    });
+    (status, Json(body))
+}
+
+/// Runs `SELECT 1`, failing when it errors or takes longer than `CHECK_TIMEOUT`.
+async fn database_check(pool: &PgPool) -> Check {
+    match tokio::time::timeout(CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await {
+        Ok(Ok(_)) => Check::passed(),
+        Ok(Err(e)) => {
+            tracing::warn!("Health database check failed: {}", e);
+            Check::failed("Database query failed")
+        }
+        Err(_) => Check::failed("Database did not answer within 2s"),
+    }
+}
+
+/// Runs the storage write probe, or returns the last result while it is fresh.
+async fn storage_check(state: &HealthState) -> Check {
+    let mut last = state.last_storage_check.lock().await;
+    if let Some((checked_at, check)) = last.as_ref() {
+        if checked_at.elapsed() < STORAGE_CHECK_TTL {
+            return check.clone();
+        }
+    }
+
+    let check = match tokio::time::timeout(CHECK_TIMEOUT, state.storage.check_writable()).await {
+        Ok(Ok(())) => Check::passed(),
+        Ok(Err(e)) => {
+            tracing::warn!("Readiness storage check failed: {}", e);
+            Check::failed("Storage is not writable")
+        }
+        Err(_) => Check::failed("Storage did not answer within 2s"),
+    };
+    *last = Some((Instant::now(), check.clone()));
+    check
+}
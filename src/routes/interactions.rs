@@ -0,0 +1,164 @@
+use crate::errors::{AppError, AppResult};
+use crate::extractors::current_user::CurrentUser;
+use crate::utils::interactions::{self, Interaction};
+use crate::utils::pagination::{Paginated, Pagination};
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+
+pub fn interactions_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/mine", get(my_interactions))
+        .route("/me", get(my_interactions))
+        .route("/contact", post(record_contact))
+        .with_state(pool)
+}
+
+fn validate_target_type(target_type: &str) -> AppResult<String> {
+    let target_type = target_type.to_lowercase();
+    if !["provider", "business"].contains(&target_type.as_str()) {
+        return Err(AppError::BadRequest("Invalid target type".to_string()));
+    }
+    Ok(target_type)
+}
+
+// ── POST /interactions/contact ────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct ContactPayload {
+    pub target_type: String,
+    pub target_id: i32,
+}
+
+/// Records that the caller used a listing's phone, WhatsApp, email or
+/// website link. Sent by the frontend as the link is followed.
+pub async fn record_contact(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<ContactPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let target_type = validate_target_type(&payload.target_type)?;
+
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+               SELECT 1 FROM providers WHERE $1 = 'provider' AND id = $2
+               UNION ALL
+               SELECT 1 FROM businesses WHERE $1 = 'business' AND id = $2
+           ) AS "exists!""#,
+        target_type,
+        payload.target_id
+    )
+    .fetch_one(&pool)
+    .await?;
+    if !exists {
+        return Err(AppError::NotFound("Listing not found".to_string()));
+    }
+
+    interactions::record(&pool, user_id, &target_type, payload.target_id, Interaction::ContactClick).await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Contact recorded" }))))
+}
+
+// ── GET /interactions/mine ────────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct MyInteractionsQuery {
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
+    pub can_review: Option<bool>,
+}
+
+/// Everything the caller has done with one listing.
+#[derive(Serialize, Debug)]
+pub struct ListingInteractions {
+    pub target_type: String,
+    pub target_id: i32,
+    /// Distinct kinds, e.g. `["message", "booking_completed"]`.
+    pub interaction_types: Vec<String>,
+    pub first_at: NaiveDateTime,
+    pub last_at: NaiveDateTime,
+    /// A booking with the listing was completed and the caller hasn't
+    /// reviewed it yet, so `createReviews` would accept a review.
+    pub can_review: bool,
+}
+
+/// The listings the caller has interacted with, most recent first, for hints
+/// such as "you've contacted this provider before". `?target_type=` and
+/// `?target_id=` narrow it to one kind of listing or one listing, and
+/// `?can_review=true` to the listings the caller may review.
+pub async fn my_interactions(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    pagination: Pagination,
+    Query(params): Query<MyInteractionsQuery>,
+) -> AppResult<(StatusCode, Json<Paginated<ListingInteractions>>)> {
+    let target_type = params.target_type.as_deref().map(validate_target_type).transpose()?;
+
+    let reviewable = Interaction::BookingCompleted.as_str();
+
+    let items = sqlx::query_as!(
+        ListingInteractions,
+        r#"WITH listings AS (
+               SELECT i.target_type, i.target_id,
+                      ARRAY_AGG(DISTINCT i.interaction_type ORDER BY i.interaction_type) AS interaction_types,
+                      MIN(i.occurred_at) AS first_at,
+                      MAX(i.occurred_at) AS last_at,
+                      BOOL_OR(i.interaction_type = $4) AND NOT EXISTS (
+                          SELECT 1 FROM reviews r
+                          WHERE r.reviewer_id = $1 AND r.target_type = i.target_type AND r.target_id = i.target_id
+                      ) AS can_review
+               FROM interactions i
+               WHERE i.user_id = $1
+                 AND ($2::text IS NULL OR i.target_type = $2)
+                 AND ($3::int IS NULL OR i.target_id = $3)
+               GROUP BY i.target_type, i.target_id
+           )
+           SELECT target_type AS "target_type!", target_id AS "target_id!",
+                  interaction_types AS "interaction_types!", first_at AS "first_at!", last_at AS "last_at!",
+                  can_review AS "can_review!"
+           FROM listings
+           WHERE $5::bool IS NULL OR can_review = $5
+           ORDER BY last_at DESC, target_type, target_id
+           LIMIT $6 OFFSET $7"#,
+        user_id,
+        target_type,
+        params.target_id,
+        reviewable,
+        params.can_review,
+        pagination.limit(),
+        pagination.offset()
+    )
+    .fetch_all(&pool);
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM (
+               SELECT BOOL_OR(i.interaction_type = $4) AND NOT EXISTS (
+                          SELECT 1 FROM reviews r
+                          WHERE r.reviewer_id = $1 AND r.target_type = i.target_type AND r.target_id = i.target_id
+                      ) AS can_review
+               FROM interactions i
+               WHERE i.user_id = $1
+                 AND ($2::text IS NULL OR i.target_type = $2)
+                 AND ($3::int IS NULL OR i.target_id = $3)
+               GROUP BY i.target_type, i.target_id
+           ) listings
+           WHERE $5::bool IS NULL OR can_review = $5"#,
+        user_id,
+        target_type,
+        params.target_id,
+        reviewable,
+        params.can_review
+    )
+    .fetch_one(&pool);
+
+    let (items, total) = tokio::try_join!(items, total)?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(items, pagination, total))))
+}
@@ -1,9 +1,13 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::routes::branches;
+use crate::utils::etag::{self, CacheClass};
+use crate::utils::onboarding;
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
+    response::Response,
     routing::{get, post},
 };
 use chrono::NaiveDateTime;
@@ -17,11 +21,11 @@ pub fn locations_routes(pool: PgPool) -> Router {
         .route("/allcounties", get(get_locations_counties))
         .route("/counties/:county_id/constituencies", get(get_constituencies_by_county))
         .route("/constituencies/:constituency_id/wards", get(get_wards_by_constituency))
-        .route("/branches/:business_id/location", post(create_branch_location))
-        .route("/branches/:business_id/locations", get(get_branch_locations))
-        .route("/branches/location/:id", get(get_branch_by_id))
-        .route("/branches/location/:id/update", post(update_branch_location))
-        .route("/branches/location/:id/delete", post(delete_branch_location))
+        .route("/branches/:business_id/location", post(branches::create_branch))
+        .route("/branches/:business_id/locations", get(branches::list_branches))
+        .route("/branches/location/:id", get(branches::get_branch))
+        .route("/branches/location/:id/update", post(branches::update_branch))
+        .route("/branches/location/:id/delete", post(branches::delete_branch))
         .route("/providers/:provider_id", post(create_provider_location))
         .route("/providers/location/:id", get(get_provider_location_by_id))
         .route("/providers/location/:id/update", post(update_provider_location))
@@ -38,12 +42,18 @@ pub struct Counties {
 
 pub async fn get_locations_counties(
     State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let etag = etag::dataset_etag(&pool, etag::LOCATIONS).await?;
+    if etag::matches(&headers, &etag) {
+        return Ok(etag::not_modified(&etag, CacheClass::Reference));
+    }
+
     let counties = sqlx::query_as::<_, Counties>("SELECT id, name FROM counties")
         .fetch_all(&pool)
         .await?;
 
-    Ok((StatusCode::OK, Json(json!({ "data": counties }))))
+    Ok(etag::tagged(&etag, CacheClass::Reference, Json(json!({ "data": counties }))))
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
@@ -55,7 +65,13 @@ pub struct Constituency {
 pub async fn get_constituencies_by_county(
     Path(county_id): Path<i32>,
     State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let etag = etag::dataset_etag(&pool, etag::LOCATIONS).await?;
+    if etag::matches(&headers, &etag) {
+        return Ok(etag::not_modified(&etag, CacheClass::Reference));
+    }
+
     let constituencies = sqlx::query_as::<_, Constituency>(
         "SELECT id, name FROM constituencies WHERE county_id = $1",
     )
@@ -63,7 +79,7 @@ pub async fn get_constituencies_by_county(
     .fetch_all(&pool)
     .await?;
 
-    Ok((StatusCode::OK, Json(json!({ "data": constituencies }))))
+    Ok(etag::tagged(&etag, CacheClass::Reference, Json(json!({ "data": constituencies }))))
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
@@ -75,7 +91,13 @@ pub struct Ward {
 pub async fn get_wards_by_constituency(
     Path(constituency_id): Path<i32>,
     State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let etag = etag::dataset_etag(&pool, etag::LOCATIONS).await?;
+    if etag::matches(&headers, &etag) {
+        return Ok(etag::not_modified(&etag, CacheClass::Reference));
+    }
+
     let wards = sqlx::query_as::<_, Ward>(
         "SELECT id, name FROM wards WHERE constituency_id = $1",
     )
@@ -83,118 +105,7 @@ pub async fn get_wards_by_constituency(
     .fetch_all(&pool)
     .await?;
 
-    Ok((StatusCode::OK, Json(json!({ "data": wards }))))
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
-pub struct BusinessBranchLocation {
-    id: i32,
-    created_at: NaiveDateTime,
-    updated_at: Option<NaiveDateTime>,
-    name: String,
-    latitude: f64,
-    longitude: f64,
-    ward_id: i32,
-    phone: String,
-    address: String,
-}
-
-#[derive(Deserialize, Validate, Serialize, Debug, Clone, sqlx::FromRow)]
-pub struct CreateBranchLocationRequest {
-    #[validate(length(min = 1, max = 100))]
-    pub name: String,
-    pub latitude: f64,
-    pub longitude: f64,
-    pub ward_id: i32,
-    #[validate(length(min = 1, max = 15))]
-    pub phone: String,
-    #[validate(length(min = 1, max = 255))]
-    pub address: String,
-}
-
-pub async fn create_branch_location(
-    Path(business_id): Path<i32>,
-    State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
-    Json(payload): Json<CreateBranchLocationRequest>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let owns = sqlx::query_scalar!(
-        "SELECT id FROM businesses WHERE id = $1 AND user_id = $2",
-        business_id, user_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    if owns.is_none() {
-        return Err(AppError::Forbidden(
-            "You do not have permission to create a branch for this business".to_string(),
-        ));
-    }
-
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
-
-    if business_id <= 0 {
-        return Err(AppError::BadRequest("Invalid business ID".to_string()));
-    }
-
-    let now = chrono::Utc::now().naive_utc();
-
-    let location = sqlx::query_as::<_, CreateBranchLocationRequest>(
-        r#"INSERT INTO business_branches (business_id, name, latitude, longitude, ward_id, phone, address, created_at, updated_at)
-           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-           RETURNING name, latitude, longitude, ward_id, phone, address"#,
-    )
-    .bind(business_id)
-    .bind(&payload.name)
-    .bind(payload.latitude)
-    .bind(payload.longitude)
-    .bind(payload.ward_id)
-    .bind(&payload.phone)
-    .bind(&payload.address)
-    .bind(now)
-    .bind(now)
-    .fetch_one(&pool)
-    .await?;
-
-    Ok((StatusCode::CREATED, Json(json!({ "data": location }))))
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
-pub struct BranchLocationResponse {
-    pub id: i32,
-    pub business_id: i32,
-    pub name: String,
-    pub latitude: f64,
-    pub longitude: f64,
-    pub ward_id: i32,
-    pub ward_name: String,
-    pub constituency_name: String,
-    pub county_name: String,
-    pub phone: String,
-    pub address: String,
-    pub created_at: NaiveDateTime,
-    pub updated_at: NaiveDateTime,
-}
-
-pub async fn get_branch_locations(
-    Path(business_id): Path<i32>,
-    State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let locations = sqlx::query_as::<_, BranchLocationResponse>(
-        r#"SELECT bl.id, bl.business_id, bl.name, bl.latitude, bl.longitude,
-                  bl.ward_id, w.name AS ward_name, c.name AS constituency_name,
-                  co.name AS county_name, bl.phone, bl.address, bl.created_at, bl.updated_at
-           FROM business_branches bl
-           JOIN wards w ON bl.ward_id = w.id
-           JOIN constituencies c ON w.constituency_id = c.id
-           JOIN counties co ON c.county_id = co.id
-           WHERE bl.business_id = $1"#,
-    )
-    .bind(business_id)
-    .fetch_all(&pool)
-    .await?;
-
-    Ok((StatusCode::OK, Json(json!({ "data": locations }))))
+    Ok(etag::tagged(&etag, CacheClass::Reference, Json(json!({ "data": wards }))))
 }
 
 #[derive(Deserialize, Validate, Serialize, Debug, Clone, sqlx::FromRow)]
@@ -209,7 +120,7 @@ pub struct ProviderLocationRequest {
 pub async fn create_provider_location(
     Path(provider_id): Path<i32>,
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<ProviderLocationRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let owns = sqlx::query_scalar!(
@@ -225,7 +136,7 @@ pub async fn create_provider_location(
         ));
     }
 
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
     if provider_id <= 0 {
         return Err(AppError::BadRequest("Invalid provider ID".to_string()));
@@ -248,6 +159,7 @@ pub async fn create_provider_location(
     .bind(now)
     .fetch_one(&pool)
     .await?;
+    onboarding::refresh_score(&pool, provider_id).await;
 
     Ok((StatusCode::CREATED, Json(json!({ "data": location }))))
 }
@@ -281,11 +193,13 @@ pub async fn search_business_or_provider_by_location(
                       bb.address, bb.phone,
                       w.name AS ward_name, c.name AS constituency_name, co.name AS county_name
                FROM businesses b
-               JOIN business_branches bb ON bb.business_id = b.id
+               JOIN business_branches bb ON bb.business_id = b.id AND bb.is_active
                JOIN wards w ON bb.ward_id = w.id
                JOIN constituencies c ON w.constituency_id = c.id
                JOIN counties co ON c.county_id = co.id
-               WHERE ($1::int IS NULL OR co.id = $1)
+               WHERE b.listing_status = 'approved'
+                 AND NOT user_is_hidden(b.user_id)
+                 AND ($1::int IS NULL OR co.id = $1)
                  AND ($2::int IS NULL OR c.id = $2)
                  AND ($3::int IS NULL OR w.id = $3)
                ORDER BY b.id"#,
@@ -305,7 +219,9 @@ pub async fn search_business_or_provider_by_location(
                JOIN wards w ON pl.ward_id = w.id
                JOIN constituencies c ON w.constituency_id = c.id
                JOIN counties co ON c.county_id = co.id
-               WHERE ($1::int IS NULL OR co.id = $1)
+               WHERE p.listing_status = 'approved'
+                 AND NOT user_is_hidden(p.user_id)
+                 AND ($1::int IS NULL OR co.id = $1)
                  AND ($2::int IS NULL OR c.id = $2)
                  AND ($3::int IS NULL OR w.id = $3)
                ORDER BY p.id"#,
@@ -324,118 +240,6 @@ pub async fn search_business_or_provider_by_location(
     Ok((StatusCode::OK, Json(json!({ "data": results }))))
 }
 
-// ── Branch location CRUD ──────────────────────────────────────────────────────
-
-pub async fn get_branch_by_id(
-    Path(id): Path<i32>,
-    State(pool): State<PgPool>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let branch = sqlx::query_as::<_, BranchLocationResponse>(
-        r#"SELECT bl.id, bl.business_id, bl.name, bl.latitude, bl.longitude,
-                  bl.ward_id, w.name AS ward_name, c.name AS constituency_name,
-                  co.name AS county_name, bl.phone, bl.address, bl.created_at, bl.updated_at
-           FROM business_branches bl
-           JOIN wards w ON bl.ward_id = w.id
-           JOIN constituencies c ON w.constituency_id = c.id
-           JOIN counties co ON c.county_id = co.id
-           WHERE bl.id = $1"#,
-    )
-    .bind(id)
-    .fetch_optional(&pool)
-    .await?
-    .ok_or_else(|| AppError::NotFound("Branch location not found".to_string()))?;
-
-    Ok((StatusCode::OK, Json(json!({ "data": branch }))))
-}
-
-#[derive(Deserialize, Validate, Debug)]
-pub struct UpdateBranchRequest {
-    #[validate(length(min = 1, max = 100))]
-    pub name: Option<String>,
-    pub latitude: Option<f64>,
-    pub longitude: Option<f64>,
-    pub ward_id: Option<i32>,
-    #[validate(length(min = 1, max = 15))]
-    pub phone: Option<String>,
-    #[validate(length(min = 1, max = 255))]
-    pub address: Option<String>,
-}
-
-pub async fn update_branch_location(
-    Path(id): Path<i32>,
-    State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
-    Json(payload): Json<UpdateBranchRequest>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
-
-    // Ownership check — user must own the business this branch belongs to
-    let owned = sqlx::query_scalar!(
-        "SELECT 1 FROM business_branches bb
-         JOIN businesses b ON bb.business_id = b.id
-         WHERE bb.id = $1 AND b.user_id = $2",
-        id, user_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    if owned.is_none() {
-        return Err(AppError::Forbidden(
-            "You do not have permission to update this branch".to_string(),
-        ));
-    }
-
-    sqlx::query!(
-        r#"UPDATE business_branches SET
-               name       = COALESCE($1, name),
-               latitude   = COALESCE($2, latitude),
-               longitude  = COALESCE($3, longitude),
-               ward_id    = COALESCE($4, ward_id),
-               phone      = COALESCE($5, phone),
-               address    = COALESCE($6, address),
-               updated_at = NOW()
-           WHERE id = $7"#,
-        payload.name,
-        payload.latitude,
-        payload.longitude,
-        payload.ward_id,
-        payload.phone,
-        payload.address,
-        id
-    )
-    .execute(&pool)
-    .await?;
-
-    Ok((StatusCode::OK, Json(json!({ "message": "Branch location updated successfully" }))))
-}
-
-pub async fn delete_branch_location(
-    Path(id): Path<i32>,
-    State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let owned = sqlx::query_scalar!(
-        "SELECT 1 FROM business_branches bb
-         JOIN businesses b ON bb.business_id = b.id
-         WHERE bb.id = $1 AND b.user_id = $2",
-        id, user_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    if owned.is_none() {
-        return Err(AppError::Forbidden(
-            "You do not have permission to delete this branch".to_string(),
-        ));
-    }
-
-    sqlx::query!("DELETE FROM business_branches WHERE id = $1", id)
-        .execute(&pool)
-        .await?;
-
-    Ok((StatusCode::OK, Json(json!({ "message": "Branch location deleted successfully" }))))
-}
-
 // ── Provider location CRUD ────────────────────────────────────────────────────
 
 #[derive(Serialize, sqlx::FromRow, Debug)]
@@ -480,19 +284,20 @@ pub struct UpdateProviderLocationRequest {
 pub async fn update_provider_location(
     Path(id): Path<i32>,
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<UpdateProviderLocationRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let owned = sqlx::query_scalar!(
-        "SELECT 1 FROM provider_locations pl
+    let owner_id = sqlx::query_scalar!(
+        "SELECT p.user_id FROM provider_locations pl
          JOIN providers p ON pl.provider_id = p.id
-         WHERE pl.id = $1 AND p.user_id = $2",
-        id, user_id
+         WHERE pl.id = $1",
+        id
     )
     .fetch_optional(&pool)
-    .await?;
+    .await?
+    .ok_or_else(|| AppError::NotFound("Provider location not found".to_string()))?;
 
-    if owned.is_none() {
+    if owner_id != user_id {
         return Err(AppError::Forbidden(
             "You do not have permission to update this location".to_string(),
         ));
@@ -523,26 +328,30 @@ pub async fn update_provider_location(
 pub async fn delete_provider_location(
     Path(id): Path<i32>,
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let owned = sqlx::query_scalar!(
-        "SELECT 1 FROM provider_locations pl
+    let owner_id = sqlx::query_scalar!(
+        "SELECT p.user_id FROM provider_locations pl
          JOIN providers p ON pl.provider_id = p.id
-         WHERE pl.id = $1 AND p.user_id = $2",
-        id, user_id
+         WHERE pl.id = $1",
+        id
     )
     .fetch_optional(&pool)
-    .await?;
+    .await?
+    .ok_or_else(|| AppError::NotFound("Provider location not found".to_string()))?;
 
-    if owned.is_none() {
+    if owner_id != user_id {
         return Err(AppError::Forbidden(
             "You do not have permission to delete this location".to_string(),
         ));
     }
 
-    sqlx::query!("DELETE FROM provider_locations WHERE id = $1", id)
-        .execute(&pool)
+    let provider_id = sqlx::query_scalar!("DELETE FROM provider_locations WHERE id = $1 RETURNING provider_id", id)
+        .fetch_optional(&pool)
         .await?;
+    if let Some(provider_id) = provider_id {
+        onboarding::refresh_score(&pool, provider_id).await;
+    }
 
     Ok((StatusCode::OK, Json(json!({ "message": "Provider location deleted successfully" }))))
 }
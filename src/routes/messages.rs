@@ -1,8 +1,14 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::routes::ws;
+use crate::utils::body_limit::upload_route;
 use crate::utils::notifications::notify_best_effort;
+use crate::utils::pagination::{Paginated, Pagination};
+use crate::utils::prefs;
 use crate::utils::image_upload::parse_image_from_multipart;
+use crate::utils::interactions::{self, Interaction};
 use crate::utils::storage::{SharedStorage, generate_key};
+use crate::utils::thumbnails::store_image_variants;
 use crate::utils::ws_state::{WsConnections, push_to_user};
 use axum::{
     Extension, Json, Router,
@@ -10,7 +16,7 @@ use axum::{
     http::StatusCode,
     routing::{get, post},
 };
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
@@ -22,7 +28,8 @@ pub fn messages_routes(pool: PgPool) -> Router {
         .route("/markMessagesAsRead", post(mark_messages_as_read))
         .route("/unreadMessagesCount", get(get_unread_messages_count))
         .route("/conversations", get(get_conversations))
-        .route("/upload", post(upload_message_attachment))
+        .route("/upload", upload_route(post(upload_message_attachment), &pool))
+        .route("/ws", ws::ws_route())
         .with_state(pool)
 }
 
@@ -50,7 +57,7 @@ pub struct Message {
 pub async fn send_message(
     State(pool): State<PgPool>,
     Extension(ws_conns): Extension<WsConnections>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<NewMessage>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if payload.content.is_empty() {
@@ -77,15 +84,7 @@ pub async fn send_message(
     .fetch_one(&mut *tx)
     .await?;
 
-    sqlx::query!(
-        "INSERT INTO interactions (user_id, target_type, target_id, interaction_type)
-         VALUES ($1, $2, $3, 'message')",
-        user_id,
-        &target_type,
-        payload.target_id
-    )
-    .execute(&mut *tx)
-    .await?;
+    interactions::record(&mut *tx, user_id, &target_type, payload.target_id, Interaction::Message).await?;
 
     tx.commit().await?;
 
@@ -96,14 +95,7 @@ pub async fn send_message(
         Some("message"), Some(message.id),
     ).await;
 
-    push_to_user(&ws_conns, payload.receiver_id, "new_message", json!({
-        "id": message.id,
-        "sender_id": message.sender_id,
-        "content": message.content,
-        "target_type": message.target_type,
-        "target_id": message.target_id,
-        "created_at": message.created_at.to_string(),
-    })).await;
+    push_to_user(&ws_conns, payload.receiver_id, "new_message", json!(message)).await;
 
     Ok((StatusCode::CREATED, Json(json!({ "message": message }))))
 }
@@ -115,24 +107,20 @@ pub struct MessageQuery {
     pub other_user_id: i32,
     pub target_type: String,
     pub target_id: i32,
-    page: Option<i32>,
-    limit: Option<i32>,
 }
 
+/// One thread, oldest first, paged with `?page=&per_page=`.
 pub async fn get_messages(
     State(pool): State<PgPool>,
     Query(params): Query<MessageQuery>,
-    CurrentUser { user_id }: CurrentUser,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    pagination: Pagination,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<Paginated<Message>>)> {
     let target_type = params.target_type.to_lowercase();
     if !["provider", "business"].contains(&target_type.as_str()) {
         return Err(AppError::BadRequest("Invalid target type".to_string()));
     }
 
-    let page = params.page.unwrap_or(1).max(1);
-    let limit = params.limit.unwrap_or(50).clamp(1, 100);
-    let offset = (page - 1) * limit;
-
     let messages = sqlx::query_as::<sqlx::Postgres, Message>(
         "SELECT id, sender_id, receiver_id, content, target_type, target_id, created_at, read_at, is_read
          FROM messages
@@ -142,19 +130,32 @@ pub async fn get_messages(
          )
          AND target_type = $3
          AND target_id = $4
-         ORDER BY created_at ASC
+         ORDER BY created_at ASC, id ASC
          LIMIT $5 OFFSET $6",
     )
     .bind(user_id)
     .bind(params.other_user_id)
     .bind(&target_type)
     .bind(params.target_id)
-    .bind(limit as i64)
-    .bind(offset as i64)
-    .fetch_all(&pool)
-    .await?;
+    .bind(pagination.limit())
+    .bind(pagination.offset())
+    .fetch_all(&pool);
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM messages
+           WHERE ((sender_id = $1 AND receiver_id = $2) OR (sender_id = $2 AND receiver_id = $1))
+             AND target_type = $3
+             AND target_id = $4"#,
+        user_id,
+        params.other_user_id,
+        &target_type,
+        params.target_id
+    )
+    .fetch_one(&pool);
 
-    Ok((StatusCode::OK, Json(json!({ "messages": messages }))))
+    let (messages, total) = tokio::try_join!(messages, total)?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(messages, pagination, total))))
 }
 
 // ── Mark messages as read ─────────────────────────────────────────────────────
@@ -166,7 +167,7 @@ pub struct MarkReadPayload {
 
 pub async fn mark_messages_as_read(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<MarkReadPayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if payload.message_ids.is_empty() {
@@ -192,7 +193,7 @@ pub async fn mark_messages_as_read(
 
 pub async fn get_unread_messages_count(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let count = sqlx::query_scalar!(
         "SELECT COUNT(*) FROM messages WHERE receiver_id = $1 AND is_read = FALSE",
@@ -215,12 +216,15 @@ pub struct ConversationRow {
     pub last_message: String,
     pub last_message_at: NaiveDateTime,
     pub unread_count: i64,
+    /// Null when the other user hides when they were last active.
+    pub other_last_seen_at: Option<DateTime<Utc>>,
 }
 
 pub async fn get_conversations(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    CurrentUser { user_id, .. }: CurrentUser,
+    pagination: Pagination,
+) -> AppResult<(StatusCode, Json<Paginated<ConversationRow>>)> {
     // Return one row per unique (other_user, target_type, target_id) thread,
     // with the latest message and unread count. Works for any role.
     let conversations = sqlx::query_as::<sqlx::Postgres, ConversationRow>(
@@ -256,7 +260,11 @@ pub async fn get_conversations(
             r.target_id,
             r.last_message,
             r.last_message_at,
-            COALESCE(uc.unread_count, 0) AS unread_count
+            COALESCE(uc.unread_count, 0) AS unread_count,
+            CASE WHEN COALESCE((SELECT (up.prefs->>$2)::boolean
+                                FROM user_preferences up WHERE up.user_id = u.id), $3)
+                 THEN u.last_seen_at
+            END AS other_last_seen_at
         FROM ranked r
         JOIN users u ON u.id = r.other_user_id
         LEFT JOIN unread_counts uc
@@ -264,14 +272,29 @@ pub async fn get_conversations(
             AND uc.target_type   = r.target_type
             AND uc.target_id     = r.target_id
         WHERE r.rn = 1
-        ORDER BY r.last_message_at DESC
+        ORDER BY r.last_message_at DESC, r.other_user_id, r.target_type, r.target_id
+        LIMIT $4 OFFSET $5
         "#,
     )
     .bind(user_id)
-    .fetch_all(&pool)
-    .await?;
+    .bind(prefs::SHOW_LAST_SEEN)
+    .bind(prefs::default_flag(prefs::SHOW_LAST_SEEN))
+    .bind(pagination.limit())
+    .bind(pagination.offset())
+    .fetch_all(&pool);
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(DISTINCT (CASE WHEN sender_id = $1 THEN receiver_id ELSE sender_id END, target_type, target_id))
+               AS "count!"
+           FROM messages
+           WHERE sender_id = $1 OR receiver_id = $1"#,
+        user_id
+    )
+    .fetch_one(&pool);
+
+    let (conversations, total) = tokio::try_join!(conversations, total)?;
 
-    Ok((StatusCode::OK, Json(json!({ "conversations": conversations }))))
+    Ok((StatusCode::OK, Json(Paginated::new(conversations, pagination, total))))
 }
 
 // ── Upload message attachment ─────────────────────────────────────────────────
@@ -279,11 +302,12 @@ pub async fn get_conversations(
 pub async fn upload_message_attachment(
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
-    CurrentUser { user_id: _ }: CurrentUser,
+    CurrentUser { user_id: _, .. }: CurrentUser,
     multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let (data, ext, _ct) = parse_image_from_multipart(multipart).await?;
+    let (data, ext, content_type) = parse_image_from_multipart(&pool, multipart).await?;
     let key = generate_key("messages/attachments", &ext);
-    let url = storage.save(&key, &data).await?;
-    Ok((StatusCode::CREATED, Json(json!({ "url": url }))))
+    let url = storage.save(&key, &data, &content_type).await?;
+    let variants = store_image_variants(&pool, &storage, &key, &url, data).await;
+    Ok((StatusCode::CREATED, Json(json!(variants))))
 }
@@ -1,13 +1,17 @@
 pub mod admin;
 pub mod analytics;
+pub mod announcements;
 pub mod auth;
 pub mod availability;
 pub mod bookings;
+pub mod branches;
 pub mod businesses;
 pub mod categories;
 pub mod clients;
 pub mod dashboard;
 pub mod favorites;
+pub mod health;
+pub mod interactions;
 pub mod locations;
 pub mod messages;
 pub mod notifications;
@@ -17,6 +21,45 @@ pub mod posts;
 pub mod reviews;
 pub mod search;
 pub mod service_providers;
+pub mod sitemap;
 pub mod wallet;
+pub mod webhooks;
 pub mod ws;
 pub mod services;
+
+use crate::utils::attachments::attachments_routes;
+use axum::Router;
+use sqlx::PgPool;
+
+/// Every API route, relative to the API prefix. `build_app` mounts this under
+/// `API_PREFIX` and again, deprecated, at the root.
+pub fn api_routes(pool: PgPool) -> Router {
+    Router::new()
+        .nest("/auth", auth::auth_routes(pool.clone()))
+        .nest("/dashboard", dashboard::dashboard_routes(pool.clone()))
+        .nest("/service_providers", service_providers::service_providers_routes(pool.clone()))
+        .nest("/businesses", businesses::businesses_routes(pool.clone()))
+        .nest("/branches", branches::branches_routes(pool.clone()))
+        .nest("/clients", clients::client_routes(pool.clone()))
+        .nest("/reviews", reviews::reviews_routes(pool.clone()))
+        .nest("/favorites", favorites::favorites_routes(pool.clone()))
+        .nest("/interactions", interactions::interactions_routes(pool.clone()))
+        .nest("/messages", messages::messages_routes(pool.clone()))
+        .nest("/categories", categories::category_routes(pool.clone()))
+        .nest("/bookings", bookings::booking_routes(pool.clone()))
+        .nest("/admin", admin::admin_routes(pool.clone()))
+        .nest("/locations", locations::locations_routes(pool.clone()))
+        .nest("/posts", posts::posts_routes(pool.clone()))
+        .nest("/attachments", attachments_routes(pool.clone()))
+        .nest("/services", services::services_routes(pool.clone()))
+        .nest("/payments", payments::payment_routes(pool.clone()))
+        .nest("/notifications", notifications::notification_routes(pool.clone()))
+        .nest("/packages", packages::package_routes(pool.clone()))
+        .nest("/search", search::search_routes(pool.clone()))
+        .nest("/analytics", analytics::analytics_routes(pool.clone()))
+        .nest("/availability", availability::availability_routes(pool.clone()))
+        .nest("/wallet", wallet::wallet_routes(pool.clone()))
+        .nest("/announcements", announcements::announcements_routes(pool.clone()))
+        .nest("/webhooks", webhooks::webhook_routes(pool.clone()))
+        .nest("/ws", ws::ws_routes())
+}
@@ -1,20 +1,25 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::utils::notifications::Notification;
+use crate::utils::pagination::{Paginated, Pagination};
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
     http::StatusCode,
     routing::{delete, get, post},
 };
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::json;
 use sqlx::PgPool;
 
+/// Most ids accepted by one `POST /notifications/markRead`.
+const MAX_MARK_READ_IDS: usize = 500;
+
 pub fn notification_routes(pool: PgPool) -> Router {
     Router::new()
         .route("/", get(list_notifications))
         .route("/unread-count", get(unread_count))
+        .route("/markRead", post(mark_read))
         .route("/read-all", post(mark_all_read))
         .route("/:id/read", post(mark_one_read))
         .route("/:id", delete(delete_notification))
@@ -25,81 +30,62 @@ pub fn notification_routes(pool: PgPool) -> Router {
 
 #[derive(Deserialize, Debug)]
 pub struct NotifQuery {
-    pub page: Option<i64>,
-    pub per_page: Option<i64>,
+    #[serde(alias = "unread")]
     pub unread_only: Option<bool>,
 }
 
-#[derive(Serialize, sqlx::FromRow, Debug)]
-pub struct NotificationRow {
-    pub id: i32,
-    pub notif_type: String,
-    pub title: String,
-    pub body: String,
-    pub target_type: Option<String>,
-    pub target_id: Option<i32>,
-    pub is_read: bool,
-    pub created_at: NaiveDateTime,
+#[derive(Deserialize, Debug)]
+pub struct MarkReadPayload {
+    pub ids: Vec<i32>,
 }
 
 // ── GET /notifications ────────────────────────────────────────────────────────
 
+/// Newest first. `?unread_only=true` (or `?unread=true`) leaves out read ones.
 pub async fn list_notifications(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
+    pagination: Pagination,
     Query(params): Query<NotifQuery>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let page = params.page.unwrap_or(1).max(1);
-    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
-    let offset = (page - 1) * per_page;
+) -> AppResult<(StatusCode, Json<Paginated<Notification>>)> {
     let unread_only = params.unread_only.unwrap_or(false);
 
-    let notifications = if unread_only {
-        sqlx::query_as::<_, NotificationRow>(
-            r#"SELECT id, notif_type, title, body, target_type, target_id, is_read, created_at
-               FROM notifications
-               WHERE user_id = $1 AND is_read = false
-               ORDER BY created_at DESC
-               LIMIT $2 OFFSET $3"#,
-        )
-        .bind(user_id)
-        .bind(per_page)
-        .bind(offset)
-        .fetch_all(&pool)
-        .await?
-    } else {
-        sqlx::query_as::<_, NotificationRow>(
-            r#"SELECT id, notif_type, title, body, target_type, target_id, is_read, created_at
-               FROM notifications
-               WHERE user_id = $1
-               ORDER BY created_at DESC
-               LIMIT $2 OFFSET $3"#,
-        )
-        .bind(user_id)
-        .bind(per_page)
-        .bind(offset)
-        .fetch_all(&pool)
-        .await?
-    };
+    let items = sqlx::query_as!(
+        Notification,
+        r#"SELECT id, notif_type, title, body, target_type, target_id, data,
+                  read_at IS NOT NULL AS "is_read!", read_at, created_at
+           FROM notifications
+           WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+           ORDER BY created_at DESC, id DESC
+           LIMIT $3 OFFSET $4"#,
+        user_id,
+        unread_only,
+        pagination.limit(),
+        pagination.offset()
+    )
+    .fetch_all(&pool);
 
-    Ok((
-        StatusCode::OK,
-        Json(json!({
-            "notifications": notifications,
-            "page": page,
-            "per_page": per_page,
-        })),
-    ))
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM notifications
+           WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)"#,
+        user_id,
+        unread_only
+    )
+    .fetch_one(&pool);
+
+    let (items, total) = tokio::try_join!(items, total)?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(items, pagination, total))))
 }
 
 // ── GET /notifications/unread-count ──────────────────────────────────────────
 
 pub async fn unread_count(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let count: i64 = sqlx::query_scalar!(
-        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND is_read = false",
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL",
         user_id
     )
     .fetch_one(&pool)
@@ -109,15 +95,49 @@ pub async fn unread_count(
     Ok((StatusCode::OK, Json(json!({ "unread_count": count }))))
 }
 
+// ── POST /notifications/markRead ─────────────────────────────────────────────
+
+/// Marks the given notifications as read. Ids that don't exist, belong to
+/// someone else or are already read are skipped; `updated` counts the rest.
+pub async fn mark_read(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<MarkReadPayload>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if payload.ids.is_empty() {
+        return Err(AppError::BadRequest("Notification ID list cannot be empty".to_string()));
+    }
+    if payload.ids.len() > MAX_MARK_READ_IDS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} notifications can be marked at once",
+            MAX_MARK_READ_IDS
+        )));
+    }
+
+    let updated = sqlx::query!(
+        "UPDATE notifications SET read_at = NOW()
+         WHERE id = ANY($1) AND user_id = $2 AND read_at IS NULL",
+        &payload.ids,
+        user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "message": "Marked as read", "updated": updated.rows_affected() })),
+    ))
+}
+
 // ── POST /notifications/:id/read ─────────────────────────────────────────────
 
 pub async fn mark_one_read(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let updated = sqlx::query!(
-        "UPDATE notifications SET is_read = true WHERE id = $1 AND user_id = $2",
+        "UPDATE notifications SET read_at = COALESCE(read_at, NOW()) WHERE id = $1 AND user_id = $2",
         id,
         user_id
     )
@@ -135,10 +155,10 @@ pub async fn mark_one_read(
 
 pub async fn mark_all_read(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let updated = sqlx::query!(
-        "UPDATE notifications SET is_read = true WHERE user_id = $1 AND is_read = false",
+        "UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL",
         user_id
     )
     .execute(&pool)
@@ -154,7 +174,7 @@ pub async fn mark_all_read(
 
 pub async fn delete_notification(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let deleted = sqlx::query!(
@@ -93,7 +93,7 @@ async fn verify_owner(
 
 pub async fn create_package(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<CreatePackageInput>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let target_type = payload.target_type.to_lowercase();
@@ -120,27 +120,31 @@ pub async fn create_package(
     .fetch_one(&mut *tx)
     .await?;
 
-    for sid in &payload.service_ids {
-        // Verify service belongs to this target
-        let exists = sqlx::query_scalar!(
-            "SELECT id FROM services WHERE id = $1 AND target_type = $2 AND target_id = $3",
-            sid, target_type, payload.target_id
-        )
-        .fetch_optional(&mut *tx)
-        .await?;
-
-        if exists.is_none() {
-            return Err(AppError::BadRequest(format!("Service {} not found for this target", sid)));
-        }
-
-        sqlx::query!(
-            "INSERT INTO service_package_items (package_id, service_id) VALUES ($1, $2)",
-            pkg.id, sid
-        )
-        .execute(&mut *tx)
-        .await?;
+    // Every service must belong to this target
+    let missing = sqlx::query_scalar!(
+        r#"SELECT sid AS "sid!" FROM UNNEST($1::int[]) AS sid
+           WHERE NOT EXISTS (
+               SELECT 1 FROM services s WHERE s.id = sid AND s.target_type = $2 AND s.target_id = $3
+           )
+           LIMIT 1"#,
+        &payload.service_ids,
+        target_type,
+        payload.target_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(sid) = missing {
+        return Err(AppError::BadRequest(format!("Service {} not found for this target", sid)));
     }
 
+    sqlx::query!(
+        "INSERT INTO service_package_items (package_id, service_id) SELECT $1, UNNEST($2::int[])",
+        pkg.id,
+        &payload.service_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
     tx.commit().await?;
 
     Ok((StatusCode::CREATED, Json(json!({ "message": "Package created", "package_id": pkg.id }))))
@@ -179,7 +183,7 @@ pub async fn get_package(
 
 pub async fn update_package(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(id): Path<i32>,
     Json(payload): Json<UpdatePackageInput>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -213,7 +217,7 @@ pub async fn update_package(
 
 pub async fn delete_package(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let pkg = sqlx::query!(
@@ -236,7 +240,7 @@ pub async fn delete_package(
 
 pub async fn add_item(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(id): Path<i32>,
     Json(payload): Json<PackageItemInput>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -272,7 +276,7 @@ pub async fn add_item(
 
 pub async fn remove_item(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(id): Path<i32>,
     Json(payload): Json<PackageItemInput>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -1,8 +1,12 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::utils::booking_events::record_booking_event;
+use crate::utils::flags;
+use crate::utils::money;
 use crate::utils::mpesa::{MpesaConfig, MpesaCallback, normalize_phone, stk_push};
 use crate::utils::notifications::{notify_and_push, notify_target_owner_and_push};
 use crate::utils::wallet::credit_wallet_best_effort;
+use crate::utils::webhooks;
 use crate::utils::sms::{SmsConfig, payment_success_sms, payment_failed_sms, send_sms_best_effort};
 use crate::utils::ws_state::WsConnections;
 use axum::{
@@ -21,6 +25,7 @@ pub fn payment_routes(pool: PgPool) -> Router {
         .route("/initiate", post(initiate_payment))
         .route("/mpesa/callback", post(mpesa_callback))
         .route("/booking/:booking_id", get(get_payment_status))
+        .route_layer(axum::middleware::from_fn_with_state(flags::PAYMENTS, flags::require_flag))
         .with_state(pool)
 }
 
@@ -50,9 +55,12 @@ pub struct PaymentRecord {
 
 pub async fn initiate_payment(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    current_user: CurrentUser,
     Json(payload): Json<InitiatePaymentRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    current_user.deny_impersonation()?;
+    let user_id = current_user.user_id;
+
     // Verify booking belongs to this user
     let booking = sqlx::query!(
         "SELECT id, status FROM bookings WHERE id = $1 AND client_id = $2",
@@ -180,17 +188,34 @@ pub async fn mpesa_callback(
 
     // If payment succeeded, mark the booking as confirmed and credit the wallet
     if status == "completed" {
-        let _ = sqlx::query!(
+        let confirmed = sqlx::query_scalar!(
             r#"UPDATE bookings b
                SET status = 'confirmed', updated_at = NOW()
                FROM payments p
                WHERE p.checkout_request_id = $1
                  AND p.booking_id = b.id
-                 AND b.status = 'pending'"#,
+                 AND b.status = 'pending'
+               RETURNING b.id"#,
             cb.checkout_request_id
         )
-        .execute(&pool)
-        .await;
+        .fetch_optional(&pool)
+        .await
+        .ok()
+        .flatten();
+
+        if let Some(booking_id) = confirmed {
+            if let Err(e) = record_booking_event(
+                &pool, booking_id, None, "payment_confirmed",
+                Some("pending"), Some("confirmed"), transaction_id.as_deref(),
+            ).await {
+                tracing::error!("Failed to record booking event for booking {}: {}", booking_id, e);
+            }
+            if let Err(e) = webhooks::enqueue_booking_event(
+                &pool, webhooks::BOOKING_STATUS_CHANGED, booking_id, Some("pending"),
+            ).await {
+                tracing::error!("Failed to queue webhooks for booking {}: {}", booking_id, e);
+            }
+        }
 
         // Credit the provider/business wallet with the payment amount
         if let Some(row) = sqlx::query!(
@@ -244,7 +269,7 @@ pub async fn mpesa_callback(
                     .and_then(|m| m.receipt_number())
                     .unwrap_or_else(|| "N/A".to_string());
                 payment_success_sms(
-                    &p.amount.to_string(),
+                    &money::format_kes(&p.amount),
                     &receipt,
                     p.booking_id.unwrap_or(0),
                 )
@@ -296,7 +321,7 @@ pub async fn mpesa_callback(
 
 pub async fn get_payment_status(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(booking_id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     // Check booking belongs to this user
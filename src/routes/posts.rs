@@ -1,17 +1,24 @@
+use crate::config;
 use crate::errors::{AppError, AppResult};
-use crate::extractors::current_user::CurrentUser;
-use crate::utils::notifications::notify_and_push;
-use crate::utils::ws_state::WsConnections;
+use crate::extractors::administrator::is_admin;
+use crate::extractors::current_user::{CurrentUser, OptionalCurrentUser};
+use crate::extractors::tx::{Tx, transactional};
+use crate::utils::audit::record_audit;
+use crate::utils::client_ip::client_ip;
+use crate::utils::pagination::{Paginated, Pagination};
 use axum::{
-    Extension, Json, Router,
-    extract::{Path, Query, State},
-    http::StatusCode,
+    Json, Router,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     routing::{delete, get, post},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use sqlx::PgPool;
+use std::net::{IpAddr, SocketAddr};
 use validator::Validate;
 
 pub fn posts_routes(pool: PgPool) -> Router {
@@ -19,14 +26,21 @@ pub fn posts_routes(pool: PgPool) -> Router {
         .route("/createPosts", post(create_posts))
         .route("/getAllPosts", get(get_all_posts))
         .route("/getPost/:id", get(get_post_by_id))
+        .route("/slug/:slug", get(get_post_by_slug))
         .route("/provider/:id/posts", get(get_posts_by_provider_id))
         .route("/business/:id/posts", get(get_posts_by_business_id))
         .route("/deletePost/:id", post(delete_post))
-        .route("/updatePost/:id", post(update_post_and_attachments))
+        .route("/updatePost/:id", transactional(post(update_post_and_attachments)))
         // Interactions
         .route("/:id/like", post(like_post).delete(unlike_post))
         .route("/:id/comments", get(get_comments).post(add_comment))
         .route("/:id/comments/:comment_id", delete(delete_comment))
+        // Reach
+        .route("/:id/view", post(record_post_view))
+        .route("/:id/analytics", get(get_post_analytics))
+        // Moderation
+        .route("/:id/report", post(report_post))
+        .route("/:id/appeal", post(appeal_post))
         .with_state(pool)
 }
 
@@ -42,21 +56,12 @@ pub struct CreatePost {
 
 pub async fn create_posts(
     State(pool): State<PgPool>,
-    Extension(ws_conns): Extension<WsConnections>,
-    CurrentUser { user_id }: CurrentUser,
+    user: CurrentUser,
     Json(payload): Json<CreatePost>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let role = sqlx::query_scalar!("SELECT role FROM users WHERE id = $1", user_id)
-        .fetch_optional(&pool)
-        .await?
-        .flatten()
-        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
-
-    if role == "client" {
-        return Err(AppError::Forbidden("Clients are not authorized to create posts".to_string()));
-    }
+    user.require_role(&["provider", "business"])?;
 
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
     if let Some(business_id) = payload.business_id {
         let exists = sqlx::query_scalar!("SELECT id FROM businesses WHERE id = $1", business_id)
@@ -76,45 +81,28 @@ pub async fn create_posts(
         }
     }
 
-    let now = Utc::now();
+    // created_at / updated_at come from the column defaults (TIMESTAMPTZ, NOW())
     let post = sqlx::query!(
-        r#"INSERT INTO posts (title, content, business_id, provider_id, created_at, updated_at)
-           VALUES ($1, $2, $3, $4, $5, $6) RETURNING id"#,
+        r#"INSERT INTO posts (title, content, business_id, provider_id)
+           VALUES ($1, $2, $3, $4) RETURNING id, created_at"#,
         payload.title,
         payload.content,
         payload.business_id,
         payload.provider_id,
-        now,
-        now
     )
     .fetch_one(&pool)
     .await?;
 
-    // Notify all users who have favourited this provider/business
-    let (target_type, target_id) = match (payload.provider_id, payload.business_id) {
-        (Some(pid), _) => ("provider", pid),
-        (_, Some(bid)) => ("business", bid),
-        _ => return Ok((StatusCode::CREATED, Json(json!({ "post_id": post.id })))),
-    };
-
-    let favouriters: Vec<i32> = sqlx::query_scalar!(
-        "SELECT user_id FROM favorites WHERE target_type = $1 AND target_id = $2",
-        target_type, target_id
-    )
-    .fetch_all(&pool)
-    .await
-    .unwrap_or_default();
-
-    for uid in favouriters {
-        notify_and_push(
-            &pool, &ws_conns, uid,
-            "new_post", "New Post",
-            &format!("A provider you follow posted: {}", payload.title.trim()),
-            Some(target_type), Some(target_id),
-        ).await;
-    }
+    // Followers are notified by the post fan-out sweep (utils::post_fanout)
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "post_id": post.id, "created_at": rfc3339(&post.created_at) })),
+    ))
+}
 
-    Ok((StatusCode::CREATED, Json(json!({ "post_id": post.id }))))
+/// Post timestamps are always rendered as UTC RFC 3339, e.g. `2025-06-01T09:30:00.000Z`.
+fn rfc3339(ts: &DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Millis, true)
 }
 
 #[derive(Deserialize, Serialize)]
@@ -126,6 +114,7 @@ pub struct PostQuery {
 #[derive(Debug, sqlx::FromRow)]
 struct PostRow {
     pub id: i32,
+    pub slug: String,
     pub title: String,
     pub content: String,
     pub business_id: Option<i32>,
@@ -133,8 +122,15 @@ struct PostRow {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     image_urls_csv: String,
+    images: serde_json::Value,
     pub like_count: i64,
     pub comment_count: Option<i64>,
+    pub view_count: i32,
+    pub hidden: bool,
+    pub hidden_reason: Option<String>,
+    pub appeal_note: Option<String>,
+    /// NULL for anonymous callers.
+    pub liked_by_me: Option<bool>,
 }
 
 impl PostRow {
@@ -144,61 +140,162 @@ impl PostRow {
         } else {
             self.image_urls_csv.split(',').collect()
         };
-        json!({
+        let mut value = json!({
             "id": self.id,
+            "slug": self.slug,
             "title": self.title,
             "content": self.content,
             "business_id": self.business_id,
             "provider_id": self.provider_id,
-            "created_at": self.created_at,
-            "updated_at": self.updated_at,
+            "created_at": rfc3339(&self.created_at),
+            "updated_at": rfc3339(&self.updated_at),
             "image_urls": image_urls,
+            "images": self.images,
             "like_count": self.like_count,
             "comment_count": self.comment_count.unwrap_or(0),
-        })
+            "view_count": self.view_count,
+            "hidden": self.hidden,
+        });
+        if let Some(liked) = self.liked_by_me {
+            value["liked_by_me"] = json!(liked);
+        }
+        // Hidden posts only reach their author, who gets told why
+        if self.hidden {
+            value["moderation_notice"] = json!({
+                "message": "This post was hidden by our moderators and is only visible to you.",
+                "reason": self.hidden_reason,
+                "appeal_note": self.appeal_note,
+            });
+        }
+        value
     }
 }
 
-const POSTS_WITH_DETAILS_SQL: &str = r#"
+/// Posts with their images and counts, for a caller whose user id (or NULL)
+/// is bound to `$viewer_param`.
+fn posts_with_details(viewer_param: usize) -> String {
+    format!(
+        r#"
     SELECT
-        p.id, p.title, p.content, p.business_id, p.provider_id,
-        p.created_at, p.updated_at,
+        p.id, p.slug, p.title, p.content, p.business_id, p.provider_id,
+        p.created_at, p.updated_at, p.view_count, p.hidden, p.hidden_reason, p.appeal_note,
         COALESCE(string_agg(DISTINCT a.file_path, ','), '') AS image_urls_csv,
+        COALESCE(jsonb_agg(DISTINCT jsonb_build_object(
+            'url', a.file_path,
+            'thumb_url', image_variant(a.file_path, 'thumb'),
+            'medium_url', image_variant(a.file_path, 'medium'),
+            'caption', a.caption,
+            'alt_text', a.alt_text
+        )) FILTER (WHERE a.id IS NOT NULL), '[]') AS images,
         COUNT(DISTINCT pl.user_id) AS like_count,
-        (SELECT COUNT(*) FROM post_comments WHERE post_id = p.id) AS comment_count
+        (SELECT COUNT(*) FROM post_comments WHERE post_id = p.id) AS comment_count,
+        CASE WHEN ${viewer_param}::int IS NULL THEN NULL
+             ELSE COALESCE(bool_or(pl.user_id = ${viewer_param}), FALSE) END AS liked_by_me
     FROM posts p
     LEFT JOIN attachments a ON a.post_id = p.id
     LEFT JOIN post_likes pl ON pl.post_id = p.id
-"#;
+"#
+    )
+}
+
+/// WHERE fragment that hides moderated posts, and posts by suspended or deleted authors, from
+/// everyone except their author.
+/// `param` is the placeholder index the viewer's user id is bound to.
+fn visible_to_viewer(param: usize) -> String {
+    format!(
+        "((NOT p.hidden
+           AND NOT EXISTS (SELECT 1 FROM providers sp WHERE sp.id = p.provider_id AND user_is_hidden(sp.user_id))
+           AND NOT EXISTS (SELECT 1 FROM businesses sb WHERE sb.id = p.business_id AND user_is_hidden(sb.user_id)))
+          OR EXISTS (SELECT 1 FROM providers pr WHERE pr.id = p.provider_id AND pr.user_id = ${param})
+          OR EXISTS (SELECT 1 FROM businesses bz WHERE bz.id = p.business_id AND bz.user_id = ${param}))"
+    )
+}
 
+/// 404s unless `viewer` may see the post, so a taken-down post can't be
+/// liked, commented on or have its thread read by anyone but its author.
+async fn ensure_visible(pool: &PgPool, post_id: i32, viewer: Option<i32>) -> AppResult<()> {
+    sqlx::query_scalar::<_, i32>(&format!("SELECT p.id FROM posts p WHERE p.id = $1 AND {}", visible_to_viewer(2)))
+        .bind(post_id)
+        .bind(viewer)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+    Ok(())
+}
+
+/// Posts, newest first, paged with `?page=&per_page=`.
 pub async fn get_all_posts(
     State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
     Query(params): Query<PostQuery>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let posts = sqlx::query_as::<_, PostRow>(
-        &format!(
-            "{} WHERE ($1::int IS NULL OR p.business_id = $1) AND ($2::int IS NULL OR p.provider_id = $2)
-             GROUP BY p.id ORDER BY p.created_at DESC",
-            POSTS_WITH_DETAILS_SQL
-        ),
-    )
-    .bind(params.business_id)
-    .bind(params.provider_id)
-    .fetch_all(&pool)
-    .await?;
+    pagination: Pagination,
+) -> AppResult<(StatusCode, Json<Paginated<serde_json::Value>>)> {
+    let filters = format!(
+        "WHERE ($1::int IS NULL OR p.business_id = $1) AND ($2::int IS NULL OR p.provider_id = $2) AND {}",
+        visible_to_viewer(3)
+    );
+    let list_sql = format!(
+        "{} {filters} GROUP BY p.id ORDER BY p.created_at DESC, p.id DESC LIMIT $4 OFFSET $5",
+        posts_with_details(3)
+    );
+    let count_sql = format!("SELECT COUNT(*) FROM posts p {filters}");
+
+    let (posts, total) = tokio::try_join!(
+        sqlx::query_as::<_, PostRow>(&list_sql)
+            .bind(params.business_id)
+            .bind(params.provider_id)
+            .bind(viewer.user_id())
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&pool),
+        sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(params.business_id)
+            .bind(params.provider_id)
+            .bind(viewer.user_id())
+            .fetch_one(&pool),
+    )?;
 
     let values: Vec<serde_json::Value> = posts.iter().map(|p| p.to_value()).collect();
-    Ok((StatusCode::OK, Json(json!({ "posts": values }))))
+    Ok((StatusCode::OK, Json(Paginated::new(values, pagination, total))))
 }
 
 pub async fn get_post_by_id(
     State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
     Path(id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let post = sqlx::query_as::<_, PostRow>(
-        &format!("{} WHERE p.id = $1 GROUP BY p.id", POSTS_WITH_DETAILS_SQL),
+        &format!(
+            "{} WHERE p.id = $1 AND {} GROUP BY p.id",
+            posts_with_details(2),
+            visible_to_viewer(2)
+        ),
     )
     .bind(id)
+    .bind(viewer.user_id())
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    Ok((StatusCode::OK, Json(json!({ "post": post.to_value() }))))
+}
+
+/// The post behind a `/posts/<slug>` page, with the same visibility rules as
+/// fetching it by id.
+pub async fn get_post_by_slug(
+    State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
+    Path(slug): Path<String>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let post = sqlx::query_as::<_, PostRow>(
+        &format!(
+            "{} WHERE p.slug = $1 AND {} GROUP BY p.id",
+            posts_with_details(2),
+            visible_to_viewer(2)
+        ),
+    )
+    .bind(slug)
+    .bind(viewer.user_id())
     .fetch_optional(&pool)
     .await?
     .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
@@ -208,12 +305,18 @@ pub async fn get_post_by_id(
 
 pub async fn get_posts_by_provider_id(
     State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
     Path(provider_id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let posts = sqlx::query_as::<_, PostRow>(
-        &format!("{} WHERE p.provider_id = $1 GROUP BY p.id ORDER BY p.created_at DESC", POSTS_WITH_DETAILS_SQL),
+        &format!(
+            "{} WHERE p.provider_id = $1 AND {} GROUP BY p.id ORDER BY p.created_at DESC",
+            posts_with_details(2),
+            visible_to_viewer(2)
+        ),
     )
     .bind(provider_id)
+    .bind(viewer.user_id())
     .fetch_all(&pool)
     .await?;
 
@@ -223,12 +326,18 @@ pub async fn get_posts_by_provider_id(
 
 pub async fn get_posts_by_business_id(
     State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
     Path(business_id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let posts = sqlx::query_as::<_, PostRow>(
-        &format!("{} WHERE p.business_id = $1 GROUP BY p.id ORDER BY p.created_at DESC", POSTS_WITH_DETAILS_SQL),
+        &format!(
+            "{} WHERE p.business_id = $1 AND {} GROUP BY p.id ORDER BY p.created_at DESC",
+            posts_with_details(2),
+            visible_to_viewer(2)
+        ),
     )
     .bind(business_id)
+    .bind(viewer.user_id())
     .fetch_all(&pool)
     .await?;
 
@@ -239,7 +348,7 @@ pub async fn get_posts_by_business_id(
 pub async fn delete_post(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let post = sqlx::query!(
         "SELECT provider_id, business_id FROM posts WHERE id = $1", id
@@ -258,13 +367,28 @@ pub async fn delete_post(
         _ => false,
     };
 
-    if !owned {
+    // Admins can take down any post; that removal is audited
+    let moderated = !owned && is_admin(&pool, user_id).await?;
+    if !owned && !moderated {
         return Err(AppError::Forbidden("You do not have permission to delete this post".to_string()));
     }
 
+    let mut tx = pool.begin().await?;
     sqlx::query!("DELETE FROM posts WHERE id = $1", id)
-        .execute(&pool)
+        .execute(&mut *tx)
+        .await?;
+    if moderated {
+        record_audit(
+            &mut *tx,
+            Some(user_id),
+            "post_deleted",
+            "post",
+            Some(id),
+            json!({ "provider_id": post.provider_id, "business_id": post.business_id }),
+        )
         .await?;
+    }
+    tx.commit().await?;
 
     Ok((StatusCode::OK, Json(json!({ "message": "Post deleted successfully" }))))
 }
@@ -277,9 +401,9 @@ pub struct UpdatePost {
 }
 
 pub async fn update_post_and_attachments(
-    State(pool): State<PgPool>,
     Path(id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
+    mut tx: Tx,
     Json(payload): Json<UpdatePost>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if payload.attachments.len() > 5 {
@@ -289,17 +413,17 @@ pub async fn update_post_and_attachments(
     let post = sqlx::query!(
         "SELECT provider_id, business_id FROM posts WHERE id = $1", id
     )
-    .fetch_optional(&pool)
+    .fetch_optional(&mut *tx)
     .await?
     .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
 
     let owned = match (post.provider_id, post.business_id) {
         (Some(pid), _) => sqlx::query_scalar!(
             "SELECT id FROM providers WHERE id = $1 AND user_id = $2", pid, user_id
-        ).fetch_optional(&pool).await?.is_some(),
+        ).fetch_optional(&mut *tx).await?.is_some(),
         (_, Some(bid)) => sqlx::query_scalar!(
             "SELECT id FROM businesses WHERE id = $1 AND user_id = $2", bid, user_id
-        ).fetch_optional(&pool).await?.is_some(),
+        ).fetch_optional(&mut *tx).await?.is_some(),
         _ => false,
     };
 
@@ -310,8 +434,6 @@ pub async fn update_post_and_attachments(
     let target_type = if post.provider_id.is_some() { "provider" } else { "business" };
     let target_id = post.provider_id.or(post.business_id).unwrap_or(0);
 
-    let mut tx = pool.begin().await?;
-
     sqlx::query!(
         "UPDATE posts SET title = COALESCE($1, title), content = COALESCE($2, content) WHERE id = $3",
         payload.title,
@@ -325,13 +447,20 @@ pub async fn update_post_and_attachments(
         .execute(&mut *tx)
         .await?;
 
+    // Only the profile's own public uploads can be attached: a post is
+    // public, so a private file attached here would be served to anyone
     for path in &payload.attachments {
-        let file_name = path.split('/').last().unwrap_or("image");
-        sqlx::query!(
-            r#"INSERT INTO attachments (post_id, file_name, file_path, file_type, target_type, target_id, uploaded_by)
-               VALUES ($1, $2, $3, 'image', $4, $5, $6)"#,
+        let copied = sqlx::query!(
+            r#"INSERT INTO attachments (post_id, file_name, file_path, file_type, content_type, visibility,
+                                        target_type, target_id, uploaded_by, caption, alt_text)
+               SELECT $1, src.file_name, src.file_path, src.file_type, src.content_type, src.visibility,
+                      src.target_type, src.target_id, $5, src.caption, src.alt_text
+               FROM attachments src
+               WHERE src.file_path = $2 AND src.target_type = $3 AND src.target_id = $4
+                 AND src.post_id IS NULL AND src.visibility = 'public'
+               ORDER BY src.id
+               LIMIT 1"#,
             id,
-            file_name,
             path,
             target_type,
             target_id,
@@ -339,10 +468,14 @@ pub async fn update_post_and_attachments(
         )
         .execute(&mut *tx)
         .await?;
+        if copied.rows_affected() == 0 {
+            return Err(AppError::BadRequest(format!(
+                "{} is not a public file uploaded to this profile",
+                path
+            )));
+        }
     }
 
-    tx.commit().await?;
-
     Ok((StatusCode::OK, Json(json!({ "message": "Post and attachments updated successfully" }))))
 }
 
@@ -350,13 +483,10 @@ pub async fn update_post_and_attachments(
 
 pub async fn like_post(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(post_id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    sqlx::query_scalar!("SELECT id FROM posts WHERE id = $1", post_id)
-        .fetch_optional(&pool)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+    ensure_visible(&pool, post_id, Some(user_id)).await?;
 
     sqlx::query!(
         "INSERT INTO post_likes (user_id, post_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
@@ -377,7 +507,7 @@ pub async fn like_post(
 
 pub async fn unlike_post(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(post_id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     sqlx::query!(
@@ -415,8 +545,11 @@ pub struct CommentInput {
 
 pub async fn get_comments(
     State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
     Path(post_id): Path<i32>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    ensure_visible(&pool, post_id, viewer.user_id()).await?;
+
     let comments = sqlx::query_as::<_, CommentRow>(
         r#"SELECT pc.id, pc.user_id, u.username, pc.comment, pc.created_at
            FROM post_comments pc
@@ -443,7 +576,7 @@ pub async fn get_comments(
 
 pub async fn add_comment(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(post_id): Path<i32>,
     Json(payload): Json<CommentInput>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -451,10 +584,7 @@ pub async fn add_comment(
         return Err(AppError::BadRequest("Comment cannot be empty".to_string()));
     }
 
-    sqlx::query_scalar!("SELECT id FROM posts WHERE id = $1", post_id)
-        .fetch_optional(&pool)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+    ensure_visible(&pool, post_id, Some(user_id)).await?;
 
     let comment = sqlx::query!(
         "INSERT INTO post_comments (post_id, user_id, comment) VALUES ($1, $2, $3) RETURNING id",
@@ -468,7 +598,7 @@ pub async fn add_comment(
 
 pub async fn delete_comment(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path((post_id, comment_id)): Path<(i32, i32)>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let deleted = sqlx::query!(
@@ -484,3 +614,255 @@ pub async fn delete_comment(
 
     Ok((StatusCode::OK, Json(json!({ "message": "Comment deleted" }))))
 }
+
+// ── Views & analytics ─────────────────────────────────────────────────────────
+
+/// Dedup key for a view: the user id when signed in, otherwise an HMAC of
+/// the client IP and the day under `VIEWER_HASH_SECRET`. Raw addresses are
+/// never stored, the keys can't be reversed by hashing every IPv4 address,
+/// and one visitor's keys can't be linked from one day to the next.
+fn viewer_key(user_id: Option<i32>, ip: IpAddr, day: NaiveDate) -> String {
+    if let Some(user_id) = user_id {
+        return format!("u:{user_id}");
+    }
+    let secret = &config::get().viewer_hash_secret;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(format!("post-view|{day}|{ip}").as_bytes());
+    format!("ip:{}", &hex::encode(mac.finalize().into_bytes())[..32])
+}
+
+/// Counts at most one view per viewer per post per day.
+pub async fn record_post_view(
+    State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(post_id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    sqlx::query_scalar!("SELECT id FROM posts WHERE id = $1 AND NOT hidden", post_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    let ip = client_ip(&headers, addr.ip(), &config::get().trusted_proxies);
+    let today = Utc::now().date_naive();
+    let key = viewer_key(viewer.user_id(), ip, today);
+
+    let mut tx = pool.begin().await?;
+
+    let inserted = sqlx::query!(
+        "INSERT INTO post_view_events (post_id, viewed_on, viewer_key) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+        post_id,
+        today,
+        key
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let counted = inserted.rows_affected() > 0;
+    let view_count = if counted {
+        sqlx::query_scalar!(
+            "UPDATE posts SET view_count = view_count + 1 WHERE id = $1 RETURNING view_count",
+            post_id
+        )
+        .fetch_one(&mut *tx)
+        .await?
+    } else {
+        sqlx::query_scalar!("SELECT view_count FROM posts WHERE id = $1", post_id)
+            .fetch_one(&mut *tx)
+            .await?
+    };
+
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(json!({ "counted": counted, "view_count": view_count }))))
+}
+
+/// Daily views, likes and comments over the last 30 days. Author only.
+pub async fn get_post_analytics(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(post_id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let post = sqlx::query!(
+        r#"SELECT p.view_count, COALESCE(pr.user_id, b.user_id) AS owner_user_id
+           FROM posts p
+           LEFT JOIN providers  pr ON pr.id = p.provider_id
+           LEFT JOIN businesses b  ON b.id  = p.business_id
+           WHERE p.id = $1"#,
+        post_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if post.owner_user_id != Some(user_id) {
+        return Err(AppError::Forbidden("Only the author can view post analytics".to_string()));
+    }
+
+    // Views are bucketed by UTC day, so the series must be too, whatever the
+    // database session's time zone
+    let today = Utc::now().date_naive();
+    let daily = sqlx::query!(
+        r#"SELECT
+               d.day AS "day!",
+               (SELECT COUNT(*) FROM post_view_events v
+                 WHERE v.post_id = $1 AND v.viewed_on = d.day)        AS "views!",
+               (SELECT COUNT(*) FROM post_likes l
+                 WHERE l.post_id = $1 AND l.created_at::date = d.day) AS "likes!",
+               (SELECT COUNT(*) FROM post_comments c
+                 WHERE c.post_id = $1 AND c.created_at::date = d.day) AS "comments!"
+           FROM (
+               SELECT generate_series($2::date - 29, $2::date, INTERVAL '1 day')::date AS day
+           ) d
+           ORDER BY d.day"#,
+        post_id,
+        today
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let totals = sqlx::query!(
+        r#"SELECT
+               (SELECT COUNT(*) FROM post_likes    WHERE post_id = $1) AS "likes!",
+               (SELECT COUNT(*) FROM post_comments WHERE post_id = $1) AS "comments!""#,
+        post_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let series: Vec<serde_json::Value> = daily
+        .iter()
+        .map(|r| json!({ "day": r.day, "views": r.views, "likes": r.likes, "comments": r.comments }))
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "post_id": post_id,
+            "totals": {
+                "views": post.view_count,
+                "likes": totals.likes,
+                "comments": totals.comments,
+            },
+            "daily": series,
+        })),
+    ))
+}
+
+// ── Reports & appeals ─────────────────────────────────────────────────────────
+
+/// Reasons a user may pick when reporting a post.
+pub const POST_REPORT_REASONS: [&str; 5] = ["spam", "scam", "offensive", "misleading", "other"];
+
+#[derive(Deserialize, Debug)]
+pub struct ReportPostInput {
+    pub reason: String,
+    pub detail: Option<String>,
+}
+
+/// Any logged-in user may report a post once. Reports land in `content_flags`
+/// alongside review reports so admins work a single queue.
+pub async fn report_post(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(post_id): Path<i32>,
+    Json(payload): Json<ReportPostInput>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let reason = payload.reason.trim().to_lowercase();
+    if !POST_REPORT_REASONS.contains(&reason.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "Invalid reason. Must be one of: {}",
+            POST_REPORT_REASONS.join(", ")
+        )));
+    }
+    let detail = payload
+        .detail
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+    if detail.is_some_and(|d| d.len() > 1000) {
+        return Err(AppError::BadRequest("Detail must be at most 1000 characters".to_string()));
+    }
+
+    let post = sqlx::query!(
+        r#"SELECT COALESCE(pr.user_id, b.user_id) AS owner_user_id
+           FROM posts p
+           LEFT JOIN providers  pr ON pr.id = p.provider_id
+           LEFT JOIN businesses b  ON b.id  = p.business_id
+           WHERE p.id = $1"#,
+        post_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if post.owner_user_id == Some(user_id) {
+        return Err(AppError::BadRequest("You cannot report your own post".to_string()));
+    }
+
+    let inserted = sqlx::query!(
+        r#"INSERT INTO content_flags (target_type, target_id, reason, detail, flagged_by, target_owner_id)
+           VALUES ('post', $1, $2, $3, $4, $5)
+           ON CONFLICT (target_type, target_id, flagged_by) WHERE flagged_by IS NOT NULL DO NOTHING"#,
+        post_id,
+        reason,
+        detail,
+        user_id,
+        post.owner_user_id
+    )
+    .execute(&pool)
+    .await?;
+
+    if inserted.rows_affected() == 0 {
+        return Err(AppError::Conflict("You have already reported this post".to_string()));
+    }
+
+    Ok((StatusCode::CREATED, Json(json!({ "message": "Post reported — our team will review it" }))))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AppealInput {
+    pub note: String,
+}
+
+/// The author of a hidden post can ask moderators to take another look.
+pub async fn appeal_post(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(post_id): Path<i32>,
+    Json(payload): Json<AppealInput>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let note = payload.note.trim();
+    if note.is_empty() {
+        return Err(AppError::BadRequest("Appeal note cannot be empty".to_string()));
+    }
+    if note.len() > 1000 {
+        return Err(AppError::BadRequest("Appeal note must be at most 1000 characters".to_string()));
+    }
+
+    let post = sqlx::query!(
+        r#"SELECT p.hidden, COALESCE(pr.user_id, b.user_id) AS owner_user_id
+           FROM posts p
+           LEFT JOIN providers  pr ON pr.id = p.provider_id
+           LEFT JOIN businesses b  ON b.id  = p.business_id
+           WHERE p.id = $1"#,
+        post_id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if post.owner_user_id != Some(user_id) {
+        return Err(AppError::Forbidden("You can only appeal your own posts".to_string()));
+    }
+    if !post.hidden {
+        return Err(AppError::BadRequest("Only hidden posts can be appealed".to_string()));
+    }
+
+    sqlx::query!("UPDATE posts SET appeal_note = $1 WHERE id = $2", note, post_id)
+        .execute(&pool)
+        .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Appeal submitted — our team will review it" }))))
+}
@@ -1,6 +1,9 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::utils::interactions::{self, Interaction};
 use crate::utils::notifications::{notify_and_push, notify_target_owner_and_push};
+use crate::utils::pagination::{Paginated, Pagination, Sort};
+use crate::utils::webhooks;
 use crate::utils::ws_state::WsConnections;
 use axum::{
     Extension, Json, Router,
@@ -20,6 +23,7 @@ pub fn reviews_routes(pool: PgPool) -> Router {
         .route("/rankProviders", get(rank_providers))
         .route("/rankBusinesses", get(rank_businesses))
         .route("/getReviewAggById", get(get_review_agg_by_id))
+        .route("/:id/reply", post(reply_review))
         .route("/:id/replyReview", post(reply_review))
         .route("/:id/flag", post(flag_review))
         .with_state(pool)
@@ -37,22 +41,49 @@ pub struct ReviewQuery {
     target_id: i32,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ReviewListQuery {
+    target_type: String,
+    target_id: i32,
+    sort: Option<String>,
+}
+
+/// `?sort=` fields for `get_reviews`.
+const REVIEW_SORTS: &[(&str, &str)] = &[("created_at", "r.created_at"), ("rating", "r.rating")];
+
+/// Shorthands `get_reviews` also takes for `sort`, with their direction.
+fn review_sort_alias(sort: &str) -> Option<&'static str> {
+    match sort {
+        "newest" => Some("-created_at"),
+        "highest" => Some("-rating"),
+        "lowest" => Some("rating"),
+        _ => None,
+    }
+}
+
 #[derive(Serialize, sqlx::FromRow, Debug)]
 pub struct ReviewResponse {
     id: i32,
     reviewer_id: i32,
+    /// Null once the reviewer's account is deleted.
+    reviewer_username: Option<String>,
+    /// The reviewer's client profile picture and its thumbnail, if they set one.
+    reviewer_profile_picture: Option<String>,
+    reviewer_profile_picture_thumb_url: Option<String>,
     rating: i32,
     comment: String,
     created_at: NaiveDateTime,
     /// True when the review is backed by a completed booking.
     verified: bool,
+    /// The owner's public reply, `{ comment, created_at }`, if any.
+    reply: Option<serde_json::Value>,
 }
 
 pub async fn create_reviews(
     State(pool): State<PgPool>,
     Extension(ws_conns): Extension<WsConnections>,
     Query(params): Query<ReviewQuery>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<Review>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if payload.comment.trim().is_empty() {
@@ -100,6 +131,13 @@ pub async fn create_reviews(
     }
 
     // Only clients with a completed booking may leave a review
+    if !interactions::has_interacted(&pool, user_id, &target_type, target_id, &[Interaction::BookingCompleted]).await? {
+        return Err(AppError::Forbidden(
+            "You can only review a provider or business after completing a booking with them".to_string(),
+        ));
+    }
+
+    // Linked when the booking is still there, for the verified badge
     let verified_booking_id = sqlx::query_scalar!(
         r#"SELECT id FROM bookings
            WHERE client_id = $1 AND target_type = $2 AND target_id = $3
@@ -113,12 +151,7 @@ pub async fn create_reviews(
     .fetch_optional(&pool)
     .await?;
 
-    if verified_booking_id.is_none() {
-        return Err(AppError::Forbidden(
-            "You can only review a provider or business after completing a booking with them".to_string(),
-        ));
-    }
-
+    let mut tx = pool.begin().await?;
     let review = sqlx::query!(
         r#"INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment, verified_booking_id)
            VALUES ($1, $2, $3, $4, $5, $6) RETURNING id"#,
@@ -129,8 +162,10 @@ pub async fn create_reviews(
         payload.comment.trim(),
         verified_booking_id
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
     .await?;
+    webhooks::enqueue_review_created(&mut *tx, review.id).await?;
+    tx.commit().await?;
 
     notify_target_owner_and_push(
         &pool, &ws_conns, &target_type, target_id,
@@ -149,10 +184,12 @@ pub async fn create_reviews(
     ))
 }
 
+/// Verified reviews always come first; `sort` orders within each group.
 pub async fn get_reviews(
     State(pool): State<PgPool>,
-    Query(params): Query<ReviewQuery>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    Query(params): Query<ReviewListQuery>,
+    pagination: Pagination,
+) -> AppResult<(StatusCode, Json<Paginated<ReviewResponse>>)> {
     let target_type = params.target_type.to_lowercase();
     if target_type != "provider" && target_type != "business" {
         return Err(AppError::BadRequest("Invalid target type. Must be 'provider' or 'business'".to_string()));
@@ -160,20 +197,43 @@ pub async fn get_reviews(
     if params.target_id <= 0 {
         return Err(AppError::BadRequest("Invalid target ID. Must be greater than 0".to_string()));
     }
-
-    let reviews = sqlx::query_as::<sqlx::Postgres, ReviewResponse>(
-        r#"SELECT id, reviewer_id, rating, comment, created_at,
-                  (verified_booking_id IS NOT NULL) AS verified
-           FROM reviews
-           WHERE target_type = $1 AND target_id = $2
-           ORDER BY verified DESC, created_at DESC"#,
-    )
-    .bind(target_type)
-    .bind(params.target_id)
-    .fetch_all(&pool)
-    .await?;
-
-    Ok((StatusCode::OK, Json(json!({ "reviews": reviews }))))
+    let sort = params.sort.as_deref().map(|raw| review_sort_alias(raw.trim()).unwrap_or(raw));
+    let sort = Sort::parse(sort, REVIEW_SORTS, "-created_at")?;
+
+    let list_sql = format!(
+        r#"SELECT r.id, r.reviewer_id, u.username AS reviewer_username,
+                  c.profile_picture AS reviewer_profile_picture,
+                  image_variant(c.profile_picture, 'thumb') AS reviewer_profile_picture_thumb_url,
+                  r.rating, r.comment, r.created_at,
+                  (r.verified_booking_id IS NOT NULL) AS verified,
+                  CASE WHEN rr.id IS NOT NULL
+                       THEN jsonb_build_object('comment', rr.comment, 'created_at', rr.created_at)
+                  END AS reply
+           FROM reviews r
+           LEFT JOIN users u ON u.id = r.reviewer_id AND u.deleted_at IS NULL
+           LEFT JOIN clients c ON c.user_id = u.id
+           LEFT JOIN review_replies rr ON rr.review_id = r.id
+           WHERE r.target_type = $1 AND r.target_id = $2 AND r.hidden = FALSE
+           ORDER BY verified DESC, {}, r.id DESC
+           LIMIT $3 OFFSET $4"#,
+        sort.order_by()
+    );
+    let (reviews, total) = tokio::try_join!(
+        sqlx::query_as::<sqlx::Postgres, ReviewResponse>(&list_sql)
+            .bind(&target_type)
+            .bind(params.target_id)
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .fetch_all(&pool),
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM reviews WHERE target_type = $1 AND target_id = $2 AND hidden = FALSE",
+        )
+        .bind(&target_type)
+        .bind(params.target_id)
+        .fetch_one(&pool),
+    )?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(reviews, pagination, total))))
 }
 
 #[derive(Deserialize, Serialize, Debug, sqlx::FromRow)]
@@ -188,7 +248,7 @@ pub async fn rank_providers(
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let results = sqlx::query_as::<sqlx::Postgres, AggregatedRating>(
         "SELECT target_id, ROUND(AVG(rating)::numeric,2)::float8 as average_rating, COUNT(*) as review_count
-         FROM reviews WHERE target_type = 'provider'
+         FROM reviews WHERE target_type = 'provider' AND hidden = FALSE
          GROUP BY target_id ORDER BY average_rating DESC, review_count DESC",
     )
     .fetch_all(&pool)
@@ -202,7 +262,7 @@ pub async fn rank_businesses(
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let results = sqlx::query_as::<sqlx::Postgres, AggregatedRating>(
         "SELECT target_id, AVG(rating) as average_rating, COUNT(*) as review_count
-         FROM reviews WHERE target_type = 'business'
+         FROM reviews WHERE target_type = 'business' AND hidden = FALSE
          GROUP BY target_id ORDER BY average_rating DESC, review_count DESC",
     )
     .fetch_all(&pool)
@@ -223,17 +283,27 @@ pub async fn get_review_agg_by_id(
         return Err(AppError::BadRequest("Invalid target ID. Must be greater than 0".to_string()));
     }
 
-    let result = sqlx::query_as::<sqlx::Postgres, AggregatedRating>(
+    let result = aggregated_rating(&pool, &target_type, params.target_id).await?;
+
+    Ok((StatusCode::OK, Json(json!({ "aggregated_rating": result }))))
+}
+
+/// Average of the visible reviews on one provider or business, or `None`
+/// when it has none.
+pub async fn aggregated_rating(
+    pool: &PgPool,
+    target_type: &str,
+    target_id: i32,
+) -> Result<Option<AggregatedRating>, sqlx::Error> {
+    sqlx::query_as::<sqlx::Postgres, AggregatedRating>(
         "SELECT target_id, ROUND(AVG(rating)::numeric,2)::float8 as average_rating, COUNT(*) as review_count
-         FROM reviews WHERE target_type = $1 AND target_id = $2
+         FROM reviews WHERE target_type = $1 AND target_id = $2 AND hidden = FALSE
          GROUP BY target_id",
     )
     .bind(target_type)
-    .bind(params.target_id)
-    .fetch_optional(&pool)
-    .await?;
-
-    Ok((StatusCode::OK, Json(json!({ "aggregated_rating": result }))))
+    .bind(target_id)
+    .fetch_optional(pool)
+    .await
 }
 
 // ── Review replies ────────────────────────────────────────────────────────────
@@ -257,7 +327,7 @@ pub async fn reply_review(
     State(pool): State<PgPool>,
     Extension(ws_conns): Extension<WsConnections>,
     Path(review_id): Path<i32>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<ReplyPayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     if payload.comment.trim().is_empty() {
@@ -291,30 +361,20 @@ pub async fn reply_review(
         ));
     }
 
-    // Prevent duplicate replies from the same user
-    let existing = sqlx::query_scalar!(
-        "SELECT id FROM review_replies WHERE review_id = $1 AND reviewer_id = $2",
-        review_id,
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    if existing.is_some() {
-        return Err(AppError::Conflict("You have already replied to this review".to_string()));
-    }
-
+    // One reply per review, enforced by a unique index on review_id
     let reply = sqlx::query_as!(
         ReviewReply,
         "INSERT INTO review_replies (review_id, reviewer_id, comment)
          VALUES ($1, $2, $3)
+         ON CONFLICT (review_id) DO NOTHING
          RETURNING id, review_id, reviewer_id, comment, created_at",
         review_id,
         user_id,
         payload.comment.trim()
     )
-    .fetch_one(&pool)
-    .await?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::Conflict("This review already has a reply".to_string()))?;
 
     // Notify the original reviewer that their review received a reply
     let reviewer_id = sqlx::query_scalar!(
@@ -349,7 +409,7 @@ pub struct FlagPayload {
 
 pub async fn flag_review(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path(review_id): Path<i32>,
     Json(payload): Json<FlagPayload>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -359,12 +419,10 @@ pub async fn flag_review(
     }
 
     // Verify review exists
-    let exists = sqlx::query_scalar!("SELECT id FROM reviews WHERE id = $1", review_id)
+    let reviewer_id = sqlx::query_scalar!("SELECT reviewer_id FROM reviews WHERE id = $1", review_id)
         .fetch_optional(&pool)
-        .await?;
-    if exists.is_none() {
-        return Err(AppError::NotFound("Review not found".to_string()));
-    }
+        .await?
+        .ok_or_else(|| AppError::NotFound("Review not found".to_string()))?;
 
     // Prevent the same user flagging the same review twice
     let already = sqlx::query_scalar!(
@@ -378,8 +436,9 @@ pub async fn flag_review(
     }
 
     sqlx::query!(
-        "INSERT INTO content_flags (target_type, target_id, reason, flagged_by) VALUES ('review', $1, $2, $3)",
-        review_id, reason, user_id
+        r#"INSERT INTO content_flags (target_type, target_id, reason, flagged_by, target_owner_id)
+           VALUES ('review', $1, $2, $3, $4)"#,
+        review_id, reason, user_id, reviewer_id
     )
     .execute(&pool)
     .await?;
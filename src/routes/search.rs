@@ -1,12 +1,15 @@
 use crate::errors::{AppError, AppResult};
+use crate::utils::pagination::{Paginated, Pagination};
+use crate::utils::settings;
 use axum::{
     Json, Router,
     extract::{Query, State},
     http::StatusCode,
     routing::get,
 };
+use bigdecimal::BigDecimal;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use sqlx::PgPool;
 
 pub fn search_routes(pool: PgPool) -> Router {
@@ -15,15 +18,26 @@ pub fn search_routes(pool: PgPool) -> Router {
         .with_state(pool)
 }
 
+/// How much a perfect 5-star average lifts a hit's score: up to 30%.
+const RATING_BOOST: f64 = 0.3;
+/// How much a fully completed profile lifts a hit's score: up to 20%.
+const COMPLETENESS_BOOST: f64 = 0.2;
+/// Logged queries are cut to this many characters.
+const MAX_LOGGED_QUERY_CHARS: usize = 200;
+
 // ── Query params ──────────────────────────────────────────────────────────────
 
 #[derive(Deserialize, Debug)]
 pub struct SearchQuery {
-    /// Free-text query — matched against name, description, category fields.
+    /// Free-text query (web-search syntax: quotes, `or`, `-word`) matched
+    /// against names, categories and descriptions.
     pub q: Option<String>,
-    /// Filter by entity type: "provider", "business". Omit for both.
+    /// Only return one kind of result: "provider", "business" or "service".
+    /// Per-type counts still cover all three.
     #[serde(rename = "type")]
     pub search_type: Option<String>,
+    /// Only listings with a location or branch in this county.
+    pub county_id: Option<i32>,
     /// Filter by category (partial, case-insensitive).
     pub category: Option<String>,
     /// Filter by location (partial, case-insensitive), e.g. "Kasarani, Nairobi".
@@ -34,57 +48,245 @@ pub struct SearchQuery {
     pub lng: Option<f64>,
     /// Search radius in km (default 10.0). Only used when lat+lng are provided.
     pub radius_km: Option<f64>,
-    pub page: Option<i64>,
-    pub per_page: Option<i64>,
+    pub page: Option<String>,
+    pub per_page: Option<String>,
+    /// Alias for `per_page`.
+    pub limit: Option<String>,
 }
 
 // ── Result types ──────────────────────────────────────────────────────────────
 
+/// One provider, business or service matching the search. Services carry
+/// their owner's category, location, photo and rating.
 #[derive(Serialize, sqlx::FromRow, Debug)]
-pub struct ProviderResult {
+pub struct SearchHit {
+    #[serde(rename = "type")]
+    pub result_type: String,
     pub id: i32,
-    pub user_id: i32,
-    pub service_name: Option<String>,
-    pub service_description: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
     pub category: Option<String>,
     pub location: Option<String>,
-    pub profile_photo: Option<String>,
-    pub phone_number: Option<String>,
+    pub photo_url: Option<String>,
+    pub photo_thumb_url: Option<String>,
+    /// For services: "provider" or "business", with the owner's id and name.
+    pub owner_type: Option<String>,
+    pub owner_id: Option<i32>,
+    pub owner_name: Option<String>,
+    pub price: Option<BigDecimal>,
     pub average_rating: f64,
     pub review_count: i64,
     pub distance_km: Option<f64>,
+    /// Text relevance, lifted by rating and profile completeness. Higher first.
+    pub score: f64,
 }
 
-#[derive(Serialize, sqlx::FromRow, Debug)]
-pub struct BusinessResult {
-    pub id: i32,
-    pub user_id: i32,
-    pub business_name: String,
-    pub description: Option<String>,
-    pub category: Option<String>,
-    pub location: Option<String>,
-    pub profile_photo: Option<String>,
-    pub logo: Option<String>,
-    pub phone_number: Option<String>,
-    pub average_rating: f64,
-    pub review_count: i64,
-    pub distance_km: Option<f64>,
+#[derive(Serialize, Debug, Default)]
+pub struct TypeCounts {
+    pub provider: i64,
+    pub business: i64,
+    pub service: i64,
 }
 
+#[derive(Serialize, Debug)]
+pub struct SearchResults {
+    #[serde(flatten)]
+    pub results: Paginated<SearchHit>,
+    /// Matches of each type, ignoring `type`.
+    pub counts: TypeCounts,
+}
+
+// ── SQL ───────────────────────────────────────────────────────────────────────
+
+/// Every listed match, before the `type` filter. Shared by the page and
+/// count queries.
+///
+/// Params: $1=q, $2=county_id, $3=category, $4=location, $5=lat, $6=lng, $7=radius_km
+///
+/// NULL-safe filter pattern: ($N IS NULL OR <condition using $N>). Without
+/// lat/lng, `haversine_km` is NULL and so is `distance_km`.
+const HITS_SQL: &str = r#"
+    WITH hits AS (
+        SELECT
+            'provider'::text                AS result_type,
+            p.id,
+            p.service_name                  AS name,
+            p.service_description           AS description,
+            p.category,
+            p.location,
+            p.profile_photo                 AS photo_url,
+            NULL::text                      AS owner_type,
+            NULL::int                       AS owner_id,
+            NULL::text                      AS owner_name,
+            NULL::numeric                   AS price,
+            rt.average_rating,
+            rt.review_count,
+            pl.distance_km,
+            pl.in_county,
+            CASE WHEN $1::text IS NULL THEN 1.0::float8
+                 ELSE ts_rank(p.search_vector, websearch_to_tsquery('english', $1::text))::float8
+            END                             AS relevance,
+            p.onboarding_score::float8      AS completeness
+        FROM providers p
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(AVG(r.rating)::float8, 0.0::float8) AS average_rating, COUNT(*) AS review_count
+            FROM reviews r
+            WHERE r.target_type = 'provider' AND r.target_id = p.id AND r.hidden = FALSE
+        ) rt
+        CROSS JOIN LATERAL (
+            SELECT MIN(haversine_km($5::float8, $6::float8, lp.latitude, lp.longitude)) AS distance_km,
+                   COALESCE(bool_or(lp.county_id = $2::int), FALSE)                     AS in_county
+            FROM listing_places('provider', p.id) lp
+        ) pl
+        WHERE p.approved = true
+          AND NOT user_is_hidden(p.user_id)
+          AND p.onboarding_completed = true
+          AND p.listing_status = 'approved'
+          AND ($1::text IS NULL OR p.search_vector @@ websearch_to_tsquery('english', $1::text))
+          AND ($3::text IS NULL OR p.category ILIKE $3::text)
+          AND ($4::text IS NULL OR p.location ILIKE $4::text)
+
+        UNION ALL
+
+        SELECT
+            'business'::text,
+            b.id,
+            b.business_name,
+            b.description,
+            b.category,
+            b.location,
+            COALESCE(b.logo, b.profile_photo),
+            NULL::text,
+            NULL::int,
+            NULL::text,
+            NULL::numeric,
+            rt.average_rating,
+            rt.review_count,
+            bl.distance_km,
+            bl.in_county,
+            CASE WHEN $1::text IS NULL THEN 1.0::float8
+                 ELSE ts_rank(b.search_vector, websearch_to_tsquery('english', $1::text))::float8
+            END,
+            -- Businesses have no onboarding score; verified ones count as complete
+            100.0::float8
+        FROM businesses b
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(AVG(r.rating)::float8, 0.0::float8) AS average_rating, COUNT(*) AS review_count
+            FROM reviews r
+            WHERE r.target_type = 'business' AND r.target_id = b.id AND r.hidden = FALSE
+        ) rt
+        CROSS JOIN LATERAL (
+            SELECT MIN(haversine_km($5::float8, $6::float8, lp.latitude, lp.longitude)) AS distance_km,
+                   COALESCE(bool_or(lp.county_id = $2::int), FALSE)                     AS in_county
+            FROM listing_places('business', b.id) lp
+        ) bl
+        WHERE b.verified = true
+          AND NOT user_is_hidden(b.user_id)
+          AND b.onboarding_completed = true
+          AND b.listing_status = 'approved'
+          AND ($1::text IS NULL OR b.search_vector @@ websearch_to_tsquery('english', $1::text))
+          AND ($3::text IS NULL OR b.category ILIKE $3::text)
+          AND ($4::text IS NULL OR b.location ILIKE $4::text)
+
+        UNION ALL
+
+        SELECT
+            'service'::text,
+            s.id,
+            s.title,
+            s.description,
+            o.category,
+            o.location,
+            o.photo_url,
+            s.target_type::text,
+            s.target_id,
+            o.name,
+            s.price,
+            rt.average_rating,
+            rt.review_count,
+            sl.distance_km,
+            sl.in_county,
+            CASE WHEN $1::text IS NULL THEN 1.0::float8
+                 ELSE ts_rank(s.search_vector, websearch_to_tsquery('english', $1::text))::float8
+            END,
+            o.completeness
+        FROM services s
+        -- Only services whose owner is itself listed
+        CROSS JOIN LATERAL (
+            SELECT p.service_name AS name, p.category, p.location, p.profile_photo AS photo_url,
+                   p.onboarding_score::float8 AS completeness
+            FROM providers p
+            WHERE s.target_type = 'provider' AND p.id = s.target_id
+              AND p.approved = true
+              AND NOT user_is_hidden(p.user_id)
+              AND p.onboarding_completed = true
+              AND p.listing_status = 'approved'
+            UNION ALL
+            SELECT b.business_name, b.category, b.location, COALESCE(b.logo, b.profile_photo), 100.0::float8
+            FROM businesses b
+            WHERE s.target_type = 'business' AND b.id = s.target_id
+              AND b.verified = true
+              AND NOT user_is_hidden(b.user_id)
+              AND b.onboarding_completed = true
+              AND b.listing_status = 'approved'
+        ) o
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(AVG(r.rating)::float8, 0.0::float8) AS average_rating, COUNT(*) AS review_count
+            FROM reviews r
+            WHERE r.target_type = s.target_type AND r.target_id = s.target_id AND r.hidden = FALSE
+        ) rt
+        CROSS JOIN LATERAL (
+            SELECT MIN(haversine_km($5::float8, $6::float8, lp.latitude, lp.longitude)) AS distance_km,
+                   COALESCE(bool_or(lp.county_id = $2::int), FALSE)                     AS in_county
+            FROM listing_places(s.target_type, s.target_id) lp
+        ) sl
+        WHERE s.is_active = true
+          AND ($1::text IS NULL OR s.search_vector @@ websearch_to_tsquery('english', $1::text))
+          AND ($3::text IS NULL OR o.category ILIKE $3::text)
+          AND ($4::text IS NULL OR o.location ILIKE $4::text)
+    ),
+    matches AS (
+        SELECT * FROM hits
+        WHERE ($2::int IS NULL OR in_county)
+          AND ($5::float8 IS NULL OR $6::float8 IS NULL OR distance_km <= $7::float8)
+    )
+"#;
+
+/// Params after the shared ones: $8=type, $9=rating boost,
+/// $10=completeness boost, $11=limit, $12=offset
+const PAGE_SQL: &str = r#"
+    SELECT
+        result_type, id, name, description, category, location,
+        photo_url, image_variant(photo_url, 'thumb') AS photo_thumb_url,
+        owner_type, owner_id, owner_name, price,
+        average_rating, review_count, distance_km,
+        relevance * (1.0 + $9::float8 * average_rating / 5.0 + $10::float8 * completeness / 100.0) AS score
+    FROM matches
+    WHERE ($8::text IS NULL OR result_type = $8::text)
+    ORDER BY score DESC, distance_km ASC NULLS LAST, result_type, id
+    LIMIT $11 OFFSET $12
+"#;
+
+const COUNTS_SQL: &str = "SELECT result_type, COUNT(*) FROM matches GROUP BY result_type";
+
 // ── Handler ───────────────────────────────────────────────────────────────────
 
 pub async fn search(
     State(pool): State<PgPool>,
     Query(params): Query<SearchQuery>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+) -> AppResult<(StatusCode, Json<SearchResults>)> {
     match params.search_type.as_deref() {
-        None | Some("provider") | Some("business") => {}
+        None | Some("provider") | Some("business") | Some("service") => {}
         _ => {
             return Err(AppError::BadRequest(
-                "type must be 'provider' or 'business'".to_string(),
+                "type must be 'provider', 'business' or 'service'".to_string(),
             ))
         }
     }
+    let pagination = Pagination::from_raw(
+        params.page.as_deref(),
+        params.per_page.as_deref().or(params.limit.as_deref()),
+    )?;
 
     let q = params
         .q
@@ -107,237 +309,90 @@ pub async fn search(
         .filter(|s| !s.trim().is_empty())
         .map(|s| format!("%{}%", s.trim()));
 
-    let page = params.page.unwrap_or(1).max(1);
-    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
-    let offset = (page - 1) * per_page;
     let radius_km = params.radius_km.unwrap_or(10.0);
-    let lat = params.lat;
-    let lng = params.lng;
 
-    let include_providers = !matches!(params.search_type.as_deref(), Some("business"));
-    let include_businesses = !matches!(params.search_type.as_deref(), Some("provider"));
+    let page_sql = format!("{HITS_SQL}{PAGE_SQL}");
+    let counts_sql = format!("{HITS_SQL}{COUNTS_SQL}");
+
+    let page_query = sqlx::query_as::<_, SearchHit>(&page_sql)
+        .bind(q.as_deref())
+        .bind(params.county_id)
+        .bind(category.as_deref())
+        .bind(location.as_deref())
+        .bind(params.lat)
+        .bind(params.lng)
+        .bind(radius_km)
+        .bind(params.search_type.as_deref())
+        .bind(RATING_BOOST)
+        .bind(COMPLETENESS_BOOST)
+        .bind(pagination.limit())
+        .bind(pagination.offset());
+    let counts_query = sqlx::query_as::<_, (String, i64)>(&counts_sql)
+        .bind(q.as_deref())
+        .bind(params.county_id)
+        .bind(category.as_deref())
+        .bind(location.as_deref())
+        .bind(params.lat)
+        .bind(params.lng)
+        .bind(radius_km);
 
-    let (providers, businesses) = tokio::try_join!(
-        async {
-            if include_providers {
-                search_providers(&pool, q.as_deref(), category.as_deref(), location.as_deref(), lat, lng, radius_km, per_page, offset).await
-            } else {
-                Ok(vec![])
-            }
-        },
-        async {
-            if include_businesses {
-                search_businesses(&pool, q.as_deref(), category.as_deref(), location.as_deref(), lat, lng, radius_km, per_page, offset).await
-            } else {
-                Ok(vec![])
-            }
+    let (hits, count_rows) = tokio::try_join!(page_query.fetch_all(&pool), counts_query.fetch_all(&pool))?;
+
+    let mut counts = TypeCounts::default();
+    for (result_type, count) in count_rows {
+        match result_type.as_str() {
+            "provider" => counts.provider = count,
+            "business" => counts.business = count,
+            _ => counts.service = count,
         }
-    )?;
+    }
+    let total = match params.search_type.as_deref() {
+        Some("provider") => counts.provider,
+        Some("business") => counts.business,
+        Some("service") => counts.service,
+        _ => counts.provider + counts.business + counts.service,
+    };
+
+    if let Some(q) = q {
+        log_query(&pool, q, params.search_type, params.county_id, total).await;
+    }
 
     Ok((
         StatusCode::OK,
-        Json(json!({
-            "providers": providers,
-            "businesses": businesses,
-            "total": providers.len() + businesses.len(),
-            "page": page,
-            "per_page": per_page,
-        })),
+        Json(SearchResults {
+            results: Paginated::new(hits, pagination, total),
+            counts,
+        }),
     ))
 }
 
-// ── Provider search ───────────────────────────────────────────────────────────
-
-async fn search_providers(
-    pool: &PgPool,
-    q: Option<&str>,
-    category: Option<&str>,
-    location: Option<&str>,
-    lat: Option<f64>,
-    lng: Option<f64>,
-    radius_km: f64,
-    limit: i64,
-    offset: i64,
-) -> Result<Vec<ProviderResult>, AppError> {
-    // Params: $1=q, $2=category, $3=lat, $4=lng, $5=radius_km, $6=limit, $7=offset, $8=location
-    //
-    // NULL-safe filter pattern: ($N IS NULL OR <condition using $N>)
-    // When the param is NULL the guard short-circuits, skipping that filter.
-    //
-    // Haversine distance formula used for geo-proximity; LEAST(1,sqrt(...))
-    // guards against floating-point values just above 1.0 that would make asin error.
-    let sql = r#"
-        SELECT
-            p.id,
-            p.user_id,
-            p.service_name,
-            p.service_description,
-            p.category,
-            p.location,
-            p.profile_photo,
-            p.phone_number,
-            COALESCE(AVG(r.rating)::float8, 0.0::float8) AS average_rating,
-            COUNT(DISTINCT r.id)                          AS review_count,
-            CASE
-                WHEN $3::float8 IS NOT NULL AND $4::float8 IS NOT NULL THEN
-                    MIN(6371.0 * 2.0 * asin(LEAST(1.0, sqrt(
-                        power(sin(radians(pl.latitude  - $3::float8) / 2.0), 2) +
-                        cos(radians($3::float8)) * cos(radians(pl.latitude)) *
-                        power(sin(radians(pl.longitude - $4::float8) / 2.0), 2)
-                    ))))
-                ELSE NULL
-            END AS distance_km
-        FROM providers p
-        LEFT JOIN reviews r
-            ON r.target_type = 'provider' AND r.target_id = p.id
-        LEFT JOIN provider_locations pl
-            ON pl.provider_id = p.id
-        WHERE p.approved = true
-          AND p.onboarding_completed = true
-          AND (
-              $1::text IS NULL
-              OR to_tsvector('english',
-                     coalesce(p.service_name, '') || ' ' ||
-                     coalesce(p.service_description, '') || ' ' ||
-                     coalesce(p.category, '') || ' ' ||
-                     coalesce(p.location, '')
-                 ) @@ plainto_tsquery('english', $1::text)
-          )
-          AND ($2::text IS NULL OR p.category ILIKE $2::text)
-          AND ($8::text IS NULL OR p.location ILIKE $8::text)
-        GROUP BY p.id
-        HAVING (
-            $3::float8 IS NULL
-            OR $4::float8 IS NULL
-            OR MIN(6371.0 * 2.0 * asin(LEAST(1.0, sqrt(
-                power(sin(radians(pl.latitude  - $3::float8) / 2.0), 2) +
-                cos(radians($3::float8)) * cos(radians(pl.latitude)) *
-                power(sin(radians(pl.longitude - $4::float8) / 2.0), 2)
-            )))) <= $5::float8
-        )
-        ORDER BY
-            CASE
-                WHEN $1::text IS NOT NULL THEN
-                    ts_rank(
-                        to_tsvector('english',
-                            coalesce(p.service_name, '') || ' ' ||
-                            coalesce(p.service_description, '') || ' ' ||
-                            coalesce(p.category, '') || ' ' ||
-                            coalesce(p.location, '')
-                        ),
-                        plainto_tsquery('english', $1::text)
-                    )
-                ELSE 0.0
-            END DESC,
-            average_rating DESC
-        LIMIT $6 OFFSET $7
-    "#;
-
-    sqlx::query_as::<_, ProviderResult>(sql)
-        .bind(q)
-        .bind(category)
-        .bind(lat)
-        .bind(lng)
-        .bind(radius_km)
-        .bind(limit)
-        .bind(offset)
-        .bind(location)
-        .fetch_all(pool)
-        .await
-        .map_err(AppError::Database)
-}
+// ── Query log ─────────────────────────────────────────────────────────────────
 
-// ── Business search ───────────────────────────────────────────────────────────
+/// Records a sample of searches (`search.log_sample_percent`) in
+/// `search_queries`. The insert runs after the response is on its way; a
+/// failure is only logged.
+async fn log_query(pool: &PgPool, q: String, search_type: Option<String>, county_id: Option<i32>, result_count: i64) {
+    let percent = settings::int(pool, "search.log_sample_percent").await;
+    if rand::thread_rng().gen_range(0..100) >= percent {
+        return;
+    }
 
-async fn search_businesses(
-    pool: &PgPool,
-    q: Option<&str>,
-    category: Option<&str>,
-    location: Option<&str>,
-    lat: Option<f64>,
-    lng: Option<f64>,
-    radius_km: f64,
-    limit: i64,
-    offset: i64,
-) -> Result<Vec<BusinessResult>, AppError> {
-    let sql = r#"
-        SELECT
-            b.id,
-            b.user_id,
-            b.business_name,
-            b.description,
-            b.category,
-            b.location,
-            b.profile_photo,
-            b.logo,
-            b.phone_number,
-            COALESCE(AVG(r.rating)::float8, 0.0::float8) AS average_rating,
-            COUNT(DISTINCT r.id)                          AS review_count,
-            CASE
-                WHEN $3::float8 IS NOT NULL AND $4::float8 IS NOT NULL THEN
-                    MIN(6371.0 * 2.0 * asin(LEAST(1.0, sqrt(
-                        power(sin(radians(bb.latitude  - $3::float8) / 2.0), 2) +
-                        cos(radians($3::float8)) * cos(radians(bb.latitude)) *
-                        power(sin(radians(bb.longitude - $4::float8) / 2.0), 2)
-                    ))))
-                ELSE NULL
-            END AS distance_km
-        FROM businesses b
-        LEFT JOIN reviews r
-            ON r.target_type = 'business' AND r.target_id = b.id
-        LEFT JOIN business_branches bb
-            ON bb.business_id = b.id
-        WHERE b.verified = true
-          AND b.onboarding_completed = true
-          AND (
-              $1::text IS NULL
-              OR to_tsvector('english',
-                     coalesce(b.business_name, '') || ' ' ||
-                     coalesce(b.description, '') || ' ' ||
-                     coalesce(b.category, '') || ' ' ||
-                     coalesce(b.location, '')
-                 ) @@ plainto_tsquery('english', $1::text)
-          )
-          AND ($2::text IS NULL OR b.category ILIKE $2::text)
-          AND ($8::text IS NULL OR b.location ILIKE $8::text)
-        GROUP BY b.id
-        HAVING (
-            $3::float8 IS NULL
-            OR $4::float8 IS NULL
-            OR MIN(6371.0 * 2.0 * asin(LEAST(1.0, sqrt(
-                power(sin(radians(bb.latitude  - $3::float8) / 2.0), 2) +
-                cos(radians($3::float8)) * cos(radians(bb.latitude)) *
-                power(sin(radians(bb.longitude - $4::float8) / 2.0), 2)
-            )))) <= $5::float8
+    let query: String = q.chars().take(MAX_LOGGED_QUERY_CHARS).collect();
+    let result_count = i32::try_from(result_count).unwrap_or(i32::MAX);
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = sqlx::query!(
+            r#"INSERT INTO search_queries (query, result_type, county_id, result_count)
+               VALUES ($1, $2, $3, $4)"#,
+            query,
+            search_type,
+            county_id,
+            result_count
         )
-        ORDER BY
-            CASE
-                WHEN $1::text IS NOT NULL THEN
-                    ts_rank(
-                        to_tsvector('english',
-                            coalesce(b.business_name, '') || ' ' ||
-                            coalesce(b.description, '') || ' ' ||
-                            coalesce(b.category, '') || ' ' ||
-                            coalesce(b.location, '')
-                        ),
-                        plainto_tsquery('english', $1::text)
-                    )
-                ELSE 0.0
-            END DESC,
-            average_rating DESC
-        LIMIT $6 OFFSET $7
-    "#;
-
-    sqlx::query_as::<_, BusinessResult>(sql)
-        .bind(q)
-        .bind(category)
-        .bind(lat)
-        .bind(lng)
-        .bind(radius_km)
-        .bind(limit)
-        .bind(offset)
-        .bind(location)
-        .fetch_all(pool)
+        .execute(&pool)
         .await
-        .map_err(AppError::Database)
+        {
+            tracing::warn!("Failed to log search query: {}", e);
+        }
+    });
 }
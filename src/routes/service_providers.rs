@@ -1,11 +1,23 @@
 use crate::errors::{AppError, AppResult};
-use crate::extractors::current_user::CurrentUser;
+use crate::extractors::current_user::{CurrentUser, OptionalCurrentUser};
+use crate::extractors::roles::CurrentProvider;
+use crate::extractors::tx::{Tx, transactional};
+use crate::routes::favorites::favorited_by;
+use crate::routes::{availability, reviews};
+use crate::utils::body_limit::upload_route;
+use crate::utils::etag::{CacheClass, conditional_route};
 use crate::utils::image_upload::parse_image_from_multipart;
+use crate::utils::onboarding;
+use crate::utils::pagination::{Paginated, Pagination, Sort};
+use crate::utils::settings;
 use crate::utils::storage::{SharedStorage, generate_key};
+use crate::utils::structured_data;
+use crate::utils::thumbnails::store_image_variants;
 use axum::{
     Extension, Json, Router,
     extract::{Multipart, Path, Query, State},
     http::StatusCode,
+    response::Response,
     routing::{get, post},
 };
 use chrono::NaiveTime;
@@ -16,12 +28,13 @@ use validator::Validate;
 
 pub fn service_providers_routes(pool: PgPool) -> Router {
     Router::new()
-        .route("/onboard", post(onboard_service_provider))
-        .route("/listProviders", get(list_providers))
+        .route("/onboard", transactional(post(onboard_service_provider)))
+        .route("/listProviders", conditional_route(get(list_providers), CacheClass::Listing))
         .route("/:id", get(get_provider_public_profile))
+        .route("/:id/schema.json", get(get_provider_schema))
         .route("/updateProfile", post(update_provider_profile))
-        .route("/uploadProfilePhoto", post(upload_provider_profile_photo))
-        .route("/uploadCoverPhoto", post(upload_provider_cover_photo))
+        .route("/uploadProfilePhoto", upload_route(post(upload_provider_profile_photo), &pool))
+        .route("/uploadCoverPhoto", upload_route(post(upload_provider_cover_photo), &pool))
         .route("/getProviderData", get(get_provider_data))
         .route("/updateAvailability", post(update_provider_availability))
         .route("/updateBulkAvailability", post(update_bulk_availability))
@@ -47,40 +60,48 @@ pub struct ProviderOnboardRequest {
 }
 
 pub async fn onboard_service_provider(
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     State(pool): State<PgPool>,
+    mut tx: Tx,
     Json(payload): Json<ProviderOnboardRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
-    let exists = sqlx::query_scalar!(
-        "SELECT 1 FROM providers WHERE user_id = $1",
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    if exists.is_none() {
-        return Err(AppError::BadRequest(
-            "Provider profile not found. Please register as a provider first.".to_string(),
-        ));
-    }
-
-    let mut tx = pool.begin().await?;
+    let require_review = settings::flag(&pool, "listings.require_review").await;
 
+    // Creates the profile if the account has none yet, otherwise updates it.
+    // A first onboard (or a resubmission after rejection) goes back into the
+    // moderation queue while review is required. Only provider accounts get
+    // a new profile: for anyone else the SELECT yields no row to insert.
     let record = sqlx::query!(
-        r#"UPDATE providers SET
-             service_name = $1,
-             service_description = $2,
-             category = $3,
-             location = $4,
-             phone_number = $5,
-             email = $6,
-             website = $7,
-             whatsapp = $8,
-             profile_photo = COALESCE($9, profile_photo),
+        r#"INSERT INTO providers (user_id, service_name, service_description, category, location,
+                                phone_number, email, website, whatsapp, profile_photo,
+                                listing_status, onboarding_completed)
+           SELECT u.id, $1, $2, $3, $4, $5, $6, $7, $8, $9,
+                  CASE WHEN $11 THEN 'pending_review' ELSE 'approved' END, TRUE
+           FROM users u
+           WHERE u.id = $10 AND u.role = 'provider'
+           ON CONFLICT (user_id) DO UPDATE SET
+             service_name = EXCLUDED.service_name,
+             service_description = EXCLUDED.service_description,
+             category = EXCLUDED.category,
+             location = EXCLUDED.location,
+             phone_verified_at = CASE
+                 WHEN providers.phone_number IS DISTINCT FROM EXCLUDED.phone_number THEN NULL
+                 ELSE providers.phone_verified_at
+             END,
+             phone_number = EXCLUDED.phone_number,
+             email = EXCLUDED.email,
+             website = EXCLUDED.website,
+             whatsapp = EXCLUDED.whatsapp,
+             profile_photo = COALESCE(EXCLUDED.profile_photo, providers.profile_photo),
+             listing_status = CASE
+                 WHEN $11 AND (NOT providers.onboarding_completed OR providers.listing_status = 'rejected')
+                     THEN 'pending_review'
+                 ELSE providers.listing_status
+             END,
              onboarding_completed = TRUE
-         WHERE user_id = $10 RETURNING id"#,
+           RETURNING id, listing_status, (xmax = 0) AS "created!""#,
         payload.service_name,
         payload.service_description,
         payload.category,
@@ -90,19 +111,30 @@ pub async fn onboard_service_provider(
         payload.website,
         payload.whatsapp,
         payload.profile_photo,
-        user_id
+        user_id,
+        require_review
     )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| {
-        AppError::Internal(format!("Failed to update provider: {}", e))
-    })?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Forbidden("Only provider accounts can onboard as a provider".to_string()))?;
 
+    // Committed before the score is refreshed, which reads the new row
     tx.commit().await?;
+    onboarding::refresh_score(&pool, record.id).await;
 
+    let (status, message) = if record.created {
+        (StatusCode::CREATED, "Provider profile created successfully")
+    } else {
+        (StatusCode::OK, "Provider profile updated successfully")
+    };
     Ok((
-        StatusCode::OK,
-        Json(json!({ "message": "Provider profile updated successfully", "provider_id": record.id })),
+        status,
+        Json(json!({
+            "message": message,
+            "provider_id": record.id,
+            "listing_status": record.listing_status,
+            "created": record.created,
+        })),
     ))
 }
 
@@ -110,10 +142,52 @@ pub async fn onboard_service_provider(
 pub struct ProviderQuery {
     pub category: Option<String>,
     pub location: Option<String>,
+    /// Free text matched against name, category and description.
+    pub q: Option<String>,
+    pub sort: Option<String>,
 }
 
+/// `?sort=` fields for `list_providers`.
+const PROVIDER_SORTS: &[(&str, &str)] = &[
+    ("rating", "avg_rating"),
+    ("review_count", "review_count"),
+    ("reviews", "review_count"),
+    ("name", "p.service_name"),
+    ("newest", "p.id"),
+];
+
+/// Approved, visible providers matching the list filters: $1 category and
+/// $2 location as `ILIKE` patterns, and $3 free text. The text matches the
+/// full-text document, or closely enough by trigrams (the expression is the
+/// one `idx_providers_search_text_trgm` indexes).
+const LISTED_PROVIDERS_WHERE: &str = r#"
+    WHERE p.onboarding_completed = TRUE
+      AND p.listing_status = 'approved'
+      AND NOT user_is_hidden(p.user_id)
+      AND ($1::text IS NULL OR p.category ILIKE $1)
+      AND ($2::text IS NULL OR p.location ILIKE $2)
+      AND ($3::text IS NULL
+           OR p.search_vector @@ websearch_to_tsquery('english', $3)
+           OR $3 <% (coalesce(p.service_name, '') || ' ' || coalesce(p.category, '') || ' '
+                     || coalesce(p.service_description, '')))
+"#;
+
+/// Case-insensitive substring pattern for an optional filter; blank is unset.
+fn contains_pattern(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(|v| format!("%{}%", v))
+}
+
+/// Rating and review count per provider, aggregated once rather than joining
+/// every review row to the listing and grouping it back up.
+const PROVIDER_RATINGS: &str = r#"
+    SELECT target_id, ROUND(AVG(rating)::numeric, 1)::float8 AS avg_rating, COUNT(*) AS review_count
+    FROM reviews
+    WHERE target_type = 'provider' AND hidden = FALSE
+    GROUP BY target_id
+"#;
+
 #[derive(Serialize, Debug, sqlx::FromRow)]
-struct PublicProvider {
+pub struct PublicProvider {
     id: i32,
     service_name: Option<String>,
     category: Option<String>,
@@ -122,35 +196,65 @@ struct PublicProvider {
     phone_number: Option<String>,
     website: Option<String>,
     profile_photo: Option<String>,
+    profile_photo_thumb_url: Option<String>,
     avg_rating: Option<f64>,
     review_count: Option<i64>,
+    /// Only sent to signed-in callers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_favorited: Option<bool>,
 }
 
 pub async fn list_providers(
     State(pool): State<PgPool>,
+    viewer: OptionalCurrentUser,
     Query(params): Query<ProviderQuery>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let providers = sqlx::query_as::<_, PublicProvider>(
+    pagination: Pagination,
+) -> AppResult<(StatusCode, Json<Paginated<PublicProvider>>)> {
+    let sort = Sort::parse(params.sort.as_deref(), PROVIDER_SORTS, "-rating")?;
+    // The default order puts providers who finished setting up first; an
+    // explicit ?sort= is taken literally.
+    let boost = if params.sort.as_deref().is_some_and(|s| !s.trim().is_empty()) {
+        ""
+    } else {
+        "p.onboarding_score DESC, "
+    };
+
+    let list_sql = format!(
         r#"SELECT p.id, p.service_name, p.category, p.location, p.email, p.phone_number,
                   p.website, p.profile_photo,
-                  ROUND(AVG(r.rating)::numeric, 1)::float8 AS avg_rating,
-                  COUNT(r.id) AS review_count
+                  image_variant(p.profile_photo, 'thumb') AS profile_photo_thumb_url,
+                  r.avg_rating, COALESCE(r.review_count, 0) AS review_count,
+                  {} AS is_favorited
            FROM providers p
-           JOIN users u ON p.user_id = u.id
-           LEFT JOIN reviews r ON r.target_id = p.id AND r.target_type = 'provider'
-           WHERE p.onboarding_completed = TRUE
-             AND ($1::text IS NULL OR p.category = $1)
-             AND ($2::text IS NULL OR p.location = $2)
-           GROUP BY p.id
-           ORDER BY avg_rating DESC NULLS LAST, p.id"#,
-    )
-    .bind(&params.category)
-    .bind(&params.location)
-    .fetch_all(&pool)
-    .await
-    .map_err(AppError::Database)?;
-
-    Ok((StatusCode::OK, Json(json!({ "providers": providers }))))
+           LEFT JOIN ({PROVIDER_RATINGS}) r ON r.target_id = p.id
+           {LISTED_PROVIDERS_WHERE}
+           ORDER BY {boost}{}, p.id
+           LIMIT $4 OFFSET $5"#,
+        favorited_by("provider", "p.id", 6),
+        sort.order_by()
+    );
+    let count_sql = format!("SELECT COUNT(*) FROM providers p {LISTED_PROVIDERS_WHERE}");
+    let category = contains_pattern(params.category.as_deref());
+    let location = contains_pattern(params.location.as_deref());
+    let q = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    let (providers, total) = tokio::try_join!(
+        sqlx::query_as::<_, PublicProvider>(&list_sql)
+            .bind(&category)
+            .bind(&location)
+            .bind(q)
+            .bind(pagination.limit())
+            .bind(pagination.offset())
+            .bind(viewer.user_id())
+            .fetch_all(&pool),
+        sqlx::query_scalar::<_, i64>(&count_sql)
+            .bind(&category)
+            .bind(&location)
+            .bind(q)
+            .fetch_one(&pool),
+    )?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(providers, pagination, total))))
 }
 
 #[derive(Serialize, Debug, sqlx::FromRow)]
@@ -166,28 +270,48 @@ struct ProviderPublicProfile {
     website: Option<String>,
     whatsapp: Option<String>,
     profile_photo: Option<String>,
+    profile_photo_thumb_url: Option<String>,
+    profile_photo_medium_url: Option<String>,
     cover_photo: Option<String>,
+    cover_photo_medium_url: Option<String>,
     onboarding_completed: bool,
+    listing_status: String,
     avg_rating: Option<f64>,
     review_count: Option<i64>,
+    /// Only sent to signed-in callers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_favorited: Option<bool>,
 }
 
+/// A provider's public profile with their active services, weekly schedule
+/// and rating. Listings still under review (or rejected) are only visible to
+/// their owner.
 pub async fn get_provider_public_profile(
     State(pool): State<PgPool>,
     Path(id): Path<i32>,
+    viewer: OptionalCurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let profile = sqlx::query_as::<_, ProviderPublicProfile>(
+    let viewer_id = viewer.user_id();
+
+    let profile = sqlx::query_as::<_, ProviderPublicProfile>(&format!(
         r#"SELECT p.id, p.user_id, p.service_name, p.service_description, p.category, p.location,
                   p.email, p.phone_number, p.website, p.whatsapp,
-                  p.profile_photo, p.cover_photo, p.onboarding_completed,
+                  p.profile_photo, p.cover_photo, p.onboarding_completed, p.listing_status,
+                  image_variant(p.profile_photo, 'thumb') AS profile_photo_thumb_url,
+                  image_variant(p.profile_photo, 'medium') AS profile_photo_medium_url,
+                  image_variant(p.cover_photo, 'medium') AS cover_photo_medium_url,
                   ROUND(AVG(r.rating)::numeric, 1)::float8 AS avg_rating,
-                  COUNT(r.id) AS review_count
+                  COUNT(r.id) AS review_count,
+                  {} AS is_favorited
            FROM providers p
-           LEFT JOIN reviews r ON r.target_id = p.id AND r.target_type = 'provider'
-           WHERE p.id = $1
+           LEFT JOIN reviews r ON r.target_id = p.id AND r.target_type = 'provider' AND r.hidden = FALSE
+           WHERE p.id = $1 AND NOT user_is_hidden(p.user_id)
+             AND (p.listing_status = 'approved' OR p.user_id = $2)
            GROUP BY p.id"#,
-    )
+        favorited_by("provider", "p.id", 2)
+    ))
     .bind(id)
+    .bind(viewer_id)
     .fetch_optional(&pool)
     .await?
     .ok_or_else(|| AppError::NotFound("Provider not found".to_string()))?;
@@ -215,12 +339,28 @@ pub async fn get_provider_public_profile(
         }))
         .collect();
 
+    let (availability, rating) = tokio::try_join!(
+        availability::schedule(&pool, id),
+        reviews::aggregated_rating(&pool, "provider", id),
+    )?;
+
     Ok((StatusCode::OK, Json(json!({
         "provider": profile,
         "services": services_json,
+        "availability": availability,
+        "aggregated_rating": rating,
     }))))
 }
 
+/// schema.org `LocalBusiness` JSON-LD for the provider's page, for search
+/// engines. Only listed providers have one.
+pub async fn get_provider_schema(State(pool): State<PgPool>, Path(id): Path<i32>) -> AppResult<Response> {
+    let doc = structured_data::provider(&pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Provider not found".to_string()))?;
+    Ok(structured_data::json_ld(doc))
+}
+
 #[derive(Deserialize, Debug, Validate)]
 pub struct UpdateProviderProfileRequest {
     #[validate(length(min = 3))]
@@ -236,11 +376,11 @@ pub struct UpdateProviderProfileRequest {
 }
 
 pub async fn update_provider_profile(
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     State(pool): State<PgPool>,
     Json(payload): Json<UpdateProviderProfileRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    payload.validate().map_err(|e| AppError::BadRequest(e.to_string()))?;
+    payload.validate()?;
 
     let mut query = String::from("UPDATE providers SET ");
     let mut updates = vec![];
@@ -263,6 +403,10 @@ pub async fn update_provider_profile(
         idx += 1;
     }
     if let Some(ref v) = payload.phone_number {
+        // A new number needs verifying again
+        updates.push(format!(
+            "phone_verified_at = CASE WHEN phone_number IS DISTINCT FROM ${idx} THEN NULL ELSE phone_verified_at END"
+        ));
         updates.push(format!("phone_number = ${}", idx));
         bindings.push(v.clone());
         idx += 1;
@@ -288,15 +432,18 @@ pub async fn update_provider_profile(
     }
 
     query.push_str(&updates.join(", "));
-    query.push_str(&format!(" WHERE user_id = ${}", idx));
+    query.push_str(&format!(" WHERE user_id = ${} RETURNING id", idx));
 
-    let mut q = sqlx::query(&query);
+    let mut q = sqlx::query_scalar::<_, i32>(&query);
     for b in bindings {
         q = q.bind(b);
     }
     q = q.bind(user_id);
 
-    q.execute(&pool).await.map_err(AppError::Database)?;
+    let provider_id = q.fetch_optional(&pool).await.map_err(AppError::Database)?;
+    if let Some(provider_id) = provider_id {
+        onboarding::refresh_score(&pool, provider_id).await;
+    }
 
     Ok((StatusCode::OK, Json(json!({ "message": "Profile updated successfully" }))))
 }
@@ -304,51 +451,63 @@ pub async fn update_provider_profile(
 pub async fn upload_provider_profile_photo(
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let (data, ext, _ct) = parse_image_from_multipart(multipart).await?;
+    let (data, ext, content_type) = parse_image_from_multipart(&pool, multipart).await?;
     let key = generate_key("providers/profile_photos", &ext);
-    let url = storage.save(&key, &data).await?;
+    let url = storage.save(&key, &data, &content_type).await?;
 
-    let result = sqlx::query!(
-        "UPDATE providers SET profile_photo = $1 WHERE user_id = $2",
+    let result = sqlx::query_scalar!(
+        "UPDATE providers SET profile_photo = $1 WHERE user_id = $2 RETURNING id",
         url, user_id
     )
-    .execute(&pool)
+    .fetch_optional(&pool)
     .await;
 
-    if let Err(e) = result {
-        let _ = storage.delete(&key).await;
-        return Err(AppError::Database(e));
+    match result {
+        Ok(Some(provider_id)) => onboarding::refresh_score(&pool, provider_id).await,
+        Ok(None) => {}
+        Err(e) => {
+            let _ = storage.delete(&key).await;
+            return Err(AppError::Database(e));
+        }
     }
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Profile photo uploaded successfully", "url": url }))))
+    let variants = store_image_variants(&pool, &storage, &key, &url, data).await;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Profile photo uploaded successfully", "url": url, "thumb_url": variants.thumb_url, "medium_url": variants.medium_url }))))
 }
 
 pub async fn upload_provider_cover_photo(
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     multipart: Multipart,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let (data, ext, _ct) = parse_image_from_multipart(multipart).await?;
+    let (data, ext, content_type) = parse_image_from_multipart(&pool, multipart).await?;
     let key = generate_key("providers/cover_photos", &ext);
-    let url = storage.save(&key, &data).await?;
+    let url = storage.save(&key, &data, &content_type).await?;
 
-    let result = sqlx::query!(
-        "UPDATE providers SET cover_photo = $1 WHERE user_id = $2",
+    let result = sqlx::query_scalar!(
+        "UPDATE providers SET cover_photo = $1 WHERE user_id = $2 RETURNING id",
         url, user_id
     )
-    .execute(&pool)
+    .fetch_optional(&pool)
     .await;
 
-    if let Err(e) = result {
-        let _ = storage.delete(&key).await;
-        return Err(AppError::Database(e));
+    match result {
+        Ok(Some(provider_id)) => onboarding::refresh_score(&pool, provider_id).await,
+        Ok(None) => {}
+        Err(e) => {
+            let _ = storage.delete(&key).await;
+            return Err(AppError::Database(e));
+        }
     }
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Cover photo uploaded successfully", "url": url }))))
+    let variants = store_image_variants(&pool, &storage, &key, &url, data).await;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Cover photo uploaded successfully", "url": url, "thumb_url": variants.thumb_url, "medium_url": variants.medium_url }))))
 }
 
 #[derive(Serialize, Debug, sqlx::FromRow)]
@@ -362,16 +521,21 @@ pub struct ProviderData {
     email: Option<String>,
     website: Option<String>,
     whatsapp: Option<String>,
+    listing_status: String,
+    listing_rejection_reason: Option<String>,
+    listing_reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 pub async fn get_provider_data(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let provider = sqlx::query_as!(
         ProviderData,
         "SELECT id, service_name, service_description, category, location, \
-         phone_number, email, website, whatsapp FROM providers WHERE user_id = $1",
+         phone_number, email, website, whatsapp, \
+         listing_status, listing_rejection_reason, listing_reviewed_at \
+         FROM providers WHERE user_id = $1",
         user_id
     )
     .fetch_optional(&pool)
@@ -394,10 +558,10 @@ pub struct ProviderAvailability {
 
 pub async fn update_provider_availability(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentProvider { provider_id, .. }: CurrentProvider,
     Json(payload): Json<ProviderAvailability>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    if payload.provider_id != user_id {
+    if payload.provider_id != provider_id {
         return Err(AppError::Forbidden(
             "You are not authorized to update this provider's availability".to_string(),
         ));
@@ -408,17 +572,6 @@ pub async fn update_provider_availability(
     let end_time = NaiveTime::parse_from_str(&payload.end_time, "%H:%M")
         .map_err(|e| AppError::BadRequest(format!("Invalid end time format: {}", e)))?;
 
-    let provider_exists = sqlx::query_scalar!(
-        "SELECT 1 FROM providers WHERE id = $1",
-        payload.provider_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    if provider_exists.is_none() {
-        return Err(AppError::NotFound("Provider not found".to_string()));
-    }
-
     let availability_exists = sqlx::query_scalar!(
         "SELECT 1 FROM provider_availability WHERE provider_id = $1 AND day = $2",
         payload.provider_id,
@@ -439,6 +592,7 @@ pub async fn update_provider_availability(
         )
         .execute(&pool)
         .await?;
+        onboarding::refresh_score(&pool, payload.provider_id).await;
 
         Ok((StatusCode::OK, Json(json!({ "message": "Availability updated successfully" }))))
     } else {
@@ -453,6 +607,7 @@ pub async fn update_provider_availability(
         )
         .execute(&pool)
         .await?;
+        onboarding::refresh_score(&pool, payload.provider_id).await;
 
         Ok((StatusCode::CREATED, Json(json!({ "message": "Availability created successfully" }))))
     }
@@ -473,18 +628,9 @@ pub struct BulkAvailabilityUpdate {
 
 pub async fn update_bulk_availability(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentProvider { provider_id, .. }: CurrentProvider,
     Json(payload): Json<BulkAvailabilityUpdate>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let provider = sqlx::query_scalar!(
-        "SELECT id FROM providers WHERE user_id = $1",
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await?;
-
-    let provider_id = provider.ok_or_else(|| AppError::NotFound("Provider not found".to_string()))?;
-
     let mut tx = pool.begin().await?;
     let mut updated_count = 0u32;
     let mut created_count = 0u32;
@@ -535,6 +681,7 @@ pub async fn update_bulk_availability(
     }
 
     tx.commit().await?;
+    onboarding::refresh_score(&pool, provider_id).await;
 
     Ok((
         StatusCode::OK,
@@ -548,16 +695,8 @@ pub async fn update_bulk_availability(
 
 pub async fn get_provider_availability(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentProvider { provider_id, .. }: CurrentProvider,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let provider_id = sqlx::query_scalar!(
-        "SELECT id FROM providers WHERE user_id = $1",
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await?
-    .ok_or_else(|| AppError::NotFound("Provider not found".to_string()))?;
-
     let records = sqlx::query!(
         "SELECT id, day, start_time, end_time, is_available \
          FROM provider_availability \
@@ -1,5 +1,9 @@
 use crate::errors::{AppError, AppResult};
 use crate::extractors::current_user::CurrentUser;
+use crate::extractors::tx::{Tx, transactional};
+use crate::utils::onboarding;
+use crate::utils::pagination::{Paginated, Pagination, Sort};
+use crate::utils::settings;
 use axum::{
     Json, Router,
     extract::{Query, State},
@@ -14,7 +18,7 @@ use sqlx::PgPool;
 
 pub fn services_routes(pool: PgPool) -> Router {
     Router::new()
-        .route("/createService", post(create_service))
+        .route("/createService", transactional(post(create_service)))
         .route("/getServices", get(get_services))
         .route("/deleteService", post(delete_service))
         .route("/updateService", post(edit_service))
@@ -50,11 +54,10 @@ pub struct CreateServiceParams {
 
 pub async fn create_service(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
+    mut tx: Tx,
     Json(payload): Json<CreateServiceParams>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let mut tx = pool.begin().await?;
-
     let target_exists = match payload.target_type.as_str() {
         "provider" => sqlx::query_scalar!(
             "SELECT id FROM providers WHERE id = $1 AND user_id = $2",
@@ -71,6 +74,20 @@ pub async fn create_service(
         return Err(AppError::Forbidden("You are not authorized to create services for this target".to_string()));
     }
 
+    let max_services = settings::int(&pool, "services.max_per_target").await;
+    let existing = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM services WHERE target_type = $1 AND target_id = $2"#,
+        payload.target_type,
+        payload.target_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if existing >= max_services {
+        return Err(AppError::BadRequest(format!(
+            "You can list at most {max_services} services"
+        )));
+    }
+
     let record = sqlx::query!(
         r#"INSERT INTO services (target_id, target_type, title, description, price, duration, category_id, is_active)
            VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id"#,
@@ -86,7 +103,11 @@ pub async fn create_service(
     .fetch_one(&mut *tx)
     .await?;
 
+    // Committed before the score is refreshed, which counts the new service
     tx.commit().await?;
+    if payload.target_type == "provider" {
+        onboarding::refresh_score(&pool, payload.target_id).await;
+    }
 
     Ok((
         StatusCode::CREATED,
@@ -104,37 +125,77 @@ pub struct GetServicesParams {
     pub target_type: Option<String>,
     pub category_id: Option<i32>,
     pub is_active: Option<bool>,
+    pub sort: Option<String>,
 }
 
-pub async fn get_services(
-    State(pool): State<PgPool>,
-    Query(params): Query<GetServicesParams>,
-) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
-    let mut qb = sqlx::QueryBuilder::new("SELECT * FROM services WHERE 1=1");
+/// `?sort=` fields for `get_services`.
+const SERVICE_SORTS: &[(&str, &str)] = &[
+    ("created_at", "s.created_at"),
+    ("price", "s.price"),
+    ("duration", "s.duration"),
+    ("title", "s.title"),
+];
 
+/// Services whose owner is itself listed: approved or verified, onboarded,
+/// through listing review and not suspended or deleted. The same rule
+/// search applies.
+const LISTED_SERVICES_WHERE: &str = r#"
+    WHERE (
+        (s.target_type = 'provider' AND EXISTS (
+            SELECT 1 FROM providers p
+            WHERE p.id = s.target_id
+              AND p.approved = TRUE
+              AND p.onboarding_completed = TRUE
+              AND p.listing_status = 'approved'
+              AND NOT user_is_hidden(p.user_id)))
+        OR (s.target_type = 'business' AND EXISTS (
+            SELECT 1 FROM businesses b
+            WHERE b.id = s.target_id
+              AND b.verified = TRUE
+              AND b.onboarding_completed = TRUE
+              AND b.listing_status = 'approved'
+              AND NOT user_is_hidden(b.user_id)))
+    )
+"#;
+
+/// Adds the `get_services` filters, shared by the page and total queries.
+fn push_service_filters<'a>(qb: &mut sqlx::QueryBuilder<'a, sqlx::Postgres>, params: &'a GetServicesParams) {
+    if let Some(ref target_type) = params.target_type {
+        qb.push(" AND s.target_type = ").push_bind(target_type);
+    }
     if let Some(target_id) = params.target_id {
-        if let Some(ref target_type) = params.target_type {
-            qb.push(" AND target_type = ").push_bind(target_type);
-            qb.push(" AND target_id = ").push_bind(target_id);
-        } else {
-            qb.push(" AND target_id = ").push_bind(target_id);
-        }
+        qb.push(" AND s.target_id = ").push_bind(target_id);
     }
     if let Some(category_id) = params.category_id {
-        qb.push(" AND category_id = ").push_bind(category_id);
+        qb.push(" AND s.category_id = ").push_bind(category_id);
     }
     if let Some(is_active) = params.is_active {
-        qb.push(" AND is_active = ").push_bind(is_active);
+        qb.push(" AND s.is_active = ").push_bind(is_active);
     }
-    qb.push(" ORDER BY created_at DESC");
+}
+
+pub async fn get_services(
+    State(pool): State<PgPool>,
+    Query(params): Query<GetServicesParams>,
+    pagination: Pagination,
+) -> AppResult<(StatusCode, Json<Paginated<Service>>)> {
+    let sort = Sort::parse(params.sort.as_deref(), SERVICE_SORTS, "-created_at")?;
+
+    let mut qb = sqlx::QueryBuilder::new(format!("SELECT s.* FROM services s {LISTED_SERVICES_WHERE}"));
+    push_service_filters(&mut qb, &params);
+    qb.push(format!(" ORDER BY {}, s.id DESC", sort.order_by()));
+    qb.push(" LIMIT ").push_bind(pagination.limit());
+    qb.push(" OFFSET ").push_bind(pagination.offset());
 
-    let services = qb
-        .build_query_as::<Service>()
-        .fetch_all(&pool)
-        .await
-        .map_err(AppError::Database)?;
+    let mut count_qb = sqlx::QueryBuilder::new(format!("SELECT COUNT(*) FROM services s {LISTED_SERVICES_WHERE}"));
+    push_service_filters(&mut count_qb, &params);
 
-    Ok((StatusCode::OK, Json(json!({ "services": services }))))
+    let (services, total) = tokio::try_join!(
+        qb.build_query_as::<Service>().fetch_all(&pool),
+        count_qb.build_query_scalar::<i64>().fetch_one(&pool),
+    )?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(services, pagination, total))))
 }
 
 #[derive(Deserialize, Serialize)]
@@ -152,7 +213,7 @@ pub struct EditServiceParams {
 
 pub async fn edit_service(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<EditServiceParams>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let mut tx = pool.begin().await?;
@@ -197,6 +258,9 @@ pub async fn edit_service(
     .await?;
 
     tx.commit().await?;
+    if payload.target_type == "provider" {
+        onboarding::refresh_score(&pool, payload.target_id).await;
+    }
 
     Ok((StatusCode::OK, Json(json!({ "message": "Service updated successfully", "service_id": record.id }))))
 }
@@ -208,7 +272,7 @@ pub struct DeleteServiceParams {
 
 pub async fn delete_service(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Json(payload): Json<DeleteServiceParams>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let mut tx = pool.begin().await?;
@@ -245,6 +309,9 @@ pub async fn delete_service(
     .await?;
 
     tx.commit().await?;
+    if service.target_type == "provider" {
+        onboarding::refresh_score(&pool, service.target_id).await;
+    }
 
     Ok((StatusCode::OK, Json(json!({ "message": "Service deleted successfully", "service_id": record.id }))))
 }
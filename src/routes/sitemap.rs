@@ -0,0 +1,50 @@
+use crate::errors::{AppError, AppResult};
+use crate::utils::sitemap;
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use sqlx::PgPool;
+
+/// Crawlers may reuse a sitemap for this long; the job rebuilds it every six
+/// hours anyway.
+const SITEMAP_CACHE_CONTROL: &str = "public, max-age=3600";
+
+/// Sitemaps for search engines. Mounted at the root rather than under the
+/// API prefix, where crawlers (and the site's proxy) look for them.
+pub fn sitemap_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/sitemap.xml", get(root_sitemap))
+        .route("/sitemaps/:name", get(sitemap_file))
+        .with_state(pool)
+}
+
+async fn serve(pool: &PgPool, name: &str) -> AppResult<Response> {
+    let body = sitemap::file(pool, name)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Sitemap not found".to_string()))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/xml; charset=utf-8"),
+            (header::CACHE_CONTROL, SITEMAP_CACHE_CONTROL),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+pub async fn root_sitemap(State(pool): State<PgPool>) -> AppResult<Response> {
+    serve(&pool, sitemap::ROOT_FILE).await
+}
+
+/// One `sitemap-N.xml` listed by the index, once there are too many URLs
+/// for a single file.
+pub async fn sitemap_file(State(pool): State<PgPool>, Path(name): Path<String>) -> AppResult<Response> {
+    if name == sitemap::ROOT_FILE {
+        return Err(AppError::NotFound("Sitemap not found".to_string()));
+    }
+    serve(&pool, &name).await
+}
@@ -101,7 +101,7 @@ async fn verify_owner(
 
 pub async fn get_wallet(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path((target_type, target_id)): Path<(String, i32)>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
     let target_type = target_type.to_lowercase();
@@ -139,7 +139,7 @@ pub async fn get_wallet(
 
 pub async fn get_transactions(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path((target_type, target_id)): Path<(String, i32)>,
     Query(params): Query<PageQuery>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
@@ -178,10 +178,13 @@ pub async fn get_transactions(
 
 pub async fn request_payout(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    current_user: CurrentUser,
     Path((target_type, target_id)): Path<(String, i32)>,
     Json(payload): Json<PayoutRequest>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    current_user.deny_impersonation()?;
+    let user_id = current_user.user_id;
+
     let target_type = target_type.to_lowercase();
     if !["provider", "business"].contains(&target_type.as_str()) {
         return Err(AppError::BadRequest("target_type must be 'provider' or 'business'".to_string()));
@@ -264,7 +267,7 @@ pub async fn request_payout(
 
 pub async fn list_payouts(
     State(pool): State<PgPool>,
-    CurrentUser { user_id }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     Path((target_type, target_id)): Path<(String, i32)>,
     Query(params): Query<PageQuery>,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
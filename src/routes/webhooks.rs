@@ -0,0 +1,325 @@
+use crate::config;
+use crate::errors::{AppError, AppResult};
+use crate::extractors::current_user::CurrentUser;
+use crate::utils::pagination::{Paginated, Pagination};
+use crate::utils::webhooks::{self, EVENT_TYPES};
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+
+/// Most subscriptions one provider or business may have.
+const MAX_SUBSCRIPTIONS_PER_TARGET: i64 = 5;
+
+pub fn webhook_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/", get(list_subscriptions).post(create_subscription))
+        .route("/:id", get(get_subscription).put(update_subscription).delete(delete_subscription))
+        .route("/:id/deliveries", get(list_deliveries))
+        .with_state(pool)
+}
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Deserialize, Debug)]
+pub struct CreateSubscriptionInput {
+    pub target_type: String,
+    pub target_id: i32,
+    pub url: String,
+    pub event_types: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdateSubscriptionInput {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    /// Turning a subscription back on clears its failure count.
+    pub active: Option<bool>,
+}
+
+/// A subscription as shown to its owner. The secret is only returned once,
+/// when the subscription is created.
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct SubscriptionRow {
+    pub id: i32,
+    pub target_type: String,
+    pub target_id: i32,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub consecutive_failures: i32,
+    pub disabled_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeliveryQuery {
+    /// `pending`, `delivered` or `failed`
+    pub status: Option<String>,
+}
+
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct DeliveryRow {
+    pub id: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+async fn verify_owner(pool: &PgPool, target_type: &str, target_id: i32, user_id: i32) -> AppResult<()> {
+    let owner = match target_type {
+        "provider" => sqlx::query_scalar!(
+            "SELECT user_id FROM providers WHERE id = $1", target_id
+        ).fetch_optional(pool).await?,
+        _ => sqlx::query_scalar!(
+            "SELECT user_id FROM businesses WHERE id = $1", target_id
+        ).fetch_optional(pool).await?,
+    };
+    if owner != Some(user_id) {
+        return Err(AppError::Forbidden("You do not own this profile".to_string()));
+    }
+    Ok(())
+}
+
+fn check_url(url: &str) -> AppResult<()> {
+    webhooks::validate_url(url, config::get().app_env == "production").map_err(AppError::BadRequest)
+}
+
+/// Deduplicated, sorted event types; every one must be known.
+fn check_event_types(event_types: &[String]) -> AppResult<Vec<String>> {
+    if event_types.is_empty() {
+        return Err(AppError::BadRequest("Subscribe to at least one event type".to_string()));
+    }
+    if let Some(unknown) = event_types.iter().find(|e| !EVENT_TYPES.contains(&e.as_str())) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown event type {:?}. Use one of: {}",
+            unknown,
+            EVENT_TYPES.join(", ")
+        )));
+    }
+    let mut event_types = event_types.to_vec();
+    event_types.sort();
+    event_types.dedup();
+    Ok(event_types)
+}
+
+async fn owned_subscription(pool: &PgPool, id: i32, user_id: i32) -> AppResult<SubscriptionRow> {
+    sqlx::query_as!(
+        SubscriptionRow,
+        r#"SELECT id, target_type, target_id, url, event_types, active, consecutive_failures,
+                  disabled_reason, created_at, updated_at
+           FROM webhook_subscriptions WHERE id = $1 AND user_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Webhook subscription not found".to_string()))
+}
+
+// ── GET /webhooks ─────────────────────────────────────────────────────────────
+
+pub async fn list_subscriptions(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let subscriptions = sqlx::query_as!(
+        SubscriptionRow,
+        r#"SELECT id, target_type, target_id, url, event_types, active, consecutive_failures,
+                  disabled_reason, created_at, updated_at
+           FROM webhook_subscriptions WHERE user_id = $1
+           ORDER BY id"#,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "subscriptions": subscriptions }))))
+}
+
+// ── POST /webhooks ────────────────────────────────────────────────────────────
+
+pub async fn create_subscription(
+    State(pool): State<PgPool>,
+    current_user: CurrentUser,
+    Json(payload): Json<CreateSubscriptionInput>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    current_user.deny_impersonation()?;
+    let user_id = current_user.user_id;
+    let target_type = payload.target_type.to_lowercase();
+    if !["provider", "business"].contains(&target_type.as_str()) {
+        return Err(AppError::BadRequest("target_type must be 'provider' or 'business'".to_string()));
+    }
+    verify_owner(&pool, &target_type, payload.target_id, user_id).await?;
+    let url = payload.url.trim();
+    check_url(url)?;
+    let event_types = check_event_types(&payload.event_types)?;
+
+    let existing = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM webhook_subscriptions WHERE target_type = $1 AND target_id = $2"#,
+        target_type,
+        payload.target_id
+    )
+    .fetch_one(&pool)
+    .await?;
+    if existing >= MAX_SUBSCRIPTIONS_PER_TARGET {
+        return Err(AppError::BadRequest(format!(
+            "A profile can have at most {} webhook subscriptions",
+            MAX_SUBSCRIPTIONS_PER_TARGET
+        )));
+    }
+
+    let secret = webhooks::generate_secret();
+    let subscription = sqlx::query_as!(
+        SubscriptionRow,
+        r#"INSERT INTO webhook_subscriptions (user_id, target_type, target_id, url, secret, event_types)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING id, target_type, target_id, url, event_types, active, consecutive_failures,
+                     disabled_reason, created_at, updated_at"#,
+        user_id,
+        target_type,
+        payload.target_id,
+        url,
+        secret,
+        &event_types
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "subscription": subscription,
+            "secret": secret,
+            "message": "Store the secret now; it is not shown again",
+        })),
+    ))
+}
+
+// ── GET /webhooks/:id ─────────────────────────────────────────────────────────
+
+pub async fn get_subscription(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let subscription = owned_subscription(&pool, id, user_id).await?;
+    Ok((StatusCode::OK, Json(json!({ "subscription": subscription }))))
+}
+
+// ── PUT /webhooks/:id ─────────────────────────────────────────────────────────
+
+pub async fn update_subscription(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    current_user: CurrentUser,
+    Json(payload): Json<UpdateSubscriptionInput>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    current_user.deny_impersonation()?;
+    let user_id = current_user.user_id;
+    owned_subscription(&pool, id, user_id).await?;
+    let url = payload.url.as_deref().map(str::trim);
+    if let Some(url) = url {
+        check_url(url)?;
+    }
+    let event_types = payload.event_types.as_deref().map(check_event_types).transpose()?;
+
+    let subscription = sqlx::query_as!(
+        SubscriptionRow,
+        r#"UPDATE webhook_subscriptions SET
+               url                  = COALESCE($2, url),
+               event_types          = COALESCE($3, event_types),
+               active               = COALESCE($4, active),
+               consecutive_failures = CASE WHEN $4 THEN 0 ELSE consecutive_failures END,
+               disabled_reason      = CASE WHEN $4 THEN NULL ELSE disabled_reason END,
+               updated_at           = NOW()
+           WHERE id = $1
+           RETURNING id, target_type, target_id, url, event_types, active, consecutive_failures,
+                     disabled_reason, created_at, updated_at"#,
+        id,
+        url,
+        event_types.as_deref(),
+        payload.active
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "subscription": subscription }))))
+}
+
+// ── DELETE /webhooks/:id ──────────────────────────────────────────────────────
+
+pub async fn delete_subscription(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    current_user: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    current_user.deny_impersonation()?;
+    let user_id = current_user.user_id;
+    owned_subscription(&pool, id, user_id).await?;
+    sqlx::query!("DELETE FROM webhook_subscriptions WHERE id = $1", id)
+        .execute(&pool)
+        .await?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Webhook subscription deleted" }))))
+}
+
+// ── GET /webhooks/:id/deliveries ──────────────────────────────────────────────
+
+/// Newest first, with each attempt's outcome.
+pub async fn list_deliveries(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    pagination: Pagination,
+    Query(params): Query<DeliveryQuery>,
+) -> AppResult<(StatusCode, Json<Paginated<DeliveryRow>>)> {
+    owned_subscription(&pool, id, user_id).await?;
+    let status = params.status.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if let Some(status) = status {
+        if !["pending", "delivered", "failed"].contains(&status) {
+            return Err(AppError::BadRequest("status must be pending, delivered or failed".to_string()));
+        }
+    }
+
+    let (items, total) = tokio::try_join!(
+        sqlx::query_as!(
+            DeliveryRow,
+            r#"SELECT id, event_type, payload, status, attempts, next_attempt_at, last_status_code,
+                      last_error, created_at, delivered_at
+               FROM webhook_deliveries
+               WHERE subscription_id = $1 AND ($2::text IS NULL OR status = $2)
+               ORDER BY created_at DESC, id DESC
+               LIMIT $3 OFFSET $4"#,
+            id,
+            status,
+            pagination.limit(),
+            pagination.offset()
+        )
+        .fetch_all(&pool),
+        sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM webhook_deliveries
+               WHERE subscription_id = $1 AND ($2::text IS NULL OR status = $2)"#,
+            id,
+            status
+        )
+        .fetch_one(&pool),
+    )?;
+
+    Ok((StatusCode::OK, Json(Paginated::new(items, pagination, total))))
+}
@@ -1,3 +1,5 @@
+use crate::errors::AppError;
+use crate::utils::flags;
 use crate::utils::jwt::decode_jwt;
 use crate::utils::ws_state::WsConnections;
 use axum::{
@@ -6,17 +8,28 @@ use axum::{
         Query,
         ws::{Message, WebSocket, WebSocketUpgrade},
     },
-    http::StatusCode,
     response::IntoResponse,
-    routing::get,
+    routing::{MethodRouter, get},
 };
 use futures_util::{SinkExt, StreamExt};
 use serde::Deserialize;
 use serde_json::json;
+use std::time::Duration;
 use tokio::sync::broadcast;
 
+/// How often the server pings an idle socket.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// A socket that sends nothing, not even a pong, for this long is dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(75);
+
 pub fn ws_routes() -> Router {
-    Router::new().route("/", get(ws_handler))
+    Router::new().route("/", ws_route())
+}
+
+/// The upgrade route behind the `websocket` flag, also mounted at
+/// `/messages/ws`.
+pub fn ws_route<S: Clone + Send + Sync + 'static>() -> MethodRouter<S> {
+    get(ws_handler).route_layer(axum::middleware::from_fn_with_state(flags::WEBSOCKET, flags::require_flag))
 }
 
 // ── Query param extractor for the JWT ─────────────────────────────────────────
@@ -37,10 +50,10 @@ pub async fn ws_handler(
         Ok(claims) => match claims.sub.parse::<i32>() {
             Ok(id) => id,
             Err(_) => {
-                return (StatusCode::UNAUTHORIZED, "Invalid token subject").into_response()
+                return AppError::Unauthorized("Invalid token subject".to_string()).into_response()
             }
         },
-        Err(_) => return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response(),
+        Err(_) => return AppError::Unauthorized("Invalid or expired token".to_string()).into_response(),
     };
 
     ws.on_upgrade(move |socket| handle_socket(socket, ws_connections, user_id))
@@ -66,31 +79,35 @@ async fn handle_socket(socket: WebSocket, connections: WsConnections, user_id: i
     // Send a connected confirmation
     let hello = serde_json::to_string(&json!({ "event": "connected", "user_id": user_id }))
         .unwrap_or_default();
-    if ws_sender.send(Message::Text(hello.into())).await.is_err() {
+    if ws_sender.send(Message::Text(hello)).await.is_err() {
         return;
     }
 
-    // Task: forward broadcast messages → WebSocket client
+    // Task: forward broadcast messages → WebSocket client, pinging between them
     let mut send_task = tokio::spawn(async move {
         let mut rx = rx;
+        let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
         loop {
-            match rx.recv().await {
-                Ok(msg) => {
-                    if ws_sender.send(Message::Text(msg.into())).await.is_err() {
-                        break;
+            let outgoing = tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(msg) => Message::Text(msg),
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        // Client is too slow; skip missed messages and continue
+                        continue;
                     }
-                }
-                Err(broadcast::error::RecvError::Lagged(_)) => {
-                    // Client is too slow; skip missed messages and continue
-                }
-                Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = ping.tick() => Message::Ping(Vec::new()),
+            };
+            if ws_sender.send(outgoing).await.is_err() {
+                break;
             }
         }
     });
 
-    // Task: read from WebSocket (detect close / pong)
+    // Task: read from WebSocket (detect close, or silence past IDLE_TIMEOUT)
     let mut recv_task = tokio::spawn(async move {
-        while let Some(result) = ws_receiver.next().await {
+        while let Ok(Some(result)) = tokio::time::timeout(IDLE_TIMEOUT, ws_receiver.next()).await {
             match result {
                 Ok(Message::Close(_)) | Err(_) => break,
                 _ => {} // ignore text/binary/ping/pong from client
@@ -103,6 +120,19 @@ async fn handle_socket(socket: WebSocket, connections: WsConnections, user_id: i
         _ = &mut send_task => recv_task.abort(),
         _ = &mut recv_task => send_task.abort(),
     }
+    // Wait for the aborted task so its receiver is gone before counting
+    let _ = send_task.await;
+    let _ = recv_task.await;
+    forget_if_unused(&connections, user_id).await;
 
     tracing::info!("WebSocket disconnected: user_id={}", user_id);
 }
+
+/// Drops the user's channel once their last socket has closed, so the map
+/// only holds users who are connected.
+async fn forget_if_unused(connections: &WsConnections, user_id: i32) {
+    let mut map = connections.write().await;
+    if map.get(&user_id).is_some_and(|tx| tx.receiver_count() == 0) {
+        map.remove(&user_id);
+    }
+}
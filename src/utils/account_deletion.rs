@@ -0,0 +1,69 @@
+use crate::utils::settings;
+use sqlx::{PgConnection, PgPool};
+
+/// Days a soft-deleted account can still be restored before the cleanup sweep
+/// purges it (`users.deletion_retention_days`).
+pub async fn retention_days(pool: &PgPool) -> i32 {
+    settings::int(pool, "users.deletion_retention_days").await as i32
+}
+
+/// Tables keyed by `(target_type, target_id)` rather than a foreign key, so
+/// deleting a provider/business row leaves them pointing at nothing.
+const TARGET_TABLES: [&str; 10] = [
+    "bookings",
+    "reviews",
+    "favorites",
+    "messages",
+    "interactions",
+    "services",
+    "service_packages",
+    "attachments",
+    "wallets",
+    "content_flags",
+];
+
+/// Permanently removes a user. Rows the foreign keys can't reach (anything
+/// addressed by target_type/target_id, messages they received) and rows whose
+/// foreign keys would block the delete are removed first. Run inside a
+/// transaction.
+pub async fn purge_user(conn: &mut PgConnection, user_id: i32) -> Result<(), sqlx::Error> {
+    for table in TARGET_TABLES {
+        let sql = format!(
+            r#"DELETE FROM {table}
+               WHERE (target_type, target_id) IN (
+                   SELECT 'provider', id FROM providers  WHERE user_id = $1
+                   UNION ALL
+                   SELECT 'business', id FROM businesses WHERE user_id = $1
+               )"#
+        );
+        sqlx::query(&sql).bind(user_id).execute(&mut *conn).await?;
+    }
+
+    // provider_availability has no ON DELETE CASCADE
+    sqlx::query!(
+        "DELETE FROM provider_availability WHERE provider_id IN (SELECT id FROM providers WHERE user_id = $1)",
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!("DELETE FROM messages WHERE receiver_id = $1", user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    // Reports on their reviews would outlive the reviews themselves
+    sqlx::query!(
+        r#"DELETE FROM content_flags
+           WHERE target_type = 'review'
+             AND target_id IN (SELECT id FROM reviews WHERE reviewer_id = $1)"#,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
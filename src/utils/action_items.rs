@@ -0,0 +1,188 @@
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+// Thresholds for the owner dashboard's to-do list. Tune them here.
+
+/// A booking request left pending this long needs an answer.
+pub const STALE_REQUEST_HOURS: i64 = 12;
+/// A message left unread this long is overdue.
+pub const UNREAD_MESSAGE_HOURS: i64 = 24;
+/// Reviews older than this no longer ask for a reply.
+pub const UNREPLIED_REVIEW_DAYS: i64 = 90;
+/// A dismissed profile reminder comes back after this many days if the
+/// field is still missing.
+pub const PROFILE_REMINDER_DAYS: i64 = 7;
+
+pub const STALE_REQUESTS: &str = "stale_booking_requests";
+pub const UNREAD_MESSAGES: &str = "unread_messages";
+pub const UNREPLIED_REVIEWS: &str = "unreplied_reviews";
+pub const MISSING_PHOTO: &str = "missing_photo";
+pub const MISSING_AVAILABILITY: &str = "missing_availability";
+pub const MISSING_LOCATION: &str = "missing_location";
+
+/// Every item type, as accepted by `/dashboard/dismiss`.
+pub const ITEM_TYPES: [&str; 6] = [
+    STALE_REQUESTS,
+    UNREAD_MESSAGES,
+    UNREPLIED_REVIEWS,
+    MISSING_PHOTO,
+    MISSING_AVAILABILITY,
+    MISSING_LOCATION,
+];
+
+#[derive(Serialize, Debug)]
+pub struct ActionItem {
+    #[serde(rename = "type")]
+    pub item_type: &'static str,
+    pub message: String,
+    pub count: i64,
+    /// Where the app should go to deal with it.
+    pub link: Value,
+    /// Identifies this instance of the item. Dismissing hides it until the
+    /// occurrence changes: a newer overdue request, message or review, or
+    /// the next `PROFILE_REMINDER_DAYS` window for profile fields.
+    pub occurrence: String,
+}
+
+fn plural(count: i64, one: &str, many: &str) -> String {
+    if count == 1 { format!("1 {one}") } else { format!("{count} {many}") }
+}
+
+/// Things the owner of listing `role`/`target_id` should deal with, minus
+/// the ones they dismissed.
+pub async fn for_owner(
+    pool: &PgPool,
+    user_id: i32,
+    role: &str,
+    target_id: i32,
+    now: NaiveDateTime,
+) -> Result<Vec<ActionItem>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT
+               (SELECT COUNT(*) FROM bookings
+                WHERE target_type = $2 AND target_id = $3 AND status = 'pending' AND created_at < $4) AS "stale_requests!",
+               (SELECT MAX(created_at) FROM bookings
+                WHERE target_type = $2 AND target_id = $3 AND status = 'pending' AND created_at < $4) AS stale_requests_latest,
+               (SELECT COUNT(*) FROM messages WHERE receiver_id = $1 AND is_read = FALSE AND created_at < $5) AS "unread_messages!",
+               (SELECT MAX(created_at) FROM messages WHERE receiver_id = $1 AND is_read = FALSE AND created_at < $5) AS unread_messages_latest,
+               (SELECT COUNT(*) FROM reviews r
+                WHERE r.target_type = $2 AND r.target_id = $3 AND r.hidden = FALSE AND r.created_at >= $6
+                  AND NOT EXISTS (SELECT 1 FROM review_replies rr WHERE rr.review_id = r.id AND rr.reviewer_id = $1)) AS "unreplied_reviews!",
+               (SELECT MAX(r.created_at) FROM reviews r
+                WHERE r.target_type = $2 AND r.target_id = $3 AND r.hidden = FALSE AND r.created_at >= $6
+                  AND NOT EXISTS (SELECT 1 FROM review_replies rr WHERE rr.review_id = r.id AND rr.reviewer_id = $1)) AS unreplied_reviews_latest,
+               CASE $2
+                   WHEN 'provider' THEN (SELECT profile_photo IS NULL FROM providers WHERE id = $3)
+                   ELSE (SELECT logo IS NULL AND profile_photo IS NULL FROM businesses WHERE id = $3)
+               END AS "missing_photo!",
+               CASE $2
+                   WHEN 'provider' THEN NOT EXISTS (
+                       SELECT 1 FROM provider_availability WHERE provider_id = $3 AND is_available)
+                   ELSE FALSE
+               END AS "missing_availability!",
+               CASE $2
+                   WHEN 'provider' THEN NOT EXISTS (SELECT 1 FROM provider_locations WHERE provider_id = $3)
+                   ELSE NOT EXISTS (SELECT 1 FROM business_branches WHERE business_id = $3 AND is_active)
+               END AS "missing_location!""#,
+        user_id,
+        role,
+        target_id,
+        now - Duration::hours(STALE_REQUEST_HOURS),
+        now - Duration::hours(UNREAD_MESSAGE_HOURS),
+        now - Duration::days(UNREPLIED_REVIEW_DAYS)
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let latest = |at: Option<NaiveDateTime>| at.map(|at| at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string());
+    let window = now.and_utc().timestamp() / (PROFILE_REMINDER_DAYS * 86_400);
+    let profile_occurrence = format!("every-{PROFILE_REMINDER_DAYS}d-{window}");
+    let profile_link = json!({ "screen": "profile", "target_type": role, "target_id": target_id });
+
+    let mut items = Vec::new();
+    if let (count @ 1.., Some(occurrence)) = (row.stale_requests, latest(row.stale_requests_latest)) {
+        items.push(ActionItem {
+            item_type: STALE_REQUESTS,
+            message: format!(
+                "{} waiting over {STALE_REQUEST_HOURS} hours for an answer",
+                plural(count, "booking request", "booking requests")
+            ),
+            count,
+            link: json!({ "screen": "bookings", "status": "pending", "target_type": role, "target_id": target_id }),
+            occurrence,
+        });
+    }
+    if let (count @ 1.., Some(occurrence)) = (row.unread_messages, latest(row.unread_messages_latest)) {
+        items.push(ActionItem {
+            item_type: UNREAD_MESSAGES,
+            message: format!("{} unread for over a day", plural(count, "message", "messages")),
+            count,
+            link: json!({ "screen": "messages" }),
+            occurrence,
+        });
+    }
+    if let (count @ 1.., Some(occurrence)) = (row.unreplied_reviews, latest(row.unreplied_reviews_latest)) {
+        items.push(ActionItem {
+            item_type: UNREPLIED_REVIEWS,
+            message: format!("{} without a reply", plural(count, "review", "reviews")),
+            count,
+            link: json!({ "screen": "reviews", "target_type": role, "target_id": target_id }),
+            occurrence,
+        });
+    }
+    for (missing, item_type, message) in [
+        (row.missing_photo, MISSING_PHOTO, "Add a profile photo so clients recognise you"),
+        (row.missing_availability, MISSING_AVAILABILITY, "Set your availability so clients can book you"),
+        (row.missing_location, MISSING_LOCATION, "Add your location so nearby clients can find you"),
+    ] {
+        if missing {
+            items.push(ActionItem {
+                item_type,
+                message: message.to_string(),
+                count: 1,
+                link: profile_link.clone(),
+                occurrence: profile_occurrence.clone(),
+            });
+        }
+    }
+
+    let dismissed = sqlx::query!(
+        "SELECT item_type, occurrence FROM dashboard_dismissals WHERE user_id = $1",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+    items.retain(|item| {
+        !dismissed
+            .iter()
+            .any(|d| d.item_type == item.item_type && d.occurrence == item.occurrence)
+    });
+    Ok(items)
+}
+
+/// Hides one occurrence of an item for the user. Earlier dismissals of the
+/// same type are dropped, since only the latest occurrence can match again.
+pub async fn dismiss(pool: &PgPool, user_id: i32, item_type: &str, occurrence: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        "DELETE FROM dashboard_dismissals WHERE user_id = $1 AND item_type = $2 AND occurrence <> $3",
+        user_id,
+        item_type,
+        occurrence
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO dashboard_dismissals (user_id, item_type, occurrence)
+           VALUES ($1, $2, $3)
+           ON CONFLICT DO NOTHING"#,
+        user_id,
+        item_type,
+        occurrence
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
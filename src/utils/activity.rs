@@ -0,0 +1,83 @@
+use crate::errors::error_body;
+use crate::extractors::current_user::bearer_token;
+use crate::utils::audit::record_audit;
+use crate::utils::jwt::decode_jwt;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use sqlx::PgPool;
+
+/// Records `users.last_seen_at` for any request carrying a valid Bearer token.
+/// The write happens off the request path and is throttled to once every
+/// 5 minutes per user by the WHERE clause. Invalid tokens are ignored here —
+/// rejecting them is the CurrentUser extractor's job.
+///
+/// Requests made with an impersonation token don't count as activity by the
+/// user. They are written to the audit log before they run instead, and are
+/// refused with a 503 if that write fails, so none goes unaudited.
+pub async fn track_last_seen(
+    State(pool): State<PgPool>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let claims = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(bearer_token)
+        .and_then(|token| decode_jwt(token).ok());
+    let user_id = claims.as_ref().and_then(|c| c.sub.parse::<i32>().ok());
+
+    match (user_id, claims.and_then(|c| c.impersonator)) {
+        (Some(user_id), Some(impersonator_id)) => {
+            let details = json!({
+                "method": request.method().as_str(),
+                "path": request.uri().path(),
+            });
+            if let Err(e) = record_audit(
+                &pool,
+                Some(impersonator_id),
+                "impersonated_request",
+                "user",
+                Some(user_id),
+                details,
+            )
+            .await
+            {
+                tracing::error!("Failed to audit impersonated request for user {}: {}", user_id, e);
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    error_body(
+                        "audit_unavailable",
+                        "Impersonated requests can't be recorded right now. Please try again shortly.",
+                        None,
+                    ),
+                )
+                    .into_response();
+            }
+        }
+        (Some(user_id), None) => {
+            tokio::spawn(async move {
+                if let Err(e) = sqlx::query!(
+                    r#"UPDATE users SET last_seen_at = NOW()
+                       WHERE id = $1
+                         AND (last_seen_at IS NULL OR last_seen_at < NOW() - INTERVAL '5 minutes')"#,
+                    user_id
+                )
+                .execute(&pool)
+                .await
+                {
+                    tracing::warn!("Failed to record last_seen_at for user {}: {}", user_id, e);
+                }
+            });
+        }
+        _ => {}
+    }
+
+    next.run(request).await
+}
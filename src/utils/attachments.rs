@@ -1,99 +1,618 @@
-use crate::errors::{AppError, AppResult};
-use crate::extractors::current_user::CurrentUser;
-use crate::utils::storage::{SharedStorage, generate_key};
+use crate::errors::{AppError, AppResult, error_body};
+use crate::extractors::administrator::is_admin;
+use crate::extractors::current_user::{CurrentUser, OptionalCurrentUser};
+use crate::utils::body_limit::upload_route;
+use crate::utils::image_upload::{MediaKind, SniffedMedia, UploadLimits, read_media_field};
+use crate::utils::settings;
+use crate::utils::spool::SpooledFile;
+use crate::utils::storage::{PRIVATE_PREFIX, SharedStorage, generate_key};
+use crate::utils::thumbnails::{ImageUrls, delete_image_variants, store_image_variants, variant_key};
+use crate::utils::upload_sessions;
+use crate::utils::video::{SharedVideoProbe, inspect_video};
 use axum::{
     Extension, Json, Router,
-    extract::{Query, State},
-    http::StatusCode,
-    routing::{get, post},
+    body::Body,
+    extract::{DefaultBodyLimit, Path, Query, State, multipart::Field},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
 };
+use bytes::Bytes;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
-use uuid::Uuid;
+use validator::Validate;
 
 pub fn attachments_routes(pool: PgPool) -> Router {
     Router::new()
-        .route("/uploadAttachments", post(upload_attachments))
+        // Size caps are enforced per file and per request while streaming
+        .route("/uploadAttachments", upload_route(post(upload_attachments), &pool))
+        .route("/", get(get_attachments))
         .route("/getAttachments", get(get_attachments))
+        .route("/usage", get(get_attachment_usage))
+        .route("/:id/download", get(download_attachment))
+        .route("/:id/delete", post(delete_attachment))
+        .route("/:id/metadata", post(update_attachment_metadata))
+        // Resumable uploads for large files on unreliable connections
+        .route("/uploads", post(upload_sessions::create_upload_session))
+        .route(
+            "/uploads/:id",
+            get(upload_sessions::get_upload_session).delete(upload_sessions::cancel_upload_session),
+        )
+        .route(
+            "/uploads/:id/chunks/:n",
+            put(upload_sessions::put_upload_chunk)
+                .layer(DefaultBodyLimit::max(upload_sessions::MAX_CHUNK_BYTES)),
+        )
+        .route("/uploads/:id/complete", post(upload_sessions::complete_upload_session))
         .with_state(pool)
 }
 
+/// Whether `user_id` may manage attachments filed under the given target.
+/// Post attachments are filed under the post's author, so owning the author
+/// profile covers them too.
+pub async fn owns_attachment_target(
+    pool: &PgPool,
+    user_id: i32,
+    target_type: &str,
+    target_id: i32,
+) -> AppResult<bool> {
+    let owned = match target_type {
+        "provider" => sqlx::query_scalar!(
+            "SELECT id FROM providers WHERE id = $1 AND user_id = $2",
+            target_id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .is_some(),
+        "business" => sqlx::query_scalar!(
+            "SELECT id FROM businesses WHERE id = $1 AND user_id = $2",
+            target_id,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .is_some(),
+        _ => false,
+    };
+    Ok(owned)
+}
+
+// ── Visibility ────────────────────────────────────────────────────────────────
+
+/// Who may fetch an attachment. `public` files are served straight from
+/// `/uploads`; the others only through `/attachments/:id/download`.
+const VISIBILITIES: [&str; 3] = ["public", "participants", "admin"];
+
+/// Visibility levels `viewer` may read for a target: everyone sees public
+/// files; clients who booked or messaged the target also see participant
+/// files; the owner and admins see everything.
+async fn readable_visibilities(
+    pool: &PgPool,
+    viewer: Option<i32>,
+    target_type: &str,
+    target_id: i32,
+) -> AppResult<&'static [&'static str]> {
+    let Some(user_id) = viewer else {
+        return Ok(&VISIBILITIES[..1]);
+    };
+
+    if is_admin(pool, user_id).await? || owns_attachment_target(pool, user_id, target_type, target_id).await? {
+        return Ok(&VISIBILITIES);
+    }
+
+    let is_participant = sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+               SELECT 1 FROM bookings
+               WHERE client_id = $1 AND target_type = $2 AND target_id = $3
+           ) OR EXISTS (
+               SELECT 1 FROM messages
+               WHERE (sender_id = $1 OR receiver_id = $1) AND target_type = $2 AND target_id = $3
+           ) AS "participant!""#,
+        user_id,
+        target_type,
+        target_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(if is_participant { &VISIBILITIES[..2] } else { &VISIBILITIES[..1] })
+}
+
+// ── Quotas ────────────────────────────────────────────────────────────────────
+
+/// What a target has stored against its attachment quota.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct AttachmentUsage {
+    pub files: i64,
+    pub max_files: i64,
+    pub bytes: i64,
+    pub max_bytes: i64,
+}
+
+/// Current usage of a target. Post attachments point at files already
+/// uploaded here, so only the uploads themselves are counted.
+pub async fn attachment_usage(
+    pool: &PgPool,
+    target_type: &str,
+    target_id: i32,
+) -> AppResult<AttachmentUsage> {
+    let used = sqlx::query!(
+        r#"SELECT COUNT(*) AS "files!", COALESCE(SUM(size_bytes), 0)::int8 AS "bytes!"
+           FROM attachments
+           WHERE target_type = $1 AND target_id = $2 AND post_id IS NULL"#,
+        target_type,
+        target_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(AttachmentUsage {
+        files: used.files,
+        max_files: settings::int(pool, "attachments.max_files_per_target").await,
+        bytes: used.bytes,
+        max_bytes: settings::int(pool, "attachments.max_bytes_per_target").await,
+    })
+}
+
+/// 413 `quota_exceeded` response carrying the usage that was exceeded.
+pub fn quota_exceeded(message: &str, usage: &AttachmentUsage) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        error_body("quota_exceeded", message, Some(json!({ "usage": usage }))),
+    )
+}
+
+pub async fn get_attachment_usage(
+    State(pool): State<PgPool>,
+    Query(params): Query<AttachmentParams>,
+    CurrentUser { user_id, .. }: CurrentUser,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    if !owns_attachment_target(&pool, user_id, &params.target_type, params.target_id).await? {
+        return Err(AppError::Forbidden(
+            "You do not have permission to view this usage".to_string(),
+        ));
+    }
+    let usage = attachment_usage(&pool, &params.target_type, params.target_id).await?;
+    Ok((StatusCode::OK, Json(json!({ "usage": usage }))))
+}
+
 #[derive(Deserialize, Serialize, sqlx::FromRow)]
 pub struct AttachmentParams {
     pub target_type: String,
     pub target_id: i32,
+    /// `public` (default), `participants` or `admin`.
+    pub visibility: Option<String>,
+}
+
+/// Longest original file name kept for display, in characters.
+const MAX_FILE_NAME_CHARS: usize = 255;
+
+/// The client's file name, kept only for display; the stored file is named
+/// by a UUID and the sniffed extension. Drops any directory part some
+/// browsers send (`C:\fakepath\x.jpg`) and control characters.
+pub fn display_file_name(raw: &str) -> String {
+    let base = raw.rsplit(['/', '\\']).next().unwrap_or(raw);
+    let name: String = base
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FILE_NAME_CHARS)
+        .collect();
+    let name = name.trim();
+    if name.is_empty() { "upload".to_string() } else { name.to_string() }
+}
+
+// ── Captions ──────────────────────────────────────────────────────────────────
+
+/// Caption and alt text for one attachment. Blank values count as unset.
+#[derive(Deserialize, Validate, Debug, Clone, Default, PartialEq)]
+pub struct AttachmentText {
+    #[validate(length(max = 200, message = "caption can be at most 200 characters"))]
+    pub caption: Option<String>,
+    #[validate(length(max = 300, message = "alt_text can be at most 300 characters"))]
+    pub alt_text: Option<String>,
+}
+
+impl AttachmentText {
+    /// Trims both values, drops blank ones and checks the length limits.
+    pub fn normalized(self) -> AppResult<Self> {
+        let clean = |value: Option<String>| {
+            value
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let text = AttachmentText {
+            caption: clean(self.caption),
+            alt_text: clean(self.alt_text),
+        };
+        text.validate()?;
+        Ok(text)
+    }
+}
+
+/// Longest plain form field read from an upload request.
+const MAX_TEXT_FIELD_BYTES: usize = 16 * 1024;
+
+/// Reads a non-file multipart field as text, refusing oversized ones (the
+/// upload body limit leaves room for files, not for megabytes of caption).
+async fn read_text_field(field: &mut Field<'_>) -> AppResult<String> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = field.chunk().await? {
+        buf.extend_from_slice(&chunk);
+        if buf.len() > MAX_TEXT_FIELD_BYTES {
+            return Err(AppError::BadRequest("Form field is too long".to_string()));
+        }
+    }
+    String::from_utf8(buf).map_err(|_| AppError::BadRequest("Form fields must be UTF-8".to_string()))
+}
+
+/// Captions and alt text sent alongside files, in either form: repeated
+/// `caption` / `alt_text` fields matched to files by position, or a
+/// `metadata` part holding a JSON array with one object per file. The JSON
+/// entry wins where both are given.
+#[derive(Default)]
+struct UploadText {
+    captions: Vec<Option<String>>,
+    alt_texts: Vec<Option<String>>,
+    metadata: Vec<AttachmentText>,
+}
+
+impl UploadText {
+    /// Takes in a plain form field; returns false for fields it doesn't know.
+    fn accept(&mut self, name: &str, value: String) -> AppResult<bool> {
+        match name {
+            "caption" => {
+                let text = AttachmentText { caption: Some(value), alt_text: None }.normalized()?;
+                self.captions.push(text.caption);
+            }
+            "alt_text" => {
+                let text = AttachmentText { caption: None, alt_text: Some(value) }.normalized()?;
+                self.alt_texts.push(text.alt_text);
+            }
+            "metadata" => {
+                let entries: Vec<AttachmentText> = serde_json::from_str(&value).map_err(|e| {
+                    AppError::BadRequest(format!("metadata must be a JSON array of {{caption, alt_text}}: {}", e))
+                })?;
+                self.metadata = entries
+                    .into_iter()
+                    .map(AttachmentText::normalized)
+                    .collect::<AppResult<_>>()?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// What the `index`-th file should carry, given the fields seen so far.
+    fn for_file(&self, index: usize) -> AttachmentText {
+        let entry = self.metadata.get(index);
+        AttachmentText {
+            caption: entry
+                .and_then(|e| e.caption.clone())
+                .or_else(|| self.captions.get(index).cloned().flatten()),
+            alt_text: entry
+                .and_then(|e| e.alt_text.clone())
+                .or_else(|| self.alt_texts.get(index).cloned().flatten()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentTextUpdate {
+    /// Omitted fields are left as they are; an empty string clears one.
+    pub caption: Option<String>,
+    pub alt_text: Option<String>,
+}
+
+/// Sets an attachment's caption and alt text. Copies of the file attached to
+/// the owner's posts are updated too, so posts show the same text.
+pub async fn update_attachment_metadata(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+    Json(payload): Json<AttachmentTextUpdate>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let attachment = sqlx::query!(
+        "SELECT file_path, target_type, target_id FROM attachments WHERE id = $1",
+        id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Attachment not found".to_string()))?;
+
+    if !owns_attachment_target(&pool, user_id, &attachment.target_type, attachment.target_id).await? {
+        return Err(AppError::Forbidden(
+            "You do not have permission to edit this attachment".to_string(),
+        ));
+    }
+
+    let caption_given = payload.caption.is_some();
+    let alt_text_given = payload.alt_text.is_some();
+    let text = AttachmentText {
+        caption: payload.caption,
+        alt_text: payload.alt_text,
+    }
+    .normalized()?;
+
+    let updated = sqlx::query!(
+        r#"UPDATE attachments
+           SET caption = CASE WHEN $5 THEN $6 ELSE caption END,
+               alt_text = CASE WHEN $7 THEN $8 ELSE alt_text END
+           WHERE id = $1
+              OR (file_path = $2 AND target_type = $3 AND target_id = $4 AND post_id IS NOT NULL)
+           RETURNING id, caption, alt_text"#,
+        id,
+        attachment.file_path,
+        attachment.target_type,
+        attachment.target_id,
+        caption_given,
+        text.caption,
+        alt_text_given,
+        text.alt_text
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let row = updated
+        .iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| AppError::NotFound("Attachment not found".to_string()))?;
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "message": "Attachment updated",
+            "id": id,
+            "caption": row.caption,
+            "alt_text": row.alt_text,
+        })),
+    ))
+}
+
+/// Checks a requested visibility, defaulting to `public`.
+pub fn parse_visibility(visibility: Option<String>) -> AppResult<String> {
+    let visibility = visibility.unwrap_or_else(|| "public".to_string());
+    if !VISIBILITIES.contains(&visibility.as_str()) {
+        return Err(AppError::BadRequest(
+            "visibility must be 'public', 'participants' or 'admin'".to_string(),
+        ));
+    }
+    Ok(visibility)
+}
+
+/// Where a new attachment is filed and who uploaded it.
+pub struct AttachmentTarget {
+    pub target_type: String,
+    pub target_id: i32,
+    pub visibility: String,
     pub uploaded_by: i32,
 }
 
+/// A received file that has passed type, size and quota checks.
+pub struct IncomingFile {
+    pub file_name: String,
+    pub text: AttachmentText,
+    pub data: SpooledFile,
+    pub media: SniffedMedia,
+}
+
+/// Stores one upload: probes videos, saves the file with its poster or
+/// resized variants, and records the row. Returns the new id and the entry
+/// reported back to the client.
+pub async fn store_attachment(
+    pool: &PgPool,
+    storage: &SharedStorage,
+    video_probe: &SharedVideoProbe,
+    target: &AttachmentTarget,
+    file: IncomingFile,
+) -> AppResult<(i32, serde_json::Value)> {
+    let IncomingFile { file_name, text, data, media } = file;
+    let private = target.visibility != "public";
+    let size_bytes = data.size() as i64;
+    let file_type = media.kind.as_str();
+
+    // Probed before anything is stored so an overlong clip is refused outright
+    let video = match media.kind {
+        MediaKind::Video => {
+            let max_video_seconds = settings::int(pool, "uploads.max_video_seconds").await as f64;
+            Some(inspect_video(video_probe.as_ref(), data.path(), max_video_seconds).await?)
+        }
+        MediaKind::Image => None,
+    };
+    let info = video.as_ref().map(|v| v.info);
+
+    let prefix = if private {
+        format!("{}attachments", PRIVATE_PREFIX)
+    } else {
+        "attachments".to_string()
+    };
+    // Public images are read back to render their variants; nothing else is
+    let image = match media.kind {
+        MediaKind::Image if !private => Some(data.read().await?),
+        _ => None,
+    };
+    let key = generate_key(&prefix, media.extension);
+    let url = storage.save_file(&key, data.path(), media.content_type).await?;
+    drop(data);
+
+    // Posters are public images, so private videos go without one
+    let poster_key = variant_key(&key, "poster");
+    let poster_url = match video.and_then(|v| v.poster).filter(|_| !private) {
+        Some(frame) => match storage.save(&poster_key, &Bytes::from(frame), "image/jpeg").await {
+            Ok(url) => Some(url),
+            Err(e) => {
+                tracing::warn!("Could not store poster for {}: {}", key, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let result = sqlx::query_scalar!(
+        "INSERT INTO attachments (file_name, file_path, file_type, target_type, target_id, uploaded_by, size_bytes, visibility, content_type, \
+                                  duration_seconds, width, height, poster_path, caption, alt_text) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15) RETURNING id",
+        file_name,
+        url,
+        file_type,
+        target.target_type,
+        target.target_id,
+        target.uploaded_by,
+        size_bytes,
+        target.visibility,
+        media.content_type,
+        info.map(|i| i.duration_seconds),
+        info.and_then(|i| i.width),
+        info.and_then(|i| i.height),
+        poster_url,
+        text.caption,
+        text.alt_text
+    )
+    .fetch_one(pool)
+    .await;
+
+    let id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            let _ = storage.delete(&key).await;
+            if poster_url.is_some() {
+                let _ = storage.delete(&poster_key).await;
+            }
+            return Err(AppError::Database(e));
+        }
+    };
+
+    let urls = match image {
+        Some(image) => store_image_variants(pool, storage, &key, &url, image).await,
+        // Private files are only reachable through the download route,
+        // which serves the original
+        None if private => {
+            let download = format!("/attachments/{}/download", id);
+            ImageUrls {
+                url: download.clone(),
+                thumb_url: download.clone(),
+                medium_url: download,
+            }
+        }
+        None => ImageUrls {
+            url: url.clone(),
+            thumb_url: url.clone(),
+            medium_url: url,
+        },
+    };
+    let entry = json!({
+        "id": id,
+        "file_name": file_name,
+        "file_type": file_type,
+        "visibility": target.visibility,
+        "file_path": urls.url,
+        "thumb_url": urls.thumb_url,
+        "medium_url": urls.medium_url,
+        "duration_seconds": info.map(|i| i.duration_seconds),
+        "width": info.and_then(|i| i.width),
+        "height": info.and_then(|i| i.height),
+        "poster_url": poster_url,
+        "caption": text.caption,
+        "alt_text": text.alt_text,
+    });
+    Ok((id, entry))
+}
+
 pub async fn upload_attachments(
     State(pool): State<PgPool>,
     Extension(storage): Extension<SharedStorage>,
+    Extension(video_probe): Extension<SharedVideoProbe>,
     Query(params): Query<AttachmentParams>,
-    CurrentUser { user_id: _ }: CurrentUser,
+    CurrentUser { user_id, .. }: CurrentUser,
     mut multipart: axum::extract::Multipart,
 ) -> Result<(StatusCode, Json<serde_json::Value>), AppError> {
-    let target_type = params.target_type;
-    let target_id = params.target_id;
-    let uploaded_by = params.uploaded_by;
-    let created_at = chrono::Utc::now().naive_utc();
-
-    while let Some(field) = multipart
-        .next_field()
-        .await
-        .map_err(|e| AppError::BadRequest(format!("Multipart error: {}", e)))?
-    {
-        let file_name = field
-            .file_name()
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| Uuid::new_v4().to_string());
-
-        let data = field
-            .bytes()
-            .await
-            .map_err(|e| AppError::BadRequest(format!("Failed to read field: {}", e)))?;
-
-        if data.is_empty() {
-            return Err(AppError::BadRequest("File is empty".to_string()));
-        }
+    let target = AttachmentTarget {
+        visibility: parse_visibility(params.visibility)?,
+        target_type: params.target_type,
+        target_id: params.target_id,
+        uploaded_by: user_id,
+    };
 
-        let extension = file_name
-            .split('.')
-            .last()
-            .map(|ext| ext.to_lowercase())
-            .unwrap_or_else(|| "bin".to_string());
+    // Checked before any field is read so a foreign upload is refused without
+    // consuming the body
+    if !owns_attachment_target(&pool, user_id, &target.target_type, target.target_id).await? {
+        return Err(AppError::Forbidden(
+            "You do not have permission to add attachments here".to_string(),
+        ));
+    }
+    let mut usage = attachment_usage(&pool, &target.target_type, target.target_id).await?;
+    if usage.files >= usage.max_files {
+        return Ok(quota_exceeded("Attachment file limit reached", &usage));
+    }
+
+    let max_files = settings::int(&pool, "attachments.max_files_per_upload").await;
+    let mut limits = UploadLimits::load(&pool, true).await;
+    let mut saved = 0;
+    let mut uploaded: Vec<serde_json::Value> = Vec::new();
+    let mut stored: Vec<(i32, AttachmentText)> = Vec::new();
+    let mut upload_text = UploadText::default();
 
-        let file_type = match extension.as_str() {
-            "jpg" | "jpeg" | "png" | "gif" => "image",
-            "mp4" | "avi" | "mov" => "video",
-            _ => continue,
+    while let Some(mut field) = multipart.next_field().await? {
+        // Plain form fields carry no file name and aren't uploads
+        let Some(file_name) = field.file_name().map(display_file_name) else {
+            let name = field.name().unwrap_or_default().to_string();
+            let value = read_text_field(&mut field).await?;
+            upload_text.accept(&name, value)?;
+            continue;
         };
 
-        let key = generate_key("attachments", &extension);
-        let url = storage.save(&key, &data).await?;
+        saved += 1;
+        if saved > max_files {
+            return Err(AppError::BadRequest(format!(
+                "At most {} files can be uploaded at once",
+                max_files
+            )));
+        }
+        if usage.files >= usage.max_files {
+            return Ok(quota_exceeded("Attachment file limit reached", &usage));
+        }
+
+        let (data, media) = read_media_field(&mut field, &file_name, &mut limits).await?;
+        let size_bytes = data.size() as i64;
+        if usage.bytes + size_bytes > usage.max_bytes {
+            return Ok(quota_exceeded("Attachment storage limit reached", &usage));
+        }
 
-        let result = sqlx::query!(
-            "INSERT INTO attachments (file_name, file_path, file_type, target_type, target_id, uploaded_by, created_at) \
-             VALUES ($1, $2, $3, $4, $5, $6, $7)",
-            file_name,
-            url,
-            file_type,
-            target_type,
-            target_id,
-            uploaded_by,
-            created_at
-        )
-        .execute(&pool)
-        .await;
+        let text = upload_text.for_file(stored.len());
+        let file = IncomingFile { file_name, text: text.clone(), data, media };
+        let (id, entry) = store_attachment(&pool, &storage, &video_probe, &target, file).await?;
+        usage.files += 1;
+        usage.bytes += size_bytes;
+        stored.push((id, text));
+        uploaded.push(entry);
+    }
 
-        if let Err(e) = result {
-            let _ = storage.delete(&key).await;
-            return Err(AppError::Database(e));
+    // Text fields sent after their file are applied once the request is read
+    for (index, (id, text)) in stored.iter().enumerate() {
+        let text_now = upload_text.for_file(index);
+        if &text_now == text {
+            continue;
         }
+        sqlx::query!(
+            "UPDATE attachments SET caption = $2, alt_text = $3 WHERE id = $1",
+            id,
+            text_now.caption,
+            text_now.alt_text
+        )
+        .execute(&pool)
+        .await?;
+        uploaded[index]["caption"] = json!(text_now.caption);
+        uploaded[index]["alt_text"] = json!(text_now.alt_text);
     }
 
-    Ok((StatusCode::OK, Json(json!({ "message": "Files uploaded successfully" }))))
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "message": "Files uploaded successfully",
+            "attachments": uploaded,
+            "usage": usage,
+        })),
+    ))
 }
 
 #[derive(Deserialize, Serialize)]
@@ -107,26 +626,286 @@ pub struct SerializableAttachment {
     pub id: i32,
     pub file_name: String,
     pub file_path: String,
+    pub thumb_url: Option<String>,
+    pub medium_url: Option<String>,
     pub file_type: String,
+    pub visibility: String,
+    pub duration_seconds: Option<f64>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub poster_url: Option<String>,
+    pub caption: Option<String>,
+    pub alt_text: Option<String>,
     pub post_id: Option<i32>,
     pub target_type: String,
     pub target_id: i32,
     pub created_at: Option<NaiveDateTime>,
 }
 
+/// Lists the attachments the caller may see. Non-public files are listed
+/// with their download route in place of a storage URL.
 pub async fn get_attachments(
     State(pool): State<PgPool>,
     Query(params): Query<AttachmentQuery>,
+    viewer: OptionalCurrentUser,
 ) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let readable = readable_visibilities(
+        &pool,
+        viewer.user_id(),
+        &params.target_type,
+        params.target_id,
+    )
+    .await?;
+    let readable: Vec<String> = readable.iter().map(|v| v.to_string()).collect();
+
     let attachments = sqlx::query_as!(
         SerializableAttachment,
-        "SELECT id, file_name, file_path, file_type, post_id, target_type, target_id, created_at \
-         FROM attachments WHERE target_type = $1 AND target_id = $2",
+        r#"SELECT id, file_name,
+                  CASE WHEN visibility = 'public' THEN file_path
+                       ELSE '/attachments/' || id || '/download' END AS "file_path!",
+                  CASE WHEN visibility = 'public' THEN image_variant(file_path, 'thumb')
+                       ELSE '/attachments/' || id || '/download' END AS thumb_url,
+                  CASE WHEN visibility = 'public' THEN image_variant(file_path, 'medium')
+                       ELSE '/attachments/' || id || '/download' END AS medium_url,
+                  file_type, visibility, duration_seconds, width, height,
+                  poster_path AS poster_url, caption, alt_text,
+                  post_id, target_type, target_id, created_at
+           FROM attachments
+           WHERE target_type = $1 AND target_id = $2 AND visibility = ANY($3)"#,
         params.target_type,
-        params.target_id
+        params.target_id,
+        &readable
     )
     .fetch_all(&pool)
     .await?;
 
     Ok((StatusCode::OK, Json(json!({ "attachments": attachments }))))
 }
+
+// ── Download ──────────────────────────────────────────────────────────────────
+
+/// Parses a single-range `Range` header against an object of `total` bytes.
+/// `Ok(None)` means serve the whole file (no header, a multi-range request or
+/// one we don't understand); `Err(())` means the range can't be satisfied.
+fn parse_range(value: &str, total: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return Ok(None);
+    };
+
+    match (start.trim(), end.trim()) {
+        // Suffix range: the last N bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => Err(()),
+            Ok(n) => Ok(Some((total.saturating_sub(n), total - 1))),
+            Err(_) => Ok(None),
+        },
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return Ok(None);
+            };
+            let end = match end {
+                "" => total - 1,
+                end => match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.min(total - 1),
+                    _ => return Ok(None),
+                },
+            };
+            if start >= total { Err(()) } else { Ok(Some((start, end))) }
+        }
+    }
+}
+
+/// `Content-Disposition` value that survives any file name: an ASCII fallback
+/// plus the exact name percent-encoded per RFC 6266.
+fn content_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' { c } else { '_' })
+        .collect();
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect();
+    format!("inline; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+/// Streams an attachment after checking the caller may see it. Supports
+/// single byte ranges so videos can be seeked.
+pub async fn download_attachment(
+    State(pool): State<PgPool>,
+    Extension(storage): Extension<SharedStorage>,
+    Path(id): Path<i32>,
+    viewer: OptionalCurrentUser,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let attachment = sqlx::query!(
+        r#"SELECT file_name, file_path, content_type, visibility, target_type, target_id
+           FROM attachments WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Attachment not found".to_string()))?;
+
+    if attachment.visibility != "public" {
+        let Some(user_id) = viewer.user_id() else {
+            return Err(AppError::Unauthorized(
+                "Sign in to download this attachment".to_string(),
+            ));
+        };
+        let readable = readable_visibilities(
+            &pool,
+            Some(user_id),
+            &attachment.target_type,
+            attachment.target_id,
+        )
+        .await?;
+        if !readable.contains(&attachment.visibility.as_str()) {
+            return Err(AppError::Forbidden(
+                "You do not have permission to download this attachment".to_string(),
+            ));
+        }
+    }
+
+    let not_found = || AppError::NotFound("Attachment file not found".to_string());
+    let key = storage.key_for_url(&attachment.file_path).ok_or_else(not_found)?;
+    let total = storage.size(&key).await?.filter(|&len| len > 0).ok_or_else(not_found)?;
+
+    let range = match headers.get(header::RANGE).and_then(|v| v.to_str().ok()) {
+        Some(value) => parse_range(value, total),
+        None => Ok(None),
+    };
+    let range = match range {
+        Ok(range) => range,
+        Err(()) => {
+            return Ok((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{}", total))],
+                Body::empty(),
+            )
+                .into_response());
+        }
+    };
+
+    let (start, end) = range.unwrap_or((0, total - 1));
+    let body = storage.read_range(&key, start, end).await?;
+
+    let mut response = (
+        [
+            (
+                header::CONTENT_TYPE,
+                attachment
+                    .content_type
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+            ),
+            (header::CONTENT_DISPOSITION, content_disposition(&attachment.file_name)),
+            (header::CONTENT_LENGTH, (end - start + 1).to_string()),
+            (header::ACCEPT_RANGES, "bytes".to_string()),
+        ],
+        body,
+    )
+        .into_response();
+    if attachment.visibility != "public" {
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, header::HeaderValue::from_static("private, no-store"));
+    }
+    if range.is_some() {
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+        response.headers_mut().insert(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, total)
+                .parse()
+                .map_err(|_| AppError::Internal("Invalid Content-Range".to_string()))?,
+        );
+    }
+    Ok(response)
+}
+
+pub async fn delete_attachment(
+    State(pool): State<PgPool>,
+    Extension(storage): Extension<SharedStorage>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let attachment = sqlx::query!(
+        "SELECT file_path, poster_path, target_type, target_id, uploaded_by FROM attachments WHERE id = $1",
+        id
+    )
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Attachment not found".to_string()))?;
+
+    // Whoever uploaded a file can take it down, as can the target's owner
+    if attachment.uploaded_by != user_id
+        && !owns_attachment_target(&pool, user_id, &attachment.target_type, attachment.target_id).await?
+    {
+        return Err(AppError::Forbidden(
+            "You do not have permission to delete this attachment".to_string(),
+        ));
+    }
+
+    // Post attachments can point at a file another row still uses
+    let still_referenced = sqlx::query_scalar!(
+        r#"WITH deleted AS (DELETE FROM attachments WHERE id = $1 RETURNING file_path)
+           SELECT EXISTS (
+               SELECT 1 FROM attachments a, deleted d
+               WHERE a.file_path = d.file_path AND a.id <> $1
+           ) AS "exists!""#,
+        id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    if !still_referenced {
+        delete_image_variants(&pool, &storage, &attachment.file_path).await;
+        match storage.key_for_url(&attachment.file_path) {
+            Some(key) => {
+                if let Err(e) = storage.delete(&key).await {
+                    tracing::warn!("Failed to delete file for attachment {}: {}", id, e);
+                }
+            }
+            None => tracing::warn!(
+                "Attachment {} points outside storage ({}); row deleted, file left alone",
+                id,
+                attachment.file_path
+            ),
+        }
+    }
+
+    if let Some(key) = attachment.poster_path.as_deref().and_then(|url| storage.key_for_url(url)) {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!("Failed to delete poster for attachment {}: {}", id, e);
+        }
+    }
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Attachment deleted" }))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_names_keep_what_the_client_sent_minus_paths_and_control_characters() {
+        assert_eq!(display_file_name("my photo (1).jpg"), "my photo (1).jpg");
+        assert_eq!(display_file_name("C:\\fakepath\\my photo (1).jpg"), "my photo (1).jpg");
+        assert_eq!(display_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(display_file_name("bad\r\nname\0.png"), "badname.png");
+        assert_eq!(display_file_name("  "), "upload");
+        assert_eq!(display_file_name("dir/"), "upload");
+        assert_eq!(display_file_name(&"a".repeat(300)).chars().count(), MAX_FILE_NAME_CHARS);
+    }
+}
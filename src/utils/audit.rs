@@ -0,0 +1,25 @@
+use sqlx::PgExecutor;
+
+/// Appends a row to `audit_log`. Pass the transaction that makes the change
+/// being recorded so the entry commits or rolls back with it.
+pub async fn record_audit<'e>(
+    executor: impl PgExecutor<'e>,
+    actor_id: Option<i32>,
+    action: &str,
+    target_type: &str,
+    target_id: Option<i32>,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO audit_log (actor_id, action, target_type, target_id, details)
+           VALUES ($1, $2, $3, $4, $5)"#,
+        actor_id,
+        action,
+        target_type,
+        target_id,
+        details
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
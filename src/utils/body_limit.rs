@@ -0,0 +1,63 @@
+use crate::errors::AppError;
+use crate::utils::settings;
+use axum::{
+    body::Body,
+    extract::{DefaultBodyLimit, State},
+    http::{Request, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use http_body_util::Limited;
+use sqlx::PgPool;
+
+/// Room for multipart framing and small text fields (captions, alt text) on
+/// top of the file bytes themselves.
+const MULTIPART_OVERHEAD_BYTES: usize = 1024 * 1024;
+
+/// Gives a multipart upload route the upload body limit in place of the
+/// global `MAX_BODY_BYTES` one.
+pub fn upload_route(route: MethodRouter<PgPool>, pool: &PgPool) -> MethodRouter<PgPool> {
+    route
+        .layer(DefaultBodyLimit::disable())
+        .layer(middleware::from_fn_with_state(pool.clone(), limit_upload_body))
+}
+
+/// Caps upload bodies at the `uploads.max_request_bytes` setting (the same
+/// total the upload handlers enforce across files) plus framing. A request
+/// that declares a larger `Content-Length` is refused before any of it is
+/// read; one that doesn't is cut off once it passes the cap.
+async fn limit_upload_body(State(pool): State<PgPool>, request: Request<Body>, next: Next) -> Response {
+    let max_request_bytes = settings::int(&pool, "uploads.max_request_bytes").await as usize;
+    let limit = max_request_bytes.saturating_add(MULTIPART_OVERHEAD_BYTES);
+
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > limit) {
+        return AppError::PayloadTooLarge(format!(
+            "Uploads are limited to {} bytes per request",
+            max_request_bytes
+        ))
+        .into_response();
+    }
+
+    next.run(request.map(|body| Body::new(Limited::new(body, limit)))).await
+}
+
+/// Axum's own rejections for an oversized body (from `Json`, `Bytes` and the
+/// like) are plain text; this turns them into the usual JSON error.
+pub async fn json_payload_too_large(request: Request<Body>, next: Next) -> Response {
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return AppError::PayloadTooLarge("Request body is too large".to_string()).into_response();
+    }
+    response
+}
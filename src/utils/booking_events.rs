@@ -0,0 +1,27 @@
+use sqlx::PgExecutor;
+
+/// Appends an entry to a booking's timeline. `actor_id` is `None` for system
+/// changes such as the payment callback.
+pub async fn record_booking_event<'e>(
+    executor: impl PgExecutor<'e>,
+    booking_id: i32,
+    actor_id: Option<i32>,
+    event_type: &str,
+    from_status: Option<&str>,
+    to_status: Option<&str>,
+    note: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO booking_events (booking_id, actor_id, event_type, from_status, to_status, note)
+           VALUES ($1, $2, $3, $4, $5, $6)"#,
+        booking_id,
+        actor_id,
+        event_type,
+        from_status,
+        to_status,
+        note
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::utils::booking_events::record_booking_event;
+use crate::utils::jobs::{self, BOOKING_EXPIRY, JobReport};
+use crate::utils::notifications::{notify_and_push, notify_target_owner_and_push};
+use crate::utils::webhooks;
+use crate::utils::ws_state::WsConnections;
+use chrono::Utc;
+use sqlx::PgPool;
+
+const EXPIRY_REASON: &str = "Expired: not confirmed before the scheduled time";
+
+/// Spawns a background loop that cancels bookings still pending once their
+/// scheduled time has passed, every 10 minutes, and tells both sides.
+pub fn start_booking_expiry_task(pool: PgPool, ws_conns: WsConnections, mut trigger: jobs::JobTrigger) {
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&BOOKING_EXPIRY, &mut trigger).await {
+            jobs::record_run(&pool, &BOOKING_EXPIRY, expire_pending_bookings(&pool, &ws_conns)).await;
+        }
+    });
+}
+
+pub async fn expire_pending_bookings(pool: &PgPool, ws_conns: &WsConnections) -> JobReport {
+    let mut report = JobReport::default();
+    let now = Utc::now().naive_utc();
+
+    // The status change and its timeline entries land together
+    let expired = async {
+        let mut tx = pool.begin().await?;
+        let rows = sqlx::query!(
+            r#"UPDATE bookings
+               SET status = 'cancelled', cancel_reason = $1, updated_at = NOW()
+               WHERE status = 'pending' AND scheduled_time < $2
+               RETURNING id, client_id, target_type, target_id"#,
+            EXPIRY_REASON,
+            now
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for row in &rows {
+            record_booking_event(
+                &mut *tx, row.id, None, "status_changed",
+                Some("pending"), Some("cancelled"), Some(EXPIRY_REASON),
+            ).await?;
+            webhooks::enqueue_booking_event(&mut *tx, webhooks::BOOKING_STATUS_CHANGED, row.id, Some("pending")).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(rows)
+    }
+    .await;
+
+    let expired = match expired {
+        Ok(rows) => rows,
+        Err(e) => {
+            report.fail("Booking expiry failed", e);
+            return report;
+        }
+    };
+
+    if expired.is_empty() {
+        return report;
+    }
+    tracing::info!("Expired {} pending bookings", expired.len());
+
+    for booking in &expired {
+        notify_and_push(
+            pool, ws_conns, booking.client_id, "booking_expired",
+            "Booking Expired",
+            &format!("Your booking #{} was not confirmed in time and has been cancelled", booking.id),
+            Some("booking"), Some(booking.id),
+        ).await;
+        notify_target_owner_and_push(
+            pool, ws_conns, &booking.target_type, booking.target_id, "booking_expired",
+            "Booking Expired",
+            &format!("Booking #{} was cancelled because it wasn't confirmed before its scheduled time", booking.id),
+            Some("booking"), Some(booking.id),
+        ).await;
+        report.items_processed += 1;
+    }
+
+    report
+}
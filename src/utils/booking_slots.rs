@@ -0,0 +1,116 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use sqlx::PgConnection;
+
+/// Most start times one day can be split into.
+const MAX_SLOTS_PER_DAY: usize = 288;
+
+/// Weekday as stored in `provider_availability.day`.
+pub fn weekday_name(w: Weekday) -> &'static str {
+    match w {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// When a provider takes bookings on a given day.
+pub enum Hours {
+    /// No schedule set at all: any time is accepted.
+    Unrestricted,
+    /// A schedule exists but this weekday is missing or marked unavailable.
+    Closed,
+    Open { start: NaiveTime, end: NaiveTime },
+}
+
+/// A live booking's window, from its start to start + `duration`.
+pub struct Booked {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl Booked {
+    fn overlaps(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        self.start < end && self.end > start
+    }
+}
+
+/// The provider's hours for the weekday of `date`.
+pub async fn provider_hours(conn: &mut PgConnection, provider_id: i32, date: NaiveDate) -> Result<Hours, sqlx::Error> {
+    let has_schedule = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM provider_availability WHERE provider_id = $1) AS "exists!""#,
+        provider_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if !has_schedule {
+        return Ok(Hours::Unrestricted);
+    }
+
+    let hours = sqlx::query!(
+        "SELECT start_time, end_time FROM provider_availability
+         WHERE provider_id = $1 AND day = $2 AND is_available IS NOT FALSE",
+        provider_id,
+        weekday_name(date.weekday())
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(match hours {
+        Some(row) => Hours::Open { start: row.start_time, end: row.end_time },
+        None => Hours::Closed,
+    })
+}
+
+/// The provider's bookings, other than cancelled or rejected ones, that
+/// overlap `[from, to)`, earliest first. Bookings without a duration count
+/// as an hour.
+pub async fn booked_between(
+    conn: &mut PgConnection,
+    provider_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<Booked>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT scheduled_time, COALESCE(duration, 60) AS "duration!" FROM bookings
+           WHERE target_type = 'provider' AND target_id = $1 AND status NOT IN ('cancelled', 'rejected')
+             AND scheduled_time < $3
+             AND scheduled_time + make_interval(mins => COALESCE(duration, 60)) > $2
+           ORDER BY scheduled_time"#,
+        provider_id,
+        from,
+        to
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| Booked { start: r.scheduled_time, end: r.scheduled_time + Duration::minutes(i64::from(r.duration)) })
+        .collect())
+}
+
+/// Start times between `open` and `close`, `step` apart, where a booking of
+/// `length` fits before `close`, clashes with nothing in `booked` and starts
+/// no earlier than `not_before`.
+pub fn free_starts(
+    open: NaiveDateTime,
+    close: NaiveDateTime,
+    step: Duration,
+    length: Duration,
+    booked: &[Booked],
+    not_before: NaiveDateTime,
+) -> Vec<NaiveDateTime> {
+    let mut starts = Vec::new();
+    let mut cursor = open;
+    while cursor + length <= close && starts.len() < MAX_SLOTS_PER_DAY {
+        let end = cursor + length;
+        if cursor >= not_before && !booked.iter().any(|b| b.overlaps(cursor, end)) {
+            starts.push(cursor);
+        }
+        cursor += step;
+    }
+    starts
+}
@@ -0,0 +1,69 @@
+/// Every status a booking can be in, as allowed by the table's CHECK.
+pub const BOOKING_STATUSES: [&str; 8] = [
+    "pending", "confirmed", "rejected", "cancelled", "pending_confirmation", "completed", "disputed", "no_show",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BookingStatus {
+    Pending,
+    Confirmed,
+    /// Declined by the provider or business before confirming.
+    Rejected,
+    Cancelled,
+    /// The provider or business says the job is done; awaiting the client.
+    PendingConfirmation,
+    Completed,
+    Disputed,
+    /// The client didn't turn up to a confirmed booking.
+    NoShow,
+}
+
+/// Which party is changing a booking through `POST /bookings/:id/status`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Party {
+    /// The provider or business the booking was made with.
+    Owner,
+    Client,
+}
+
+impl BookingStatus {
+    pub fn parse(status: &str) -> Option<BookingStatus> {
+        Some(match status {
+            "pending" => BookingStatus::Pending,
+            "confirmed" => BookingStatus::Confirmed,
+            "rejected" => BookingStatus::Rejected,
+            "cancelled" => BookingStatus::Cancelled,
+            "pending_confirmation" => BookingStatus::PendingConfirmation,
+            "completed" => BookingStatus::Completed,
+            "disputed" => BookingStatus::Disputed,
+            "no_show" => BookingStatus::NoShow,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BookingStatus::Pending => "pending",
+            BookingStatus::Confirmed => "confirmed",
+            BookingStatus::Rejected => "rejected",
+            BookingStatus::Cancelled => "cancelled",
+            BookingStatus::PendingConfirmation => "pending_confirmation",
+            BookingStatus::Completed => "completed",
+            BookingStatus::Disputed => "disputed",
+            BookingStatus::NoShow => "no_show",
+        }
+    }
+
+    /// Where `party` may move a booking from this status. Anything else,
+    /// including a move out of a final status, is refused. Disputes are
+    /// settled by admins, who can also override any status.
+    pub fn next_for(self, party: Party) -> &'static [BookingStatus] {
+        use BookingStatus::*;
+        match (party, self) {
+            (Party::Owner, Pending) => &[Confirmed, Rejected, Cancelled],
+            (Party::Owner, Confirmed) => &[PendingConfirmation, Cancelled, NoShow],
+            (Party::Client, PendingConfirmation) => &[Completed, Disputed],
+            _ => &[],
+        }
+    }
+}
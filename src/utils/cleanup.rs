@@ -0,0 +1,179 @@
+use crate::utils::account_deletion::{purge_user, retention_days};
+use crate::utils::audit::record_audit;
+use crate::utils::jobs::{self, CLEANUP, JobReport};
+use crate::utils::storage::SharedStorage;
+use crate::utils::upload_sessions::{SESSION_TTL_HOURS, chunk_key};
+use serde_json::json;
+use sqlx::PgPool;
+
+/// How long background job run history is kept.
+const JOB_RUN_RETENTION_DAYS: i32 = 30;
+
+/// Attachments sized per run while older rows are backfilled.
+const SIZE_BACKFILL_BATCH: i64 = 500;
+
+/// Spawns a background loop that prunes short-lived data once an hour.
+/// Tables with an admin-set retention period are left to `retention`.
+pub fn start_cleanup_task(pool: PgPool, storage: SharedStorage, mut trigger: jobs::JobTrigger) {
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&CLEANUP, &mut trigger).await {
+            jobs::record_run(&pool, &CLEANUP, run_cleanup(&pool, &storage)).await;
+        }
+    });
+}
+
+pub async fn run_cleanup(pool: &PgPool, storage: &SharedStorage) -> JobReport {
+    let mut report = JobReport::default();
+
+    // Fan-out dedup rows only matter for the day they were written
+    match sqlx::query!("DELETE FROM post_fanout_log WHERE sent_on < CURRENT_DATE - 1")
+        .execute(pool)
+        .await
+    {
+        Ok(res) => report.items_processed += res.rows_affected() as i64,
+        Err(e) => report.fail("Cleanup of post fan-out log failed", e),
+    }
+
+    match sqlx::query!(
+        "DELETE FROM job_runs WHERE started_at < NOW() - make_interval(days => $1)",
+        JOB_RUN_RETENTION_DAYS
+    )
+    .execute(pool)
+    .await
+    {
+        Ok(res) => report.items_processed += res.rows_affected() as i64,
+        Err(e) => report.fail("Cleanup of job run history failed", e),
+    }
+
+    purge_deleted_users(pool, &mut report).await;
+    backfill_attachment_sizes(pool, storage, &mut report).await;
+    purge_stale_upload_sessions(pool, storage, &mut report).await;
+
+    report
+}
+
+/// Records the size of uploads made before sizes were tracked, so they count
+/// towards quotas and storage totals. Files no longer in storage count as 0.
+async fn backfill_attachment_sizes(pool: &PgPool, storage: &SharedStorage, report: &mut JobReport) {
+    let unsized_rows = match sqlx::query!(
+        r#"SELECT id, file_path FROM attachments
+           WHERE size_bytes IS NULL AND post_id IS NULL
+           ORDER BY id
+           LIMIT $1"#,
+        SIZE_BACKFILL_BATCH
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            report.fail("Cleanup: failed to list attachments without a size", e);
+            return;
+        }
+    };
+
+    for row in unsized_rows {
+        // URLs this backend didn't issue take up none of our storage
+        let size = match storage.key_for_url(&row.file_path) {
+            Some(key) => match storage.size(&key).await {
+                Ok(size) => size.unwrap_or(0),
+                Err(e) => {
+                    report.fail(&format!("Cleanup: failed to size attachment {}", row.id), e);
+                    continue;
+                }
+            },
+            None => 0,
+        };
+
+        match sqlx::query!(
+            "UPDATE attachments SET size_bytes = $1 WHERE id = $2",
+            size as i64,
+            row.id
+        )
+        .execute(pool)
+        .await
+        {
+            Ok(_) => report.items_processed += 1,
+            Err(e) => report.fail(&format!("Cleanup: failed to record size of attachment {}", row.id), e),
+        }
+    }
+}
+
+/// Drops resumable uploads nobody has touched for a day, with their chunks.
+async fn purge_stale_upload_sessions(pool: &PgPool, storage: &SharedStorage, report: &mut JobReport) {
+    // The select sees the chunk rows as they were before the cascade
+    let chunks = match sqlx::query!(
+        r#"WITH stale AS (
+               DELETE FROM upload_sessions
+               WHERE updated_at < NOW() - make_interval(hours => $1)
+               RETURNING id
+           )
+           SELECT s.id AS "session_id!", c.chunk_number AS "chunk_number?"
+           FROM stale s LEFT JOIN upload_chunks c ON c.session_id = s.id
+           ORDER BY s.id"#,
+        SESSION_TTL_HOURS
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            report.fail("Cleanup of stale upload sessions failed", e);
+            return;
+        }
+    };
+
+    let mut sessions: Vec<i32> = chunks.iter().map(|c| c.session_id).collect();
+    sessions.dedup();
+    for chunk in &chunks {
+        let Some(n) = chunk.chunk_number else {
+            continue;
+        };
+        if let Err(e) = storage.delete(&chunk_key(chunk.session_id, n)).await {
+            report.fail(&format!("Cleanup: failed to delete chunk {} of upload {}", n, chunk.session_id), e);
+        }
+    }
+    if !sessions.is_empty() {
+        tracing::info!("Cleanup: purged {} stale upload sessions", sessions.len());
+        report.items_processed += sessions.len() as i64;
+    }
+}
+
+/// Hard-deletes accounts whose soft-delete retention window has run out.
+async fn purge_deleted_users(pool: &PgPool, report: &mut JobReport) {
+    let retention = retention_days(pool).await;
+    let due = match sqlx::query_scalar!(
+        r#"SELECT id FROM users
+           WHERE deleted_at IS NOT NULL
+             AND deleted_at < NOW() - make_interval(days => $1)"#,
+        retention
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            report.fail("Cleanup: failed to list users due for purge", e);
+            return;
+        }
+    };
+
+    for user_id in due {
+        let result = async {
+            let mut tx = pool.begin().await?;
+            purge_user(&mut tx, user_id).await?;
+            record_audit(&mut *tx, None, "user_purged", "user", Some(user_id), json!({ "retention_days": retention }))
+                .await?;
+            tx.commit().await
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                tracing::info!("Cleanup: purged deleted user {}", user_id);
+                report.items_processed += 1;
+            }
+            Err(e) => report.fail(&format!("Cleanup: failed to purge user {}", user_id), e),
+        }
+    }
+}
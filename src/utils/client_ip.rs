@@ -0,0 +1,111 @@
+use axum::http::HeaderMap;
+use std::net::IpAddr;
+
+/// A proxy address or CIDR range from `TRUSTED_PROXIES`, e.g. `10.0.0.0/8`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl TrustedProxy {
+    /// Parses a bare address or `address/prefix`.
+    pub fn parse(raw: &str) -> Option<TrustedProxy> {
+        let (addr, prefix) = match raw.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (raw, None),
+        };
+        let network: IpAddr = addr.trim().parse().ok()?;
+        let bits = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse::<u8>().ok().filter(|p| *p <= bits)?,
+            None => bits,
+        };
+        Some(TrustedProxy { network, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let (network, ip, bits) = match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => (u128::from(u32::from(net)), u128::from(u32::from(ip)), 32),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => (u128::from(net), u128::from(ip), 128),
+            _ => return false,
+        };
+        (network ^ ip).checked_shr(bits - u32::from(self.prefix)).unwrap_or(0) == 0
+    }
+}
+
+/// The address a request came from. `X-Forwarded-For` is only believed when
+/// the peer is a trusted proxy, and is read from the right, past any other
+/// trusted hops, so a client can't choose its address by sending the header.
+pub fn client_ip(headers: &HeaderMap, peer: IpAddr, trusted: &[TrustedProxy]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|proxy| proxy.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .collect();
+    let mut client = peer;
+    for hop in hops.into_iter().rev() {
+        let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().expect("test address")
+    }
+
+    fn forwarded(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", value.parse().expect("header value"));
+        headers
+    }
+
+    #[test]
+    fn ranges_match_on_their_prefix() {
+        let range = TrustedProxy::parse("10.1.0.0/16").expect("range");
+        assert!(range.contains(ip("10.1.200.3")));
+        assert!(!range.contains(ip("10.2.0.1")));
+        assert!(range.contains(ip("::ffff:10.1.0.9")));
+
+        let single = TrustedProxy::parse("2001:db8::1").expect("address");
+        assert!(single.contains(ip("2001:db8::1")));
+        assert!(!single.contains(ip("2001:db8::2")));
+        assert!(TrustedProxy::parse("::/0").expect("everything").contains(ip("2001:db8::2")));
+
+        for bad in ["", "10.0.0.0/33", "10.0.0/8", "proxy.internal"] {
+            assert_eq!(TrustedProxy::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn forwarded_addresses_are_only_taken_from_trusted_proxies() {
+        let trusted = [TrustedProxy::parse("10.0.0.0/8").expect("range")];
+        let spoofed = forwarded("1.2.3.4");
+
+        assert_eq!(client_ip(&spoofed, ip("203.0.113.7"), &trusted), ip("203.0.113.7"));
+        assert_eq!(client_ip(&spoofed, ip("10.0.0.2"), &[]), ip("10.0.0.2"));
+        assert_eq!(client_ip(&spoofed, ip("10.0.0.2"), &trusted), ip("1.2.3.4"));
+
+        // The client's own header comes first; the proxy appends what it saw
+        let chained = forwarded("1.2.3.4, 198.51.100.9, 10.0.0.5");
+        assert_eq!(client_ip(&chained, ip("10.0.0.2"), &trusted), ip("198.51.100.9"));
+        assert_eq!(client_ip(&forwarded("junk, 10.0.0.5"), ip("10.0.0.2"), &trusted), ip("10.0.0.5"));
+        assert_eq!(client_ip(&HeaderMap::new(), ip("10.0.0.2"), &trusted), ip("10.0.0.2"));
+    }
+}
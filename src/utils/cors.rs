@@ -0,0 +1,206 @@
+use crate::config::Config;
+use crate::utils::deprecation::DEPRECATION_HEADER;
+use crate::utils::request_log::REQUEST_ID_HEADER;
+use crate::utils::upload_sessions::CHUNK_CHECKSUM_HEADER;
+use axum::http::{HeaderName, HeaderValue, Method, header};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// How long browsers may cache a preflight response.
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// One entry of `ALLOWED_ORIGINS`: either an exact origin such as
+/// `https://mtaalink.com`, or `https://*.mtaalink.com`, which matches any
+/// subdomain (but not the bare domain) on the same scheme and port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OriginPattern {
+    Exact(Origin),
+    Subdomains(Origin),
+}
+
+/// A normalised origin: lowercase, with the port filled in from the scheme
+/// when it isn't written out, so `https://a.com` and `https://a.com:443` agree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    scheme: String,
+    host: String,
+    port: u16,
+}
+
+impl Origin {
+    /// Parses `scheme://host[:port]`. A trailing slash is tolerated; any other
+    /// path, query or userinfo is not, and neither is a scheme besides http(s).
+    pub fn parse(raw: &str) -> Option<Origin> {
+        let raw = raw.trim().trim_end_matches('/').to_ascii_lowercase();
+        let (scheme, rest) = raw.split_once("://")?;
+        let default_port = match scheme {
+            "http" => 80,
+            "https" => 443,
+            _ => return None,
+        };
+        if rest.is_empty() || rest.contains(['/', '?', '#', '@']) {
+            return None;
+        }
+        let (host, port) = match rest.rsplit_once(':') {
+            // An IPv6 literal like `[::1]` without a port also contains ':'
+            Some((host, port)) if !port.ends_with(']') => (host, port.parse().ok()?),
+            _ => (rest, default_port),
+        };
+        if host.is_empty() {
+            return None;
+        }
+        Some(Origin {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl OriginPattern {
+    /// Parses one `ALLOWED_ORIGINS` entry. `*.` is only accepted as the first
+    /// label of the host, and needs at least a registrable domain after it.
+    pub fn parse(raw: &str) -> Option<OriginPattern> {
+        let origin = Origin::parse(raw)?;
+        match origin.host.strip_prefix("*.") {
+            Some(domain) if domain.contains('.') && !domain.contains('*') => Some(OriginPattern::Subdomains(Origin {
+                host: domain.to_string(),
+                ..origin
+            })),
+            Some(_) => None,
+            None if origin.host.contains('*') => None,
+            None => Some(OriginPattern::Exact(origin)),
+        }
+    }
+
+    fn matches(&self, origin: &Origin) -> bool {
+        match self {
+            OriginPattern::Exact(allowed) => allowed == origin,
+            OriginPattern::Subdomains(domain) => {
+                domain.scheme == origin.scheme
+                    && domain.port == origin.port
+                    && origin
+                        .host
+                        .strip_suffix(domain.host.as_str())
+                        .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.'))
+            }
+        }
+    }
+}
+
+/// True when the request's `Origin` header matches any allowed pattern.
+/// Unparseable origins (including `null`) are never allowed.
+pub fn origin_allowed(patterns: &[OriginPattern], origin: &str) -> bool {
+    Origin::parse(origin).is_some_and(|origin| patterns.iter().any(|p| p.matches(&origin)))
+}
+
+/// Builds the CORS layer from config. In development with no `ALLOWED_ORIGINS`
+/// every origin is reflected back, so any local frontend port works.
+pub fn cors_layer(config: &Config) -> CorsLayer {
+    let allow_origin = if config.cors_permissive {
+        AllowOrigin::mirror_request()
+    } else {
+        let patterns = config.cors_origins.clone();
+        AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            origin.to_str().is_ok_and(|origin| origin_allowed(&patterns, origin))
+        })
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::CONTENT_LENGTH,
+            header::ACCEPT,
+            header::RANGE,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static(CHUNK_CHECKSUM_HEADER),
+            REQUEST_ID_HEADER.clone(),
+        ])
+        .expose_headers([
+            header::CONTENT_RANGE,
+            header::CONTENT_DISPOSITION,
+            header::ETAG,
+            header::LINK,
+            DEPRECATION_HEADER.clone(),
+            REQUEST_ID_HEADER.clone(),
+        ])
+        .allow_credentials(true)
+        .max_age(PREFLIGHT_MAX_AGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn patterns(list: &[&str]) -> Vec<OriginPattern> {
+        list.iter().map(|p| OriginPattern::parse(p).unwrap_or_else(|| panic!("{p} should parse"))).collect()
+    }
+
+    fn config(vars: &[(&str, &str)]) -> Config {
+        let mut vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        vars.insert("DATABASE_URL".into(), "postgres://localhost/mtaalink".into());
+        vars.insert("JWT_SECRET".into(), "0123456789abcdef0123456789abcdef".into());
+        Config::from_lookup(&move |name| vars.get(name).cloned()).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn exact_origins_match_scheme_host_and_port() {
+        let allowed = patterns(&["https://mtaalink.com"]);
+        assert!(origin_allowed(&allowed, "https://mtaalink.com"));
+        assert!(origin_allowed(&allowed, "https://MtaaLink.com:443"));
+        assert!(origin_allowed(&allowed, "https://mtaalink.com/"));
+        assert!(!origin_allowed(&allowed, "http://mtaalink.com"));
+        assert!(!origin_allowed(&allowed, "https://mtaalink.com:8443"));
+        assert!(!origin_allowed(&allowed, "https://www.mtaalink.com"));
+        assert!(!origin_allowed(&allowed, "null"));
+    }
+
+    #[test]
+    fn explicit_ports_must_agree() {
+        let allowed = patterns(&["http://localhost:3000"]);
+        assert!(origin_allowed(&allowed, "http://localhost:3000"));
+        assert!(!origin_allowed(&allowed, "http://localhost"));
+        assert!(!origin_allowed(&allowed, "http://localhost:3001"));
+    }
+
+    #[test]
+    fn wildcards_match_subdomains_only() {
+        let allowed = patterns(&["https://*.example.com"]);
+        assert!(origin_allowed(&allowed, "https://a.example.com"));
+        assert!(origin_allowed(&allowed, "https://a.b.example.com"));
+        assert!(!origin_allowed(&allowed, "https://example.com"));
+        assert!(!origin_allowed(&allowed, "https://evilexample.com"));
+        assert!(!origin_allowed(&allowed, "https://a.example.com.evil.com"));
+        assert!(!origin_allowed(&allowed, "http://a.example.com"));
+        assert!(!origin_allowed(&allowed, "https://a.example.com:8443"));
+    }
+
+    #[test]
+    fn malformed_patterns_are_refused() {
+        for raw in ["mtaalink.com", "ftp://mtaalink.com", "https://*.com", "https://a.*.com", "https://mtaalink.com/app", ""] {
+            assert_eq!(OriginPattern::parse(raw), None, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn empty_or_whitespace_allowed_origins_count_as_unset() {
+        for blank in ["", "   ", " , ,"] {
+            let dev = config(&[("ALLOWED_ORIGINS", blank)]);
+            assert!(dev.cors_permissive, "{blank:?}");
+
+            let prod = config(&[
+                ("ALLOWED_ORIGINS", blank),
+                ("APP_ENV", "production"),
+                ("FRONTEND_URL", "https://mtaalink.com"),
+                ("VIEWER_HASH_SECRET", "fedcba9876543210fedcba9876543210"),
+            ]);
+            assert!(!prod.cors_permissive, "{blank:?}");
+            assert_eq!(prod.cors_origins, patterns(&["https://mtaalink.com"]), "{blank:?}");
+        }
+        assert!(!origin_allowed(&[], "https://mtaalink.com"));
+    }
+}
@@ -0,0 +1,27 @@
+use crate::config;
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue, Request, header},
+    middleware::Next,
+    response::Response,
+};
+
+/// When the unprefixed routes were deprecated (2026-10-16), as the
+/// `Deprecation` header's `@<unix seconds>` date (RFC 9745).
+const UNPREFIXED_DEPRECATED_AT: &str = "@1792108800";
+
+pub static DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+
+/// Marks a response from an unprefixed route as deprecated and links to the
+/// same route under `API_PREFIX`, so clients can tell they need updating.
+pub async fn mark_unprefixed(request: Request<Body>, next: Next) -> Response {
+    let successor = format!("{}{}", config::get().api_prefix, request.uri().path());
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION_HEADER.clone(), HeaderValue::from_static(UNPREFIXED_DEPRECATED_AT));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor)) {
+        headers.append(header::LINK, link);
+    }
+    response
+}
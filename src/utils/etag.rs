@@ -0,0 +1,129 @@
+use crate::errors::{AppError, AppResult};
+use axum::{
+    body::{Body, to_bytes},
+    extract::State,
+    http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+/// Datasets with a version counter in `dataset_versions`. Triggers on their
+/// tables bump it on every write, so the version alone identifies the data.
+pub const CATEGORIES: &str = "categories";
+pub const LOCATIONS: &str = "locations";
+
+/// Largest body `conditional_route` will buffer to hash. Listings are
+/// paginated, so this is far above anything they return.
+const MAX_HASHED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// How a class of route may be cached, sent as `Cache-Control`.
+#[derive(Clone, Copy, Debug)]
+pub enum CacheClass {
+    /// Reference data that rarely changes (categories, locations). Reused for
+    /// a few minutes, then revalidated against the dataset version.
+    Reference,
+    /// Public listings that change as providers and reviews do. Always
+    /// revalidated, which costs a 304 when nothing changed.
+    Listing,
+}
+
+impl CacheClass {
+    fn cache_control(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            CacheClass::Reference => "public, max-age=300",
+            CacheClass::Listing => "public, no-cache",
+        })
+    }
+}
+
+/// The ETag for a versioned dataset, e.g. `"categories-v12"`. Cheap enough
+/// to check before running the query it stands for.
+pub async fn dataset_etag(pool: &PgPool, dataset: &str) -> AppResult<String> {
+    let version = sqlx::query_scalar!("SELECT version FROM dataset_versions WHERE name = $1", dataset)
+        .fetch_optional(pool)
+        .await?
+        .unwrap_or(0);
+    Ok(format!("\"{}-v{}\"", dataset, version))
+}
+
+/// A strong ETag over the exact bytes of a response body.
+pub fn body_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Whether the request's `If-None-Match` lists `etag` (or is `*`). The
+/// comparison is weak, as RFC 9110 requires for this header.
+pub fn matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+fn cache_headers(etag: &str, class: CacheClass) -> [(header::HeaderName, HeaderValue); 2] {
+    // Every ETag built here is a quoted hex digest or dataset name
+    let etag = HeaderValue::from_str(etag).expect("ETag is a valid header value");
+    [(header::ETAG, etag), (header::CACHE_CONTROL, class.cache_control())]
+}
+
+/// 304 for a conditional request whose `If-None-Match` matched.
+pub fn not_modified(etag: &str, class: CacheClass) -> Response {
+    (StatusCode::NOT_MODIFIED, cache_headers(etag, class)).into_response()
+}
+
+/// Sends `response` with its ETag and `Cache-Control`.
+pub fn tagged(etag: &str, class: CacheClass, response: impl IntoResponse) -> Response {
+    (cache_headers(etag, class), response).into_response()
+}
+
+/// Gives a GET route an ETag computed from its response body and answers a
+/// matching `If-None-Match` with 304. The handler still runs; what's saved is
+/// sending the body. For versioned datasets use `dataset_etag` instead, which
+/// skips the query too.
+pub fn conditional_route<S>(route: MethodRouter<S>, class: CacheClass) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    route.layer(middleware::from_fn_with_state(class, tag_body))
+}
+
+async fn tag_body(State(class): State<CacheClass>, request: Request<Body>, next: Next) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+    let request_headers = request.headers().clone();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_HASHED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::Internal(format!("Failed to buffer response for ETag: {}", e)).into_response(),
+    };
+
+    let etag = body_etag(&bytes);
+    let mut response = if matches(&request_headers, &etag) {
+        not_modified(&etag, class)
+    } else {
+        parts.headers.extend(cache_headers(&etag, class));
+        Response::from_parts(parts, Body::from(bytes))
+    };
+    // Signed-in callers may get personal fields, which shared caches must
+    // neither store nor hand to anyone else
+    let headers = response.headers_mut();
+    headers.append(header::VARY, HeaderValue::from_static("Authorization"));
+    if request_headers.contains_key(header::AUTHORIZATION) {
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+    }
+    response
+}
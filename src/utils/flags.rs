@@ -0,0 +1,236 @@
+use crate::config::Config;
+use crate::errors::AppError;
+use crate::extractors::current_user::bearer_token;
+use crate::utils::jwt::decode_jwt;
+use axum::{
+    Extension,
+    body::Body,
+    extract::{Query, State},
+    http::{Request, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// How long overrides read from `feature_flags` are reused. A toggle made
+/// through `/admin/feature_flags` on another instance shows up within this
+/// window; on the instance that served it, immediately.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+pub const PAYMENTS: &str = "payments";
+pub const WEBSOCKET: &str = "websocket";
+
+pub struct FlagDef {
+    pub name: &'static str,
+    /// Used outside production when `FEATURE_FLAGS` doesn't mention the flag.
+    pub default: bool,
+    /// The same in production, where unfinished features ship dark.
+    pub production_default: bool,
+    pub description: &'static str,
+}
+
+/// Every flag. Unknown names are rejected in `FEATURE_FLAGS` and on update.
+pub const KNOWN_FLAGS: [FlagDef; 2] = [
+    FlagDef {
+        name: PAYMENTS,
+        default: true,
+        production_default: false,
+        description: "M-Pesa payment routes under /payments",
+    },
+    FlagDef {
+        name: WEBSOCKET,
+        default: true,
+        production_default: false,
+        description: "WebSocket upgrade at /ws for live chat and notifications",
+    },
+];
+
+pub fn definition(name: &str) -> Option<&'static FlagDef> {
+    KNOWN_FLAGS.iter().find(|def| def.name == name)
+}
+
+/// Roles a flag can be limited to. `admin` means a super admin.
+pub const ROLES: [&str; 4] = ["client", "provider", "business", "admin"];
+
+/// An admin override from `feature_flags`.
+#[derive(Debug)]
+pub struct FlagRule {
+    pub enabled: bool,
+    pub rollout_percent: i16,
+    pub roles: Vec<String>,
+}
+
+impl FlagRule {
+    fn is_targeted(&self) -> bool {
+        self.rollout_percent < 100 || !self.roles.is_empty()
+    }
+}
+
+/// Which of 100 rollout buckets a user falls in for `flag`. Stable, so a
+/// user keeps the feature as the percentage grows, and independent per flag.
+pub fn rollout_bucket(flag: &str, user_id: i32) -> i16 {
+    let digest = Sha256::digest(format!("{flag}:{user_id}").as_bytes());
+    (u16::from_be_bytes([digest[0], digest[1]]) % 100) as i16
+}
+
+type Snapshot = Arc<HashMap<String, FlagRule>>;
+
+/// Flag state for one app: environment defaults plus cached admin overrides.
+/// Checking a flag is a map lookup unless the cache has expired.
+pub struct FeatureFlags {
+    pool: PgPool,
+    defaults: HashMap<&'static str, bool>,
+    cache: RwLock<Option<(Instant, Snapshot)>>,
+}
+
+pub type SharedFeatureFlags = Arc<FeatureFlags>;
+
+impl FeatureFlags {
+    pub fn new(pool: PgPool, config: &Config) -> FeatureFlags {
+        let production = config.app_env == "production";
+        let defaults = KNOWN_FLAGS
+            .iter()
+            .map(|def| {
+                let compiled = if production { def.production_default } else { def.default };
+                (def.name, config.feature_flags.get(def.name).copied().unwrap_or(compiled))
+            })
+            .collect();
+        FeatureFlags { pool, defaults, cache: RwLock::new(None) }
+    }
+
+    /// The value a flag has without an admin override.
+    pub fn default_for(&self, name: &str) -> bool {
+        self.defaults.get(name).copied().unwrap_or(false)
+    }
+
+    /// Current overrides, from the cache when it is fresh. A failed read keeps
+    /// the last overrides seen (or none), so a database blip doesn't flip flags.
+    async fn rules(&self) -> Snapshot {
+        let stale = {
+            let cache = self.cache.read().unwrap();
+            match cache.as_ref() {
+                Some((at, rules)) if at.elapsed() < CACHE_TTL => return rules.clone(),
+                Some((_, rules)) => Some(rules.clone()),
+                None => None,
+            }
+        };
+
+        match sqlx::query!("SELECT name, enabled, rollout_percent, roles FROM feature_flags")
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(rows) => {
+                let rules: Snapshot = Arc::new(
+                    rows.into_iter()
+                        .map(|r| {
+                            let rule = FlagRule { enabled: r.enabled, rollout_percent: r.rollout_percent, roles: r.roles };
+                            (r.name, rule)
+                        })
+                        .collect(),
+                );
+                *self.cache.write().unwrap() = Some((Instant::now(), rules.clone()));
+                rules
+            }
+            Err(e) => {
+                tracing::error!("Failed to load feature flags: {}", e);
+                stale.unwrap_or_default()
+            }
+        }
+    }
+
+    /// Whether the feature is on at all. Role and percentage targeting only
+    /// narrow it for signed-in users (see `enabled_for`), so anonymous
+    /// requests such as the M-Pesa callback see it whenever it is enabled.
+    pub async fn enabled(&self, name: &str) -> bool {
+        match self.rules().await.get(name) {
+            Some(rule) => rule.enabled,
+            None => self.default_for(name),
+        }
+    }
+
+    /// Whether the feature is on for `user_id` (or an anonymous caller).
+    pub async fn enabled_for(&self, name: &str, user_id: Option<i32>) -> bool {
+        let rules = self.rules().await;
+        let Some(rule) = rules.get(name) else {
+            return self.default_for(name);
+        };
+        if !rule.enabled {
+            return false;
+        }
+        let Some(user_id) = user_id.filter(|_| rule.is_targeted()) else {
+            return true;
+        };
+        if rule.rollout_percent < 100 && rollout_bucket(name, user_id) >= rule.rollout_percent {
+            return false;
+        }
+        if rule.roles.is_empty() {
+            return true;
+        }
+        match self.role_of(user_id).await {
+            Some(roles) => roles.iter().any(|role| rule.roles.contains(role)),
+            None => false,
+        }
+    }
+
+    /// The user's role, plus `admin` for a super admin.
+    async fn role_of(&self, user_id: i32) -> Option<Vec<String>> {
+        let row = sqlx::query!(
+            r#"SELECT u.role,
+                      EXISTS (SELECT 1 FROM admins a WHERE a.user_id = u.id AND a.is_super_admin) AS "is_admin!"
+               FROM users u WHERE u.id = $1"#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| tracing::error!("Failed to load role for feature flag check: {}", e))
+        .ok()??;
+
+        let mut roles: Vec<String> = row.role.into_iter().collect();
+        if row.is_admin {
+            roles.push("admin".to_string());
+        }
+        Some(roles)
+    }
+
+    /// Drops the cached overrides so this instance sees an update immediately.
+    pub fn invalidate(&self) {
+        *self.cache.write().unwrap() = None;
+    }
+}
+
+/// Whether `name` is on at all; cheap enough for any handler.
+pub async fn enabled(flags: &FeatureFlags, name: &str) -> bool {
+    flags.enabled(name).await
+}
+
+/// Answers 404 for routes behind the flag named in the state when it is off
+/// for the caller, as if they didn't exist. The caller is taken from the
+/// bearer token, or the `token` query parameter the WebSocket upgrade uses.
+/// Use as `.route_layer(from_fn_with_state(flags::PAYMENTS, flags::require_flag))`.
+pub async fn require_flag(
+    State(name): State<&'static str>,
+    Extension(flags): Extension<SharedFeatureFlags>,
+    Query(query): Query<HashMap<String, String>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(bearer_token)
+        .map(str::to_string);
+    let token = bearer.or_else(|| query.get("token").cloned());
+    let user_id = token
+        .and_then(|token| decode_jwt(&token).ok())
+        .and_then(|claims| claims.sub.parse::<i32>().ok());
+
+    if !flags.enabled_for(name, user_id).await {
+        return AppError::NotFound("Not found".to_string()).into_response();
+    }
+    next.run(request).await
+}
@@ -1,46 +1,221 @@
 use crate::errors::{AppError, AppResult};
+use crate::utils::settings;
+use crate::utils::spool::{SpoolWriter, SpooledFile};
 use axum::extract::Multipart;
-use bytes::Bytes;
-use std::path::Path;
+use axum::extract::multipart::Field;
+use bytes::{Bytes, BytesMut};
+use sqlx::PgPool;
 
-/// Parse the first file field from a multipart request.
-/// Returns (data, lowercase_extension, content_type).
+// ── Content sniffing ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+impl MediaKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MediaKind::Image => "image",
+            MediaKind::Video => "video",
+        }
+    }
+}
+
+/// A file type recognised from its leading bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct SniffedMedia {
+    pub kind: MediaKind,
+    pub extension: &'static str,
+    pub content_type: &'static str,
+}
+
+/// Bytes needed to tell every supported format apart.
+const SNIFF_LEN: usize = 12;
+
+/// Identifies a supported image or video from its magic bytes. The client's
+/// file name and Content-Type are never consulted.
+pub fn sniff_media(head: &[u8]) -> Option<SniffedMedia> {
+    let media = |kind, extension, content_type| SniffedMedia { kind, extension, content_type };
+
+    if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(media(MediaKind::Image, "jpg", "image/jpeg"));
+    }
+    if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(media(MediaKind::Image, "png", "image/png"));
+    }
+    if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        return Some(media(MediaKind::Image, "gif", "image/gif"));
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" {
+        match &head[8..12] {
+            b"WEBP" => return Some(media(MediaKind::Image, "webp", "image/webp")),
+            b"AVI " => return Some(media(MediaKind::Video, "avi", "video/x-msvideo")),
+            _ => {}
+        }
+    }
+    // ISO base media (MP4 / QuickTime): box size, then "ftyp" and the brand
+    if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        return if &head[8..12] == b"qt  " {
+            Some(media(MediaKind::Video, "mov", "video/quicktime"))
+        } else {
+            Some(media(MediaKind::Video, "mp4", "video/mp4"))
+        };
+    }
+    None
+}
+
+// ── Size-capped field reading ─────────────────────────────────────────────────
+
+/// Byte caps applied while a multipart field is streamed in.
+#[derive(Debug, Clone, Copy)]
+pub struct UploadLimits {
+    pub max_image_bytes: usize,
+    /// `None` rejects videos outright.
+    pub max_video_bytes: Option<usize>,
+    /// What is left of the per-request budget.
+    pub remaining_request_bytes: usize,
+}
+
+impl UploadLimits {
+    /// Current caps from the `uploads.*` settings.
+    pub async fn load(pool: &PgPool, allow_video: bool) -> Self {
+        let max_image_bytes = settings::int(pool, "uploads.max_image_bytes").await as usize;
+        let max_video_bytes = if allow_video {
+            Some(settings::int(pool, "uploads.max_video_bytes").await as usize)
+        } else {
+            None
+        };
+        UploadLimits {
+            max_image_bytes,
+            max_video_bytes,
+            remaining_request_bytes: settings::int(pool, "uploads.max_request_bytes").await as usize,
+        }
+    }
+}
+
+/// The byte cap for a file of the sniffed kind; videos are refused when the
+/// limits don't allow them.
+fn media_cap(media: SniffedMedia, file_name: &str, limits: &UploadLimits) -> AppResult<usize> {
+    match media.kind {
+        MediaKind::Image => Ok(limits.max_image_bytes),
+        MediaKind::Video => limits
+            .max_video_bytes
+            .ok_or_else(|| AppError::UnsupportedMediaType(format!("{} must be an image", file_name))),
+    }
+}
+
+fn too_large(file_name: &str, media: SniffedMedia, file_cap: usize) -> AppError {
+    AppError::PayloadTooLarge(format!(
+        "{} exceeds the {} byte limit for {}s",
+        file_name,
+        file_cap,
+        media.kind.as_str()
+    ))
+}
+
+/// Applies the same type and size checks as `read_media_field` to a file
+/// that arrives some other way, e.g. assembled from resumable chunks. `head`
+/// is the start of the file and `size` its full length.
+pub fn check_media(head: &[u8], size: u64, file_name: &str, limits: &UploadLimits) -> AppResult<SniffedMedia> {
+    if head.is_empty() || size == 0 {
+        return Err(AppError::BadRequest(format!("{} is empty", file_name)));
+    }
+    let media = sniff_media(head).ok_or_else(|| {
+        AppError::UnsupportedMediaType(format!("{} is not a supported image or video", file_name))
+    })?;
+    let file_cap = media_cap(media, file_name, limits)?;
+    if size > file_cap as u64 {
+        return Err(too_large(file_name, media, file_cap));
+    }
+    Ok(media)
+}
+
+/// Writes one chunk to the spool unless it would take the file past its cap
+/// or the request past its budget, so nothing over a limit reaches disk.
+async fn spool_chunk(
+    spool: &mut SpoolWriter,
+    chunk: &[u8],
+    media: SniffedMedia,
+    file_cap: usize,
+    file_name: &str,
+    limits: &UploadLimits,
+) -> AppResult<()> {
+    let size = spool.size() as usize + chunk.len();
+    if size > file_cap {
+        return Err(too_large(file_name, media, file_cap));
+    }
+    if size > limits.remaining_request_bytes {
+        return Err(AppError::PayloadTooLarge(
+            "Upload exceeds the total size allowed per request".to_string(),
+        ));
+    }
+    spool.write(chunk).await
+}
+
+/// Streams one file field to a temporary file. The type is sniffed from the
+/// first bytes, which are the only part held in memory, and reading stops
+/// as soon as the applicable cap is passed, so an oversized or disguised
+/// file is refused early and an accepted one is never buffered in full.
+pub async fn read_media_field(
+    field: &mut Field<'_>,
+    file_name: &str,
+    limits: &mut UploadLimits,
+) -> AppResult<(SpooledFile, SniffedMedia)> {
+    let mut head = BytesMut::with_capacity(SNIFF_LEN);
+    let mut rest = Bytes::new();
+    let mut ended = false;
+    while head.len() < SNIFF_LEN {
+        let Some(mut chunk) = field.chunk().await? else {
+            ended = true;
+            break;
+        };
+        let take = chunk.len().min(SNIFF_LEN - head.len());
+        head.extend_from_slice(&chunk.split_to(take));
+        rest = chunk;
+    }
+    if head.is_empty() {
+        return Err(AppError::BadRequest(format!("{} is empty", file_name)));
+    }
+    let media = sniff_media(&head).ok_or_else(|| {
+        AppError::UnsupportedMediaType(format!("{} is not a supported image or video", file_name))
+    })?;
+    let file_cap = media_cap(media, file_name, limits)?;
+
+    let mut spool = SpooledFile::create(media.extension).await?;
+    spool_chunk(&mut spool, &head, media, file_cap, file_name, limits).await?;
+    spool_chunk(&mut spool, &rest, media, file_cap, file_name, limits).await?;
+    if !ended {
+        while let Some(chunk) = field.chunk().await? {
+            spool_chunk(&mut spool, &chunk, media, file_cap, file_name, limits).await?;
+        }
+    }
+
+    let file = spool.finish().await?;
+    limits.remaining_request_bytes -= file.size() as usize;
+    Ok((file, media))
+}
+
+/// Parse the first file field from a multipart request as an image.
+/// Returns (data, lowercase_extension, content_type), both taken from the
+/// sniffed format rather than the upload's name or headers.
 pub async fn parse_image_from_multipart(
+    pool: &PgPool,
     mut multipart: Multipart,
 ) -> AppResult<(Bytes, String, String)> {
-    while let Some(field) = multipart
-        .next_field()
-        .await
-        .map_err(|e| AppError::BadRequest(e.to_string()))?
-    {
-        let content_type = field
-            .content_type()
-            .map(|ct| ct.to_string())
-            .unwrap_or_else(|| "application/octet-stream".to_string());
-
+    if let Some(mut field) = multipart.next_field().await? {
         let file_name = field
             .file_name()
             .map(|s| s.to_string())
-            .unwrap_or_else(|| "upload.jpg".to_string());
-
-        let extension = Path::new(&file_name)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("jpg")
-            .to_lowercase();
+            .unwrap_or_else(|| "upload".to_string());
 
-        let data = field
-            .bytes()
-            .await
-            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+        let mut limits = UploadLimits::load(pool, false).await;
+        let (file, media) = read_media_field(&mut field, &file_name, &mut limits).await?;
+        let data = file.read().await?;
 
-        if data.is_empty() {
-            return Err(AppError::BadRequest("File is empty".to_string()));
-        }
-
-        return Ok((data, extension, content_type));
+        return Ok((data, media.extension.to_string(), media.content_type.to_string()));
     }
 
     Err(AppError::BadRequest("No file uploaded".to_string()))
 }
-
@@ -0,0 +1,89 @@
+use sqlx::{PgExecutor, PgPool};
+
+/// Something a user did with a provider or business, stored in
+/// `interactions.interaction_type`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interaction {
+    Message,
+    BookingCreated,
+    BookingCompleted,
+    ContactClick,
+    Favorite,
+}
+
+impl Interaction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Interaction::Message => "message",
+            Interaction::BookingCreated => "booking_created",
+            Interaction::BookingCompleted => "booking_completed",
+            Interaction::ContactClick => "contact_click",
+            Interaction::Favorite => "favorite",
+        }
+    }
+}
+
+/// Records that `user_id` interacted with the listing. Repeats of the same
+/// kind on the same day are ignored.
+pub async fn record<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: i32,
+    target_type: &str,
+    target_id: i32,
+    kind: Interaction,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO interactions (user_id, target_type, target_id, interaction_type)
+           VALUES ($1, $2, $3, $4)
+           ON CONFLICT DO NOTHING"#,
+        user_id,
+        target_type,
+        target_id,
+        kind.as_str()
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Records an interaction by a booking's client with the booked listing.
+pub async fn record_for_booking<'e>(
+    executor: impl PgExecutor<'e>,
+    booking_id: i32,
+    kind: Interaction,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO interactions (user_id, target_type, target_id, interaction_type)
+           SELECT client_id, target_type, target_id, $2 FROM bookings WHERE id = $1
+           ON CONFLICT DO NOTHING"#,
+        booking_id,
+        kind.as_str()
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Whether `user_id` has ever interacted with the listing in one of `kinds`.
+pub async fn has_interacted(
+    pool: &PgPool,
+    user_id: i32,
+    target_type: &str,
+    target_id: i32,
+    kinds: &[Interaction],
+) -> Result<bool, sqlx::Error> {
+    let kinds: Vec<&str> = kinds.iter().map(|kind| kind.as_str()).collect();
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+               SELECT 1 FROM interactions
+               WHERE user_id = $1 AND target_type = $2 AND target_id = $3
+                 AND interaction_type = ANY($4)
+           ) AS "exists!""#,
+        user_id,
+        target_type,
+        target_id,
+        &kinds as &[&str]
+    )
+    .fetch_one(pool)
+    .await
+}
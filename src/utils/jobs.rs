@@ -0,0 +1,278 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+// ── Job registry ──────────────────────────────────────────────────────────────
+
+pub struct JobDef {
+    pub name: &'static str,
+    pub interval: Duration,
+}
+
+pub const BOOKING_REMINDERS: JobDef = JobDef {
+    name: "booking_reminders",
+    interval: Duration::from_secs(15 * 60),
+};
+
+pub const BOOKING_EXPIRY: JobDef = JobDef {
+    name: "booking_expiry",
+    interval: Duration::from_secs(10 * 60),
+};
+
+pub const CLEANUP: JobDef = JobDef {
+    name: "cleanup",
+    interval: Duration::from_secs(60 * 60),
+};
+
+pub const PASSWORD_RESET_CLEANUP: JobDef = JobDef {
+    name: "password_reset_cleanup",
+    interval: Duration::from_secs(60 * 60),
+};
+
+pub const POST_FANOUT: JobDef = JobDef {
+    name: "post_fanout",
+    interval: Duration::from_secs(5 * 60),
+};
+
+pub const WEBHOOK_DELIVERY: JobDef = JobDef {
+    name: "webhook_delivery",
+    interval: Duration::from_secs(60),
+};
+
+pub const STORAGE_GC: JobDef = JobDef {
+    name: "storage_gc",
+    interval: Duration::from_secs(7 * 24 * 60 * 60),
+};
+
+pub const RETENTION: JobDef = JobDef {
+    name: "retention",
+    interval: Duration::from_secs(24 * 60 * 60),
+};
+
+pub const SITEMAP: JobDef = JobDef {
+    name: "sitemap",
+    interval: Duration::from_secs(6 * 60 * 60),
+};
+
+/// Every background job, as listed by `/admin/jobs`.
+pub const JOBS: [&JobDef; 9] = [
+    &BOOKING_EXPIRY,
+    &BOOKING_REMINDERS,
+    &CLEANUP,
+    &PASSWORD_RESET_CLEANUP,
+    &POST_FANOUT,
+    &RETENTION,
+    &SITEMAP,
+    &STORAGE_GC,
+    &WEBHOOK_DELIVERY,
+];
+
+pub fn definition(name: &str) -> Option<&'static JobDef> {
+    JOBS.into_iter().find(|def| def.name == name)
+}
+
+// ── Run-now triggers ──────────────────────────────────────────────────────────
+
+/// Senders that wake a job's loop early, keyed by job name.
+pub struct JobTriggers {
+    senders: HashMap<&'static str, mpsc::Sender<()>>,
+    /// Flipped to true at shutdown. Each loop holds a receiver, so the
+    /// channel closes once every loop has exited.
+    shutdown: watch::Sender<bool>,
+    /// When this instance's job loops were started; a job with no recorded
+    /// runs is measured against this.
+    pub started_at: DateTime<Utc>,
+}
+
+pub type SharedJobTriggers = Arc<JobTriggers>;
+
+impl JobTriggers {
+    pub fn new() -> Self {
+        Self {
+            senders: HashMap::new(),
+            shutdown: watch::Sender::new(false),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// Registers a job and returns what its loop waits on. Capacity 1:
+    /// repeated requests while a run is already queued collapse into one.
+    pub fn register(&mut self, job: &JobDef) -> JobTrigger {
+        let (tx, rx) = mpsc::channel(1);
+        self.senders.insert(job.name, tx);
+        JobTrigger {
+            run_now: rx,
+            shutdown: self.shutdown.subscribe(),
+        }
+    }
+
+    /// Queues an immediate run. Returns false if the job is unknown to this
+    /// instance or a run is already queued.
+    pub fn trigger(&self, name: &str) -> bool {
+        self.senders
+            .get(name)
+            .is_some_and(|tx| tx.try_send(()).is_ok())
+    }
+
+    /// Tells every job loop to exit once its current pass (if any) is done.
+    pub fn stop(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Waits up to `timeout` for the loops to exit after `stop`. Returns
+    /// false if a pass was still running when time ran out.
+    pub async fn wait_stopped(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, self.shutdown.closed()).await.is_ok()
+    }
+}
+
+impl Default for JobTriggers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a job's loop waits on between passes.
+pub struct JobTrigger {
+    run_now: mpsc::Receiver<()>,
+    shutdown: watch::Receiver<bool>,
+}
+
+/// Upper bound on the random delay added to each sleep.
+const MAX_JITTER: Duration = Duration::from_secs(5 * 60);
+
+/// Up to a tenth of the interval (capped at `MAX_JITTER`), so instances
+/// started together don't all wake at the same moment.
+fn jitter(interval: Duration) -> Duration {
+    let max = (interval / 10).min(MAX_JITTER);
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    rand::thread_rng().gen_range(Duration::ZERO..=max)
+}
+
+/// Sleeps for the job's interval plus jitter, or until a run is requested.
+/// Returns false once the server is shutting down, and the loop should exit.
+pub async fn wait_for_next_run(job: &JobDef, trigger: &mut JobTrigger) -> bool {
+    tokio::select! {
+        _ = trigger.shutdown.wait_for(|stopping| *stopping) => false,
+        _ = tokio::time::sleep(job.interval + jitter(job.interval)) => true,
+        Some(()) = trigger.run_now.recv() => {
+            tracing::info!("Job {} triggered manually", job.name);
+            true
+        }
+    }
+}
+
+// ── Run bookkeeping ───────────────────────────────────────────────────────────
+
+/// What a single pass of a job did. Failures are logged as they happen and
+/// kept so the run is recorded as failed.
+#[derive(Default)]
+pub struct JobReport {
+    pub items_processed: i64,
+    errors: Vec<String>,
+    details: Map<String, Value>,
+}
+
+impl JobReport {
+    pub fn fail(&mut self, context: &str, e: impl Display) {
+        tracing::error!("{}: {}", context, e);
+        self.errors.push(format!("{}: {}", context, e));
+    }
+
+    /// Adds an entry to the breakdown saved in `job_runs.details`.
+    pub fn detail(&mut self, key: &str, value: impl Into<Value>) {
+        self.details.insert(key.to_string(), value.into());
+    }
+
+    fn error(&self) -> Option<String> {
+        (!self.errors.is_empty()).then(|| self.errors.join("; "))
+    }
+
+    fn details(&self) -> Option<Value> {
+        (!self.details.is_empty()).then(|| Value::Object(self.details.clone()))
+    }
+}
+
+/// First key of the advisory locks taken for jobs; the second is a hash of
+/// the job name. Keeps them clear of any other advisory lock use.
+const JOB_LOCK_NAMESPACE: i32 = 0x6a6f_6273; // "jobs"
+
+/// Runs one pass of a job and records it in `job_runs`. The row is written
+/// before the pass starts so a run that never finishes still shows up.
+/// Bookkeeping failures are logged and never stop the job itself.
+///
+/// The pass only runs while this instance holds the job's advisory lock, so
+/// when several instances wake at once just one of them does the work. The
+/// lock belongs to a transaction held open for the pass and is released when
+/// it ends, even if the pass panics. Returns false if another instance had
+/// the lock and the pass was skipped.
+pub async fn record_run<F>(pool: &PgPool, job: &JobDef, pass: F) -> bool
+where
+    F: Future<Output = JobReport>,
+{
+    let mut lock_tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::error!("Failed to start job {}: {}", job.name, e);
+            return false;
+        }
+    };
+    let locked = sqlx::query_scalar!(
+        r#"SELECT pg_try_advisory_xact_lock($1, hashtext($2)) AS "locked!""#,
+        JOB_LOCK_NAMESPACE,
+        job.name
+    )
+    .fetch_one(&mut *lock_tx)
+    .await;
+    match locked {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::info!("Job {} is already running on another instance; skipping", job.name);
+            return false;
+        }
+        Err(e) => {
+            tracing::error!("Failed to take the lock for job {}: {}", job.name, e);
+            return false;
+        }
+    }
+
+    let run_id = sqlx::query_scalar!(
+        "INSERT INTO job_runs (job_name) VALUES ($1) RETURNING id",
+        job.name
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| tracing::error!("Failed to record start of job {}: {}", job.name, e))
+    .ok();
+
+    let report = pass.await;
+
+    if let Some(run_id) = run_id {
+        if let Err(e) = sqlx::query!(
+            r#"UPDATE job_runs SET finished_at = NOW(), items_processed = $1, error = $2, details = $3
+               WHERE id = $4"#,
+            report.items_processed,
+            report.error(),
+            report.details(),
+            run_id
+        )
+        .execute(pool)
+        .await
+        {
+            tracing::error!("Failed to record end of job {}: {}", job.name, e);
+        }
+    }
+
+    if let Err(e) = lock_tx.commit().await {
+        tracing::warn!("Failed to release the lock for job {}: {}", job.name, e);
+    }
+    true
+}
@@ -1,46 +1,191 @@
 use crate::AppResult;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use crate::config;
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode};
 use serde::{Deserialize, Serialize};
-use std::env;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    /// Set by `JwtKeys::sign` from its scope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// Admin user id when the token was minted through `/admin/impersonate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<i32>,
+    /// The user's role when the token was issued, for clients to read.
+    /// Tokens from before it was added have none; the server always goes by
+    /// the role stored on the account (see `CurrentUser::role`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
 }
 
-fn jwt_secret() -> Vec<u8> {
-    env::var("JWT_SECRET")
-        .expect("JWT_SECRET must be set")
-        .as_bytes()
-        .to_vec()
+struct JwtKey {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
 }
 
-pub fn create_jwt(user_id: &str) -> AppResult<String> {
+/// Who issued a token and which environment it is for.
+pub struct JwtScope {
+    pub issuer: String,
+    pub audience: String,
+    /// Accept tokens with no `iss` and `aud` at all, as minted before the
+    /// claims existed. Tokens that carry them must always match.
+    pub accept_unscoped: bool,
+}
+
+/// The configured signing keys. The first signs and stamps its `kid` on the
+/// token header; verification picks the key by `kid`, so a token stays valid
+/// for as long as its key is still listed.
+pub struct JwtKeys {
+    keys: Vec<JwtKey>,
+    scope: Option<JwtScope>,
+}
+
+impl JwtKeys {
+    /// `secrets` newest first; there must be at least one.
+    pub fn new(secrets: &[String]) -> Self {
+        assert!(!secrets.is_empty(), "at least one JWT secret is required");
+        let keys = secrets
+            .iter()
+            .map(|secret| JwtKey {
+                kid: key_id(secret),
+                encoding: EncodingKey::from_secret(secret.as_bytes()),
+                decoding: DecodingKey::from_secret(secret.as_bytes()),
+            })
+            .collect();
+        Self { keys, scope: None }
+    }
+
+    /// Stamp `scope` on signed tokens and check it on verified ones.
+    pub fn with_scope(self, scope: JwtScope) -> Self {
+        Self { scope: Some(scope), ..self }
+    }
+
+    pub fn sign(&self, claims: &Claims) -> Result<String, Error> {
+        let key = &self.keys[0];
+        let header = Header { kid: Some(key.kid.clone()), ..Header::default() };
+        match &self.scope {
+            Some(scope) => {
+                let claims = Claims {
+                    iss: Some(scope.issuer.clone()),
+                    aud: Some(scope.audience.clone()),
+                    ..claims.clone()
+                };
+                encode(&header, &claims, &key.encoding)
+            }
+            None => encode(&header, claims, &key.encoding),
+        }
+    }
+
+    /// A token for another issuer or audience fails with `InvalidIssuer` or
+    /// `InvalidAudience`; see `is_wrong_environment`.
+    pub fn verify(&self, token: &str) -> Result<Claims, Error> {
+        let mut validation = Validation::default();
+        match &self.scope {
+            Some(scope) => {
+                validation.set_issuer(&[&scope.issuer]);
+                validation.set_audience(&[&scope.audience]);
+                if !scope.accept_unscoped {
+                    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+                }
+            }
+            None => validation.validate_aud = false,
+        }
+        match decode_header(token)?.kid {
+            Some(kid) => {
+                let key = self
+                    .keys
+                    .iter()
+                    .find(|k| k.kid == kid)
+                    .ok_or_else(|| Error::from(ErrorKind::InvalidSignature))?;
+                decode::<Claims>(token, &key.decoding, &validation).map(|data| data.claims)
+            }
+            // Issued before tokens carried a kid: try each key
+            None => {
+                for key in &self.keys {
+                    match decode::<Claims>(token, &key.decoding, &validation) {
+                        Ok(data) => return Ok(data.claims),
+                        // Signed with this key, but the claims don't pass
+                        Err(e) if *e.kind() != ErrorKind::InvalidSignature => return Err(e),
+                        Err(_) => {}
+                    }
+                }
+                Err(Error::from(ErrorKind::InvalidSignature))
+            }
+        }
+    }
+}
+
+/// Whether `verify` refused the token because it was issued elsewhere, e.g.
+/// a staging token sent to production.
+pub fn is_wrong_environment(err: &Error) -> bool {
+    matches!(err.kind(), ErrorKind::InvalidIssuer | ErrorKind::InvalidAudience)
+}
+
+/// A public identifier for a secret: the first 8 bytes of its SHA-256, hex.
+fn key_id(secret: &str) -> String {
+    hex::encode(&Sha256::digest(secret.as_bytes())[..8])
+}
+
+fn keys() -> &'static JwtKeys {
+    static KEYS: OnceLock<JwtKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let config = config::get();
+        JwtKeys::new(&config.jwt_secrets).with_scope(JwtScope {
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+            accept_unscoped: config.jwt_accept_unscoped,
+        })
+    })
+}
+
+/// An access token for `user_id`, valid for `jwt_access_ttl_minutes`. Longer
+/// sessions go through a refresh token (see `utils::refresh_tokens`).
+pub fn create_jwt(user_id: &str, role: Option<&str>) -> AppResult<String> {
     let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::days(30))
+        .checked_add_signed(chrono::Duration::minutes(config::get().jwt_access_ttl_minutes))
         .expect("valid timestamp")
         .timestamp() as usize;
 
     let claims = Claims {
         sub: user_id.to_owned(),
         exp: expiration,
+        iss: None,
+        aud: None,
+        impersonator: None,
+        role: role.map(str::to_owned),
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(&jwt_secret()),
-    )?;
+    Ok(keys().sign(&claims)?)
+}
+
+/// Short-lived token that acts as `user_id` on behalf of `impersonator_id`.
+pub fn create_impersonation_jwt(
+    user_id: i32,
+    impersonator_id: i32,
+    ttl: chrono::Duration,
+) -> AppResult<(String, chrono::DateTime<chrono::Utc>)> {
+    let expires_at = chrono::Utc::now() + ttl;
+
+    let claims = Claims {
+        sub: user_id.to_string(),
+        exp: expires_at.timestamp() as usize,
+        iss: None,
+        aud: None,
+        impersonator: Some(impersonator_id),
+        role: None,
+    };
 
-    Ok(token)
+    Ok((keys().sign(&claims)?, expires_at))
 }
 
 pub fn decode_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(&jwt_secret()),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
+    keys().verify(token)
 }
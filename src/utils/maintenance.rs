@@ -0,0 +1,136 @@
+use crate::config::{self, Config};
+use crate::errors::error_body;
+use crate::utils::settings::MAINTENANCE_READ_ONLY;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderValue, Method, Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// How long the stored switch is reused. Much shorter than the settings
+/// cache: turning maintenance on from another instance should hold writes
+/// within seconds. On the instance that served the toggle it is immediate.
+const CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Sent as `Retry-After` on refused writes.
+pub const RETRY_AFTER_SECONDS: u64 = 120;
+
+/// Writes still accepted in maintenance, relative to the API prefix: admin
+/// routes (so it can be switched off again), signing in to reach them,
+/// refreshing access tokens so nobody is signed out meanwhile, and M-Pesa
+/// callbacks, which Safaricom doesn't retry.
+const ALLOWED_WRITES: [&str; 4] = ["/admin/", "/auth/login", "/auth/refresh", "/payments/mpesa/callback"];
+
+/// Where the current read-only state comes from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Source {
+    /// `MAINTENANCE_MODE` in the environment.
+    Environment,
+    /// The `maintenance.read_only` setting.
+    Setting,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Environment => "environment",
+            Source::Setting => "setting",
+        }
+    }
+}
+
+/// Read-only mode for one app: the environment override, or else the stored
+/// setting through a short-lived cache, so the check costs a query at most
+/// once per `CACHE_TTL`.
+pub struct Maintenance {
+    pool: PgPool,
+    forced: Option<bool>,
+    cache: RwLock<Option<(Instant, bool)>>,
+}
+
+pub type SharedMaintenance = Arc<Maintenance>;
+
+impl Maintenance {
+    pub fn new(pool: PgPool, config: &Config) -> Maintenance {
+        Maintenance { pool, forced: config.maintenance_mode, cache: RwLock::new(None) }
+    }
+
+    pub fn source(&self) -> Source {
+        if self.forced.is_some() { Source::Environment } else { Source::Setting }
+    }
+
+    /// Whether writes are being refused. A failed read keeps the last value
+    /// seen (or off), so a database blip doesn't flip the API read-only.
+    pub async fn read_only(&self) -> bool {
+        if let Some(forced) = self.forced {
+            return forced;
+        }
+        let stale = {
+            let cache = self.cache.read().unwrap();
+            match *cache {
+                Some((at, on)) if at.elapsed() < CACHE_TTL => return on,
+                Some((_, on)) => on,
+                None => false,
+            }
+        };
+
+        match sqlx::query_scalar!("SELECT value FROM platform_settings WHERE key = $1", MAINTENANCE_READ_ONLY)
+            .fetch_optional(&self.pool)
+            .await
+        {
+            Ok(value) => {
+                let on = value.and_then(|v| v.as_i64()).is_some_and(|v| v != 0);
+                self.remember(on);
+                on
+            }
+            Err(e) => {
+                tracing::error!("Failed to read maintenance mode: {}", e);
+                stale
+            }
+        }
+    }
+
+    /// Records a value just written, so this instance acts on it at once.
+    pub fn remember(&self, on: bool) {
+        *self.cache.write().unwrap() = Some((Instant::now(), on));
+    }
+}
+
+fn allows_write(path: &str) -> bool {
+    let prefix = &config::get().api_prefix;
+    let path = path.strip_prefix(prefix.as_str()).filter(|p| p.starts_with('/')).unwrap_or(path);
+    ALLOWED_WRITES
+        .iter()
+        .any(|allowed| path.starts_with(allowed) || path == allowed.trim_end_matches('/'))
+}
+
+/// Answers mutating requests with `503` and a `maintenance` error while the
+/// API is read-only. Reads always go through.
+pub async fn enforce_read_only(
+    State(maintenance): State<SharedMaintenance>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let safe = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if safe || allows_write(request.uri().path()) || !maintenance.read_only().await {
+        return next.run(request).await;
+    }
+
+    let mut response = (
+        StatusCode::SERVICE_UNAVAILABLE,
+        error_body(
+            "maintenance",
+            "The service is read-only for maintenance. Please try again shortly.",
+            Some(json!({ "retry_after_seconds": RETRY_AFTER_SECONDS })),
+        ),
+    )
+        .into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECONDS));
+    response
+}
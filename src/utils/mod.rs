@@ -1,11 +1,50 @@
+pub mod account_deletion;
+pub mod action_items;
+pub mod activity;
 pub mod attachments;
+pub mod audit;
+pub mod body_limit;
+pub mod booking_events;
+pub mod booking_slots;
+pub mod booking_expiry;
+pub mod booking_status;
+pub mod cleanup;
+pub mod client_ip;
+pub mod cors;
+pub mod deprecation;
 pub mod email;
+pub mod etag;
+pub mod flags;
 pub mod image_upload;
+pub mod interactions;
+pub mod jobs;
 pub mod jwt;
+pub mod maintenance;
+pub mod money;
 pub mod mpesa;
 pub mod notifications;
+pub mod onboarding;
+pub mod pagination;
+pub mod password_resets;
+pub mod post_fanout;
+pub mod prefs;
+pub mod refresh_tokens;
 pub mod reminders;
+pub mod request_log;
+pub mod request_timeout;
+pub mod retention;
+pub mod seed;
+pub mod settings;
+pub mod sitemap;
 pub mod sms;
+pub mod spool;
+pub mod takeout;
 pub mod wallet;
+pub mod webhooks;
 pub mod ws_state;
 pub mod storage;
+pub mod storage_gc;
+pub mod structured_data;
+pub mod thumbnails;
+pub mod upload_sessions;
+pub mod video;
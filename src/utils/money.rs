@@ -0,0 +1,35 @@
+use bigdecimal::BigDecimal;
+use serde_json::{Value, json};
+
+/// Everything is charged and paid out in Kenyan shillings.
+pub const CURRENCY: &str = "KES";
+
+/// The amount rounded to cents, as a plain decimal string: `"1234.50"`.
+pub fn to_cents_string(amount: &BigDecimal) -> String {
+    amount.round(2).with_scale(2).to_string()
+}
+
+/// The amount for display: `"KES 1,234.50"`.
+pub fn format_kes(amount: &BigDecimal) -> String {
+    let plain = to_cents_string(amount);
+    let (sign, unsigned) = match plain.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", plain.as_str()),
+    };
+    let (whole, cents) = unsigned.split_once('.').unwrap_or((unsigned, "00"));
+
+    let mut grouped = String::with_capacity(whole.len() + whole.len() / 3);
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{CURRENCY} {sign}{grouped}.{cents}")
+}
+
+/// An amount in an API response: the exact value for arithmetic and the
+/// display string, so clients don't each format currency their own way.
+pub fn amount_json(amount: &BigDecimal) -> Value {
+    json!({ "amount": to_cents_string(amount), "formatted": format_kes(amount) })
+}
@@ -1,33 +1,122 @@
 use crate::utils::ws_state::{WsConnections, push_to_user};
-use serde_json::json;
-use sqlx::PgPool;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
 
-/// Insert a notification row. Errors are caller's responsibility.
-pub async fn notify(
+/// A stored notification, as listed by `GET /notifications` and pushed over
+/// the messaging socket as a `notification` event.
+#[derive(Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct Notification {
+    pub id: i32,
+    pub notif_type: String,
+    pub title: String,
+    pub body: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
+    /// Extra payload for deep-linking (e.g. post id and author for `new_post`).
+    pub data: Option<serde_json::Value>,
+    pub is_read: bool,
+    pub read_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// What a producer wants to tell a user. `notif_type` is what the app routes
+/// on; `target_type`/`target_id` point at the thing the notification is about.
+#[derive(Debug, Clone, Default)]
+pub struct NewNotification<'a> {
+    pub notif_type: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+    pub target_type: Option<&'a str>,
+    pub target_id: Option<i32>,
+    pub data: Option<serde_json::Value>,
+}
+
+/// Stores a notification for `user_id`. With `ws_conns`, it is also pushed to
+/// the user if they have the messaging socket open. Every producer ends up
+/// here (or in `create_many`), directly or through the helpers below.
+pub async fn create(
     pool: &PgPool,
+    ws_conns: Option<&WsConnections>,
     user_id: i32,
-    notif_type: &str,
-    title: &str,
-    body: &str,
-    target_type: Option<&str>,
-    target_id: Option<i32>,
-) -> Result<(), sqlx::Error> {
-    sqlx::query!(
-        r#"INSERT INTO notifications (user_id, notif_type, title, body, target_type, target_id)
-           VALUES ($1, $2, $3, $4, $5, $6)"#,
+    new: &NewNotification<'_>,
+) -> Result<Notification, sqlx::Error> {
+    let notification = sqlx::query_as!(
+        Notification,
+        r#"INSERT INTO notifications (user_id, notif_type, title, body, target_type, target_id, data)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           RETURNING id, notif_type, title, body, target_type, target_id, data,
+                     read_at IS NOT NULL AS "is_read!", read_at, created_at"#,
         user_id,
-        notif_type,
-        title,
-        body,
-        target_type,
-        target_id
+        new.notif_type,
+        new.title,
+        new.body,
+        new.target_type,
+        new.target_id,
+        new.data
     )
-    .execute(pool)
+    .fetch_one(pool)
     .await?;
-    Ok(())
+
+    if let Some(ws_conns) = ws_conns {
+        push(ws_conns, user_id, &notification).await;
+    }
+    Ok(notification)
 }
 
-/// Best-effort notification — logs on failure, never propagates the error.
+/// Stores the same notification for many users inside the caller's
+/// transaction. Returns `(user_id, notification)` pairs so they can be
+/// `push`ed once the transaction commits.
+pub async fn create_many(
+    conn: &mut PgConnection,
+    user_ids: &[i32],
+    new: &NewNotification<'_>,
+) -> Result<Vec<(i32, Notification)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"INSERT INTO notifications (user_id, notif_type, title, body, target_type, target_id, data)
+           SELECT uid, $2, $3, $4, $5, $6, $7 FROM UNNEST($1::int[]) AS uid
+           RETURNING user_id, id, notif_type, title, body, target_type, target_id, data, read_at, created_at"#,
+        user_ids,
+        new.notif_type,
+        new.title,
+        new.body,
+        new.target_type,
+        new.target_id,
+        new.data
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let notification = Notification {
+                id: r.id,
+                notif_type: r.notif_type,
+                title: r.title,
+                body: r.body,
+                target_type: r.target_type,
+                target_id: r.target_id,
+                data: r.data,
+                is_read: r.read_at.is_some(),
+                read_at: r.read_at,
+                created_at: r.created_at,
+            };
+            (r.user_id, notification)
+        })
+        .collect())
+}
+
+/// Sends a stored notification to the user's open sockets, if any.
+pub async fn push(ws_conns: &WsConnections, user_id: i32, notification: &Notification) {
+    match serde_json::to_value(notification) {
+        Ok(data) => push_to_user(ws_conns, user_id, "notification", data).await,
+        Err(e) => tracing::warn!("Could not serialize notification {}: {}", notification.id, e),
+    }
+}
+
+/// Best-effort notification that is stored but not pushed — for callers that
+/// already push their own socket event (e.g. `new_message`). Logs on failure.
 pub async fn notify_best_effort(
     pool: &PgPool,
     user_id: i32,
@@ -37,12 +126,21 @@ pub async fn notify_best_effort(
     target_type: Option<&str>,
     target_id: Option<i32>,
 ) {
-    if let Err(e) = notify(pool, user_id, notif_type, title, body, target_type, target_id).await {
+    let new = NewNotification {
+        notif_type,
+        title,
+        body,
+        target_type,
+        target_id,
+        data: None,
+    };
+    if let Err(e) = create(pool, None, user_id, &new).await {
         tracing::warn!("Notification creation failed (non-fatal): {}", e);
     }
 }
 
-/// Creates a DB notification AND pushes a real-time WS event.
+/// Best-effort notification that is stored AND pushed as a real-time WS event.
+#[allow(clippy::too_many_arguments)]
 pub async fn notify_and_push(
     pool: &PgPool,
     ws_conns: &WsConnections,
@@ -53,60 +151,22 @@ pub async fn notify_and_push(
     target_type: Option<&str>,
     target_id: Option<i32>,
 ) {
-    notify_best_effort(pool, user_id, notif_type, title, body, target_type, target_id).await;
-    push_to_user(
-        ws_conns,
-        user_id,
-        "notification",
-        json!({
-            "notif_type": notif_type,
-            "title":       title,
-            "body":        body,
-            "target_type": target_type,
-            "target_id":   target_id,
-        }),
-    )
-    .await;
-}
-
-#[allow(dead_code)]
-/// Looks up the owner's user_id for a provider or business, then notifies them.
-pub async fn notify_target_owner(
-    pool: &PgPool,
-    target_type: &str,
-    target_id: i32,
-    notif_type: &str,
-    title: &str,
-    body: &str,
-    ref_type: Option<&str>,
-    ref_id: Option<i32>,
-) {
-    let owner_user_id: Option<i32> = match target_type {
-        "provider" => sqlx::query_scalar!(
-            "SELECT user_id FROM providers WHERE id = $1",
-            target_id
-        )
-        .fetch_optional(pool)
-        .await
-        .ok()
-        .flatten(),
-        "business" => sqlx::query_scalar!(
-            "SELECT user_id FROM businesses WHERE id = $1",
-            target_id
-        )
-        .fetch_optional(pool)
-        .await
-        .ok()
-        .flatten(),
-        _ => None,
+    let new = NewNotification {
+        notif_type,
+        title,
+        body,
+        target_type,
+        target_id,
+        data: None,
     };
-
-    if let Some(uid) = owner_user_id {
-        notify_best_effort(pool, uid, notif_type, title, body, ref_type, ref_id).await;
+    if let Err(e) = create(pool, Some(ws_conns), user_id, &new).await {
+        tracing::warn!("Notification creation failed (non-fatal): {}", e);
     }
 }
 
-/// Looks up the owner's user_id, then notifies + pushes via WebSocket.
+/// Looks up the owner's user_id for a provider or business, then notifies +
+/// pushes via WebSocket.
+#[allow(clippy::too_many_arguments)]
 pub async fn notify_target_owner_and_push(
     pool: &PgPool,
     ws_conns: &WsConnections,
@@ -0,0 +1,123 @@
+use serde::Serialize;
+use sqlx::PgPool;
+
+// Weights of each setup step, adding up to 100. Tune them here; stored
+// scores catch up the next time each provider changes something.
+
+pub const PROFILE_WEIGHT: i16 = 25;
+pub const PHOTO_WEIGHT: i16 = 15;
+pub const AVAILABILITY_WEIGHT: i16 = 15;
+pub const SERVICE_WEIGHT: i16 = 20;
+pub const LOCATION_WEIGHT: i16 = 15;
+pub const PHONE_WEIGHT: i16 = 10;
+
+#[derive(Serialize, Debug)]
+pub struct OnboardingStep {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub weight: i16,
+    pub done: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OnboardingProgress {
+    /// Sum of the weights of the finished steps, out of 100.
+    pub score: i16,
+    pub complete: bool,
+    pub steps: Vec<OnboardingStep>,
+}
+
+/// The provider's setup checklist, computed from their current rows.
+/// `None` if the provider doesn't exist.
+pub async fn progress(pool: &PgPool, provider_id: i32) -> Result<Option<OnboardingProgress>, sqlx::Error> {
+    let Some(row) = sqlx::query!(
+        r#"SELECT
+               (NULLIF(TRIM(p.service_name), '') IS NOT NULL
+                AND NULLIF(TRIM(p.service_description), '') IS NOT NULL
+                AND NULLIF(TRIM(p.category), '') IS NOT NULL
+                AND NULLIF(TRIM(p.location), '') IS NOT NULL
+                AND NULLIF(TRIM(p.phone_number), '') IS NOT NULL
+                AND NULLIF(TRIM(p.email), '') IS NOT NULL) AS "profile!",
+               (p.profile_photo IS NOT NULL OR p.cover_photo IS NOT NULL) AS "photo!",
+               EXISTS (SELECT 1 FROM provider_availability a
+                       WHERE a.provider_id = p.id AND a.is_available) AS "availability!",
+               EXISTS (SELECT 1 FROM services s
+                       WHERE s.target_type = 'provider' AND s.target_id = p.id AND s.is_active) AS "service!",
+               EXISTS (SELECT 1 FROM provider_locations l WHERE l.provider_id = p.id) AS "location!",
+               (p.phone_verified_at IS NOT NULL) AS "phone_verified!"
+           FROM providers p
+           WHERE p.id = $1"#,
+        provider_id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let steps = vec![
+        OnboardingStep {
+            key: "profile",
+            label: "Fill in your name, description, category, town, phone and email",
+            weight: PROFILE_WEIGHT,
+            done: row.profile,
+        },
+        OnboardingStep {
+            key: "photo",
+            label: "Add a profile or cover photo",
+            weight: PHOTO_WEIGHT,
+            done: row.photo,
+        },
+        OnboardingStep {
+            key: "availability",
+            label: "Set the days you're available",
+            weight: AVAILABILITY_WEIGHT,
+            done: row.availability,
+        },
+        OnboardingStep {
+            key: "service",
+            label: "List at least one service",
+            weight: SERVICE_WEIGHT,
+            done: row.service,
+        },
+        OnboardingStep {
+            key: "location",
+            label: "Pin your location on the map",
+            weight: LOCATION_WEIGHT,
+            done: row.location,
+        },
+        OnboardingStep {
+            key: "phone_verified",
+            label: "Verify your phone number",
+            weight: PHONE_WEIGHT,
+            done: row.phone_verified,
+        },
+    ];
+    let score = steps.iter().filter(|s| s.done).map(|s| s.weight).sum();
+    Ok(Some(OnboardingProgress { score, complete: steps.iter().all(|s| s.done), steps }))
+}
+
+/// Recomputes and stores the provider's `onboarding_score`, which
+/// `list_providers` sorts by. Call it after changing any row `progress`
+/// reads. Failures are logged rather than returned: the change itself has
+/// already been saved, and the score catches up on the next one.
+pub async fn refresh_score(pool: &PgPool, provider_id: i32) {
+    let result = async {
+        let Some(progress) = progress(pool, provider_id).await? else {
+            return Ok(());
+        };
+        sqlx::query!(
+            "UPDATE providers SET onboarding_score = $1 WHERE id = $2 AND onboarding_score <> $1",
+            progress.score,
+            provider_id
+        )
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to refresh onboarding score for provider {}: {}", provider_id, e);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::errors::AppError;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PER_PAGE: i64 = 20;
+pub const MAX_PER_PAGE: i64 = 100;
+
+// ── Pagination ────────────────────────────────────────────────────────────────
+
+/// `?page=&per_page=` from the query string. `page` starts at 1 and must be at
+/// least 1; `per_page` defaults to 20 and is capped at 100. Other query
+/// parameters are ignored, so this sits alongside an endpoint's own `Query`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination {
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawPagination {
+    page: Option<String>,
+    per_page: Option<String>,
+}
+
+impl Pagination {
+    /// Validates raw query values. Blank values count as absent.
+    pub fn from_raw(page: Option<&str>, per_page: Option<&str>) -> Result<Pagination, AppError> {
+        let parse = |name: &str, raw: Option<&str>| -> Result<Option<i64>, AppError> {
+            match raw.map(str::trim).filter(|v| !v.is_empty()) {
+                None => Ok(None),
+                Some(v) => v
+                    .parse::<i64>()
+                    .map(Some)
+                    .map_err(|_| AppError::BadRequest(format!("{} must be a whole number", name))),
+            }
+        };
+
+        let page = parse("page", page)?.unwrap_or(1);
+        if page < 1 {
+            return Err(AppError::BadRequest("page must be 1 or more".to_string()));
+        }
+        let per_page = parse("per_page", per_page)?.unwrap_or(DEFAULT_PER_PAGE);
+        if per_page < 1 {
+            return Err(AppError::BadRequest("per_page must be 1 or more".to_string()));
+        }
+        Ok(Pagination {
+            page,
+            per_page: per_page.min(MAX_PER_PAGE),
+        })
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page - 1).saturating_mul(self.per_page)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Pagination
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(raw) = Query::<RawPagination>::try_from_uri(&parts.uri)
+            .map_err(|_| AppError::BadRequest("Invalid query string".to_string()))?;
+        Pagination::from_raw(raw.page.as_deref(), raw.per_page.as_deref())
+    }
+}
+
+/// One page of a list endpoint's results, always serialized as
+/// `{ items, page, per_page, total, has_more }`.
+#[derive(Serialize, Debug)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub page: i64,
+    pub per_page: i64,
+    /// Rows matching the filters across all pages.
+    pub total: i64,
+    pub has_more: bool,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, pagination: Pagination, total: i64) -> Self {
+        Paginated {
+            has_more: pagination.offset() + (items.len() as i64) < total,
+            items,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            total,
+        }
+    }
+}
+
+// ── Sorting ───────────────────────────────────────────────────────────────────
+
+/// A validated `?sort=` value: `field` for ascending, `-field` for descending.
+/// Only names on the endpoint's allow-list are accepted, and the SQL that ends
+/// up in `ORDER BY` comes from that list, never from the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    column: &'static str,
+    pub descending: bool,
+}
+
+impl Sort {
+    /// `allowed` maps public field names to the SQL expression they sort by.
+    /// `default` (in the same `field`/`-field` form) applies when `raw` is
+    /// absent or blank, and must itself be on the list.
+    pub fn parse(
+        raw: Option<&str>,
+        allowed: &[(&str, &'static str)],
+        default: &str,
+    ) -> Result<Sort, AppError> {
+        let raw = raw.map(str::trim).filter(|v| !v.is_empty()).unwrap_or(default);
+        let (name, descending) = match raw.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (raw, false),
+        };
+        allowed
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, column)| Sort { column, descending })
+            .ok_or_else(|| {
+                let names: Vec<&str> = allowed.iter().map(|(field, _)| *field).collect();
+                AppError::BadRequest(format!(
+                    "sort must be one of {} (prefix with '-' for descending)",
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// The `ORDER BY` term, e.g. `avg_rating DESC NULLS LAST`. Callers add
+    /// their own unique tie-breaker so pages don't overlap.
+    pub fn order_by(&self) -> String {
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        format!("{} {} NULLS LAST", self.column, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SORTS: &[(&str, &str)] = &[("name", "p.service_name"), ("rating", "avg_rating")];
+
+    fn page(page: i64, per_page: i64) -> Pagination {
+        Pagination { page, per_page }
+    }
+
+    #[test]
+    fn defaults_apply_when_absent_or_blank() {
+        assert_eq!(Pagination::from_raw(None, None).ok(), Some(Pagination::default()));
+        assert_eq!(Pagination::from_raw(Some(" "), Some("")).ok(), Some(page(1, DEFAULT_PER_PAGE)));
+    }
+
+    #[test]
+    fn page_must_be_one_or_more() {
+        for raw in ["0", "-1"] {
+            assert!(matches!(Pagination::from_raw(Some(raw), None), Err(AppError::BadRequest(_))), "{raw}");
+        }
+        assert!(matches!(Pagination::from_raw(None, Some("0")), Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn per_page_is_capped() {
+        assert_eq!(Pagination::from_raw(Some("3"), Some("500")).ok(), Some(page(3, MAX_PER_PAGE)));
+        assert_eq!(Pagination::from_raw(None, Some("100")).ok(), Some(page(1, 100)));
+    }
+
+    #[test]
+    fn non_numeric_values_are_refused() {
+        for (p, pp) in [(Some("two"), None), (None, Some("ten")), (Some("1.5"), None)] {
+            assert!(matches!(Pagination::from_raw(p, pp), Err(AppError::BadRequest(_))), "{p:?} {pp:?}");
+        }
+    }
+
+    #[test]
+    fn offset_follows_page_and_saturates() {
+        assert_eq!(page(1, 20).offset(), 0);
+        assert_eq!(page(3, 20).offset(), 40);
+        assert_eq!(page(i64::MAX, 100).offset(), i64::MAX);
+    }
+
+    #[test]
+    fn has_more_counts_rows_on_earlier_pages() {
+        assert!(Paginated::new(vec![1, 2], page(1, 2), 5).has_more);
+        assert!(!Paginated::new(vec![5], page(3, 2), 5).has_more);
+        assert!(!Paginated::new(vec![1, 2], page(1, 2), 2).has_more);
+        assert!(!Paginated::<i32>::new(vec![], page(9, 2), 5).has_more);
+    }
+
+    #[test]
+    fn sort_accepts_allowed_fields_and_direction() {
+        let asc = Sort::parse(Some("name"), SORTS, "-rating").expect("name");
+        assert_eq!(asc.order_by(), "p.service_name ASC NULLS LAST");
+        let desc = Sort::parse(Some("-name"), SORTS, "-rating").expect("-name");
+        assert!(desc.descending);
+        assert_eq!(desc.order_by(), "p.service_name DESC NULLS LAST");
+        let default = Sort::parse(Some("  "), SORTS, "-rating").expect("default");
+        assert_eq!(default.order_by(), "avg_rating DESC NULLS LAST");
+    }
+
+    #[test]
+    fn sort_refuses_anything_off_the_allow_list() {
+        for raw in ["name;drop table users", "p.service_name", "--name", "NAME", "rating desc", "1"] {
+            assert!(matches!(Sort::parse(Some(raw), SORTS, "name"), Err(AppError::BadRequest(_))), "{raw}");
+        }
+    }
+}
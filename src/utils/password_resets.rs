@@ -0,0 +1,33 @@
+use crate::utils::jobs::{self, JobReport, PASSWORD_RESET_CLEANUP};
+use chrono::Utc;
+use sqlx::PgPool;
+
+/// Spawns a background loop that deletes expired password reset tokens once
+/// an hour. Expired tokens are already refused; this keeps the table small.
+pub fn start_password_reset_cleanup_task(pool: PgPool, mut trigger: jobs::JobTrigger) {
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&PASSWORD_RESET_CLEANUP, &mut trigger).await {
+            jobs::record_run(&pool, &PASSWORD_RESET_CLEANUP, delete_expired_resets(&pool)).await;
+        }
+    });
+}
+
+pub async fn delete_expired_resets(pool: &PgPool) -> JobReport {
+    let mut report = JobReport::default();
+
+    // expires_at is naive UTC, as written by forgot_password
+    let now = Utc::now().naive_utc();
+    match sqlx::query!("DELETE FROM password_resets WHERE expires_at < $1", now)
+        .execute(pool)
+        .await
+    {
+        Ok(res) if res.rows_affected() > 0 => {
+            tracing::info!("Deleted {} expired password reset tokens", res.rows_affected());
+            report.items_processed += res.rows_affected() as i64;
+        }
+        Ok(_) => {}
+        Err(e) => report.fail("Cleanup of expired password resets failed", e),
+    }
+
+    report
+}
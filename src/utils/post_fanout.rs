@@ -0,0 +1,183 @@
+use crate::utils::jobs::{self, JobReport, POST_FANOUT};
+use crate::utils::notifications::{self, NewNotification};
+use crate::utils::prefs;
+use crate::utils::settings;
+use crate::utils::ws_state::WsConnections;
+use serde_json::json;
+use sqlx::PgPool;
+use std::time::Instant;
+
+/// Posts picked up per pass.
+const POSTS_PER_SWEEP: i64 = 100;
+
+/// Spawns a background loop that notifies followers about newly published posts
+/// every 5 minutes. Followers get at most one post notification per author per day.
+pub fn start_post_fanout_task(pool: PgPool, ws_conns: WsConnections, mut trigger: jobs::JobTrigger) {
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&POST_FANOUT, &mut trigger).await {
+            jobs::record_run(&pool, &POST_FANOUT, fan_out_new_posts(&pool, &ws_conns)).await;
+        }
+    });
+}
+
+struct PendingPost {
+    id: i32,
+    title: Option<String>,
+    author_type: String,
+    author_id: i32,
+    author_name: Option<String>,
+    hidden: bool,
+}
+
+pub async fn fan_out_new_posts(pool: &PgPool, ws_conns: &WsConnections) -> JobReport {
+    let mut report = JobReport::default();
+    let posts = match sqlx::query_as!(
+        PendingPost,
+        r#"SELECT p.id, p.title,
+                  (p.hidden OR user_is_hidden(COALESCE(pr.user_id, b.user_id))) AS "hidden!",
+                  CASE WHEN p.provider_id IS NOT NULL THEN 'provider' ELSE 'business' END AS "author_type!",
+                  COALESCE(p.provider_id, p.business_id)     AS "author_id!",
+                  COALESCE(pr.service_name, b.business_name) AS author_name
+           FROM posts p
+           LEFT JOIN providers  pr ON pr.id = p.provider_id
+           LEFT JOIN businesses b  ON b.id  = p.business_id
+           WHERE p.fanned_out_at IS NULL
+           ORDER BY p.id
+           LIMIT $1"#,
+        POSTS_PER_SWEEP
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            report.fail("Post fan-out query failed", e);
+            return report;
+        }
+    };
+
+    for post in &posts {
+        match fan_out_post(pool, ws_conns, post).await {
+            Ok(()) => report.items_processed += 1,
+            Err(e) => report.fail(&format!("Post fan-out for post {} failed", post.id), e),
+        }
+    }
+
+    report
+}
+
+async fn fan_out_post(
+    pool: &PgPool,
+    ws_conns: &WsConnections,
+    post: &PendingPost,
+) -> Result<(), sqlx::Error> {
+    let started = Instant::now();
+    // Cap on followers notified for a single post so one popular author can't stall the sweep
+    let max_fanout = settings::int(pool, "posts.max_fanout_per_post").await;
+    let mut tx = pool.begin().await?;
+
+    // Posts hidden (or whose author is suspended or deleted) before the sweep reached them
+    // are marked done without notifying anyone
+    // Followers who turned post notifications off altogether are skipped too
+    let notify_by_default = prefs::default_flag(prefs::NOTIFY_ON_POSTS);
+    let recipients: Vec<i32> = if post.hidden {
+        vec![]
+    } else {
+        let followers = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM favorites f
+               WHERE f.target_type = $1 AND f.target_id = $2 AND f.notify_on_posts
+                 AND COALESCE((SELECT (up.prefs->>$3)::boolean
+                               FROM user_preferences up WHERE up.user_id = f.user_id), $4)"#,
+            post.author_type,
+            post.author_id,
+            prefs::NOTIFY_ON_POSTS,
+            notify_by_default
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if followers > max_fanout {
+            tracing::warn!(
+                "Post {} has {} followers; fan-out capped at {}",
+                post.id, followers, max_fanout
+            );
+        }
+
+        // Claim today's slot per follower; anyone already notified about this author today is
+        // skipped before the cap is applied, so it isn't spent on them. Past the cap, followers
+        // are taken in an order hashed from the post id: each post reaches a different slice of
+        // the audience, and a retried pass picks the same one.
+        sqlx::query_scalar!(
+            r#"WITH followers AS (
+                   SELECT f.user_id FROM favorites f
+                   WHERE f.target_type = $1 AND f.target_id = $2 AND f.notify_on_posts
+                     AND COALESCE((SELECT (up.prefs->>$4)::boolean
+                                   FROM user_preferences up WHERE up.user_id = f.user_id), $5)
+                     AND NOT EXISTS (
+                         SELECT 1 FROM post_fanout_log l
+                         WHERE l.user_id = f.user_id AND l.author_type = $1
+                           AND l.author_id = $2 AND l.sent_on = CURRENT_DATE
+                     )
+                   ORDER BY md5(f.user_id::text || ':' || $6::int::text)
+                   LIMIT $3
+               )
+               INSERT INTO post_fanout_log (user_id, author_type, author_id)
+               SELECT user_id, $1, $2 FROM followers
+               ON CONFLICT DO NOTHING
+               RETURNING user_id"#,
+            post.author_type,
+            post.author_id,
+            max_fanout,
+            prefs::NOTIFY_ON_POSTS,
+            notify_by_default,
+            post.id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+    };
+
+    let author_name = post.author_name.as_deref().unwrap_or("A provider you follow");
+    let post_title = post.title.as_deref().unwrap_or("New post");
+    let title = "New Post";
+    let body = format!("{author_name} posted: {}", post_title.trim());
+    let data = json!({
+        "post_id": post.id,
+        "post_title": post.title,
+        "author_type": post.author_type,
+        "author_id": post.author_id,
+        "author_name": post.author_name,
+    });
+
+    let created = if recipients.is_empty() {
+        Vec::new()
+    } else {
+        let new = NewNotification {
+            notif_type: "new_post",
+            title,
+            body: &body,
+            target_type: Some("post"),
+            target_id: Some(post.id),
+            data: Some(data),
+        };
+        notifications::create_many(&mut tx, &recipients, &new).await?
+    };
+
+    sqlx::query!("UPDATE posts SET fanned_out_at = NOW() WHERE id = $1", post.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    for (uid, notification) in &created {
+        notifications::push(ws_conns, *uid, notification).await;
+    }
+
+    tracing::info!(
+        "Post {} fanned out to {} follower(s) in {}ms",
+        post.id,
+        recipients.len(),
+        started.elapsed().as_millis()
+    );
+
+    Ok(())
+}
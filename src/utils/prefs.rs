@@ -0,0 +1,146 @@
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+
+pub const ALLOW_BROADCASTS: &str = "allow_broadcasts";
+pub const NOTIFY_ON_POSTS: &str = "notify_on_posts";
+pub const NOTIFY_ON_FAVORITE: &str = "notify_on_favorite";
+pub const SHOW_LAST_SEEN: &str = "show_last_seen";
+pub const BOOKING_REMINDER_HOURS: &str = "booking_reminder_hours";
+
+pub enum PrefKind {
+    Bool { default: bool },
+    Int { default: i64, min: i64, max: i64 },
+}
+
+pub struct PrefDef {
+    pub key: &'static str,
+    pub kind: PrefKind,
+    pub description: &'static str,
+}
+
+/// Every preference a user may set. Unknown keys are rejected on update.
+pub const KNOWN_PREFS: [PrefDef; 5] = [
+    PrefDef {
+        key: ALLOW_BROADCASTS,
+        kind: PrefKind::Bool { default: true },
+        description: "Show info and warning announcements; critical ones are always shown",
+    },
+    PrefDef {
+        key: NOTIFY_ON_POSTS,
+        kind: PrefKind::Bool { default: true },
+        description: "Notify about new posts from favorites (each favorite can also be muted)",
+    },
+    PrefDef {
+        key: NOTIFY_ON_FAVORITE,
+        kind: PrefKind::Bool { default: true },
+        description: "Notify a listing owner when someone adds their listing to favorites",
+    },
+    PrefDef {
+        key: SHOW_LAST_SEEN,
+        kind: PrefKind::Bool { default: true },
+        description: "Let the people you message see when you were last active",
+    },
+    PrefDef {
+        key: BOOKING_REMINDER_HOURS,
+        kind: PrefKind::Int { default: 24, min: 0, max: 72 },
+        description: "Hours before a booking to send the reminder; 0 turns reminders off",
+    },
+];
+
+pub fn definition(key: &str) -> Option<&'static PrefDef> {
+    KNOWN_PREFS.iter().find(|def| def.key == key)
+}
+
+impl PrefDef {
+    pub fn default_value(&self) -> Value {
+        match self.kind {
+            PrefKind::Bool { default } => Value::Bool(default),
+            PrefKind::Int { default, .. } => Value::from(default),
+        }
+    }
+
+    /// Checks a user-supplied value against the preference's type and bounds.
+    pub fn validate(&self, value: &Value) -> Result<Value, String> {
+        match self.kind {
+            PrefKind::Bool { .. } => value
+                .as_bool()
+                .map(Value::Bool)
+                .ok_or_else(|| format!("{} must be true or false", self.key)),
+            PrefKind::Int { min, max, .. } => match value.as_i64() {
+                Some(v) if (min..=max).contains(&v) => Ok(Value::from(v)),
+                Some(_) => Err(format!("{} must be between {} and {}", self.key, min, max)),
+                None => Err(format!("{} must be a whole number", self.key)),
+            },
+        }
+    }
+}
+
+/// The default of a known on/off preference, for queries that apply it to
+/// many users at once and so read `user_preferences` directly.
+pub fn default_flag(key: &str) -> bool {
+    match definition(key).map(|def| &def.kind) {
+        Some(PrefKind::Bool { default }) => *default,
+        _ => false,
+    }
+}
+
+/// The default of a known numeric preference; see `default_flag`.
+pub fn default_int(key: &str) -> i64 {
+    match definition(key).map(|def| &def.kind) {
+        Some(PrefKind::Int { default, .. }) => *default,
+        _ => 0,
+    }
+}
+
+/// A user's stored values. A failed read falls back to none stored, so
+/// callers get the defaults.
+async fn stored_values(pool: &PgPool, user_id: i32) -> Map<String, Value> {
+    match sqlx::query_scalar!("SELECT prefs FROM user_preferences WHERE user_id = $1", user_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(Value::Object(values))) => values,
+        Ok(_) => Map::new(),
+        Err(e) => {
+            tracing::error!("Failed to load preferences for user {}: {}", user_id, e);
+            Map::new()
+        }
+    }
+}
+
+/// Every known preference for the user, stored values over defaults.
+pub async fn all(pool: &PgPool, user_id: i32) -> Map<String, Value> {
+    let stored = stored_values(pool, user_id).await;
+    KNOWN_PREFS
+        .iter()
+        .map(|def| {
+            let value = stored
+                .get(def.key)
+                .and_then(|v| def.validate(v).ok())
+                .unwrap_or_else(|| def.default_value());
+            (def.key.to_string(), value)
+        })
+        .collect()
+}
+
+/// Reads one known preference, falling back to its declared default.
+pub async fn get(pool: &PgPool, user_id: i32, key: &str) -> Value {
+    let Some(def) = definition(key) else {
+        return Value::Null;
+    };
+    stored_values(pool, user_id)
+        .await
+        .get(key)
+        .and_then(|v| def.validate(v).ok())
+        .unwrap_or_else(|| def.default_value())
+}
+
+/// Reads an on/off preference.
+pub async fn flag(pool: &PgPool, user_id: i32, key: &str) -> bool {
+    get(pool, user_id, key).await.as_bool().unwrap_or_else(|| default_flag(key))
+}
+
+/// Reads a numeric preference.
+pub async fn int(pool: &PgPool, user_id: i32, key: &str) -> i64 {
+    get(pool, user_id, key).await.as_i64().unwrap_or_else(|| default_int(key))
+}
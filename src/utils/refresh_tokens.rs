@@ -0,0 +1,101 @@
+use crate::config;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+
+/// What the database keeps of a refresh token: its hex SHA-256. The token
+/// itself is 32 random bytes, so a plain hash is enough.
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// A token that passed `redeem`.
+pub struct Redeemed {
+    pub id: i32,
+    pub user_id: i32,
+}
+
+/// Why `redeem` refused a token.
+pub enum Refused {
+    Unknown,
+    Expired,
+    /// Already exchanged or logged out. Presenting a rotated token again
+    /// means it was copied, so every session of the user is revoked.
+    Revoked,
+}
+
+/// Creates a refresh token for `user_id`, valid for `jwt_ttl_days`. Returns
+/// the token to hand to the client and its row id.
+pub async fn issue(conn: &mut PgConnection, user_id: i32) -> Result<(String, i32), sqlx::Error> {
+    let token = hex::encode(rand::thread_rng().r#gen::<[u8; 32]>());
+    let expires_at: DateTime<Utc> = Utc::now() + Duration::days(config::get().jwt_ttl_days);
+    let id = sqlx::query_scalar!(
+        "INSERT INTO refresh_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, $3) RETURNING id",
+        user_id,
+        hash(&token),
+        expires_at
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok((token, id))
+}
+
+/// Checks `token` and locks its row for the rest of the transaction, so two
+/// refreshes racing with the same token can't both succeed.
+pub async fn redeem(conn: &mut PgConnection, token: &str) -> Result<Result<Redeemed, Refused>, sqlx::Error> {
+    let Some(row) = sqlx::query!(
+        "SELECT id, user_id, expires_at, revoked_at FROM refresh_tokens WHERE token_hash = $1 FOR UPDATE",
+        hash(token)
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(Err(Refused::Unknown));
+    };
+
+    if row.revoked_at.is_some() {
+        tracing::warn!("Revoked refresh token {} presented again; revoking all sessions of user {}", row.id, row.user_id);
+        revoke_all(conn, row.user_id).await?;
+        return Ok(Err(Refused::Revoked));
+    }
+    if row.expires_at < Utc::now() {
+        return Ok(Err(Refused::Expired));
+    }
+    Ok(Ok(Redeemed { id: row.id, user_id: row.user_id }))
+}
+
+/// Marks a redeemed token used, pointing at the token that replaced it.
+pub async fn rotate(conn: &mut PgConnection, id: i32, replaced_by: i32) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = NOW(), replaced_by = $2 WHERE id = $1",
+        id,
+        replaced_by
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Ends the session `token` belongs to. Unknown or already revoked tokens
+/// are ignored.
+pub async fn revoke(conn: &mut PgConnection, token: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = NOW() WHERE token_hash = $1 AND revoked_at IS NULL",
+        hash(token)
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Ends every session of `user_id`, e.g. after a password reset.
+pub async fn revoke_all(conn: &mut PgConnection, user_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        user_id
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
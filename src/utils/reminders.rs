@@ -1,49 +1,66 @@
-use crate::utils::notifications::notify;
-use crate::utils::sms::{booking_reminder_sms, send_sms_best_effort, SmsConfig};
+use crate::utils::jobs::{self, JobReport, BOOKING_REMINDERS};
+use crate::utils::notifications::notify_and_push;
+use crate::utils::prefs::{self, PrefKind};
+use crate::utils::sms::{booking_reminder_sms, client_sms_phone, send_sms_best_effort, SmsConfig};
+use crate::utils::ws_state::WsConnections;
 use chrono::{Duration, Utc};
 use sqlx::PgPool;
 
 /// Spawns a background loop that checks for upcoming bookings every 15 minutes
-/// and sends a reminder SMS + in-app notification ~24 hours before the appointment.
-pub fn start_reminder_task(pool: PgPool) {
+/// and sends a reminder SMS + in-app notification ahead of the appointment,
+/// 24 hours by default or as the client set in `booking_reminder_hours`.
+pub fn start_reminder_task(pool: PgPool, ws_conns: WsConnections, mut trigger: jobs::JobTrigger) {
     tokio::spawn(async move {
-        loop {
-            // Wait 15 minutes between each pass (first run is 15 min after startup)
-            tokio::time::sleep(std::time::Duration::from_secs(15 * 60)).await;
-            send_pending_reminders(&pool).await;
+        // Wait 15 minutes between each pass (first run is 15 min after startup)
+        while jobs::wait_for_next_run(&BOOKING_REMINDERS, &mut trigger).await {
+            jobs::record_run(&pool, &BOOKING_REMINDERS, send_pending_reminders(&pool, &ws_conns)).await;
         }
     });
 }
 
-async fn send_pending_reminders(pool: &PgPool) {
+pub async fn send_pending_reminders(pool: &PgPool, ws_conns: &WsConnections) -> JobReport {
+    let mut report = JobReport::default();
     let now = Utc::now().naive_utc();
-    // 2-hour window centred on the 24-hour mark prevents both missed and duplicate sends
-    let window_start = now + Duration::hours(23);
-    let window_end = now + Duration::hours(25);
+    // Each client picks how many hours ahead (booking_reminder_hours, 0 = off).
+    // A 2-hour window centred on that mark prevents both missed and duplicate sends.
+    let max_hours = match prefs::definition(prefs::BOOKING_REMINDER_HOURS).map(|def| &def.kind) {
+        Some(PrefKind::Int { max, .. }) => *max,
+        _ => prefs::default_int(prefs::BOOKING_REMINDER_HOURS),
+    };
+    let horizon = now + Duration::hours(max_hours + 1);
 
     let bookings = match sqlx::query!(
         r#"SELECT b.id, b.client_id, b.scheduled_time,
                   b.service_description, b.target_type, b.target_id
            FROM bookings b
+           CROSS JOIN LATERAL (
+               SELECT COALESCE((SELECT (up.prefs->>$3)::int FROM user_preferences up
+                                WHERE up.user_id = b.client_id), $4::int) AS hours
+           ) pref
            WHERE b.scheduled_time >= $1
              AND b.scheduled_time <= $2
-             AND b.status NOT IN ('cancelled', 'completed')
-             AND b.reminder_sent = false"#,
-        window_start,
-        window_end
+             AND b.status IN ('pending', 'confirmed')
+             AND b.reminder_sent = false
+             AND pref.hours > 0
+             AND b.scheduled_time BETWEEN $1 + make_interval(hours => pref.hours - 1)
+                                      AND $1 + make_interval(hours => pref.hours + 1)"#,
+        now,
+        horizon,
+        prefs::BOOKING_REMINDER_HOURS,
+        prefs::default_int(prefs::BOOKING_REMINDER_HOURS) as i32
     )
     .fetch_all(pool)
     .await
     {
         Ok(rows) => rows,
         Err(e) => {
-            tracing::error!("Reminder query failed: {}", e);
-            return;
+            report.fail("Reminder query failed", e);
+            return report;
         }
     };
 
     if bookings.is_empty() {
-        return;
+        return report;
     }
 
     tracing::info!("Sending reminders for {} upcoming bookings", bookings.len());
@@ -55,11 +72,12 @@ async fn send_pending_reminders(pool: &PgPool) {
         let service = booking.service_description.as_deref().unwrap_or("your appointment");
 
         // In-app notification (always attempted)
-        let _ = notify(
+        notify_and_push(
             pool,
+            ws_conns,
             booking.client_id,
             "booking_reminder",
-            "Upcoming Booking Tomorrow",
+            "Upcoming Booking",
             &format!(
                 "Reminder: you have a booking #{} for {} scheduled for {}",
                 booking.id, service, scheduled_str
@@ -69,19 +87,9 @@ async fn send_pending_reminders(pool: &PgPool) {
         )
         .await;
 
-        // SMS notification (only if AT is configured and client has a payment phone)
+        // SMS notification (only if AT is configured, the client has a phone and hasn't opted out)
         if let Some(ref cfg) = sms_cfg {
-            let phone = sqlx::query_scalar!(
-                "SELECT phone_number FROM payments WHERE booking_id = $1
-                 ORDER BY created_at DESC LIMIT 1",
-                booking.id
-            )
-            .fetch_optional(pool)
-            .await
-            .ok()
-            .flatten();
-
-            if let Some(phone) = phone {
+            if let Some(phone) = client_sms_phone(pool, booking.id).await {
                 let msg = booking_reminder_sms(booking.id, &scheduled_str, service);
                 send_sms_best_effort(cfg, &phone, &msg).await;
             }
@@ -98,5 +106,8 @@ async fn send_pending_reminders(pool: &PgPool) {
         {
             tracing::warn!("Failed to mark booking {} as reminded: {}", booking.id, e);
         }
+        report.items_processed += 1;
     }
+
+    report
 }
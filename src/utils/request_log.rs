@@ -0,0 +1,76 @@
+use crate::extractors::current_user::bearer_token;
+use crate::utils::jwt::decode_jwt;
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue, Request, header},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::{Instrument, field};
+use uuid::Uuid;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest incoming `X-Request-Id` we keep; anything longer gets a fresh id.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Reuses the caller's id when it is short printable ASCII, so a request can
+/// be followed across the proxy and this service.
+fn incoming_request_id(request: &Request<Body>) -> Option<String> {
+    let id = request.headers().get(&REQUEST_ID_HEADER)?.to_str().ok()?.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|b| b.is_ascii_graphic());
+    valid.then(|| id.to_string())
+}
+
+/// Runs every request inside a `request` span carrying its id, method, path
+/// and (when a valid Bearer token is sent) user id, so anything logged while
+/// handling it — including errors turned into responses by `AppError` — can be
+/// correlated. Logs one line per request with status and latency, and echoes
+/// the id back in `X-Request-Id`.
+///
+/// Only the path is logged: query strings can hold one-time tokens.
+pub async fn log_requests(request: Request<Body>, next: Next) -> Response {
+    let started = Instant::now();
+    let request_id = incoming_request_id(&request).unwrap_or_else(|| Uuid::new_v4().to_string());
+    let user_id = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(bearer_token)
+        .and_then(|token| decode_jwt(token).ok())
+        .and_then(|claims| claims.sub.parse::<i32>().ok());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+        user_id = field::Empty,
+        status = field::Empty,
+        latency_ms = field::Empty,
+    );
+    if let Some(user_id) = user_id {
+        span.record("user_id", user_id);
+    }
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+
+    let status = response.status();
+    span.record("status", status.as_u16());
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+    span.in_scope(|| {
+        if status.is_server_error() {
+            tracing::warn!("request failed");
+        } else {
+            tracing::info!("request completed");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
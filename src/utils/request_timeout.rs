@@ -0,0 +1,43 @@
+use crate::config;
+use crate::errors::AppError;
+use axum::{
+    body::Body,
+    http::{Method, Request, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+
+/// Uploads stream large bodies over slow connections, so they get
+/// `UPLOAD_TIMEOUT_SECONDS` instead of the usual limit. Chunk uploads are
+/// matched with or without the API prefix.
+fn is_upload(request: &Request<Body>, api_prefix: &str) -> bool {
+    let multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim_start().to_ascii_lowercase().starts_with("multipart/"));
+    let path = request.uri().path();
+    let path = path.strip_prefix(api_prefix).unwrap_or(path);
+    let upload_chunk = request.method() == Method::PUT && path.starts_with("/attachments/uploads/");
+    multipart || upload_chunk
+}
+
+/// Answers with a 504 when a handler hasn't produced a response in time, so a
+/// stuck query or upstream call can't hold the client (and a connection)
+/// forever. The limit covers the time to the response head; a download that
+/// has started streaming is not cut off.
+pub async fn enforce_timeout(request: Request<Body>, next: Next) -> Response {
+    let config = config::get();
+    let limit: Duration = if is_upload(&request, &config.api_prefix) {
+        config.upload_timeout
+    } else {
+        config.request_timeout
+    };
+
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => AppError::Timeout(format!("The request did not finish within {}s", limit.as_secs()))
+            .into_response(),
+    }
+}
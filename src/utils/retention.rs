@@ -0,0 +1,155 @@
+use crate::utils::jobs::{self, JobReport, RETENTION};
+use crate::utils::settings;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// How long a row lives in a table that would otherwise grow forever. Rows
+/// older than the policy's setting (in days) are deleted by the retention job.
+#[derive(Serialize, Debug)]
+pub struct RetentionPolicy {
+    pub table: &'static str,
+    /// Platform setting holding the age limit in days; 0 keeps rows forever.
+    pub setting: &'static str,
+    /// Column the age is measured from.
+    pub age_column: &'static str,
+    /// Further condition a row must meet to be deleted, if any.
+    pub condition: Option<&'static str>,
+}
+
+/// Every table the retention job prunes. Table and column names are spliced
+/// into SQL, so they only ever come from here.
+pub const RETENTION_POLICIES: [RetentionPolicy; 6] = [
+    RetentionPolicy {
+        table: "notifications",
+        setting: "retention.notifications_days",
+        age_column: "created_at",
+        // Unread notifications stay until the user sees them
+        condition: Some("read_at IS NOT NULL"),
+    },
+    RetentionPolicy {
+        table: "post_view_events",
+        setting: "retention.post_view_events_days",
+        age_column: "viewed_on",
+        condition: None,
+    },
+    RetentionPolicy {
+        table: "webhook_deliveries",
+        setting: "retention.webhook_deliveries_days",
+        age_column: "created_at",
+        // Deliveries still being retried are left alone
+        condition: Some("status <> 'pending'"),
+    },
+    RetentionPolicy {
+        table: "search_queries",
+        setting: "retention.search_queries_days",
+        age_column: "created_at",
+        condition: None,
+    },
+    RetentionPolicy {
+        table: "email_verification_tokens",
+        setting: "retention.email_verification_tokens_days",
+        age_column: "expires_at",
+        condition: None,
+    },
+    RetentionPolicy {
+        table: "refresh_tokens",
+        setting: "retention.refresh_tokens_days",
+        // Rotated tokens are kept until they expire, so reuse is still caught
+        age_column: "expires_at",
+        condition: None,
+    },
+];
+
+/// Tables holding records the platform must keep. A policy naming one of
+/// these is refused at run time, whatever its setting says.
+pub const PROTECTED_TABLES: [&str; 8] = [
+    "audit_log",
+    "booking_events",
+    "bookings",
+    "dispute_evidence",
+    "payments",
+    "payout_requests",
+    "reviews",
+    "wallet_transactions",
+];
+
+/// Rows deleted per statement, so no delete holds its locks for long.
+const DELETE_BATCH: i64 = 1000;
+
+/// Pause between batches, to leave room for other writers.
+const BATCH_PAUSE: Duration = Duration::from_millis(50);
+
+/// Batches per table per run. Anything left over goes on the next run.
+const MAX_BATCHES: usize = 500;
+
+/// Spawns a background loop that applies the retention policies once a day.
+pub fn start_retention_task(pool: PgPool, mut trigger: jobs::JobTrigger) {
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&RETENTION, &mut trigger).await {
+            jobs::record_run(&pool, &RETENTION, run_retention(&pool)).await;
+        }
+    });
+}
+
+/// One pass over every policy. The rows deleted from each table are kept in
+/// the run's details, keyed by table.
+pub async fn run_retention(pool: &PgPool) -> JobReport {
+    let mut report = JobReport::default();
+
+    for policy in &RETENTION_POLICIES {
+        if PROTECTED_TABLES.contains(&policy.table) {
+            report.fail("Retention", format!("refusing to prune protected table {}", policy.table));
+            continue;
+        }
+        let days = settings::int(pool, policy.setting).await;
+        if days <= 0 {
+            continue;
+        }
+
+        match prune(pool, policy, days).await {
+            Ok(deleted) => {
+                if deleted > 0 {
+                    tracing::info!("Retention: deleted {} rows from {}", deleted, policy.table);
+                }
+                report.items_processed += deleted as i64;
+                report.detail(policy.table, deleted);
+            }
+            Err(e) => report.fail(&format!("Retention of {} failed", policy.table), e),
+        }
+    }
+
+    report
+}
+
+/// Deletes the policy's expired rows a batch at a time. Returns how many
+/// went.
+async fn prune(pool: &PgPool, policy: &RetentionPolicy, days: i64) -> Result<u64, sqlx::Error> {
+    let condition = policy.condition.map(|c| format!(" AND ({c})")).unwrap_or_default();
+    // ctid, as not every table here has a primary key
+    let sql = format!(
+        "DELETE FROM {table} WHERE ctid = ANY(ARRAY(
+             SELECT ctid FROM {table}
+             WHERE {column} < NOW() - make_interval(days => $1){condition}
+             LIMIT $2
+         ))",
+        table = policy.table,
+        column = policy.age_column,
+    );
+
+    let mut deleted = 0;
+    for _ in 0..MAX_BATCHES {
+        let batch = sqlx::query(&sql)
+            .bind(days as i32)
+            .bind(DELETE_BATCH)
+            .execute(pool)
+            .await?
+            .rows_affected();
+        deleted += batch;
+        if batch < DELETE_BATCH as u64 {
+            break;
+        }
+        tokio::time::sleep(BATCH_PAUSE).await;
+    }
+    Ok(deleted)
+}
@@ -0,0 +1,211 @@
+//! Reference data for bootstrapping a fresh environment. The data files live in
+//! `seed/` and are compiled into the binary.
+//!
+//! The location set carries all 47 counties and 290 constituencies; wards are
+//! included for Nairobi and Mombasa only. Add more to `seed/kenya_locations.json`
+//! as they're needed — re-running with `force` only inserts what is missing.
+
+use crate::errors::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+
+const KENYA_LOCATIONS_JSON: &str = include_str!("../../seed/kenya_locations.json");
+const CATEGORIES_JSON: &str = include_str!("../../seed/categories.json");
+
+/// Named groups of tables that are seeded together.
+pub const SEED_SETS: [&str; 2] = ["locations", "categories"];
+
+#[derive(Deserialize)]
+struct LocationSeed {
+    counties: Vec<CountySeed>,
+}
+
+#[derive(Deserialize)]
+struct CountySeed {
+    name: String,
+    constituencies: Vec<ConstituencySeed>,
+}
+
+#[derive(Deserialize)]
+struct ConstituencySeed {
+    name: String,
+    wards: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CategorySeed {
+    categories: Vec<ParentCategorySeed>,
+}
+
+#[derive(Deserialize)]
+struct ParentCategorySeed {
+    name: String,
+    subcategories: Vec<String>,
+}
+
+/// Rows inserted per table by one seed run.
+#[derive(Serialize, Default, Debug)]
+pub struct SeedCounts {
+    pub counties: u64,
+    pub constituencies: u64,
+    pub wards: u64,
+    pub categories: u64,
+}
+
+/// Current row count of each seeded table, grouped by seed set.
+#[derive(Serialize, Debug)]
+pub struct SeedSetStatus {
+    pub set: &'static str,
+    pub present: bool,
+    pub tables: serde_json::Value,
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(json: &str, file: &str) -> AppResult<T> {
+    serde_json::from_str(json)
+        .map_err(|e| AppError::Internal(format!("Embedded seed file {file} is invalid: {e}")))
+}
+
+/// True when any table in the set already has rows.
+pub async fn set_has_rows(pool: &PgPool, set: &str) -> Result<bool, sqlx::Error> {
+    let present = match set {
+        "locations" => sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM counties)
+                   OR EXISTS (SELECT 1 FROM constituencies)
+                   OR EXISTS (SELECT 1 FROM wards) AS "present!""#
+        )
+        .fetch_one(pool)
+        .await?,
+        _ => sqlx::query_scalar!(r#"SELECT EXISTS (SELECT 1 FROM categories) AS "present!""#)
+            .fetch_one(pool)
+            .await?,
+    };
+    Ok(present)
+}
+
+pub async fn seed_status(pool: &PgPool) -> Result<Vec<SeedSetStatus>, sqlx::Error> {
+    let counts = sqlx::query!(
+        r#"SELECT
+             (SELECT COUNT(*) FROM counties)       AS "counties!",
+             (SELECT COUNT(*) FROM constituencies) AS "constituencies!",
+             (SELECT COUNT(*) FROM wards)          AS "wards!",
+             (SELECT COUNT(*) FROM categories)     AS "categories!""#
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(vec![
+        SeedSetStatus {
+            set: "locations",
+            present: counts.counties + counts.constituencies + counts.wards > 0,
+            tables: serde_json::json!({
+                "counties": counts.counties,
+                "constituencies": counts.constituencies,
+                "wards": counts.wards,
+            }),
+        },
+        SeedSetStatus {
+            set: "categories",
+            present: counts.categories > 0,
+            tables: serde_json::json!({ "categories": counts.categories }),
+        },
+    ])
+}
+
+/// Inserts the county → constituency → ward hierarchy, skipping rows that
+/// already exist by name under the same parent.
+pub async fn seed_locations(conn: &mut PgConnection, counts: &mut SeedCounts) -> AppResult<()> {
+    let seed: LocationSeed = parse(KENYA_LOCATIONS_JSON, "kenya_locations.json")?;
+
+    for county in &seed.counties {
+        let inserted = sqlx::query_scalar!(
+            "INSERT INTO counties (name) VALUES ($1) ON CONFLICT (name) DO NOTHING RETURNING id",
+            county.name
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        let county_id = match inserted {
+            Some(id) => {
+                counts.counties += 1;
+                id
+            }
+            None => sqlx::query_scalar!("SELECT id FROM counties WHERE name = $1", county.name)
+                .fetch_one(&mut *conn)
+                .await?,
+        };
+
+        for constituency in &county.constituencies {
+            let inserted = sqlx::query_scalar!(
+                r#"INSERT INTO constituencies (name, county_id)
+                   SELECT $1, $2
+                   WHERE NOT EXISTS (SELECT 1 FROM constituencies WHERE name = $1 AND county_id = $2)
+                   RETURNING id"#,
+                constituency.name,
+                county_id
+            )
+            .fetch_optional(&mut *conn)
+            .await?;
+            let constituency_id = match inserted {
+                Some(id) => {
+                    counts.constituencies += 1;
+                    id
+                }
+                None => sqlx::query_scalar!(
+                    "SELECT id FROM constituencies WHERE name = $1 AND county_id = $2",
+                    constituency.name,
+                    county_id
+                )
+                .fetch_one(&mut *conn)
+                .await?,
+            };
+
+            let wards = sqlx::query!(
+                r#"INSERT INTO wards (name, constituency_id)
+                   SELECT w.name, $2
+                   FROM UNNEST($1::text[]) AS w(name)
+                   WHERE NOT EXISTS (SELECT 1 FROM wards WHERE name = w.name AND constituency_id = $2)"#,
+                &constituency.wards,
+                constituency_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            counts.wards += wards.rows_affected();
+        }
+    }
+    Ok(())
+}
+
+/// Inserts the starter category tree. Category names are globally unique, so
+/// existing names are left untouched wherever they sit.
+pub async fn seed_categories(conn: &mut PgConnection, counts: &mut SeedCounts) -> AppResult<()> {
+    let seed: CategorySeed = parse(CATEGORIES_JSON, "categories.json")?;
+
+    for parent in &seed.categories {
+        let inserted = sqlx::query_scalar!(
+            "INSERT INTO categories (name, parent_id) VALUES ($1, NULL) ON CONFLICT (name) DO NOTHING RETURNING id",
+            parent.name
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        let parent_id = match inserted {
+            Some(id) => {
+                counts.categories += 1;
+                id
+            }
+            None => sqlx::query_scalar!("SELECT id FROM categories WHERE name = $1", parent.name)
+                .fetch_one(&mut *conn)
+                .await?,
+        };
+
+        let subs = sqlx::query!(
+            r#"INSERT INTO categories (name, parent_id)
+               SELECT s.name, $2 FROM UNNEST($1::text[]) AS s(name)
+               ON CONFLICT (name) DO NOTHING"#,
+            &parent.subcategories,
+            parent_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        counts.categories += subs.rows_affected();
+    }
+    Ok(())
+}
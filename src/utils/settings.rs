@@ -0,0 +1,288 @@
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long values read from `platform_settings` are reused before the table
+/// is read again. Changes made through `/admin/settings` on another instance
+/// take effect within this window.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+static CACHE: Mutex<Option<(Instant, HashMap<String, Value>)>> = Mutex::new(None);
+
+pub struct SettingDef {
+    pub key: &'static str,
+    pub default: i64,
+    pub min: i64,
+    pub max: i64,
+    pub description: &'static str,
+}
+
+/// Every setting an admin may change. Unknown keys are rejected on update.
+pub const KNOWN_SETTINGS: [SettingDef; 22] = [
+    SettingDef {
+        key: "bookings.max_pending_per_client",
+        default: 10,
+        min: 1,
+        max: 1000,
+        description: "Open (pending) bookings a client may hold at once",
+    },
+    SettingDef {
+        key: "services.max_per_target",
+        default: 100,
+        min: 1,
+        max: 10_000,
+        description: "Services a single provider or business may list",
+    },
+    SettingDef {
+        key: "attachments.max_files_per_upload",
+        default: 10,
+        min: 1,
+        max: 100,
+        description: "Files accepted in one attachment upload request",
+    },
+    SettingDef {
+        key: "attachments.max_files_per_target",
+        default: 50,
+        min: 1,
+        max: 100_000,
+        description: "Attachments a single provider or business may keep",
+    },
+    SettingDef {
+        key: "attachments.max_bytes_per_target",
+        default: 200 * 1024 * 1024,
+        min: 1024,
+        max: 100 * 1024 * 1024 * 1024,
+        description: "Total attachment storage a single provider or business may use, in bytes",
+    },
+    SettingDef {
+        key: "uploads.max_image_bytes",
+        default: 5 * 1024 * 1024,
+        min: 1024,
+        max: 1024 * 1024 * 1024,
+        description: "Largest single image (attachments, profile and cover photos, logos), in bytes",
+    },
+    SettingDef {
+        key: "uploads.max_video_bytes",
+        default: 50 * 1024 * 1024,
+        min: 1024,
+        max: 1024 * 1024 * 1024,
+        description: "Largest single video attachment, in bytes",
+    },
+    SettingDef {
+        key: "uploads.max_video_seconds",
+        default: 60,
+        min: 1,
+        max: 3600,
+        description: "Longest video attachment accepted, in seconds",
+    },
+    SettingDef {
+        key: "uploads.max_request_bytes",
+        default: 100 * 1024 * 1024,
+        min: 1024,
+        max: 4 * 1024 * 1024 * 1024,
+        description: "Total bytes accepted across all files in one upload request",
+    },
+    SettingDef {
+        key: "posts.max_fanout_per_post",
+        default: 5000,
+        min: 0,
+        max: 1_000_000,
+        description: "Followers notified about a new post; past this, each post reaches a different subset",
+    },
+    SettingDef {
+        key: "users.deletion_retention_days",
+        default: 30,
+        min: 0,
+        max: 3650,
+        description: "Days a soft-deleted account stays restorable before it is purged",
+    },
+    SettingDef {
+        key: "admin.export_row_cap",
+        default: 100_000,
+        min: 1,
+        max: 10_000_000,
+        description: "Largest dataset /admin/export will stream",
+    },
+    SettingDef {
+        key: "listings.require_review",
+        default: 0,
+        min: 0,
+        max: 1,
+        description: "1 to hold newly onboarded providers and businesses for admin review",
+    },
+    SettingDef {
+        key: "storage.gc_delete_orphans",
+        default: 0,
+        min: 0,
+        max: 1,
+        description: "1 to let the weekly storage sweep delete orphaned files; 0 only reports them",
+    },
+    SettingDef {
+        key: "search.log_sample_percent",
+        default: 10,
+        min: 0,
+        max: 100,
+        description: "Percentage of /search queries recorded in search_queries",
+    },
+    SettingDef {
+        key: "retention.notifications_days",
+        default: 90,
+        min: 0,
+        max: 3650,
+        description: "Days read notifications are kept; 0 keeps them forever",
+    },
+    SettingDef {
+        key: "retention.post_view_events_days",
+        default: 90,
+        min: 0,
+        max: 3650,
+        description: "Days per-viewer post view records are kept; 0 keeps them forever",
+    },
+    SettingDef {
+        key: "retention.webhook_deliveries_days",
+        default: 30,
+        min: 0,
+        max: 3650,
+        description: "Days finished webhook deliveries are kept; 0 keeps them forever",
+    },
+    SettingDef {
+        key: "retention.search_queries_days",
+        default: 180,
+        min: 0,
+        max: 3650,
+        description: "Days logged search queries are kept; 0 keeps them forever",
+    },
+    SettingDef {
+        key: "retention.email_verification_tokens_days",
+        default: 7,
+        min: 0,
+        max: 3650,
+        description: "Days expired email verification tokens are kept; 0 keeps them forever",
+    },
+    SettingDef {
+        key: "retention.refresh_tokens_days",
+        default: 7,
+        min: 0,
+        max: 3650,
+        description: "Days expired refresh tokens are kept; 0 keeps them forever",
+    },
+    SettingDef {
+        key: MAINTENANCE_READ_ONLY,
+        default: 0,
+        min: 0,
+        max: 1,
+        description: "1 to refuse writes outside /admin while maintenance runs",
+    },
+];
+
+/// Read by `utils::maintenance` on its own, shorter, cache.
+pub const MAINTENANCE_READ_ONLY: &str = "maintenance.read_only";
+
+pub fn definition(key: &str) -> Option<&'static SettingDef> {
+    KNOWN_SETTINGS.iter().find(|def| def.key == key)
+}
+
+impl SettingDef {
+    /// Checks an admin-supplied value against the setting's bounds. Booleans
+    /// are accepted as 1/0 for on/off switches.
+    pub fn validate(&self, value: &Value) -> Result<i64, String> {
+        match value.as_i64().or_else(|| value.as_bool().map(i64::from)) {
+            Some(v) if (self.min..=self.max).contains(&v) => Ok(v),
+            Some(_) => Err(format!("{} must be between {} and {}", self.key, self.min, self.max)),
+            None => Err(format!("{} must be an integer", self.key)),
+        }
+    }
+}
+
+/// The cached values, unless they are older than `CACHE_TTL`.
+fn cached_values() -> Option<HashMap<String, Value>> {
+    match CACHE.lock().unwrap().as_ref() {
+        Some((at, values)) if at.elapsed() < CACHE_TTL => Some(values.clone()),
+        _ => None,
+    }
+}
+
+/// Current stored values, from the cache when it is fresh. A failed read
+/// falls back to an empty map so callers get their defaults.
+async fn stored_values(pool: &PgPool) -> HashMap<String, Value> {
+    if let Some(values) = cached_values() {
+        return values;
+    }
+
+    match sqlx::query!("SELECT key, value FROM platform_settings")
+        .fetch_all(pool)
+        .await
+    {
+        Ok(rows) => {
+            let values: HashMap<String, Value> = rows.into_iter().map(|r| (r.key, r.value)).collect();
+            *CACHE.lock().unwrap() = Some((Instant::now(), values.clone()));
+            values
+        }
+        Err(e) => {
+            tracing::error!("Failed to load platform settings: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+pub async fn get_i64(pool: &PgPool, key: &str, default: i64) -> i64 {
+    stored_values(pool)
+        .await
+        .get(key)
+        .and_then(Value::as_i64)
+        .unwrap_or(default)
+}
+
+/// Reads a known setting, falling back to its declared default.
+pub async fn int(pool: &PgPool, key: &str) -> i64 {
+    let default = definition(key).map(|def| def.default).unwrap_or_default();
+    get_i64(pool, key, default).await
+}
+
+/// Reads an on/off setting; any non-zero value is on.
+pub async fn flag(pool: &PgPool, key: &str) -> bool {
+    int(pool, key).await != 0
+}
+
+/// Drops the cached values so this instance sees an update immediately.
+pub fn invalidate() {
+    *CACHE.lock().unwrap() = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cache_from(age: Duration) {
+        let values = HashMap::from([("services.max_per_target".to_string(), json!(1))]);
+        *CACHE.lock().unwrap() = Some((Instant::now() - age, values));
+    }
+
+    #[test]
+    fn cached_values_are_served_until_the_ttl_runs_out() {
+        cache_from(Duration::ZERO);
+        assert_eq!(cached_values().expect("fresh")["services.max_per_target"], 1);
+        cache_from(CACHE_TTL - Duration::from_secs(1));
+        assert!(cached_values().is_some());
+
+        cache_from(CACHE_TTL);
+        assert!(cached_values().is_none());
+
+        cache_from(Duration::ZERO);
+        invalidate();
+        assert!(cached_values().is_none());
+    }
+
+    #[test]
+    fn values_outside_the_bounds_are_rejected() {
+        let def = definition("services.max_per_target").expect("known setting");
+        assert_eq!(def.validate(&json!(5)), Ok(5));
+        assert!(def.validate(&json!(0)).is_err());
+        assert!(def.validate(&json!(def.max + 1)).is_err());
+        assert!(def.validate(&json!("5")).is_err());
+        assert_eq!(definition("maintenance.read_only").expect("known").validate(&json!(true)), Ok(1));
+    }
+}
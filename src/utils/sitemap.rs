@@ -0,0 +1,173 @@
+use crate::config;
+use crate::utils::jobs::{self, JobReport, SITEMAP};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+/// URLs per sitemap file. The protocol allows 50,000; smaller files are
+/// quicker for crawlers to fetch and for us to rewrite.
+pub const URLS_PER_FILE: usize = 10_000;
+
+/// The document crawlers are pointed at: a `urlset` while everything fits in
+/// one file, otherwise a `sitemapindex` of `sitemap-N.xml` files.
+pub const ROOT_FILE: &str = "sitemap.xml";
+
+const SITEMAP_NS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+/// Spawns a background loop that rebuilds the sitemap every six hours, so
+/// delisted, hidden or suspended profiles, and their posts, drop out on the
+/// next pass.
+pub fn start_sitemap_task(pool: PgPool, mut trigger: jobs::JobTrigger) {
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&SITEMAP, &mut trigger).await {
+            jobs::record_run(&pool, &SITEMAP, run_sitemap(&pool)).await;
+        }
+    });
+}
+
+async fn run_sitemap(pool: &PgPool) -> JobReport {
+    let mut report = JobReport::default();
+    match regenerate(pool, &config::get().frontend_url, URLS_PER_FILE).await {
+        Ok(urls) => {
+            tracing::info!("Sitemap rebuilt with {} URLs", urls);
+            report.items_processed = urls as i64;
+        }
+        Err(e) => report.fail("Sitemap rebuild failed", e),
+    }
+    report
+}
+
+struct SitemapUrl {
+    path: String,
+    lastmod: Option<NaiveDate>,
+}
+
+/// Public profile pages of listed providers and businesses (approved,
+/// onboarded, not suspended and owned by a visible user), and the pages of
+/// their posts that haven't been hidden by moderators.
+async fn listed_urls(pool: &PgPool) -> Result<Vec<SitemapUrl>, sqlx::Error> {
+    sqlx::query_as!(
+        SitemapUrl,
+        r#"WITH listed AS (
+               SELECT 'provider' AS kind, p.id, GREATEST(p.created_at::date, p.listing_reviewed_at::date) AS lastmod
+               FROM providers p
+               WHERE p.approved = TRUE
+                 AND p.onboarding_completed = TRUE
+                 AND p.listing_status = 'approved'
+                 AND (p.suspended_until IS NULL OR p.suspended_until <= NOW())
+                 AND NOT user_is_hidden(p.user_id)
+               UNION ALL
+               SELECT 'business', b.id, GREATEST(b.created_at::date, b.listing_reviewed_at::date)
+               FROM businesses b
+               WHERE b.verified = TRUE
+                 AND b.onboarding_completed = TRUE
+                 AND b.listing_status = 'approved'
+                 AND (b.suspended_until IS NULL OR b.suspended_until <= NOW())
+                 AND NOT user_is_hidden(b.user_id)
+           )
+           SELECT CASE l.kind WHEN 'provider' THEN '/providers/' ELSE '/businesses/' END || l.id AS "path!",
+                  l.lastmod
+           FROM listed l
+           UNION ALL
+           SELECT '/posts/' || po.slug, po.updated_at::date
+           FROM posts po
+           JOIN listed l ON (l.kind = 'provider' AND l.id = po.provider_id)
+                         OR (l.kind = 'business' AND l.id = po.business_id)
+           WHERE NOT po.hidden
+           ORDER BY 1"#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Escapes text for use inside an XML element.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn urlset(base_url: &str, urls: &[SitemapUrl]) -> String {
+    let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"{SITEMAP_NS}\">\n");
+    for url in urls {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!("    <loc>{}</loc>\n", xml_escape(&format!("{base_url}{}", url.path))));
+        if let Some(lastmod) = url.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod.format("%Y-%m-%d")));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn sitemap_index(base_url: &str, files: &[String]) -> String {
+    let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<sitemapindex xmlns=\"{SITEMAP_NS}\">\n");
+    for name in files {
+        xml.push_str("  <sitemap>\n");
+        xml.push_str(&format!("    <loc>{}</loc>\n", xml_escape(&format!("{base_url}/sitemaps/{name}"))));
+        xml.push_str("  </sitemap>\n");
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+/// Rebuilds every sitemap file from the current listings, replacing the
+/// stored set in one transaction. Page URLs (and, past `urls_per_file`, the
+/// index entries) are absolute under `base_url`, the public site: the
+/// protocol wants a sitemap on the same host as its pages, so the site is
+/// expected to proxy `/sitemap.xml` and `/sitemaps/*` here. Returns how many
+/// URLs were written.
+pub async fn regenerate(pool: &PgPool, base_url: &str, urls_per_file: usize) -> Result<usize, sqlx::Error> {
+    let base_url = base_url.trim_end_matches('/');
+    let urls = listed_urls(pool).await?;
+
+    let mut files: Vec<(String, String)> = Vec::new();
+    if urls.len() <= urls_per_file {
+        files.push((ROOT_FILE.to_string(), urlset(base_url, &urls)));
+    } else {
+        let chunks: Vec<(String, String)> = urls
+            .chunks(urls_per_file.max(1))
+            .enumerate()
+            .map(|(i, chunk)| (format!("sitemap-{}.xml", i + 1), urlset(base_url, chunk)))
+            .collect();
+        let names: Vec<String> = chunks.iter().map(|(name, _)| name.clone()).collect();
+        files.push((ROOT_FILE.to_string(), sitemap_index(base_url, &names)));
+        files.extend(chunks);
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!("DELETE FROM sitemap_files").execute(&mut *tx).await?;
+    for (name, body) in &files {
+        sqlx::query!("INSERT INTO sitemap_files (name, body) VALUES ($1, $2)", name, body)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(urls.len())
+}
+
+/// A stored sitemap file. The root file is built on the spot if the job
+/// hasn't produced one yet, as on a fresh deployment.
+pub async fn file(pool: &PgPool, name: &str) -> Result<Option<String>, sqlx::Error> {
+    let body = sqlx::query_scalar!("SELECT body FROM sitemap_files WHERE name = $1", name)
+        .fetch_optional(pool)
+        .await?;
+    if body.is_some() || name != ROOT_FILE {
+        return Ok(body);
+    }
+
+    regenerate(pool, &config::get().frontend_url, URLS_PER_FILE).await?;
+    sqlx::query_scalar!("SELECT body FROM sitemap_files WHERE name = $1", name)
+        .fetch_optional(pool)
+        .await
+}
@@ -1,5 +1,6 @@
 use crate::errors::{AppError, AppResult};
 use serde::Deserialize;
+use sqlx::PgPool;
 use std::env;
 
 // ── Configuration ─────────────────────────────────────────────────────────────
@@ -89,6 +90,29 @@ pub async fn send_sms_best_effort(config: &SmsConfig, to: &str, message: &str) {
     }
 }
 
+/// Where to text the client about a booking: the number they last paid from,
+/// else the one on the booking, else their profile's. `None` if there is no
+/// number or the client has turned booking SMS off.
+pub async fn client_sms_phone(pool: &PgPool, booking_id: i32) -> Option<String> {
+    sqlx::query_scalar!(
+        r#"SELECT COALESCE(
+                      (SELECT phone_number FROM payments WHERE booking_id = b.id ORDER BY created_at DESC LIMIT 1),
+                      b.client_phone,
+                      c.phone
+                  )
+           FROM bookings b
+           LEFT JOIN clients c ON c.user_id = b.client_id
+           WHERE b.id = $1 AND COALESCE(c.sms_notifications, TRUE)"#,
+        booking_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| tracing::warn!("Failed to look up client phone for booking {}: {}", booking_id, e))
+    .ok()
+    .flatten()
+    .flatten()
+}
+
 // ── Message templates ─────────────────────────────────────────────────────────
 
 pub fn booking_confirmation_sms(booking_id: i32, scheduled_time: &str, service: &str) -> String {
@@ -105,9 +129,10 @@ pub fn booking_cancelled_sms(booking_id: i32, reason: &str) -> String {
     )
 }
 
+/// `amount` is already formatted, e.g. by `money::format_kes`.
 pub fn payment_success_sms(amount: &str, receipt: &str, booking_id: i32) -> String {
     format!(
-        "MtaaLink: Payment of KES {amount} received for booking #{booking_id}. \
+        "MtaaLink: Payment of {amount} received for booking #{booking_id}. \
          M-Pesa receipt: {receipt}. Thank you!"
     )
 }
@@ -122,7 +147,7 @@ pub fn payment_failed_sms(booking_id: i32) -> String {
 pub fn booking_reminder_sms(booking_id: i32, scheduled_time: &str, service: &str) -> String {
     format!(
         "MtaaLink: Reminder — you have a booking #{booking_id} for {service} \
-         tomorrow at {scheduled_time}. Please be ready."
+         on {scheduled_time}. Please be ready."
     )
 }
 
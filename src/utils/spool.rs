@@ -0,0 +1,74 @@
+use crate::errors::AppResult;
+use bytes::Bytes;
+use std::env;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+/// An upload written out to a temporary file as it streams in, so a large
+/// video never has to sit in memory. The file is removed when this is dropped.
+pub struct SpooledFile {
+    path: PathBuf,
+    size: u64,
+}
+
+impl SpooledFile {
+    /// Starts a new spool file in the system temp directory. `extension` is
+    /// kept on the name because ffprobe and ffmpeg use it as a format hint.
+    pub async fn create(extension: &str) -> AppResult<SpoolWriter> {
+        let path = env::temp_dir().join(format!("mtaalink-{}.{}", Uuid::new_v4(), extension));
+        let file = fs::File::create(&path).await?;
+        Ok(SpoolWriter {
+            file,
+            spooled: SpooledFile { path, size: 0 },
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Size in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The whole file. Only for images, which are capped far lower than
+    /// videos and have to be decoded in full to render their variants anyway.
+    pub async fn read(&self) -> AppResult<Bytes> {
+        Ok(Bytes::from(fs::read(&self.path).await?))
+    }
+}
+
+impl Drop for SpooledFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A spool file still being written. Dropping it part way removes the file.
+pub struct SpoolWriter {
+    file: fs::File,
+    spooled: SpooledFile,
+}
+
+impl SpoolWriter {
+    pub async fn write(&mut self, chunk: &[u8]) -> AppResult<()> {
+        self.file.write_all(chunk).await?;
+        self.spooled.size += chunk.len() as u64;
+        Ok(())
+    }
+
+    /// Bytes written so far.
+    pub fn size(&self) -> u64 {
+        self.spooled.size
+    }
+
+    /// Flushes and closes the file.
+    pub async fn finish(self) -> AppResult<SpooledFile> {
+        let SpoolWriter { mut file, spooled } = self;
+        file.flush().await?;
+        Ok(spooled)
+    }
+}
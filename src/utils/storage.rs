@@ -1,106 +1,478 @@
+use crate::config::Config;
 use crate::errors::{AppError, AppResult};
-use bytes::Bytes;
+use axum::async_trait;
+use axum::body::Body;
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, BoxStream};
+use futures_util::{StreamExt, TryStreamExt};
 use hmac::{Hmac, Mac};
 use sha2::{Digest, Sha256};
-use std::env;
+use std::io::SeekFrom;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
 // ── Shared type alias ─────────────────────────────────────────────────────────
 
-pub type SharedStorage = Arc<AppStorage>;
+pub type SharedStorage = Arc<dyn Storage>;
+
+/// An upload body, read chunk by chunk as it arrives.
+pub type ByteStream = BoxStream<'static, std::io::Result<Bytes>>;
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
+/// Keys under this prefix are never publicly served: the local backend keeps
+/// them outside the static `uploads/` directory, and S3 buckets must not grant
+/// public reads on it. They are read back through `read_range`.
+pub const PRIVATE_PREFIX: &str = "private/";
+
 /// Generate a unique storage key. Returns e.g. `providers/profile_photos/<uuid>.jpg`
 pub fn generate_key(prefix: &str, extension: &str) -> String {
     format!("{}/{}.{}", prefix, Uuid::new_v4(), extension)
 }
 
+/// True when `key` is a plain relative path that stays inside the storage
+/// root: no absolute paths, `..`, `.` or backslash segments.
+fn is_safe_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.contains('\\')
+        && key
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+/// An object found when listing a backend.
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+}
+
+// ── Backend contract ──────────────────────────────────────────────────────────
+
+/// What every storage backend provides. Keys are relative paths such as
+/// `attachments/<uuid>.jpg`; the URLs handed back are what clients fetch.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Store everything `body` yields at `key` and return its public URL.
+    /// The body is written as it is read, never collected in memory whole.
+    async fn put_stream(&self, key: &str, body: ByteStream, content_type: &str) -> AppResult<String>;
+
+    /// Remove the object at `key`. Missing objects are not an error.
+    async fn delete(&self, key: &str) -> AppResult<()>;
+
+    /// Size in bytes of the object at `key`, or `None` if there is none.
+    async fn size(&self, key: &str) -> AppResult<Option<u64>>;
+
+    /// Streams bytes `start..=end` of the object at `key`.
+    async fn read_range(&self, key: &str, start: u64, end: u64) -> AppResult<Body>;
+
+    /// Every object held by the backend, public and private.
+    async fn list(&self) -> AppResult<Vec<StoredObject>>;
+
+    /// Public URL for the object at `key`.
+    fn url_for(&self, key: &str) -> String;
+
+    /// Store `data` at `key` and return its public URL.
+    async fn save(&self, key: &str, data: &Bytes, content_type: &str) -> AppResult<String> {
+        let body = stream::once(std::future::ready(Ok(data.clone()))).boxed();
+        self.put_stream(key, body, content_type).await
+    }
+
+    /// Store the file at `path` under `key` and return its public URL.
+    async fn save_file(&self, key: &str, path: &Path, content_type: &str) -> AppResult<String> {
+        let file = fs::File::open(path).await?;
+        self.put_stream(key, ReaderStream::new(file).boxed(), content_type).await
+    }
+
+    /// Maps a URL returned by `save` back to its storage key. `None` for URLs
+    /// this backend didn't issue or that would escape the storage root.
+    fn key_for_url(&self, url: &str) -> Option<String> {
+        url.strip_prefix(self.url_for("").as_str())
+            .filter(|key| is_safe_key(key))
+            .map(str::to_string)
+    }
+
+    /// Writes and removes a small private probe object, for readiness checks.
+    async fn check_writable(&self) -> AppResult<()> {
+        let key = format!("{}healthcheck/probe", PRIVATE_PREFIX);
+        self.save(&key, &Bytes::from_static(b"ok"), "text/plain").await?;
+        self.delete(&key).await
+    }
+}
+
+/// The backend `config` selects.
+pub fn init(config: &Config) -> SharedStorage {
+    match &config.storage {
+        StorageBackend::S3(s3) => {
+            let s3 = S3Storage::new(s3);
+            tracing::info!("Storage backend: S3 ({})", s3.endpoint);
+            Arc::new(s3)
+        }
+        StorageBackend::Local => {
+            tracing::info!("Storage backend: local disk ({}/)", config.upload_dir);
+            Arc::new(LocalStorage::new(&config.upload_dir, &config.private_upload_dir))
+        }
+    }
+}
+
 // ── Local filesystem backend ──────────────────────────────────────────────────
 
 #[derive(Clone)]
 pub struct LocalStorage {
-    base_dir: String, // "uploads"
-    base_url: String, // "/uploads"
+    base_dir: String,    // "uploads"
+    private_dir: String, // "private_uploads", not served
+    base_url: String,    // "/uploads"
 }
 
 impl LocalStorage {
-    async fn save(&self, key: &str, data: &Bytes) -> AppResult<String> {
-        let full_path = format!("{}/{}", self.base_dir, key);
+    /// Public files go under `base_dir` and are served at `/uploads`.
+    pub fn new(base_dir: &str, private_dir: &str) -> Self {
+        LocalStorage {
+            base_dir: base_dir.to_string(),
+            private_dir: private_dir.to_string(),
+            base_url: "/uploads".to_string(),
+        }
+    }
+}
+
+impl LocalStorage {
+    fn path_for(&self, key: &str) -> String {
+        match key.strip_prefix(PRIVATE_PREFIX) {
+            Some(rest) => format!("{}/{}", self.private_dir, rest),
+            None => format!("{}/{}", self.base_dir, key),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put_stream(&self, key: &str, mut body: ByteStream, _content_type: &str) -> AppResult<String> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        let full_path = self.path_for(key);
         let dir = Path::new(&full_path)
             .parent()
-            .and_then(|p| p.to_str())
             .ok_or_else(|| AppError::Internal("Invalid storage key".to_string()))?;
         fs::create_dir_all(dir).await?;
-        fs::write(&full_path, data).await?;
-        Ok(format!("{}/{}", self.base_url, key))
+        let mut file = fs::File::create(&full_path).await?;
+        let written = async {
+            while let Some(chunk) = body.try_next().await? {
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await
+        }
+        .await;
+        if let Err(e) = written {
+            // Leave nothing half-written behind for the URL to point at
+            let _ = fs::remove_file(&full_path).await;
+            return Err(e.into());
+        }
+        Ok(self.url_for(key))
+    }
+
+    async fn save_file(&self, key: &str, path: &Path, _content_type: &str) -> AppResult<String> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        let full_path = self.path_for(key);
+        let dir = Path::new(&full_path)
+            .parent()
+            .ok_or_else(|| AppError::Internal("Invalid storage key".to_string()))?;
+        fs::create_dir_all(dir).await?;
+        fs::copy(path, &full_path).await?;
+        Ok(self.url_for(key))
     }
 
     async fn delete(&self, key: &str) -> AppResult<()> {
-        let _ = fs::remove_file(format!("{}/{}", self.base_dir, key)).await;
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        let _ = fs::remove_file(self.path_for(key)).await;
         Ok(())
     }
+
+    async fn size(&self, key: &str) -> AppResult<Option<u64>> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        match fs::metadata(self.path_for(key)).await {
+            Ok(meta) => Ok(Some(meta.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn read_range(&self, key: &str, start: u64, end: u64) -> AppResult<Body> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        let mut file = fs::File::open(self.path_for(key)).await?;
+        file.seek(SeekFrom::Start(start)).await?;
+        Ok(Body::from_stream(ReaderStream::new(file.take(end - start + 1))))
+    }
+
+    async fn list(&self) -> AppResult<Vec<StoredObject>> {
+        let mut objects = Vec::new();
+        for (root, key_prefix) in [(&self.base_dir, ""), (&self.private_dir, PRIVATE_PREFIX)] {
+            let mut pending = vec![(root.clone(), key_prefix.to_string())];
+            while let Some((dir, prefix)) = pending.pop() {
+                let mut entries = match fs::read_dir(&dir).await {
+                    Ok(entries) => entries,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                while let Some(entry) = entries.next_entry().await? {
+                    let Ok(name) = entry.file_name().into_string() else {
+                        tracing::warn!("Skipping non-UTF-8 file name in {}", dir);
+                        continue;
+                    };
+                    // Symlinks are neither followed nor reported
+                    let file_type = entry.file_type().await?;
+                    let key = format!("{}{}", prefix, name);
+                    if file_type.is_dir() {
+                        pending.push((format!("{}/{}", dir, name), format!("{}/", key)));
+                    } else if file_type.is_file() {
+                        let meta = entry.metadata().await?;
+                        objects.push(StoredObject {
+                            key,
+                            size: meta.len(),
+                            modified: meta
+                                .modified()
+                                .map(DateTime::<Utc>::from)
+                                .unwrap_or_else(|_| Utc::now()),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(objects)
+    }
+
+    fn url_for(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
+
+// ── S3-compatible backend ─────────────────────────────────────────────────────
+
+/// Where uploads are kept, chosen by `STORAGE_BACKEND`.
+pub enum StorageBackend {
+    /// Files under `UPLOAD_DIR` and `PRIVATE_UPLOAD_DIR`. Only safe with a
+    /// single instance on a persistent disk.
+    Local,
+    S3(S3Config),
+}
+
+/// Bucket and credentials for the S3 backend, from the `AWS_*` variables.
+pub struct S3Config {
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// `scheme://host[:port]` of an S3-compatible service (Cloudflare R2,
+    /// MinIO); `None` for AWS itself.
+    pub endpoint: Option<String>,
+    /// Public base for returned URLs, e.g. a CDN in front of the bucket.
+    pub base_url: Option<String>,
 }
 
-// ── AWS S3 backend ────────────────────────────────────────────────────────────
+/// Uploads larger than this go up as a multipart upload in parts of this
+/// size, so memory use is bounded whatever the file size. S3 requires parts
+/// of at least 5 MiB, except the last.
+const S3_PART_BYTES: usize = 8 * 1024 * 1024;
 
+/// AWS S3, or any S3-compatible service (Cloudflare R2, MinIO) when
+/// `AWS_S3_ENDPOINT` is set.
 pub struct S3Storage {
-    bucket: String,
     region: String,
     access_key: String,
     secret_key: String,
+    /// `scheme://host[:port]` requests are sent to.
+    endpoint: String,
+    /// Host header that is signed, e.g. `bucket.s3.us-east-1.amazonaws.com`.
+    host: String,
+    /// Path before the key: empty for virtual-hosted AWS buckets, `/<bucket>`
+    /// for path-style custom endpoints.
+    path_prefix: String,
+    /// Public base for returned URLs; may be a CDN in front of the bucket.
     base_url: String,
     client: reqwest::Client,
 }
 
 impl S3Storage {
-    fn from_env() -> AppResult<Self> {
-        let region = env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string());
-        let bucket = env::var("AWS_S3_BUCKET")
-            .map_err(|_| AppError::Internal("AWS_S3_BUCKET not set".to_string()))?;
-        let access_key = env::var("AWS_ACCESS_KEY_ID")
-            .map_err(|_| AppError::Internal("AWS_ACCESS_KEY_ID not set".to_string()))?;
-        let secret_key = env::var("AWS_SECRET_ACCESS_KEY")
-            .map_err(|_| AppError::Internal("AWS_SECRET_ACCESS_KEY not set".to_string()))?;
-        let base_url = env::var("AWS_S3_BASE_URL")
-            .unwrap_or_else(|_| format!("https://{}.s3.{}.amazonaws.com", bucket, region));
-
-        Ok(S3Storage {
-            bucket,
-            region,
-            access_key,
-            secret_key,
+    pub fn new(config: &S3Config) -> Self {
+        let (endpoint, host, path_prefix) = match &config.endpoint {
+            // S3-compatible services are addressed path-style
+            Some(endpoint) => {
+                let endpoint = endpoint.trim_end_matches('/').to_string();
+                let host = endpoint
+                    .split_once("://")
+                    .map(|(_, host)| host)
+                    .unwrap_or(&endpoint)
+                    .to_string();
+                (endpoint, host, format!("/{}", config.bucket))
+            }
+            None => {
+                let host = format!("{}.s3.{}.amazonaws.com", config.bucket, config.region);
+                (format!("https://{}", host), host, String::new())
+            }
+        };
+        let base_url = match &config.base_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!("{}{}", endpoint, path_prefix),
+        };
+
+        S3Storage {
+            region: config.region.clone(),
+            access_key: config.access_key.clone(),
+            secret_key: config.secret_key.clone(),
+            endpoint,
+            host,
+            path_prefix,
             base_url,
             client: reqwest::Client::new(),
-        })
+        }
+    }
+
+    /// Request path of the object at `key`.
+    fn object_path(&self, key: &str) -> String {
+        format!("{}/{}", self.path_prefix, key)
+    }
+
+    /// Sends a signed request for the object at `key`. `query` must already
+    /// be canonical: sorted by name, values encoded with `uri_encode`.
+    async fn send_signed(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        query: &str,
+        body: Bytes,
+        content_type: &str,
+    ) -> AppResult<reqwest::Response> {
+        let path = self.object_path(key);
+        let body_hash = hex::encode(Sha256::digest(&body));
+        let datetime = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let auth = self.sign(method.as_str(), &path, query, &body_hash, &datetime, content_type);
+        let url = match query {
+            "" => format!("{}{}", self.endpoint, path),
+            query => format!("{}{}?{}", self.endpoint, path, query),
+        };
+
+        self.client
+            .request(method.clone(), url)
+            .header("Host", &self.host)
+            .header("Content-Type", content_type)
+            .header("Content-Length", body.len())
+            .header("x-amz-date", &datetime)
+            .header("x-amz-content-sha256", &body_hash)
+            .header("Authorization", auth)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("S3 {} failed: {}", method, e)))
+    }
+
+    /// Fails with the response body when `resp` is not a success. S3 may
+    /// also report a failed CompleteMultipartUpload inside a 200.
+    async fn check(resp: reqwest::Response, what: &str) -> AppResult<String> {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        if !status.is_success() || body.contains("<Error>") {
+            return Err(AppError::Internal(format!("S3 {} error {}: {}", what, status, body)));
+        }
+        Ok(body)
+    }
+
+    /// Uploads `body` as the whole object at `key` in a single PUT.
+    async fn put(&self, key: &str, body: Bytes, content_type: &str) -> AppResult<String> {
+        let resp = self.send_signed(reqwest::Method::PUT, key, "", body, content_type).await?;
+        Self::check(resp, "upload").await?;
+        Ok(self.url_for(key))
     }
 
-    /// AWS Signature Version 4 — signs a PUT or DELETE request.
+    /// Uploads `first` and then the rest of `body` as the parts of a
+    /// multipart upload, aborting it if anything fails so no parts linger.
+    async fn put_multipart(&self, key: &str, first: Bytes, body: ByteStream, content_type: &str) -> AppResult<String> {
+        let resp = self
+            .send_signed(reqwest::Method::POST, key, "uploads=", Bytes::new(), content_type)
+            .await?;
+        let created = Self::check(resp, "multipart start").await?;
+        let upload_id = xml_value(&created, "UploadId")
+            .ok_or_else(|| AppError::Internal("S3 multipart start returned no UploadId".to_string()))?;
+
+        match self.upload_parts(key, &upload_id, first, body).await {
+            Ok(()) => Ok(self.url_for(key)),
+            Err(e) => {
+                let query = format!("uploadId={}", uri_encode(&upload_id));
+                if let Err(abort) = self.send_signed(reqwest::Method::DELETE, key, &query, Bytes::new(), "").await {
+                    tracing::warn!("Failed to abort multipart upload of {}: {}", key, abort);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn upload_parts(&self, key: &str, upload_id: &str, first: Bytes, mut body: ByteStream) -> AppResult<()> {
+        let upload_id = uri_encode(upload_id);
+        let mut etags = Vec::new();
+        let mut part = Some(first);
+        while let Some(data) = part {
+            let query = format!("partNumber={}&uploadId={}", etags.len() + 1, upload_id);
+            let resp = self.send_signed(reqwest::Method::PUT, key, &query, data, "").await?;
+            let etag = resp
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            Self::check(resp, "part upload").await?;
+            etags.push(etag.ok_or_else(|| AppError::Internal("S3 part upload returned no ETag".to_string()))?);
+            part = next_part(&mut body).await?;
+        }
+
+        let parts: String = etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", i + 1, etag))
+            .collect();
+        let manifest = format!("<CompleteMultipartUpload>{}</CompleteMultipartUpload>", parts);
+        let query = format!("uploadId={}", upload_id);
+        let resp = self
+            .send_signed(reqwest::Method::POST, key, &query, manifest.into(), "application/xml")
+            .await?;
+        Self::check(resp, "multipart complete").await?;
+        Ok(())
+    }
+
+    /// AWS Signature Version 4 — signs a request to the bucket.
     fn sign(
         &self,
         method: &str,
-        key: &str,
+        path: &str,
+        query: &str, // canonical (sorted, encoded) query string
         body_hash: &str,
         datetime: &str, // "20240101T120000Z"
-        date: &str,     // "20240101"
         content_type: &str,
     ) -> String {
-        let host = format!("{}.s3.{}.amazonaws.com", self.bucket, self.region);
+        let date = &datetime[..8];
         let service = "s3";
         let scope = format!("{}/{}/{}/aws4_request", date, self.region, service);
 
         // 1. Canonical request
         let canonical_headers = format!(
             "content-type:{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
-            content_type, host, body_hash, datetime
+            content_type, self.host, body_hash, datetime
         );
         let signed_headers = "content-type;host;x-amz-content-sha256;x-amz-date";
         let canonical_request = format!(
-            "{}\n/{}\n\n{}\n{}\n{}",
-            method, key, canonical_headers, signed_headers, body_hash
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, query, canonical_headers, signed_headers, body_hash
         );
 
         // 2. String to sign
@@ -124,120 +496,247 @@ impl S3Storage {
             self.access_key, scope, signed_headers, signature
         )
     }
+}
 
-    async fn save(&self, key: &str, data: &Bytes, content_type: &str) -> AppResult<String> {
-        let url = format!("{}/{}", self.base_url, key);
-        let body_hash = hex::encode(Sha256::digest(data));
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put_stream(&self, key: &str, mut body: ByteStream, content_type: &str) -> AppResult<String> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        // SigV4 signs each request's payload hash, so the body goes up a
+        // part at a time; one that fits in a single part is a plain PUT
+        let first = next_part(&mut body).await?.unwrap_or_default();
+        if first.len() < S3_PART_BYTES {
+            return self.put(key, first, content_type).await;
+        }
+        self.put_multipart(key, first, body, content_type).await
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        let path = self.object_path(key);
+        let body_hash = hex::encode(Sha256::digest(b""));
         let now = chrono::Utc::now();
         let datetime = now.format("%Y%m%dT%H%M%SZ").to_string();
-        let date = now.format("%Y%m%d").to_string();
 
-        let auth = self.sign("PUT", key, &body_hash, &datetime, &date, content_type);
-        let host = format!("{}.s3.{}.amazonaws.com", self.bucket, self.region);
+        let auth = self.sign("DELETE", &path, "", &body_hash, &datetime, "");
 
         let resp = self
             .client
-            .put(&url)
-            .header("Host", &host)
-            .header("Content-Type", content_type)
+            .delete(format!("{}{}", self.endpoint, path))
+            .header("Host", &self.host)
+            .header("Content-Type", "")
             .header("x-amz-date", &datetime)
             .header("x-amz-content-sha256", &body_hash)
             .header("Authorization", auth)
-            .body(data.clone())
             .send()
             .await
-            .map_err(|e| AppError::Internal(format!("S3 upload failed: {}", e)))?;
+            .map_err(|e| AppError::Internal(format!("S3 delete failed: {}", e)))?;
 
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
+        // S3 answers 204 whether or not the key existed
+        if !resp.status().is_success() && resp.status() != reqwest::StatusCode::NOT_FOUND {
             return Err(AppError::Internal(format!(
-                "S3 upload error {}: {}",
-                status, body
+                "S3 delete error {}",
+                resp.status()
             )));
         }
 
-        Ok(format!("{}/{}", self.base_url, key))
+        Ok(())
     }
 
-    async fn delete(&self, key: &str) -> AppResult<()> {
-        let url = format!("{}/{}", self.base_url, key);
+    async fn size(&self, key: &str) -> AppResult<Option<u64>> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        let path = self.object_path(key);
         let body_hash = hex::encode(Sha256::digest(b""));
         let now = chrono::Utc::now();
         let datetime = now.format("%Y%m%dT%H%M%SZ").to_string();
-        let date = now.format("%Y%m%d").to_string();
 
-        let auth = self.sign("DELETE", key, &body_hash, &datetime, &date, "");
-        let host = format!("{}.s3.{}.amazonaws.com", self.bucket, self.region);
+        let auth = self.sign("HEAD", &path, "", &body_hash, &datetime, "");
 
-        self.client
-            .delete(&url)
-            .header("Host", &host)
+        let resp = self
+            .client
+            .head(format!("{}{}", self.endpoint, path))
+            .header("Host", &self.host)
             .header("Content-Type", "")
             .header("x-amz-date", &datetime)
             .header("x-amz-content-sha256", &body_hash)
             .header("Authorization", auth)
             .send()
             .await
-            .map_err(|e| AppError::Internal(format!("S3 delete failed: {}", e)))?;
+            .map_err(|e| AppError::Internal(format!("S3 HEAD failed: {}", e)))?;
 
-        Ok(())
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(AppError::Internal(format!("S3 HEAD error {}", resp.status())));
+        }
+        // Read the header itself: a HEAD response has no body to size
+        Ok(resp
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok()))
     }
-}
 
-fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
-    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key size");
-    mac.update(data);
-    mac.finalize().into_bytes().to_vec()
-}
+    async fn read_range(&self, key: &str, start: u64, end: u64) -> AppResult<Body> {
+        if !is_safe_key(key) {
+            return Err(AppError::BadRequest("Invalid storage key".to_string()));
+        }
+        let path = self.object_path(key);
+        let body_hash = hex::encode(Sha256::digest(b""));
+        let now = chrono::Utc::now();
+        let datetime = now.format("%Y%m%dT%H%M%SZ").to_string();
 
-// ── Unified enum ──────────────────────────────────────────────────────────────
+        let auth = self.sign("GET", &path, "", &body_hash, &datetime, "");
 
-pub enum AppStorage {
-    Local(LocalStorage),
-    S3(S3Storage),
-}
+        let resp = self
+            .client
+            .get(format!("{}{}", self.endpoint, path))
+            .header("Host", &self.host)
+            .header("Content-Type", "")
+            .header("x-amz-date", &datetime)
+            .header("x-amz-content-sha256", &body_hash)
+            .header("Authorization", auth)
+            .header("Range", format!("bytes={}-{}", start, end))
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("S3 GET failed: {}", e)))?;
 
-impl AppStorage {
-    /// Initialise from `STORAGE_BACKEND` env var ("s3" or "local"/unset).
-    pub fn init() -> Self {
-        match env::var("STORAGE_BACKEND").as_deref() {
-            Ok("s3") => match S3Storage::from_env() {
-                Ok(s3) => {
-                    tracing::info!("Storage backend: AWS S3");
-                    AppStorage::S3(s3)
-                }
-                Err(e) => {
-                    tracing::error!("S3 init failed ({}); falling back to local storage", e);
-                    AppStorage::Local(LocalStorage {
-                        base_dir: "uploads".to_string(),
-                        base_url: "/uploads".to_string(),
-                    })
-                }
-            },
-            _ => {
-                tracing::info!("Storage backend: local disk (uploads/)");
-                AppStorage::Local(LocalStorage {
-                    base_dir: "uploads".to_string(),
-                    base_url: "/uploads".to_string(),
-                })
-            }
+        if !resp.status().is_success() {
+            return Err(AppError::Internal(format!("S3 GET error {}", resp.status())));
         }
+        Ok(Body::from_stream(resp.bytes_stream().map_err(std::io::Error::other)))
     }
 
-    /// Save `data` at `key` and return a public URL.
-    /// `content_type` is used only by the S3 backend.
-    pub async fn save(&self, key: &str, data: &Bytes) -> AppResult<String> {
-        match self {
-            AppStorage::Local(s) => s.save(key, data).await,
-            AppStorage::S3(s) => s.save(key, data, "application/octet-stream").await,
+    async fn list(&self) -> AppResult<Vec<StoredObject>> {
+        let path = format!("{}/", self.path_prefix);
+        let mut objects = Vec::new();
+        let mut continuation: Option<String> = None;
+
+        loop {
+            // Canonical query strings are sorted by parameter name
+            let query = match &continuation {
+                Some(token) => format!("continuation-token={}&list-type=2", uri_encode(token)),
+                None => "list-type=2".to_string(),
+            };
+            let body_hash = hex::encode(Sha256::digest(b""));
+            let now = chrono::Utc::now();
+            let datetime = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+            let auth = self.sign("GET", &path, &query, &body_hash, &datetime, "");
+
+            let resp = self
+                .client
+                .get(format!("{}{}?{}", self.endpoint, path, query))
+                .header("Host", &self.host)
+                .header("Content-Type", "")
+                .header("x-amz-date", &datetime)
+                .header("x-amz-content-sha256", &body_hash)
+                .header("Authorization", auth)
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("S3 list failed: {}", e)))?;
+
+            if !resp.status().is_success() {
+                return Err(AppError::Internal(format!("S3 list error {}", resp.status())));
+            }
+            let xml = resp
+                .text()
+                .await
+                .map_err(|e| AppError::Internal(format!("S3 list failed: {}", e)))?;
+
+            for entry in xml_blocks(&xml, "Contents") {
+                let (Some(key), Some(size), Some(modified)) = (
+                    xml_value(entry, "Key"),
+                    xml_value(entry, "Size").and_then(|v| v.parse().ok()),
+                    xml_value(entry, "LastModified").and_then(|v| DateTime::parse_from_rfc3339(&v).ok()),
+                ) else {
+                    continue;
+                };
+                objects.push(StoredObject {
+                    key,
+                    size,
+                    modified: modified.with_timezone(&Utc),
+                });
+            }
+
+            continuation = match xml_value(&xml, "IsTruncated").as_deref() {
+                Some("true") => xml_value(&xml, "NextContinuationToken"),
+                _ => None,
+            };
+            if continuation.is_none() {
+                break;
+            }
         }
+        Ok(objects)
     }
 
-    pub async fn delete(&self, key: &str) -> AppResult<()> {
-        match self {
-            AppStorage::Local(s) => s.delete(key).await,
-            AppStorage::S3(s) => s.delete(key).await,
+    fn url_for(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
+
+/// Reads up to `S3_PART_BYTES` from `body`. `None` once it is exhausted.
+async fn next_part(body: &mut ByteStream) -> AppResult<Option<Bytes>> {
+    let mut part = BytesMut::new();
+    while part.len() < S3_PART_BYTES {
+        match body.try_next().await? {
+            Some(chunk) => part.extend_from_slice(&chunk),
+            None if part.is_empty() => return Ok(None),
+            None => break,
         }
     }
+    Ok(Some(part.freeze()))
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters, as SigV4
+/// requires for query values.
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect()
+}
+
+/// The bodies of every `<tag>...</tag>` element in a ListObjectsV2 response.
+fn xml_blocks<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut blocks = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(&close) else { break };
+        blocks.push(&after[..end]);
+        rest = &after[end + close.len()..];
+    }
+    blocks
+}
+
+/// Text of the first `<tag>` element, with XML entities decoded.
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    xml_blocks(xml, tag).first().map(|text| {
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    })
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
 }
@@ -0,0 +1,204 @@
+use crate::errors::AppResult;
+use crate::utils::jobs::{self, JobReport, STORAGE_GC};
+use crate::utils::settings;
+use crate::utils::storage::{SharedStorage, StoredObject};
+use crate::utils::upload_sessions::chunk_key;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+/// Files younger than this are never collected: an upload is stored a moment
+/// before the row that references it is written.
+const MIN_ORPHAN_AGE_HOURS: i64 = 24;
+
+/// Entries listed per category in a report; the counts cover everything.
+const REPORT_LIMIT: usize = 500;
+
+#[derive(Serialize, Debug)]
+pub struct OrphanFile {
+    pub key: String,
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+}
+
+/// A row pointing at a file this backend should hold but doesn't.
+#[derive(Serialize, Debug)]
+pub struct MissingFile {
+    pub source: String,
+    pub id: i32,
+    pub url: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub files_scanned: usize,
+    pub orphan_count: usize,
+    pub orphan_bytes: u64,
+    pub deleted: usize,
+    pub delete_failures: usize,
+    pub orphans: Vec<OrphanFile>,
+    pub missing_count: usize,
+    pub missing: Vec<MissingFile>,
+}
+
+/// Spawns a background loop that reconciles storage with the database once a
+/// week. Orphans are only deleted while `storage.gc_delete_orphans` is on;
+/// otherwise the run just records what it found.
+pub fn start_storage_gc_task(pool: PgPool, storage: SharedStorage, mut trigger: jobs::JobTrigger) {
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&STORAGE_GC, &mut trigger).await {
+            jobs::record_run(&pool, &STORAGE_GC, run_storage_gc(&pool, &storage)).await;
+        }
+    });
+}
+
+async fn run_storage_gc(pool: &PgPool, storage: &SharedStorage) -> JobReport {
+    let mut report = JobReport::default();
+    let dry_run = !settings::flag(pool, "storage.gc_delete_orphans").await;
+
+    match collect_garbage(pool, storage, dry_run).await {
+        Ok(gc) => {
+            tracing::info!(
+                "Storage GC: {} orphaned files ({} bytes), {} deleted, {} rows with missing files",
+                gc.orphan_count,
+                gc.orphan_bytes,
+                gc.deleted,
+                gc.missing_count
+            );
+            report.items_processed = (if dry_run { gc.orphan_count } else { gc.deleted }) as i64;
+            if gc.delete_failures > 0 {
+                report.fail("Storage GC", format!("{} orphaned files could not be deleted", gc.delete_failures));
+            }
+        }
+        Err(e) => report.fail("Storage GC failed", e),
+    }
+    report
+}
+
+/// Cross-references every stored file with the rows that point at it.
+/// Files nothing references (older than a day) are orphans and, unless
+/// `dry_run`, deleted; rows whose file is gone are reported.
+pub async fn collect_garbage(pool: &PgPool, storage: &SharedStorage, dry_run: bool) -> AppResult<GcReport> {
+    let objects = storage.list().await?;
+    let stored: HashSet<&str> = objects.iter().map(|o| o.key.as_str()).collect();
+
+    let references = sqlx::query!(
+        r#"SELECT 'attachment' AS "source!", id AS "id!", file_path AS "url!" FROM attachments
+           UNION ALL SELECT 'attachment', id, poster_path FROM attachments WHERE poster_path IS NOT NULL
+           UNION ALL SELECT 'provider', id, profile_photo FROM providers WHERE profile_photo IS NOT NULL
+           UNION ALL SELECT 'provider', id, cover_photo FROM providers WHERE cover_photo IS NOT NULL
+           UNION ALL SELECT 'business', id, logo FROM businesses WHERE logo IS NOT NULL
+           UNION ALL SELECT 'business', id, profile_photo FROM businesses WHERE profile_photo IS NOT NULL
+           UNION ALL SELECT 'business', id, cover_photo FROM businesses WHERE cover_photo IS NOT NULL
+           UNION ALL SELECT 'client', id, profile_picture FROM clients WHERE profile_picture IS NOT NULL
+           UNION ALL SELECT 'dispute_evidence', id, file_url FROM dispute_evidence"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut live: HashSet<String> = HashSet::new();
+    let mut missing = Vec::new();
+    let mut missing_count = 0;
+    for reference in references {
+        // URLs hosted elsewhere (e.g. an external CDN) are not ours to check
+        let Some(key) = storage.key_for_url(&reference.url) else {
+            continue;
+        };
+        if !stored.contains(key.as_str()) {
+            missing_count += 1;
+            if missing.len() < REPORT_LIMIT {
+                missing.push(MissingFile {
+                    source: reference.source,
+                    id: reference.id,
+                    url: reference.url,
+                });
+            }
+        }
+        live.insert(key);
+    }
+
+    // Message attachments are only referenced from message text
+    let message_urls: Vec<String> = objects
+        .iter()
+        .filter(|o| o.key.starts_with("messages/") && !live.contains(&o.key))
+        .map(|o| storage.url_for(&o.key))
+        .collect();
+    let quoted = sqlx::query_scalar!(
+        r#"SELECT u AS "u!" FROM unnest($1::text[]) AS u
+           WHERE EXISTS (SELECT 1 FROM messages m WHERE strpos(m.content, u) > 0)"#,
+        &message_urls
+    )
+    .fetch_all(pool)
+    .await?;
+    live.extend(quoted.iter().filter_map(|url| storage.key_for_url(url)));
+
+    // Resized variants live as long as their original does
+    let variants = sqlx::query!("SELECT original_path, thumb_path, medium_path FROM image_variants")
+        .fetch_all(pool)
+        .await?;
+    for variant in variants {
+        let original_live = storage
+            .key_for_url(&variant.original_path)
+            .is_some_and(|key| live.contains(&key));
+        if original_live {
+            live.extend(storage.key_for_url(&variant.thumb_path));
+            live.extend(storage.key_for_url(&variant.medium_path));
+        }
+    }
+
+    // Chunks of resumable uploads still in progress; the cleanup job removes
+    // abandoned ones
+    let chunks = sqlx::query!("SELECT session_id, chunk_number FROM upload_chunks")
+        .fetch_all(pool)
+        .await?;
+    live.extend(chunks.iter().map(|c| chunk_key(c.session_id, c.chunk_number)));
+
+    let cutoff = Utc::now() - Duration::hours(MIN_ORPHAN_AGE_HOURS);
+    let mut orphans: Vec<&StoredObject> = objects
+        .iter()
+        .filter(|o| !live.contains(&o.key) && o.modified < cutoff)
+        .collect();
+    orphans.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let mut deleted_urls = Vec::new();
+    let mut delete_failures = 0;
+    if !dry_run {
+        for orphan in &orphans {
+            match storage.delete(&orphan.key).await {
+                Ok(()) => deleted_urls.push(storage.url_for(&orphan.key)),
+                Err(e) => {
+                    tracing::warn!("Storage GC: failed to delete {}: {}", orphan.key, e);
+                    delete_failures += 1;
+                }
+            }
+        }
+        sqlx::query!(
+            "DELETE FROM image_variants WHERE original_path = ANY($1)",
+            &deleted_urls
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(GcReport {
+        dry_run,
+        files_scanned: objects.len(),
+        orphan_count: orphans.len(),
+        orphan_bytes: orphans.iter().map(|o| o.size).sum(),
+        deleted: deleted_urls.len(),
+        delete_failures,
+        orphans: orphans
+            .iter()
+            .take(REPORT_LIMIT)
+            .map(|o| OrphanFile {
+                key: o.key.clone(),
+                size: o.size,
+                modified: o.modified,
+            })
+            .collect(),
+        missing_count,
+        missing,
+    })
+}
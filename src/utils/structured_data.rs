@@ -0,0 +1,253 @@
+use crate::config;
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde_json::{Map, Value, json};
+use sqlx::PgPool;
+
+/// Where a listing can be found, from its provider locations or branches.
+struct Place {
+    address: Option<String>,
+    ward: Option<String>,
+    county: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+struct Rating {
+    average: Option<f64>,
+    count: i64,
+}
+
+struct Hours {
+    day: String,
+    opens: chrono::NaiveTime,
+    closes: chrono::NaiveTime,
+}
+
+/// The fields shared by providers and businesses.
+struct Listing {
+    name: Option<String>,
+    description: Option<String>,
+    location: Option<String>,
+    phone_number: Option<String>,
+    email: Option<String>,
+    website: Option<String>,
+    photo: Option<String>,
+}
+
+fn postal_address(place: &Place) -> Value {
+    let mut address = Map::new();
+    address.insert("@type".into(), json!("PostalAddress"));
+    if let Some(street) = &place.address {
+        address.insert("streetAddress".into(), json!(street));
+    }
+    if let Some(ward) = &place.ward {
+        address.insert("addressLocality".into(), json!(ward));
+    }
+    if let Some(county) = &place.county {
+        address.insert("addressRegion".into(), json!(county));
+    }
+    address.insert("addressCountry".into(), json!("KE"));
+    Value::Object(address)
+}
+
+/// schema.org `LocalBusiness` JSON-LD for a listing's public page. Empty
+/// fields are left out rather than sent as null.
+fn local_business(path: &str, listing: Listing, places: &[Place], rating: &Rating, hours: &[Hours]) -> Value {
+    let url = format!("{}{}", config::get().frontend_url.trim_end_matches('/'), path);
+    let mut doc = Map::new();
+    doc.insert("@context".into(), json!("https://schema.org"));
+    doc.insert("@type".into(), json!("LocalBusiness"));
+    doc.insert("@id".into(), json!(url));
+    doc.insert("url".into(), json!(url));
+    doc.insert("name".into(), json!(listing.name.unwrap_or_default()));
+
+    for (key, value) in [
+        ("description", listing.description),
+        ("telephone", listing.phone_number),
+        ("email", listing.email),
+        ("sameAs", listing.website),
+        // Only absolute URLs are any use to a crawler
+        ("image", listing.photo.filter(|p| p.starts_with("http://") || p.starts_with("https://"))),
+    ] {
+        if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+            doc.insert(key.into(), json!(value));
+        }
+    }
+
+    let addresses: Vec<Value> = places.iter().map(postal_address).collect();
+    match addresses.len() {
+        0 => {
+            // No structured location; the free-text one is better than nothing
+            if let Some(location) = listing.location.filter(|l| !l.trim().is_empty()) {
+                doc.insert(
+                    "address".into(),
+                    json!({ "@type": "PostalAddress", "addressLocality": location, "addressCountry": "KE" }),
+                );
+            }
+        }
+        1 => {
+            doc.insert("address".into(), addresses[0].clone());
+        }
+        _ => {
+            doc.insert("address".into(), Value::Array(addresses));
+        }
+    }
+    if let Some(place) = places.iter().find(|p| p.latitude.is_some() && p.longitude.is_some()) {
+        doc.insert(
+            "geo".into(),
+            json!({ "@type": "GeoCoordinates", "latitude": place.latitude, "longitude": place.longitude }),
+        );
+    }
+
+    if !hours.is_empty() {
+        let specs: Vec<Value> = hours
+            .iter()
+            .map(|h| {
+                json!({
+                    "@type": "OpeningHoursSpecification",
+                    "dayOfWeek": format!("https://schema.org/{}", h.day),
+                    "opens": h.opens.format("%H:%M").to_string(),
+                    "closes": h.closes.format("%H:%M").to_string(),
+                })
+            })
+            .collect();
+        doc.insert("openingHoursSpecification".into(), Value::Array(specs));
+    }
+
+    if let (Some(average), true) = (rating.average, rating.count > 0) {
+        doc.insert(
+            "aggregateRating".into(),
+            json!({
+                "@type": "AggregateRating",
+                "ratingValue": average,
+                "reviewCount": rating.count,
+                "bestRating": 5,
+                "worstRating": 1,
+            }),
+        );
+    }
+
+    Value::Object(doc)
+}
+
+async fn rating(pool: &PgPool, target_type: &str, target_id: i32) -> Result<Rating, sqlx::Error> {
+    sqlx::query_as!(
+        Rating,
+        r#"SELECT ROUND(AVG(rating)::numeric, 1)::float8 AS average, COUNT(*) AS "count!"
+           FROM reviews
+           WHERE target_type = $1 AND target_id = $2 AND hidden = FALSE"#,
+        target_type,
+        target_id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// JSON-LD for a listed provider (the ones in the sitemap), or `None`.
+pub async fn provider(pool: &PgPool, id: i32) -> Result<Option<Value>, sqlx::Error> {
+    let Some(listing) = sqlx::query_as!(
+        Listing,
+        r#"SELECT service_name AS name, service_description AS description, location,
+                  phone_number, email, website, profile_photo AS photo
+           FROM providers
+           WHERE id = $1
+             AND approved = TRUE
+             AND onboarding_completed = TRUE
+             AND listing_status = 'approved'
+             AND (suspended_until IS NULL OR suspended_until <= NOW())
+             AND NOT user_is_hidden(user_id)"#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let places = sqlx::query_as!(
+        Place,
+        r#"SELECT pl.address, w.name AS "ward?", co.name AS "county?", pl.latitude, pl.longitude
+           FROM provider_locations pl
+           LEFT JOIN wards w ON w.id = pl.ward_id
+           LEFT JOIN constituencies c ON c.id = w.constituency_id
+           LEFT JOIN counties co ON co.id = c.county_id
+           WHERE pl.provider_id = $1
+           ORDER BY pl.id"#,
+        id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let hours = sqlx::query_as!(
+        Hours,
+        r#"SELECT INITCAP(day) AS "day!", start_time AS opens, end_time AS closes
+           FROM provider_availability
+           WHERE provider_id = $1 AND COALESCE(is_available, TRUE)
+           ORDER BY CASE LOWER(day)
+                        WHEN 'monday' THEN 1 WHEN 'tuesday' THEN 2 WHEN 'wednesday' THEN 3
+                        WHEN 'thursday' THEN 4 WHEN 'friday' THEN 5 WHEN 'saturday' THEN 6
+                        ELSE 7
+                    END, start_time"#,
+        id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let rating = rating(pool, "provider", id).await?;
+    Ok(Some(local_business(&format!("/providers/{id}"), listing, &places, &rating, &hours)))
+}
+
+/// JSON-LD for a listed business, or `None`. Businesses keep no opening
+/// hours, so none are given.
+pub async fn business(pool: &PgPool, id: i32) -> Result<Option<Value>, sqlx::Error> {
+    let Some(listing) = sqlx::query_as!(
+        Listing,
+        r#"SELECT business_name AS "name?", description, location,
+                  phone_number, email, website, COALESCE(logo, profile_photo) AS photo
+           FROM businesses
+           WHERE id = $1
+             AND verified = TRUE
+             AND onboarding_completed = TRUE
+             AND listing_status = 'approved'
+             AND (suspended_until IS NULL OR suspended_until <= NOW())
+             AND NOT user_is_hidden(user_id)"#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let places = sqlx::query_as!(
+        Place,
+        r#"SELECT bb.address, w.name AS "ward?", co.name AS "county?", bb.latitude, bb.longitude
+           FROM business_branches bb
+           LEFT JOIN wards w ON w.id = bb.ward_id
+           LEFT JOIN constituencies c ON c.id = w.constituency_id
+           LEFT JOIN counties co ON co.id = c.county_id
+           WHERE bb.business_id = $1 AND bb.is_active
+           ORDER BY bb.id"#,
+        id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let rating = rating(pool, "business", id).await?;
+    Ok(Some(local_business(&format!("/businesses/{id}"), listing, &places, &rating, &[])))
+}
+
+/// A JSON-LD document as a response, with its own media type.
+pub fn json_ld(doc: Value) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "application/ld+json"),
+            (header::CACHE_CONTROL, "public, max-age=3600"),
+        ],
+        doc.to_string(),
+    )
+        .into_response()
+}
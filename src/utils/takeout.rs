@@ -0,0 +1,116 @@
+use axum::body::Body;
+use chrono::Utc;
+use futures_util::StreamExt;
+use serde_json::{Map, Value, json};
+use sqlx::PgPool;
+
+/// The document is flushed to the client in chunks of roughly this many bytes.
+const CHUNK_BYTES: usize = 32 * 1024;
+
+/// One category of a user's personal data, as it appears in their export
+/// and privacy summary.
+pub struct TakeoutSection {
+    pub name: &'static str,
+    /// Plain-language summary of what the category holds.
+    pub description: &'static str,
+    /// Keys kept from each row, in this order. Anything else the query
+    /// returns is dropped, so a query shared between roles can be narrowed
+    /// to what each role should see.
+    pub fields: &'static [&'static str],
+    /// Rows belonging to the user `$1`.
+    pub sql: &'static str,
+    /// The section is one object (the profile) rather than a list.
+    pub single: bool,
+}
+
+impl TakeoutSection {
+    fn filter(&self, row: &str) -> Value {
+        let mut record: Map<String, Value> = serde_json::from_str(row).unwrap_or_default();
+        let kept = self
+            .fields
+            .iter()
+            .map(|field| (field.to_string(), record.remove(*field).unwrap_or(Value::Null)))
+            .collect();
+        Value::Object(kept)
+    }
+}
+
+/// How many rows each section holds for the user, for the privacy summary.
+pub async fn counts(
+    pool: &PgPool,
+    user_id: i32,
+    sections: &'static [TakeoutSection],
+) -> Result<Vec<(&'static TakeoutSection, i64)>, sqlx::Error> {
+    let mut counts = Vec::with_capacity(sections.len());
+    for section in sections {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM ({}) t", section.sql))
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+        counts.push((section, count));
+    }
+    Ok(counts)
+}
+
+/// Streams the user's sections as one JSON document:
+/// `{"role": ..., "exported_at": ..., "<section>": [...], ...}`. Rows are read
+/// with a cursor and written out in chunks, so a long message history is
+/// never held in memory. A database error mid-way aborts the response, which
+/// the client sees as a truncated download rather than a valid document.
+pub fn stream_document(pool: PgPool, user_id: i32, role: &'static str, sections: &'static [TakeoutSection]) -> Body {
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<String, std::io::Error>>(8);
+
+    tokio::spawn(async move {
+        let header = json!({ "role": role, "exported_at": Utc::now() }).to_string();
+        // Keep the header's fields and drop its closing brace; sections follow
+        let mut buf = header[..header.len() - 1].to_string();
+
+        for section in sections {
+            buf.push_str(&format!(",\"{}\":", section.name));
+            if !section.single {
+                buf.push('[');
+            }
+
+            let sql = format!("SELECT row_to_json(t)::text FROM ({}) t", section.sql);
+            let mut cursor = sqlx::query_scalar::<_, String>(&sql).bind(user_id).fetch(&pool);
+            let mut rows = 0;
+            while let Some(row) = cursor.next().await {
+                let row = match row {
+                    Ok(row) => row,
+                    Err(e) => {
+                        tracing::error!("Takeout of {} for user {} failed: {}", section.name, user_id, e);
+                        let _ = tx.send(Err(std::io::Error::other(e))).await;
+                        return;
+                    }
+                };
+                if rows > 0 {
+                    if section.single {
+                        break;
+                    }
+                    buf.push(',');
+                }
+                buf.push_str(&section.filter(&row).to_string());
+                rows += 1;
+
+                if buf.len() >= CHUNK_BYTES && tx.send(Ok(std::mem::take(&mut buf))).await.is_err() {
+                    // Client went away
+                    return;
+                }
+            }
+
+            if section.single {
+                if rows == 0 {
+                    buf.push_str("null");
+                }
+            } else {
+                buf.push(']');
+            }
+        }
+        buf.push('}');
+        let _ = tx.send(Ok(buf)).await;
+    });
+
+    Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
@@ -0,0 +1,154 @@
+use crate::utils::storage::SharedStorage;
+use bytes::Bytes;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageDecoder, ImageReader, Rgb, RgbImage};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::io::Cursor;
+
+/// Longest edge of each stored variant, in pixels.
+const THUMB_SIZE: u32 = 200;
+const MEDIUM_SIZE: u32 = 800;
+const JPEG_QUALITY: u8 = 80;
+
+/// URLs handed back for an uploaded image. The variants fall back to the
+/// original when they could not be generated.
+#[derive(Serialize, Debug, Clone)]
+pub struct ImageUrls {
+    pub url: String,
+    pub thumb_url: String,
+    pub medium_url: String,
+}
+
+impl ImageUrls {
+    fn original_only(url: &str) -> Self {
+        ImageUrls {
+            url: url.to_string(),
+            thumb_url: url.to_string(),
+            medium_url: url.to_string(),
+        }
+    }
+}
+
+/// Shrinks the image to fit within `size` (never upscaling) and encodes it as
+/// JPEG. Alpha is flattened onto white.
+fn resize_to_jpeg(img: &DynamicImage, size: u32) -> Result<Vec<u8>, image::ImageError> {
+    let resized = if img.width() > size || img.height() > size {
+        img.thumbnail(size, size)
+    } else {
+        img.clone()
+    };
+
+    let rgba = resized.to_rgba8();
+    let flattened = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    });
+
+    // Re-encoding from pixels drops EXIF (GPS, camera) and any other metadata
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY).encode_image(&flattened)?;
+    Ok(out)
+}
+
+/// Decodes the upload, applies its EXIF orientation and renders both sizes.
+fn render_variants(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), image::ImageError> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+
+    Ok((resize_to_jpeg(&img, THUMB_SIZE)?, resize_to_jpeg(&img, MEDIUM_SIZE)?))
+}
+
+/// Key for a variant stored next to the original: `a/b/<uuid>.png` becomes
+/// `a/b/<uuid>_thumb.jpg`.
+pub fn variant_key(key: &str, variant: &str) -> String {
+    let stem = key.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(key);
+    format!("{}_{}.jpg", stem, variant)
+}
+
+/// Generates and stores thumb/medium variants of an image that was just
+/// saved at `key` (public URL `url`), and records them in `image_variants`
+/// so later reads can find them. Any failure is logged and the original URL
+/// is used for every size; the upload itself is never failed.
+pub async fn store_image_variants(
+    pool: &PgPool,
+    storage: &SharedStorage,
+    key: &str,
+    url: &str,
+    data: Bytes,
+) -> ImageUrls {
+    let rendered = match tokio::task::spawn_blocking(move || render_variants(&data)).await {
+        Ok(Ok(rendered)) => rendered,
+        Ok(Err(e)) => {
+            tracing::warn!("Could not generate variants for {}: {}", key, e);
+            return ImageUrls::original_only(url);
+        }
+        Err(e) => {
+            tracing::error!("Variant generation task for {} failed: {}", key, e);
+            return ImageUrls::original_only(url);
+        }
+    };
+
+    let (thumb_key, medium_key) = (variant_key(key, "thumb"), variant_key(key, "medium"));
+    let (thumb, medium) = (Bytes::from(rendered.0), Bytes::from(rendered.1));
+    let saved = async {
+        let thumb_url = storage.save(&thumb_key, &thumb, "image/jpeg").await?;
+        let medium_url = storage.save(&medium_key, &medium, "image/jpeg").await?;
+        sqlx::query!(
+            r#"INSERT INTO image_variants (original_path, thumb_path, medium_path)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (original_path) DO UPDATE
+               SET thumb_path = EXCLUDED.thumb_path, medium_path = EXCLUDED.medium_path"#,
+            url,
+            thumb_url,
+            medium_url
+        )
+        .execute(pool)
+        .await?;
+        Ok::<_, crate::errors::AppError>(ImageUrls {
+            url: url.to_string(),
+            thumb_url,
+            medium_url,
+        })
+    }
+    .await;
+
+    match saved {
+        Ok(urls) => urls,
+        Err(e) => {
+            tracing::warn!("Could not store variants for {}: {}", key, e);
+            let _ = storage.delete(&thumb_key).await;
+            let _ = storage.delete(&medium_key).await;
+            ImageUrls::original_only(url)
+        }
+    }
+}
+
+/// Removes the stored variants of an original that is being deleted.
+pub async fn delete_image_variants(pool: &PgPool, storage: &SharedStorage, url: &str) {
+    let variants = match sqlx::query!(
+        "DELETE FROM image_variants WHERE original_path = $1 RETURNING thumb_path, medium_path",
+        url
+    )
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(v)) => v,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!("Could not look up variants of {}: {}", url, e);
+            return;
+        }
+    };
+
+    for path in [variants.thumb_path, variants.medium_path] {
+        if let Some(key) = storage.key_for_url(&path) {
+            let _ = storage.delete(&key).await;
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use crate::errors::{AppError, AppResult, error_body};
+use crate::extractors::current_user::CurrentUser;
+use crate::utils::attachments::{
+    AttachmentTarget, AttachmentText, IncomingFile, attachment_usage, display_file_name, owns_attachment_target,
+    parse_visibility, quota_exceeded, store_attachment,
+};
+use crate::utils::image_upload::{SniffedMedia, UploadLimits, check_media};
+use crate::utils::spool::{SpoolWriter, SpooledFile};
+use crate::utils::storage::{PRIVATE_PREFIX, SharedStorage};
+use crate::utils::video::SharedVideoProbe;
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+};
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+/// Largest chunk a client may send, and the default chunk size.
+pub const MAX_CHUNK_BYTES: usize = 5 * 1024 * 1024;
+
+/// Smallest chunk size a session may use (the last chunk may be shorter).
+const MIN_CHUNK_BYTES: i32 = 256 * 1024;
+
+/// Sessions with no activity for this long are treated as abandoned and
+/// purged by the cleanup job.
+pub const SESSION_TTL_HOURS: i32 = 24;
+
+/// Unfinished sessions one user may have open at a time.
+const MAX_OPEN_SESSIONS: i64 = 5;
+
+/// Request header carrying the hex SHA-256 of a chunk, checked when present.
+pub const CHUNK_CHECKSUM_HEADER: &str = "x-chunk-sha256";
+
+/// Storage key for one received chunk. Chunks sit under the private prefix so
+/// they are never served directly.
+pub fn chunk_key(session_id: i32, chunk_number: i32) -> String {
+    format!("{}upload_chunks/{}_{}", PRIVATE_PREFIX, session_id, chunk_number)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+struct UploadSession {
+    id: i32,
+    target_type: String,
+    target_id: i32,
+    visibility: String,
+    file_name: String,
+    total_bytes: i64,
+    chunk_bytes: i32,
+    checksum: String,
+    caption: Option<String>,
+    alt_text: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+impl UploadSession {
+    fn chunk_count(&self) -> i32 {
+        ((self.total_bytes + self.chunk_bytes as i64 - 1) / self.chunk_bytes as i64) as i32
+    }
+
+    /// Every chunk is `chunk_bytes` long except the last, which holds the rest.
+    fn chunk_len(&self, chunk_number: i32) -> i64 {
+        if chunk_number == self.chunk_count() - 1 {
+            self.total_bytes - chunk_number as i64 * self.chunk_bytes as i64
+        } else {
+            self.chunk_bytes as i64
+        }
+    }
+
+    fn expires_at(&self) -> DateTime<Utc> {
+        self.updated_at + Duration::hours(SESSION_TTL_HOURS as i64)
+    }
+}
+
+/// The caller's session, unless it has expired.
+async fn load_session(pool: &PgPool, id: i32, user_id: i32) -> AppResult<UploadSession> {
+    sqlx::query_as!(
+        UploadSession,
+        r#"SELECT id, target_type, target_id, visibility, file_name, total_bytes, chunk_bytes, checksum,
+                  caption, alt_text, updated_at
+           FROM upload_sessions
+           WHERE id = $1 AND user_id = $2 AND updated_at > NOW() - make_interval(hours => $3)"#,
+        id,
+        user_id,
+        SESSION_TTL_HOURS
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Upload session not found or expired".to_string()))
+}
+
+async fn received_chunks(pool: &PgPool, session_id: i32) -> AppResult<Vec<i32>> {
+    Ok(sqlx::query_scalar!(
+        "SELECT chunk_number FROM upload_chunks WHERE session_id = $1 ORDER BY chunk_number",
+        session_id
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Removes a session's chunk files; the rows go with the session.
+pub async fn delete_chunk_files(storage: &SharedStorage, session_id: i32, chunk_numbers: &[i32]) {
+    for &n in chunk_numbers {
+        if let Err(e) = storage.delete(&chunk_key(session_id, n)).await {
+            tracing::warn!("Failed to delete chunk {} of upload session {}: {}", n, session_id, e);
+        }
+    }
+}
+
+fn session_status(session: &UploadSession, received: &[i32]) -> serde_json::Value {
+    json!({
+        "upload_id": session.id,
+        "file_name": session.file_name,
+        "target_type": session.target_type,
+        "target_id": session.target_id,
+        "visibility": session.visibility,
+        "total_size": session.total_bytes,
+        "chunk_size": session.chunk_bytes,
+        "chunk_count": session.chunk_count(),
+        "received_chunks": received,
+        "caption": session.caption,
+        "alt_text": session.alt_text,
+        "expires_at": session.expires_at(),
+    })
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CreateUploadSession {
+    pub target_type: String,
+    pub target_id: i32,
+    pub visibility: Option<String>,
+    pub file_name: String,
+    /// Size of the whole file in bytes.
+    pub total_size: i64,
+    /// Hex SHA-256 of the whole file.
+    pub checksum: String,
+    /// Bytes per chunk; defaults to the maximum.
+    pub chunk_size: Option<i32>,
+    pub caption: Option<String>,
+    pub alt_text: Option<String>,
+}
+
+/// Opens a resumable upload. Size and quota are checked up front so a client
+/// doesn't send 30MB only to be refused at the end.
+pub async fn create_upload_session(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Json(payload): Json<CreateUploadSession>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let visibility = parse_visibility(payload.visibility)?;
+    let file_name = display_file_name(&payload.file_name);
+    let text = AttachmentText {
+        caption: payload.caption,
+        alt_text: payload.alt_text,
+    }
+    .normalized()?;
+    let checksum = payload.checksum.trim().to_ascii_lowercase();
+    if !is_sha256_hex(&checksum) {
+        return Err(AppError::BadRequest(
+            "checksum must be the file's SHA-256 as 64 hex characters".to_string(),
+        ));
+    }
+    if payload.total_size <= 0 {
+        return Err(AppError::BadRequest("total_size must be positive".to_string()));
+    }
+    let chunk_bytes = payload.chunk_size.unwrap_or(MAX_CHUNK_BYTES as i32);
+    if !(MIN_CHUNK_BYTES..=MAX_CHUNK_BYTES as i32).contains(&chunk_bytes) {
+        return Err(AppError::BadRequest(format!(
+            "chunk_size must be between {} and {} bytes",
+            MIN_CHUNK_BYTES, MAX_CHUNK_BYTES
+        )));
+    }
+
+    if !owns_attachment_target(&pool, user_id, &payload.target_type, payload.target_id).await? {
+        return Err(AppError::Forbidden(
+            "You do not have permission to add attachments here".to_string(),
+        ));
+    }
+
+    // The file's type isn't known until it arrives, so only the larger cap
+    // applies here; the exact one is checked on completion
+    let limits = UploadLimits::load(&pool, true).await;
+    let max_bytes = limits.max_image_bytes.max(limits.max_video_bytes.unwrap_or(0));
+    if payload.total_size > max_bytes as i64 {
+        return Err(AppError::PayloadTooLarge(format!(
+            "{} exceeds the {} byte upload limit",
+            file_name, max_bytes
+        )));
+    }
+    let usage = attachment_usage(&pool, &payload.target_type, payload.target_id).await?;
+    if usage.files >= usage.max_files {
+        return Ok(quota_exceeded("Attachment file limit reached", &usage));
+    }
+    if usage.bytes + payload.total_size > usage.max_bytes {
+        return Ok(quota_exceeded("Attachment storage limit reached", &usage));
+    }
+
+    let open = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM upload_sessions
+           WHERE user_id = $1 AND updated_at > NOW() - make_interval(hours => $2)"#,
+        user_id,
+        SESSION_TTL_HOURS
+    )
+    .fetch_one(&pool)
+    .await?;
+    if open >= MAX_OPEN_SESSIONS {
+        return Err(AppError::Conflict(format!(
+            "At most {} uploads can be in progress at once; complete or cancel one first",
+            MAX_OPEN_SESSIONS
+        )));
+    }
+
+    let session = sqlx::query_as!(
+        UploadSession,
+        r#"INSERT INTO upload_sessions
+               (user_id, target_type, target_id, visibility, file_name, total_bytes, chunk_bytes, checksum, caption, alt_text)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+           RETURNING id, target_type, target_id, visibility, file_name, total_bytes, chunk_bytes, checksum,
+                     caption, alt_text, updated_at"#,
+        user_id,
+        payload.target_type,
+        payload.target_id,
+        visibility,
+        file_name,
+        payload.total_size,
+        chunk_bytes,
+        checksum,
+        text.caption,
+        text.alt_text
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(session_status(&session, &[]))))
+}
+
+/// What has arrived so far, so an interrupted client knows where to resume.
+pub async fn get_upload_session(
+    State(pool): State<PgPool>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let session = load_session(&pool, id, user_id).await?;
+    let received = received_chunks(&pool, id).await?;
+    Ok((StatusCode::OK, Json(session_status(&session, &received))))
+}
+
+/// Stores chunk `n` (counted from 0). Sending the same chunk again is
+/// harmless; a chunk whose length or checksum is wrong is refused.
+pub async fn put_upload_chunk(
+    State(pool): State<PgPool>,
+    Extension(storage): Extension<SharedStorage>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path((id, chunk_number)): Path<(i32, i32)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let session = load_session(&pool, id, user_id).await?;
+    let chunk_count = session.chunk_count();
+    if !(0..chunk_count).contains(&chunk_number) {
+        return Err(AppError::BadRequest(format!(
+            "Chunk number must be between 0 and {}",
+            chunk_count - 1
+        )));
+    }
+    let expected_len = session.chunk_len(chunk_number);
+    if body.len() as i64 != expected_len {
+        return Err(AppError::BadRequest(format!(
+            "Chunk {} must be {} bytes, got {}",
+            chunk_number,
+            expected_len,
+            body.len()
+        )));
+    }
+
+    let digest = sha256_hex(&body);
+    if let Some(claimed) = headers.get(CHUNK_CHECKSUM_HEADER) {
+        let claimed = claimed.to_str().unwrap_or_default().trim();
+        if !claimed.eq_ignore_ascii_case(&digest) {
+            return Err(AppError::BadRequest(format!(
+                "Chunk {} does not match its checksum; send it again",
+                chunk_number
+            )));
+        }
+    }
+
+    let stored = sqlx::query_scalar!(
+        "SELECT sha256 FROM upload_chunks WHERE session_id = $1 AND chunk_number = $2",
+        id,
+        chunk_number
+    )
+    .fetch_optional(&pool)
+    .await?;
+    if stored.as_deref() != Some(digest.as_str()) {
+        storage
+            .save(&chunk_key(id, chunk_number), &body, "application/octet-stream")
+            .await?;
+        sqlx::query!(
+            r#"INSERT INTO upload_chunks (session_id, chunk_number, size_bytes, sha256)
+               VALUES ($1, $2, $3, $4)
+               ON CONFLICT (session_id, chunk_number)
+               DO UPDATE SET size_bytes = EXCLUDED.size_bytes, sha256 = EXCLUDED.sha256"#,
+            id,
+            chunk_number,
+            body.len() as i32,
+            digest
+        )
+        .execute(&pool)
+        .await?;
+    }
+    sqlx::query!("UPDATE upload_sessions SET updated_at = NOW() WHERE id = $1", id)
+        .execute(&pool)
+        .await?;
+
+    let received = received_chunks(&pool, id).await?;
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "chunk": chunk_number,
+            "received_chunks": received.len(),
+            "chunk_count": chunk_count,
+        })),
+    ))
+}
+
+/// Assembles the chunks, verifies the whole-file checksum and then files the
+/// result like any other upload. A checksum mismatch leaves the session open
+/// so chunks can be re-sent.
+pub async fn complete_upload_session(
+    State(pool): State<PgPool>,
+    Extension(storage): Extension<SharedStorage>,
+    Extension(video_probe): Extension<SharedVideoProbe>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    let session = load_session(&pool, id, user_id).await?;
+    let received = received_chunks(&pool, id).await?;
+    let missing: Vec<i32> = (0..session.chunk_count())
+        .filter(|n| received.binary_search(n).is_err())
+        .collect();
+    if !missing.is_empty() {
+        return Ok((
+            StatusCode::CONFLICT,
+            error_body(
+                "upload_incomplete",
+                "Some chunks have not been received",
+                Some(json!({ "missing_chunks": missing })),
+            ),
+        ));
+    }
+
+    if !owns_attachment_target(&pool, user_id, &session.target_type, session.target_id).await? {
+        return Err(AppError::Forbidden(
+            "You do not have permission to add attachments here".to_string(),
+        ));
+    }
+
+    // Chunks are copied into one temporary file, a chunk at a time, hashing
+    // as they go; the type is checked from the first chunk
+    let limits = UploadLimits::load(&pool, true).await;
+    let mut hasher = Sha256::new();
+    let mut spool: Option<(SpoolWriter, SniffedMedia)> = None;
+    for &n in &received {
+        let len = session.chunk_len(n) as u64;
+        let body = storage.read_range(&chunk_key(id, n), 0, len - 1).await?;
+        let chunk = axum::body::to_bytes(body, len as usize)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read chunk {} of upload {}: {}", n, id, e)))?;
+        hasher.update(&chunk);
+        let writer = match &mut spool {
+            Some((writer, _)) => writer,
+            None => {
+                let media = check_media(&chunk, session.total_bytes as u64, &session.file_name, &limits)?;
+                &mut spool.insert((SpooledFile::create(media.extension).await?, media)).0
+            }
+        };
+        writer.write(&chunk).await?;
+    }
+    let (writer, media) =
+        spool.ok_or_else(|| AppError::BadRequest(format!("{} is empty", session.file_name)))?;
+    let data = writer.finish().await?;
+    if data.size() as i64 != session.total_bytes || hex::encode(hasher.finalize()) != session.checksum {
+        return Err(AppError::BadRequest(
+            "The assembled file does not match its checksum; re-send the chunks and try again".to_string(),
+        ));
+    }
+
+    let mut usage = attachment_usage(&pool, &session.target_type, session.target_id).await?;
+    if usage.files >= usage.max_files {
+        return Ok(quota_exceeded("Attachment file limit reached", &usage));
+    }
+    if usage.bytes + data.size() as i64 > usage.max_bytes {
+        return Ok(quota_exceeded("Attachment storage limit reached", &usage));
+    }
+
+    // Claiming the row first means a repeated request can't file the upload twice
+    let claimed = sqlx::query_scalar!("DELETE FROM upload_sessions WHERE id = $1 RETURNING id", id)
+        .fetch_optional(&pool)
+        .await?;
+    if claimed.is_none() {
+        return Err(AppError::NotFound("Upload session not found or expired".to_string()));
+    }
+
+    let target = AttachmentTarget {
+        target_type: session.target_type,
+        target_id: session.target_id,
+        visibility: session.visibility,
+        uploaded_by: user_id,
+    };
+    let size_bytes = data.size() as i64;
+    let file = IncomingFile {
+        file_name: session.file_name,
+        text: AttachmentText {
+            caption: session.caption,
+            alt_text: session.alt_text,
+        },
+        data,
+        media,
+    };
+    let result = store_attachment(&pool, &storage, &video_probe, &target, file).await;
+    delete_chunk_files(&storage, id, &received).await;
+    let (_, attachment) = result?;
+
+    usage.files += 1;
+    usage.bytes += size_bytes;
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "message": "File uploaded successfully",
+            "attachment": attachment,
+            "usage": usage,
+        })),
+    ))
+}
+
+/// Abandons an upload and frees its chunks.
+pub async fn cancel_upload_session(
+    State(pool): State<PgPool>,
+    Extension(storage): Extension<SharedStorage>,
+    CurrentUser { user_id, .. }: CurrentUser,
+    Path(id): Path<i32>,
+) -> AppResult<(StatusCode, Json<serde_json::Value>)> {
+    load_session(&pool, id, user_id).await?;
+    let received = received_chunks(&pool, id).await?;
+    sqlx::query!("DELETE FROM upload_sessions WHERE id = $1", id)
+        .execute(&pool)
+        .await?;
+    delete_chunk_files(&storage, id, &received).await;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Upload cancelled" }))))
+}
@@ -0,0 +1,176 @@
+use crate::errors::{AppError, AppResult};
+use axum::async_trait;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::process::Command;
+
+/// What probing a video reports.
+#[derive(Debug, Clone, Copy)]
+pub struct VideoInfo {
+    pub duration_seconds: f64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+/// Reads video metadata and frames. The ffmpeg-backed implementation is used
+/// in production; tests pass a stub to `build_app_with_video_probe`.
+#[async_trait]
+pub trait VideoProbe: Send + Sync {
+    async fn probe(&self, path: &Path) -> AppResult<VideoInfo>;
+
+    /// A single frame at `at_seconds`, encoded as JPEG.
+    async fn poster_frame(&self, path: &Path, at_seconds: f64) -> AppResult<Vec<u8>>;
+}
+
+// ── ffmpeg implementation ─────────────────────────────────────────────────────
+
+/// Shells out to `ffprobe` / `ffmpeg`, at the paths configured by
+/// `FFPROBE_PATH` / `FFMPEG_PATH`.
+pub struct FfmpegProbe {
+    ffprobe: String,
+    ffmpeg: String,
+}
+
+pub type SharedVideoProbe = Arc<dyn VideoProbe>;
+
+impl FfmpegProbe {
+    pub fn new(ffprobe: &str, ffmpeg: &str) -> Self {
+        FfmpegProbe {
+            ffprobe: ffprobe.to_string(),
+            ffmpeg: ffmpeg.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    width: Option<i32>,
+    height: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    // ffprobe prints numbers as strings
+    duration: Option<String>,
+}
+
+/// Runs a tool to completion, returning its stdout. A tool that isn't
+/// installed is a server problem; one that fails is taken to mean the file
+/// isn't a usable video.
+async fn run_tool(program: &str, args: &[&str]) -> AppResult<Vec<u8>> {
+    let output = Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| {
+            tracing::error!("Could not run {}: {}", program, e);
+            AppError::Internal("Video processing is unavailable".to_string())
+        })?;
+
+    if !output.status.success() {
+        tracing::warn!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Err(AppError::BadRequest("The video could not be read".to_string()));
+    }
+    Ok(output.stdout)
+}
+
+#[async_trait]
+impl VideoProbe for FfmpegProbe {
+    async fn probe(&self, path: &Path) -> AppResult<VideoInfo> {
+        let path = path.to_string_lossy();
+        let stdout = run_tool(
+            &self.ffprobe,
+            &[
+                "-v", "error",
+                "-select_streams", "v:0",
+                "-show_entries", "stream=width,height:format=duration",
+                "-of", "json",
+                &path,
+            ],
+        )
+        .await?;
+
+        let parsed: ProbeOutput = serde_json::from_slice(&stdout)
+            .map_err(|e| AppError::Internal(format!("Unexpected ffprobe output: {}", e)))?;
+        let stream = parsed.streams.first().ok_or_else(|| {
+            AppError::BadRequest("The video has no video stream".to_string())
+        })?;
+        let duration_seconds = parsed
+            .format
+            .and_then(|f| f.duration)
+            .and_then(|d| d.parse::<f64>().ok())
+            .filter(|d| d.is_finite() && *d >= 0.0)
+            .ok_or_else(|| AppError::BadRequest("The video's length could not be read".to_string()))?;
+
+        Ok(VideoInfo {
+            duration_seconds,
+            width: stream.width,
+            height: stream.height,
+        })
+    }
+
+    async fn poster_frame(&self, path: &Path, at_seconds: f64) -> AppResult<Vec<u8>> {
+        let path = path.to_string_lossy();
+        let at = format!("{:.3}", at_seconds);
+        run_tool(
+            &self.ffmpeg,
+            &[
+                "-v", "error",
+                "-ss", &at,
+                "-i", &path,
+                "-frames:v", "1",
+                "-f", "image2",
+                "-c:v", "mjpeg",
+                "pipe:1",
+            ],
+        )
+        .await
+    }
+}
+
+// ── Inspection ────────────────────────────────────────────────────────────────
+
+/// A probed upload, plus its poster frame when one could be taken.
+pub struct InspectedVideo {
+    pub info: VideoInfo,
+    pub poster: Option<Vec<u8>>,
+}
+
+/// Probes an uploaded video, already on disk at `path`, and grabs a poster
+/// frame. Videos longer than `max_seconds` are refused; a missing poster
+/// only gets logged.
+pub async fn inspect_video(probe: &dyn VideoProbe, path: &Path, max_seconds: f64) -> AppResult<InspectedVideo> {
+    let info = probe.probe(path).await?;
+    if info.duration_seconds > max_seconds {
+        return Err(AppError::BadRequest(format!(
+            "Videos can be at most {} seconds long",
+            max_seconds
+        )));
+    }
+
+    // A second in, unless the clip is shorter, skips black lead-in frames
+    let at = (info.duration_seconds / 2.0).min(1.0);
+    let poster = match probe.poster_frame(path, at).await {
+        Ok(frame) if !frame.is_empty() => Some(frame),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Could not extract a poster frame: {}", e);
+            None
+        }
+    };
+
+    Ok(InspectedVideo { info, poster })
+}
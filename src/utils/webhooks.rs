@@ -0,0 +1,484 @@
+use crate::config;
+use crate::utils::jobs::{self, JobReport, WEBHOOK_DELIVERY};
+use crate::utils::notifications::notify_and_push;
+use crate::utils::ws_state::WsConnections;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use sha2::Sha256;
+use sqlx::{PgExecutor, PgPool};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+// ── Events ────────────────────────────────────────────────────────────────────
+
+pub const BOOKING_CREATED: &str = "booking.created";
+pub const BOOKING_STATUS_CHANGED: &str = "booking.status_changed";
+pub const REVIEW_CREATED: &str = "review.created";
+
+/// Every event a subscription can ask for.
+pub const EVENT_TYPES: [&str; 3] = [BOOKING_CREATED, BOOKING_STATUS_CHANGED, REVIEW_CREATED];
+
+/// Queues `event` about a booking for each active subscription on the
+/// booking's provider or business that wants it. Run it in the transaction
+/// that made the change, so the event is only sent if the change sticks.
+pub async fn enqueue_booking_event<'e>(
+    executor: impl PgExecutor<'e>,
+    event: &str,
+    booking_id: i32,
+    previous_status: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO webhook_deliveries (subscription_id, event_type, payload)
+           SELECT s.id, $1, jsonb_build_object(
+                      'event', $1::text,
+                      'occurred_at', NOW(),
+                      'data', jsonb_build_object('booking', jsonb_build_object(
+                          'id', b.id,
+                          'status', b.status,
+                          'previous_status', $3::text,
+                          'target_type', b.target_type,
+                          'target_id', b.target_id,
+                          'service_id', b.service_id,
+                          'scheduled_time', b.scheduled_time,
+                          'duration', b.duration,
+                          'confirmation_code', b.confirmation_code)))
+           FROM bookings b
+           JOIN webhook_subscriptions s
+             ON s.target_type = b.target_type AND s.target_id = b.target_id
+           WHERE b.id = $2 AND s.active AND $1 = ANY(s.event_types)"#,
+        event,
+        booking_id,
+        previous_status
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Queues `review.created` for the reviewed provider or business.
+pub async fn enqueue_review_created<'e>(executor: impl PgExecutor<'e>, review_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO webhook_deliveries (subscription_id, event_type, payload)
+           SELECT s.id, $1, jsonb_build_object(
+                      'event', $1::text,
+                      'occurred_at', NOW(),
+                      'data', jsonb_build_object('review', jsonb_build_object(
+                          'id', r.id,
+                          'target_type', r.target_type,
+                          'target_id', r.target_id,
+                          'rating', r.rating,
+                          'comment', r.comment,
+                          'verified', r.verified_booking_id IS NOT NULL,
+                          'created_at', r.created_at)))
+           FROM reviews r
+           JOIN webhook_subscriptions s
+             ON s.target_type = r.target_type AND s.target_id = r.target_id
+           WHERE r.id = $2 AND s.active AND $1 = ANY(s.event_types)"#,
+        REVIEW_CREATED,
+        review_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+// ── Signing ───────────────────────────────────────────────────────────────────
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256>` over `"<t>.<body>"`, keyed with
+/// the subscription secret.
+pub const SIGNATURE_HEADER: &str = "x-mtaalink-signature";
+pub const EVENT_HEADER: &str = "x-mtaalink-event";
+pub const DELIVERY_HEADER: &str = "x-mtaalink-delivery";
+
+fn signing_mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Hex HMAC-SHA256 of `"<timestamp>.<body>"`.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    hex::encode(signing_mac(secret, timestamp, body).finalize().into_bytes())
+}
+
+/// The `x-mtaalink-signature` value for a delivery sent at `timestamp`.
+pub fn signature_header(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("t={},v1={}", timestamp, signature(secret, timestamp, body))
+}
+
+/// What a receiver does with `x-mtaalink-signature`: true when a `v1`
+/// signature matches `body` and the timestamp is within `tolerance_secs` of
+/// `now`, which stops an old delivery being replayed. Compared in constant time.
+pub fn verify_signature(secret: &str, header: &str, body: &[u8], now: i64, tolerance_secs: i64) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    if (now - timestamp).abs() > tolerance_secs {
+        return false;
+    }
+    signatures.into_iter().any(|candidate| {
+        hex::decode(candidate).is_ok_and(|bytes| signing_mac(secret, timestamp, body).verify_slice(&bytes).is_ok())
+    })
+}
+
+/// A new subscription secret: 32 random bytes, hex-encoded.
+pub fn generate_secret() -> String {
+    format!("whsec_{}", hex::encode(rand::thread_rng().r#gen::<[u8; 32]>()))
+}
+
+/// Checks a subscription URL. Production only delivers over https and never
+/// to loopback, private or link-local addresses written as IP literals or
+/// `localhost`. Names are resolved and checked again at every delivery, by
+/// the client `delivery_client(true)` builds.
+pub fn validate_url(raw: &str, production: bool) -> Result<(), String> {
+    let url = Url::parse(raw).map_err(|_| "url must be an absolute http(s) URL".to_string())?;
+    match url.scheme() {
+        "https" => {}
+        "http" if !production => {}
+        "http" => return Err("url must use https".to_string()),
+        _ => return Err("url must be an absolute http(s) URL".to_string()),
+    }
+    let host = url.host_str().filter(|h| !h.is_empty()).ok_or("url must include a host")?;
+    if production && is_internal_host(host) {
+        return Err("url must not point at an internal address".to_string());
+    }
+    Ok(())
+}
+
+/// `localhost` names, and IP literals `is_internal` refuses.
+fn is_internal_host(host: &str) -> bool {
+    match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => is_internal(ip),
+        Err(_) => host.eq_ignore_ascii_case("localhost") || host.to_ascii_lowercase().ends_with(".localhost"),
+    }
+}
+
+/// Addresses a webhook must never reach: loopback, private, link-local (and
+/// with it cloud metadata at 169.254.169.254), shared CGNAT, `0.0.0.0/8`,
+/// broadcast and multicast, including IPv4 written as IPv6.
+pub fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || a == 0
+                // 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            // ::ffff:a.b.c.d, and a.b.c.d behind the 64:ff9b::/96 NAT64 prefix
+            let [.., w, x, y, z] = ip.octets();
+            if ip.to_ipv4_mapped().is_some() || ip.segments()[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                return is_internal(IpAddr::V4(Ipv4Addr::new(w, x, y, z)));
+            }
+            let first = ip.segments()[0];
+            // fc00::/7 is unique-local, fe80::/10 link-local
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// Resolves delivery hosts and refuses the lot if any address is internal,
+/// so a name can't be pointed inside after its subscription was accepted.
+/// The client connects only to the addresses returned here.
+struct PublicOnlyResolver;
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| is_internal(addr.ip())) {
+                return Err(format!("{} resolves to internal address {}", host, addr.ip()).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+// ── Delivery ──────────────────────────────────────────────────────────────────
+
+/// Attempts per delivery before it is marked failed.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Failed attempts in a row, across a subscription's deliveries, after which
+/// the subscription is switched off and its owner told.
+pub const DISABLE_AFTER_FAILURES: i32 = 15;
+
+/// Wait before the first retry; each later retry waits four times longer
+/// (1, 4, 16 and 64 minutes).
+const FIRST_RETRY_DELAY_SECS: i64 = 60;
+
+const DELIVERY_BATCH: i64 = 100;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest response snippet kept in `last_error`.
+const MAX_ERROR_CHARS: usize = 300;
+
+/// How long to wait after a delivery's `attempts`-th failure.
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, MAX_ATTEMPTS) as u32 - 1;
+    chrono::Duration::seconds(FIRST_RETRY_DELAY_SECS * 4i64.pow(exponent))
+}
+
+/// HTTP client for deliveries, and whether it keeps them off internal
+/// addresses.
+pub struct DeliveryClient {
+    http: reqwest::Client,
+    public_only: bool,
+}
+
+impl DeliveryClient {
+    /// Why `url` may not be sent to, for IP-literal hosts, which never reach
+    /// the resolver.
+    fn refusal(&self, url: &str) -> Option<String> {
+        let host = Url::parse(url).ok()?.host_str()?.to_string();
+        let ip = host.trim_matches(['[', ']']).parse::<IpAddr>().ok()?;
+        (self.public_only && is_internal(ip)).then(|| format!("{} is an internal address", host))
+    }
+}
+
+/// Client used for deliveries: short timeout, and redirects are not followed
+/// so a subscription can't bounce requests somewhere it wasn't allowed to
+/// point. With `public_only` (production), hosts that are or resolve to
+/// internal addresses are refused, and proxies are bypassed so the address
+/// checked is the one connected to.
+pub fn delivery_client(public_only: bool) -> DeliveryClient {
+    let mut builder = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .user_agent("mtaalink-webhooks/1");
+    if public_only {
+        builder = builder.no_proxy().dns_resolver(Arc::new(PublicOnlyResolver));
+    }
+    DeliveryClient {
+        http: builder.build().expect("webhook HTTP client builds"),
+        public_only,
+    }
+}
+
+/// Spawns a background loop that sends due webhook deliveries every minute.
+pub fn start_webhook_delivery_task(pool: PgPool, ws_conns: WsConnections, mut trigger: jobs::JobTrigger) {
+    let client = delivery_client(config::get().app_env == "production");
+    tokio::spawn(async move {
+        while jobs::wait_for_next_run(&WEBHOOK_DELIVERY, &mut trigger).await {
+            jobs::record_run(&pool, &WEBHOOK_DELIVERY, deliver_due(&pool, &client, &ws_conns)).await;
+        }
+    });
+}
+
+/// Sends every pending delivery whose retry time has come, oldest first.
+pub async fn deliver_due(pool: &PgPool, client: &DeliveryClient, ws_conns: &WsConnections) -> JobReport {
+    let mut report = JobReport::default();
+
+    let due = match sqlx::query!(
+        r#"SELECT d.id, d.event_type, d.payload, s.id AS subscription_id, s.url, s.secret
+           FROM webhook_deliveries d
+           JOIN webhook_subscriptions s ON s.id = d.subscription_id
+           WHERE d.status = 'pending' AND d.next_attempt_at <= NOW() AND s.active
+           ORDER BY d.next_attempt_at, d.id
+           LIMIT $1"#,
+        DELIVERY_BATCH
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            report.fail("Webhook delivery query failed", e);
+            return report;
+        }
+    };
+
+    // Subscriptions switched off during this pass get no more attempts
+    let mut disabled = HashSet::new();
+    for delivery in due {
+        if disabled.contains(&delivery.subscription_id) {
+            continue;
+        }
+        let outcome = attempt(
+            client,
+            delivery.id,
+            &delivery.url,
+            &delivery.event_type,
+            &delivery.secret,
+            &delivery.payload,
+        )
+        .await;
+
+        let recorded = match outcome {
+            Ok(code) => record_success(pool, delivery.id, delivery.subscription_id, code as i32).await,
+            Err((code, error)) => {
+                match record_failure(pool, delivery.id, delivery.subscription_id, code, &error).await {
+                    Ok(Some(owner_id)) => {
+                        disabled.insert(delivery.subscription_id);
+                        tracing::warn!("Webhook subscription {} disabled after repeated failures", delivery.subscription_id);
+                        notify_and_push(
+                            pool, ws_conns, owner_id, "webhook_disabled",
+                            "Webhook Disabled",
+                            &format!(
+                                "Webhook #{} was switched off after {} failed deliveries in a row. Fix the endpoint and re-enable it.",
+                                delivery.subscription_id, DISABLE_AFTER_FAILURES
+                            ),
+                            Some("webhook"), Some(delivery.subscription_id),
+                        ).await;
+                        Ok(())
+                    }
+                    Ok(None) => Ok(()),
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        match recorded {
+            Ok(()) => report.items_processed += 1,
+            Err(e) => report.fail(&format!("Failed to record webhook delivery {}", delivery.id), e),
+        }
+    }
+
+    report
+}
+
+/// Sends one delivery. Returns the status code, or the code (if there was a
+/// response) and the error to record.
+async fn attempt(
+    client: &DeliveryClient,
+    delivery_id: i64,
+    url: &str,
+    event_type: &str,
+    secret: &str,
+    payload: &serde_json::Value,
+) -> Result<u16, (Option<i32>, String)> {
+    if let Some(refusal) = client.refusal(url) {
+        return Err((None, refusal));
+    }
+    let body = payload.to_string().into_bytes();
+    let sent = client
+        .http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, event_type)
+        .header(DELIVERY_HEADER, delivery_id.to_string())
+        .header(SIGNATURE_HEADER, signature_header(secret, Utc::now().timestamp(), &body))
+        .body(body)
+        .send()
+        .await;
+
+    match sent {
+        Ok(res) if res.status().is_success() => Ok(res.status().as_u16()),
+        Ok(res) => {
+            let code = res.status().as_u16();
+            let text = res.text().await.unwrap_or_default();
+            let snippet: String = text.chars().take(MAX_ERROR_CHARS).collect();
+            Err((Some(code as i32), format!("HTTP {}: {}", code, snippet.trim())))
+        }
+        // reqwest's own message omits the cause, e.g. a refused address
+        Err(e) => {
+            let mut error = e.to_string();
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                error.push_str(&format!(": {}", cause));
+                source = cause.source();
+            }
+            Err((None, error))
+        }
+    }
+}
+
+async fn record_success(pool: &PgPool, delivery_id: i64, subscription_id: i32, status_code: i32) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        r#"UPDATE webhook_deliveries
+           SET status = 'delivered', attempts = attempts + 1, last_status_code = $2,
+               last_error = NULL, delivered_at = NOW()
+           WHERE id = $1"#,
+        delivery_id,
+        status_code
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE webhook_subscriptions SET consecutive_failures = 0 WHERE id = $1 AND consecutive_failures > 0",
+        subscription_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Records a failed attempt and schedules the retry, or gives up after
+/// `MAX_ATTEMPTS`. Returns the owner's user id if this failure switched the
+/// subscription off.
+async fn record_failure(
+    pool: &PgPool,
+    delivery_id: i64,
+    subscription_id: i32,
+    status_code: Option<i32>,
+    error: &str,
+) -> Result<Option<i32>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let attempts = sqlx::query_scalar!(
+        "SELECT attempts FROM webhook_deliveries WHERE id = $1 FOR UPDATE",
+        delivery_id
+    )
+    .fetch_one(&mut *tx)
+    .await?
+        + 1;
+    let next_attempt_at: DateTime<Utc> = Utc::now() + retry_delay(attempts);
+    let status = if attempts >= MAX_ATTEMPTS { "failed" } else { "pending" };
+    sqlx::query!(
+        r#"UPDATE webhook_deliveries
+           SET attempts = $2, status = $3, next_attempt_at = $4, last_status_code = $5, last_error = $6
+           WHERE id = $1"#,
+        delivery_id,
+        attempts,
+        status,
+        next_attempt_at,
+        status_code,
+        error
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let reason = format!("Disabled after {} failed deliveries in a row", DISABLE_AFTER_FAILURES);
+    let disabled_owner = sqlx::query_scalar!(
+        r#"UPDATE webhook_subscriptions
+           SET consecutive_failures = consecutive_failures + 1,
+               active = consecutive_failures + 1 < $2,
+               disabled_reason = CASE WHEN consecutive_failures + 1 >= $2 THEN $3 ELSE disabled_reason END,
+               updated_at = NOW()
+           WHERE id = $1
+           RETURNING CASE WHEN active THEN NULL ELSE user_id END AS "disabled_owner?""#,
+        subscription_id,
+        DISABLE_AFTER_FAILURES,
+        reason
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(disabled_owner)
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::Value;
+
+fn series(body: &Value, metric: &str, key: &str) -> Vec<i64> {
+    body[metric][key].as_array().expect(key).iter().map(|v| v.as_i64().expect("count")).collect()
+}
+
+#[tokio::test]
+async fn platform_analytics_counts_users_bookings_reviews_and_messages() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let (provider_user, provider_id) = seed::provider(&app.pool).await;
+    seed::business(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    seed::booking(&app.pool, client_id, "provider", provider_id, None, "completed").await;
+    sqlx::query("INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment) VALUES ($1, 'provider', $2, 4, 'Good')")
+        .bind(client_id)
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("seed review");
+    sqlx::query("INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content) VALUES ($1, $2, 'provider', $3, 'Hi')")
+        .bind(client_id)
+        .bind(provider_user)
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("seed message");
+    // Onboarded five months ago, before the default 12 weeks
+    let (_, old_provider) = seed::provider(&app.pool).await;
+    sqlx::query("UPDATE providers SET created_at = $2 WHERE id = $1")
+        .bind(old_provider)
+        .bind((Utc::now() - Duration::weeks(20)).naive_utc())
+        .execute(&app.pool)
+        .await
+        .expect("backdate provider");
+
+    let res = app.get("/admin/analytics", Some(&mint_jwt(client_id))).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+
+    let res = app.get("/admin/analytics", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let body = &res.body;
+
+    let roles: Vec<&str> = body["users_by_role"]["labels"].as_array().unwrap().iter().map(|r| r.as_str().unwrap()).collect();
+    let clients = roles.iter().position(|r| *r == "client").expect("client role");
+    // The admin is a client account too
+    assert_eq!(series(body, "users_by_role", "total")[clients], 2);
+
+    let weeks = &body["onboarded_per_week"];
+    assert_eq!(weeks["labels"].as_array().unwrap().len(), 12, "{body}");
+    assert_eq!(series(body, "onboarded_per_week", "providers").iter().sum::<i64>(), 1);
+    assert_eq!(series(body, "onboarded_per_week", "businesses").last(), Some(&1));
+    assert_eq!(series(body, "onboarded_per_week", "clients").last(), Some(&1));
+
+    let statuses = &body["bookings_by_status"];
+    assert_eq!(statuses["labels"].as_array().unwrap().len(), 8);
+    assert_eq!(statuses["labels"][0], "pending");
+    assert_eq!(series(body, "bookings_by_status", "total").iter().sum::<i64>(), 2);
+    assert_eq!(series(body, "bookings_by_status", "total")[0], 1);
+
+    assert_eq!(body["reviews"]["total"], 1);
+    assert_eq!(body["reviews"]["average_rating"], 4.0);
+    assert_eq!(body["reviews"]["by_rating"]["values"], serde_json::json!([0, 0, 0, 1, 0]));
+    assert_eq!(body["messages"]["total"], 1);
+    assert_eq!(body["messages"]["per_week"]["values"].as_array().unwrap().last(), Some(&Value::from(1)));
+
+    // A range reaching back far enough picks up the older provider
+    let from = (Utc::now() - Duration::weeks(26)).date_naive();
+    let res = app.get(&format!("/admin/analytics?from={from}"), Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(series(&res.body, "onboarded_per_week", "providers").iter().sum::<i64>(), 2);
+
+    for query in ["from=2026-03-01&to=2026-02-01", "from=2020-01-01&to=2026-01-01"] {
+        let res = app.get(&format!("/admin/analytics?{query}"), Some(&admin)).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{query}: {}", res.body);
+    }
+}
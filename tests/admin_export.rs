@@ -0,0 +1,41 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+
+// Changes the export row cap, which the per-process settings cache holds, so
+// this file keeps to a single test.
+#[tokio::test]
+async fn exports_over_the_row_cap_are_refused_and_the_rest_stream_in_full() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin_id = seed::admin(&app.pool).await;
+    let admin = mint_jwt(admin_id);
+    for _ in 0..3 {
+        seed::client(&app.pool).await;
+    }
+    let res = app.post_json("/admin/settings", Some(&admin), json!({ "admin.export_row_cap": 3 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.get("/admin/export?dataset=users", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    let message = res.body["message"].as_str().unwrap_or_default();
+    assert!(message.contains("4 rows, above the limit of 3"), "{}", res.body);
+
+    // A refused export is not recorded as having happened
+    let audited: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE action = 'data_exported'")
+        .fetch_one(&app.pool)
+        .await
+        .expect("count exports");
+    assert_eq!(audited, 0);
+
+    let res = app.post_json("/admin/settings", Some(&admin), json!({ "admin.export_row_cap": 4 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/admin/export?dataset=users", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.headers["x-export-rows"], "4");
+    let csv = res.body.as_str().expect("csv body");
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "id,username,email,role,created_at,last_login_at,last_seen_at,suspended");
+    assert_eq!(lines.len(), 5, "{csv}");
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use common::{TestApp, TestResponse, mint_jwt, seed, test_config};
+use serde_json::json;
+use sqlx::PgPool;
+use std::io::Cursor;
+
+const BOUNDARY: &str = "mtaalink-attachment-boundary";
+
+/// Posts one file, named `file_name`, to the provider's attachments.
+async fn upload(app: &TestApp, token: &str, provider_id: i32, file_name: &str, file: &[u8]) -> TestResponse {
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"files\"; filename=\"{file_name}\"\r\n\
+         Content-Type: image/jpeg\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(file);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/attachments/uploadAttachments?target_type=provider&target_id={provider_id}"))
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={BOUNDARY}"))
+        .body(Body::from(body))
+        .expect("build request");
+    app.send(request).await
+}
+
+async fn attachment_count(pool: &PgPool, provider_id: i32) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM attachments WHERE target_type = 'provider' AND target_id = $1")
+        .bind(provider_id)
+        .fetch_one(pool)
+        .await
+        .expect("count attachments")
+}
+
+async fn attachment(pool: &PgPool, provider_id: i32, uploaded_by: i32, post_id: Option<i32>) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO attachments (file_name, file_path, file_type, target_type, target_id, uploaded_by, post_id)
+         VALUES ('photo.png', '/uploads/never-written.png', 'image', 'provider', $1, $2, $3) RETURNING id",
+    )
+    .bind(provider_id)
+    .bind(uploaded_by)
+    .bind(post_id)
+    .fetch_one(pool)
+    .await
+    .expect("seed attachment")
+}
+
+async fn count(pool: &PgPool, id: i32) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM attachments WHERE id = $1")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .expect("count attachments")
+}
+
+#[tokio::test]
+async fn the_uploader_or_target_owner_can_delete_an_attachment() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let uploader_id = seed::client(&app.pool).await;
+    let post_id: i32 = sqlx::query_scalar("INSERT INTO posts (provider_id, title, content) VALUES ($1, 'Work', 'Done') RETURNING id")
+        .bind(provider_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("seed post");
+
+    let uploaded = attachment(&app.pool, provider_id, uploader_id, None).await;
+    let on_post = attachment(&app.pool, provider_id, owner_id, Some(post_id)).await;
+
+    let stranger = mint_jwt(seed::client(&app.pool).await);
+    for id in [uploaded, on_post] {
+        let res = app.post_json(&format!("/attachments/{id}/delete"), Some(&stranger), json!({})).await;
+        assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+        assert_eq!(count(&app.pool, id).await, 1);
+    }
+
+    // The file was never written; the row goes anyway
+    let res = app.post_json(&format!("/attachments/{uploaded}/delete"), Some(&mint_jwt(uploader_id)), json!({})).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(count(&app.pool, uploaded).await, 0);
+
+    let res = app.post_json(&format!("/attachments/{on_post}/delete"), Some(&mint_jwt(owner_id)), json!({})).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get(&format!("/posts/getPost/{post_id}"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body.to_string().matches("never-written").count(), 0, "{}", res.body);
+
+    let res = app.post_json(&format!("/attachments/{on_post}/delete"), Some(&mint_jwt(owner_id)), json!({})).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+}
+
+#[tokio::test]
+async fn only_super_admins_see_admin_only_attachments() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    sqlx::query(
+        "INSERT INTO attachments (file_name, file_path, file_type, target_type, target_id, uploaded_by, visibility)
+         VALUES ('open.png', '/uploads/open.png', 'image', 'provider', $1, $2, 'public'),
+                ('kyc.png', 'private/kyc.png', 'image', 'provider', $1, $2, 'admin')",
+    )
+    .bind(provider_id)
+    .bind(owner_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed attachments");
+    // A row in admins without the super-admin bit doesn't pass require_admin
+    let demoted = seed::client(&app.pool).await;
+    sqlx::query("INSERT INTO admins (user_id, is_super_admin) VALUES ($1, FALSE)")
+        .bind(demoted)
+        .execute(&app.pool)
+        .await
+        .expect("seed demoted admin");
+    let admin = seed::admin(&app.pool).await;
+
+    let uri = format!("/attachments/getAttachments?target_type=provider&target_id={provider_id}");
+    for (viewer, expected) in [(None, 1), (Some(demoted), 1), (Some(admin), 2), (Some(owner_id), 2)] {
+        let token = viewer.map(mint_jwt);
+        let res = app.get(&uri, token.as_deref()).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        assert_eq!(res.body["attachments"].as_array().expect("attachments").len(), expected, "{viewer:?}");
+    }
+}
+
+#[tokio::test]
+async fn an_accepted_upload_is_stored_byte_for_byte() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    // Small enough to pass even if another test has lowered the image cap
+    let mut png = Cursor::new(Vec::new());
+    image::RgbImage::from_pixel(8, 8, image::Rgb([20, 120, 200]))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .expect("encode png");
+    let png = png.into_inner();
+    assert!(png.len() < 1024);
+
+    let res = upload(&app, &mint_jwt(owner_id), provider_id, "swatch.png", &png).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let url = res.body["attachments"][0]["file_path"].as_str().expect("file_path");
+    let key = url.strip_prefix("/uploads/").expect("local upload url");
+    let stored = std::fs::read(std::path::Path::new(&test_config().upload_dir).join(key)).expect("read stored file");
+    assert_eq!(stored, png);
+}
+
+#[tokio::test]
+async fn the_client_file_name_is_kept_for_display_and_the_stored_name_is_a_uuid() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let mut png = Cursor::new(Vec::new());
+    image::RgbImage::from_pixel(8, 8, image::Rgb([200, 60, 20]))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .expect("encode png");
+
+    let res = upload(&app, &mint_jwt(owner_id), provider_id, "my photo (1).jpg", png.get_ref()).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let url = res.body["attachments"][0]["file_path"].as_str().expect("file_path");
+
+    // Named by the detected type, not the claimed one, and free of the
+    // client's spaces and brackets
+    let stored = url.strip_prefix("/uploads/attachments/").expect("attachment key");
+    let stem = stored.strip_suffix(".png").expect("detected extension");
+    assert!(uuid::Uuid::parse_str(stem).is_ok(), "{url}");
+    assert!(std::path::Path::new(&test_config().upload_dir).join("attachments").join(stored).is_file());
+
+    let file_name: String = sqlx::query_scalar("SELECT file_name FROM attachments WHERE file_path = $1")
+        .bind(url)
+        .fetch_one(&app.pool)
+        .await
+        .expect("attachment row");
+    assert_eq!(file_name, "my photo (1).jpg");
+}
+
+#[tokio::test]
+async fn a_renamed_executable_is_refused_by_its_content() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let mut exe = b"MZ\x90\x00\x03\x00\x00\x00\x04\x00\x00\x00\xff\xff".to_vec();
+    exe.resize(2048, 0);
+
+    let res = upload(&app, &mint_jwt(owner_id), provider_id, "holiday.jpg", &exe).await;
+    assert_eq!(res.status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{}", res.body);
+    assert_eq!(res.body["code"], "unsupported_media_type");
+    assert_eq!(attachment_count(&app.pool, provider_id).await, 0);
+}
+
+#[tokio::test]
+async fn an_image_over_the_configured_cap_is_a_413() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let res = app.post_json("/admin/settings", Some(&admin), json!({ "uploads.max_image_bytes": 1024 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    // A JPEG header followed by padding, four times the cap
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0];
+    jpeg.resize(4096, 0);
+
+    // Settings are cached per process; another test here may briefly load
+    // its own database's defaults, so allow a few attempts
+    let mut res = upload(&app, &mint_jwt(owner_id), provider_id, "big.jpg", &jpeg).await;
+    for _ in 0..20 {
+        if res.status == StatusCode::PAYLOAD_TOO_LARGE {
+            break;
+        }
+        sqlx::query("DELETE FROM attachments").execute(&app.pool).await.expect("clear attachments");
+        mtaalink::utils::settings::invalidate();
+        res = upload(&app, &mint_jwt(owner_id), provider_id, "big.jpg", &jpeg).await;
+    }
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE, "{}", res.body);
+    assert!(res.body["message"].as_str().unwrap().contains("1024 byte limit"), "{}", res.body);
+    assert_eq!(attachment_count(&app.pool, provider_id).await, 0);
+}
@@ -0,0 +1,442 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use common::{TestApp, mint_jwt, seed, test_config};
+use mtaalink::utils::account_deletion::retention_days;
+use mtaalink::utils::cleanup::run_cleanup;
+use mtaalink::utils::storage;
+use serde_json::json;
+
+#[tokio::test]
+async fn register_returns_a_working_token() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let user = app.register_user("client").await;
+    let me = app.get("/auth/me", Some(&user.token)).await;
+
+    assert_eq!(me.status, StatusCode::OK, "{}", me.body);
+    assert_eq!(me.body["id"], user.id);
+    assert_eq!(me.body["username"], user.username);
+    assert_eq!(me.body["role"], "client");
+    assert_eq!(me.body["email_verified"], false);
+}
+
+#[tokio::test]
+async fn register_rejects_a_duplicate_email() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user = app.register_user("client").await;
+
+    let res = app
+        .post_json(
+            "/auth/register",
+            None,
+            json!({
+                "username": "someone_else",
+                "email": user.email,
+                "password": "another-password",
+                "confirm_password": "another-password",
+                "role": "client",
+            }),
+        )
+        .await;
+
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["code"], "conflict");
+}
+
+#[tokio::test]
+async fn register_requires_matching_passwords() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let res = app
+        .post_json(
+            "/auth/register",
+            None,
+            json!({
+                "username": "mismatch",
+                "email": "mismatch@example.com",
+                "password": "first-password",
+                "confirm_password": "second-password",
+                "role": "client",
+            }),
+        )
+        .await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn provider_registration_starts_onboarding() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let user = app.register_user("provider").await;
+    let me = app.get("/auth/me", Some(&user.token)).await;
+
+    assert_eq!(me.body["role"], "provider");
+    assert_eq!(me.body["onboarding_completed"], false);
+}
+
+#[tokio::test]
+async fn login_with_the_registered_password() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user = app.register_user("client").await;
+
+    let res = app
+        .post_json("/auth/login", None, json!({ "email": user.email, "password": user.password }))
+        .await;
+
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["user_id"], user.id);
+    let token = res.body["token"].as_str().expect("token");
+    let me = app.get("/auth/me", Some(token)).await;
+    assert_eq!(me.status, StatusCode::OK);
+    assert_eq!(me.body["email"], user.email);
+}
+
+#[tokio::test]
+async fn login_with_a_wrong_password_is_unauthorized() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user = app.register_user("client").await;
+
+    let res = app
+        .post_json("/auth/login", None, json!({ "email": user.email, "password": "not-it" }))
+        .await;
+
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(res.body["code"], "unauthorized");
+}
+
+#[tokio::test]
+async fn me_requires_a_token() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let missing = app.get("/auth/me", None).await;
+    let garbage = app.get("/auth/me", Some("not-a-jwt")).await;
+
+    assert_eq!(missing.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(garbage.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn me_accepts_a_minted_token_for_a_seeded_user() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (user_id, _) = seed::provider(&app.pool).await;
+
+    let me = app.get("/auth/me", Some(&mint_jwt(user_id))).await;
+
+    assert_eq!(me.status, StatusCode::OK, "{}", me.body);
+    assert_eq!(me.body["role"], "provider");
+    assert_eq!(me.body["onboarding_completed"], true);
+}
+
+#[tokio::test]
+async fn a_signed_token_with_a_non_numeric_subject_is_a_clean_401() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let token = mtaalink::utils::jwt::create_jwt("abc", None).expect("mint jwt");
+
+    for path in ["/auth/me", "/bookings/getBookings/me", "/notifications/unread-count", "/dashboard"] {
+        let res = app.get(path, Some(&token)).await;
+
+        assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{path}: {}", res.body);
+        assert_eq!(res.body["code"], "unauthorized", "{path}");
+        assert_eq!(res.body["message"], "Invalid token subject", "{path}");
+    }
+}
+
+#[tokio::test]
+async fn the_bearer_scheme_is_case_insensitive_and_tolerates_extra_spaces() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let token = mint_jwt(seed::client(&app.pool).await);
+
+    let me = |value: String| {
+        Request::get("/auth/me")
+            .header(header::AUTHORIZATION, value)
+            .body(Body::empty())
+            .expect("build request")
+    };
+
+    for value in [format!("bearer {token}"), format!("Bearer  {token}"), format!("BEARER {token} ")] {
+        let res = app.send(me(value.clone())).await;
+        assert_eq!(res.status, StatusCode::OK, "{value:?}: {}", res.body);
+    }
+
+    for value in [token.clone(), format!("Token {token}"), "Bearer ".to_string(), format!("Bearer{token}")] {
+        let res = app.send(me(value.clone())).await;
+        assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{value:?}");
+        assert_eq!(res.body["code"], "unauthorized");
+        assert_eq!(res.body["message"], "Invalid Authorization header format", "{value:?}");
+    }
+
+    let res = app.send(Request::get("/auth/me").body(Body::empty()).expect("build request")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(res.body["message"], "Missing Authorization header");
+}
+
+#[tokio::test]
+async fn provider_endpoints_reject_other_roles() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (provider_user, provider_id) = seed::provider(&app.pool).await;
+    let provider = mint_jwt(provider_user);
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    let week = json!({ "availability": [
+        { "day": "monday", "start_time": "08:00", "end_time": "17:00", "is_available": true }
+    ] });
+    let res = app.post_json("/service_providers/updateBulkAvailability", Some(&client), week.clone()).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(res.body["message"], "You are not a provider");
+    let res = app.get("/service_providers/getAvailability", None).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = app.post_json("/service_providers/updateBulkAvailability", Some(&provider), week).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/service_providers/getAvailability", Some(&provider)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["availability"][0]["day"], "monday");
+
+    // The single-day form only takes the caller's own provider id
+    let day = |provider_id: i32| json!({
+        "provider_id": provider_id, "day": "tuesday",
+        "start_time": "09:00", "end_time": "12:00", "is_available": true
+    });
+    let res = app.post_json("/service_providers/updateAvailability", Some(&provider), day(provider_id)).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let res = app.post_json("/service_providers/updateAvailability", Some(&provider), day(provider_id + 1)).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn a_token_for_another_environment_gets_its_own_error_code() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+
+    // Same signing key as this environment, but minted for staging
+    let staging = mtaalink::utils::jwt::JwtKeys::new(&mtaalink::config::get().jwt_secrets).with_scope(
+        mtaalink::utils::jwt::JwtScope {
+            issuer: "mtaalink-api".to_string(),
+            audience: "mtaalink-staging".to_string(),
+            accept_unscoped: true,
+        },
+    );
+    let exp = (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize;
+    let token = staging
+        .sign(&mtaalink::utils::jwt::Claims { sub: user_id.to_string(), exp, iss: None, aud: None, impersonator: None, role: None })
+        .expect("sign");
+
+    let res = app.get("/auth/me", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(res.body["code"], "token_wrong_environment");
+
+    let res = app.get("/auth/me", Some(&mint_jwt(user_id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn adminship_follows_admins_user_id_not_the_row_id() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin_user = seed::user(&app.pool, "client").await;
+    let bystander = seed::client(&app.pool).await;
+
+    // The admin row's own id equals the bystander's user id
+    sqlx::query("INSERT INTO admins (id, user_id, is_super_admin) VALUES ($1, $2, TRUE)")
+        .bind(bystander)
+        .bind(admin_user)
+        .execute(&app.pool)
+        .await
+        .expect("seed admin");
+
+    let res = app.get("/admin/overview", Some(&mint_jwt(bystander))).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    let res = app.get("/auth/me", Some(&mint_jwt(bystander))).await;
+    assert_eq!(res.body["is_admin"], false);
+
+    let res = app.get("/admin/overview", Some(&mint_jwt(admin_user))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.get("/auth/me", Some(&mint_jwt(admin_user))).await;
+    assert_eq!(res.body["is_admin"], true);
+}
+
+async fn login(app: &TestApp, user: &common::TestUser) -> serde_json::Value {
+    let res = app
+        .post_json("/auth/login", None, json!({ "email": user.email, "password": user.password }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    res.body
+}
+
+#[tokio::test]
+async fn a_refresh_token_buys_a_new_access_token_once() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user = app.register_user("client").await;
+    let session = login(&app, &user).await;
+    assert_eq!(session["expires_in"], 15 * 60);
+    let claims = mtaalink::utils::jwt::decode_jwt(session["token"].as_str().unwrap()).expect("decode access token");
+    let lifetime = claims.exp as i64 - chrono::Utc::now().timestamp();
+    assert!((14 * 60..=15 * 60).contains(&lifetime), "{lifetime}");
+    let first = session["refresh_token"].as_str().expect("refresh token").to_string();
+
+    let res = app.post_json("/auth/refresh", None, json!({ "refresh_token": first })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let second = res.body["refresh_token"].as_str().expect("rotated token").to_string();
+    assert_ne!(first, second);
+    let me = app.get("/auth/me", Some(res.body["token"].as_str().unwrap())).await;
+    assert_eq!(me.status, StatusCode::OK);
+    assert_eq!(me.body["id"], user.id);
+
+    // Only hashes are stored
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM refresh_tokens WHERE token_hash IN ($1, $2)")
+        .bind(&first)
+        .bind(&second)
+        .fetch_one(&app.pool)
+        .await
+        .expect("count tokens");
+    assert_eq!(stored, 0);
+
+    // Replaying the old token ends the whole session family
+    let res = app.post_json("/auth/refresh", None, json!({ "refresh_token": first })).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+    let res = app.post_json("/auth/refresh", None, json!({ "refresh_token": second })).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+
+    let res = app.post_json("/auth/refresh", None, json!({ "refresh_token": "made-up" })).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+}
+
+#[tokio::test]
+async fn logout_and_expiry_end_a_session() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user = app.register_user("client").await;
+    let session = login(&app, &user).await;
+    let token = session["refresh_token"].as_str().unwrap();
+
+    let res = app.post_json("/auth/logout", None, json!({ "refresh_token": token })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.post_json("/auth/refresh", None, json!({ "refresh_token": token })).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+
+    let session = login(&app, &user).await;
+    sqlx::query("UPDATE refresh_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE revoked_at IS NULL")
+        .execute(&app.pool)
+        .await
+        .expect("expire tokens");
+    let res = app
+        .post_json("/auth/refresh", None, json!({ "refresh_token": session["refresh_token"] }))
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+    assert_eq!(res.body["message"], "Your session has expired. Please log in again.");
+}
+
+#[tokio::test]
+async fn tokens_carry_the_role_but_access_follows_the_account() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user = app.register_user("business").await;
+    let session = login(&app, &user).await;
+    let claims = mtaalink::utils::jwt::decode_jwt(session["token"].as_str().unwrap()).expect("decode access token");
+    assert_eq!(claims.role.as_deref(), Some("business"));
+
+    // mint_jwt tokens have no role claim, like those issued before it existed
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let post = json!({ "title": "Open on Saturday", "content": "Book a slot", "provider_id": provider_id });
+    let res = app.post_json("/posts/createPosts", Some(&mint_jwt(owner_id)), post.clone()).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let client_id = seed::client(&app.pool).await;
+    let client = mint_jwt(client_id);
+    let res = app.post_json("/posts/createPosts", Some(&client), post.clone()).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    assert_eq!(res.body["message"], "Only provider or business accounts can do this");
+    let res = app.post_json("/businesses/updateProfile", Some(&client), json!({ "location": "Thika" })).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+
+    sqlx::query("UPDATE users SET role = 'provider' WHERE id = $1")
+        .bind(client_id)
+        .execute(&app.pool)
+        .await
+        .expect("change role");
+    let res = app.post_json("/posts/createPosts", Some(&client), post).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+}
+
+#[tokio::test]
+async fn deleting_an_account_drops_its_sessions_and_reset_links() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let user = app.register_user("client").await;
+    let session = login(&app, &user).await;
+    sqlx::query("INSERT INTO password_resets (user_id, token, expires_at) VALUES ($1, 'pending-reset', NOW() + INTERVAL '1 hour')")
+        .bind(user.id)
+        .execute(&app.pool)
+        .await
+        .expect("seed reset");
+
+    let res = app.post_json("/admin/delete_user", Some(&admin), json!({ "user_id": user.id })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.post_json("/admin/restore_user", Some(&admin), json!({ "user_id": user.id })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    // Restoring brings the account back, not what was issued before the delete
+    let res = app.post_json("/auth/refresh", None, json!({ "refresh_token": session["refresh_token"] })).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+    let res = app
+        .post_json(
+            "/auth/reset-password",
+            None,
+            json!({ "token": "pending-reset", "password": "An0ther-Passw0rd!", "confirm_password": "An0ther-Passw0rd!" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+    login(&app, &user).await;
+}
+
+#[tokio::test]
+async fn deleted_accounts_restore_inside_the_retention_window_and_are_purged_after_it() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let recent = seed::client(&app.pool).await;
+    let pending = seed::client(&app.pool).await;
+    let (expired, expired_provider) = seed::provider(&app.pool).await;
+    let retention = retention_days(&app.pool).await;
+
+    for user_id in [recent, pending, expired] {
+        let res = app.post_json("/admin/delete_user", Some(&admin), json!({ "user_id": user_id })).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    }
+    for (user_id, days) in [(recent, retention - 1), (pending, retention - 1), (expired, retention + 1)] {
+        sqlx::query("UPDATE users SET deleted_at = NOW() - make_interval(days => $2) WHERE id = $1")
+            .bind(user_id)
+            .bind(days)
+            .execute(&app.pool)
+            .await
+            .expect("backdate deletion");
+    }
+
+    let res = app.post_json("/admin/restore_user", Some(&admin), json!({ "user_id": expired })).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+    let res = app.post_json("/admin/restore_user", Some(&admin), json!({ "user_id": recent })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let storage = storage::init(test_config());
+    let report = run_cleanup(&app.pool, &storage).await;
+    assert!(report.items_processed >= 1);
+
+    let remaining: Vec<(i32, bool)> =
+        sqlx::query_as("SELECT id, deleted_at IS NOT NULL FROM users WHERE id = ANY($1) ORDER BY id")
+            .bind(vec![recent, pending, expired])
+            .fetch_all(&app.pool)
+            .await
+            .expect("remaining users");
+    assert_eq!(remaining, vec![(recent, false), (pending, true)]);
+
+    let provider_left: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM providers WHERE id = $1)")
+        .bind(expired_provider)
+        .fetch_one(&app.pool)
+        .await
+        .expect("provider lookup");
+    assert!(!provider_left);
+    let purged: Vec<Option<i32>> = sqlx::query_scalar("SELECT target_id FROM audit_log WHERE action = 'user_purged'")
+        .fetch_all(&app.pool)
+        .await
+        .expect("purge audit");
+    assert_eq!(purged, vec![Some(expired)]);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use common::{TestApp, mint_jwt, seed};
+use futures_util::stream;
+use serde_json::json;
+use std::io::Cursor;
+
+const BOUNDARY: &str = "mtaalink-test-boundary";
+
+/// A PNG of random pixels, which doesn't compress, so it is about
+/// `side * side * 3` bytes.
+fn noisy_png(side: u32) -> Vec<u8> {
+    let mut seed: u32 = 0x9e37_79b9;
+    let image = image::RgbImage::from_fn(side, side, |_, _| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let [r, g, b, _] = seed.to_le_bytes();
+        image::Rgb([r, g, b])
+    });
+    let mut out = Cursor::new(Vec::new());
+    image.write_to(&mut out, image::ImageFormat::Png).expect("encode png");
+    out.into_inner()
+}
+
+fn multipart_file(file: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"photo.png\"\r\n\
+         Content-Type: image/png\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(file);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    body
+}
+
+fn multipart_request(uri: &str, token: &str, body: Body, content_length: usize) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={BOUNDARY}"))
+        .header(header::CONTENT_LENGTH, content_length)
+        .body(body)
+        .expect("build request")
+}
+
+#[tokio::test]
+async fn an_oversized_json_body_is_a_json_413() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    // The test config allows 64 KiB outside upload routes
+    let res = app
+        .post_json(
+            "/auth/login",
+            None,
+            json!({ "email": "someone@example.com", "password": "x".repeat(100 * 1024) }),
+        )
+        .await;
+
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(res.body["code"], "payload_too_large");
+}
+
+#[tokio::test]
+async fn upload_routes_accept_bodies_over_the_json_limit() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    let body = multipart_file(&noisy_png(200));
+    assert!(body.len() > 64 * 1024);
+
+    let len = body.len();
+    let res = app
+        .send(multipart_request("/clients/uploadProfilePicture", &mint_jwt(user_id), Body::from(body), len))
+        .await;
+
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(res.body["url"].is_string());
+}
+
+#[tokio::test]
+async fn an_upload_over_the_request_cap_is_refused_unread() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+
+    // Default uploads.max_request_bytes is 100 MiB; 1 MiB more is allowed
+    // for multipart framing. The body is never read, so it is generated lazily.
+    const CHUNK: usize = 1024 * 1024;
+    let chunks = 102;
+    let body = Body::from_stream(stream::iter(
+        (0..chunks).map(|_| Ok::<_, std::io::Error>(vec![0u8; CHUNK])),
+    ));
+
+    let res = app
+        .send(multipart_request("/clients/uploadProfilePicture", &mint_jwt(user_id), body, chunks * CHUNK))
+        .await;
+
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(res.body["code"], "payload_too_large");
+}
+
+#[tokio::test]
+async fn a_file_that_is_not_an_image_is_refused_whatever_its_name() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    // A Windows executable header, uploaded as photo.png with image/png
+    let mut exe = b"MZ\x90\x00\x03\x00\x00\x00\x04\x00\x00\x00\xff\xff".to_vec();
+    exe.resize(4096, 0);
+    let body = multipart_file(&exe);
+
+    let len = body.len();
+    let res = app
+        .send(multipart_request("/clients/uploadProfilePicture", &mint_jwt(user_id), Body::from(body), len))
+        .await;
+
+    assert_eq!(res.status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{}", res.body);
+    assert_eq!(res.body["code"], "unsupported_media_type");
+}
+
+#[tokio::test]
+async fn an_image_over_the_image_cap_is_a_413() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    // About 7 MiB, over the 5 MiB default
+    let body = multipart_file(&noisy_png(1550));
+    assert!(body.len() > 5 * 1024 * 1024);
+
+    let len = body.len();
+    let res = app
+        .send(multipart_request("/clients/uploadProfilePicture", &mint_jwt(user_id), Body::from(body), len))
+        .await;
+
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE, "{}", res.body);
+    assert!(res.body["message"].as_str().unwrap().contains("5242880 byte limit"), "{}", res.body);
+}
@@ -0,0 +1,304 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{Datelike, Duration, NaiveDate};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+
+fn ids(body: &serde_json::Value) -> Vec<i64> {
+    let mut ids: Vec<i64> = body["bookings"]
+        .as_array()
+        .expect("bookings")
+        .iter()
+        .map(|b| b["id"].as_i64().expect("booking id"))
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn client_bookings_filter_by_status_and_target_type() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let (_, business_id) = seed::business(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let client = mint_jwt(client_id);
+
+    let mut by_status = Vec::new();
+    for status in ["pending", "confirmed", "cancelled", "completed"] {
+        let id = seed::booking(&app.pool, client_id, "provider", provider_id, None, status).await;
+        by_status.push((status, i64::from(id)));
+    }
+    let at_business = i64::from(seed::booking(&app.pool, client_id, "business", business_id, None, "pending").await);
+    // Someone else's booking never shows up
+    let other = seed::client(&app.pool).await;
+    seed::booking(&app.pool, other, "provider", provider_id, None, "pending").await;
+
+    let res = app.get("/bookings/getBookings/me", Some(&client)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(ids(&res.body).len(), 5);
+
+    for (status, id) in &by_status {
+        let res = app.get(&format!("/bookings/getBookings/me?status={status}&target_type=provider"), Some(&client)).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        assert_eq!(ids(&res.body), [*id], "{status}");
+    }
+    let res = app.get("/bookings/getBookings/me?status=pending", Some(&client)).await;
+    assert_eq!(ids(&res.body), [by_status[0].1, at_business]);
+
+    let res = app.get("/bookings/getBookings/me?target_type=business", Some(&client)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(ids(&res.body), [at_business]);
+    let res = app.get("/bookings/getBookings/me?target_type=provider", Some(&client)).await;
+    assert_eq!(ids(&res.body).len(), 4);
+
+    for query in ["status=archived", "status=pending'%20OR%20'1'%3D'1", "target_type=clinic"] {
+        let res = app.get(&format!("/bookings/getBookings/me?{query}"), Some(&client)).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{query}: {}", res.body);
+    }
+}
+
+#[tokio::test]
+async fn provider_bookings_respect_working_hours_and_existing_appointments() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    sqlx::query("UPDATE providers SET approved = TRUE WHERE id = $1")
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("approve provider");
+    sqlx::query(
+        "INSERT INTO provider_availability (provider_id, day, start_time, end_time, is_available)
+         VALUES ($1, 'Monday', '09:00', '17:00', TRUE), ($1, 'Tuesday', '00:00', '00:00', FALSE)",
+    )
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed availability");
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    let today = chrono::Local::now().date_naive();
+    let monday = today + Duration::days(7 + (7 - i64::from(today.weekday().num_days_from_monday())) % 7);
+    let book = |day: NaiveDate, time: &str| {
+        json!({
+            "target_type": "provider",
+            "target_id": provider_id,
+            "service_description": "Fix the kitchen sink",
+            "scheduled_time": format!("{day}T{time}:00"),
+        })
+    };
+
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(monday, "10:00")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let first = res.body["booking_id"].as_i64().expect("booking id") as i32;
+
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(monday, "10:15")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT, "{}", res.body);
+    assert!(res.body["message"].as_str().unwrap().contains("from 10:00 to 11:00"), "{}", res.body);
+    // Back to back is fine
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(monday, "11:00")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    for (day, time) in [(monday, "08:30"), (monday, "16:30"), (monday + Duration::days(1), "10:00"), (monday + Duration::days(2), "10:00")] {
+        let res = app.post_json("/bookings/createBooking", Some(&client), book(day, time)).await;
+        assert_eq!(res.status, StatusCode::CONFLICT, "{day} {time}: {}", res.body);
+    }
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(monday, "08:30")).await;
+    assert_eq!(res.body["message"], "This provider takes bookings between 09:00 and 17:00 on Mondays");
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(monday + Duration::days(1), "10:00")).await;
+    assert_eq!(res.body["message"], "This provider does not take bookings on Tuesdays");
+
+    // A cancelled booking frees its window
+    sqlx::query("UPDATE bookings SET status = 'cancelled' WHERE id = $1")
+        .bind(first)
+        .execute(&app.pool)
+        .await
+        .expect("cancel booking");
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(monday, "09:45")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+}
+
+#[tokio::test]
+async fn status_changes_follow_the_transition_table_and_are_kept_as_history() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let (owner, client) = (mint_jwt(owner_id), mint_jwt(client_id));
+    let booking_id = seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    let set = |token: &str, status: &str| {
+        let (token, status) = (token.to_string(), status.to_string());
+        let app = &app;
+        async move {
+            app.post_json(&format!("/bookings/{booking_id}/status"), Some(&token), json!({ "status": status }))
+                .await
+        }
+    };
+
+    let res = set(&owner, "confirmd").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    assert!(res.body["message"].as_str().unwrap().contains("no_show"), "{}", res.body);
+
+    // Skipping ahead, or the client acting before the job is done, is refused
+    for (token, status) in [(&owner, "pending_confirmation"), (&owner, "no_show"), (&client, "completed")] {
+        let res = set(token, status).await;
+        assert_eq!(res.status, StatusCode::CONFLICT, "{status}: {}", res.body);
+    }
+    let res = set(&owner, "completed").await;
+    assert_eq!(res.body["message"], "A pending booking can only be moved to: confirmed, rejected, cancelled");
+
+    for (token, status) in [(&owner, "confirmed"), (&owner, "pending_confirmation"), (&client, "completed")] {
+        let res = set(token, status).await;
+        assert_eq!(res.status, StatusCode::OK, "{status}: {}", res.body);
+    }
+    // Completed is final
+    let res = set(&owner, "pending").await;
+    assert_eq!(res.status, StatusCode::CONFLICT, "{}", res.body);
+    assert_eq!(res.body["message"], "You can't change a completed booking");
+
+    let res = app.get(&format!("/bookings/{booking_id}"), Some(&client)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let history: Vec<(String, String, i64)> = res.body["history"]
+        .as_array()
+        .expect("history")
+        .iter()
+        .map(|h| {
+            (
+                h["from_status"].as_str().unwrap().to_string(),
+                h["to_status"].as_str().unwrap().to_string(),
+                h["changed_by"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    let (owner_id, client_id) = (i64::from(owner_id), i64::from(client_id));
+    assert_eq!(
+        history,
+        [
+            ("pending".into(), "confirmed".into(), owner_id),
+            ("confirmed".into(), "pending_confirmation".into(), owner_id),
+            ("pending_confirmation".into(), "completed".into(), client_id),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn a_no_show_can_only_be_marked_after_the_scheduled_time() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let owner = mint_jwt(owner_id);
+    let booking_id = seed::booking(&app.pool, client_id, "provider", provider_id, None, "confirmed").await;
+    let uri = format!("/bookings/{booking_id}/status");
+    let mark = || app.post_json(&uri, Some(&owner), json!({ "status": "no_show" }));
+
+    // Seeded bookings are a day ahead
+    let res = mark().await;
+    assert_eq!(res.status, StatusCode::CONFLICT, "{}", res.body);
+
+    sqlx::query("UPDATE bookings SET scheduled_time = $2 WHERE id = $1")
+        .bind(booking_id)
+        .bind((chrono::Utc::now() - Duration::hours(2)).naive_utc())
+        .execute(&app.pool)
+        .await
+        .expect("move booking back");
+    let res = mark().await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let status: String = sqlx::query_scalar("SELECT status FROM bookings WHERE id = $1")
+        .bind(booking_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("read status");
+    assert_eq!(status, "no_show");
+}
+
+#[tokio::test]
+async fn available_slots_leave_out_booked_windows_and_closed_days() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    sqlx::query("UPDATE providers SET approved = TRUE WHERE id = $1")
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("approve provider");
+    sqlx::query(
+        "INSERT INTO provider_availability (provider_id, day, start_time, end_time, is_available)
+         VALUES ($1, 'Monday', '09:00', '12:00', TRUE)",
+    )
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed availability");
+    let service_id = seed::service(&app.pool, "provider", provider_id).await;
+    sqlx::query("UPDATE services SET duration = 45 WHERE id = $1")
+        .bind(service_id)
+        .execute(&app.pool)
+        .await
+        .expect("shorten service");
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    let today = chrono::Local::now().date_naive();
+    let monday = today + Duration::days(7 + (7 - i64::from(today.weekday().num_days_from_monday())) % 7);
+    let res = app
+        .post_json(
+            "/bookings/createBooking",
+            Some(&client),
+            json!({
+                "target_type": "provider",
+                "target_id": provider_id,
+                "service_description": "Fix the kitchen sink",
+                "scheduled_time": format!("{monday}T10:00:00"),
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let slots = |query: String| {
+        let app = &app;
+        async move { app.get(&format!("/bookings/availableSlots?target_type=provider&target_id={provider_id}&{query}"), None).await }
+    };
+
+    // Half-hour starts for an hour-long booking, clear of 10:00-11:00
+    let res = slots(format!("date={monday}")).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["available_slots"], json!(["09:00", "11:00"]));
+    assert_eq!(res.body["hours"], json!({ "start": "09:00", "end": "12:00" }));
+
+    let res = slots(format!("date={monday}&service_id={service_id}")).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slot_minutes"], 45);
+    assert_eq!(res.body["available_slots"], json!(["09:00", "11:15"]));
+
+    // What the endpoint offers, create_booking accepts
+    let res = app
+        .post_json(
+            "/bookings/createBooking",
+            Some(&client),
+            json!({
+                "target_type": "provider",
+                "target_id": provider_id,
+                "service_id": service_id,
+                "service_description": "Replace a tap",
+                "scheduled_time": format!("{monday}T11:15:00"),
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let res = slots(format!("date={}", monday + Duration::days(1))).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["available_slots"], json!([]));
+    assert_eq!(res.body["message"], "This provider does not take bookings on Tuesdays");
+
+    let res = slots(format!("date={monday}&service_id=987654")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    let res = app.get(&format!("/bookings/availableSlots?target_type=business&target_id=1&date={monday}"), None).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+
+    // Without a schedule the whole day is open, but not the part already gone
+    let (_, anytime) = seed::provider(&app.pool).await;
+    let res = app.get(&format!("/bookings/availableSlots?target_type=provider&target_id={anytime}&date={today}"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let now = chrono::Local::now().format("%H:%M").to_string();
+    for slot in res.body["available_slots"].as_array().unwrap() {
+        assert!(slot.as_str().unwrap() >= now.as_str(), "{slot} is in the past");
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::{Value, json};
+
+fn branch(ward_id: i32) -> Value {
+    json!({
+        "name": "Westlands",
+        "latitude": -1.2676,
+        "longitude": 36.8108,
+        "ward_id": ward_id,
+        "phone": "0712345678",
+        "address": "Sarit Centre, 2nd floor",
+    })
+}
+
+#[tokio::test]
+async fn owners_manage_branches_and_bookings_only_take_their_own_active_ones() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, business_id) = seed::business(&app.pool).await;
+    sqlx::query("UPDATE businesses SET verified = TRUE WHERE id = $1")
+        .bind(business_id)
+        .execute(&app.pool)
+        .await
+        .expect("verify business");
+    let ward_id: i32 = sqlx::query_scalar(
+        "WITH county AS (INSERT INTO counties (name) VALUES ('Nairobi') RETURNING id),
+              constituency AS (INSERT INTO constituencies (name, county_id) SELECT 'Westlands', id FROM county RETURNING id)
+         INSERT INTO wards (name, constituency_id) SELECT 'Parklands', id FROM constituency RETURNING id",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed ward");
+    let owner = mint_jwt(owner_id);
+    let (other_owner, other_business) = seed::business(&app.pool).await;
+    let uri = format!("/businesses/{business_id}/branches");
+
+    let res = app.post_json(&uri, Some(&mint_jwt(other_owner)), branch(ward_id)).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.post_json(&uri, Some(&owner), branch(987_654)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+
+    let res = app.post_json(&uri, Some(&owner), branch(ward_id)).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    assert_eq!(res.body["data"]["ward_name"], "Parklands");
+    assert_eq!(res.body["data"]["is_active"], true);
+    let branch_id = res.body["data"]["id"].as_i64().expect("branch id");
+
+    let res = app
+        .request(Method::PUT, &format!("/branches/{branch_id}"), Some(&owner), Some(json!({ "name": "Westlands Mall" })))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["data"]["name"], "Westlands Mall");
+    assert_eq!(res.body["data"]["address"], "Sarit Centre, 2nd floor");
+
+    let client = mint_jwt(seed::client(&app.pool).await);
+    let book = |target_id: i32| {
+        json!({
+            "target_type": "business",
+            "target_id": target_id,
+            "branch_id": branch_id,
+            "service_description": "Braids for a wedding",
+            "scheduled_time": (chrono::Local::now() + chrono::Duration::days(2)).naive_local(),
+        })
+    };
+    // Another business's branch can't be booked
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(other_business)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(business_id)).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let res = app.post_json(&format!("/branches/{branch_id}/deactivate"), Some(&client), json!({})).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.post_json(&format!("/branches/{branch_id}/deactivate"), Some(&owner), json!({})).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["data"]["is_active"], false);
+
+    // Gone for the public and for new bookings, still there for the owner
+    let res = app.get(&uri, None).await;
+    assert_eq!(res.body["data"], json!([]));
+    assert_eq!(app.get(&format!("/branches/{branch_id}"), None).await.status, StatusCode::NOT_FOUND);
+    let res = app.get(&uri, Some(&owner)).await;
+    assert_eq!(res.body["data"].as_array().unwrap().len(), 1);
+    let res = app.post_json("/bookings/createBooking", Some(&client), book(business_id)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+
+    let res = app
+        .request(Method::PUT, &format!("/branches/{branch_id}"), Some(&owner), Some(json!({ "is_active": true })))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(app.get(&format!("/branches/{branch_id}"), None).await.status, StatusCode::OK);
+}
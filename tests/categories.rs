@@ -0,0 +1,59 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn category(pool: &PgPool, name: &str) -> i32 {
+    sqlx::query_scalar("INSERT INTO categories (name) VALUES ($1) RETURNING id")
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .expect("seed category")
+}
+
+async fn assigned(pool: &PgPool, provider_id: i32) -> Vec<i32> {
+    sqlx::query_scalar("SELECT category_id FROM provider_categories WHERE provider_id = $1 ORDER BY category_id")
+        .bind(provider_id)
+        .fetch_all(pool)
+        .await
+        .expect("read provider categories")
+}
+
+#[tokio::test]
+async fn only_the_owner_assigns_categories_and_a_bad_id_changes_nothing() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let owner = mint_jwt(owner_id);
+    let (electrical, roofing) = (category(&app.pool, "Electrical").await, category(&app.pool, "Roofing").await);
+    let assign = |ids: Vec<i32>| json!({ "target_type": "provider", "target_id": provider_id, "category_ids": ids });
+
+    let res = app.post_json("/categories/assignCategories", None, assign(vec![electrical])).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+    let (stranger, _) = seed::provider(&app.pool).await;
+    let res = app.post_json("/categories/assignCategories", Some(&mint_jwt(stranger)), assign(vec![electrical])).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+
+    let res = app.post_json("/categories/assignCategories", Some(&owner), assign(vec![roofing, electrical, roofing])).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body["categories"],
+        json!([{ "id": roofing, "name": "Roofing" }, { "id": electrical, "name": "Electrical" }])
+    );
+    let mut expected = vec![electrical, roofing];
+    expected.sort();
+    assert_eq!(assigned(&app.pool, provider_id).await, expected);
+    let main: String = sqlx::query_scalar("SELECT category FROM providers WHERE id = $1")
+        .bind(provider_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("read category");
+    assert_eq!(main, "Roofing");
+
+    // An unknown id is refused before anything is replaced
+    let res = app.post_json("/categories/assignCategories", Some(&owner), assign(vec![electrical, 987_654])).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    assert_eq!(res.body["message"], "Unknown category IDs: 987654");
+    assert_eq!(assigned(&app.pool, provider_id).await, expected);
+}
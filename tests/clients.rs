@@ -0,0 +1,448 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+use std::io::Cursor;
+
+/// A multipart request carrying a small PNG as `file`.
+fn picture_upload(token: &str) -> Request<Body> {
+    let mut png = Cursor::new(Vec::new());
+    image::RgbImage::from_pixel(8, 8, image::Rgb([200, 80, 40]))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .expect("encode png");
+    let boundary = "client-picture-boundary";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"me.png\"\r\n\
+         Content-Type: image/png\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(&png.into_inner());
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    Request::builder()
+        .method(Method::POST)
+        .uri("/clients/uploadProfilePicture")
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={boundary}"))
+        .body(Body::from(body))
+        .expect("build request")
+}
+
+#[tokio::test]
+async fn clients_read_and_update_their_profile() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let token = mint_jwt(seed::client(&app.pool).await);
+
+    let res = app.get("/api/v1/clients/me", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(res.body["profile"]["username"].as_str().is_some_and(|u| u.starts_with("seed_")));
+    assert_eq!(res.body["profile"]["display_name"], json!(null));
+    assert_eq!(res.body["profile"]["sms_notifications"], true);
+
+    let res = app
+        .post_json(
+            "/api/v1/clients/updateProfile",
+            Some(&token),
+            json!({
+                "display_name": "  Wanjiku M.  ",
+                "phone": "0712 345 678",
+                "location": "Kilimani",
+                "sms_notifications": false,
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let profile = &res.body["profile"];
+    assert_eq!(profile["display_name"], "Wanjiku M.");
+    assert_eq!(profile["phone"], "254712345678");
+    assert_eq!(profile["location"], "Kilimani");
+    assert_eq!(profile["sms_notifications"], false);
+    assert_eq!(profile["email_notifications"], true);
+
+    // Omitted fields stay, empty strings clear
+    let res = app
+        .post_json("/api/v1/clients/updateProfile", Some(&token), json!({ "phone": "" }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["profile"]["phone"], json!(null));
+    assert_eq!(res.body["profile"]["display_name"], "Wanjiku M.");
+}
+
+#[tokio::test]
+async fn invalid_updates_change_nothing() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let token = mint_jwt(seed::client(&app.pool).await);
+
+    for body in [
+        json!({ "display_name": "A", "location": "Westlands" }),
+        json!({ "display_name": "x".repeat(51) }),
+        json!({ "phone": "12345", "location": "Westlands" }),
+    ] {
+        let res = app.post_json("/api/v1/clients/updateProfile", Some(&token), body).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    }
+
+    let res = app.get("/api/v1/clients/me", Some(&token)).await;
+    assert_eq!(res.body["profile"]["location"], json!(null));
+}
+
+#[tokio::test]
+async fn received_bookings_show_the_clients_profile_name_and_phone() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+
+    let res = app
+        .post_json(
+            "/api/v1/clients/updateProfile",
+            Some(&mint_jwt(client_id)),
+            json!({ "display_name": "Otieno", "phone": "+254 700 000 001" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app
+        .get(
+            &format!("/api/v1/bookings/getBookings/received?target_type=provider&target_id={provider_id}"),
+            Some(&mint_jwt(owner_id)),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["bookings"][0]["client_name"], "Otieno");
+    assert_eq!(res.body["bookings"][0]["client_phone"], "254700000001");
+}
+
+#[tokio::test]
+async fn booking_history_splits_upcoming_from_past() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let (_, business_id) = seed::business(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let token = mint_jwt(client_id);
+
+    let tomorrow = seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    let next_week = seed::booking(&app.pool, client_id, "business", business_id, None, "confirmed").await;
+    let cancelled = seed::booking(&app.pool, client_id, "provider", provider_id, None, "cancelled").await;
+    let last_week = seed::booking(&app.pool, client_id, "provider", provider_id, None, "completed").await;
+    sqlx::query("UPDATE bookings SET scheduled_time = scheduled_time + INTERVAL '6 days' WHERE id = $1")
+        .bind(next_week)
+        .execute(&app.pool)
+        .await
+        .expect("move booking");
+    sqlx::query("UPDATE bookings SET scheduled_time = scheduled_time - INTERVAL '8 days' WHERE id = $1")
+        .bind(last_week)
+        .execute(&app.pool)
+        .await
+        .expect("move booking");
+
+    let ids = |body: &serde_json::Value| -> Vec<i64> {
+        body["items"].as_array().expect("items").iter().filter_map(|b| b["id"].as_i64()).collect()
+    };
+
+    let res = app.get("/api/v1/clients/bookings", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(ids(&res.body), [i64::from(tomorrow), i64::from(next_week)]);
+    assert_eq!(res.body["items"][0]["target_name"], "Test Plumbing");
+    assert_eq!(res.body["items"][1]["target_name"], "Test Salon");
+    assert_eq!(res.body["items"][0]["target_location"], "Nairobi");
+    assert!(res.body["items"][0]["confirmation_code"].as_str().is_some_and(|c| c.len() == 8));
+
+    let res = app.get("/api/v1/clients/bookings?scope=past&per_page=1", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(ids(&res.body), [i64::from(cancelled)]);
+    assert_eq!(res.body["total"], 2);
+    assert_eq!(res.body["has_more"], true);
+
+    let res = app.get("/api/v1/clients/bookings?scope=past&page=2&per_page=1", Some(&token)).await;
+    assert_eq!(ids(&res.body), [i64::from(last_week)]);
+
+    let res = app.get("/api/v1/clients/bookings?scope=later", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn address_book_keeps_one_default_and_caps_at_five() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let token = mint_jwt(seed::client(&app.pool).await);
+    let address = |label: &str| json!({ "label": label, "address": format!("{label} Road") });
+
+    let res = app.post_json("/api/v1/clients/addresses", Some(&token), address("Home")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let home = res.body["id"].as_i64().expect("address id");
+    assert_eq!(res.body["addresses"][0]["is_default"], true);
+
+    let mut work = address("Work");
+    work["is_default"] = json!(true);
+    let res = app.post_json("/api/v1/clients/addresses", Some(&token), work).await;
+    let work = res.body["id"].as_i64().expect("address id");
+    let defaults: Vec<i64> = res.body["addresses"]
+        .as_array()
+        .expect("addresses")
+        .iter()
+        .filter(|a| a["is_default"] == true)
+        .filter_map(|a| a["id"].as_i64())
+        .collect();
+    assert_eq!(defaults, [work]);
+
+    for label in ["Mum", "Gym", "Shop"] {
+        let res = app.post_json("/api/v1/clients/addresses", Some(&token), address(label)).await;
+        assert_eq!(res.status, StatusCode::CREATED);
+    }
+    let res = app.post_json("/api/v1/clients/addresses", Some(&token), address("Sixth")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    // Deleting the default hands it to the oldest remaining address
+    let res = app.request(Method::DELETE, &format!("/api/v1/clients/addresses/{work}"), Some(&token), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["addresses"][0]["id"].as_i64(), Some(home));
+    assert_eq!(res.body["addresses"][0]["is_default"], true);
+
+    // Someone else's address is not found
+    let other = mint_jwt(seed::client(&app.pool).await);
+    let res = app.request(Method::DELETE, &format!("/api/v1/clients/addresses/{home}"), Some(&other), None).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn bookings_copy_the_saved_address_for_the_provider() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let token = mint_jwt(client_id);
+    sqlx::query("UPDATE providers SET approved = TRUE WHERE id = $1")
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("approve provider");
+    let ward_id: i32 = sqlx::query_scalar(
+        "WITH county AS (INSERT INTO counties (name) VALUES ('Nairobi') RETURNING id),
+              constituency AS (INSERT INTO constituencies (name, county_id) SELECT 'Dagoretti North', id FROM county RETURNING id)
+         INSERT INTO wards (name, constituency_id) SELECT 'Kilimani', id FROM constituency RETURNING id",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed ward");
+
+    let res = app
+        .post_json(
+            "/api/v1/clients/addresses",
+            Some(&token),
+            json!({
+                "label": "Home",
+                "ward_id": ward_id,
+                "address": "Argwings Kodhek Rd, Hse 12",
+                "latitude": -1.29,
+                "longitude": 36.78,
+                "phone": "0711 000 111",
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let address_id = res.body["id"].as_i64().expect("address id");
+
+    let booking = |address_id: i64| {
+        json!({
+            "target_type": "provider",
+            "target_id": provider_id,
+            "service_description": "Fix the kitchen sink",
+            "scheduled_time": (chrono::Utc::now() + chrono::Duration::days(2)).naive_utc(),
+            "address_id": address_id,
+        })
+    };
+    let stranger = mint_jwt(seed::client(&app.pool).await);
+    let res = app.post_json("/api/v1/bookings/createBooking", Some(&stranger), booking(address_id)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["message"], "Address not found");
+
+    let res = app.post_json("/api/v1/bookings/createBooking", Some(&token), booking(address_id)).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    // The booking keeps its copy after the address is gone
+    let res = app.request(Method::DELETE, &format!("/api/v1/clients/addresses/{address_id}"), Some(&token), None).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app
+        .get(
+            &format!("/api/v1/bookings/getBookings/received?target_type=provider&target_id={provider_id}"),
+            Some(&mint_jwt(owner_id)),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let received = &res.body["bookings"][0];
+    assert_eq!(received["client_address"], "Argwings Kodhek Rd, Hse 12");
+    assert_eq!(received["client_address_label"], "Home");
+    assert_eq!(received["client_ward"], "Kilimani");
+    assert_eq!(received["client_latitude"], -1.29);
+    assert_eq!(received["client_phone"], "254711000111");
+}
+
+#[tokio::test]
+async fn activity_merges_sources_newest_first_without_duplicates() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let token = mint_jwt(client_id);
+    let notified = seed::booking(&app.pool, client_id, "provider", provider_id, None, "confirmed").await;
+    let silent = seed::booking(&app.pool, client_id, "provider", provider_id, None, "confirmed").await;
+
+    sqlx::query(
+        "INSERT INTO booking_events (booking_id, actor_id, event_type, from_status, to_status, created_at) VALUES
+             ($1, $3, 'status_changed', 'pending', 'confirmed', NOW() - INTERVAL '4 hours'),
+             ($2, $3, 'status_changed', 'pending', 'confirmed', NOW() - INTERVAL '3 hours'),
+             ($2, $4, 'rescheduled', NULL, NULL, NOW() - INTERVAL '2 hours')",
+    )
+    .bind(notified)
+    .bind(silent)
+    .bind(owner_id)
+    .bind(client_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed booking events");
+    // The confirmation of `notified` also produced a notification
+    sqlx::query(
+        "INSERT INTO notifications (user_id, notif_type, title, body, target_type, target_id, created_at)
+         VALUES ($1, 'confirmed', 'Booking Confirmed', 'See you soon', 'booking', $2, (NOW() - INTERVAL '4 hours') AT TIME ZONE 'UTC')",
+    )
+    .bind(client_id)
+    .bind(notified)
+    .execute(&app.pool)
+    .await
+    .expect("seed notification");
+    let review_id: i32 = sqlx::query_scalar(
+        "INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment)
+         VALUES ($1, 'provider', $2, 5, 'Great work') RETURNING id",
+    )
+    .bind(client_id)
+    .bind(provider_id)
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed review");
+    sqlx::query(
+        "INSERT INTO review_replies (review_id, reviewer_id, comment, created_at)
+         VALUES ($1, $2, 'Thank you!', (NOW() - INTERVAL '1 hour') AT TIME ZONE 'UTC')",
+    )
+    .bind(review_id)
+    .bind(owner_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed reply");
+
+    let kinds = |body: &serde_json::Value| -> Vec<(String, i64)> {
+        body["items"]
+            .as_array()
+            .expect("items")
+            .iter()
+            .map(|i| (i["kind"].as_str().unwrap_or_default().to_string(), i["target_id"].as_i64().unwrap_or_default()))
+            .collect()
+    };
+
+    let res = app.get("/api/v1/clients/activity", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    // The client's own reschedule and the notified confirmation's event are left out
+    assert_eq!(
+        kinds(&res.body),
+        [
+            ("review_reply".to_string(), i64::from(review_id)),
+            ("booking_event".to_string(), i64::from(silent)),
+            ("notification".to_string(), i64::from(notified)),
+        ]
+    );
+    assert_eq!(res.body["items"][1]["data"]["to_status"], "confirmed");
+    assert_eq!(res.body["next_cursor"], json!(null));
+
+    let res = app.get("/api/v1/clients/activity?limit=2", Some(&token)).await;
+    assert_eq!(res.body["items"].as_array().map(Vec::len), Some(2));
+    let cursor = res.body["next_cursor"].as_str().expect("next cursor").to_string();
+    let res = app
+        .get(&format!("/api/v1/clients/activity?limit=2&before={}", cursor.replace('+', "%2B")), Some(&token))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(kinds(&res.body), [("notification".to_string(), i64::from(notified))]);
+}
+
+#[tokio::test]
+async fn export_holds_every_category_the_privacy_summary_counts() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let token = mint_jwt(client_id);
+    let booking_id = seed::booking(&app.pool, client_id, "provider", provider_id, None, "completed").await;
+    sqlx::query(
+        "WITH m AS (
+             INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content)
+             VALUES ($1, $2, 'provider', $3, 'Hello'), ($1, $2, 'provider', $3, 'Are you there?'),
+                    ($2, $1, 'provider', $3, 'Yes')
+         )
+         INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment)
+         VALUES ($1, 'provider', $3, 4, 'Quick and tidy')",
+    )
+    .bind(client_id)
+    .bind(owner_id)
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed messages and review");
+
+    let res = app.get("/api/v1/clients/export", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.headers[axum::http::header::CONTENT_DISPOSITION].to_str().is_ok_and(|d| d.starts_with("attachment")));
+    let export = &res.body;
+    assert_eq!(export["role"], "client");
+    assert_eq!(export["profile"]["id"].as_i64(), Some(i64::from(client_id)));
+    assert!(export["profile"].get("password").is_none());
+    assert_eq!(export["bookings"][0]["id"].as_i64(), Some(i64::from(booking_id)));
+    assert_eq!(export["reviews"][0]["comment"], "Quick and tidy");
+    // Only messages the client sent
+    assert_eq!(export["messages"].as_array().map(Vec::len), Some(2));
+    assert_eq!(export["favorites"], json!([]));
+
+    let res = app.get("/api/v1/clients/privacy", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    for category in res.body["categories"].as_array().expect("categories") {
+        let name = category["name"].as_str().expect("name");
+        let exported = match &export[name] {
+            serde_json::Value::Array(rows) => rows.len(),
+            serde_json::Value::Null => 0,
+            _ => 1,
+        };
+        assert_eq!(category["count"].as_u64(), Some(exported as u64), "{name}");
+        assert!(category["description"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn a_profile_picture_lands_on_the_callers_client_row() {
+    let Some(app) = TestApp::spawn().await else { return };
+    // Rows created in reverse, so one client's row id is the other's user id
+    let first = seed::user(&app.pool, "client").await;
+    let second = seed::user(&app.pool, "client").await;
+    let mut row_of = std::collections::HashMap::new();
+    for user_id in [second, first] {
+        let id: i32 = sqlx::query_scalar("INSERT INTO clients (user_id) VALUES ($1) RETURNING id")
+            .bind(user_id)
+            .fetch_one(&app.pool)
+            .await
+            .expect("seed client");
+        row_of.insert(user_id, id);
+    }
+    assert_ne!(row_of[&first], first);
+    assert_eq!(row_of[&first], second);
+
+    let res = app.send(picture_upload(&mint_jwt(first))).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let url = res.body["url"].as_str().expect("url");
+    assert!(url.starts_with("/uploads/clients/profile_pictures/"), "{url}");
+
+    let pictures: Vec<(i32, Option<String>)> = sqlx::query_as("SELECT user_id, profile_picture FROM clients ORDER BY user_id")
+        .fetch_all(&app.pool)
+        .await
+        .expect("read clients");
+    assert_eq!(pictures, [(first, Some(url.to_string())), (second, None)]);
+
+    // An account without a client row has nothing to update
+    let (provider, _) = seed::provider(&app.pool).await;
+    let res = app.send(picture_upload(&mint_jwt(provider))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+}
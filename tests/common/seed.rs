@@ -0,0 +1,112 @@
+//! Direct inserts for setting up test data without going through the API.
+//! Seeded users have an unusable password; use `mint_jwt` to act as them.
+
+use chrono::{NaiveDateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn user(pool: &PgPool, role: &str) -> i32 {
+    let username = format!("seed_{}", &Uuid::new_v4().simple().to_string()[..10]);
+    sqlx::query_scalar(
+        "INSERT INTO users (username, email, password, role, email_verified)
+         VALUES ($1, $2, 'not-a-password-hash', $3, TRUE) RETURNING id",
+    )
+    .bind(&username)
+    .bind(format!("{username}@example.com"))
+    .bind(role)
+    .fetch_one(pool)
+    .await
+    .expect("seed user")
+}
+
+/// A super admin, allowed through `/admin`. Returns the user id.
+pub async fn admin(pool: &PgPool) -> i32 {
+    let user_id = user(pool, "client").await;
+    sqlx::query("INSERT INTO admins (user_id, is_super_admin) VALUES ($1, TRUE)")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .expect("seed admin");
+    user_id
+}
+
+/// A client user; returns the user id (bookings reference users directly).
+pub async fn client(pool: &PgPool) -> i32 {
+    let user_id = user(pool, "client").await;
+    sqlx::query("INSERT INTO clients (user_id) VALUES ($1)")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .expect("seed client");
+    user_id
+}
+
+/// An onboarded, approved provider. Returns `(user_id, provider_id)`.
+pub async fn provider(pool: &PgPool) -> (i32, i32) {
+    let user_id = user(pool, "provider").await;
+    let provider_id = sqlx::query_scalar(
+        "INSERT INTO providers (user_id, service_name, service_description, category, location,
+                                onboarding_completed, listing_status)
+         VALUES ($1, 'Test Plumbing', 'Fixes pipes', 'Plumbing', 'Nairobi', TRUE, 'approved')
+         RETURNING id",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .expect("seed provider");
+    (user_id, provider_id)
+}
+
+/// An onboarded, approved business. Returns `(user_id, business_id)`.
+pub async fn business(pool: &PgPool) -> (i32, i32) {
+    let user_id = user(pool, "business").await;
+    let business_id = sqlx::query_scalar(
+        "INSERT INTO businesses (user_id, business_name, description, category, location,
+                                 onboarding_completed, listing_status)
+         VALUES ($1, 'Test Salon', 'Hair and nails', 'Beauty', 'Nairobi', TRUE, 'approved')
+         RETURNING id",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .expect("seed business");
+    (user_id, business_id)
+}
+
+/// An active 60-minute service for a provider or business.
+pub async fn service(pool: &PgPool, target_type: &str, target_id: i32) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO services (target_type, target_id, title, description, price, duration, is_active)
+         VALUES ($1, $2, 'Test service', 'A service', 1500, 60, TRUE) RETURNING id",
+    )
+    .bind(target_type)
+    .bind(target_id)
+    .fetch_one(pool)
+    .await
+    .expect("seed service")
+}
+
+/// A booking by `client_id` (a user id) for one hour, a day from now.
+pub async fn booking(
+    pool: &PgPool,
+    client_id: i32,
+    target_type: &str,
+    target_id: i32,
+    service_id: Option<i32>,
+    status: &str,
+) -> i32 {
+    let scheduled: NaiveDateTime = (Utc::now() + chrono::Duration::days(1)).naive_utc();
+    sqlx::query_scalar(
+        "INSERT INTO bookings (client_id, target_type, target_id, service_id, scheduled_time, duration, status)
+         VALUES ($1, $2, $3, $4, $5, 60, $6) RETURNING id",
+    )
+    .bind(client_id)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(service_id)
+    .bind(scheduled)
+    .bind(status)
+    .fetch_one(pool)
+    .await
+    .expect("seed booking")
+}
@@ -0,0 +1,344 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+
+#[tokio::test]
+async fn clients_see_upcoming_bookings_and_listings_to_review() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (_, business_id) = seed::business(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let upcoming = seed::booking(&app.pool, client_id, "provider", provider_id, None, "confirmed").await;
+    seed::booking(&app.pool, client_id, "business", business_id, None, "completed").await;
+    seed::booking(&app.pool, client_id, "provider", provider_id, None, "completed").await;
+    sqlx::query("INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment) VALUES ($1, 'provider', $2, 5, 'Great')")
+        .bind(client_id)
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("seed review");
+    sqlx::query(
+        "INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content) VALUES ($1, $2, 'provider', $3, 'See you')",
+    )
+    .bind(owner_id)
+    .bind(client_id)
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed message");
+
+    let res = app.get("/api/v1/dashboard", Some(&mint_jwt(client_id))).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let dash = &res.body;
+    assert_eq!(dash["role"], "client");
+    assert_eq!(dash["upcoming_bookings"], 1);
+    assert_eq!(dash["upcoming"][0]["id"].as_i64(), Some(i64::from(upcoming)));
+    assert_eq!(dash["upcoming"][0]["with_name"], "Test Plumbing");
+    assert_eq!(dash["unread_messages"], 1);
+    // The provider is already reviewed; the business isn't
+    assert_eq!(
+        dash["pending_review_invites"].as_array().map(|i| i.iter().map(|i| i["target_name"].clone()).collect()),
+        Some(vec![json!("Test Salon")])
+    );
+}
+
+#[tokio::test]
+async fn owners_see_today_pending_reviews_and_week_over_week() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let today = seed::booking(&app.pool, client_id, "provider", provider_id, None, "confirmed").await;
+    seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    let old = seed::booking(&app.pool, client_id, "provider", provider_id, None, "completed").await;
+    sqlx::query(
+        "UPDATE bookings SET scheduled_time = date_trunc('day', NOW() AT TIME ZONE 'UTC') + INTERVAL '23 hours 59 minutes'
+         WHERE id = $1",
+    )
+    .bind(today)
+    .execute(&app.pool)
+    .await
+    .expect("move booking to today");
+    sqlx::query("UPDATE bookings SET created_at = NOW() - INTERVAL '10 days' WHERE id = $1")
+        .bind(old)
+        .execute(&app.pool)
+        .await
+        .expect("age booking");
+    sqlx::query("INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment) VALUES ($1, 'provider', $2, 4, 'Good')")
+        .bind(client_id)
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("seed review");
+
+    let token = mint_jwt(owner_id);
+    let res = app.get("/api/v1/dashboard", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let dash = &res.body;
+    assert_eq!(dash["role"], "provider");
+    assert_eq!(dash["provider_id"].as_i64(), Some(i64::from(provider_id)));
+    assert_eq!(dash["todays_bookings"][0]["id"].as_i64(), Some(i64::from(today)));
+    assert_eq!(dash["pending_bookings"], 1);
+    assert_eq!(dash["new_reviews"], 1);
+    assert_eq!(dash["bookings_this_week"], 2);
+    assert_eq!(dash["bookings_last_week"], 1);
+    assert_eq!(dash["bookings_week_delta"], 1);
+
+    // Served from the cache for a while
+    seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    let res = app.get("/api/v1/dashboard", Some(&token)).await;
+    assert_eq!(res.body["pending_bookings"], 1);
+}
+
+#[tokio::test]
+async fn owners_see_earnings_only_while_payments_are_on() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (other_owner_id, _) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let booking_id = seed::booking(&app.pool, client_id, "provider", provider_id, None, "confirmed").await;
+    sqlx::query(
+        "INSERT INTO payments (booking_id, phone_number, amount, status) VALUES
+             ($1, '254700000001', 1250.50, 'completed'),
+             ($1, '254700000001', 99999, 'failed');
+         ",
+    )
+    .bind(booking_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed payments");
+    sqlx::query(
+        "WITH w AS (INSERT INTO wallets (target_type, target_id, balance) VALUES ('provider', $1, 1250.50) RETURNING id)
+         INSERT INTO payout_requests (wallet_id, amount, phone_number) SELECT id, 1000, '254700000002' FROM w",
+    )
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed payout request");
+
+    let res = app.get("/api/v1/dashboard", Some(&mint_jwt(owner_id))).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let earnings = &res.body["earnings"];
+    assert_eq!(earnings["currency"], "KES");
+    assert_eq!(earnings["collected_this_month"], json!({ "amount": "1250.50", "formatted": "KES 1,250.50" }));
+    assert_eq!(earnings["collected_this_week"]["amount"], "1250.50");
+    assert_eq!(earnings["pending_payouts"]["formatted"], "KES 1,000.00");
+    let daily = earnings["daily"].as_array().expect("daily series");
+    assert_eq!(daily.len(), 30);
+    assert_eq!(daily.last().map(|d| d["amount"].clone()), Some(json!("1250.50")));
+
+    let res = app.get("/api/v1/dashboard", Some(&mint_jwt(client_id))).await;
+    assert!(res.body.get("earnings").is_none());
+
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let res = app
+        .request(Method::PUT, "/api/v1/admin/feature_flags/payments", Some(&admin), Some(json!({ "enabled": false })))
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    // A user with no cached dashboard yet
+    let res = app.get("/api/v1/dashboard", Some(&mint_jwt(other_owner_id))).await;
+    assert_eq!(res.body["role"], "provider");
+    assert!(res.body.get("earnings").is_none());
+}
+
+#[tokio::test]
+async fn owners_can_dismiss_action_items_until_something_new_comes_up() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let stale = seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    sqlx::query("UPDATE bookings SET created_at = NOW() - INTERVAL '13 hours' WHERE id = $1")
+        .bind(stale)
+        .execute(&app.pool)
+        .await
+        .expect("age booking");
+    let owner = mint_jwt(owner_id);
+
+    let item = |body: &serde_json::Value, item_type: &str| {
+        body["action_items"]
+            .as_array()
+            .expect("action items")
+            .iter()
+            .find(|i| i["type"] == item_type)
+            .cloned()
+    };
+
+    let res = app.get("/api/v1/dashboard", Some(&owner)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    // Only the request older than the threshold counts
+    let requests = item(&res.body, "stale_booking_requests").expect("stale requests item");
+    assert_eq!(requests["count"], 1);
+    assert_eq!(requests["link"]["status"], "pending");
+    // The seeded provider has no photo or location
+    let photo = item(&res.body, "missing_photo").expect("missing photo item");
+    assert!(item(&res.body, "missing_location").is_some());
+
+    let res = app
+        .post_json(
+            "/api/v1/dashboard/dismiss",
+            Some(&owner),
+            json!({ "type": "stale_booking_requests", "occurrence": requests["occurrence"] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/api/v1/dashboard", Some(&owner)).await;
+    assert!(item(&res.body, "stale_booking_requests").is_none());
+    assert!(item(&res.body, "missing_photo").is_some());
+
+    // A newer overdue request brings the item back
+    let newer = seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    sqlx::query("UPDATE bookings SET created_at = NOW() - INTERVAL '12 hours 30 minutes' WHERE id = $1")
+        .bind(newer)
+        .execute(&app.pool)
+        .await
+        .expect("age booking");
+    let res = app
+        .post_json(
+            "/api/v1/dashboard/dismiss",
+            Some(&owner),
+            json!({ "type": "missing_photo", "occurrence": photo["occurrence"] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.get("/api/v1/dashboard", Some(&owner)).await;
+    assert!(item(&res.body, "missing_photo").is_none());
+    assert_eq!(item(&res.body, "stale_booking_requests").map(|i| i["count"].clone()), Some(json!(2)));
+
+    for body in [
+        json!({ "type": "everything", "occurrence": "x" }),
+        json!({ "type": "missing_photo", "occurrence": "" }),
+    ] {
+        let res = app.post_json("/api/v1/dashboard/dismiss", Some(&owner), body).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn provider_onboarding_progress_is_scored_and_boosts_listing_order() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, unfinished_id) = seed::provider(&app.pool).await;
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let owner = mint_jwt(owner_id);
+
+    let res = app.get("/api/v1/dashboard/onboarding", Some(&owner)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["score"], 0);
+    assert_eq!(res.body["complete"], false);
+    assert_eq!(res.body["steps"].as_array().map(Vec::len), Some(6));
+
+    let res = app
+        .post_json(
+            "/api/v1/service_providers/onboard",
+            Some(&owner),
+            json!({
+                "service_name": "Test Plumbing",
+                "service_description": "Fixes pipes and taps",
+                "category": "Plumbing",
+                "location": "Nairobi",
+                "phone_number": "0712345678",
+                "email": "plumber@example.com",
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app
+        .post_json(
+            "/api/v1/services/createService",
+            Some(&owner),
+            json!({
+                "target_id": provider_id, "target_type": "provider", "title": "Leak repair",
+                "description": "Any leak", "price": 1500, "duration": 60, "is_active": true,
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let res = app.get("/api/v1/dashboard/onboarding", Some(&owner)).await;
+    assert_eq!(res.body["score"], 45);
+    let done: Vec<&str> = res.body["steps"]
+        .as_array()
+        .expect("steps")
+        .iter()
+        .filter(|s| s["done"] == true)
+        .filter_map(|s| s["key"].as_str())
+        .collect();
+    assert_eq!(done, ["profile", "service"]);
+    let stored: i16 = sqlx::query_scalar("SELECT onboarding_score FROM providers WHERE id = $1")
+        .bind(provider_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("stored score");
+    assert_eq!(stored, 45);
+
+    let res = app.get("/api/v1/dashboard", Some(&owner)).await;
+    assert_eq!(res.body["onboarding"]["score"], 45);
+
+    // Further along in setup lists first by default; an explicit sort wins
+    let ids = |body: &serde_json::Value| -> Vec<i64> {
+        body["items"].as_array().expect("providers").iter().filter_map(|p| p["id"].as_i64()).collect()
+    };
+    let res = app.get("/api/v1/service_providers/listProviders", None).await;
+    assert_eq!(ids(&res.body), [i64::from(provider_id), i64::from(unfinished_id)]);
+    let res = app.get("/api/v1/service_providers/listProviders?sort=newest", None).await;
+    assert_eq!(ids(&res.body), [i64::from(unfinished_id), i64::from(provider_id)]);
+
+    // Clients have no checklist
+    let client = mint_jwt(seed::client(&app.pool).await);
+    let res = app.get("/api/v1/dashboard/onboarding", Some(&client)).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admins_get_the_moderation_view() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let reporter = seed::client(&app.pool).await;
+    sqlx::query("UPDATE providers SET listing_status = 'pending_review' WHERE id = $1")
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("queue listing");
+    sqlx::query(
+        "INSERT INTO content_flags (target_type, target_id, reason, flagged_by) VALUES ('provider', $1, 'spam', $2)",
+    )
+    .bind(provider_id)
+    .bind(reporter)
+    .execute(&app.pool)
+    .await
+    .expect("seed flag");
+    sqlx::query(
+        "INSERT INTO job_runs (job_name, started_at, finished_at, error) VALUES
+             ('booking_reminders', NOW() - INTERVAL '2 hours', NOW() - INTERVAL '2 hours', 'timeout'),
+             ('booking_reminders', NOW() - INTERVAL '1 hour', NOW() - INTERVAL '1 hour', 'connection reset'),
+             ('storage_gc', NOW() - INTERVAL '3 days', NOW() - INTERVAL '3 days', 'old failure')",
+    )
+    .execute(&app.pool)
+    .await
+    .expect("seed job runs");
+
+    let res = app.get("/api/v1/dashboard", Some(&mint_jwt(seed::admin(&app.pool).await))).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let dash = &res.body;
+    assert_eq!(dash["role"], "admin");
+    assert_eq!(dash["open_flags"]["count"], 1);
+    assert_eq!(dash["open_flags"]["latest"][0]["reason"], "spam");
+    assert_eq!(dash["pending_verifications"]["count"], 1);
+    assert_eq!(dash["pending_verifications"]["oldest"][0]["entity_id"].as_i64(), Some(i64::from(provider_id)));
+    assert_eq!(
+        dash["failing_jobs"],
+        json!([{
+            "job_name": "booking_reminders",
+            "failures": 2,
+            "last_failure_at": dash["failing_jobs"][0]["last_failure_at"],
+            "last_error": "connection reset",
+        }])
+    );
+    assert!(dash["overview"]["users"]["all"].is_i64());
+
+    // Other roles get their own view with none of it
+    let res = app.get("/api/v1/dashboard", Some(&mint_jwt(reporter))).await;
+    assert_eq!(res.body["role"], "client");
+    assert!(res.body.get("open_flags").is_none());
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use common::TestApp;
+use mtaalink::AppError;
+
+#[tokio::test]
+async fn pooled_connections_have_the_statement_timeout() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let timeout: String = sqlx::query_scalar("SHOW statement_timeout")
+        .fetch_one(&app.pool)
+        .await
+        .expect("show statement_timeout");
+
+    assert_eq!(timeout, "10s");
+}
+
+#[tokio::test]
+async fn a_cancelled_query_is_a_gateway_timeout() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let mut tx = app.pool.begin().await.expect("begin");
+    sqlx::query("SET LOCAL statement_timeout = 50")
+        .execute(&mut *tx)
+        .await
+        .expect("set timeout");
+
+    let err = sqlx::query("SELECT pg_sleep(1)")
+        .execute(&mut *tx)
+        .await
+        .expect_err("query should be cancelled");
+    let response = AppError::from(err).into_response();
+
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+}
+
+#[tokio::test]
+async fn readiness_reports_pool_usage() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let res = app.get("/readyz", None).await;
+
+    assert_eq!(res.body["pool"]["max"], 5);
+    assert_eq!(res.body["pool"]["saturated"], false);
+    assert!(res.body["pool"]["in_use"].is_u64());
+}
+
+#[tokio::test]
+async fn health_reports_the_database_up_then_down() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let res = app.get("/health", None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["status"], "ok");
+    assert_eq!(res.body["db"], "up");
+    assert_eq!(res.body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(res.body["uptime_seconds"].is_u64());
+    assert!(res.body["pool"]["idle"].is_u64());
+
+    // The router shares this pool, so every query now fails
+    app.pool.close().await;
+    let res = app.get("/health", None).await;
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE, "{}", res.body);
+    assert_eq!(res.body["db"], "down");
+}
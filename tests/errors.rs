@@ -0,0 +1,83 @@
+use axum::{body::to_bytes, http::StatusCode, response::IntoResponse};
+use mtaalink::AppError;
+use serde_json::Value;
+use validator::Validate;
+
+async fn render(error: AppError) -> (StatusCode, Value) {
+    let response = error.into_response();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.expect("read body");
+    (status, serde_json::from_slice(&bytes).expect("json body"))
+}
+
+#[derive(Validate)]
+struct Signup {
+    #[validate(email)]
+    email: String,
+    #[validate(length(min = 8))]
+    password: String,
+}
+
+#[tokio::test]
+async fn each_variant_maps_to_its_status_and_code() {
+    let cases = [
+        (AppError::NotFound("Provider not found".into()), StatusCode::NOT_FOUND, "not_found"),
+        (AppError::Unauthorized("Missing token".into()), StatusCode::UNAUTHORIZED, "unauthorized"),
+        (AppError::Forbidden("Not yours".into()), StatusCode::FORBIDDEN, "forbidden"),
+        (AppError::BadRequest("Invalid target type".into()), StatusCode::BAD_REQUEST, "bad_request"),
+        (AppError::Conflict("Slot taken".into()), StatusCode::CONFLICT, "conflict"),
+        (AppError::PayloadTooLarge("Too big".into()), StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large"),
+        (AppError::UnsupportedMediaType("Not an image".into()), StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type"),
+        (AppError::Timeout("Too slow".into()), StatusCode::GATEWAY_TIMEOUT, "timeout"),
+    ];
+    for (error, status, code) in cases {
+        let message = match &error {
+            AppError::NotFound(m)
+            | AppError::Unauthorized(m)
+            | AppError::Forbidden(m)
+            | AppError::BadRequest(m)
+            | AppError::Conflict(m)
+            | AppError::PayloadTooLarge(m)
+            | AppError::UnsupportedMediaType(m)
+            | AppError::Timeout(m) => m.clone(),
+            _ => unreachable!(),
+        };
+        let (got, body) = render(error).await;
+        assert_eq!((got, body["code"].as_str()), (status, Some(code)));
+        assert_eq!(body["message"], message);
+        assert!(body.get("details").is_none(), "{body}");
+    }
+}
+
+#[tokio::test]
+async fn database_and_internal_errors_never_reach_the_client() {
+    let (status, body) = render(sqlx::Error::Protocol("SELECT secret FROM users".into()).into()).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["code"], "internal_error");
+    assert!(!body.to_string().contains("secret"), "{body}");
+
+    let (status, body) = render(AppError::Internal("disk full at /var/lib/secret".into())).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(!body.to_string().contains("secret"), "{body}");
+
+    let (status, body) = render(AppError::EmailError("Brevo said 401 for key secret".into())).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::INTERNAL_SERVER_ERROR, Some("email_failed")));
+    assert!(!body.to_string().contains("secret"), "{body}");
+
+    let (status, body) = render(sqlx::Error::RowNotFound.into()).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::NOT_FOUND, Some("not_found")));
+    let (status, body) = render(sqlx::Error::PoolTimedOut.into()).await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::SERVICE_UNAVAILABLE, Some("unavailable")));
+}
+
+#[tokio::test]
+async fn validation_errors_list_fields_without_echoing_values() {
+    let signup = Signup { email: "not-an-email".into(), password: "hunter2".into() };
+    let (status, body) = render(signup.validate().unwrap_err().into()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["details"]["fields"]["email"][0], "must be a valid email address");
+    assert_eq!(body["details"]["fields"]["password"][0], "must be at least 8 characters");
+    assert_eq!(body["message"], "email: must be a valid email address; password: must be at least 8 characters");
+    assert!(!body.to_string().contains("hunter2"), "{body}");
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use common::{TestApp, TestResponse, mint_jwt, seed};
+use serde_json::json;
+
+async fn get_if_none_match(app: &TestApp, uri: &str, etag: &str) -> TestResponse {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header(header::IF_NONE_MATCH, etag)
+        .body(Body::empty())
+        .expect("build request");
+    app.send(request).await
+}
+
+fn etag_of(res: &TestResponse) -> String {
+    res.headers
+        .get(header::ETAG)
+        .expect("ETag header")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn categories_revalidate_with_304_until_a_category_changes() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin_id = seed::admin(&app.pool).await;
+
+    let first = app.get("/categories/allCategories", None).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    assert_eq!(first.headers[header::CACHE_CONTROL], "public, max-age=300");
+    let etag = etag_of(&first);
+
+    let again = get_if_none_match(&app, "/categories/allCategories", &etag).await;
+    assert_eq!(again.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(etag_of(&again), etag);
+
+    let created = app
+        .post_json("/admin/create_category", Some(&mint_jwt(admin_id)), json!({ "name": "Roofing" }))
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+
+    let after = get_if_none_match(&app, "/categories/allCategories", &etag).await;
+    assert_eq!(after.status, StatusCode::OK);
+    assert_ne!(etag_of(&after), etag);
+    let names: Vec<&str> = after.body["categories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|c| c["category_name"].as_str())
+        .collect();
+    assert!(names.contains(&"Roofing"));
+}
+
+#[tokio::test]
+async fn location_lists_share_the_locations_version() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let first = app.get("/locations/allcounties", None).await;
+    let etag = etag_of(&first);
+    assert_eq!(get_if_none_match(&app, "/locations/allcounties", &etag).await.status, StatusCode::NOT_MODIFIED);
+
+    sqlx::query("INSERT INTO counties (name) VALUES ('Test County')")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let after = get_if_none_match(&app, "/locations/allcounties", &etag).await;
+    assert_eq!(after.status, StatusCode::OK);
+    assert_ne!(etag_of(&after), etag);
+}
+
+#[tokio::test]
+async fn provider_listing_is_tagged_by_its_body() {
+    let Some(app) = TestApp::spawn().await else { return };
+    seed::provider(&app.pool).await;
+
+    let first = app.get("/service_providers/listProviders", None).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    assert_eq!(first.headers[header::CACHE_CONTROL], "public, no-cache");
+    let etag = etag_of(&first);
+
+    let again = get_if_none_match(&app, "/service_providers/listProviders", &etag).await;
+    assert_eq!(again.status, StatusCode::NOT_MODIFIED);
+
+    // A weak validator from an intermediary still matches
+    let weak = get_if_none_match(&app, "/service_providers/listProviders", &format!("W/{etag}")).await;
+    assert_eq!(weak.status, StatusCode::NOT_MODIFIED);
+
+    seed::provider(&app.pool).await;
+    let changed = get_if_none_match(&app, "/service_providers/listProviders", &etag).await;
+    assert_eq!(changed.status, StatusCode::OK);
+    assert_eq!(changed.body["total"], 2);
+}
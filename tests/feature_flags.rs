@@ -0,0 +1,105 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::{Value, json};
+
+async fn set_flag(app: &TestApp, admin_token: &str, name: &str, body: Value) {
+    let res = app
+        .request(Method::PUT, &format!("/api/v1/admin/feature_flags/{name}"), Some(admin_token), Some(body))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+}
+
+/// An empty body: the handler's JSON rejection (422) once past the flag.
+async fn initiate_payment(app: &TestApp, token: Option<&str>) -> StatusCode {
+    app.post_json("/api/v1/payments/initiate", token, json!({})).await.status
+}
+
+#[tokio::test]
+async fn payments_disappear_and_return_without_a_restart() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    // On by default outside production
+    assert_eq!(initiate_payment(&app, Some(&client)).await, StatusCode::UNPROCESSABLE_ENTITY);
+
+    set_flag(&app, &admin, "payments", json!({ "enabled": false })).await;
+    assert_eq!(initiate_payment(&app, Some(&client)).await, StatusCode::NOT_FOUND);
+    let res = app.post_json("/api/v1/payments/mpesa/callback", None, json!({})).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app.get("/api/v1/admin/feature_flags", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let payments = res.body["feature_flags"]
+        .as_array()
+        .and_then(|flags| flags.iter().find(|f| f["name"] == "payments"))
+        .expect("payments flag listed");
+    assert_eq!(payments["default"], true);
+    assert_eq!(payments["enabled"], false);
+
+    let res = app
+        .request(Method::DELETE, "/api/v1/admin/feature_flags/payments", Some(&admin), None)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(initiate_payment(&app, Some(&client)).await, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn targeting_limits_a_flag_to_matching_signed_in_users() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let (provider_user, _) = seed::provider(&app.pool).await;
+    let (business_user, _) = seed::business(&app.pool).await;
+
+    set_flag(&app, &admin, "payments", json!({ "enabled": true, "roles": ["business"] })).await;
+    assert_eq!(initiate_payment(&app, Some(&mint_jwt(provider_user))).await, StatusCode::NOT_FOUND);
+    assert_eq!(initiate_payment(&app, Some(&mint_jwt(business_user))).await, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Nobody signed in is in a 0% rollout, but anonymous calls (the M-Pesa
+    // callback) still reach an enabled feature
+    set_flag(&app, &admin, "payments", json!({ "enabled": true, "rollout_percent": 0 })).await;
+    assert_eq!(initiate_payment(&app, Some(&mint_jwt(business_user))).await, StatusCode::NOT_FOUND);
+    let res = app.post_json("/api/v1/payments/mpesa/callback", None, json!({})).await;
+    assert_ne!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn the_websocket_upgrade_is_gated() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let uri = format!("/api/v1/ws?token={}", mint_jwt(seed::client(&app.pool).await));
+
+    // A plain GET reaches the handler, which refuses to upgrade it
+    assert_ne!(app.get(&uri, None).await.status, StatusCode::NOT_FOUND);
+
+    set_flag(&app, &admin, "websocket", json!({ "enabled": false })).await;
+    assert_eq!(app.get(&uri, None).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn updates_are_validated_and_admin_only() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    let put = |name: &'static str, token: String, body: Value| {
+        let app = &app;
+        async move {
+            app.request(Method::PUT, &format!("/api/v1/admin/feature_flags/{name}"), Some(&token), Some(body))
+                .await
+                .status
+        }
+    };
+    assert_eq!(put("payments", client, json!({ "enabled": false })).await, StatusCode::FORBIDDEN);
+    assert_eq!(put("teleport", admin.clone(), json!({ "enabled": true })).await, StatusCode::NOT_FOUND);
+    assert_eq!(
+        put("payments", admin.clone(), json!({ "enabled": true, "rollout_percent": 101 })).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        put("payments", admin, json!({ "enabled": true, "roles": ["wizard"] })).await,
+        StatusCode::BAD_REQUEST
+    );
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+
+async fn impersonate(app: &TestApp, admin: &str, user_id: i32) -> String {
+    let res = app
+        .post_json("/admin/impersonate", Some(admin), json!({ "user_id": user_id, "reason": "Ticket 42" }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    res.body["token"].as_str().expect("token").to_string()
+}
+
+async fn audited_requests(app: &TestApp, user_id: i32) -> Vec<(Option<i32>, String)> {
+    sqlx::query_as::<_, (Option<i32>, String)>(
+        "SELECT actor_id, details->>'path' FROM audit_log
+         WHERE action = 'impersonated_request' AND target_id = $1 ORDER BY id",
+    )
+    .bind(user_id)
+    .fetch_all(&app.pool)
+    .await
+    .expect("read audit log")
+}
+
+#[tokio::test]
+async fn only_admins_impersonate_and_every_request_is_audited() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin_id = seed::admin(&app.pool).await;
+    let admin = mint_jwt(admin_id);
+    let client_id = seed::client(&app.pool).await;
+
+    let res = app
+        .post_json("/admin/impersonate", Some(&mint_jwt(seed::client(&app.pool).await)), json!({ "user_id": client_id }))
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.post_json("/admin/impersonate", Some(&admin), json!({ "user_id": seed::admin(&app.pool).await })).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+
+    let token = impersonate(&app, &admin, client_id).await;
+    let res = app.get("/api/v1/auth/me", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    // The impersonated token can't be used to mint another
+    let res = app.post_json("/admin/impersonate", Some(&token), json!({ "user_id": client_id })).await;
+    assert_ne!(res.status, StatusCode::OK, "{}", res.body);
+
+    assert_eq!(
+        audited_requests(&app, client_id).await,
+        [(Some(admin_id), "/api/v1/auth/me".to_string()), (Some(admin_id), "/admin/impersonate".to_string())]
+    );
+    let res = app.get(&format!("/admin/impersonation_log?user_id={client_id}"), Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let actions: Vec<&str> = res.body["entries"]
+        .as_array()
+        .expect("entries")
+        .iter()
+        .map(|e| e["action"].as_str().expect("action"))
+        .collect();
+    assert_eq!(actions, ["impersonated_request", "impersonated_request", "impersonation_started"]);
+}
+
+#[tokio::test]
+async fn an_impersonated_request_that_cannot_be_audited_does_not_run() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let client_id = seed::client(&app.pool).await;
+    let token = impersonate(&app, &admin, client_id).await;
+
+    sqlx::query("ALTER TABLE audit_log ADD CONSTRAINT refuse_impersonation CHECK (action <> 'impersonated_request') NOT VALID")
+        .execute(&app.pool)
+        .await
+        .expect("break audit writes");
+    let res = app
+        .post_json("/clients/addresses", Some(&token), json!({ "label": "Home", "address": "1 Moi Avenue" }))
+        .await;
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE, "{}", res.body);
+    assert_eq!(res.body["code"], "audit_unavailable");
+    let addresses: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM client_addresses")
+        .fetch_one(&app.pool)
+        .await
+        .expect("count addresses");
+    assert_eq!(addresses, 0);
+}
+
+#[tokio::test]
+async fn impersonated_tokens_cannot_pay_sign_out_export_or_manage_webhooks() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let client = app.register_user("client").await;
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_token = impersonate(&app, &admin, client.id).await;
+    let owner_token = impersonate(&app, &admin, owner_id).await;
+
+    let res = app
+        .post_json(
+            "/payments/initiate",
+            Some(&client_token),
+            json!({ "booking_id": 1, "phone_number": "254700000000", "amount": 100 }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.get("/clients/export", Some(&client_token)).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+
+    // Logging out with the user's refresh token would end their real session
+    let login = app
+        .post_json("/auth/login", None, json!({ "email": client.email, "password": client.password }))
+        .await;
+    let refresh_token = login.body["refresh_token"].as_str().expect("refresh_token").to_string();
+    let res = app.post_json("/auth/logout", Some(&client_token), json!({ "refresh_token": refresh_token })).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.post_json("/auth/refresh", None, json!({ "refresh_token": refresh_token })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let subscription = json!({
+        "target_type": "provider", "target_id": provider_id,
+        "url": "https://partner.example.com/hooks", "event_types": ["booking.created"],
+    });
+    let res = app.post_json("/webhooks", Some(&owner_token), subscription.clone()).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.post_json("/webhooks", Some(&mint_jwt(owner_id)), subscription).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let id = res.body["subscription"]["id"].as_i64().expect("subscription id");
+    let res = app
+        .request(Method::PUT, &format!("/webhooks/{id}"), Some(&owner_token), Some(json!({ "active": false })))
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.request(Method::DELETE, &format!("/webhooks/{id}"), Some(&owner_token), None).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    // Reading is what support is there for
+    let res = app.get(&format!("/webhooks/{id}"), Some(&owner_token)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+}
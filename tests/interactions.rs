@@ -0,0 +1,143 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn kinds(pool: &PgPool, user_id: i32, provider_id: i32) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT interaction_type FROM interactions
+         WHERE user_id = $1 AND target_type = 'provider' AND target_id = $2
+         ORDER BY interaction_type",
+    )
+    .bind(user_id)
+    .bind(provider_id)
+    .fetch_all(pool)
+    .await
+    .expect("read interactions")
+}
+
+#[tokio::test]
+async fn each_way_of_dealing_with_a_listing_is_recorded_once_a_day() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    sqlx::query("UPDATE providers SET approved = TRUE WHERE id = $1")
+        .bind(provider_id)
+        .execute(&app.pool)
+        .await
+        .expect("approve provider");
+    let client_id = seed::client(&app.pool).await;
+    let client = mint_jwt(client_id);
+    let owner = mint_jwt(owner_id);
+    let target = json!({ "target_type": "provider", "target_id": provider_id });
+
+    let message = json!({ "content": "Are you free Friday?", "target_type": "provider", "target_id": provider_id, "receiver_id": owner_id });
+    for _ in 0..2 {
+        let res = app.post_json("/messages/sendMessage", Some(&client), message.clone()).await;
+        assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+        let res = app.post_json("/interactions/contact", Some(&client), target.clone()).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    }
+    let res = app.post_json("/favorites/addFavorite", Some(&client), target.clone()).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app
+        .post_json(
+            "/bookings/createBooking",
+            Some(&client),
+            json!({
+                "target_type": "provider",
+                "target_id": provider_id,
+                "service_description": "Fix the kitchen sink",
+                "scheduled_time": (chrono::Utc::now() + chrono::Duration::days(2)).naive_utc(),
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let booking_id = res.body["booking_id"].as_i64().expect("booking id");
+    assert_eq!(
+        kinds(&app.pool, client_id, provider_id).await,
+        ["booking_created", "contact_click", "favorite", "message"]
+    );
+
+    // Completion only counts once the client confirms it
+    for status in ["confirmed", "pending_confirmation"] {
+        let res = app
+            .post_json(&format!("/bookings/{booking_id}/status"), Some(&owner), json!({ "status": status }))
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    }
+    assert!(!kinds(&app.pool, client_id, provider_id).await.contains(&"booking_completed".to_string()));
+    let res = app
+        .post_json(&format!("/bookings/{booking_id}/status"), Some(&client), json!({ "status": "completed" }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        kinds(&app.pool, client_id, provider_id).await,
+        ["booking_completed", "booking_created", "contact_click", "favorite", "message"]
+    );
+
+    let res = app.get("/interactions/mine?target_type=provider", Some(&client)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total"], 1);
+    assert_eq!(res.body["items"][0]["target_id"], provider_id);
+    assert_eq!(res.body["items"][0]["interaction_types"].as_array().map(Vec::len), Some(5));
+    let res = app.get("/interactions/mine?target_type=business", Some(&client)).await;
+    assert_eq!(res.body["total"], 0);
+    let res = app.get("/interactions/mine", Some(&owner)).await;
+    assert_eq!(res.body["total"], 0);
+
+    let res = app
+        .post_json("/interactions/contact", Some(&client), json!({ "target_type": "provider", "target_id": 987_654 }))
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn reviews_need_a_recorded_completed_booking() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let client = mint_jwt(client_id);
+    let review = json!({ "comment": "Tidy work", "rating": 5 });
+    let uri = format!("/reviews/createReviews?target_type=provider&target_id={provider_id}");
+
+    // Messaging and contacting aren't enough
+    let res = app
+        .post_json("/interactions/contact", Some(&client), json!({ "target_type": "provider", "target_id": provider_id }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.post_json(&uri, Some(&client), review.clone()).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let res = app.get("/interactions/me", Some(&client)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["items"][0]["can_review"], false);
+    assert_eq!(app.get("/interactions/me?can_review=true", Some(&client)).await.body["total"], 0);
+
+    sqlx::query(
+        "INSERT INTO interactions (user_id, target_type, target_id, interaction_type)
+         VALUES ($1, 'provider', $2, 'booking_completed')",
+    )
+    .bind(client_id)
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("record completed booking");
+    let res = app.get("/interactions/me?can_review=true", Some(&client)).await;
+    assert_eq!(res.body["total"], 1, "{}", res.body);
+    assert_eq!(res.body["items"][0]["target_id"], provider_id);
+    let res = app.post_json(&uri, Some(&client), review).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    // Reviewed once, so no longer offered
+    let res = app.get("/interactions/me?can_review=true", Some(&client)).await;
+    assert_eq!(res.body["total"], 0, "{}", res.body);
+    assert_eq!(app.get("/interactions/me", Some(&client)).await.body["total"], 1);
+    // No booking row to link, so it carries no verified badge
+    let verified: Option<i32> = sqlx::query_scalar("SELECT verified_booking_id FROM reviews WHERE reviewer_id = $1")
+        .bind(client_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("read review");
+    assert_eq!(verified, None);
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use common::{TestApp, seed};
+use mtaalink::utils::booking_expiry::expire_pending_bookings;
+use mtaalink::utils::jobs::{self, JobDef, JobReport, JobTriggers};
+use mtaalink::utils::password_resets::delete_expired_resets;
+use mtaalink::utils::ws_state::new_ws_connections;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+static QUICK_JOB: JobDef = JobDef {
+    name: "quick_test_job",
+    interval: Duration::from_millis(20),
+};
+
+async fn run_count(pool: &PgPool, job: &JobDef) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM job_runs WHERE job_name = $1 AND finished_at IS NOT NULL")
+        .bind(job.name)
+        .fetch_one(pool)
+        .await
+        .expect("count job runs")
+}
+
+async fn backdate_booking(pool: &PgPool, booking_id: i32) {
+    sqlx::query("UPDATE bookings SET scheduled_time = scheduled_time - INTERVAL '2 days' WHERE id = $1")
+        .bind(booking_id)
+        .execute(pool)
+        .await
+        .expect("backdate booking");
+}
+
+async fn status_of(pool: &PgPool, booking_id: i32) -> String {
+    sqlx::query_scalar("SELECT status FROM bookings WHERE id = $1")
+        .bind(booking_id)
+        .fetch_one(pool)
+        .await
+        .expect("booking status")
+}
+
+#[tokio::test]
+async fn a_job_loop_runs_every_interval_and_records_each_pass() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let mut triggers = JobTriggers::new();
+    let mut trigger = triggers.register(&QUICK_JOB);
+    let pool = app.pool.clone();
+    let handle = tokio::spawn(async move {
+        while jobs::wait_for_next_run(&QUICK_JOB, &mut trigger).await {
+            jobs::record_run(&pool, &QUICK_JOB, async {
+                let mut report = JobReport::default();
+                report.items_processed = 2;
+                report
+            })
+            .await;
+        }
+    });
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while run_count(&app.pool, &QUICK_JOB).await < 3 {
+        assert!(tokio::time::Instant::now() < deadline, "job did not run three times");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    triggers.stop();
+    handle.await.expect("job loop");
+
+    let (items, errors): (i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(items_processed), 0)::bigint, COUNT(error) FROM job_runs WHERE job_name = $1",
+    )
+    .bind(QUICK_JOB.name)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(items >= 6);
+    assert_eq!(errors, 0);
+}
+
+#[tokio::test]
+async fn a_failed_pass_is_recorded_with_its_error() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let ran = jobs::record_run(&app.pool, &QUICK_JOB, async {
+        let mut report = JobReport::default();
+        report.fail("Something broke", "disk full");
+        report
+    })
+    .await;
+
+    assert!(ran);
+    let error: Option<String> = sqlx::query_scalar("SELECT error FROM job_runs WHERE job_name = $1")
+        .bind(QUICK_JOB.name)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(error.as_deref(), Some("Something broke: disk full"));
+}
+
+#[tokio::test]
+async fn a_job_already_running_elsewhere_is_skipped() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (started_tx, started_rx) = oneshot::channel();
+    let (release_tx, release_rx) = oneshot::channel::<()>();
+
+    // Stands in for another instance, holding the lock mid-pass
+    let pool = app.pool.clone();
+    let first = tokio::spawn(async move {
+        jobs::record_run(&pool, &QUICK_JOB, async {
+            started_tx.send(()).unwrap();
+            release_rx.await.ok();
+            JobReport::default()
+        })
+        .await
+    });
+    started_rx.await.unwrap();
+
+    let second = jobs::record_run(&app.pool, &QUICK_JOB, async {
+        panic!("the pass should not run while another instance holds the lock")
+    })
+    .await;
+    assert!(!second);
+
+    release_tx.send(()).unwrap();
+    assert!(first.await.unwrap());
+    assert_eq!(run_count(&app.pool, &QUICK_JOB).await, 1);
+
+    // The lock is released with the first run
+    assert!(jobs::record_run(&app.pool, &QUICK_JOB, async { JobReport::default() }).await);
+}
+
+#[tokio::test]
+async fn password_reset_cleanup_deletes_only_expired_tokens() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let expired_user = seed::client(&app.pool).await;
+    let valid_user = seed::client(&app.pool).await;
+    sqlx::query(
+        "INSERT INTO password_resets (user_id, token, expires_at) VALUES
+            ($1, 'expired-token', (NOW() AT TIME ZONE 'UTC') - INTERVAL '1 hour'),
+            ($2, 'valid-token', (NOW() AT TIME ZONE 'UTC') + INTERVAL '10 minutes')",
+    )
+    .bind(expired_user)
+    .bind(valid_user)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let report = delete_expired_resets(&app.pool).await;
+
+    assert_eq!(report.items_processed, 1);
+    let left: Vec<String> = sqlx::query_scalar("SELECT token FROM password_resets")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(left, vec!["valid-token".to_string()]);
+}
+
+#[tokio::test]
+async fn booking_expiry_cancels_past_pending_bookings_and_tells_both_sides() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let client_id = seed::client(&app.pool).await;
+    let (provider_user_id, provider_id) = seed::provider(&app.pool).await;
+    let stale = seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    let upcoming = seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    let past_confirmed = seed::booking(&app.pool, client_id, "provider", provider_id, None, "confirmed").await;
+    backdate_booking(&app.pool, stale).await;
+    backdate_booking(&app.pool, past_confirmed).await;
+
+    let report = expire_pending_bookings(&app.pool, &new_ws_connections()).await;
+
+    assert_eq!(report.items_processed, 1);
+    assert_eq!(status_of(&app.pool, stale).await, "cancelled");
+    assert_eq!(status_of(&app.pool, upcoming).await, "pending");
+    assert_eq!(status_of(&app.pool, past_confirmed).await, "confirmed");
+
+    let (from, to): (Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT from_status, to_status FROM booking_events
+         WHERE booking_id = $1 AND event_type = 'status_changed'",
+    )
+    .bind(stale)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!((from.as_deref(), to.as_deref()), (Some("pending"), Some("cancelled")));
+
+    let notified: Vec<i32> = sqlx::query_scalar(
+        "SELECT user_id FROM notifications WHERE notif_type = 'booking_expired' AND target_id = $1 ORDER BY user_id",
+    )
+    .bind(stale)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    let mut expected = vec![client_id, provider_user_id];
+    expected.sort();
+    assert_eq!(notified, expected);
+}
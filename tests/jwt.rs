@@ -0,0 +1,91 @@
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{EncodingKey, Header, decode_header, encode};
+use mtaalink::utils::jwt::{Claims, JwtKeys, JwtScope, is_wrong_environment};
+
+const OLD: &str = "old-secret-that-is-at-least-32-characters-long";
+const NEW: &str = "new-secret-that-is-at-least-32-characters-long";
+
+fn keys(secrets: &[&str]) -> JwtKeys {
+    JwtKeys::new(&secrets.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+}
+
+fn claims(sub: &str) -> Claims {
+    let exp = (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize;
+    Claims { sub: sub.to_string(), exp, iss: None, aud: None, impersonator: None, role: None }
+}
+
+fn scoped(audience: &str, accept_unscoped: bool) -> JwtKeys {
+    keys(&[NEW]).with_scope(JwtScope {
+        issuer: "mtaalink-api".to_string(),
+        audience: audience.to_string(),
+        accept_unscoped,
+    })
+}
+
+#[test]
+fn old_tokens_verify_while_their_key_is_listed_and_fail_once_removed() {
+    let token = keys(&[OLD]).sign(&claims("7")).expect("sign");
+
+    // Overlap window: NEW signs, OLD still verifies
+    let rotating = keys(&[NEW, OLD]);
+    assert_eq!(rotating.verify(&token).expect("old token verifies").sub, "7");
+    let fresh = rotating.sign(&claims("8")).expect("sign");
+    assert_ne!(decode_header(&fresh).expect("header").kid, decode_header(&token).expect("header").kid);
+    assert_eq!(keys(&[NEW]).verify(&fresh).expect("new token verifies").sub, "8");
+
+    // OLD retired
+    assert!(keys(&[NEW]).verify(&token).is_err());
+}
+
+#[test]
+fn tokens_without_a_kid_are_checked_against_every_key() {
+    let legacy = encode(&Header::default(), &claims("9"), &EncodingKey::from_secret(OLD.as_bytes())).expect("sign");
+
+    assert_eq!(keys(&[NEW, OLD]).verify(&legacy).expect("legacy token verifies").sub, "9");
+    assert!(keys(&[NEW]).verify(&legacy).is_err());
+}
+
+#[test]
+fn a_token_signed_with_an_unlisted_key_is_rejected() {
+    let forged = keys(&["some-other-secret-that-is-long-enough!!"]).sign(&claims("1")).expect("sign");
+    assert!(keys(&[NEW, OLD]).verify(&forged).is_err());
+}
+
+#[test]
+fn during_the_grace_period_unscoped_tokens_still_verify() {
+    let legacy = keys(&[NEW]).sign(&claims("3")).expect("sign");
+    let prod = scoped("mtaalink-prod", true);
+    assert_eq!(prod.verify(&legacy).expect("unscoped token verifies").sub, "3");
+
+    let fresh = prod.sign(&claims("4")).expect("sign");
+    let verified = prod.verify(&fresh).expect("scoped token verifies");
+    assert_eq!(verified.iss.as_deref(), Some("mtaalink-api"));
+    assert_eq!(verified.aud.as_deref(), Some("mtaalink-prod"));
+}
+
+#[test]
+fn tokens_for_another_environment_are_always_rejected() {
+    let staging_token = scoped("mtaalink-staging", true).sign(&claims("5")).expect("sign");
+    for accept_unscoped in [true, false] {
+        let err = scoped("mtaalink-prod", accept_unscoped).verify(&staging_token).expect_err("audience differs");
+        assert_eq!(*err.kind(), ErrorKind::InvalidAudience);
+        assert!(is_wrong_environment(&err));
+    }
+
+    let other_issuer = Claims { iss: Some("someone-else".to_string()), aud: Some("mtaalink-prod".to_string()), ..claims("5") };
+    let token = keys(&[NEW]).sign(&other_issuer).expect("sign");
+    let err = scoped("mtaalink-prod", true).verify(&token).expect_err("issuer differs");
+    assert!(is_wrong_environment(&err));
+}
+
+#[test]
+fn after_the_grace_period_unscoped_tokens_are_rejected() {
+    let prod = scoped("mtaalink-prod", false);
+    let legacy = keys(&[NEW]).sign(&claims("6")).expect("sign");
+    let err = prod.verify(&legacy).expect_err("no iss or aud");
+    assert!(matches!(err.kind(), ErrorKind::MissingRequiredClaim(_)));
+    assert!(!is_wrong_environment(&err));
+
+    let fresh = prod.sign(&claims("6")).expect("sign");
+    assert_eq!(prod.verify(&fresh).expect("scoped token verifies").sub, "6");
+}
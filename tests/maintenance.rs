@@ -0,0 +1,84 @@
+mod common;
+
+use axum::http::{StatusCode, header};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+
+/// A write that changes nothing when it gets through: contacting a listing
+/// that doesn't exist is a 404.
+fn probe() -> Value {
+    json!({ "target_type": "provider", "target_id": 987_654 })
+}
+
+#[tokio::test]
+async fn maintenance_mode_refuses_writes_but_not_reads_or_admin() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    let res = app.get("/admin/maintenance", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["read_only"], false);
+    assert_eq!(res.body["source"], "setting");
+
+    let res = app.post_json("/admin/maintenance", Some(&admin), json!({ "enabled": true })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["read_only"], true);
+
+    for uri in ["/interactions/contact", "/api/v1/interactions/contact"] {
+        let res = app.post_json(uri, Some(&client), probe()).await;
+        assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE, "{uri}: {}", res.body);
+        assert_eq!(res.body["code"], "maintenance");
+        assert_eq!(res.headers[header::RETRY_AFTER], "120");
+    }
+    let res = app.request(axum::http::Method::DELETE, "/notifications/1", Some(&client), None).await;
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // Reads, health checks and admin routes carry on
+    assert_eq!(app.get("/auth/me", Some(&client)).await.status, StatusCode::OK);
+    assert_eq!(app.get("/healthz", None).await.status, StatusCode::OK);
+    let res = app.post_json("/api/v1/admin/settings", Some(&admin), json!({ "admin.export_row_cap": 500 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.post_json("/admin/maintenance", Some(&admin), json!({ "enabled": false })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.post_json("/interactions/contact", Some(&client), probe()).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+
+    let audited: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE action = 'maintenance_toggled'")
+        .fetch_one(&app.pool)
+        .await
+        .expect("count audit entries");
+    assert_eq!(audited, 2);
+}
+
+#[tokio::test]
+async fn a_toggle_from_another_instance_takes_effect_within_seconds() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    // Primes this instance's cache with "off"
+    let res = app.post_json("/interactions/contact", Some(&client), probe()).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+
+    // What another instance's POST /admin/maintenance writes
+    sqlx::query(
+        "INSERT INTO platform_settings (key, value) VALUES ('maintenance.read_only', '1')
+         ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value",
+    )
+    .execute(&app.pool)
+    .await
+    .expect("turn maintenance on");
+
+    let started = Instant::now();
+    loop {
+        let res = app.post_json("/interactions/contact", Some(&client), probe()).await;
+        if res.status == StatusCode::SERVICE_UNAVAILABLE {
+            break;
+        }
+        assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+        assert!(started.elapsed() < Duration::from_secs(10), "maintenance mode never took effect");
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use sqlx::PgPool;
+
+async fn message(pool: &PgPool, from: i32, to: i32, provider_id: i32, content: &str, minutes_ago: i32) {
+    sqlx::query(
+        "INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content, created_at)
+         VALUES ($1, $2, 'provider', $3, $4, NOW() - make_interval(mins => $5))",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(provider_id)
+    .bind(content)
+    .bind(minutes_ago)
+    .execute(pool)
+    .await
+    .expect("seed message");
+}
+
+#[tokio::test]
+async fn conversations_list_one_row_per_counterpart_by_latest_activity() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (alice, bob, carol) = (seed::client(&app.pool).await, seed::client(&app.pool).await, seed::client(&app.pool).await);
+
+    message(&app.pool, alice, owner_id, provider_id, "Are you free Friday?", 60).await;
+    message(&app.pool, owner_id, alice, provider_id, "Yes, from 10", 50).await;
+    message(&app.pool, bob, owner_id, provider_id, "How much for a tap?", 40).await;
+    message(&app.pool, bob, owner_id, provider_id, "Hello?", 30).await;
+    message(&app.pool, carol, owner_id, provider_id, "Thanks!", 45).await;
+    // Not the owner's conversation
+    message(&app.pool, alice, bob, provider_id, "Do you know a plumber?", 1).await;
+
+    let owner = mint_jwt(owner_id);
+    let res = app.get("/messages/conversations", Some(&owner)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total"], 3);
+    let rows = res.body["items"].as_array().expect("items");
+    let counterparts: Vec<i64> = rows.iter().map(|r| r["other_user_id"].as_i64().unwrap()).collect();
+    assert_eq!(counterparts, [i64::from(bob), i64::from(carol), i64::from(alice)]);
+    assert_eq!(rows[0]["last_message"], "Hello?");
+    assert_eq!(rows[0]["unread_count"], 2);
+    assert!(rows[0]["other_username"].is_string());
+    assert_eq!(rows[2]["last_message"], "Yes, from 10");
+    assert_eq!(rows[2]["unread_count"], 1);
+
+    let res = app.get("/messages/conversations?page=2&per_page=2", Some(&owner)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["items"].as_array().expect("items").len(), 1);
+    assert_eq!(res.body["items"][0]["other_user_id"], alice);
+    assert_eq!(res.body["has_more"], false);
+
+    let res = app.get("/messages/conversations", Some(&mint_jwt(alice))).await;
+    assert_eq!(res.body["total"], 2);
+    assert_eq!(res.body["items"][0]["other_user_id"], bob);
+
+    assert_eq!(app.get("/messages/conversations", None).await.status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{TestApp, seed};
+use sqlx::Executor;
+
+/// Migrations are written to be rerun safely, so one can be replayed over
+/// rows that predate the constraint it adds.
+const POST_REPORTS: &str = include_str!("../migrations/0023_post_reports.sql");
+const POST_SLUGS: &str = include_str!("../migrations/0066_post_slugs.sql");
+
+#[tokio::test]
+async fn duplicate_reports_are_merged_into_the_earliest_one() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let (repeat, once) = (seed::client(&app.pool).await, seed::client(&app.pool).await);
+    let post_id: i32 = sqlx::query_scalar("INSERT INTO posts (provider_id, title, content) VALUES ($1, 'Open', 'Open today') RETURNING id")
+        .bind(provider_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("seed post");
+
+    app.pool
+        .execute("DROP INDEX idx_content_flags_one_per_reporter")
+        .await
+        .expect("drop unique index");
+    let first: i32 = sqlx::query_scalar(
+        "INSERT INTO content_flags (target_type, target_id, reason, flagged_by, resolved)
+         VALUES ('post', $1, 'spam', $2, TRUE),
+                ('post', $1, 'scam', $2, FALSE),
+                ('post', $1, 'spam', $2, TRUE),
+                ('post', $1, 'rude', $3, TRUE),
+                ('post', $1, 'admin note', NULL, FALSE),
+                ('post', $1, 'admin note', NULL, FALSE)
+         RETURNING id",
+    )
+    .bind(post_id)
+    .bind(repeat)
+    .bind(once)
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed reports");
+
+    app.pool.execute(POST_REPORTS).await.expect("replay migration");
+
+    let rows: Vec<(i32, Option<i32>, String, bool)> =
+        sqlx::query_as("SELECT id, flagged_by, reason, resolved FROM content_flags ORDER BY id")
+            .fetch_all(&app.pool)
+            .await
+            .expect("reports");
+    assert_eq!(rows.len(), 4, "{rows:?}");
+    assert_eq!(rows[0], (first, Some(repeat), "scam; spam".to_string(), false));
+    assert_eq!((rows[1].1, rows[1].2.as_str(), rows[1].3), (Some(once), "rude", true));
+    // Admin-raised flags have no reporter and are left alone
+    assert!(rows[2..].iter().all(|r| r.1.is_none()));
+
+    let index: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pg_indexes WHERE indexname = 'idx_content_flags_one_per_reporter')",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .expect("look up index");
+    assert!(index);
+}
+
+#[tokio::test]
+async fn posts_from_before_slugs_are_backfilled_with_unique_ones() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    app.pool
+        .execute("DROP INDEX idx_posts_slug; ALTER TABLE posts ALTER COLUMN slug DROP NOT NULL")
+        .await
+        .expect("undo slug constraints");
+    let ids: Vec<i32> = sqlx::query_scalar(
+        "INSERT INTO posts (provider_id, title, content)
+         VALUES ($1, '  Fundi''s Tips & Tricks!  ', ''), ($1, 'Fundi''s tips & tricks', ''), ($1, NULL, ''), ($1, '¡¡¡', '')
+         RETURNING id",
+    )
+    .bind(provider_id)
+    .fetch_all(&app.pool)
+    .await
+    .expect("seed posts");
+    app.pool.execute("UPDATE posts SET slug = NULL").await.expect("clear slugs");
+
+    app.pool.execute(POST_SLUGS).await.expect("replay migration");
+
+    let slugs: Vec<String> = sqlx::query_scalar("SELECT slug FROM posts ORDER BY id")
+        .fetch_all(&app.pool)
+        .await
+        .expect("slugs");
+    assert_eq!(
+        slugs,
+        [
+            format!("fundi-s-tips-tricks-{}", ids[0]),
+            format!("fundi-s-tips-tricks-{}", ids[1]),
+            format!("post-{}", ids[2]),
+            format!("post-{}", ids[3]),
+        ]
+    );
+    let duplicate = sqlx::query("UPDATE posts SET slug = $1 WHERE id = $2")
+        .bind(&slugs[0])
+        .bind(ids[1])
+        .execute(&app.pool)
+        .await;
+    assert!(duplicate.is_err(), "slugs must stay unique");
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+
+/// Ids well past anything a fresh test database hands out.
+const MISSING_ID: i32 = 987_654;
+
+fn assert_not_found(res: &common::TestResponse, message: &str) {
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+    assert_eq!(res.body, json!({ "code": "not_found", "message": message }));
+}
+
+#[tokio::test]
+async fn missing_bookings_and_posts_are_404s() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let client = mint_jwt(seed::client(&app.pool).await);
+
+    let res = app.get(&format!("/bookings/{MISSING_ID}"), Some(&client)).await;
+    assert_not_found(&res, "Booking not found");
+
+    let res = app.get(&format!("/posts/getPost/{MISSING_ID}"), None).await;
+    assert_not_found(&res, "Post not found");
+    let res = app.post_json(&format!("/posts/{MISSING_ID}/comments"), Some(&client), json!({ "comment": "Hi" })).await;
+    assert_not_found(&res, "Post not found");
+}
+
+#[tokio::test]
+async fn missing_locations_are_404s_not_403s() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (provider_user, _) = seed::provider(&app.pool).await;
+    let provider = mint_jwt(provider_user);
+
+    let res = app.get(&format!("/locations/providers/location/{MISSING_ID}"), Some(&provider)).await;
+    assert_not_found(&res, "Provider location not found");
+    let res = app
+        .post_json(&format!("/locations/providers/location/{MISSING_ID}/update"), Some(&provider), json!({ "address": "Moi Avenue" }))
+        .await;
+    assert_not_found(&res, "Provider location not found");
+    let res = app
+        .request(Method::POST, &format!("/locations/branches/location/{MISSING_ID}/delete"), Some(&provider), None)
+        .await;
+    assert_not_found(&res, "Branch location not found");
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use mtaalink::utils::notifications::{self, NewNotification};
+use mtaalink::utils::ws_state::new_ws_connections;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+
+async fn notify(pool: &PgPool, user_id: i32, title: &str) -> i32 {
+    let new = NewNotification {
+        notif_type: "booking_reminder",
+        title,
+        body: "Something happened",
+        target_type: Some("booking"),
+        target_id: Some(7),
+        data: None,
+    };
+    notifications::create(pool, None, user_id, &new)
+        .await
+        .expect("create notification")
+        .id
+}
+
+fn ids(body: &Value) -> Vec<i64> {
+    body["items"]
+        .as_array()
+        .expect("items")
+        .iter()
+        .map(|n| n["id"].as_i64().expect("id"))
+        .collect()
+}
+
+#[tokio::test]
+async fn list_is_paginated_newest_first() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    let token = mint_jwt(user_id);
+    let first = notify(&app.pool, user_id, "First").await;
+    let second = notify(&app.pool, user_id, "Second").await;
+    let third = notify(&app.pool, user_id, "Third").await;
+
+    let page1 = app.get("/notifications?per_page=2", Some(&token)).await;
+    let page2 = app.get("/notifications?per_page=2&page=2", Some(&token)).await;
+
+    assert_eq!(page1.status, StatusCode::OK, "{}", page1.body);
+    assert_eq!(ids(&page1.body), vec![third as i64, second as i64]);
+    assert_eq!(page1.body["total"], 3);
+    assert_eq!(page1.body["has_more"], true);
+    assert_eq!(ids(&page2.body), vec![first as i64]);
+    assert_eq!(page2.body["has_more"], false);
+    assert_eq!(page1.body["items"][0]["is_read"], false);
+    assert_eq!(page1.body["items"][0]["target_type"], "booking");
+}
+
+#[tokio::test]
+async fn unread_filter_and_count_skip_read_notifications() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    let token = mint_jwt(user_id);
+    let read = notify(&app.pool, user_id, "Read").await;
+    let unread = notify(&app.pool, user_id, "Unread").await;
+
+    let res = app.post_json("/notifications/markRead", Some(&token), json!({ "ids": [read] })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["updated"], 1);
+
+    let list = app.get("/notifications?unread_only=true", Some(&token)).await;
+    assert_eq!(ids(&list.body), vec![unread as i64]);
+    assert_eq!(list.body["total"], 1);
+
+    let all = app.get("/notifications", Some(&token)).await;
+    let read_row = all.body["items"].as_array().unwrap().iter().find(|n| n["id"] == read).unwrap().clone();
+    assert_eq!(read_row["is_read"], true);
+    assert!(read_row["read_at"].is_string());
+
+    let count = app.get("/notifications/unread-count", Some(&token)).await;
+    assert_eq!(count.body["unread_count"], 1);
+}
+
+#[tokio::test]
+async fn mark_read_leaves_other_users_notifications_alone() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let me = seed::client(&app.pool).await;
+    let someone_else = seed::client(&app.pool).await;
+    let mine = notify(&app.pool, me, "Mine").await;
+    let theirs = notify(&app.pool, someone_else, "Theirs").await;
+
+    let res = app
+        .post_json("/notifications/markRead", Some(&mint_jwt(me)), json!({ "ids": [mine, theirs] }))
+        .await;
+
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["updated"], 1);
+    let count = app.get("/notifications/unread-count", Some(&mint_jwt(someone_else))).await;
+    assert_eq!(count.body["unread_count"], 1);
+}
+
+#[tokio::test]
+async fn mark_read_needs_at_least_one_id() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+
+    let res = app
+        .post_json("/notifications/markRead", Some(&mint_jwt(user_id)), json!({ "ids": [] }))
+        .await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn create_pushes_to_a_connected_user() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    let ws_conns = new_ws_connections();
+    let (sender, mut receiver) = broadcast::channel(8);
+    ws_conns.write().await.insert(user_id, sender);
+
+    let new = NewNotification {
+        notif_type: "account_approved",
+        title: "Approved",
+        body: "You're live",
+        data: Some(json!({ "listing": "provider" })),
+        ..Default::default()
+    };
+    let created = notifications::create(&app.pool, Some(&ws_conns), user_id, &new)
+        .await
+        .expect("create notification");
+
+    let event: Value = serde_json::from_str(&receiver.try_recv().expect("pushed event")).unwrap();
+    assert_eq!(event["event"], "notification");
+    assert_eq!(event["data"]["id"], created.id);
+    assert_eq!(event["data"]["title"], "Approved");
+    assert_eq!(event["data"]["data"]["listing"], "provider");
+    assert_eq!(event["data"]["is_read"], false);
+}
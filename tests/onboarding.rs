@@ -0,0 +1,136 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::{Value, json};
+
+fn provider_profile() -> Value {
+    json!({
+        "service_name": "Kamau Plumbing",
+        "service_description": "Fixes pipes and taps",
+        "category": "Plumbing",
+        "location": "Nairobi",
+        "phone_number": "0712345678",
+        "email": "kamau@example.com",
+    })
+}
+
+fn business_profile(krapin: &str) -> Value {
+    json!({
+        "business_name": "Wanjiru Salon",
+        "description": "Hair, nails and makeup",
+        "category": "Beauty",
+        "location": "Nakuru",
+        "license_number": "LIC-001",
+        "krapin": krapin,
+        "phone_number": "0712345678",
+        "email": "salon@example.com",
+    })
+}
+
+#[tokio::test]
+async fn a_provider_without_a_profile_onboards_and_can_onboard_again() {
+    let Some(app) = TestApp::spawn().await else { return };
+    // An account whose profile row was never created
+    let user_id = seed::user(&app.pool, "provider").await;
+    let token = mint_jwt(user_id);
+
+    let res = app.post_json("/service_providers/onboard", Some(&token), provider_profile()).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    assert_eq!(res.body["created"], true);
+    assert_eq!(res.body["listing_status"], "approved");
+    let provider_id = res.body["provider_id"].as_i64().expect("provider id");
+    let (owner, completed, name): (i32, bool, String) =
+        sqlx::query_as("SELECT user_id, onboarding_completed, service_name FROM providers WHERE id = $1")
+            .bind(provider_id as i32)
+            .fetch_one(&app.pool)
+            .await
+            .expect("read provider");
+    assert_eq!((owner, completed, name.as_str()), (user_id, true, "Kamau Plumbing"));
+
+    let mut changed = provider_profile();
+    changed["service_name"] = "Kamau & Sons Plumbing".into();
+    let res = app.post_json("/service_providers/onboard", Some(&token), changed).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["created"], false);
+    assert_eq!(res.body["provider_id"], provider_id);
+    let rows: Vec<String> = sqlx::query_scalar("SELECT service_name FROM providers WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(&app.pool)
+        .await
+        .expect("read providers");
+    assert_eq!(rows, ["Kamau & Sons Plumbing"]);
+
+    // A registered provider already has a bare row; onboarding fills it in
+    let registered = app.register_user("provider").await;
+    let res = app.post_json("/service_providers/onboard", Some(&registered.token), provider_profile()).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["created"], false);
+}
+
+#[tokio::test]
+async fn provider_onboarding_rejects_bad_payloads_and_other_roles() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let token = mint_jwt(seed::user(&app.pool, "provider").await);
+
+    let mut invalid = provider_profile();
+    invalid["service_name"] = "ab".into();
+    invalid["email"] = "not-an-email".into();
+    let res = app.post_json("/service_providers/onboard", Some(&token), invalid).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    assert_eq!(res.body["code"], "validation_failed");
+    assert!(res.body["details"]["fields"]["service_name"].is_array(), "{}", res.body);
+    assert!(res.body["details"]["fields"]["email"].is_array(), "{}", res.body);
+
+    let client = mint_jwt(seed::client(&app.pool).await);
+    let res = app.post_json("/service_providers/onboard", Some(&client), provider_profile()).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    let providers: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM providers")
+        .fetch_one(&app.pool)
+        .await
+        .expect("count providers");
+    assert_eq!(providers, 0);
+}
+
+#[tokio::test]
+async fn a_business_without_a_profile_onboards_and_can_onboard_again() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::user(&app.pool, "business").await;
+    let token = mint_jwt(user_id);
+
+    let res = app.post_json("/businesses/onboard", Some(&token), business_profile("A012345678Z")).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    assert_eq!(res.body["created"], true);
+    let business_id = res.body["business_id"].as_i64().expect("business id");
+
+    let mut changed = business_profile("A012345678Z");
+    changed["location"] = "Naivasha".into();
+    let res = app.post_json("/businesses/onboard", Some(&token), changed).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["created"], false);
+    assert_eq!(res.body["business_id"], business_id);
+    let location: String = sqlx::query_scalar("SELECT location FROM businesses WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("read business");
+    assert_eq!(location, "Naivasha");
+
+    // A KRA PIN belongs to one business
+    let other = mint_jwt(seed::user(&app.pool, "business").await);
+    let res = app.post_json("/businesses/onboard", Some(&other), business_profile("A012345678Z")).await;
+    assert_eq!(res.status, StatusCode::CONFLICT, "{}", res.body);
+
+    let mut invalid = business_profile("short");
+    invalid["description"] = "Too short".into();
+    let res = app.post_json("/businesses/onboard", Some(&other), invalid).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    assert!(res.body["details"]["fields"]["krapin"].is_array(), "{}", res.body);
+    assert!(res.body["details"]["fields"]["description"].is_array(), "{}", res.body);
+
+    let (provider_user, _) = seed::provider(&app.pool).await;
+    let res = app
+        .post_json("/businesses/onboard", Some(&mint_jwt(provider_user)), business_profile("P098765432Q"))
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use axum::http::{StatusCode, header};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::Value;
+
+async fn favorite(app: &TestApp, user_id: i32, target_type: &str, target_id: i32) {
+    sqlx::query("INSERT INTO favorites (user_id, target_type, target_id) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(target_type)
+        .bind(target_id)
+        .execute(&app.pool)
+        .await
+        .expect("seed favorite");
+}
+
+#[tokio::test]
+async fn listings_add_is_favorited_only_for_signed_in_callers() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let (_, business_id) = seed::business(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    favorite(&app, client_id, "provider", provider_id).await;
+    let token = mint_jwt(client_id);
+
+    let anon = app.get("/service_providers/listProviders", None).await;
+    assert_eq!(anon.status, StatusCode::OK, "{}", anon.body);
+    assert!(anon.body["items"][0].get("is_favorited").is_none());
+    assert_eq!(anon.headers[header::CACHE_CONTROL], "public, no-cache");
+
+    let mine = app.get("/service_providers/listProviders", Some(&token)).await;
+    assert_eq!(mine.body["items"][0]["is_favorited"], true);
+    // Personal fields keep the response out of shared caches
+    assert_eq!(mine.headers[header::CACHE_CONTROL], "private, no-cache");
+    assert_eq!(mine.headers[header::VARY], "Authorization");
+
+    let profile = app.get(&format!("/service_providers/{provider_id}"), Some(&token)).await;
+    assert_eq!(profile.body["provider"]["is_favorited"], true);
+    let profile = app.get(&format!("/businesses/{business_id}"), Some(&token)).await;
+    assert_eq!(profile.body["business"]["is_favorited"], false);
+
+    // Businesses are browsable without signing in
+    let anon = app.get("/businesses/listBusinesses", None).await;
+    assert_eq!(anon.status, StatusCode::OK, "{}", anon.body);
+    assert!(anon.body["items"][0].get("is_favorited").is_none());
+    let mine = app.get("/businesses/listBusinesses", Some(&token)).await;
+    assert_eq!(mine.body["items"][0]["is_favorited"], false);
+}
+
+#[tokio::test]
+async fn posts_say_whether_the_caller_liked_them() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let fan = seed::client(&app.pool).await;
+    let other = seed::client(&app.pool).await;
+    let post_id: i32 =
+        sqlx::query_scalar("INSERT INTO posts (provider_id, title, content) VALUES ($1, 'Open', 'Open today') RETURNING id")
+            .bind(provider_id)
+            .fetch_one(&app.pool)
+            .await
+            .expect("seed post");
+    sqlx::query("INSERT INTO post_likes (user_id, post_id) VALUES ($1, $2)")
+        .bind(fan)
+        .bind(post_id)
+        .execute(&app.pool)
+        .await
+        .expect("seed like");
+
+    let liked = |body: &Value| body["items"][0].get("liked_by_me").cloned();
+    assert_eq!(liked(&app.get("/posts/getAllPosts", None).await.body), None);
+    assert_eq!(liked(&app.get("/posts/getAllPosts", Some(&mint_jwt(fan))).await.body), Some(Value::Bool(true)));
+    assert_eq!(liked(&app.get("/posts/getAllPosts", Some(&mint_jwt(other))).await.body), Some(Value::Bool(false)));
+
+    let res = app.get(&format!("/posts/getPost/{post_id}"), Some(&mint_jwt(fan))).await;
+    assert_eq!(res.body["post"]["liked_by_me"], true);
+    assert_eq!(res.body["post"]["like_count"], 1);
+}
+
+#[tokio::test]
+async fn a_bad_token_on_a_public_endpoint_is_a_401_not_an_anonymous_view() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (_, business_id) = seed::business(&app.pool).await;
+    let deleted = seed::client(&app.pool).await;
+    sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1")
+        .bind(deleted)
+        .execute(&app.pool)
+        .await
+        .expect("delete user");
+
+    for path in [
+        "/service_providers/listProviders".to_string(),
+        format!("/service_providers/{provider_id}"),
+        "/businesses/listBusinesses".to_string(),
+        format!("/businesses/{business_id}"),
+        "/posts/getAllPosts".to_string(),
+        "/announcements/active".to_string(),
+        format!("/attachments/getAttachments?target_type=provider&target_id={provider_id}"),
+    ] {
+        let expired = app.get(&path, Some("not-a-jwt")).await;
+        assert_eq!(expired.status, StatusCode::UNAUTHORIZED, "{path}");
+        assert_eq!(expired.body["code"], "unauthorized", "{path}");
+
+        let gone = app.get(&path, Some(&mint_jwt(deleted))).await;
+        assert_eq!(gone.status, StatusCode::UNAUTHORIZED, "{path}");
+
+        assert_eq!(app.get(&path, None).await.status, StatusCode::OK, "{path}");
+    }
+
+    // The same holds where signing in only changes what is counted or allowed
+    let post_id: i32 =
+        sqlx::query_scalar("INSERT INTO posts (provider_id, title, content) VALUES ($1, 'Open', 'Open today') RETURNING id")
+            .bind(provider_id)
+            .fetch_one(&app.pool)
+            .await
+            .expect("seed post");
+    let view = format!("/posts/{post_id}/view");
+    let res = app.post_json(&view, Some("not-a-jwt"), serde_json::json!({})).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+    assert_eq!(app.post_json(&view, None, serde_json::json!({})).await.status, StatusCode::OK);
+
+    let attachment_id: i32 = sqlx::query_scalar(
+        "INSERT INTO attachments (file_name, file_path, file_type, target_type, target_id, uploaded_by, visibility)
+         VALUES ('kyc.png', 'private/kyc.png', 'image', 'provider', $1, $2, 'admin') RETURNING id",
+    )
+    .bind(provider_id)
+    .bind(owner_id)
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed attachment");
+    let download = format!("/attachments/{attachment_id}/download");
+    let res = app.get(&download, Some(&mint_jwt(deleted))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+    assert_eq!(res.body["code"], "unauthorized");
+    let res = app.get(&download, None).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+    assert_eq!(res.body["message"], "Sign in to download this attachment");
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::Value;
+
+fn page_of(body: &Value) -> (usize, i64, i64, i64, bool) {
+    (
+        body["items"].as_array().expect("items").len(),
+        body["page"].as_i64().expect("page"),
+        body["per_page"].as_i64().expect("per_page"),
+        body["total"].as_i64().expect("total"),
+        body["has_more"].as_bool().expect("has_more"),
+    )
+}
+
+#[tokio::test]
+async fn list_endpoints_share_page_defaults_clamping_and_validation() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let client = mint_jwt(client_id);
+    for i in 0..3 {
+        sqlx::query("INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content) VALUES ($1, $2, 'provider', $3, $4)")
+            .bind(client_id)
+            .bind(owner_id)
+            .bind(provider_id)
+            .bind(format!("Message {i}"))
+            .execute(&app.pool)
+            .await
+            .expect("seed message");
+    }
+    let thread = format!("/messages/getMessages?other_user_id={owner_id}&target_type=provider&target_id={provider_id}");
+
+    // Defaults
+    let res = app.get(&thread, Some(&client)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(page_of(&res.body), (3, 1, 20, 3, false));
+    assert_eq!(res.body["items"][0]["content"], "Message 0");
+
+    // Pages don't overlap and has_more tracks the remainder
+    let res = app.get(&format!("{thread}&per_page=2"), Some(&client)).await;
+    assert_eq!(page_of(&res.body), (2, 1, 2, 3, true));
+    let res = app.get(&format!("{thread}&per_page=2&page=2"), Some(&client)).await;
+    assert_eq!(page_of(&res.body), (1, 2, 2, 3, false));
+    assert_eq!(res.body["items"][0]["content"], "Message 2");
+
+    // Oversized per_page is clamped, a page past the end is empty
+    let res = app.get("/admin/users?per_page=500", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(page_of(&res.body), (3, 1, 100, 3, false));
+    let res = app.get("/admin/users?page=9", Some(&admin)).await;
+    assert_eq!(page_of(&res.body), (0, 9, 20, 3, false));
+
+    for uri in [
+        "/businesses/listBusinesses",
+        "/posts/getAllPosts",
+        "/api/v1/favorites/getFavorites",
+        "/admin/users",
+        thread.as_str(),
+    ] {
+        let sep = if uri.contains('?') { '&' } else { '?' };
+        let token = if uri.starts_with("/admin") { &admin } else { &client };
+
+        let res = app.get(&format!("{uri}{sep}per_page=500&page=4"), Some(token)).await;
+        assert_eq!(res.status, StatusCode::OK, "{uri}: {}", res.body);
+        assert_eq!(res.body["per_page"], 100, "{uri}");
+        assert_eq!(res.body["page"], 4, "{uri}");
+        assert_eq!(res.body["items"], serde_json::json!([]), "{uri}");
+        assert_eq!(res.body["has_more"], false, "{uri}");
+
+        for bad in ["page=0", "per_page=0", "page=two"] {
+            let res = app.get(&format!("{uri}{sep}{bad}"), Some(token)).await;
+            assert_eq!(res.status, StatusCode::BAD_REQUEST, "{uri} {bad}: {}", res.body);
+        }
+    }
+}
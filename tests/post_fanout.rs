@@ -0,0 +1,65 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use mtaalink::utils::post_fanout::fan_out_new_posts;
+use mtaalink::utils::ws_state::new_ws_connections;
+use serde_json::json;
+use std::collections::HashSet;
+
+// Lowers a platform setting, which is cached per process, so this file keeps
+// to a single test.
+#[tokio::test]
+async fn a_capped_fan_out_reaches_different_followers_for_each_post() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let mut followers = HashSet::new();
+    for _ in 0..6 {
+        let user_id = seed::client(&app.pool).await;
+        sqlx::query("INSERT INTO favorites (user_id, target_type, target_id) VALUES ($1, 'provider', $2)")
+            .bind(user_id)
+            .bind(provider_id)
+            .execute(&app.pool)
+            .await
+            .expect("seed favorite");
+        followers.insert(user_id);
+    }
+    let res = app.post_json("/admin/settings", Some(&admin), json!({ "posts.max_fanout_per_post": 3 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let mut post_ids = Vec::new();
+    for title in ["Open late", "New tools"] {
+        let post_id: i32 = sqlx::query_scalar("INSERT INTO posts (provider_id, title, content) VALUES ($1, $2, '') RETURNING id")
+            .bind(provider_id)
+            .bind(title)
+            .fetch_one(&app.pool)
+            .await
+            .expect("seed post");
+        post_ids.push(post_id);
+    }
+
+    let report = fan_out_new_posts(&app.pool, &new_ws_connections()).await;
+    assert_eq!(report.items_processed, 2);
+
+    let mut reached = Vec::new();
+    for post_id in &post_ids {
+        let users: HashSet<i32> = sqlx::query_scalar(
+            "SELECT user_id FROM notifications WHERE notif_type = 'new_post' AND target_id = $1",
+        )
+        .bind(post_id)
+        .fetch_all(&app.pool)
+        .await
+        .expect("notified users")
+        .into_iter()
+        .collect();
+        reached.push(users);
+    }
+
+    // Followers already told about this author today don't use up the
+    // second post's cap, so between them the posts reach everyone once
+    assert_eq!(reached[0].len(), 3);
+    assert_eq!(reached[1].len(), 3);
+    assert!(reached[0].is_disjoint(&reached[1]));
+    assert_eq!(&reached[0] | &reached[1], followers);
+}
@@ -0,0 +1,264 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn post(pool: &PgPool, column: &str, owner_id: i32) -> i32 {
+    sqlx::query_scalar(&format!("INSERT INTO posts ({column}, title, content) VALUES ($1, 'Open', 'Open today') RETURNING id"))
+        .bind(owner_id)
+        .fetch_one(pool)
+        .await
+        .expect("seed post")
+}
+
+async fn exists(pool: &PgPool, post_id: i32) -> bool {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM posts WHERE id = $1)")
+        .bind(post_id)
+        .fetch_one(pool)
+        .await
+        .expect("look up post")
+}
+
+#[tokio::test]
+async fn only_the_owner_can_edit_or_delete_a_post() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (provider_user, provider_id) = seed::provider(&app.pool).await;
+    let (business_user, business_id) = seed::business(&app.pool).await;
+    let provider_post = post(&app.pool, "provider_id", provider_id).await;
+    let business_post = post(&app.pool, "business_id", business_id).await;
+    let strangers = [
+        mint_jwt(seed::client(&app.pool).await),
+        mint_jwt(seed::provider(&app.pool).await.0),
+        mint_jwt(seed::business(&app.pool).await.0),
+    ];
+
+    for post_id in [provider_post, business_post] {
+        for stranger in &strangers {
+            let res = app
+                .post_json(&format!("/posts/updatePost/{post_id}"), Some(stranger), json!({ "title": "Mine now", "attachments": [] }))
+                .await;
+            assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+            let res = app.post_json(&format!("/posts/deletePost/{post_id}"), Some(stranger), json!({})).await;
+            assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+            assert!(exists(&app.pool, post_id).await);
+        }
+    }
+    // The provider doesn't own the business's post either
+    let provider = mint_jwt(provider_user);
+    let res = app.post_json(&format!("/posts/deletePost/{business_post}"), Some(&provider), json!({})).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+
+    let business = mint_jwt(business_user);
+    for (post_id, owner) in [(provider_post, &provider), (business_post, &business)] {
+        let res = app
+            .post_json(&format!("/posts/updatePost/{post_id}"), Some(owner), json!({ "title": "Updated", "attachments": [] }))
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        let res = app.post_json(&format!("/posts/deletePost/{post_id}"), Some(owner), json!({})).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        assert!(!exists(&app.pool, post_id).await);
+    }
+
+    let res = app.post_json(&format!("/posts/deletePost/{provider_post}"), Some(&provider), json!({})).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+}
+
+#[tokio::test]
+async fn an_admin_can_delete_any_post_and_it_is_audited() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let post_id = post(&app.pool, "provider_id", provider_id).await;
+    let admin_id = seed::admin(&app.pool).await;
+    let admin = mint_jwt(admin_id);
+
+    // Moderation covers removal, not rewriting someone's post
+    let res = app
+        .post_json(&format!("/posts/updatePost/{post_id}"), Some(&admin), json!({ "title": "Edited", "attachments": [] }))
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+
+    let res = app.post_json(&format!("/posts/deletePost/{post_id}"), Some(&admin), json!({})).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(!exists(&app.pool, post_id).await);
+
+    let (actor, target): (Option<i32>, Option<i32>) =
+        sqlx::query_as("SELECT actor_id, target_id FROM audit_log WHERE action = 'post_deleted'")
+            .fetch_one(&app.pool)
+            .await
+            .expect("read audit entry");
+    assert_eq!((actor, target), (Some(admin_id), Some(post_id)));
+}
+
+#[tokio::test]
+async fn a_created_post_reads_back_with_the_same_utc_timestamps() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let before = chrono::Utc::now();
+
+    let res = app
+        .post_json(
+            "/posts/createPosts",
+            Some(&mint_jwt(owner_id)),
+            json!({ "title": "New stock", "content": "Fresh paint colours in", "provider_id": provider_id }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let post_id = res.body["post_id"].as_i64().expect("post id");
+    let created_at = res.body["created_at"].as_str().expect("created_at").to_string();
+    assert!(created_at.ends_with('Z'), "{created_at}");
+    let parsed = chrono::DateTime::parse_from_rfc3339(&created_at).expect("RFC 3339");
+    assert!((parsed.with_timezone(&chrono::Utc) - before).num_seconds().abs() < 60, "{created_at}");
+
+    let res = app.get(&format!("/posts/getPost/{post_id}"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["post"]["title"], "New stock");
+    assert_eq!(res.body["post"]["created_at"], created_at.as_str());
+    assert_eq!(res.body["post"]["updated_at"], created_at.as_str());
+
+    let res = app.get(&format!("/posts/provider/{provider_id}/posts"), None).await;
+    assert_eq!(res.body["posts"][0]["created_at"], created_at.as_str());
+
+    // Stored as instants, so the server's time zone can't shift them
+    let types: Vec<String> = sqlx::query_scalar(
+        "SELECT data_type FROM information_schema.columns
+         WHERE table_name = 'posts' AND column_name IN ('created_at', 'updated_at')",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .expect("read column types");
+    assert_eq!(types, ["timestamp with time zone", "timestamp with time zone"]);
+}
+
+#[tokio::test]
+async fn anonymous_views_are_keyed_by_the_peer_not_a_forwarded_header() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let post_id = post(&app.pool, "provider_id", provider_id).await;
+    let view = |forwarded_for: &str| {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/posts/{post_id}/view"))
+            .header("x-forwarded-for", forwarded_for)
+            .body(Body::empty())
+            .expect("build request");
+        app.send(request)
+    };
+
+    let res = view("198.51.100.1").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["counted"], true);
+
+    // No trusted proxies are configured, so a new header is the same visitor
+    let res = view("198.51.100.2").await;
+    assert_eq!(res.body["counted"], false, "{}", res.body);
+    assert_eq!(res.body["view_count"], 1);
+
+    let client = mint_jwt(seed::client(&app.pool).await);
+    let res = app.post_json(&format!("/posts/{post_id}/view"), Some(&client), json!({})).await;
+    assert_eq!(res.body["counted"], true, "{}", res.body);
+
+    let keys: Vec<String> = sqlx::query_scalar("SELECT viewer_key FROM post_view_events WHERE post_id = $1 ORDER BY viewer_key")
+        .bind(post_id)
+        .fetch_all(&app.pool)
+        .await
+        .expect("view keys");
+    assert_eq!(keys.len(), 2);
+    assert!(keys[0].starts_with("ip:") && keys[0].len() == 35, "{keys:?}");
+    assert!(!keys[0].contains("127.0.0.1") && !keys[0].contains("198.51.100"));
+    assert!(keys[1].starts_with("u:"));
+}
+
+#[tokio::test]
+async fn a_hidden_post_takes_no_likes_or_comments_except_from_its_author() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let post_id = post(&app.pool, "provider_id", provider_id).await;
+    let client = mint_jwt(seed::client(&app.pool).await);
+    let res = app.post_json(&format!("/posts/{post_id}/comments"), Some(&client), json!({ "comment": "Scam!" })).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    sqlx::query("UPDATE posts SET hidden = TRUE WHERE id = $1")
+        .bind(post_id)
+        .execute(&app.pool)
+        .await
+        .expect("hide post");
+
+    let res = app.post_json(&format!("/posts/{post_id}/like"), Some(&client), json!({})).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+    let res = app.post_json(&format!("/posts/{post_id}/comments"), Some(&client), json!({ "comment": "Still here" })).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+    for token in [Some(client.as_str()), None] {
+        let res = app.get(&format!("/posts/{post_id}/comments"), token).await;
+        assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+    }
+
+    let owner = mint_jwt(owner_id);
+    let res = app.get(&format!("/posts/{post_id}/comments"), Some(&owner)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["comments"].as_array().map(Vec::len), Some(1));
+    let res = app.post_json(&format!("/posts/{post_id}/comments"), Some(&owner), json!({ "comment": "Appealing" })).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let res = app.post_json(&format!("/posts/{post_id}/like"), Some(&owner), json!({})).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+}
+
+/// An upload not yet on any post. Returns its path.
+async fn upload(pool: &PgPool, target: (&str, i32), uploaded_by: i32, path: &str, visibility: &str) -> String {
+    sqlx::query(
+        "INSERT INTO attachments (file_name, file_path, file_type, content_type, visibility, target_type, target_id, uploaded_by)
+         VALUES ('clip.mp4', $1, 'video', 'video/mp4', $2, $3, $4, $5)",
+    )
+    .bind(path)
+    .bind(visibility)
+    .bind(target.0)
+    .bind(target.1)
+    .bind(uploaded_by)
+    .execute(pool)
+    .await
+    .expect("seed upload");
+    path.to_string()
+}
+
+#[tokio::test]
+async fn a_post_can_only_attach_its_profiles_own_public_uploads() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (other_id, other_provider) = seed::provider(&app.pool).await;
+    let post_id = post(&app.pool, "provider_id", provider_id).await;
+    let owner = mint_jwt(owner_id);
+    let own = upload(&app.pool, ("provider", provider_id), owner_id, "/uploads/attachments/own.mp4", "public").await;
+    let refused = [
+        upload(&app.pool, ("provider", provider_id), owner_id, "private/attachments/own.mp4", "participants").await,
+        upload(&app.pool, ("provider", other_provider), other_id, "private/attachments/theirs.mp4", "admin").await,
+        upload(&app.pool, ("provider", other_provider), other_id, "/uploads/attachments/theirs.mp4", "public").await,
+        "/uploads/attachments/never-uploaded.mp4".to_string(),
+    ];
+    let attached = |pool: PgPool| async move {
+        sqlx::query_as::<_, (String, String, Option<String>, String)>(
+            "SELECT file_path, file_type, content_type, visibility FROM attachments WHERE post_id = $1",
+        )
+        .bind(post_id)
+        .fetch_all(&pool)
+        .await
+        .expect("post attachments")
+    };
+
+    let res = app
+        .post_json(&format!("/posts/updatePost/{post_id}"), Some(&owner), json!({ "attachments": [own] }))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let expected = vec![(own.clone(), "video".to_string(), Some("video/mp4".to_string()), "public".to_string())];
+    assert_eq!(attached(app.pool.clone()).await, expected);
+
+    for path in refused {
+        let res = app
+            .post_json(&format!("/posts/updatePost/{post_id}"), Some(&owner), json!({ "attachments": [own, path] }))
+            .await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{path}: {}", res.body);
+        // Nothing changed, not even the attachment that was allowed
+        assert_eq!(attached(app.pool.clone()).await, expected, "{path}");
+    }
+}
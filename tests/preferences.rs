@@ -0,0 +1,180 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use common::{TestApp, mint_jwt, seed};
+use mtaalink::utils::reminders::send_pending_reminders;
+use mtaalink::utils::ws_state::new_ws_connections;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+async fn set_prefs(app: &TestApp, user_id: i32, body: Value) {
+    let res = app.post_json("/api/v1/clients/preferences", Some(&mint_jwt(user_id)), body).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+}
+
+async fn notification_count(pool: &PgPool, user_id: i32, notif_type: &str) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND notif_type = $2")
+        .bind(user_id)
+        .bind(notif_type)
+        .fetch_one(pool)
+        .await
+        .expect("count notifications")
+}
+
+#[tokio::test]
+async fn preferences_default_update_and_reject_unknown_keys() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let token = mint_jwt(seed::client(&app.pool).await);
+
+    let res = app.get("/api/v1/clients/preferences", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+        res.body["preferences"],
+        json!({
+            "allow_broadcasts": true,
+            "notify_on_posts": true,
+            "notify_on_favorite": true,
+            "show_last_seen": true,
+            "booking_reminder_hours": 24,
+        })
+    );
+
+    let res = app
+        .post_json(
+            "/api/v1/clients/preferences",
+            Some(&token),
+            json!({ "show_last_seen": false, "booking_reminder_hours": 2 }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["preferences"]["show_last_seen"], false);
+    assert_eq!(res.body["preferences"]["booking_reminder_hours"], 2);
+    assert_eq!(res.body["preferences"]["allow_broadcasts"], true);
+
+    // A bad entry rejects the whole batch
+    for body in [
+        json!({ "allow_broadcasts": false, "dark_mode": true }),
+        json!({ "allow_broadcasts": "no" }),
+        json!({ "booking_reminder_hours": 100 }),
+        json!({ "booking_reminder_hours": 1.5 }),
+        json!({}),
+    ] {
+        let res = app.post_json("/api/v1/clients/preferences", Some(&token), body).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    }
+    let res = app.get("/api/v1/clients/preferences", Some(&token)).await;
+    assert_eq!(res.body["preferences"]["allow_broadcasts"], true);
+    assert_eq!(res.body["preferences"]["booking_reminder_hours"], 2);
+}
+
+#[tokio::test]
+async fn muting_broadcasts_keeps_only_critical_announcements() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let client_id = seed::client(&app.pool).await;
+    sqlx::query(
+        "INSERT INTO announcements (title, body, severity) VALUES ('Promo', 'Half price', 'info'),
+                                                                 ('Outage', 'M-Pesa is down', 'critical')",
+    )
+    .execute(&app.pool)
+    .await
+    .expect("seed announcements");
+
+    let titles = |body: &Value| -> Vec<String> {
+        body["announcements"]
+            .as_array()
+            .expect("announcements")
+            .iter()
+            .filter_map(|a| a["title"].as_str().map(String::from))
+            .collect()
+    };
+
+    let res = app.get("/api/v1/announcements/active", Some(&mint_jwt(client_id))).await;
+    assert_eq!(titles(&res.body), ["Outage", "Promo"]);
+
+    set_prefs(&app, client_id, json!({ "allow_broadcasts": false })).await;
+    let res = app.get("/api/v1/announcements/active", Some(&mint_jwt(client_id))).await;
+    assert_eq!(titles(&res.body), ["Outage"]);
+}
+
+#[tokio::test]
+async fn owners_hear_about_new_favorites_unless_they_opt_out() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let favorite = json!({ "target_type": "provider", "target_id": provider_id });
+
+    let fan = mint_jwt(seed::client(&app.pool).await);
+    let res = app.post_json("/api/v1/favorites/addFavorite", Some(&fan), favorite.clone()).await;
+    assert_eq!(res.status, StatusCode::OK);
+    // Favoriting again doesn't notify twice
+    app.post_json("/api/v1/favorites/addFavorite", Some(&fan), favorite.clone()).await;
+    assert_eq!(notification_count(&app.pool, owner_id, "favorite_added").await, 1);
+
+    set_prefs(&app, owner_id, json!({ "notify_on_favorite": false })).await;
+    let another_fan = mint_jwt(seed::client(&app.pool).await);
+    app.post_json("/api/v1/favorites/addFavorite", Some(&another_fan), favorite).await;
+    assert_eq!(notification_count(&app.pool, owner_id, "favorite_added").await, 1);
+}
+
+#[tokio::test]
+async fn conversations_show_last_seen_only_when_allowed() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    sqlx::query(
+        "INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content)
+         VALUES ($1, $2, 'provider', $3, 'Are you free on Saturday?')",
+    )
+    .bind(client_id)
+    .bind(owner_id)
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed message");
+    sqlx::query("UPDATE users SET last_seen_at = NOW() WHERE id = $1")
+        .bind(client_id)
+        .execute(&app.pool)
+        .await
+        .expect("mark client seen");
+
+    let owner = mint_jwt(owner_id);
+    let res = app.get("/api/v1/messages/conversations", Some(&owner)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(res.body["items"][0]["other_last_seen_at"].is_string());
+
+    set_prefs(&app, client_id, json!({ "show_last_seen": false })).await;
+    let res = app.get("/api/v1/messages/conversations", Some(&owner)).await;
+    assert_eq!(res.body["items"][0]["other_last_seen_at"], json!(null));
+}
+
+#[tokio::test]
+async fn reminders_go_out_at_each_clients_chosen_lead_time() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let default_client = seed::client(&app.pool).await;
+    let early_client = seed::client(&app.pool).await;
+    let no_reminders = seed::client(&app.pool).await;
+    set_prefs(&app, early_client, json!({ "booking_reminder_hours": 2 })).await;
+    set_prefs(&app, no_reminders, json!({ "booking_reminder_hours": 0 })).await;
+
+    // seed::booking schedules a day ahead: due for the default client only
+    let due = seed::booking(&app.pool, default_client, "provider", provider_id, None, "confirmed").await;
+    seed::booking(&app.pool, early_client, "provider", provider_id, None, "confirmed").await;
+    seed::booking(&app.pool, no_reminders, "provider", provider_id, None, "confirmed").await;
+    let soon = seed::booking(&app.pool, early_client, "provider", provider_id, None, "confirmed").await;
+    sqlx::query("UPDATE bookings SET scheduled_time = $1 WHERE id = $2")
+        .bind((Utc::now() + Duration::hours(2)).naive_utc())
+        .bind(soon)
+        .execute(&app.pool)
+        .await
+        .expect("move booking");
+
+    let report = send_pending_reminders(&app.pool, &new_ws_connections()).await;
+    assert_eq!(report.items_processed, 2);
+
+    let reminded: Vec<i32> = sqlx::query_scalar("SELECT id FROM bookings WHERE reminder_sent ORDER BY id")
+        .fetch_all(&app.pool)
+        .await
+        .expect("reminded bookings");
+    assert_eq!(reminded, [due, soon]);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, seed};
+
+#[tokio::test]
+async fn listing_providers_pages_and_counts_only_filtered_rows() {
+    let Some(app) = TestApp::spawn().await else { return };
+    for _ in 0..3 {
+        seed::provider(&app.pool).await;
+    }
+    let (_, electrician) = seed::provider(&app.pool).await;
+    sqlx::query("UPDATE providers SET category = 'Electrical' WHERE id = $1")
+        .bind(electrician)
+        .execute(&app.pool)
+        .await
+        .expect("recategorize provider");
+
+    // No params: page 1 at the default size, not the whole table
+    let res = app.get("/service_providers/listProviders", None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!((res.body["page"].as_i64(), res.body["per_page"].as_i64()), (Some(1), Some(20)));
+    assert_eq!(res.body["total"], 4);
+
+    let res = app.get("/service_providers/listProviders?category=Plumbing&per_page=2&page=2", None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total"], 3);
+    assert_eq!(res.body["items"].as_array().map(Vec::len), Some(1));
+    assert_eq!(res.body["has_more"], false);
+
+    let res = app.get("/service_providers/listProviders?per_page=5000", None).await;
+    assert_eq!(res.body["per_page"], 100);
+    let res = app.get("/service_providers/listProviders?page=0", None).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn a_provider_profile_is_public_with_services_schedule_and_rating() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let service_id = seed::service(&app.pool, "provider", provider_id).await;
+    let hidden_service = seed::service(&app.pool, "provider", provider_id).await;
+    sqlx::query("UPDATE services SET is_active = FALSE WHERE id = $1")
+        .bind(hidden_service)
+        .execute(&app.pool)
+        .await
+        .expect("deactivate service");
+    sqlx::query(
+        "INSERT INTO provider_availability (provider_id, day, start_time, end_time, is_available)
+         VALUES ($1, 'Tuesday', '09:00', '17:00', TRUE), ($1, 'Monday', '08:00', '12:00', TRUE)",
+    )
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed availability");
+    for rating in [4, 5] {
+        let client = seed::client(&app.pool).await;
+        sqlx::query("INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment) VALUES ($1, 'provider', $2, $3, 'Good')")
+            .bind(client)
+            .bind(provider_id)
+            .bind(rating)
+            .execute(&app.pool)
+            .await
+            .expect("seed review");
+    }
+
+    let res = app.get(&format!("/service_providers/{provider_id}"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["provider"]["service_name"], "Test Plumbing");
+    assert_eq!(res.body["provider"]["category"], "Plumbing");
+    let services = res.body["services"].as_array().expect("services");
+    assert_eq!(services.len(), 1);
+    assert_eq!(services[0]["id"], service_id);
+    let days: Vec<&str> = res.body["availability"]
+        .as_array()
+        .expect("availability")
+        .iter()
+        .map(|d| d["day"].as_str().unwrap())
+        .collect();
+    assert_eq!(days, ["Monday", "Tuesday"]);
+    assert_eq!(res.body["aggregated_rating"]["average_rating"], 4.5);
+    assert_eq!(res.body["aggregated_rating"]["review_count"], 2);
+
+    let res = app.get("/service_providers/999999", None).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+}
+
+#[tokio::test]
+async fn providers_are_searchable_by_free_text_and_sortable_by_rating() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, plumber) = seed::provider(&app.pool).await;
+    let (_, electrician) = seed::provider(&app.pool).await;
+    sqlx::query(
+        "UPDATE providers SET service_name = 'Bright Sparks', category = 'Electrical',
+                              service_description = 'Wiring and solar installs', location = 'Westlands, Nairobi'
+         WHERE id = $1",
+    )
+    .bind(electrician)
+    .execute(&app.pool)
+    .await
+    .expect("update provider");
+    for (provider, rating) in [(plumber, 3), (electrician, 5), (electrician, 4)] {
+        sqlx::query("INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment) VALUES ($1, 'provider', $2, $3, 'Ok')")
+            .bind(seed::client(&app.pool).await)
+            .bind(provider)
+            .bind(rating)
+            .execute(&app.pool)
+            .await
+            .expect("seed review");
+    }
+    let ids = |body: &serde_json::Value| -> Vec<i64> {
+        body["items"].as_array().expect("items").iter().map(|p| p["id"].as_i64().unwrap()).collect()
+    };
+
+    // "plumber" stems apart from "Plumbing"; trigrams still find it
+    for (query, expected) in [
+        ("q=plumber", vec![plumber]),
+        ("q=solar", vec![electrician]),
+        ("q=wirring", vec![electrician]),
+        ("q=bright%20sparks", vec![electrician]),
+        ("q=plumbing&location=westlands", vec![]),
+        ("q=wiring&location=westlands", vec![electrician]),
+        ("category=electrical", vec![electrician]),
+        ("q=carpentry", vec![]),
+    ] {
+        let res = app.get(&format!("/service_providers/listProviders?{query}"), None).await;
+        assert_eq!(res.status, StatusCode::OK, "{query}: {}", res.body);
+        let expected: Vec<i64> = expected.into_iter().map(i64::from).collect();
+        assert_eq!(ids(&res.body), expected, "{query}");
+        assert_eq!(res.body["total"], expected.len(), "{query}");
+    }
+
+    let res = app.get("/service_providers/listProviders?sort=-reviews", None).await;
+    assert_eq!(ids(&res.body), [i64::from(electrician), i64::from(plumber)]);
+    let res = app.get("/service_providers/listProviders?sort=rating", None).await;
+    assert_eq!(ids(&res.body), [i64::from(plumber), i64::from(electrician)]);
+    assert_eq!((res.body["items"][1]["avg_rating"].as_f64(), res.body["items"][1]["review_count"].as_i64()), (Some(4.5), Some(2)));
+}
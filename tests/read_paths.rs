@@ -0,0 +1,144 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt};
+use sqlx::{Executor, PgPool};
+use std::time::{Duration, Instant};
+
+/// Slowest acceptable response for a hot listing endpoint on the seeded
+/// dataset, with room for a debug build on a busy CI runner.
+const LATENCY_BUDGET: Duration = Duration::from_millis(500);
+
+/// 20k clients and 2k providers with 100k each of reviews, bookings and
+/// messages, plus five favorites per client.
+const DATASET: &str = r#"
+INSERT INTO users (username, email, password, role, email_verified)
+SELECT 'bench_client_' || g, 'bench_client_' || g || '@example.com', 'x', 'client', TRUE
+FROM generate_series(1, 20000) g;
+INSERT INTO users (username, email, password, role, email_verified)
+SELECT 'bench_provider_' || g, 'bench_provider_' || g || '@example.com', 'x', 'provider', TRUE
+FROM generate_series(1, 2000) g;
+INSERT INTO providers (user_id, service_name, service_description, category, location, onboarding_completed, listing_status)
+SELECT id, 'Provider ' || id, 'Does things', 'Category ' || (id % 10), 'Town ' || (id % 25), TRUE, 'approved'
+FROM users WHERE username LIKE 'bench_provider_%';
+
+CREATE TEMP TABLE bench_clients AS
+    SELECT id, row_number() OVER (ORDER BY id) AS n FROM users WHERE username LIKE 'bench_client_%';
+CREATE TEMP TABLE bench_providers AS
+    SELECT id, user_id, row_number() OVER (ORDER BY id) AS n FROM providers;
+
+INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment)
+SELECT c.id, 'provider', p.id, 1 + (g % 5), 'Review ' || g
+FROM generate_series(1, 100000) g
+JOIN bench_clients c ON c.n = 1 + (g % 20000)
+JOIN bench_providers p ON p.n = 1 + ((g * 7) % 2000);
+INSERT INTO bookings (client_id, target_type, target_id, scheduled_time, duration, status, confirmation_code)
+SELECT c.id, 'provider', p.id, NOW() - INTERVAL '200 days' + (g * 3 || ' minutes')::interval, 60,
+       (ARRAY['pending', 'confirmed', 'completed', 'cancelled'])[1 + g % 4], upper(lpad(to_hex(g), 8, '0'))
+FROM generate_series(1, 100000) g
+JOIN bench_clients c ON c.n = 1 + ((g * 13) % 20000)
+JOIN bench_providers p ON p.n = 1 + ((g * 11) % 2000);
+INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content, is_read, created_at)
+SELECT CASE WHEN g % 2 = 0 THEN c.id ELSE p.user_id END,
+       CASE WHEN g % 2 = 0 THEN p.user_id ELSE c.id END,
+       'provider', p.id, 'Message ' || g, g % 3 = 0, NOW() - (g || ' seconds')::interval
+FROM generate_series(1, 100000) g
+JOIN bench_clients c ON c.n = 1 + ((g / 10) % 20000)
+JOIN bench_providers p ON p.n = 1 + ((g / 10) % 2000);
+INSERT INTO favorites (user_id, target_type, target_id)
+SELECT c.id, 'provider', p.id
+FROM bench_clients c CROSS JOIN generate_series(0, 4) k
+JOIN bench_providers p ON p.n = 1 + ((c.n * 7 + k * 401) % 2000);
+
+ANALYZE;
+"#;
+
+async fn seed_dataset(pool: &PgPool) {
+    // One connection, since the temp tables only live on it
+    let mut conn = pool.acquire().await.expect("acquire connection");
+    conn.execute(DATASET).await.expect("seed dataset");
+}
+
+/// The provider with the most bookings, and its owner.
+async fn busiest_provider(pool: &PgPool) -> (i32, i32) {
+    sqlx::query_as(
+        "SELECT p.user_id, p.id FROM providers p JOIN bookings b ON b.target_type = 'provider' AND b.target_id = p.id
+         GROUP BY p.id ORDER BY COUNT(*) DESC LIMIT 1",
+    )
+    .fetch_one(pool)
+    .await
+    .expect("busiest provider")
+}
+
+/// The plan for `sql` with `id` bound to `$1`.
+async fn plan(pool: &PgPool, sql: &str, id: i32) -> String {
+    let rows: Vec<String> = sqlx::query_scalar(&format!("EXPLAIN {sql}"))
+        .bind(id)
+        .fetch_all(pool)
+        .await
+        .expect("explain");
+    rows.join("\n")
+}
+
+#[tokio::test]
+async fn per_user_lookups_use_indexes() {
+    let Some(app) = TestApp::spawn().await else { return };
+    seed_dataset(&app.pool).await;
+    let (owner_id, provider_id) = busiest_provider(&app.pool).await;
+
+    let bookings = plan(
+        &app.pool,
+        "SELECT id FROM bookings WHERE target_type = 'provider' AND target_id = $1 ORDER BY scheduled_time DESC",
+        provider_id,
+    )
+    .await;
+    let unread = plan(&app.pool, "SELECT COUNT(*) FROM messages WHERE receiver_id = $1 AND is_read = FALSE", owner_id).await;
+    let threads = plan(&app.pool, "SELECT id FROM messages WHERE sender_id = $1 OR receiver_id = $1", owner_id).await;
+    let favorites = plan(&app.pool, "SELECT target_id FROM favorites WHERE user_id = $1 ORDER BY created_at DESC", owner_id).await;
+    let ratings = plan(
+        &app.pool,
+        "SELECT AVG(rating) FROM reviews WHERE target_type = 'provider' AND target_id = $1",
+        provider_id,
+    )
+    .await;
+
+    for plan in [bookings, unread, threads, favorites, ratings] {
+        assert!(!plan.contains("Seq Scan"), "expected an index scan:\n{plan}");
+    }
+}
+
+#[tokio::test]
+async fn hot_listing_endpoints_stay_within_the_latency_budget() {
+    let Some(app) = TestApp::spawn().await else { return };
+    seed_dataset(&app.pool).await;
+    let (owner_id, provider_id) = busiest_provider(&app.pool).await;
+    let client_id: i32 = sqlx::query_scalar("SELECT user_id FROM favorites ORDER BY user_id LIMIT 1")
+        .fetch_one(&app.pool)
+        .await
+        .expect("client with favorites");
+    let owner = mint_jwt(owner_id);
+    let client = mint_jwt(client_id);
+
+    let endpoints = [
+        ("/api/v1/service_providers/listProviders".to_string(), None),
+        ("/api/v1/service_providers/listProviders?sort=name&page=50".to_string(), None),
+        ("/api/v1/messages/conversations".to_string(), Some(&owner)),
+        (
+            format!("/api/v1/bookings/getBookings/received?target_type=provider&target_id={provider_id}"),
+            Some(&owner),
+        ),
+        ("/api/v1/favorites/getFavorites".to_string(), Some(&client)),
+    ];
+
+    for (uri, token) in endpoints {
+        // Warm the pool and plan cache, then take the best of a few runs
+        let mut best = Duration::MAX;
+        for _ in 0..3 {
+            let started = Instant::now();
+            let res = app.get(&uri, token.map(String::as_str)).await;
+            best = best.min(started.elapsed());
+            assert_eq!(res.status, StatusCode::OK, "{uri}: {}", res.body);
+        }
+        assert!(best < LATENCY_BUDGET, "{uri} took {best:?}, budget {LATENCY_BUDGET:?}");
+    }
+}
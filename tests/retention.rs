@@ -0,0 +1,109 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use mtaalink::utils::{jobs, retention};
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn count(pool: &PgPool, sql: &str) -> i64 {
+    sqlx::query_scalar(sql).fetch_one(pool).await.expect("count rows")
+}
+
+fn policy_of(body: &serde_json::Value, table: &str) -> serde_json::Value {
+    body["policies"]
+        .as_array()
+        .expect("policies")
+        .iter()
+        .find(|p| p["table"] == table)
+        .cloned()
+        .expect("policy")
+}
+
+#[tokio::test]
+async fn retention_prunes_expired_rows_and_reports_per_table() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let user_id = seed::client(&app.pool).await;
+
+    sqlx::query(
+        "INSERT INTO notifications (user_id, notif_type, title, body, created_at, read_at)
+         VALUES ($1, 'system', 'Old and read', '', NOW() - INTERVAL '100 days', NOW() - INTERVAL '99 days'),
+                ($1, 'system', 'Old but unread', '', NOW() - INTERVAL '100 days', NULL),
+                ($1, 'system', 'Recent and read', '', NOW() - INTERVAL '10 days', NOW())",
+    )
+    .bind(user_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed notifications");
+    let subscription_id: i32 = sqlx::query_scalar(
+        "INSERT INTO webhook_subscriptions (user_id, target_type, target_id, url, secret, event_types)
+         VALUES ($1, 'provider', 1, 'https://example.com/hook', 'secret', '{booking.created}') RETURNING id",
+    )
+    .bind(user_id)
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed subscription");
+    sqlx::query(
+        "INSERT INTO webhook_deliveries (subscription_id, event_type, payload, status, created_at)
+         VALUES ($1, 'booking.created', '{}', 'delivered', NOW() - INTERVAL '40 days'),
+                ($1, 'booking.created', '{}', 'pending', NOW() - INTERVAL '40 days')",
+    )
+    .bind(subscription_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed deliveries");
+    sqlx::query(
+        "INSERT INTO search_queries (query, result_count, created_at)
+         VALUES ('plumber', 3, NOW() - INTERVAL '200 days'), ('salon', 0, NOW())",
+    )
+    .execute(&app.pool)
+    .await
+    .expect("seed search log");
+
+    // Nothing has run yet
+    let res = app.get("/admin/retention", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["last_run"], json!(null));
+    assert!(res.body["protected_tables"].as_array().expect("protected tables").contains(&json!("bookings")));
+    let tables: Vec<&str> = res.body["policies"]
+        .as_array()
+        .expect("policies")
+        .iter()
+        .map(|p| p["table"].as_str().unwrap())
+        .collect();
+    assert!(tables.iter().all(|t| !retention::PROTECTED_TABLES.contains(t)));
+
+    assert!(jobs::record_run(&app.pool, &jobs::RETENTION, retention::run_retention(&app.pool)).await);
+
+    let titles: Vec<String> = sqlx::query_scalar("SELECT title FROM notifications ORDER BY title")
+        .fetch_all(&app.pool)
+        .await
+        .expect("read notifications");
+    assert_eq!(titles, ["Old but unread", "Recent and read"]);
+    assert_eq!(count(&app.pool, "SELECT COUNT(*) FROM webhook_deliveries WHERE status = 'pending'").await, 1);
+    assert_eq!(count(&app.pool, "SELECT COUNT(*) FROM webhook_deliveries").await, 1);
+    assert_eq!(count(&app.pool, "SELECT COUNT(*) FROM search_queries").await, 1);
+
+    let res = app.get("/admin/retention", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["last_run"]["items_processed"], 3);
+    assert_eq!(res.body["last_run"]["error"], json!(null));
+    assert_eq!(policy_of(&res.body, "notifications")["last_deleted"], 1);
+    assert_eq!(policy_of(&res.body, "notifications")["days"], 90);
+    assert_eq!(policy_of(&res.body, "webhook_deliveries")["last_deleted"], 1);
+    assert_eq!(policy_of(&res.body, "post_view_events")["last_deleted"], 0);
+
+    // 0 turns a policy off
+    let res = app.post_json("/admin/settings", Some(&admin), json!({ "retention.search_queries_days": 0 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    sqlx::query("UPDATE search_queries SET created_at = NOW() - INTERVAL '5 years'")
+        .execute(&app.pool)
+        .await
+        .expect("age search log");
+    assert!(jobs::record_run(&app.pool, &jobs::RETENTION, retention::run_retention(&app.pool)).await);
+    assert_eq!(count(&app.pool, "SELECT COUNT(*) FROM search_queries").await, 1);
+    let res = app.get("/admin/retention", Some(&admin)).await;
+    assert_eq!(policy_of(&res.body, "search_queries")["enabled"], false);
+    assert_eq!(policy_of(&res.body, "search_queries")["last_deleted"], json!(null));
+}
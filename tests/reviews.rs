@@ -0,0 +1,98 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn review(pool: &PgPool, reviewer_id: i32, provider_id: i32, rating: i32) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO reviews (reviewer_id, target_type, target_id, rating, comment)
+         VALUES ($1, 'provider', $2, $3, 'Came on time') RETURNING id",
+    )
+    .bind(reviewer_id)
+    .bind(provider_id)
+    .bind(rating)
+    .fetch_one(pool)
+    .await
+    .expect("seed review")
+}
+
+#[tokio::test]
+async fn the_listing_owner_replies_once_and_the_reply_is_listed() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let reviewer = seed::client(&app.pool).await;
+    let answered = review(&app.pool, reviewer, provider_id, 4).await;
+    let unanswered = review(&app.pool, seed::client(&app.pool).await, provider_id, 5).await;
+    let owner = mint_jwt(owner_id);
+
+    let body = json!({ "comment": "Thank you, see you next time!" });
+    let res = app.post_json(&format!("/reviews/{answered}/reply"), Some(&owner), body.clone()).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    let res = app.post_json(&format!("/reviews/{answered}/reply"), Some(&owner), body.clone()).await;
+    assert_eq!(res.status, StatusCode::CONFLICT, "{}", res.body);
+
+    // Neither the reviewer nor another provider owns the listing
+    let (other_owner, _) = seed::provider(&app.pool).await;
+    for token in [mint_jwt(reviewer), mint_jwt(other_owner)] {
+        let res = app.post_json(&format!("/reviews/{unanswered}/reply"), Some(&token), body.clone()).await;
+        assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    }
+    let res = app.post_json("/reviews/999999/reply", Some(&owner), body).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND, "{}", res.body);
+
+    let res = app.get(&format!("/reviews/getReviews?target_type=provider&target_id={provider_id}&sort=-rating"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let items = res.body["items"].as_array().expect("items");
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["id"], unanswered);
+    assert_eq!(items[0]["reply"], json!(null));
+    assert_eq!(items[1]["id"], answered);
+    assert_eq!(items[1]["reply"]["comment"], "Thank you, see you next time!");
+    assert!(items[1]["reply"]["created_at"].is_string(), "{}", res.body);
+}
+
+#[tokio::test]
+async fn reviews_name_their_reviewer_and_take_sort_shorthands() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let pictured = seed::client(&app.pool).await;
+    sqlx::query("UPDATE clients SET profile_picture = 'https://cdn.example.com/a.png' WHERE user_id = $1")
+        .bind(pictured)
+        .execute(&app.pool)
+        .await
+        .expect("set picture");
+    let low = review(&app.pool, pictured, provider_id, 2).await;
+    let high = review(&app.pool, seed::client(&app.pool).await, provider_id, 5).await;
+    let newest = review(&app.pool, seed::client(&app.pool).await, provider_id, 3).await;
+    let list = |query: &str| {
+        let uri = format!("/reviews/getReviews?target_type=provider&target_id={provider_id}&{query}");
+        let app = &app;
+        async move { app.get(&uri, None).await }
+    };
+
+    let res = list("sort=lowest").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let first = &res.body["items"][0];
+    assert_eq!(first["id"], low);
+    let username: String = sqlx::query_scalar("SELECT username FROM users WHERE id = $1")
+        .bind(pictured)
+        .fetch_one(&app.pool)
+        .await
+        .expect("read username");
+    assert_eq!(first["reviewer_username"], username);
+    assert_eq!(first["reviewer_profile_picture"], "https://cdn.example.com/a.png");
+    assert_eq!(res.body["items"][1]["reviewer_profile_picture"], json!(null));
+
+    assert_eq!(list("sort=highest").await.body["items"][0]["id"], high);
+    let res = list("sort=newest&per_page=1&page=1").await;
+    assert_eq!(res.body["items"].as_array().unwrap().len(), 1);
+    assert_eq!(res.body["items"][0]["id"], newest);
+    assert_eq!(res.body["total"], 3);
+    assert_eq!(res.body["has_more"], true);
+
+    assert_eq!(list("sort=loudest").await.status, StatusCode::BAD_REQUEST);
+    let res = app.get(&format!("/reviews/getReviews?target_type=clinic&target_id={provider_id}"), None).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+}
@@ -0,0 +1,84 @@
+//! Exercises `S3Storage` against a real S3-compatible server. Needs a running
+//! MinIO (or similar) with an existing bucket:
+//!
+//! ```bash
+//! docker run -d -p 9000:9000 minio/minio server /data
+//! S3_TEST_ENDPOINT=http://127.0.0.1:9000 cargo test --features s3-tests --test s3_storage
+//! ```
+#![cfg(feature = "s3-tests")]
+
+use bytes::Bytes;
+use futures_util::stream;
+use mtaalink::utils::storage::{ByteStream, S3Config, S3Storage, Storage};
+use uuid::Uuid;
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
+
+fn minio() -> S3Storage {
+    S3Storage::new(&S3Config {
+        region: env_or("S3_TEST_REGION", "us-east-1"),
+        bucket: env_or("S3_TEST_BUCKET", "mtaalink-test"),
+        access_key: env_or("S3_TEST_ACCESS_KEY", "minioadmin"),
+        secret_key: env_or("S3_TEST_SECRET_KEY", "minioadmin"),
+        endpoint: Some(env_or("S3_TEST_ENDPOINT", "http://127.0.0.1:9000")),
+        base_url: None,
+    })
+}
+
+/// `data` in uneven chunks, the way a request body arrives.
+fn chunked(data: &[u8]) -> ByteStream {
+    let chunks: Vec<std::io::Result<Bytes>> =
+        data.chunks(300_001).map(|c| Ok(Bytes::copy_from_slice(c))).collect();
+    Box::pin(stream::iter(chunks))
+}
+
+async fn read_back(storage: &S3Storage, key: &str, start: u64, end: u64) -> Bytes {
+    let body = storage.read_range(key, start, end).await.expect("read range");
+    axum::body::to_bytes(body, usize::MAX).await.expect("read body")
+}
+
+#[tokio::test]
+async fn small_uploads_round_trip() {
+    let storage = minio();
+    let key = format!("s3-tests/{}.txt", Uuid::new_v4().simple());
+
+    let url = storage.save(&key, &Bytes::from_static(b"hello, bucket"), "text/plain").await.expect("save");
+    assert_eq!(storage.key_for_url(&url).as_deref(), Some(key.as_str()));
+    assert_eq!(storage.size(&key).await.expect("size"), Some(13));
+    assert_eq!(read_back(&storage, &key, 7, 12).await, "bucket");
+    assert!(storage.list().await.expect("list").iter().any(|o| o.key == key && o.size == 13));
+
+    storage.delete(&key).await.expect("delete");
+    assert_eq!(storage.size(&key).await.expect("size after delete"), None);
+}
+
+#[tokio::test]
+async fn large_streams_go_up_in_parts() {
+    let storage = minio();
+    let key = format!("s3-tests/{}.bin", Uuid::new_v4().simple());
+    // Two full parts and a short third
+    let data: Vec<u8> = (0..20 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+
+    storage.put_stream(&key, chunked(&data), "application/octet-stream").await.expect("multipart upload");
+    assert_eq!(storage.size(&key).await.expect("size"), Some(data.len() as u64));
+    let (start, end) = (8 * 1024 * 1024 - 10, 8 * 1024 * 1024 + 10);
+    assert_eq!(read_back(&storage, &key, start, end).await, &data[start as usize..=end as usize]);
+
+    storage.delete(&key).await.expect("delete");
+}
+
+#[tokio::test]
+async fn a_failed_stream_leaves_nothing_behind() {
+    let storage = minio();
+    let key = format!("s3-tests/{}.bin", Uuid::new_v4().simple());
+    let part = Bytes::from(vec![7u8; 9 * 1024 * 1024]);
+    let body: ByteStream = Box::pin(stream::iter([
+        Ok(part),
+        Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "client went away")),
+    ]));
+
+    assert!(storage.put_stream(&key, body, "application/octet-stream").await.is_err());
+    assert_eq!(storage.size(&key).await.expect("size"), None);
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// Seeded providers are onboarded but not yet approved, so search skips them.
+async fn approve(pool: &PgPool, provider_id: i32) {
+    sqlx::query("UPDATE providers SET approved = TRUE WHERE id = $1")
+        .bind(provider_id)
+        .execute(pool)
+        .await
+        .expect("approve provider");
+}
+
+/// A new county with one constituency and ward. Returns `(county_id, ward_id)`.
+async fn ward_in_new_county(pool: &PgPool, name: &str) -> (i32, i32) {
+    let county_id: i32 = sqlx::query_scalar("INSERT INTO counties (name) VALUES ($1) RETURNING id")
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .expect("seed county");
+    let ward_id = sqlx::query_scalar(
+        "WITH c AS (INSERT INTO constituencies (name, county_id) VALUES ($1, $2) RETURNING id)
+         INSERT INTO wards (name, constituency_id) SELECT $1, id FROM c RETURNING id",
+    )
+    .bind(name)
+    .bind(county_id)
+    .fetch_one(pool)
+    .await
+    .expect("seed ward");
+    (county_id, ward_id)
+}
+
+async fn locate(pool: &PgPool, provider_id: i32, ward_id: i32) {
+    sqlx::query("INSERT INTO provider_locations (provider_id, ward_id, latitude, longitude) VALUES ($1, $2, -1.28, 36.82)")
+        .bind(provider_id)
+        .bind(ward_id)
+        .execute(pool)
+        .await
+        .expect("seed provider location");
+}
+
+#[tokio::test]
+async fn search_returns_typed_hits_with_per_type_counts() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    approve(&app.pool, provider_id).await;
+    let service_id = seed::service(&app.pool, "provider", provider_id).await;
+    sqlx::query("UPDATE services SET title = 'Emergency plumbing' WHERE id = $1")
+        .bind(service_id)
+        .execute(&app.pool)
+        .await
+        .expect("rename service");
+    // Matches too, but neither it nor its services are listed
+    let (_, unapproved_id) = seed::provider(&app.pool).await;
+    seed::service(&app.pool, "provider", unapproved_id).await;
+    seed::business(&app.pool).await;
+
+    let res = app.get("/search?q=plumbing", None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["counts"], json!({ "provider": 1, "business": 0, "service": 1 }));
+    assert_eq!(res.body["total"], 2);
+    let items = res.body["items"].as_array().expect("items");
+    let service = items.iter().find(|hit| hit["type"] == "service").expect("service hit");
+    assert_eq!(service["id"], service_id);
+    assert_eq!(service["name"], "Emergency plumbing");
+    assert_eq!(service["owner_type"], "provider");
+    assert_eq!(service["owner_id"], provider_id);
+    assert_eq!(service["owner_name"], "Test Plumbing");
+    assert!(items.iter().all(|hit| hit["score"].as_f64().unwrap_or_default() > 0.0));
+
+    // `type` narrows the list and the total, not the counts
+    let res = app.get("/search?q=plumbing&type=provider", None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total"], 1);
+    assert_eq!(res.body["items"][0]["type"], "provider");
+    assert_eq!(res.body["items"][0]["id"], provider_id);
+    assert_eq!(res.body["counts"]["service"], 1);
+
+    let res = app.get("/search?q=plumbing&type=clinic", None).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn search_filters_by_county_ranks_by_rating_and_pages() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, mombasa_ward) = ward_in_new_county(&app.pool, "Mombasa").await;
+    let (kisumu, kisumu_ward) = ward_in_new_county(&app.pool, "Kisumu").await;
+    let (_, plain) = seed::provider(&app.pool).await;
+    let (_, rated) = seed::provider(&app.pool).await;
+    let (_, elsewhere) = seed::provider(&app.pool).await;
+    for (provider_id, ward_id) in [(plain, kisumu_ward), (rated, kisumu_ward), (elsewhere, mombasa_ward)] {
+        approve(&app.pool, provider_id).await;
+        locate(&app.pool, provider_id, ward_id).await;
+    }
+    let reviewer = seed::client(&app.pool).await;
+    sqlx::query("INSERT INTO reviews (reviewer_id, target_type, target_id, rating) VALUES ($1, 'provider', $2, 5)")
+        .bind(reviewer)
+        .bind(rated)
+        .execute(&app.pool)
+        .await
+        .expect("seed review");
+
+    let res = app.get(&format!("/search?q=plumbing&type=provider&county_id={kisumu}"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total"], 2);
+    // Same text relevance, so the 5-star provider comes first
+    assert_eq!(res.body["items"][0]["id"], rated);
+    assert_eq!(res.body["items"][0]["average_rating"], 5.0);
+    assert_eq!(res.body["items"][1]["id"], plain);
+
+    let res = app.get(&format!("/search?q=plumbing&type=provider&county_id={kisumu}&limit=1&page=2"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["per_page"], 1);
+    assert_eq!(res.body["items"].as_array().map(Vec::len), Some(1));
+    assert_eq!(res.body["items"][0]["id"], plain);
+    assert_eq!(res.body["has_more"], false);
+}
+
+#[tokio::test]
+async fn sampled_searches_are_logged() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let res = app.post_json("/admin/settings", Some(&admin), json!({ "search.log_sample_percent": 100 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    // The insert is fire-and-forget, so wait for it. Settings are cached per
+    // process; other tests here may briefly load their own database's.
+    let mut logged = None;
+    for _ in 0..50 {
+        let res = app.get("/search?q=%20Bathroom%20Tiling%20&type=business", None).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        logged = sqlx::query_as::<_, (String, Option<String>, i32)>(
+            "SELECT query, result_type, result_count FROM search_queries LIMIT 1",
+        )
+        .fetch_optional(&app.pool)
+        .await
+        .expect("read search log");
+        if logged.is_some() {
+            break;
+        }
+    }
+    assert_eq!(logged, Some(("bathroom tiling".to_string(), Some("business".to_string()), 0)));
+}
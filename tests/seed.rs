@@ -0,0 +1,73 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use serde_json::json;
+use sqlx::PgPool;
+
+async fn count(pool: &PgPool, sql: &str) -> i64 {
+    sqlx::query_scalar(sql).fetch_one(pool).await.expect("count rows")
+}
+
+#[tokio::test]
+async fn seeding_builds_a_connected_hierarchy_and_reruns_are_idempotent() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+
+    let res = app.get("/admin/seed/status", Some(&admin)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(res.body["sets"].as_array().expect("sets").iter().all(|s| s["present"] == false));
+
+    let res = app.post_json("/admin/seed", Some(&admin), json!({})).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let inserted = &res.body["inserted"];
+    for table in ["counties", "constituencies", "wards", "categories"] {
+        assert!(inserted[table].as_u64().expect(table) > 0, "{table}: {}", res.body);
+    }
+    assert_eq!(inserted["counties"], 47);
+
+    // Every child points at a parent that exists and every county has constituencies
+    let orphans = [
+        "SELECT COUNT(*) FROM constituencies c WHERE NOT EXISTS (SELECT 1 FROM counties WHERE id = c.county_id)",
+        "SELECT COUNT(*) FROM wards w WHERE NOT EXISTS (SELECT 1 FROM constituencies WHERE id = w.constituency_id)",
+        "SELECT COUNT(*) FROM categories c WHERE c.parent_id IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM categories WHERE id = c.parent_id)",
+        "SELECT COUNT(*) FROM counties c WHERE NOT EXISTS (SELECT 1 FROM constituencies WHERE county_id = c.id)",
+        // The tree is two levels deep: subcategories hang off top-level parents
+        "SELECT COUNT(*) FROM categories c JOIN categories p ON p.id = c.parent_id WHERE p.parent_id IS NOT NULL",
+    ];
+    for sql in orphans {
+        assert_eq!(count(&app.pool, sql).await, 0, "{sql}");
+    }
+    let totals = (
+        count(&app.pool, "SELECT COUNT(*) FROM counties").await,
+        count(&app.pool, "SELECT COUNT(*) FROM constituencies").await,
+        count(&app.pool, "SELECT COUNT(*) FROM wards").await,
+        count(&app.pool, "SELECT COUNT(*) FROM categories").await,
+    );
+    assert_eq!(
+        totals,
+        (
+            inserted["counties"].as_i64().unwrap(),
+            inserted["constituencies"].as_i64().unwrap(),
+            inserted["wards"].as_i64().unwrap(),
+            inserted["categories"].as_i64().unwrap(),
+        )
+    );
+
+    let res = app.get("/admin/seed/status", Some(&admin)).await;
+    assert!(res.body["sets"].as_array().expect("sets").iter().all(|s| s["present"] == true));
+
+    // A second run refuses without force and adds nothing with it
+    let res = app.post_json("/admin/seed", Some(&admin), json!({})).await;
+    assert_eq!(res.status, StatusCode::CONFLICT, "{}", res.body);
+    let res = app.post_json("/admin/seed", Some(&admin), json!({ "force": true })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body["inserted"],
+        json!({ "counties": 0, "constituencies": 0, "wards": 0, "categories": 0 })
+    );
+
+    let res = app.post_json("/admin/seed", Some(&admin), json!({ "sets": ["planets"] })).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, seed};
+use sqlx::PgPool;
+
+/// Seeded profiles are onboarded but not yet approved or verified, so their
+/// services aren't listed.
+async fn list_owner(pool: &PgPool, target_type: &str, target_id: i32) {
+    let sql = match target_type {
+        "provider" => "UPDATE providers SET approved = TRUE WHERE id = $1",
+        _ => "UPDATE businesses SET verified = TRUE WHERE id = $1",
+    };
+    sqlx::query(sql).bind(target_id).execute(pool).await.expect("list owner");
+}
+
+fn ids(body: &serde_json::Value) -> Vec<i64> {
+    let mut ids: Vec<i64> = body["items"]
+        .as_array()
+        .expect("items")
+        .iter()
+        .map(|s| s["id"].as_i64().expect("service id"))
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn get_services_binds_each_filter() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    let (_, business_id) = seed::business(&app.pool).await;
+    list_owner(&app.pool, "provider", provider_id).await;
+    list_owner(&app.pool, "business", business_id).await;
+
+    let active = i64::from(seed::service(&app.pool, "provider", provider_id).await);
+    let inactive = i64::from(seed::service(&app.pool, "provider", provider_id).await);
+    sqlx::query("UPDATE services SET is_active = FALSE WHERE id = $1")
+        .bind(inactive as i32)
+        .execute(&app.pool)
+        .await
+        .expect("deactivate service");
+    let category_id: i32 = sqlx::query_scalar("INSERT INTO categories (name) VALUES ('Plumbing') RETURNING id")
+        .fetch_one(&app.pool)
+        .await
+        .expect("seed category");
+    let at_business = i64::from(seed::service(&app.pool, "business", business_id).await);
+    sqlx::query("UPDATE services SET category_id = $1 WHERE id = $2")
+        .bind(category_id)
+        .bind(at_business as i32)
+        .execute(&app.pool)
+        .await
+        .expect("categorise service");
+    // Another provider's service
+    let (_, other_provider) = seed::provider(&app.pool).await;
+    list_owner(&app.pool, "provider", other_provider).await;
+    let elsewhere = i64::from(seed::service(&app.pool, "provider", other_provider).await);
+
+    let res = app
+        .get(&format!("/services/getServices?target_type=provider&target_id={provider_id}&is_active=true"), None)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(ids(&res.body), [active]);
+    assert_eq!(res.body["total"], 1);
+
+    let res = app.get(&format!("/api/v1/services/getServices?target_type=provider&target_id={provider_id}"), None).await;
+    assert_eq!(ids(&res.body), [active, inactive]);
+    let res = app.get(&format!("/services/getServices?target_type=provider&target_id={provider_id}&is_active=false"), None).await;
+    assert_eq!(ids(&res.body), [inactive]);
+
+    let res = app.get("/services/getServices?target_type=provider", None).await;
+    assert_eq!(ids(&res.body), [active, inactive, elsewhere]);
+    let res = app.get("/services/getServices?target_type=business", None).await;
+    assert_eq!(ids(&res.body), [at_business]);
+    let res = app.get(&format!("/services/getServices?category_id={category_id}&is_active=true"), None).await;
+    assert_eq!(ids(&res.body), [at_business]);
+
+    let res = app.get("/services/getServices?target_id=abc", None).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+}
+
+#[tokio::test]
+async fn services_of_unlisted_owners_are_not_listed() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let mut listed = Vec::new();
+    let mut hidden = Vec::new();
+    for owner_state in [
+        "listed",
+        "unapproved",
+        "UPDATE providers SET listing_status = 'pending_review' WHERE id = $1",
+        "UPDATE providers SET listing_status = 'rejected' WHERE id = $1",
+        "UPDATE users SET suspended_at = NOW() WHERE id = (SELECT user_id FROM providers WHERE id = $1)",
+        "UPDATE users SET deleted_at = NOW() WHERE id = (SELECT user_id FROM providers WHERE id = $1)",
+    ] {
+        let (_, provider_id) = seed::provider(&app.pool).await;
+        let service_id = i64::from(seed::service(&app.pool, "provider", provider_id).await);
+        if owner_state != "unapproved" {
+            list_owner(&app.pool, "provider", provider_id).await;
+        }
+        if owner_state.starts_with("UPDATE") {
+            sqlx::query(owner_state).bind(provider_id).execute(&app.pool).await.expect("change owner");
+        }
+        match owner_state {
+            "listed" => listed.push(service_id),
+            _ => hidden.push(service_id),
+        }
+    }
+    // A business that hasn't been verified
+    let (_, business_id) = seed::business(&app.pool).await;
+    hidden.push(i64::from(seed::service(&app.pool, "business", business_id).await));
+
+    let res = app.get("/services/getServices", None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(ids(&res.body), listed, "hidden: {hidden:?}");
+    assert_eq!(res.body["total"], 1);
+}
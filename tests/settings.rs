@@ -0,0 +1,58 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, mint_jwt, seed};
+use mtaalink::utils::settings;
+use serde_json::json;
+
+// The settings cache is per process, so this file keeps to a single test
+// rather than racing other tests for it.
+#[tokio::test]
+async fn setting_changes_apply_at_once_here_and_after_the_cache_elsewhere() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let owner = mint_jwt(owner_id);
+    let create = || {
+        app.post_json(
+            "/services/createService",
+            Some(&owner),
+            json!({
+                "target_id": provider_id, "target_type": "provider", "title": "Leak repair",
+                "description": "Any leak", "price": 1500, "duration": 60, "is_active": true,
+            }),
+        )
+    };
+
+    let res = create().await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    // The instance that takes the update drops its cache straight away
+    let res = app.post_json("/admin/settings", Some(&admin), json!({ "services.max_per_target": 1 })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = create().await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+
+    // A change written by another instance is served from the stale cache
+    // until it expires, then read fresh
+    sqlx::query("UPDATE platform_settings SET value = '5' WHERE key = 'services.max_per_target'")
+        .execute(&app.pool)
+        .await
+        .expect("update setting");
+    assert_eq!(settings::int(&app.pool, "services.max_per_target").await, 1);
+    settings::invalidate();
+    assert_eq!(settings::int(&app.pool, "services.max_per_target").await, 5);
+    let res = create().await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let res = app.get("/admin/settings", Some(&admin)).await;
+    let view = res.body["settings"]
+        .as_array()
+        .expect("settings")
+        .iter()
+        .find(|s| s["key"] == "services.max_per_target")
+        .cloned()
+        .expect("setting");
+    assert_eq!(view["value"], 5);
+    assert_eq!(view["default"], 100);
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::TestApp;
+use mtaalink::app::{App, serve};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn shutdown_finishes_within_the_timeout_while_a_request_hangs() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let timeout = common::test_config().shutdown_timeout;
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let (trigger, shutdown) = oneshot::channel::<()>();
+    let app = App { router: app.router.clone(), job_triggers: app.job_triggers.clone() };
+    let server = tokio::spawn(serve(app, listener, timeout, async {
+        shutdown.await.ok();
+    }));
+
+    // A body that is announced but never sent keeps the handler waiting
+    // until the request timeout, well past the shutdown timeout
+    let mut hanging = TcpStream::connect(addr).await.expect("connect");
+    hanging
+        .write_all(b"POST /auth/login HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: 64\r\n\r\n{")
+        .await
+        .expect("send headers");
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let started = Instant::now();
+    trigger.send(()).expect("trigger shutdown");
+    tokio::time::timeout(timeout + Duration::from_secs(2), server)
+        .await
+        .expect("server stops within the shutdown timeout")
+        .expect("server task")
+        .expect("serve");
+    assert!(started.elapsed() >= timeout, "the in-flight request was given {timeout:?}");
+
+    // The hanging connection was closed rather than answered
+    let mut rest = Vec::new();
+    let read = tokio::time::timeout(Duration::from_secs(1), hanging.read_to_end(&mut rest)).await;
+    assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))), "{:?}", String::from_utf8_lossy(&rest));
+    assert!(TcpStream::connect(addr).await.is_err(), "still accepting connections");
+}
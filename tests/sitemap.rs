@@ -0,0 +1,243 @@
+mod common;
+
+use axum::http::{StatusCode, header};
+use common::{TestApp, seed};
+use sqlx::PgPool;
+
+const SITE: &str = "https://mtaalink.test";
+const SITEMAP_NS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+async fn approve(pool: &PgPool, provider_id: i32) {
+    sqlx::query("UPDATE providers SET approved = TRUE WHERE id = $1")
+        .bind(provider_id)
+        .execute(pool)
+        .await
+        .expect("approve provider");
+}
+
+/// Creates a provider's post and returns its id and slug.
+async fn post(pool: &PgPool, provider_id: i32, title: &str) -> (i32, String) {
+    sqlx::query_as("INSERT INTO posts (provider_id, title, content) VALUES ($1, $2, 'Come by') RETURNING id, slug")
+        .bind(provider_id)
+        .bind(title)
+        .fetch_one(pool)
+        .await
+        .expect("seed post")
+}
+
+async fn served(app: &TestApp, uri: &str) -> String {
+    let res = app.get(uri, None).await;
+    assert_eq!(res.status, StatusCode::OK, "{uri}");
+    assert_eq!(res.headers[header::CONTENT_TYPE], "application/xml; charset=utf-8");
+    res.body.as_str().expect("XML body").to_string()
+}
+
+/// The text of every `<tag>` element in `xml`.
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{tag}>"), format!("</{tag}>"));
+    xml.split(open.as_str())
+        .skip(1)
+        .map(|rest| rest.split(close.as_str()).next().expect("closing tag"))
+        .collect()
+}
+
+/// Checks a document against the rules of the sitemap 0.9 schema: the
+/// declaration, the namespaced root, and for each entry exactly one absolute
+/// `<loc>` under 2,048 characters plus an optional W3C date `<lastmod>`.
+/// Returns the `<loc>` values.
+fn validate(xml: &str, root: &str, entry: &str) -> Vec<String> {
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"), "{xml}");
+    assert!(xml.contains(&format!("<{root} xmlns=\"{SITEMAP_NS}\">")), "{xml}");
+    assert!(xml.trim_end().ends_with(&format!("</{root}>")), "{xml}");
+    let entries = elements(xml, entry);
+    assert!(entries.len() <= 50_000);
+    entries
+        .iter()
+        .map(|entry| {
+            let locs = elements(entry, "loc");
+            assert_eq!(locs.len(), 1, "{entry}");
+            assert!(locs[0].starts_with(SITE) && locs[0].len() < 2048, "{entry}");
+            for lastmod in elements(entry, "lastmod") {
+                assert!(chrono::NaiveDate::parse_from_str(lastmod, "%Y-%m-%d").is_ok(), "{entry}");
+            }
+            locs[0].to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn sitemap_lists_only_listed_profiles_and_splits_into_an_index() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, first) = seed::provider(&app.pool).await;
+    let (_, second) = seed::provider(&app.pool).await;
+    let (_, unapproved) = seed::provider(&app.pool).await;
+    approve(&app.pool, first).await;
+    approve(&app.pool, second).await;
+    let (_, business) = seed::business(&app.pool).await;
+    sqlx::query("UPDATE businesses SET verified = TRUE WHERE id = $1")
+        .bind(business)
+        .execute(&app.pool)
+        .await
+        .expect("verify business");
+
+    let written = mtaalink::utils::sitemap::regenerate(&app.pool, SITE, 10).await.expect("regenerate");
+    assert_eq!(written, 3);
+    let locs = validate(&served(&app, "/sitemap.xml").await, "urlset", "url");
+    assert!(locs.contains(&format!("{SITE}/providers/{first}")));
+    assert!(locs.contains(&format!("{SITE}/providers/{second}")));
+    assert!(locs.contains(&format!("{SITE}/businesses/{business}")));
+    assert!(!locs.contains(&format!("{SITE}/providers/{unapproved}")));
+
+    // Past the per-file limit the root becomes an index of chunks
+    mtaalink::utils::sitemap::regenerate(&app.pool, SITE, 2).await.expect("regenerate");
+    let chunks = validate(&served(&app, "/sitemap.xml").await, "sitemapindex", "sitemap");
+    assert_eq!(chunks, [format!("{SITE}/sitemaps/sitemap-1.xml"), format!("{SITE}/sitemaps/sitemap-2.xml")]);
+    let mut locs = validate(&served(&app, "/sitemaps/sitemap-1.xml").await, "urlset", "url");
+    locs.extend(validate(&served(&app, "/sitemaps/sitemap-2.xml").await, "urlset", "url"));
+    assert_eq!(locs.len(), 3);
+    assert_eq!(app.get("/sitemaps/sitemap-3.xml", None).await.status, StatusCode::NOT_FOUND);
+
+    // Suspended and delisted profiles drop out on the next rebuild
+    sqlx::query("UPDATE providers SET suspended_until = NOW() + INTERVAL '7 days' WHERE id = $1")
+        .bind(first)
+        .execute(&app.pool)
+        .await
+        .expect("suspend provider");
+    sqlx::query("UPDATE businesses SET listing_status = 'rejected' WHERE id = $1")
+        .bind(business)
+        .execute(&app.pool)
+        .await
+        .expect("delist business");
+    assert_eq!(mtaalink::utils::sitemap::regenerate(&app.pool, SITE, 10).await.expect("regenerate"), 1);
+    let locs = validate(&served(&app, "/sitemap.xml").await, "urlset", "url");
+    assert_eq!(locs, [format!("{SITE}/providers/{second}")]);
+    assert_eq!(app.get("/sitemaps/sitemap-1.xml", None).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn sitemap_lists_the_visible_posts_of_listed_profiles_by_slug() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (author, provider_id) = seed::provider(&app.pool).await;
+    let (_, unlisted) = seed::provider(&app.pool).await;
+    approve(&app.pool, provider_id).await;
+    let (published_id, published) = post(&app.pool, provider_id, "Open on Saturday!").await;
+    assert_eq!(published, format!("open-on-saturday-{published_id}"));
+    let (hidden_id, hidden) = post(&app.pool, provider_id, "Half-price pipes").await;
+    sqlx::query("UPDATE posts SET hidden = TRUE, hidden_reason = 'spam' WHERE id = $1")
+        .bind(hidden_id)
+        .execute(&app.pool)
+        .await
+        .expect("hide post");
+    let (_, unlisted_post) = post(&app.pool, unlisted, "Open too").await;
+
+    assert_eq!(mtaalink::utils::sitemap::regenerate(&app.pool, SITE, 10).await.expect("regenerate"), 2);
+    let locs = validate(&served(&app, "/sitemap.xml").await, "urlset", "url");
+    assert_eq!(locs, [format!("{SITE}/posts/{published}"), format!("{SITE}/providers/{provider_id}")]);
+    assert!(!locs.contains(&format!("{SITE}/posts/{hidden}")));
+    assert!(!locs.contains(&format!("{SITE}/posts/{unlisted_post}")));
+
+    // The page behind the URL, which keeps its slug when the title changes
+    sqlx::query("UPDATE posts SET title = 'Open all weekend' WHERE id = $1")
+        .bind(published_id)
+        .execute(&app.pool)
+        .await
+        .expect("retitle post");
+    let res = app.get(&format!("/posts/slug/{published}"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["post"]["id"], published_id);
+    assert_eq!(res.body["post"]["slug"], published);
+    assert_eq!(app.get(&format!("/posts/slug/{hidden}"), None).await.status, StatusCode::NOT_FOUND);
+
+    // A deleted author's posts drop out along with their profile
+    sqlx::query("UPDATE users SET deleted_at = NOW() WHERE id = $1")
+        .bind(author)
+        .execute(&app.pool)
+        .await
+        .expect("delete author");
+    assert_eq!(mtaalink::utils::sitemap::regenerate(&app.pool, SITE, 10).await.expect("regenerate"), 0);
+    assert!(validate(&served(&app, "/sitemap.xml").await, "urlset", "url").is_empty());
+}
+
+#[tokio::test]
+async fn listings_describe_themselves_as_schema_org_local_businesses() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (_, provider_id) = seed::provider(&app.pool).await;
+    approve(&app.pool, provider_id).await;
+    let ward_id: i32 = sqlx::query_scalar(
+        "WITH co AS (INSERT INTO counties (name) VALUES ('Nakuru') RETURNING id),
+              c AS (INSERT INTO constituencies (name, county_id) SELECT 'Naivasha', id FROM co RETURNING id)
+         INSERT INTO wards (name, constituency_id) SELECT 'Hells Gate', id FROM c RETURNING id",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed ward");
+    sqlx::query(
+        "INSERT INTO provider_locations (provider_id, ward_id, address, latitude, longitude)
+         VALUES ($1, $2, 'Moi South Lake Rd', -0.77, 36.43)",
+    )
+    .bind(provider_id)
+    .bind(ward_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed location");
+    sqlx::query(
+        "INSERT INTO provider_availability (provider_id, day, start_time, end_time, is_available)
+         VALUES ($1, 'tuesday', '09:00', '17:00', TRUE), ($1, 'monday', '08:00', '16:30', TRUE),
+                ($1, 'sunday', '10:00', '12:00', FALSE)",
+    )
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed hours");
+    let reviewer = seed::client(&app.pool).await;
+    sqlx::query(
+        "INSERT INTO reviews (reviewer_id, target_type, target_id, rating) VALUES ($1, 'provider', $2, 4), ($1, 'provider', $2, 5)",
+    )
+    .bind(reviewer)
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed reviews");
+
+    let res = app.get(&format!("/service_providers/{provider_id}/schema.json"), None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.headers[header::CONTENT_TYPE], "application/ld+json");
+    let doc = &res.body;
+    assert_eq!(doc["@context"], "https://schema.org");
+    assert_eq!(doc["@type"], "LocalBusiness");
+    assert_eq!(doc["name"], "Test Plumbing");
+    assert_eq!(doc["url"], format!("http://localhost:3000/providers/{provider_id}"));
+    assert_eq!(doc["address"]["@type"], "PostalAddress");
+    assert_eq!(doc["address"]["streetAddress"], "Moi South Lake Rd");
+    assert_eq!(doc["address"]["addressLocality"], "Hells Gate");
+    assert_eq!(doc["address"]["addressRegion"], "Nakuru");
+    assert_eq!(doc["address"]["addressCountry"], "KE");
+    assert_eq!(doc["geo"]["latitude"], -0.77);
+    let hours = doc["openingHoursSpecification"].as_array().expect("opening hours");
+    assert_eq!(hours.len(), 2);
+    assert_eq!(hours[0]["dayOfWeek"], "https://schema.org/Monday");
+    assert_eq!(hours[0]["opens"], "08:00");
+    assert_eq!(hours[0]["closes"], "16:30");
+    assert_eq!(doc["aggregateRating"]["@type"], "AggregateRating");
+    assert_eq!(doc["aggregateRating"]["ratingValue"], 4.5);
+    assert_eq!(doc["aggregateRating"]["reviewCount"], 2);
+
+    // An unverified business has no page to describe yet
+    let (_, business_id) = seed::business(&app.pool).await;
+    let uri = format!("/businesses/{business_id}/schema.json");
+    assert_eq!(app.get(&uri, None).await.status, StatusCode::NOT_FOUND);
+    sqlx::query("UPDATE businesses SET verified = TRUE WHERE id = $1")
+        .bind(business_id)
+        .execute(&app.pool)
+        .await
+        .expect("verify business");
+    let res = app.get(&uri, None).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["name"], "Test Salon");
+    // No branches, so the free-text location stands in; no reviews, no rating
+    assert_eq!(res.body["address"]["addressLocality"], "Nairobi");
+    assert!(res.body.get("aggregateRating").is_none());
+    assert!(res.body.get("openingHoursSpecification").is_none());
+
+    assert_eq!(app.get("/service_providers/987654/schema.json", None).await.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{TestApp, seed};
+use mtaalink::utils::storage::{LocalStorage, SharedStorage};
+use mtaalink::utils::storage_gc::collect_garbage;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// Writes `key` under `root`, dated `age` ago.
+fn stored_file(root: &Path, key: &str, age: Duration) {
+    let path = root.join(key);
+    std::fs::create_dir_all(path.parent().expect("parent dir")).expect("create dir");
+    std::fs::write(&path, b"stored bytes").expect("write file");
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|f| f.set_modified(SystemTime::now() - age))
+        .expect("set mtime");
+}
+
+fn keys(report: &serde_json::Value, field: &str, name: &str) -> Vec<String> {
+    report[field]
+        .as_array()
+        .expect(field)
+        .iter()
+        .map(|entry| entry[name].as_str().expect(name).to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn gc_removes_old_unreferenced_files_and_reports_missing_ones() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let root: PathBuf = std::env::temp_dir().join(format!("mtaalink-gc-{}", Uuid::new_v4().simple()));
+    let (public, private) = (root.join("public"), root.join("private"));
+    let storage: SharedStorage = Arc::new(LocalStorage::new(
+        public.to_str().expect("utf-8 path"),
+        private.to_str().expect("utf-8 path"),
+    ));
+
+    let old = Duration::from_secs(3 * 24 * 3600);
+    for key in [
+        "attachments/live.png",
+        "attachments/live_thumb.png",
+        "attachments/orphan.png",
+        "attachments/orphan_thumb.png",
+        "messages/quoted.png",
+        "messages/forgotten.png",
+    ] {
+        stored_file(&public, key, old);
+    }
+    // Too new to tell from an upload whose row isn't written yet
+    stored_file(&public, "attachments/fresh.png", Duration::ZERO);
+
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    sqlx::query(
+        "INSERT INTO attachments (file_name, file_path, file_type, target_type, target_id, uploaded_by)
+         VALUES ('live.png', '/uploads/attachments/live.png', 'image', 'provider', $1, $2),
+                ('gone.png', '/uploads/attachments/gone.png', 'image', 'provider', $1, $2),
+                ('cdn.png', 'https://cdn.example.com/cdn.png', 'image', 'provider', $1, $2)",
+    )
+    .bind(provider_id)
+    .bind(owner_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed attachments");
+    sqlx::query(
+        "INSERT INTO image_variants (original_path, thumb_path, medium_path)
+         VALUES ('/uploads/attachments/live.png', '/uploads/attachments/live_thumb.png', '/uploads/attachments/live.png'),
+                ('/uploads/attachments/orphan.png', '/uploads/attachments/orphan_thumb.png', '/uploads/attachments/orphan.png')",
+    )
+    .execute(&app.pool)
+    .await
+    .expect("seed variants");
+    sqlx::query(
+        "INSERT INTO messages (sender_id, receiver_id, target_type, target_id, content)
+         VALUES ($1, $2, 'provider', $3, 'See /uploads/messages/quoted.png')",
+    )
+    .bind(client_id)
+    .bind(owner_id)
+    .bind(provider_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed message");
+
+    let expected_orphans = ["attachments/orphan.png", "attachments/orphan_thumb.png", "messages/forgotten.png"];
+
+    // A dry run reports without touching anything
+    let report = collect_garbage(&app.pool, &storage, true).await.expect("dry run");
+    let report = serde_json::to_value(&report).expect("serialize report");
+    assert_eq!(report["files_scanned"], 7);
+    assert_eq!(keys(&report, "orphans", "key"), expected_orphans);
+    assert_eq!(report["orphan_bytes"], 3 * b"stored bytes".len());
+    assert_eq!(report["deleted"], 0);
+    assert_eq!(keys(&report, "missing", "url"), ["/uploads/attachments/gone.png"]);
+    assert!(expected_orphans.iter().all(|key| public.join(key).is_file()));
+
+    let report = collect_garbage(&app.pool, &storage, false).await.expect("gc run");
+    assert_eq!(report.deleted, 3);
+    assert_eq!(report.delete_failures, 0);
+    assert!(expected_orphans.iter().all(|key| !public.join(key).exists()));
+    for key in ["attachments/live.png", "attachments/live_thumb.png", "attachments/fresh.png", "messages/quoted.png"] {
+        assert!(public.join(key).is_file(), "{key} was collected");
+    }
+    let variants: Vec<String> = sqlx::query_scalar("SELECT original_path FROM image_variants")
+        .fetch_all(&app.pool)
+        .await
+        .expect("variants");
+    assert_eq!(variants, ["/uploads/attachments/live.png"]);
+
+    std::fs::remove_dir_all(&root).ok();
+}
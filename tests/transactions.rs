@@ -0,0 +1,113 @@
+mod common;
+
+use axum::extract::Path;
+use axum::http::{Method, Request, StatusCode};
+use axum::{Router, body::Body, routing::post};
+use common::{TestApp, mint_jwt, seed};
+use mtaalink::extractors::tx::{Tx, transactional};
+use mtaalink::{AppError, AppResult};
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+/// Renames the user, then rejects names over ten characters: a write
+/// followed by a failed check, as in a handler that validates late.
+async fn rename(mut tx: Tx, Path((user_id, name)): Path<(i32, String)>) -> AppResult<StatusCode> {
+    sqlx::query("UPDATE users SET display_name = $1 WHERE id = $2")
+        .bind(&name)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if name.len() > 10 {
+        return Err(AppError::BadRequest("Name is too long".to_string()));
+    }
+    Ok(StatusCode::OK)
+}
+
+async fn display_name(pool: &PgPool, user_id: i32) -> Option<String> {
+    sqlx::query_scalar("SELECT display_name FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .expect("read display name")
+}
+
+async fn call(router: &Router, uri: String) -> StatusCode {
+    let request = Request::builder().method(Method::POST).uri(uri).body(Body::empty()).expect("build request");
+    router.clone().oneshot(request).await.expect("router is infallible").status()
+}
+
+#[tokio::test]
+async fn tx_commits_on_success_and_rolls_back_on_error() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    let router = Router::new()
+        .route("/rename/:user_id/:name", transactional(post(rename)))
+        .route("/unwrapped/:user_id/:name", post(rename))
+        .with_state(app.pool.clone());
+
+    assert_eq!(call(&router, format!("/rename/{user_id}/Wanjiku")).await, StatusCode::OK);
+    assert_eq!(display_name(&app.pool, user_id).await.as_deref(), Some("Wanjiku"));
+
+    // The update ran before the check failed, and is undone
+    assert_eq!(call(&router, format!("/rename/{user_id}/WanjikuKamau")).await, StatusCode::BAD_REQUEST);
+    assert_eq!(display_name(&app.pool, user_id).await.as_deref(), Some("Wanjiku"));
+
+    // Without the layer there is nowhere to commit, so Tx refuses to start
+    assert_eq!(call(&router, format!("/unwrapped/{user_id}/Akinyi")).await, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(display_name(&app.pool, user_id).await.as_deref(), Some("Wanjiku"));
+}
+
+#[tokio::test]
+async fn updating_a_post_commits_without_an_explicit_commit() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (user_id, provider_id) = seed::provider(&app.pool).await;
+    let owner = mint_jwt(user_id);
+    let post_id: i32 = sqlx::query_scalar(
+        "INSERT INTO posts (provider_id, title, content) VALUES ($1, 'Old', 'Old content') RETURNING id",
+    )
+    .bind(provider_id)
+    .fetch_one(&app.pool)
+    .await
+    .expect("seed post");
+    sqlx::query(
+        "INSERT INTO attachments (file_name, file_path, file_type, content_type, visibility, target_type, target_id, uploaded_by)
+         VALUES ('a.jpg', '/uploads/a.jpg', 'image', 'image/jpeg', 'public', 'provider', $1, $2)",
+    )
+    .bind(provider_id)
+    .bind(user_id)
+    .execute(&app.pool)
+    .await
+    .expect("seed upload");
+
+    let res = app
+        .post_json(
+            &format!("/posts/updatePost/{post_id}"),
+            Some(&owner),
+            json!({ "title": "New", "attachments": ["/uploads/a.jpg"] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let (title, attachments): (String, i64) = sqlx::query_as(
+        "SELECT title, (SELECT COUNT(*) FROM attachments WHERE post_id = p.id) FROM posts p WHERE id = $1",
+    )
+    .bind(post_id)
+    .fetch_one(&app.pool)
+    .await
+    .expect("read post");
+    assert_eq!((title.as_str(), attachments), ("New", 1));
+
+    // Someone else's update is refused and changes nothing
+    let stranger = mint_jwt(seed::provider(&app.pool).await.0);
+    let res = app
+        .post_json(&format!("/posts/updatePost/{post_id}"), Some(&stranger), json!({ "title": "Hijacked", "attachments": [] }))
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    let title: String = sqlx::query_scalar("SELECT title FROM posts WHERE id = $1")
+        .bind(post_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("read post");
+    assert_eq!(title, "New");
+}
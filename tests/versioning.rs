@@ -0,0 +1,65 @@
+mod common;
+
+use axum::http::{StatusCode, header};
+use common::{TestApp, TestResponse, mint_jwt, seed};
+use serde_json::json;
+
+fn assert_same(prefixed: &TestResponse, legacy: &TestResponse) {
+    assert_eq!(prefixed.status, legacy.status);
+    assert_eq!(prefixed.body, legacy.body);
+}
+
+#[tokio::test]
+async fn both_prefixes_serve_identical_public_responses() {
+    let Some(app) = TestApp::spawn().await else { return };
+    seed::provider(&app.pool).await;
+
+    for path in ["/categories/allCategories", "/locations/allcounties", "/service_providers/listProviders"] {
+        let prefixed = app.get(&format!("/api/v1{path}"), None).await;
+        let legacy = app.get(path, None).await;
+
+        assert_eq!(prefixed.status, StatusCode::OK, "{path}: {}", prefixed.body);
+        assert_same(&prefixed, &legacy);
+        assert_eq!(prefixed.headers.get(header::ETAG), legacy.headers.get(header::ETAG));
+    }
+}
+
+#[tokio::test]
+async fn both_prefixes_serve_identical_authenticated_responses() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let user_id = seed::client(&app.pool).await;
+    let token = mint_jwt(user_id);
+
+    let prefixed = app.get("/api/v1/notifications/unread-count", Some(&token)).await;
+    let legacy = app.get("/notifications/unread-count", Some(&token)).await;
+    assert_eq!(prefixed.status, StatusCode::OK, "{}", prefixed.body);
+    assert_same(&prefixed, &legacy);
+
+    let body = json!({ "email": "nobody@example.com", "password": "wrong-password" });
+    let prefixed = app.post_json("/api/v1/auth/login", None, body.clone()).await;
+    let legacy = app.post_json("/auth/login", None, body).await;
+    assert_eq!(prefixed.status, StatusCode::UNAUTHORIZED, "{}", prefixed.body);
+    assert_same(&prefixed, &legacy);
+}
+
+#[tokio::test]
+async fn only_unprefixed_routes_are_marked_deprecated() {
+    let Some(app) = TestApp::spawn().await else { return };
+
+    let legacy = app.get("/categories/allCategories", None).await;
+    assert!(legacy.headers.contains_key("deprecation"));
+    assert_eq!(
+        legacy.headers[header::LINK],
+        "</api/v1/categories/allCategories>; rel=\"successor-version\""
+    );
+
+    let prefixed = app.get("/api/v1/categories/allCategories", None).await;
+    assert!(!prefixed.headers.contains_key("deprecation"));
+    assert!(!prefixed.headers.contains_key(header::LINK));
+
+    // Infrastructure routes stay at the root, unversioned
+    let health = app.get("/healthz", None).await;
+    assert_eq!(health.status, StatusCode::OK);
+    assert!(!health.headers.contains_key("deprecation"));
+    assert_eq!(app.get("/api/v2/categories/allCategories", None).await.status, StatusCode::NOT_FOUND);
+}
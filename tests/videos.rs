@@ -0,0 +1,134 @@
+mod common;
+
+use axum::async_trait;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use common::{TestApp, TestResponse, mint_jwt, seed, test_config};
+use mtaalink::errors::AppResult;
+use mtaalink::utils::video::{VideoInfo, VideoProbe};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+const BOUNDARY: &str = "mtaalink-video-boundary";
+const POSTER: &[u8] = b"\xFF\xD8\xFF\xE0stub poster frame";
+
+/// Reports whatever length the test sets, and counts the files it was shown.
+#[derive(Default)]
+struct StubProbe {
+    duration_ms: AtomicU64,
+    probed: AtomicUsize,
+}
+
+#[async_trait]
+impl VideoProbe for StubProbe {
+    async fn probe(&self, _path: &Path) -> AppResult<VideoInfo> {
+        self.probed.fetch_add(1, Ordering::SeqCst);
+        Ok(VideoInfo {
+            duration_seconds: self.duration_ms.load(Ordering::SeqCst) as f64 / 1000.0,
+            width: Some(1280),
+            height: Some(720),
+        })
+    }
+
+    async fn poster_frame(&self, _path: &Path, _at_seconds: f64) -> AppResult<Vec<u8>> {
+        Ok(POSTER.to_vec())
+    }
+}
+
+/// An MP4 `ftyp` box padded to `len` bytes; enough for the type sniffing.
+fn mp4(len: usize) -> Vec<u8> {
+    let mut file = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isomiso2".to_vec();
+    file.resize(len, 0);
+    file
+}
+
+async fn upload(app: &TestApp, token: &str, provider_id: i32, file: &[u8]) -> TestResponse {
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"files\"; filename=\"clip.mp4\"\r\n\
+         Content-Type: video/mp4\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(file);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/attachments/uploadAttachments?target_type=provider&target_id={provider_id}"))
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={BOUNDARY}"))
+        .body(Body::from(body))
+        .expect("build request");
+    app.send(request).await
+}
+
+fn stored_path(url: &str) -> PathBuf {
+    Path::new(&test_config().upload_dir).join(url.strip_prefix("/uploads/").expect("local upload url"))
+}
+
+// The settings cache is per process, so this file keeps to a single test
+// rather than racing other tests for it.
+#[tokio::test]
+async fn videos_are_checked_against_the_configured_limits_and_get_a_poster_not_thumbnails() {
+    let probe = Arc::new(StubProbe::default());
+    let Some(app) = TestApp::spawn_with_video_probe(probe.clone()).await else { return };
+    let admin = mint_jwt(seed::admin(&app.pool).await);
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let owner = mint_jwt(owner_id);
+    let res = app
+        .post_json(
+            "/admin/settings",
+            Some(&admin),
+            json!({ "uploads.max_video_seconds": 60, "uploads.max_video_bytes": 4096 }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    // Over the size cap: refused while reading, before it is probed
+    let res = upload(&app, &owner, provider_id, &mp4(4097)).await;
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE, "{}", res.body);
+    assert!(res.body["message"].as_str().unwrap().contains("4096 byte limit"), "{}", res.body);
+    assert_eq!(probe.probed.load(Ordering::SeqCst), 0);
+
+    probe.duration_ms.store(61_000, Ordering::SeqCst);
+    let res = upload(&app, &owner, provider_id, &mp4(2048)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", res.body);
+    assert_eq!(res.body["message"], "Videos can be at most 60 seconds long");
+    assert_eq!(probe.probed.load(Ordering::SeqCst), 1);
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM attachments")
+        .fetch_one(&app.pool)
+        .await
+        .expect("count attachments");
+    assert_eq!(stored, 0);
+
+    probe.duration_ms.store(60_000, Ordering::SeqCst);
+    let res = upload(&app, &owner, provider_id, &mp4(4096)).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let video = &res.body["attachments"][0];
+    assert_eq!(video["file_type"], "video");
+    assert_eq!((video["duration_seconds"].as_f64(), video["width"].as_i64()), (Some(60.0), Some(1280)));
+
+    let file_path = video["file_path"].as_str().expect("file_path");
+    let poster_url = video["poster_url"].as_str().expect("poster_url");
+    assert_eq!(std::fs::read(stored_path(poster_url)).expect("read poster"), POSTER);
+    let poster_path: Option<String> = sqlx::query_scalar("SELECT poster_path FROM attachments WHERE file_path = $1")
+        .bind(file_path)
+        .fetch_one(&app.pool)
+        .await
+        .expect("attachment row");
+    assert_eq!(poster_path.as_deref(), Some(poster_url));
+
+    // Videos are never run through the image resizer
+    assert_eq!((video["thumb_url"].as_str(), video["medium_url"].as_str()), (Some(file_path), Some(file_path)));
+    let variants: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM image_variants WHERE original_path = $1")
+        .bind(file_path)
+        .fetch_one(&app.pool)
+        .await
+        .expect("count variants");
+    assert_eq!(variants, 0);
+    let thumb = stored_path(file_path).with_file_name(format!(
+        "{}_thumb.jpg",
+        stored_path(file_path).file_stem().expect("stem").to_string_lossy()
+    ));
+    assert!(!thumb.exists(), "{}", thumb.display());
+}
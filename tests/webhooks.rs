@@ -0,0 +1,313 @@
+mod common;
+
+use axum::{Router, body::Bytes, extract::State, http::HeaderMap, http::Method, http::StatusCode, routing::post};
+use common::{TestApp, mint_jwt, seed};
+use mtaalink::utils::webhooks::{
+    self, DISABLE_AFTER_FAILURES, MAX_ATTEMPTS, SIGNATURE_HEADER, deliver_due, delivery_client,
+};
+use mtaalink::utils::ws_state::new_ws_connections;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+const SECRET: &str = "whsec_test";
+const TIMESTAMP: i64 = 1_700_000_000;
+const BODY: &[u8] = br#"{"event":"booking.created"}"#;
+
+/// A receiver on 127.0.0.1 that answers every delivery with `status` and
+/// hands each request's headers and body to the returned channel.
+async fn receiver(status: StatusCode) -> (String, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let app = Router::new()
+        .route(
+            "/hook",
+            post(|State((tx, status)): State<(mpsc::UnboundedSender<(HeaderMap, Bytes)>, StatusCode)>,
+                  headers: HeaderMap,
+                  body: Bytes| async move {
+                tx.send((headers, body)).ok();
+                status
+            }),
+        )
+        .with_state((tx, status));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind receiver");
+    let addr = listener.local_addr().expect("receiver address");
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+    (format!("http://{addr}/hook"), rx)
+}
+
+async fn subscribe(pool: &PgPool, user_id: i32, provider_id: i32, url: &str) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO webhook_subscriptions (user_id, target_type, target_id, url, secret, event_types)
+         VALUES ($1, 'provider', $2, $3, $4, $5) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(provider_id)
+    .bind(url)
+    .bind(SECRET)
+    .bind(webhooks::EVENT_TYPES.map(String::from).to_vec())
+    .fetch_one(pool)
+    .await
+    .expect("seed subscription")
+}
+
+async fn queue_delivery(pool: &PgPool, subscription_id: i32) -> i64 {
+    sqlx::query_scalar(
+        "INSERT INTO webhook_deliveries (subscription_id, event_type, payload)
+         VALUES ($1, 'booking.created', '{\"event\":\"booking.created\"}') RETURNING id",
+    )
+    .bind(subscription_id)
+    .fetch_one(pool)
+    .await
+    .expect("seed delivery")
+}
+
+/// Brings every pending retry forward so the next pass sends it.
+async fn make_due(pool: &PgPool) {
+    sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = NOW() WHERE status = 'pending'")
+        .execute(pool)
+        .await
+        .expect("make deliveries due");
+}
+
+#[test]
+fn signatures_match_fixed_vectors() {
+    assert_eq!(
+        webhooks::signature(SECRET, TIMESTAMP, BODY),
+        "612003e72749f743f6381c0dbfd68c80a4636ee4e67201b170529734fb032ab2"
+    );
+    assert_eq!(
+        webhooks::signature("key", 0, b""),
+        "85841b4efc3cd7776c3c8f9b7cca9e281c550e5d19889d78e9e669c6337f000d"
+    );
+    assert_eq!(
+        webhooks::signature_header(SECRET, TIMESTAMP, BODY),
+        "t=1700000000,v1=612003e72749f743f6381c0dbfd68c80a4636ee4e67201b170529734fb032ab2"
+    );
+}
+
+#[test]
+fn verification_rejects_tampering_wrong_secrets_and_stale_timestamps() {
+    let header = webhooks::signature_header(SECRET, TIMESTAMP, BODY);
+
+    assert!(webhooks::verify_signature(SECRET, &header, BODY, TIMESTAMP + 60, 300));
+    assert!(!webhooks::verify_signature(SECRET, &header, br#"{"event":"booking.cancelled"}"#, TIMESTAMP, 300));
+    assert!(!webhooks::verify_signature("whsec_other", &header, BODY, TIMESTAMP, 300));
+    assert!(!webhooks::verify_signature(SECRET, &header, BODY, TIMESTAMP + 301, 300));
+    assert!(!webhooks::verify_signature(SECRET, "v1=deadbeef", BODY, TIMESTAMP, 300));
+}
+
+#[test]
+fn production_urls_must_be_public_https() {
+    assert!(webhooks::validate_url("https://hooks.example.com/mtaalink", true).is_ok());
+    assert!(webhooks::validate_url("http://hooks.example.com/mtaalink", true).is_err());
+    assert!(webhooks::validate_url("https://127.0.0.1/hook", true).is_err());
+    assert!(webhooks::validate_url("https://10.1.2.3/hook", true).is_err());
+    assert!(webhooks::validate_url("https://[fe80::1]/hook", true).is_err());
+    assert!(webhooks::validate_url("https://localhost/hook", true).is_err());
+    for internal in [
+        "https://169.254.169.254/latest/meta-data",
+        "https://100.64.0.1/hook",
+        "https://0.1.2.3/hook",
+        "https://[::ffff:127.0.0.1]/hook",
+        "https://[::ffff:10.0.0.1]/hook",
+        "https://[64:ff9b::a9fe:a9fe]/hook",
+    ] {
+        assert!(webhooks::validate_url(internal, true).is_err(), "{internal}");
+    }
+    assert!(webhooks::validate_url("https://100.128.0.1/hook", true).is_ok());
+    assert!(webhooks::validate_url("https://[::ffff:8.8.8.8]/hook", true).is_ok());
+    assert!(webhooks::validate_url("http://127.0.0.1:8080/hook", false).is_ok());
+    assert!(webhooks::validate_url("ftp://hooks.example.com", false).is_err());
+}
+
+#[tokio::test]
+async fn owners_manage_subscriptions_and_see_the_secret_once() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (other_id, _) = seed::provider(&app.pool).await;
+    let token = mint_jwt(owner_id);
+
+    let res = app
+        .post_json(
+            "/api/v1/webhooks",
+            Some(&token),
+            json!({
+                "target_type": "provider",
+                "target_id": provider_id,
+                "url": "https://hooks.example.com/mtaalink",
+                "event_types": ["booking.created", "review.created"],
+            }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    assert!(res.body["secret"].as_str().is_some_and(|s| s.starts_with("whsec_")));
+    let id = res.body["subscription"]["id"].as_i64().expect("subscription id");
+
+    let res = app.get("/api/v1/webhooks", Some(&token)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["subscriptions"].as_array().map(Vec::len), Some(1));
+    assert!(res.body["subscriptions"][0].get("secret").is_none());
+
+    // Unknown events and someone else's listing are refused
+    let res = app
+        .post_json(
+            "/api/v1/webhooks",
+            Some(&token),
+            json!({ "target_type": "provider", "target_id": provider_id,
+                    "url": "https://hooks.example.com", "event_types": ["booking.eaten"] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    let res = app
+        .post_json(
+            "/api/v1/webhooks",
+            Some(&mint_jwt(other_id)),
+            json!({ "target_type": "provider", "target_id": provider_id,
+                    "url": "https://hooks.example.com", "event_types": ["booking.created"] }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    let res = app.get(&format!("/api/v1/webhooks/{id}"), Some(&mint_jwt(other_id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app
+        .request(Method::PUT, &format!("/api/v1/webhooks/{id}"), Some(&token), Some(json!({ "active": false })))
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["subscription"]["active"], false);
+
+    let res = app.request(Method::DELETE, &format!("/api/v1/webhooks/{id}"), Some(&token), None).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.get("/api/v1/webhooks", Some(&token)).await;
+    assert_eq!(res.body["subscriptions"], json!([]));
+}
+
+#[tokio::test]
+async fn a_status_change_is_delivered_signed() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let client_id = seed::client(&app.pool).await;
+    let booking_id = seed::booking(&app.pool, client_id, "provider", provider_id, None, "pending").await;
+    let (url, mut received) = receiver(StatusCode::OK).await;
+    let subscription_id = subscribe(&app.pool, owner_id, provider_id, &url).await;
+
+    let res = app
+        .post_json(
+            &format!("/api/v1/bookings/{booking_id}/status"),
+            Some(&mint_jwt(owner_id)),
+            json!({ "status": "confirmed" }),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let report = deliver_due(&app.pool, &delivery_client(false), &new_ws_connections()).await;
+    assert_eq!(report.items_processed, 1);
+
+    let (headers, body) = received.recv().await.expect("delivery received");
+    let signature = headers[SIGNATURE_HEADER].to_str().expect("signature header");
+    let now = chrono::Utc::now().timestamp();
+    assert!(webhooks::verify_signature(SECRET, signature, &body, now, 300));
+    assert_eq!(headers[webhooks::EVENT_HEADER], "booking.status_changed");
+    let payload: Value = serde_json::from_slice(&body).expect("json payload");
+    assert_eq!(payload["data"]["booking"]["id"], booking_id);
+    assert_eq!(payload["data"]["booking"]["status"], "confirmed");
+    assert_eq!(payload["data"]["booking"]["previous_status"], "pending");
+
+    let res = app
+        .get(&format!("/api/v1/webhooks/{subscription_id}/deliveries"), Some(&mint_jwt(owner_id)))
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["items"][0]["status"], "delivered");
+    assert_eq!(res.body["items"][0]["last_status_code"], 200);
+}
+
+#[tokio::test]
+async fn a_failing_delivery_gives_up_after_the_last_attempt() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (url, _received) = receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+    let subscription_id = subscribe(&app.pool, owner_id, provider_id, &url).await;
+    let delivery_id = queue_delivery(&app.pool, subscription_id).await;
+    let client = delivery_client(false);
+    let ws_conns = new_ws_connections();
+
+    for _ in 0..MAX_ATTEMPTS {
+        deliver_due(&app.pool, &client, &ws_conns).await;
+        make_due(&app.pool).await;
+    }
+    // Nothing is left to send
+    assert_eq!(deliver_due(&app.pool, &client, &ws_conns).await.items_processed, 0);
+
+    let (status, attempts, code): (String, i32, Option<i32>) =
+        sqlx::query_as("SELECT status, attempts, last_status_code FROM webhook_deliveries WHERE id = $1")
+            .bind(delivery_id)
+            .fetch_one(&app.pool)
+            .await
+            .expect("delivery row");
+    assert_eq!((status.as_str(), attempts, code), ("failed", MAX_ATTEMPTS, Some(500)));
+}
+
+#[tokio::test]
+async fn a_public_only_client_never_connects_to_internal_addresses() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (url, mut received) = receiver(StatusCode::OK).await;
+    let port = url.split(':').nth(2).and_then(|rest| rest.split('/').next()).expect("receiver port");
+    // A name that resolves to loopback, and loopback written as IPv6
+    let by_name = subscribe(&app.pool, owner_id, provider_id, &format!("http://localhost:{port}/hook")).await;
+    let mapped = subscribe(&app.pool, owner_id, provider_id, &format!("http://[::ffff:127.0.0.1]:{port}/hook")).await;
+    queue_delivery(&app.pool, by_name).await;
+    queue_delivery(&app.pool, mapped).await;
+
+    let report = deliver_due(&app.pool, &delivery_client(true), &new_ws_connections()).await;
+    assert_eq!(report.items_processed, 2);
+    assert!(received.try_recv().is_err(), "the receiver was reached");
+
+    let errors: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT subscription_id, last_error FROM webhook_deliveries WHERE status = 'pending' ORDER BY subscription_id",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .expect("delivery rows");
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].1.contains("localhost resolves to internal address"), "{}", errors[0].1);
+    assert!(errors[1].1.contains("is an internal address"), "{}", errors[1].1);
+
+    // Without the guard, as in development, the same deliveries go through
+    make_due(&app.pool).await;
+    let report = deliver_due(&app.pool, &delivery_client(false), &new_ws_connections()).await;
+    assert_eq!(report.items_processed, 2);
+    assert!(received.recv().await.is_some());
+}
+
+#[tokio::test]
+async fn sustained_failures_disable_the_subscription() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let (owner_id, provider_id) = seed::provider(&app.pool).await;
+    let (url, _received) = receiver(StatusCode::SERVICE_UNAVAILABLE).await;
+    let subscription_id = subscribe(&app.pool, owner_id, provider_id, &url).await;
+    for _ in 0..DISABLE_AFTER_FAILURES + 2 {
+        queue_delivery(&app.pool, subscription_id).await;
+    }
+
+    let report = deliver_due(&app.pool, &delivery_client(false), &new_ws_connections()).await;
+    assert_eq!(report.items_processed, i64::from(DISABLE_AFTER_FAILURES));
+
+    let (active, reason): (bool, Option<String>) =
+        sqlx::query_as("SELECT active, disabled_reason FROM webhook_subscriptions WHERE id = $1")
+            .bind(subscription_id)
+            .fetch_one(&app.pool)
+            .await
+            .expect("subscription row");
+    assert!(!active);
+    assert!(reason.is_some());
+
+    let notified: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND notif_type = 'webhook_disabled'",
+    )
+    .bind(owner_id)
+    .fetch_one(&app.pool)
+    .await
+    .expect("count notifications");
+    assert_eq!(notified, 1);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{TestApp, mint_jwt, seed};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Upgrades need a real connection, so the router is served on a free port.
+async fn serve(app: &TestApp) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let router = app.router.clone();
+    tokio::spawn(async move {
+        axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .expect("serve");
+    });
+    addr
+}
+
+/// The next text frame as JSON, skipping control frames.
+async fn next_event<S>(socket: &mut S) -> Value
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        let frame = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("an event within 5s")
+            .expect("socket open")
+            .expect("read frame");
+        if let Message::Text(text) = frame {
+            return serde_json::from_str(&text).expect("json event");
+        }
+    }
+}
+
+#[tokio::test]
+async fn a_sent_message_is_pushed_to_the_connected_receiver() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let addr = serve(&app).await;
+    let (receiver_id, provider_id) = seed::provider(&app.pool).await;
+    let sender = mint_jwt(seed::client(&app.pool).await);
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{addr}/messages/ws?token={}",
+        mint_jwt(receiver_id)
+    ))
+    .await
+    .expect("connect");
+    let hello = next_event(&mut socket).await;
+    assert_eq!((hello["event"].as_str(), hello["user_id"].as_i64()), (Some("connected"), Some(i64::from(receiver_id))));
+
+    let res = app
+        .post_json(
+            "/messages/sendMessage",
+            Some(&sender),
+            json!({ "content": "Are you free on Saturday?", "target_type": "provider", "target_id": provider_id, "receiver_id": receiver_id }),
+        )
+        .await;
+    assert_eq!(res.status, axum::http::StatusCode::CREATED, "{}", res.body);
+
+    let event = next_event(&mut socket).await;
+    assert_eq!(event["event"], "new_message");
+    assert_eq!(event["data"], res.body["message"]);
+
+    // Pings are answered and the socket stays usable; closing it cleanly ends it
+    socket.send(Message::Ping(b"hi".to_vec())).await.expect("ping");
+    socket.close(None).await.expect("close");
+}
+
+#[tokio::test]
+async fn a_bad_token_is_refused_before_the_upgrade() {
+    let Some(app) = TestApp::spawn().await else { return };
+    let addr = serve(&app).await;
+
+    for uri in [format!("ws://{addr}/messages/ws?token=not-a-jwt"), format!("ws://{addr}/ws?token=not-a-jwt")] {
+        let err = tokio_tungstenite::connect_async(uri).await.expect_err("refused");
+        match err {
+            tokio_tungstenite::tungstenite::Error::Http(res) => assert_eq!(res.status(), 401),
+            other => panic!("expected an HTTP refusal, got {other}"),
+        }
+    }
+}